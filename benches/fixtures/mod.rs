@@ -0,0 +1,84 @@
+//! Fixtures for benchmarks.
+//!
+//! Generates synthetic accounts and mailboxes,
+//! so benchmarks do not depend on a private database.
+
+use criterion::black_box;
+use deltachat::config::Config;
+use deltachat::context::Context;
+use deltachat::receive_imf::receive_imf;
+use deltachat::stock_str::StockStrings;
+use deltachat::Events;
+use tempfile::{tempdir, TempDir};
+
+/// Address of the account used in benchmarks.
+pub const SELF_ADDR: &str = "alice@example.org";
+
+/// Creates a new context that looks configured for [`SELF_ADDR`].
+///
+/// The returned directory contains the database
+/// and must be kept alive as long as the context is used.
+pub async fn create_context() -> (Context, TempDir) {
+    let dir = tempdir().unwrap();
+    let dbfile = dir.path().join("db.sqlite");
+    let context = Context::new(&dbfile, 100, Events::new(), StockStrings::new())
+        .await
+        .unwrap();
+    context
+        .set_config(Config::Addr, Some(SELF_ADDR))
+        .await
+        .unwrap();
+    context
+        .set_config(Config::ConfiguredAddr, Some(SELF_ADDR))
+        .await
+        .unwrap();
+    context
+        .set_config(Config::Configured, Some("1"))
+        .await
+        .unwrap();
+    (context, dir)
+}
+
+/// Returns a synthetic chat message from `from` to [`SELF_ADDR`].
+pub fn text_email(from: &str, rfc724_mid: &str, in_reply_to: Option<&str>, text: &str) -> String {
+    let in_reply_to = in_reply_to
+        .map(|mid| format!("In-Reply-To: <{mid}>\n"))
+        .unwrap_or_default();
+    format!(
+        "Subject: Benchmark
+Message-ID: <{rfc724_mid}>
+{in_reply_to}Date: Sat, 07 Dec 2019 19:00:27 +0000
+To: {SELF_ADDR}
+From: {from}
+Chat-Version: 1.0
+MIME-Version: 1.0
+Content-Type: text/plain; charset=utf-8; format=flowed; delsp=no
+
+{text}
+"
+    )
+}
+
+/// Receives `msgs_per_chat` messages from each of `chats` different senders,
+/// creating a mailbox with `chats` one-to-one chats.
+///
+/// Every tenth message contains the word "needle" for search benchmarks.
+/// `prefix` makes Message-IDs unique, so the same context can be populated several times.
+pub async fn populate_mailbox(context: &Context, prefix: &str, chats: u32, msgs_per_chat: u32) {
+    for chat in 0..chats {
+        let from = format!("sender{chat}@example.net");
+        for i in 0..msgs_per_chat {
+            let rfc724_mid = format!("{prefix}.{chat}.{i}@example.net");
+            let in_reply_to = (i > 0).then(|| format!("{prefix}.{chat}.{}@example.net", i - 1));
+            let text = if (chat + i) % 10 == 0 {
+                format!("Hello {i}, this message contains a needle.")
+            } else {
+                format!("Hello {i} from chat {chat}.")
+            };
+            let imf_raw = text_email(&from, &rfc724_mid, in_reply_to.as_deref(), &text);
+            receive_imf(context, black_box(imf_raw.as_bytes()), false)
+                .await
+                .unwrap();
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! Performance regression suite.
+//!
+//! Unlike other benchmarks, this suite does not need a private database:
+//! all data is generated by the [`fixtures`] module,
+//! so results of different contributions can be compared.
+//!
+//! Run with `cargo bench --bench regression`.
+#![recursion_limit = "256"]
+
+mod fixtures;
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use deltachat::chatlist::Chatlist;
+use deltachat::imex::{imex, ImexMode};
+use tempfile::tempdir;
+
+use fixtures::{create_context, populate_mailbox};
+
+/// Number of chats for the chatlist benchmark.
+const CHATLIST_CHATS: u32 = 10_000;
+
+fn receive_benchmark(c: &mut Criterion) {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let mut group = c.benchmark_group("receive_imf");
+    group.sample_size(10);
+    group.bench_function("Receive 1000 msgs into 100 chats", |b| {
+        let (context, _dir) = rt.block_on(create_context());
+        let mut iteration = 0;
+        b.to_async(&rt).iter(|| {
+            iteration += 1;
+            let context = context.clone();
+            async move {
+                populate_mailbox(&context, &format!("recv{iteration}"), 100, 10).await;
+            }
+        });
+    });
+    group.finish();
+}
+
+fn chatlist_benchmark(c: &mut Criterion) {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let (context, _dir) = rt.block_on(async {
+        let (context, dir) = create_context().await;
+        populate_mailbox(&context, "chatlist", CHATLIST_CHATS, 1).await;
+        (context, dir)
+    });
+    c.bench_function("Load chatlist with 10k chats", |b| {
+        b.to_async(&rt).iter(|| async {
+            black_box(Chatlist::try_load(&context, 0, None, None).await.unwrap());
+        })
+    });
+    c.bench_function("Load chatlist with 10k chats, filtered by query", |b| {
+        b.to_async(&rt).iter(|| async {
+            black_box(
+                Chatlist::try_load(&context, 0, Some("sender99"), None)
+                    .await
+                    .unwrap(),
+            );
+        })
+    });
+}
+
+fn search_benchmark(c: &mut Criterion) {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let (context, _dir) = rt.block_on(async {
+        let (context, dir) = create_context().await;
+        populate_mailbox(&context, "search", 100, 100).await;
+        (context, dir)
+    });
+    c.bench_function("Search 10k msgs", |b| {
+        b.to_async(&rt).iter(|| async {
+            black_box(context.search_msgs(None, "needle").await.unwrap());
+        })
+    });
+}
+
+fn backup_benchmark(c: &mut Criterion) {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let (context, _dir) = rt.block_on(async {
+        let (context, dir) = create_context().await;
+        populate_mailbox(&context, "backup", 100, 100).await;
+        (context, dir)
+    });
+    let mut group = c.benchmark_group("imex");
+    group.sample_size(10);
+    group.bench_function("Export backup of 10k msgs", |b| {
+        b.to_async(&rt).iter_batched(
+            || tempdir().unwrap(),
+            |backup_dir| {
+                let context = context.clone();
+                async move {
+                    imex(&context, ImexMode::ExportBackup, backup_dir.path(), None)
+                        .await
+                        .unwrap();
+                }
+            },
+            BatchSize::PerIteration,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    receive_benchmark,
+    chatlist_benchmark,
+    search_benchmark,
+    backup_benchmark
+);
+criterion_main!(benches);
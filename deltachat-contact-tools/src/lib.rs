@@ -385,13 +385,66 @@ pub fn may_be_valid_addr(addr: &str) -> bool {
 
 /// Returns address lowercased,
 /// with whitespace trimmed and `mailto:` prefix removed.
+///
+/// Internationalized domains are converted to ASCII (punycode) form,
+/// so the same address is normalized the same way
+/// no matter whether the domain is written in Unicode or ASCII.
 pub fn addr_normalize(addr: &str) -> String {
     let norm = addr.trim().to_lowercase();
 
-    if norm.starts_with("mailto:") {
+    let norm = if norm.starts_with("mailto:") {
         norm.get(7..).unwrap_or(&norm).to_string()
     } else {
         norm
+    };
+    addr_with_ascii_domain(&norm)
+}
+
+/// Converts an internationalized domain to ASCII (punycode) form,
+/// e.g. `münchen.de` to `xn--mnchen-3ya.de`.
+///
+/// ASCII domains and domains that cannot be converted are returned as is.
+pub fn domain_to_ascii(domain: &str) -> String {
+    if domain.is_ascii() {
+        return domain.to_string();
+    }
+    idna::domain_to_ascii(domain).unwrap_or_else(|_| domain.to_string())
+}
+
+/// Converts a domain in ASCII (punycode) form to Unicode form,
+/// e.g. `xn--mnchen-3ya.de` to `münchen.de`.
+///
+/// Domains that cannot be converted are returned as is.
+pub fn domain_to_unicode(domain: &str) -> String {
+    match idna::domain_to_unicode(domain) {
+        (unicode, Ok(())) => unicode,
+        (_, Err(_)) => domain.to_string(),
+    }
+}
+
+/// Returns the address with its domain in ASCII (punycode) form,
+/// as needed for SMTP envelopes, message headers and DNS lookups.
+///
+/// The local part is kept as is, non-ASCII local parts require SMTPUTF8 support.
+/// Strings that are not valid addresses are returned as is.
+pub fn addr_with_ascii_domain(addr: &str) -> String {
+    match EmailAddress::new(addr) {
+        Ok(email) if !email.domain.is_ascii() => {
+            format!("{}@{}", email.local, domain_to_ascii(&email.domain))
+        }
+        _ => addr.to_string(),
+    }
+}
+
+/// Returns the address with its domain in Unicode form, for displaying it to the user.
+///
+/// Strings that are not valid addresses are returned as is.
+pub fn addr_with_unicode_domain(addr: &str) -> String {
+    match EmailAddress::new(addr) {
+        Ok(email) if email.domain.contains("xn--") => {
+            format!("{}@{}", email.local, domain_to_unicode(&email.domain))
+        }
+        _ => addr.to_string(),
     }
 }
 
@@ -435,6 +488,11 @@ fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 
 impl EmailAddress {
     /// Performs a dead-simple parse of an email address.
+    ///
+    /// Internationalized addresses ([RFC 6530]) with non-ASCII local parts
+    /// and internationalized domains in Unicode or ASCII (punycode) form are accepted.
+    ///
+    /// [RFC 6530]: https://www.rfc-editor.org/rfc/rfc6530
     pub fn new(input: &str) -> Result<EmailAddress> {
         if input.is_empty() {
             bail!("empty string is not valid");
@@ -443,9 +501,12 @@ pub fn new(input: &str) -> Result<EmailAddress> {
 
         if input
             .chars()
-            .any(|c| c.is_whitespace() || c == '<' || c == '>')
+            .any(|c| c.is_whitespace() || c.is_control() || c == '<' || c == '>')
         {
-            bail!("Email {:?} must not contain whitespaces, '>' or '<'", input);
+            bail!(
+                "Email {:?} must not contain whitespaces, control characters, '>' or '<'",
+                input
+            );
         }
 
         match &parts[..] {
@@ -459,6 +520,12 @@ pub fn new(input: &str) -> Result<EmailAddress> {
                 if domain.ends_with('.') {
                     bail!("Domain {domain:?} should not contain the dot in the end");
                 }
+                if !domain.is_ascii() {
+                    // Internationalized domain, must be convertible to punycode.
+                    idna::domain_to_ascii(domain)
+                        .ok()
+                        .with_context(|| format!("Invalid internationalized domain {domain:?}"))?;
+                }
                 Ok(EmailAddress {
                     local: (*local).to_string(),
                     domain: (*domain).to_string(),
@@ -606,9 +673,40 @@ fn test_contact_address() -> Result<()> {
         let invalid_addr = "<> foobar";
         assert!(ContactAddress::new(invalid_addr).is_err());
 
+        let contact_address = ContactAddress::new("Alice@München.DE")?;
+        assert_eq!(contact_address.as_ref(), "alice@xn--mnchen-3ya.de");
+        let contact_address = ContactAddress::new("пользователь@пример.рф")?;
+        assert_eq!(
+            contact_address.as_ref(),
+            "пользователь@xn--e1afmkfd.xn--p1ai"
+        );
+
         Ok(())
     }
 
+    #[test]
+    fn test_idn_addresses() {
+        assert!(addr_cmp("alice@münchen.de", "Alice@xn--mnchen-3ya.de"));
+        assert_eq!(
+            addr_with_ascii_domain("alice@münchen.de"),
+            "alice@xn--mnchen-3ya.de"
+        );
+        assert_eq!(
+            addr_with_unicode_domain("alice@xn--mnchen-3ya.de"),
+            "alice@münchen.de"
+        );
+        assert_eq!(
+            addr_with_unicode_domain("alice@example.org"),
+            "alice@example.org"
+        );
+        assert_eq!(addr_with_ascii_domain("not an address"), "not an address");
+        assert_eq!(domain_to_ascii("Example.org"), "Example.org");
+        assert_eq!(domain_to_ascii("bücher.example"), "xn--bcher-kva.example");
+
+        assert!(EmailAddress::new("δοκιμή@παράδειγμα.δοκιμή").is_ok());
+        assert!(EmailAddress::new("alice@exa\u{0}mple.org").is_err());
+    }
+
     #[test]
     fn test_emailaddress_parse() {
         assert_eq!(EmailAddress::new("").is_ok(), false);
@@ -537,6 +537,50 @@ char*           dc_get_blobdir               (const dc_context_t* context);
  * - `webxdc_realtime_enabled` = Whether the realtime APIs should be enabled.
  *                               0 = WebXDC realtime API is disabled and behaves as noop.
  *                               1 = WebXDC realtime API is enabled (default).
+ * - `reactions_count_as_fresh` = 1=incoming reactions to own messages count towards
+ *                    dc_get_fresh_msg_cnt() until the chat is noticed,
+ *                    0=reactions do not change the fresh message counter (default).
+ * - `request_ocr`  = 1=request text recognition in new images with #DC_EVENT_OCR_REQUEST,
+ *                    0=do not request text recognition (default).
+ * - `dnd_schedule` = do-not-disturb schedule as comma-separated local time ranges
+ *                    such as `mon 22:00-07:00,sat 00:00-24:00`,
+ *                    ranges ending before they start end on the next day.
+ *                    While the schedule is active, #DC_EVENT_INCOMING_MSG is not emitted,
+ *                    see dc_is_dnd_active(). The schedule is synchronized to other devices.
+ *                    Unset by default.
+ * - `webxdc_max_size` = maximum size of received webxdc apps in bytes.
+ *                    Larger apps are shown as files, see dc_msg_get_webxdc_unsupported().
+ *                    Unencrypted messages with larger apps are downloaded only partially.
+ *                    Encrypted messages are downloaded as usual
+ *                    and their apps are checked after decryption.
+ *                    0=no limit (default).
+ * - `blob_quarantine_days` = number of days unused files are kept in quarantine
+ *                    before housekeeping deletes them, default 7.
+ *                    0=unused files are deleted immediately.
+ * - `group_member_soft_limit` = number of group members above which adding members
+ *                    adds a warning about the traffic of large groups to the chat, default 200.
+ *                    0=no warning.
+ * - `group_member_hard_limit` = maximum number of group members, default 1000.
+ *                    Adding more members with dc_add_contact_to_chat() fails.
+ *                    0=no limit.
+ * - `unified_push_gateway` = URL of a self-hosted UnifiedPush gateway
+ *                    where UnifiedPush endpoints are registered.
+ *                    If unset, UnifiedPush registrations are not sent anywhere.
+ * - `timezone`     = timezone used to place day markers, see #DC_GCM_ADDDAYMARKER,
+ *                    and to evaluate `dnd_schedule`,
+ *                    as an IANA timezone name such as `Europe/Berlin`,
+ *                    taking daylight saving time changes into account.
+ *                    Fixed UTC offsets such as `+02:00` are accepted as well.
+ *                    If unset (default), the system timezone is used,
+ *                    taking daylight saving time changes into account.
+ * - `dkim_selector` = DKIM selector used to sign outgoing messages,
+ *                    for self-hosted servers that do not sign messages themselves.
+ *                    The public key must be published at `<selector>._domainkey.<domain>`.
+ * - `dkim_private_key` = PEM-encoded RSA private key used to sign outgoing messages
+ *                    if `dkim_selector` is set as well.
+ * - `display_punycode` = 1=display internationalized domains of addresses
+ *                    in their ASCII (punycode) form, see dc_contact_get_display_addr(),
+ *                    0=display them in Unicode (default).
  *
  * If you want to retrieve a value, use dc_get_config().
  *
@@ -704,6 +748,22 @@ int             dc_get_connectivity          (dc_context_t* context);
 char*           dc_get_connectivity_html     (dc_context_t* context);
 
 
+/**
+ * Generate a self-diagnostics report to be shared with support.
+ *
+ * The report contains the connectivity and the last error of each connection,
+ * the folder configuration, quota, provider, key counts
+ * and recent log messages with email addresses removed.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param json 1 to get the report as JSON, 0 to get it as plain text.
+ * @return The report, empty string on errors.
+ *     Returned string must be released using dc_str_unref().
+ */
+char*           dc_generate_diagnostics      (dc_context_t* context, int json);
+
+
 #define DC_PUSH_NOT_CONNECTED 0
 #define DC_PUSH_HEARTBEAT     1
 #define DC_PUSH_CONNECTED     2
@@ -777,6 +837,25 @@ int              dc_get_push_state           (dc_context_t* context);
 void            dc_configure                 (dc_context_t* context);
 
 
+/**
+ * Get the steps tried by the current or the last dc_configure() call.
+ *
+ * Use this to show details if the configuration failed,
+ * see also #DC_EVENT_CONFIGURE_FAILED.
+ * Steps include the tried autoconfig URLs, DNS lookups,
+ * IMAP and SMTP servers and ports together with their errors.
+ * The trace is not persisted and is empty
+ * if dc_configure() was not called since the context was opened.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @return JSON array of objects with the keys
+ *     `timestamp`, `step` and `error`, where `error` is null if the step succeeded.
+ *     Returned string must be released using dc_str_unref().
+ */
+char*           dc_get_last_configure_trace  (dc_context_t* context);
+
+
 /**
  * Check if the context is already configured.
  *
@@ -799,6 +878,19 @@ void            dc_configure                 (dc_context_t* context);
 int             dc_is_configured   (const dc_context_t* context);
 
 
+/**
+ * Check if the do-not-disturb schedule set in the config option `dnd_schedule` is active.
+ * While it is active, new messages emit #DC_EVENT_MSGS_CHANGED
+ * instead of #DC_EVENT_INCOMING_MSG.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @return 1=do-not-disturb is active, no notifications should be shown;
+ *     0=do-not-disturb is not active.
+ */
+int             dc_is_dnd_active   (dc_context_t* context);
+
+
 /**
  * Start job and IMAP/SMTP tasks.
  * If IO is already running, nothing happens.
@@ -1306,6 +1398,7 @@ dc_msg_t*       dc_get_draft                 (dc_context_t* context, uint32_t ch
 
 #define         DC_GCM_ADDDAYMARKER          0x01
 #define         DC_GCM_INFO_ONLY             0x02
+#define         DC_GCM_INCLUDE_HIDDEN_LOCAL  0x04
 
 
 /**
@@ -1322,9 +1415,12 @@ dc_msg_t*       dc_get_draft                 (dc_context_t* context, uint32_t ch
  * @param context The context object as returned from dc_context_new().
  * @param chat_id The chat ID of which the messages IDs should be queried.
  * @param flags If set to DC_GCM_ADDDAYMARKER, the marker DC_MSG_ID_DAYMARKER will
- *     be added before each day (regarding the local timezone). Set this to 0 if you do not want this behaviour.
- *     To get the concrete time of the marker, use dc_array_get_timestamp().
+ *     be added before each day (regarding the local timezone or the `timezone` config option).
+ *     Set this to 0 if you do not want this behaviour.
+ *     To get the concrete time of the marker, use dc_array_get_timestamp(),
+ *     this is the timestamp of the start of the day.
  *     If set to DC_GCM_INFO_ONLY, only system messages will be returned, can be combined with DC_GCM_ADDDAYMARKER.
+ *     If set to DC_GCM_INCLUDE_HIDDEN_LOCAL, messages hidden with dc_set_msg_hidden_local() are returned as well.
  * @param marker1before Deprecated, set this to 0.
  * @return Array of message IDs, must be dc_array_unref()'d when no longer used.
  */
@@ -1481,6 +1577,80 @@ dc_array_t*     dc_wait_next_msgs            (dc_context_t* context);
 void            dc_marknoticed_chat          (dc_context_t* context, uint32_t chat_id);
 
 
+/**
+ * Mark all messages in all chats as _noticed_,
+ * e.g. for a "Mark all as read" option.
+ * Marks set by dc_set_chat_marked_unread() are removed as well.
+ * Other devices mark the messages received until the time of the call as _noticed_, too.
+ *
+ * Calling this function usually results in the event #DC_EVENT_MSGS_NOTICED
+ * for each affected chat.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as returned from dc_context_new().
+ */
+void            dc_marknoticed_all_chats     (dc_context_t* context);
+
+
+/**
+ * Mark a chat as unread intentionally or remove the mark.
+ * A marked chat counts at least one fresh message in dc_get_fresh_msg_cnt(),
+ * also for the archive link if the chat is archived.
+ * The mark is removed by dc_marknoticed_chat() and dc_marknoticed_all_chats()
+ * and is synchronized to other devices.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as returned from dc_context_new().
+ * @param chat_id The chat ID to mark.
+ * @param unread 1=mark the chat as unread, 0=remove the mark.
+ * @return 1=success, 0=error
+ */
+int             dc_set_chat_marked_unread    (dc_context_t* context, uint32_t chat_id, int unread);
+
+
+/**
+ * Check if a chat is marked as unread using dc_set_chat_marked_unread().
+ *
+ * @memberof dc_context_t
+ * @param context The context object as returned from dc_context_new().
+ * @param chat_id The chat ID to check.
+ * @return 1=chat is marked as unread, 0=chat is not marked or on errors.
+ */
+int             dc_is_chat_marked_unread     (dc_context_t* context, uint32_t chat_id);
+
+
+/**
+ * Set the last read message of a chat.
+ * All incoming _fresh_ messages received not later than the given message
+ * are marked as _noticed_.
+ * The marker is synchronized to other devices,
+ * so that the counters returned by dc_get_fresh_msg_cnt() match on all devices.
+ * Setting a marker older than the current one is ignored.
+ *
+ * Calling this function usually results in the event #DC_EVENT_MSGS_NOTICED.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as returned from dc_context_new().
+ * @param chat_id The chat ID to set the marker for.
+ * @param msg_id The ID of the last read message, must belong to the chat.
+ * @return 1=success, 0=error
+ */
+int             dc_set_last_read_msg         (dc_context_t* context, uint32_t chat_id, uint32_t msg_id);
+
+
+/**
+ * Get the last read message of a chat
+ * as set by dc_set_last_read_msg() on this or another device.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as returned from dc_context_new().
+ * @param chat_id The chat ID to get the marker for.
+ * @return The ID of the last read message.
+ *     0 if no marker is set or the message is not available on this device.
+ */
+uint32_t        dc_get_last_read_msg         (dc_context_t* context, uint32_t chat_id);
+
+
 /**
  * Returns all message IDs of the given types in a given chat or any chat.
  * Typically used to show a gallery.
@@ -1621,6 +1791,29 @@ char*           dc_get_chat_encrinfo (dc_context_t* context, uint32_t chat_id);
  */
 uint32_t dc_get_chat_ephemeral_timer (dc_context_t* context, uint32_t chat_id);
 
+/**
+ * Get the chat's inactivity timer
+ * as set by dc_set_chat_inactivity_timer() on this or another device.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param chat_id The chat ID.
+ * @return The inactivity timer in days, 0 if the timer is disabled or if there is an error.
+ */
+uint32_t dc_get_chat_inactivity_timer (dc_context_t* context, uint32_t chat_id);
+
+/**
+ * Get whether the archived chat is unarchived by new messages,
+ * as set by dc_set_chat_unarchive_policy() on this or another device.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param chat_id The chat ID.
+ * @return One of the @ref DC_UNARCHIVE_POLICY constants,
+ *     @ref DC_UNARCHIVE_POLICY_ANY_MESSAGE on errors.
+ */
+int dc_get_chat_unarchive_policy (dc_context_t* context, uint32_t chat_id);
+
 /**
  * Search messages containing the given query string.
  * Searching can be done globally (chat_id=0) or in a specified chat only (chat_id set).
@@ -1709,6 +1902,24 @@ uint32_t        dc_create_group_chat         (dc_context_t* context, int protect
 uint32_t        dc_create_broadcast_list     (dc_context_t* context);
 
 
+/**
+ * Convert a broadcast list into a group with the same name and members.
+ *
+ * The group is not promoted yet,
+ * the members learn about it with the first message sent to the group.
+ *
+ * Unlike in the broadcast list, the members see each other's addresses in the group,
+ * so the UI should ask the user for confirmation before calling this function.
+ * An info message warning about this is added to the chat.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param chat_id The chat ID of the broadcast list.
+ * @return 1=success, 0=error
+ */
+int             dc_convert_broadcast_to_group (dc_context_t* context, uint32_t chat_id);
+
+
 /**
  * Check if a given contact ID is a member of a group chat.
  *
@@ -1741,6 +1952,80 @@ int             dc_is_contact_in_chat        (dc_context_t* context, uint32_t ch
 int             dc_add_contact_to_chat       (dc_context_t* context, uint32_t chat_id, uint32_t contact_id);
 
 
+/**
+ * Add a member of a protected group back after the member's key changed,
+ * e.g. because the member reinstalled Delta Chat without a backup.
+ *
+ * The member has to be verified again before, e.g. by scanning their QR code.
+ * Then a status message is sent to the group,
+ * so that the member's new device can recreate the group
+ * and all other members learn the new verified key.
+ *
+ * The UI is notified about members needing this with #DC_EVENT_VERIFIED_GROUP_MEMBER_KEY_CHANGED.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param chat_id The protected group the contact is a member of.
+ * @param contact_id The contact ID of the member.
+ * @return 1=success, 0=error
+ */
+int             dc_repair_verified_group_member (dc_context_t* context, uint32_t chat_id, uint32_t contact_id);
+
+
+/**
+ * Allow only admins to change a group or allow all members to change it again.
+ *
+ * In a moderated group only admins can add and remove members
+ * and change the group name and image.
+ * Other members can still leave the group.
+ * Changes by other members are ignored by all members.
+ *
+ * Only admins can change moderation.
+ * The creator of a group is its first admin, more admins can be added using dc_set_group_admin().
+ *
+ * If the group is already _promoted_ (any message was sent to the group),
+ * all group members are informed by a special status message that is sent automatically by this function.
+ *
+ * Sends out #DC_EVENT_CHAT_MODIFIED and #DC_EVENT_MSGS_CHANGED if a status message was sent.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param chat_id The group chat ID.
+ * @param moderated 1=only admins can change the group, 0=all members can change the group.
+ * @return 1=success, 0=error
+ */
+int             dc_set_group_moderated       (dc_context_t* context, uint32_t chat_id, int moderated);
+
+
+/**
+ * Make a group member an admin or revoke the admin rights of a member.
+ *
+ * Only admins can do this and the last admin of a group cannot be removed.
+ * See dc_set_group_moderated() for the rights of admins.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param chat_id The group chat ID.
+ * @param contact_id The contact ID of the member.
+ * @param admin 1=make the member an admin, 0=revoke admin rights.
+ * @return 1=success, 0=error
+ */
+int             dc_set_group_admin           (dc_context_t* context, uint32_t chat_id, uint32_t contact_id, int admin);
+
+
+/**
+ * Check if a contact is an admin of a group.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param chat_id The group chat ID.
+ * @param contact_id The contact ID to check. To check if yourself is an admin,
+ *     pass DC_CONTACT_ID_SELF (1) here.
+ * @return 1=contact is an admin of the group, 0=contact is not an admin.
+ */
+int             dc_is_group_admin            (dc_context_t* context, uint32_t chat_id, uint32_t contact_id);
+
+
 /**
  * Remove a member from a group.
  *
@@ -1793,6 +2078,51 @@ int             dc_set_chat_name             (dc_context_t* context, uint32_t ch
  */
 int dc_set_chat_ephemeral_timer (dc_context_t* context, uint32_t chat_id, uint32_t timer);
 
+/**
+ * Set the chat's inactivity timer.
+ *
+ * All messages of the chat are deleted from this device
+ * once the chat was neither noticed using dc_marknoticed_chat()
+ * nor archived for the given number of days.
+ * The countdown starts when the timer is set
+ * and restarts every time the chat is noticed or archived.
+ * Messages received during the countdown are kept for at least the same time.
+ * Messages on the server are not affected.
+ *
+ * The setting is synchronized to other devices of the user,
+ * the countdown is not.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param chat_id The chat ID to set the inactivity timer for.
+ * @param days The timer value in days or 0 to disable the timer.
+ * @return 1=success, 0=error
+ */
+int dc_set_chat_inactivity_timer (dc_context_t* context, uint32_t chat_id, uint32_t days);
+
+/**
+ * Set whether the archived chat is unarchived by new messages.
+ *
+ * By default, archived chats that are not muted are unarchived by any new message.
+ * Archived chats with another policy do not count towards
+ * the fresh messages of the archive link
+ * and are not returned by dc_get_fresh_msgs().
+ *
+ * The setting is synchronized to other devices of the user.
+ *
+ * Sends out #DC_EVENT_CHAT_MODIFIED.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param chat_id The chat ID to set the policy for.
+ * @param policy One of the @ref DC_UNARCHIVE_POLICY constants:
+ *     - @ref DC_UNARCHIVE_POLICY_ANY_MESSAGE: unarchive the chat on any new message unless it is muted
+ *     - @ref DC_UNARCHIVE_POLICY_MENTION: unarchive the chat only on incoming messages mentioning self
+ *     - @ref DC_UNARCHIVE_POLICY_NEVER: keep the chat archived
+ * @return 1=success, 0=error
+ */
+int dc_set_chat_unarchive_policy (dc_context_t* context, uint32_t chat_id, int policy);
+
 /**
  * Set group profile image.
  *
@@ -1906,6 +2236,70 @@ char*           dc_get_msg_info              (dc_context_t* context, uint32_t ms
 char*           dc_get_msg_html              (dc_context_t* context, uint32_t msg_id);
 
 
+/**
+ * Store the translation of a message
+ * requested by #DC_EVENT_TRANSLATION_REQUEST.
+ *
+ * The translation is cached alongside the original message.
+ * Translations to another language than requested are ignored.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param msg_id The ID of the translated message.
+ * @param target_lang The language the message was translated to, as given in the event.
+ * @param text The translated text.
+ * @return 1=success, 0=error
+ */
+int             dc_set_msg_translation       (dc_context_t* context, uint32_t msg_id, const char* target_lang, const char* text);
+
+
+/**
+ * Request text recognition in the image attachment of a message.
+ *
+ * For new messages, this is done automatically if the config option `request_ocr` is set.
+ * #DC_EVENT_OCR_REQUEST is emitted unless the text is recognized already.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param msg_id The ID of a message with an image attachment.
+ * @return 1=success, 0=error
+ */
+int             dc_request_msg_ocr           (dc_context_t* context, uint32_t msg_id);
+
+
+/**
+ * Store the text recognized in the image attachment of a message
+ * as requested by #DC_EVENT_OCR_REQUEST.
+ *
+ * The text is cached in the message
+ * and found by dc_search_msgs() afterwards.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param msg_id The ID of the message.
+ * @param text The recognized text.
+ *     Pass an empty string if the image contains no text,
+ *     so that recognition is not requested again.
+ * @return 1=success, 0=error
+ */
+int             dc_set_msg_ocr_text          (dc_context_t* context, uint32_t msg_id, const char* text);
+
+
+/**
+ * Import the public key attached to a message as the key of the sender.
+ *
+ * The key must be an ASCII-armored key of the sender,
+ * see #DC_EVENT_INCOMING_PUBLIC_KEY.
+ * Call this only after the user confirmed the import.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param msg_id The ID of the message with the key attachment.
+ * @return 1=success, 0=error
+ */
+int             dc_import_key_from_msg       (dc_context_t* context, uint32_t msg_id);
+
+
 /**
   * Asks the core to start downloading a message fully.
   * This function is typically called when the user hits the "Download" button
@@ -1945,6 +2339,23 @@ void dc_download_full_msg (dc_context_t* context, int msg_id);
 char*           dc_get_mime_headers          (dc_context_t* context, uint32_t msg_id);
 
 
+/**
+ * Export the raw messages as `.eml` files, e.g. to open them in other mail clients.
+ * Raw messages are available for incoming messages
+ * only if `dc_set_config(context, "save_mime_headers", "1")`
+ * was called before receiving them.
+ * Messages that are not fully downloaded cannot be exported.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param msg_ids An array of uint32_t containing the message IDs to export.
+ * @param msg_cnt The number of messages IDs in the msg_ids array.
+ * @param dir The directory to write the files to. It is created if it does not exist.
+ * @return 1=success, 0=error, e.g. if no raw message is saved for one of the messages.
+ */
+int             dc_export_eml                (dc_context_t* context, const uint32_t* msg_ids, int msg_cnt, const char* dir);
+
+
 /**
  * Delete messages. The messages are deleted on the current device and
  * on the IMAP server.
@@ -2023,6 +2434,23 @@ void            dc_save_msgs                 (dc_context_t* context, const uint3
 int             dc_resend_msgs               (dc_context_t* context, const uint32_t* msg_ids, int msg_cnt);
 
 
+/**
+ * Hide a message from the chat on this device without deleting it,
+ * e.g. to hide offensive messages in shared accounts while keeping them as evidence.
+ *
+ * Hidden messages are not returned by dc_get_chat_msgs() unless DC_GCM_INCLUDE_HIDDEN_LOCAL is set
+ * and are not found by dc_search_msgs() or dc_get_chat_media().
+ * Hiding is not sent to other devices or chat members, but is kept in backups.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param msg_id The ID of the message to hide or unhide.
+ * @param hidden 1=hide the message, 0=show the message again.
+ * @return 1=success, 0=error
+ */
+int             dc_set_msg_hidden_local      (dc_context_t* context, uint32_t msg_id, int hidden);
+
+
 /**
  * Mark messages as presented to the user.
  * Typically, UIs call this function on scrolling through the message list,
@@ -2089,6 +2517,21 @@ dc_msg_t*       dc_get_msg                   (dc_context_t* context, uint32_t ms
 int             dc_may_be_valid_addr         (const char* addr);
 
 
+/**
+ * Count the emojis of a text consisting only of emojis and whitespace.
+ *
+ * Grapheme clusters such as flags or ZWJ sequences count as one emoji.
+ * UIs may use this to display short emoji-only messages larger,
+ * so that all platforms take the same decision.
+ *
+ * @memberof dc_context_t
+ * @param text The text to check, e.g. the result of dc_msg_get_text().
+ * @return The number of emojis,
+ *     0 if the text contains anything else than emojis and whitespace.
+ */
+int             dc_emoji_only_count          (const char* text);
+
+
 /**
  * Check if an e-mail address belongs to a known and unblocked contact.
  * To get a list of all known and unblocked contacts, use dc_get_contacts().
@@ -2261,6 +2704,11 @@ dc_contact_t*   dc_get_contact               (dc_context_t* context, uint32_t co
 #define         DC_IMEX_IMPORT_SELF_KEYS      2 // param1 is a directory where the keys are searched in and read from
 #define         DC_IMEX_EXPORT_BACKUP        11 // param1 is a directory where the backup is written to, param2 is a passphrase to encrypt the backup
 #define         DC_IMEX_IMPORT_BACKUP        12 // param1 is the file with the backup to import, param2 is the backup's passphrase
+#define         DC_IMEX_EXPORT_BACKUP_TO_FOLDER   13 // param1 is a directory where the transfer folder is created, param2 is a passphrase to encrypt the backup
+#define         DC_IMEX_IMPORT_BACKUP_FROM_FOLDER 14 // param1 is the transfer folder to import, param2 is the passphrase
+#define         DC_IMEX_IMPORT_MAILS         21 // param1 is an mbox file, an .eml file or a directory with .eml files
+#define         DC_IMEX_EXPORT_SETTINGS      31 // param1 is a directory where the settings file is written to, param2 is a passphrase to encrypt the file
+#define         DC_IMEX_IMPORT_SETTINGS      32 // param1 is the settings file to import, param2 is the file's passphrase
 
 
 /**
@@ -2279,6 +2727,17 @@ dc_contact_t*   dc_get_contact               (dc_context_t* context, uint32_t co
  *   The file is normally created by DC_IMEX_EXPORT_BACKUP and detected by dc_imex_has_backup(). Importing a backup
  *   is only possible as long as the context is not configured or used in another way.
  *
+ * - **DC_IMEX_EXPORT_BACKUP_TO_FOLDER** (13) - Export a backup for transferring it to another device
+ *   through a folder, e.g. on a USB stick, if the devices cannot be paired using dc_backup_provider_new().
+ *   A new transfer folder `delta-chat-transfer-<day>-<time>-<addr>` is created inside the directory given as `param1`
+ *   and the backup is written to it in chunks encrypted with the passphrase given as `param2`, which must not be empty.
+ *   The transfer folder is complete once #DC_EVENT_IMEX_FILE_WRITTEN is emitted for it.
+ *
+ * - **DC_IMEX_IMPORT_BACKUP_FROM_FOLDER** (14) - `param1` is a transfer folder created by DC_IMEX_EXPORT_BACKUP_TO_FOLDER,
+ *   `param2` is its passphrase. The chunks are decrypted and imported one by one,
+ *   so no additional space for an unencrypted copy of the backup is needed.
+ *   Importing a backup is only possible as long as the context is not configured or used in another way.
+ *
  * - **DC_IMEX_EXPORT_SELF_KEYS** (1) - Export all private keys and all public keys of the user to the
  *   directory given as `param1`. The default key is written to the files `public-key-default.asc`
  *   and `private-key-default.asc`, if there are more keys, they are written to files as
@@ -2288,6 +2747,22 @@ dc_contact_t*   dc_get_contact               (dc_context_t* context, uint32_t co
  *   The last imported key is made the default keys unless its name contains the string `legacy`. Public keys are not imported.
  *   If `param1` is a filename, import the private key from the file and make it the default.
  *
+ * - **DC_IMEX_IMPORT_MAILS** (21) - Import e-mails from other mail clients into chats.
+ *   `param1` is an mbox file, a single `.eml` file or a directory containing `.eml` files.
+ *   Messages are imported in the order of their sending date
+ *   and messages that are already in the database are skipped.
+ *
+ * - **DC_IMEX_EXPORT_SETTINGS** (31) - Export the settings of the account to the directory given as `param1`,
+ *   encrypted with the passphrase given as `param2`, which must not be empty.
+ *   The file contains server settings, display name, avatar and preferences,
+ *   but no messages, keys, login names or passwords,
+ *   so it can be used to set up another profile or shared as a provider preset.
+ *   The name of the file is `delta-chat-settings-<day>.asc`.
+ *
+ * - **DC_IMEX_IMPORT_SETTINGS** (32) - `param1` is a file created by DC_IMEX_EXPORT_SETTINGS,
+ *   `param2` is its passphrase. Importing settings is only possible as long as the context is not configured.
+ *   Afterwards, the user enters address and password and the account is configured as usual.
+ *
  * While dc_imex() returns immediately, the started job may take a while,
  * you can stop it using dc_stop_ongoing_process(). During execution of the job,
  * some events are sent out:
@@ -2594,6 +3069,27 @@ dc_lot_t*       dc_check_qr                  (dc_context_t* context, const char*
 char*           dc_get_securejoin_qr         (dc_context_t* context, uint32_t chat_id);
 
 
+/**
+ * Get a word-encoded Setup-Contact invite code.
+ *
+ * The code consists of the own address followed by 24 words
+ * and can be dictated over the phone or typed in by hand
+ * where scanning a QR code is not possible.
+ * Words may be abbreviated to their first four letters when typed in.
+ *
+ * Passed to dc_check_qr(), the code returns DC_QR_ASK_VERIFYCONTACT
+ * and can be used with dc_join_securejoin() like a QR code.
+ * The code contains a checksum and is valid for at least two days.
+ * It can only be answered by this device.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @return The invite code. On errors, an empty string is returned, NULL is never returned.
+ *     The returned string must be released using dc_str_unref() after usage.
+ */
+char*           dc_get_securejoin_invite_words (dc_context_t* context);
+
+
 /**
  * Get QR code image from the QR code text generated by dc_get_securejoin_qr().
  * See dc_get_securejoin_qr() for details about the contained QR code.
@@ -3223,6 +3719,43 @@ void           dc_accounts_set_push_device_token (dc_accounts_t* accounts, const
 dc_event_emitter_t* dc_accounts_get_event_emitter (dc_accounts_t* accounts);
 
 
+#define DC_EVENT_FILTER_COALESCE   0x01
+#define DC_EVENT_FILTER_RATE_LIMIT 0x02
+
+/**
+ * Create an event emitter that only receives some events.
+ *
+ * Events not matching the filter are dropped before they are queued,
+ * so busy accounts do not flood the emitter with events the UI does not need.
+ * Unlike emitters created with dc_accounts_get_event_emitter(),
+ * each filtered emitter receives its own copy of the matching events.
+ *
+ * @memberof dc_accounts_t
+ * @param accounts The account manager as created by dc_accounts_new().
+ * @param event_ids Array of @ref DC_EVENT constants to receive.
+ *     NULL or an empty array receives events of all types.
+ *     #DC_EVENT_CHANNEL_OVERFLOW is always received.
+ * @param event_ids_cnt Number of items in `event_ids`.
+ * @param account_ids Array of account IDs to receive events of.
+ *     NULL or an empty array receives events of all accounts.
+ *     Events of the account manager itself, e.g. #DC_EVENT_ACCOUNTS_CHANGED, are always received.
+ * @param account_ids_cnt Number of items in `account_ids`.
+ * @param flags If #DC_EVENT_FILTER_COALESCE is set,
+ *     frequent events such as #DC_EVENT_MSGS_CHANGED or #DC_EVENT_CHATLIST_CHANGED
+ *     are not queued if an equal event is already waiting to be received.
+ *     If #DC_EVENT_FILTER_RATE_LIMIT is set,
+ *     #DC_EVENT_MSGS_CHANGED, #DC_EVENT_REACTIONS_CHANGED, #DC_EVENT_CHAT_MODIFIED
+ *     and #DC_EVENT_CHATLIST_ITEM_CHANGED are delivered at most once per chat within 300 ms,
+ *     events arriving in between are delivered at the end of this time.
+ *     #DC_EVENT_MSGS_CHANGED events are merged into one with `data2` set to 0
+ *     if several messages changed, other events are only merged if they are equal.
+ *     This avoids freezing the UI while catching up with many messages.
+ * @return Returns the event emitter, NULL on errors.
+ *     Must be freed using dc_event_emitter_unref() after usage.
+ */
+dc_event_emitter_t* dc_accounts_get_filtered_event_emitter (dc_accounts_t* accounts, const int* event_ids, int event_ids_cnt, const uint32_t* account_ids, int account_ids_cnt, int flags);
+
+
 /**
  * @class dc_array_t
  *
@@ -3503,6 +4036,36 @@ uint32_t         dc_chatlist_get_msg_id      (const dc_chatlist_t* chatlist, siz
 dc_lot_t*        dc_chatlist_get_summary     (const dc_chatlist_t* chatlist, size_t index, dc_chat_t* chat);
 
 
+/**
+ * Get a summary for a chatlist index as a structured object in JSON format.
+ *
+ * Unlike dc_chatlist_get_summary(), the sender, the type of the last message
+ * and a reaction newer than the last message are returned in separate fields,
+ * so there is no need to parse the texts.
+ * The returned JSON object has the following keys:
+ *
+ * - prefix: null or an object with `kind` set to `Username`, `Draft` or `Me`;
+ *   `Username` has the sender name in `name`, the others have a stock string in `text`.
+ * - text: excerpt of the message text or strings as "No messages".
+ * - timestamp: the timestamp of the message. 0 if not applicable.
+ * - state: The state of the message as one of the @ref DC_STATE constants. 0 if not applicable.
+ * - previewImage: path of a preview image or null.
+ * - viewtype: type of the message, e.g. `Text` or `Image`; may be used to show an icon.
+ * - reaction: null or an object with the keys `contactId`, `contactName`, `emoji`
+ *   and `messageText`, set if a reaction is newer than the last message.
+ *   In this case, `text` describes the reaction.
+ *
+ * @memberof dc_chatlist_t
+ * @param chatlist The chatlist to query as returned e.g. from dc_get_chatlist().
+ * @param index The index to query in the chatlist.
+ * @param chat To speed up things, pass an already available chat object here.
+ *     If the chat object is not yet available, it is faster to pass NULL.
+ * @return UTF-8 encoded JSON string. Must be freed using dc_str_unref().
+ *     Empty string on errors. NULL is never returned.
+ */
+char*            dc_chatlist_get_summary_json (const dc_chatlist_t* chatlist, size_t index, dc_chat_t* chat);
+
+
 /**
  * Create a chatlist summary item when the chatlist object is already unref()'d.
  *
@@ -3806,6 +4369,20 @@ int             dc_chat_can_send              (const dc_chat_t* chat);
 int             dc_chat_is_protected         (const dc_chat_t* chat);
 
 
+/**
+ * Check if only admins can change members, name and image of a group,
+ * see dc_set_group_moderated().
+ *
+ * UI should hide the options to change the group from members
+ * for whom dc_is_group_admin() returns 0.
+ *
+ * @memberof dc_chat_t
+ * @param chat The chat object.
+ * @return 1=group is moderated, 0=all members can change the group.
+ */
+int             dc_chat_is_moderated         (const dc_chat_t* chat);
+
+
 /**
  * Checks if the chat was protected, and then an incoming message broke this protection.
  *
@@ -4084,6 +4661,34 @@ char*           dc_msg_get_text               (const dc_msg_t* msg);
 char*           dc_msg_get_subject            (const dc_msg_t* msg);
 
 
+/**
+ * Get the text recognized in the image attachment of the message,
+ * see dc_set_msg_ocr_text().
+ *
+ * @memberof dc_msg_t
+ * @param msg The message object.
+ * @return The recognized text, may be empty if the image contains no text.
+ *     NULL if text recognition was not done.
+ *     The result must be released using dc_str_unref().
+ */
+char*           dc_msg_get_ocr_text           (const dc_msg_t* msg);
+
+
+/**
+ * Check why a `.xdc` attachment is shown as a file instead of a webxdc app.
+ * Unencrypted messages containing webxdc apps larger than the `webxdc_max_size` config option
+ * are not downloaded automatically, see dc_msg_get_download_state().
+ *
+ * @memberof dc_msg_t
+ * @param msg The message object.
+ * @return One of:
+ *     - 0: the message is a usable webxdc app or no `.xdc` attachment.
+ *     - @ref DC_WEBXDC_OVERSIZED: the app exceeds `webxdc_max_size`.
+ *     - @ref DC_WEBXDC_INVALID: the attachment is no valid webxdc app.
+ */
+int             dc_msg_get_webxdc_unsupported (const dc_msg_t* msg);
+
+
 /**
  * Find out full path of the file associated with a message.
  *
@@ -4455,6 +5060,16 @@ int             dc_msg_is_forwarded           (const dc_msg_t* msg);
 int             dc_msg_is_info                (const dc_msg_t* msg);
 
 
+/**
+ * Check if the message is hidden locally with dc_set_msg_hidden_local().
+ *
+ * @memberof dc_msg_t
+ * @param msg The message object.
+ * @return 1=message is hidden locally, 0=message is shown.
+ */
+int             dc_msg_is_hidden_local        (const dc_msg_t* msg);
+
+
 /**
  * Get the type of an informational message.
  * If dc_msg_is_info() returns 1, this function returns the type of the informational message.
@@ -4495,6 +5110,7 @@ int             dc_msg_get_info_type          (const dc_msg_t* msg);
 #define         DC_INFO_PROTECTION_DISABLED       12
 #define         DC_INFO_INVALID_UNENCRYPTED_MAIL  13
 #define         DC_INFO_WEBXDC_INFO_MESSAGE       32
+#define         DC_INFO_GROUP_ADMINS_CHANGED      42
 
 
 /**
@@ -4907,6 +5523,29 @@ char*           dc_msg_get_quoted_text        (const dc_msg_t* msg);
  */
 dc_msg_t*       dc_msg_get_quoted_msg         (const dc_msg_t* msg);
 
+
+#define DC_QUOTE_INTEGRITY_UNKNOWN  0
+#define DC_QUOTE_INTEGRITY_VERIFIED 1
+#define DC_QUOTE_INTEGRITY_MODIFIED 2
+
+/**
+ * Check whether the quoted text matches the quoted message.
+ *
+ * The quoted text returned by dc_msg_get_quoted_text() is sent by the sender
+ * and may not match what the quoted message actually says.
+ * If the quoted message is available locally, the quoted text is compared to it,
+ * so UIs may flag quotes that were tampered with.
+ *
+ * @memberof dc_msg_t
+ * @param msg The message object.
+ * @return One of:
+ *     - DC_QUOTE_INTEGRITY_UNKNOWN (0): There is no quote,
+ *       the quoted message is not available or the quote cannot be compared to it.
+ *     - DC_QUOTE_INTEGRITY_VERIFIED (1): The quoted text matches the quoted message.
+ *     - DC_QUOTE_INTEGRITY_MODIFIED (2): The quoted text differs from the quoted message.
+ */
+int             dc_msg_get_quote_integrity    (const dc_msg_t* msg);
+
 /**
  * Get parent message, if available.
  *
@@ -5019,6 +5658,20 @@ uint32_t        dc_contact_get_id            (const dc_contact_t* contact);
 char*           dc_contact_get_addr          (const dc_contact_t* contact);
 
 
+/**
+ * Get the e-mail address of a contact for display.
+ * Internationalized domains are returned in Unicode
+ * unless the config option `display_punycode` is set.
+ * Use dc_contact_get_addr() to get the address as used on the network.
+ *
+ * @memberof dc_contact_t
+ * @param contact The contact object.
+ * @return A string with the e-mail address,
+ *     must be released using dc_str_unref(). Never returns NULL.
+ */
+char*           dc_contact_get_display_addr  (const dc_contact_t* contact);
+
+
 /**
  * Get the edited contact name.
  * This is the name as given or modified by the local user using dc_create_contact().
@@ -6175,6 +6828,23 @@ void dc_event_unref(dc_event_t* event);
 #define DC_EVENT_INCOMING_WEBXDC_NOTIFY   2003
 
 
+/**
+ * An incoming message has a public key of the sender attached
+ * which differs from the key currently used for the sender.
+ *
+ * The UI may show the fingerprint to the user,
+ * together with the current one from dc_get_contact_encrinfo(),
+ * and offer importing the key with dc_import_key_from_msg().
+ *
+ * @param data1 (int) chat_id
+ * @param data2 (int) msg_id _and_ (char*) fingerprint of the attached key.
+ *      - dc_event_get_data2_int() returns the msg_id.
+ *      - dc_event_get_data2_str() returns the hex-encoded fingerprint,
+ *        string must be passed to dc_str_unref() afterwards.
+ */
+#define DC_EVENT_INCOMING_PUBLIC_KEY      2004
+
+
 /**
  * There is a fresh message. Typically, the user will show an notification
  * when receiving this message.
@@ -6203,6 +6873,20 @@ void dc_event_unref(dc_event_t* event);
 #define DC_EVENT_INCOMING_MSG_BUNCH       2006
 
 
+/**
+ * An incoming message starts with a command registered by a bot,
+ * e.g. `/help`.
+ *
+ * Emitted in addition to #DC_EVENT_INCOMING_MSG.
+ * Bots should use the JSON-RPC API to register commands
+ * and get the parsed command and arguments.
+ *
+ * @param data1 (int) chat_id
+ * @param data2 (int) msg_id
+ */
+#define DC_EVENT_BOT_COMMAND              2007
+
+
 /**
  * Messages were marked noticed or seen.
  * The UI may update badge counters or stop showing a chatlist-item with a bold font.
@@ -6258,6 +6942,36 @@ void dc_event_unref(dc_event_t* event);
 #define DC_EVENT_MSG_DELETED              2016
 
 
+/**
+ * Translation of a message is requested,
+ * because the chat has automatic translation enabled.
+ *
+ * The UI should translate the text of the message
+ * with the provider configured for the chat
+ * and call dc_set_msg_translation() with the result.
+ *
+ * @param data1 (int) msg_id
+ * @param data2 (char*) target language, e.g. `de`.
+ *      Must be passed to dc_str_unref() afterwards.
+ */
+#define DC_EVENT_TRANSLATION_REQUEST      2017
+
+
+/**
+ * Text recognition in the image attachment of a message is requested,
+ * because the config option `request_ocr` is set
+ * or dc_request_msg_ocr() was called.
+ *
+ * The UI should recognize the text in the image file
+ * and call dc_set_msg_ocr_text() with the result.
+ *
+ * @param data1 (int) msg_id
+ * @param data2 (char*) path to the image file.
+ *      Must be passed to dc_str_unref() afterwards.
+ */
+#define DC_EVENT_OCR_REQUEST              2018
+
+
 /**
  * Chat changed. The name or the image of a chat group was changed or members were added or removed.
  * Or the verify state of a chat has changed.
@@ -6277,6 +6991,18 @@ void dc_event_unref(dc_event_t* event);
  */
 #define DC_EVENT_CHAT_EPHEMERAL_TIMER_MODIFIED 2021
 
+/**
+ * The key of a member of a protected group changed since the member was verified,
+ * e.g. because the member reinstalled Delta Chat without a backup.
+ *
+ * The UI should offer to verify the member again, e.g. by scanning their QR code,
+ * and then call dc_repair_verified_group_member().
+ *
+ * @param data1 (int) chat_id of the protected group
+ * @param data2 (int) contact_id of the member whose key changed
+ */
+#define DC_EVENT_VERIFIED_GROUP_MEMBER_KEY_CHANGED 2022
+
 
 /**
  * Contact(s) created, renamed, verified, blocked or deleted.
@@ -6308,6 +7034,19 @@ void dc_event_unref(dc_event_t* event);
 #define DC_EVENT_CONFIGURE_PROGRESS       2041
 
 
+/**
+ * Configuration started by dc_configure() has failed.
+ *
+ * Emitted in addition to #DC_EVENT_CONFIGURE_PROGRESS with data1 set to 0.
+ *
+ * @param data1 0
+ * @param data2 (char*) JSON object with the keys `error` containing the error message
+ *     and `trace` containing the steps tried,
+ *     in the same format as returned by dc_get_last_configure_trace().
+ */
+#define DC_EVENT_CONFIGURE_FAILED         2042
+
+
 /**
  * Inform about the import/export progress started by dc_imex().
  *
@@ -6330,6 +7069,45 @@ void dc_event_unref(dc_event_t* event);
 #define DC_EVENT_IMEX_FILE_WRITTEN        2052
 
 
+/**
+ * Inform about the number of bytes transferred
+ * while sending or receiving a backup with dc_backup_provider_new() or dc_receive_backup().
+ *
+ * If an interrupted transfer is resumed, the transferred size starts at the resumption point.
+ *
+ * @param data1 (int) Kilobytes transferred so far.
+ * @param data2 (int) Total size of the backup in kilobytes.
+ */
+#define DC_EVENT_BACKUP_TRANSFER_PROGRESS 2053
+
+
+/**
+ * Inform about the progress of fetching messages from the server,
+ * e.g. fetching existing messages after adding an account with a big mailbox.
+ *
+ * The folder name is not available in this event,
+ * use the JSON-RPC API to get it.
+ *
+ * @param data1 (int) Number of messages fetched so far.
+ * @param data2 (int) Number of messages to fetch.
+ */
+#define DC_EVENT_SYNC_PROGRESS            2055
+
+
+/**
+ * The first full sync after starting I/O is done.
+ *
+ * Emitted once per dc_start_io(),
+ * UIs may use it to show that the account is up to date.
+ * The number of messages marked as seen by other clients
+ * and the changes per folder are available via the JSON-RPC API.
+ *
+ * @param data1 (int) Number of new messages.
+ * @param data2 (int) Number of messages deleted from the server by other clients.
+ */
+#define DC_EVENT_SYNC_REPORT              2056
+
+
 /**
  * Progress information of a secure-join handshake from the view of the inviter
  * (Alice, the person who shows the QR code).
@@ -6363,6 +7141,19 @@ void dc_event_unref(dc_event_t* event);
 #define DC_EVENT_SECUREJOIN_JOINER_PROGRESS       2061
 
 
+/**
+ * A joiner scanned a one-time group invite QR code that requires approval
+ * and waits for the inviter to approve the request.
+ *
+ * The joiner is added to the group only after the request is approved.
+ * The fingerprint of the joiner's key can be shown using the contact's encryption info.
+ *
+ * @param data1 (int) The ID of the contact that wants to join.
+ * @param data2 (int) The ID of the group chat the contact wants to join.
+ */
+#define DC_EVENT_SECUREJOIN_APPROVAL_REQUEST      2062
+
+
 /**
  * The connectivity to the server changed.
  * This means that you should refresh the connectivity view
@@ -6418,6 +7209,33 @@ void dc_event_unref(dc_event_t* event);
 
 #define DC_EVENT_WEBXDC_INSTANCE_DELETED          2121
 
+/**
+ * A webxdc app requests to send a message on the user's behalf.
+ *
+ * The UI should show the text to the user and ask for approval.
+ * The message is only sent after the request is approved.
+ *
+ * @param data1 (int) msg_id of the webxdc instance.
+ * @param data2 (int) request_id _and_ (char*) text.
+ *      - dc_event_get_data2_int() returns the ID of the request.
+ *      - dc_event_get_data2_str() returns the text of the message to send.
+ *        string must be passed to dc_str_unref() afterwards.
+ */
+#define DC_EVENT_WEBXDC_SEND_REQUEST              2122
+
+/**
+ * A webxdc app asks to notify the user about an event in the app.
+ *
+ * The event is not emitted if the chat is muted
+ * or if the app sends notifications too often.
+ * The UI should show a system notification with the text.
+ *
+ * @param data1 (int) msg_id of the webxdc instance.
+ * @param data2 (char*) text of the notification.
+ *      Must be passed to dc_str_unref() afterwards.
+ */
+#define DC_EVENT_WEBXDC_NOTIFY                    2123
+
 /**
  * Data received over an ephemeral peer channel.
  *
@@ -6500,7 +7318,7 @@ void dc_event_unref(dc_event_t* event);
 
 
 #define DC_EVENT_DATA1_IS_STRING(e)  0    // not used anymore 
-#define DC_EVENT_DATA2_IS_STRING(e)  ((e)==DC_EVENT_CONFIGURE_PROGRESS || (e)==DC_EVENT_IMEX_FILE_WRITTEN || ((e)>=100 && (e)<=499))
+#define DC_EVENT_DATA2_IS_STRING(e)  ((e)==DC_EVENT_CONFIGURE_PROGRESS || (e)==DC_EVENT_CONFIGURE_FAILED || (e)==DC_EVENT_IMEX_FILE_WRITTEN || ((e)>=100 && (e)<=499))
 
 
 /*
@@ -6661,6 +7479,48 @@ void dc_event_unref(dc_event_t* event);
 #define DC_DOWNLOAD_IN_PROGRESS    1000
 
 
+/**
+ * The webxdc app exceeds the `webxdc_max_size` config option,
+ * see dc_msg_get_webxdc_unsupported().
+ */
+#define DC_WEBXDC_OVERSIZED        1
+
+/**
+ * The attachment is no valid webxdc app,
+ * see dc_msg_get_webxdc_unsupported().
+ */
+#define DC_WEBXDC_INVALID          2
+
+
+
+/**
+ * @}
+ */
+
+/**
+ * @defgroup DC_UNARCHIVE_POLICY DC_UNARCHIVE_POLICY
+ *
+ * These constants describe whether an archived chat is unarchived by new messages,
+ * see dc_set_chat_unarchive_policy() and dc_get_chat_unarchive_policy().
+ *
+ * @addtogroup DC_UNARCHIVE_POLICY
+ * @{
+ */
+
+/**
+ * Unarchive the chat on any new message unless the chat is muted.
+ */
+#define DC_UNARCHIVE_POLICY_ANY_MESSAGE 0
+
+/**
+ * Unarchive the chat only on incoming messages mentioning self.
+ */
+#define DC_UNARCHIVE_POLICY_MENTION     1
+
+/**
+ * Keep the chat archived.
+ */
+#define DC_UNARCHIVE_POLICY_NEVER       2
 
 /**
  * @}
@@ -7529,9 +8389,65 @@ void dc_event_unref(dc_event_t* event);
 /// Used as info message.
 #define DC_STR_SECUREJOIN_WAIT_TIMEOUT 191
 
+/// "⚠️ You have not made a backup for a long time. …"
+///
+/// Used as device message.
+#define DC_STR_BACKUP_REMINDER_MSG_BODY 192
+
+/// "⚠️ Your encryption key was created on %1$s and may use outdated algorithms."
+///
+/// `%1$s` will be replaced by the date the key was created.
+///
+/// Used as device message.
+#define DC_STR_KEY_AGE_WARNING_MSG_BODY 193
+
+/// "⚠️ The database of this profile has grown to %1$s. …"
+///
+/// `%1$s` will be replaced by the size of the database, e.g. "2100 MB".
+///
+/// Used as device message.
+#define DC_STR_DB_SIZE_WARNING_MSG_BODY 194
+
+/// "Messages can be end-to-end encrypted again."
+///
+/// Used as info message in chats requiring encryption.
+#define DC_STR_ENCRYPTION_AVAILABLE 195
+
+/// "⚠️ Messages cannot be end-to-end encrypted. …"
+///
+/// Used as info message in chats requiring encryption.
+#define DC_STR_ENCRYPTION_UNAVAILABLE 196
+
+/// "Subscription request sent to the mailing list."
+///
+/// Used as info message after subscribing to a mailing list.
+#define DC_STR_MAILINGLIST_SUBSCRIBE_SENT 197
+
+/// "You unsubscribed from this mailing list."
+///
+/// Used as info message after unsubscribing from a mailing list.
+#define DC_STR_MAILINGLIST_UNSUBSCRIBED 198
+
+/// "Unsubscribing failed: %1$s"
+///
+/// Used as info message if unsubscribing from a mailing list failed.
+/// - %1$s will be replaced by the error description.
+#define DC_STR_MAILINGLIST_UNSUBSCRIBE_FAILED 199
+
 /// "Contact". Deprecated, currently unused.
 #define DC_STR_CONTACT 200
 
+/// "⚠️ This group has more than %1$s members. Changing the members of large groups causes a lot of traffic for all members."
+///
+/// Used as info message if a group grows above `group_member_soft_limit`.
+/// - %1$s will be replaced by the limit.
+#define DC_STR_GROUP_MEMBER_SOFT_LIMIT 201
+
+/// "⚠️ This broadcast list is now a group. With the next message, all members will see each other's addresses."
+///
+/// Used as info message after a broadcast list was converted into a group.
+#define DC_STR_BROADCAST_CONVERTED_TO_GROUP 209
+
 /**
  * @}
  */
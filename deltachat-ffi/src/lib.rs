@@ -22,7 +22,9 @@
 use std::time::{Duration, SystemTime};
 
 use anyhow::Context as _;
-use deltachat::chat::{ChatId, ChatVisibility, MessageListOptions, MuteDuration, ProtectionStatus};
+use deltachat::chat::{
+    ChatId, ChatVisibility, MessageListOptions, MuteDuration, ProtectionStatus, UnarchivePolicy,
+};
 use deltachat::constants::DC_MSG_ID_LAST_SPECIAL;
 use deltachat::contact::{Contact, ContactId, Origin};
 use deltachat::context::{Context, ContextBuilder};
@@ -35,6 +37,7 @@
 use deltachat::webxdc::StatusUpdateSerial;
 use deltachat::*;
 use deltachat::{accounts::Accounts, log::LogExt};
+use deltachat_jsonrpc::api::types::chat_list::ChatListSummary;
 use deltachat_jsonrpc::api::CommandApi;
 use deltachat_jsonrpc::yerpc::{OutReceiver, RpcClient, RpcSession};
 use num_traits::{FromPrimitive, ToPrimitive};
@@ -62,6 +65,10 @@
 
 const DC_GCM_ADDDAYMARKER: u32 = 0x01;
 const DC_GCM_INFO_ONLY: u32 = 0x02;
+const DC_GCM_INCLUDE_HIDDEN_LOCAL: u32 = 0x04;
+
+const DC_EVENT_FILTER_COALESCE: libc::c_int = 0x01;
+const DC_EVENT_FILTER_RATE_LIMIT: libc::c_int = 0x02;
 
 // dc_context_t
 
@@ -405,6 +412,34 @@ fn render_info(
     })
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_generate_diagnostics(
+    context: *const dc_context_t,
+    json: libc::c_int,
+) -> *mut libc::c_char {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_generate_diagnostics()");
+        return "".strdup();
+    }
+    let ctx = &*context;
+    block_on(async move {
+        let diagnostics = match ctx.generate_diagnostics().await {
+            Ok(diagnostics) => diagnostics,
+            Err(err) => {
+                error!(ctx, "Failed to generate diagnostics: {err:#}");
+                return "".strdup();
+            }
+        };
+        if json != 0 {
+            serde_json::to_string_pretty(&diagnostics)
+                .unwrap_or_log_default(ctx, "Failed to serialize diagnostics")
+                .strdup()
+        } else {
+            diagnostics.to_string().strdup()
+        }
+    })
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_get_push_state(context: *const dc_context_t) -> libc::c_int {
     if context.is_null() {
@@ -461,6 +496,21 @@ fn spawn_configure(ctx: Context) {
     spawn_configure(ctx.clone());
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_last_configure_trace(
+    context: *mut dc_context_t,
+) -> *mut libc::c_char {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_get_last_configure_trace()");
+        return "".strdup();
+    }
+
+    let ctx = &*context;
+    serde_json::to_string(&ctx.get_last_configure_trace())
+        .unwrap_or_log_default(ctx, "Failed to serialize configure trace")
+        .strdup()
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_is_configured(context: *mut dc_context_t) -> libc::c_int {
     if context.is_null() {
@@ -478,6 +528,20 @@ fn spawn_configure(ctx: Context) {
     })
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_is_dnd_active(context: *mut dc_context_t) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_is_dnd_active()");
+        return 0;
+    }
+    let ctx = &*context;
+
+    block_on(ctx.is_dnd_active())
+        .context("failed to get do-not-disturb state")
+        .log_err(ctx)
+        .unwrap_or_default() as libc::c_int
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_start_io(context: *mut dc_context_t) {
     if context.is_null() {
@@ -518,7 +582,12 @@ fn spawn_configure(ctx: Context) {
     }
 
     let event = &*event;
-    match event.typ {
+    event_type_id(&event.typ)
+}
+
+/// Returns the `DC_EVENT_*` constant of the event type.
+fn event_type_id(event_type: &EventType) -> libc::c_int {
+    match event_type {
         EventType::Info(_) => 100,
         EventType::SmtpConnected(_) => 101,
         EventType::ImapConnected(_) => 102,
@@ -535,27 +604,39 @@ fn spawn_configure(ctx: Context) {
         EventType::ReactionsChanged { .. } => 2001,
         EventType::IncomingReaction { .. } => 2002,
         EventType::IncomingWebxdcNotify { .. } => 2003,
+        EventType::IncomingPublicKey { .. } => 2004,
         EventType::IncomingMsg { .. } => 2005,
         EventType::IncomingMsgBunch { .. } => 2006,
+        EventType::BotCommand { .. } => 2007,
         EventType::MsgsNoticed { .. } => 2008,
         EventType::MsgDelivered { .. } => 2010,
         EventType::MsgFailed { .. } => 2012,
         EventType::MsgRead { .. } => 2015,
         EventType::MsgDeleted { .. } => 2016,
+        EventType::TranslationRequest { .. } => 2017,
+        EventType::OcrRequest { .. } => 2018,
         EventType::ChatModified(_) => 2020,
         EventType::ChatEphemeralTimerModified { .. } => 2021,
+        EventType::VerifiedGroupMemberKeyChanged { .. } => 2022,
         EventType::ContactsChanged(_) => 2030,
         EventType::LocationChanged(_) => 2035,
         EventType::ConfigureProgress { .. } => 2041,
+        EventType::ConfigureFailed { .. } => 2042,
         EventType::ImexProgress(_) => 2051,
         EventType::ImexFileWritten(_) => 2052,
+        EventType::BackupTransferProgress { .. } => 2053,
+        EventType::SyncProgress { .. } => 2055,
+        EventType::SyncReport { .. } => 2056,
         EventType::SecurejoinInviterProgress { .. } => 2060,
         EventType::SecurejoinJoinerProgress { .. } => 2061,
+        EventType::SecurejoinApprovalRequest { .. } => 2062,
         EventType::ConnectivityChanged => 2100,
         EventType::SelfavatarChanged => 2110,
         EventType::ConfigSynced { .. } => 2111,
         EventType::WebxdcStatusUpdate { .. } => 2120,
         EventType::WebxdcInstanceDeleted { .. } => 2121,
+        EventType::WebxdcSendRequest { .. } => 2122,
+        EventType::WebxdcNotify { .. } => 2123,
         EventType::WebxdcRealtimeData { .. } => 2150,
         EventType::WebxdcRealtimeAdvertisementReceived { .. } => 2151,
         EventType::AccountsBackgroundFetchDone => 2200,
@@ -595,6 +676,7 @@ fn spawn_configure(ctx: Context) {
         | EventType::ConfigSynced { .. }
         | EventType::IncomingMsgBunch { .. }
         | EventType::ErrorSelfNotInGroup(_)
+        | EventType::ConfigureFailed { .. }
         | EventType::AccountsBackgroundFetchDone
         | EventType::ChatlistChanged
         | EventType::AccountsChanged
@@ -604,13 +686,18 @@ fn spawn_configure(ctx: Context) {
         EventType::MsgsChanged { chat_id, .. }
         | EventType::ReactionsChanged { chat_id, .. }
         | EventType::IncomingMsg { chat_id, .. }
+        | EventType::BotCommand { chat_id, .. }
+        | EventType::IncomingPublicKey { chat_id, .. }
         | EventType::MsgsNoticed(chat_id)
         | EventType::MsgDelivered { chat_id, .. }
         | EventType::MsgFailed { chat_id, .. }
         | EventType::MsgRead { chat_id, .. }
         | EventType::MsgDeleted { chat_id, .. }
         | EventType::ChatModified(chat_id)
-        | EventType::ChatEphemeralTimerModified { chat_id, .. } => chat_id.to_u32() as libc::c_int,
+        | EventType::ChatEphemeralTimerModified { chat_id, .. }
+        | EventType::VerifiedGroupMemberKeyChanged { chat_id, .. } => {
+            chat_id.to_u32() as libc::c_int
+        }
         EventType::ContactsChanged(id) | EventType::LocationChanged(id) => {
             let id = id.unwrap_or_default();
             id.to_u32() as libc::c_int
@@ -619,14 +706,24 @@ fn spawn_configure(ctx: Context) {
             *progress as libc::c_int
         }
         EventType::ImexFileWritten(_) => 0,
+        EventType::BackupTransferProgress { transferred, .. } => {
+            (*transferred / 1024).try_into().unwrap_or(libc::c_int::MAX)
+        }
+        EventType::SyncProgress { fetched, .. } => *fetched as libc::c_int,
+        EventType::SyncReport { new_msgs, .. } => *new_msgs as libc::c_int,
         EventType::SecurejoinInviterProgress { contact_id, .. }
-        | EventType::SecurejoinJoinerProgress { contact_id, .. } => {
+        | EventType::SecurejoinJoinerProgress { contact_id, .. }
+        | EventType::SecurejoinApprovalRequest { contact_id, .. } => {
             contact_id.to_u32() as libc::c_int
         }
         EventType::WebxdcRealtimeData { msg_id, .. }
         | EventType::WebxdcStatusUpdate { msg_id, .. }
         | EventType::WebxdcRealtimeAdvertisementReceived { msg_id }
-        | EventType::WebxdcInstanceDeleted { msg_id, .. } => msg_id.to_u32() as libc::c_int,
+        | EventType::WebxdcInstanceDeleted { msg_id, .. }
+        | EventType::WebxdcSendRequest { msg_id, .. }
+        | EventType::WebxdcNotify { msg_id, .. }
+        | EventType::TranslationRequest { msg_id, .. }
+        | EventType::OcrRequest { msg_id, .. } => msg_id.to_u32() as libc::c_int,
         EventType::ChatlistItemChanged { chat_id } => {
             chat_id.unwrap_or_default().to_u32() as libc::c_int
         }
@@ -662,6 +759,7 @@ fn spawn_configure(ctx: Context) {
         | EventType::ContactsChanged(_)
         | EventType::LocationChanged(_)
         | EventType::ConfigureProgress { .. }
+        | EventType::ConfigureFailed { .. }
         | EventType::ImexProgress(_)
         | EventType::ImexFileWritten(_)
         | EventType::MsgsNoticed(_)
@@ -677,24 +775,39 @@ fn spawn_configure(ctx: Context) {
         | EventType::ConfigSynced { .. }
         | EventType::ChatModified(_)
         | EventType::WebxdcRealtimeAdvertisementReceived { .. }
+        | EventType::TranslationRequest { .. }
+        | EventType::OcrRequest { .. }
+        | EventType::WebxdcNotify { .. }
         | EventType::EventChannelOverflow { .. } => 0,
         EventType::MsgsChanged { msg_id, .. }
         | EventType::ReactionsChanged { msg_id, .. }
         | EventType::IncomingReaction { msg_id, .. }
         | EventType::IncomingWebxdcNotify { msg_id, .. }
         | EventType::IncomingMsg { msg_id, .. }
+        | EventType::BotCommand { msg_id, .. }
+        | EventType::IncomingPublicKey { msg_id, .. }
         | EventType::MsgDelivered { msg_id, .. }
         | EventType::MsgFailed { msg_id, .. }
         | EventType::MsgRead { msg_id, .. }
         | EventType::MsgDeleted { msg_id, .. } => msg_id.to_u32() as libc::c_int,
         EventType::SecurejoinInviterProgress { progress, .. }
         | EventType::SecurejoinJoinerProgress { progress, .. } => *progress as libc::c_int,
+        EventType::SyncProgress { total, .. } => *total as libc::c_int,
+        EventType::BackupTransferProgress { total, .. } => {
+            (*total / 1024).try_into().unwrap_or(libc::c_int::MAX)
+        }
+        EventType::SyncReport { expunged, .. } => *expunged as libc::c_int,
+        EventType::SecurejoinApprovalRequest { chat_id, .. } => chat_id.to_u32() as libc::c_int,
         EventType::ChatEphemeralTimerModified { timer, .. } => timer.to_u32() as libc::c_int,
+        EventType::VerifiedGroupMemberKeyChanged { contact_id, .. } => {
+            contact_id.to_u32() as libc::c_int
+        }
         EventType::WebxdcStatusUpdate {
             status_update_serial,
             ..
         } => status_update_serial.to_u32() as libc::c_int,
         EventType::WebxdcRealtimeData { data, .. } => data.len() as libc::c_int,
+        EventType::WebxdcSendRequest { request_id, .. } => *request_id as libc::c_int,
         #[allow(unreachable_patterns)]
         #[cfg(test)]
         _ => unreachable!("This is just to silence a rust_analyzer false-positive"),
@@ -749,6 +862,7 @@ fn spawn_configure(ctx: Context) {
         EventType::MsgsChanged { .. }
         | EventType::ReactionsChanged { .. }
         | EventType::IncomingMsg { .. }
+        | EventType::BotCommand { .. }
         | EventType::ImapInboxIdle
         | EventType::MsgsNoticed(_)
         | EventType::MsgDelivered { .. }
@@ -759,14 +873,19 @@ fn spawn_configure(ctx: Context) {
         | EventType::ContactsChanged(_)
         | EventType::LocationChanged(_)
         | EventType::ImexProgress(_)
+        | EventType::BackupTransferProgress { .. }
+        | EventType::SyncProgress { .. }
+        | EventType::SyncReport { .. }
         | EventType::SecurejoinInviterProgress { .. }
         | EventType::SecurejoinJoinerProgress { .. }
+        | EventType::SecurejoinApprovalRequest { .. }
         | EventType::ConnectivityChanged
         | EventType::SelfavatarChanged
         | EventType::WebxdcStatusUpdate { .. }
         | EventType::WebxdcInstanceDeleted { .. }
         | EventType::AccountsBackgroundFetchDone
         | EventType::ChatEphemeralTimerModified { .. }
+        | EventType::VerifiedGroupMemberKeyChanged { .. }
         | EventType::IncomingMsgBunch { .. }
         | EventType::ChatlistItemChanged { .. }
         | EventType::ChatlistChanged
@@ -781,6 +900,14 @@ fn spawn_configure(ctx: Context) {
                 ptr::null_mut()
             }
         }
+        EventType::ConfigureFailed { error, trace } => {
+            let data2 = serde_json::json!({ "error": error, "trace": trace });
+            data2
+                .to_string()
+                .to_c_string()
+                .unwrap_or_default()
+                .into_raw()
+        }
         EventType::ImexFileWritten(file) => {
             let data2 = file.to_c_string().unwrap_or_default();
             data2.into_raw()
@@ -799,8 +926,15 @@ fn spawn_configure(ctx: Context) {
             .to_c_string()
             .unwrap_or_default()
             .into_raw(),
-        EventType::IncomingWebxdcNotify { text, .. } => {
-            text.to_c_string().unwrap_or_default().into_raw()
+        EventType::IncomingWebxdcNotify { text, .. }
+        | EventType::WebxdcSendRequest { text, .. }
+        | EventType::WebxdcNotify { text, .. } => text.to_c_string().unwrap_or_default().into_raw(),
+        EventType::TranslationRequest { target_lang, .. } => {
+            target_lang.to_c_string().unwrap_or_default().into_raw()
+        }
+        EventType::OcrRequest { path, .. } => path.to_c_string().unwrap_or_default().into_raw(),
+        EventType::IncomingPublicKey { fingerprint, .. } => {
+            fingerprint.to_c_string().unwrap_or_default().into_raw()
         }
         #[allow(unreachable_patterns)]
         #[cfg(test)]
@@ -1247,6 +1381,7 @@ fn spawn_configure(ctx: Context) {
 
     let info_only = (flags & DC_GCM_INFO_ONLY) != 0;
     let add_daymarker = (flags & DC_GCM_ADDDAYMARKER) != 0;
+    let include_hidden_local = (flags & DC_GCM_INCLUDE_HIDDEN_LOCAL) != 0;
     block_on(async move {
         Box::into_raw(Box::new(
             chat::get_chat_msgs_ex(
@@ -1255,6 +1390,7 @@ fn spawn_configure(ctx: Context) {
                 MessageListOptions {
                     info_only,
                     add_daymarker,
+                    include_hidden_local,
                 },
             )
             .await
@@ -1427,6 +1563,101 @@ fn spawn_configure(ctx: Context) {
     })
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_marknoticed_all_chats(context: *mut dc_context_t) {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_marknoticed_all_chats()");
+        return;
+    }
+    let ctx = &*context;
+
+    block_on(async move {
+        chat::marknoticed_all_chats(ctx)
+            .await
+            .context("Failed marknoticed all chats")
+            .log_err(ctx)
+            .unwrap_or(())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_set_chat_marked_unread(
+    context: *mut dc_context_t,
+    chat_id: u32,
+    unread: libc::c_int,
+) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_set_chat_marked_unread()");
+        return 0;
+    }
+    let ctx = &*context;
+
+    block_on(async move {
+        ChatId::new(chat_id)
+            .set_marked_unread(ctx, unread != 0)
+            .await
+            .context("Failed to set marked unread")
+            .log_err(ctx)
+            .is_ok() as libc::c_int
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_is_chat_marked_unread(
+    context: *mut dc_context_t,
+    chat_id: u32,
+) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_is_chat_marked_unread()");
+        return 0;
+    }
+    let ctx = &*context;
+
+    block_on(ChatId::new(chat_id).is_marked_unread(ctx))
+        .context("Failed to get marked unread")
+        .log_err(ctx)
+        .unwrap_or_default() as libc::c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_set_last_read_msg(
+    context: *mut dc_context_t,
+    chat_id: u32,
+    msg_id: u32,
+) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_set_last_read_msg()");
+        return 0;
+    }
+    let ctx = &*context;
+
+    block_on(async move {
+        ChatId::new(chat_id)
+            .set_last_read_msg(ctx, MsgId::new(msg_id))
+            .await
+            .context("Failed to set last read message")
+            .log_err(ctx)
+            .is_ok() as libc::c_int
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_last_read_msg(context: *mut dc_context_t, chat_id: u32) -> u32 {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_get_last_read_msg()");
+        return 0;
+    }
+    let ctx = &*context;
+
+    block_on(ChatId::new(chat_id).get_last_read_msg(ctx))
+        .context("Failed to get last read message")
+        .log_err(ctx)
+        .ok()
+        .flatten()
+        .map(|msg_id| msg_id.to_u32())
+        .unwrap_or_default()
+}
+
 fn from_prim<S, T>(s: S) -> Option<T>
 where
     T: FromPrimitive,
@@ -1672,6 +1903,22 @@ fn from_prim<S, T>(s: S) -> Option<T>
         .unwrap_or(0)
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_convert_broadcast_to_group(
+    context: *mut dc_context_t,
+    chat_id: u32,
+) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_convert_broadcast_to_group()");
+        return 0;
+    }
+    let ctx = &*context;
+    block_on(chat::convert_broadcast_to_group(ctx, ChatId::new(chat_id)))
+        .context("Failed to convert broadcast list")
+        .log_err(ctx)
+        .is_ok() as libc::c_int
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_is_contact_in_chat(
     context: *mut dc_context_t,
@@ -1716,6 +1963,96 @@ fn from_prim<S, T>(s: S) -> Option<T>
     .is_ok() as libc::c_int
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_set_group_moderated(
+    context: *mut dc_context_t,
+    chat_id: u32,
+    moderated: libc::c_int,
+) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_set_group_moderated()");
+        return 0;
+    }
+    let ctx = &*context;
+
+    block_on(chat::set_group_moderated(
+        ctx,
+        ChatId::new(chat_id),
+        moderated != 0,
+    ))
+    .context("Failed to set group moderation")
+    .log_err(ctx)
+    .is_ok() as libc::c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_set_group_admin(
+    context: *mut dc_context_t,
+    chat_id: u32,
+    contact_id: u32,
+    admin: libc::c_int,
+) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_set_group_admin()");
+        return 0;
+    }
+    let ctx = &*context;
+
+    block_on(chat::set_group_admin(
+        ctx,
+        ChatId::new(chat_id),
+        ContactId::new(contact_id),
+        admin != 0,
+    ))
+    .context("Failed to set group admin")
+    .log_err(ctx)
+    .is_ok() as libc::c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_is_group_admin(
+    context: *mut dc_context_t,
+    chat_id: u32,
+    contact_id: u32,
+) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_is_group_admin()");
+        return 0;
+    }
+    let ctx = &*context;
+
+    block_on(chat::is_group_admin(
+        ctx,
+        ChatId::new(chat_id),
+        ContactId::new(contact_id),
+    ))
+    .context("is_group_admin failed")
+    .log_err(ctx)
+    .unwrap_or_default() as libc::c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_repair_verified_group_member(
+    context: *mut dc_context_t,
+    chat_id: u32,
+    contact_id: u32,
+) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_repair_verified_group_member()");
+        return 0;
+    }
+    let ctx = &*context;
+
+    block_on(chat::repair_verified_group_member(
+        ctx,
+        ChatId::new(chat_id),
+        ContactId::new(contact_id),
+    ))
+    .context("Failed to repair group member")
+    .log_err(ctx)
+    .is_ok() as libc::c_int
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_remove_contact_from_chat(
     context: *mut dc_context_t,
@@ -1881,6 +2218,89 @@ fn from_prim<S, T>(s: S) -> Option<T>
     })
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_chat_inactivity_timer(
+    context: *mut dc_context_t,
+    chat_id: u32,
+) -> u32 {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_get_chat_inactivity_timer()");
+        return 0;
+    }
+    let ctx = &*context;
+
+    block_on(ChatId::new(chat_id).get_inactivity_timer(ctx))
+        .context("Failed to get inactivity timer")
+        .log_err(ctx)
+        .unwrap_or_default()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_set_chat_inactivity_timer(
+    context: *mut dc_context_t,
+    chat_id: u32,
+    days: u32,
+) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_set_chat_inactivity_timer()");
+        return 0;
+    }
+    let ctx = &*context;
+
+    block_on(async move {
+        ChatId::new(chat_id)
+            .set_inactivity_timer(ctx, days)
+            .await
+            .context("Failed to set inactivity timer")
+            .log_err(ctx)
+            .is_ok() as libc::c_int
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_chat_unarchive_policy(
+    context: *mut dc_context_t,
+    chat_id: u32,
+) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_get_chat_unarchive_policy()");
+        return 0;
+    }
+    let ctx = &*context;
+
+    block_on(ChatId::new(chat_id).get_unarchive_policy(ctx))
+        .context("Failed to get unarchive policy")
+        .log_err(ctx)
+        .map(|policy| policy as libc::c_int)
+        .unwrap_or_default()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_set_chat_unarchive_policy(
+    context: *mut dc_context_t,
+    chat_id: u32,
+    policy: libc::c_int,
+) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_set_chat_unarchive_policy()");
+        return 0;
+    }
+    let ctx = &*context;
+    let Some(policy) = from_prim::<_, UnarchivePolicy>(policy) else {
+        eprintln!("ignoring careless call to dc_set_chat_unarchive_policy(): bad policy");
+        return 0;
+    };
+
+    block_on(async move {
+        ChatId::new(chat_id)
+            .set_unarchive_policy(ctx, policy)
+            .await
+            .context("Failed to set unarchive policy")
+            .log_err(ctx)
+            .is_ok() as libc::c_int
+    })
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_get_msg_info(
     context: *mut dc_context_t,
@@ -1913,6 +2333,81 @@ fn from_prim<S, T>(s: S) -> Option<T>
         .strdup()
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_set_msg_translation(
+    context: *mut dc_context_t,
+    msg_id: u32,
+    target_lang: *const libc::c_char,
+    text: *const libc::c_char,
+) -> libc::c_int {
+    if context.is_null() || target_lang.is_null() || text.is_null() {
+        eprintln!("ignoring careless call to dc_set_msg_translation()");
+        return 0;
+    }
+    let ctx = &*context;
+
+    block_on(ctx.set_msg_translation(
+        MsgId::new(msg_id),
+        &to_string_lossy(target_lang),
+        &to_string_lossy(text),
+    ))
+    .context("Failed to set message translation")
+    .log_err(ctx)
+    .is_ok() as libc::c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_request_msg_ocr(
+    context: *mut dc_context_t,
+    msg_id: u32,
+) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_request_msg_ocr()");
+        return 0;
+    }
+    let ctx = &*context;
+
+    block_on(ctx.request_msg_ocr(MsgId::new(msg_id)))
+        .context("Failed to request text recognition")
+        .log_err(ctx)
+        .is_ok() as libc::c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_set_msg_ocr_text(
+    context: *mut dc_context_t,
+    msg_id: u32,
+    text: *const libc::c_char,
+) -> libc::c_int {
+    if context.is_null() || text.is_null() {
+        eprintln!("ignoring careless call to dc_set_msg_ocr_text()");
+        return 0;
+    }
+    let ctx = &*context;
+
+    block_on(ctx.set_msg_ocr_text(MsgId::new(msg_id), &to_string_lossy(text)))
+        .context("Failed to set recognized text")
+        .log_err(ctx)
+        .is_ok() as libc::c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_import_key_from_msg(
+    context: *mut dc_context_t,
+    msg_id: u32,
+) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_import_key_from_msg()");
+        return 0;
+    }
+    let ctx = &*context;
+
+    block_on(key_import::import_key_from_msg(ctx, MsgId::new(msg_id)))
+        .context("Failed to import key")
+        .log_err(ctx)
+        .is_ok() as libc::c_int
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_get_mime_headers(
     context: *mut dc_context_t,
@@ -1935,6 +2430,26 @@ fn from_prim<S, T>(s: S) -> Option<T>
     })
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_export_eml(
+    context: *mut dc_context_t,
+    msg_ids: *const u32,
+    msg_cnt: libc::c_int,
+    dir: *const libc::c_char,
+) -> libc::c_int {
+    if context.is_null() || msg_ids.is_null() || msg_cnt <= 0 || dir.is_null() {
+        eprintln!("ignoring careless call to dc_export_eml()");
+        return 0;
+    }
+    let ctx = &*context;
+    let msg_ids = convert_and_prune_message_ids(msg_ids, msg_cnt);
+
+    block_on(message::export_eml(ctx, &msg_ids, as_path(dir)))
+        .context("failed dc_export_eml() call")
+        .log_err(ctx)
+        .is_ok() as libc::c_int
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_delete_msgs(
     context: *mut dc_context_t,
@@ -2020,6 +2535,28 @@ fn from_prim<S, T>(s: S) -> Option<T>
     }
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_set_msg_hidden_local(
+    context: *mut dc_context_t,
+    msg_id: u32,
+    hidden: libc::c_int,
+) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_set_msg_hidden_local()");
+        return 0;
+    }
+    let ctx = &*context;
+
+    block_on(message::set_hidden_local(
+        ctx,
+        MsgId::new(msg_id),
+        hidden != 0,
+    ))
+    .context("Failed to set message hidden")
+    .log_err(ctx)
+    .is_ok() as libc::c_int
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_markseen_msgs(
     context: *mut dc_context_t,
@@ -2092,6 +2629,19 @@ fn from_prim<S, T>(s: S) -> Option<T>
     contact::may_be_valid_addr(&to_string_lossy(addr)) as libc::c_int
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_emoji_only_count(text: *const libc::c_char) -> libc::c_int {
+    if text.is_null() {
+        eprintln!("ignoring careless call to dc_emoji_only_count()");
+        return 0;
+    }
+
+    emoji::emoji_only_count(&to_string_lossy(text))
+        .unwrap_or_default()
+        .try_into()
+        .unwrap_or(libc::c_int::MAX)
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_lookup_contact_id_by_addr(
     context: *mut dc_context_t,
@@ -2462,6 +3012,21 @@ fn spawn_imex(ctx: Context, what: imex::ImexMode, param1: String, passphrase: Op
         .strdup()
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_securejoin_invite_words(
+    context: *mut dc_context_t,
+) -> *mut libc::c_char {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_get_securejoin_invite_words()");
+        return "".strdup();
+    }
+    let ctx = &*context;
+
+    block_on(securejoin::get_securejoin_invite_words(ctx))
+        .unwrap_or_else(|_| "".to_string())
+        .strdup()
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_get_securejoin_qr_svg(
     context: *mut dc_context_t,
@@ -2914,6 +3479,48 @@ pub struct ChatlistWrapper {
     })
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_chatlist_get_summary_json(
+    chatlist: *mut dc_chatlist_t,
+    index: libc::size_t,
+    chat: *mut dc_chat_t,
+) -> *mut libc::c_char {
+    if chatlist.is_null() {
+        eprintln!("ignoring careless call to dc_chatlist_get_summary_json()");
+        return "".strdup();
+    }
+    let maybe_chat = if chat.is_null() {
+        None
+    } else {
+        let ffi_chat = &*chat;
+        Some(&ffi_chat.chat)
+    };
+    let ffi_list = &*chatlist;
+    let ctx = &*ffi_list.context;
+
+    block_on(async move {
+        let summary = match ffi_list.list.get_summary(ctx, index, maybe_chat).await {
+            Ok(summary) => summary,
+            Err(err) => {
+                error!(ctx, "dc_chatlist_get_summary_json() failed: {err:#}");
+                return "".strdup();
+            }
+        };
+        match ChatListSummary::from_summary(ctx, summary).await {
+            Ok(summary) => serde_json::to_string(&summary)
+                .unwrap_or_log_default(
+                    ctx,
+                    "dc_chatlist_get_summary_json() failed to serialise to json",
+                )
+                .strdup(),
+            Err(err) => {
+                error!(ctx, "dc_chatlist_get_summary_json() failed: {err:#}");
+                "".strdup()
+            }
+        }
+    })
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_chatlist_get_summary2(
     context: *mut dc_context_t,
@@ -3135,6 +3742,16 @@ pub struct ChatWrapper {
     ffi_chat.chat.is_protected() as libc::c_int
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_chat_is_moderated(chat: *mut dc_chat_t) -> libc::c_int {
+    if chat.is_null() {
+        eprintln!("ignoring careless call to dc_chat_is_moderated()");
+        return 0;
+    }
+    let ffi_chat = &*chat;
+    ffi_chat.chat.is_moderated() as libc::c_int
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_chat_is_protection_broken(chat: *mut dc_chat_t) -> libc::c_int {
     if chat.is_null() {
@@ -3379,6 +3996,32 @@ pub struct MessageWrapper {
     ffi_msg.message.get_subject().strdup()
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_msg_get_ocr_text(msg: *mut dc_msg_t) -> *mut libc::c_char {
+    if msg.is_null() {
+        eprintln!("ignoring careless call to dc_msg_get_ocr_text()");
+        return ptr::null_mut();
+    }
+    let ffi_msg = &*msg;
+    ffi_msg
+        .message
+        .get_ocr_text()
+        .map_or_else(ptr::null_mut, |s| s.strdup())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_msg_get_webxdc_unsupported(msg: *mut dc_msg_t) -> libc::c_int {
+    if msg.is_null() {
+        eprintln!("ignoring careless call to dc_msg_get_webxdc_unsupported()");
+        return 0;
+    }
+    let ffi_msg = &*msg;
+    ffi_msg
+        .message
+        .get_webxdc_unsupported()
+        .map_or(0, |unsupported| unsupported as libc::c_int)
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_file(msg: *mut dc_msg_t) -> *mut libc::c_char {
     if msg.is_null() {
@@ -3693,6 +4336,16 @@ pub struct MessageWrapper {
     ffi_msg.message.is_info().into()
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_msg_is_hidden_local(msg: *mut dc_msg_t) -> libc::c_int {
+    if msg.is_null() {
+        eprintln!("ignoring careless call to dc_msg_is_hidden_local()");
+        return 0;
+    }
+    let ffi_msg = &*msg;
+    ffi_msg.message.is_hidden_local().into()
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_info_type(msg: *mut dc_msg_t) -> libc::c_int {
     if msg.is_null() {
@@ -4003,6 +4656,25 @@ pub struct MessageWrapper {
     }
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_msg_get_quote_integrity(msg: *const dc_msg_t) -> libc::c_int {
+    if msg.is_null() {
+        eprintln!("ignoring careless call to dc_msg_get_quote_integrity()");
+        return 0;
+    }
+    let ffi_msg: &MessageWrapper = &*msg;
+    let context = &*ffi_msg.context;
+    block_on(async move {
+        ffi_msg
+            .message
+            .quote_integrity(context)
+            .await
+            .context("failed to check quote integrity")
+            .log_err(context)
+            .unwrap_or_default() as libc::c_int
+    })
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_parent(msg: *const dc_msg_t) -> *mut dc_msg_t {
     if msg.is_null() {
@@ -4124,6 +4796,27 @@ pub struct ContactWrapper {
     ffi_contact.contact.get_addr().strdup()
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_contact_get_display_addr(
+    contact: *mut dc_contact_t,
+) -> *mut libc::c_char {
+    if contact.is_null() {
+        eprintln!("ignoring careless call to dc_contact_get_display_addr()");
+        return "".strdup();
+    }
+    let ffi_contact = &*contact;
+    let ctx = &*ffi_contact.context;
+
+    block_on(async move {
+        ffi_contact
+            .contact
+            .get_display_addr(ctx)
+            .await
+            .unwrap_or_log_default(ctx, "failed to get display address")
+            .strdup()
+    })
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_contact_get_name(contact: *mut dc_contact_t) -> *mut libc::c_char {
     if contact.is_null() {
@@ -5021,6 +5714,50 @@ fn new(accounts: Accounts) -> Self {
     Box::into_raw(Box::new(emitter))
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_accounts_get_filtered_event_emitter(
+    accounts: *mut dc_accounts_t,
+    event_ids: *const libc::c_int,
+    event_ids_cnt: libc::c_int,
+    account_ids: *const u32,
+    account_ids_cnt: libc::c_int,
+    flags: libc::c_int,
+) -> *mut dc_event_emitter_t {
+    if accounts.is_null() {
+        eprintln!("ignoring careless call to dc_accounts_get_filtered_event_emitter()");
+        return ptr::null_mut();
+    }
+
+    let mut filter = EventFilter::new();
+    if !event_ids.is_null() && event_ids_cnt > 0 {
+        let event_ids = std::slice::from_raw_parts(event_ids, event_ids_cnt as usize).to_vec();
+        filter = filter.with_event_types(move |typ| event_ids.contains(&event_type_id(typ)));
+    }
+    if !account_ids.is_null() && account_ids_cnt > 0 {
+        let account_ids = std::slice::from_raw_parts(account_ids, account_ids_cnt as usize);
+        filter = filter.with_accounts(account_ids.iter().copied());
+    }
+    if flags & DC_EVENT_FILTER_COALESCE != 0 {
+        filter = filter.with_coalescing();
+    }
+    if flags & DC_EVENT_FILTER_RATE_LIMIT != 0 {
+        let window = std::time::Duration::from_millis(300);
+        for name in [
+            "MsgsChanged",
+            "ReactionsChanged",
+            "ChatModified",
+            "ChatlistItemChanged",
+        ] {
+            filter = filter.with_coalescing_window(name, window);
+        }
+    }
+
+    let accounts = &*accounts;
+    let emitter = block_on(accounts.read()).get_filtered_event_emitter(filter);
+
+    Box::into_raw(Box::new(emitter))
+}
+
 pub struct dc_jsonrpc_instance_t {
     receiver: OutReceiver,
     handle: RpcSession<CommandApi>,
@@ -7,21 +7,26 @@
 
 use anyhow::{anyhow, bail, ensure, Context, Result};
 pub use deltachat::accounts::Accounts;
+use deltachat::accounts::JobConditions;
 use deltachat::chat::{
-    self, add_contact_to_chat, forward_msgs, get_chat_media, get_chat_msgs, get_chat_msgs_ex,
-    marknoticed_chat, remove_contact_from_chat, Chat, ChatId, ChatItem, MessageListOptions,
-    ProtectionStatus,
+    self, add_contact_to_chat, forward_msgs, get_chat_media, get_chat_msgs, get_chat_msgs_before,
+    get_chat_msgs_ex, get_day_boundaries, get_timeline, marknoticed_chat, remove_contact_from_chat,
+    Chat, ChatId, ChatItem, MessageListOptions, ProtectionStatus,
 };
 use deltachat::chatlist::Chatlist;
 use deltachat::config::Config;
 use deltachat::constants::DC_MSG_ID_DAYMARKER;
-use deltachat::contact::{may_be_valid_addr, Contact, ContactId, Origin};
+use deltachat::contact::{self, may_be_valid_addr, Contact, ContactId, Origin};
+use deltachat::contact_label::{self, LabelId};
 use deltachat::context::get_info;
+use deltachat::emoji;
 use deltachat::ephemeral::Timer;
+use deltachat::key::Fingerprint;
+use deltachat::key_import;
 use deltachat::location;
 use deltachat::message::get_msg_read_receipts;
 use deltachat::message::{
-    self, delete_msgs, markseen_msgs, Message, MessageState, MsgId, Viewtype,
+    self, delete_msgs, export_eml, markseen_msgs, Message, MessageState, MsgId, Viewtype,
 };
 use deltachat::peer_channels::{
     leave_webxdc_realtime, send_webxdc_realtime_advertisement, send_webxdc_realtime_data,
@@ -29,12 +34,13 @@
 use deltachat::provider::get_provider_info;
 use deltachat::qr::{self, Qr};
 use deltachat::qr_code_generator::{generate_backup_qr, get_securejoin_qr_svg};
-use deltachat::reaction::{get_msg_reactions, send_reaction};
+use deltachat::reaction::{get_msg_reactions, send_reaction, send_reaction_with_custom_emojis};
 use deltachat::securejoin;
 use deltachat::stock_str::StockMessage;
+use deltachat::thunderbird;
 use deltachat::webxdc::StatusUpdateSerial;
-use deltachat::EventEmitter;
 use deltachat::{imex, info};
+use deltachat::{EventEmitter, EventFilter};
 use sanitize_filename::is_sanitized;
 use tokio::fs;
 use tokio::sync::{watch, Mutex, RwLock};
@@ -44,25 +50,55 @@
 pub mod types;
 
 use num_traits::FromPrimitive;
-use types::account::Account;
-use types::chat::FullChat;
-use types::contact::{ContactObject, VcardContact};
-use types::events::Event;
+use types::account::{Account, JsonrpcAccountMetadata};
+use types::background_job::JsonrpcUpcomingJob;
+use types::blob_gc::{JsonrpcBlobGcReport, JsonrpcQuarantinedBlob};
+use types::chat::{
+    FullChat, JsonrpcBroadcastMsgStats, JsonrpcChatEncryptionInfo, JsonrpcGroupInvite,
+    JsonrpcJoinRequest, JsonrpcProtectionLogEntry,
+};
+use types::chat_export::JsonrpcChatExportVerification;
+use types::contact::{
+    ContactLabel, ContactObject, JsonrpcKeyInfo, JsonrpcMentionableMember, VcardContact,
+};
+use types::events::{ConfigureTraceEntry, Event};
+use types::health::JsonrpcHealthStatus;
 use types::http::HttpResponse;
-use types::message::{MessageData, MessageObject, MessageReadReceipt};
+use types::message::{
+    MessageData, MessageFailedRecipient, MessageObject, MessageReadReceipt,
+    MessageRecipientDelivery,
+};
+use types::metrics::JsonrpcMetrics;
+use types::network::JsonrpcNetworkProfile;
+use types::outbox::{JsonrpcQuarantinedMessage, JsonrpcQueuedMessage};
+use types::power_mode::JsonrpcPowerMode;
 use types::provider_info::ProviderInfo;
-use types::reactions::JSONRPCReactions;
-use types::webxdc::WebxdcMessageInfo;
+use types::push::{JsonrpcPushRegistration, JsonrpcPushTransport};
+use types::quota::JsonrpcQuotaRootUsage;
+use types::reactions::{JSONRPCCustomEmoji, JSONRPCReactions};
+use types::sync_state::{JsonrpcSyncReport, JsonrpcSyncState};
+use types::thunderbird::JsonrpcThunderbirdAccount;
+use types::translate::{JsonrpcAutoTranslate, JsonrpcMsgTranslation};
+use types::webxdc::{
+    JsonrpcWebxdcSendGrant, JsonrpcWebxdcSendOutcome, JsonrpcWebxdcUsage, WebxdcMessageInfo,
+};
 
-use self::types::message::{MessageInfo, MessageLoadResult};
+use self::types::message::{MessageInfo, MessageListPage, MessageLoadResult};
 use self::types::{
-    chat::{BasicChat, JSONRPCChatVisibility, MuteDuration},
+    chat::{
+        BasicChat, JSONRPCChatVisibility, JsonrpcMailinglistReplyMode, JsonrpcRetentionPolicy,
+        JsonrpcUnarchivePolicy, MuteDuration,
+    },
     location::JsonrpcLocation,
     message::{
-        JSONRPCMessageListItem, MessageNotificationInfo, MessageSearchResult, MessageViewtype,
+        JSONRPCDayBoundary, JSONRPCMessageListItem, JSONRPCTimelineAnchor, JSONRPCTimelineItem,
+        MessageNotificationInfo, MessageSearchResult, MessageViewtype,
     },
 };
-use crate::api::types::chat_list::{get_chat_list_item_by_id, ChatListItemFetchResult};
+use crate::api::types::chat_list::{
+    get_chat_list_item_by_id, get_chat_list_summary_by_id, ChatListItemFetchResult,
+    ChatListSummary, JsonrpcChatlistWithSections,
+};
 use crate::api::types::qr::QrObject;
 
 #[derive(Debug)]
@@ -90,14 +126,17 @@ pub struct CommandApi {
     /// Receiver side of the event channel.
     ///
     /// Events from it can be received by calling `get_next_event` method.
-    event_emitter: Arc<EventEmitter>,
+    /// It is replaced by `set_event_filter`.
+    event_emitter: Arc<std::sync::RwLock<Arc<EventEmitter>>>,
 
     states: Arc<Mutex<BTreeMap<u32, AccountState>>>,
 }
 
 impl CommandApi {
     pub fn new(accounts: Accounts) -> Self {
-        let event_emitter = Arc::new(accounts.get_event_emitter());
+        let event_emitter = Arc::new(std::sync::RwLock::new(Arc::new(
+            accounts.get_event_emitter(),
+        )));
         CommandApi {
             accounts: Arc::new(RwLock::new(accounts)),
             event_emitter,
@@ -107,7 +146,9 @@ pub fn new(accounts: Accounts) -> Self {
 
     #[allow(dead_code)]
     pub async fn from_arc(accounts: Arc<RwLock<Accounts>>) -> Self {
-        let event_emitter = Arc::new(accounts.read().await.get_event_emitter());
+        let event_emitter = Arc::new(std::sync::RwLock::new(Arc::new(
+            accounts.read().await.get_event_emitter(),
+        )));
         CommandApi {
             accounts,
             event_emitter,
@@ -166,6 +207,28 @@ async fn check_email_validity(&self, email: String) -> bool {
         may_be_valid_addr(&email)
     }
 
+    /// Returns the number of emojis if the text consists only of emojis and whitespace,
+    /// `null` otherwise.
+    ///
+    /// UIs may use this to display short emoji-only messages larger.
+    async fn emoji_only_count(&self, text: String) -> Option<usize> {
+        emoji::emoji_only_count(&text)
+    }
+
+    /// Splits text into grapheme clusters, i.e. the units perceived as single characters.
+    async fn split_graphemes(&self, text: String) -> Vec<String> {
+        emoji::split_graphemes(&text)
+            .into_iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    /// Normalizes an emoji by removing skin tone modifiers and variation selectors,
+    /// so that variants of the same emoji compare equal.
+    async fn normalize_emoji(&self, text: String) -> String {
+        emoji::normalize(&text)
+    }
+
     /// Returns general system info.
     async fn get_system_info(&self) -> BTreeMap<&'static str, String> {
         get_info()
@@ -173,13 +236,51 @@ async fn get_system_info(&self) -> BTreeMap<&'static str, String> {
 
     /// Get the next event.
     async fn get_next_event(&self) -> Result<Event> {
-        self.event_emitter
+        let event_emitter = Arc::clone(&self.event_emitter.read().unwrap());
+        event_emitter
             .recv()
             .await
             .map(|event| event.into())
             .context("event channel is closed")
     }
 
+    /// Restricts the events returned by `get_next_event`.
+    ///
+    /// `event_kinds` lists the kinds of events to return, e.g. `["IncomingMsg"]`,
+    /// and `account_ids` the accounts to return events of,
+    /// `null` returns events of all kinds or accounts.
+    /// If `coalesce` is true, frequent events such as `MsgsChanged`
+    /// are skipped while an equal event is waiting to be returned.
+    ///
+    /// Filtered events are dropped before they are queued,
+    /// so they cannot cause an `EventChannelOverflow`.
+    /// Events queued before the call are discarded.
+    async fn set_event_filter(
+        &self,
+        event_kinds: Option<Vec<String>>,
+        account_ids: Option<Vec<u32>>,
+        coalesce: bool,
+    ) -> Result<()> {
+        let accounts = self.accounts.read().await;
+        let event_emitter = if event_kinds.is_none() && account_ids.is_none() && !coalesce {
+            accounts.get_event_emitter()
+        } else {
+            let mut filter = EventFilter::new();
+            if let Some(event_kinds) = event_kinds {
+                filter = filter.with_event_names(event_kinds);
+            }
+            if let Some(account_ids) = account_ids {
+                filter = filter.with_accounts(account_ids);
+            }
+            if coalesce {
+                filter = filter.with_coalescing();
+            }
+            accounts.get_filtered_event_emitter(filter)
+        };
+        *self.event_emitter.write().unwrap() = Arc::new(event_emitter);
+        Ok(())
+    }
+
     // ---------------------------------------------
     // Account Management
     // ---------------------------------------------
@@ -188,6 +289,40 @@ async fn add_account(&self) -> Result<u32> {
         self.accounts.write().await.add_account().await
     }
 
+    /// Creates a new account and configures it from a `DCACCOUNT:` or `DCLOGIN:` QR code or URL.
+    ///
+    /// For `DCACCOUNT:` codes, the account is created on the chatmail server first.
+    /// Progress is reported with `ConfigureProgress` events of the new account.
+    /// On success, IO is started and the ID of the new account is returned.
+    /// On failure, the new account is removed again and the error is returned,
+    /// unless it was already created on the chatmail server.
+    /// Then the account is kept with its credentials, so configuring can be retried,
+    /// and the error contains its ID.
+    async fn add_account_from_qr(&self, qr_content: String) -> Result<u32> {
+        let account_id = self.accounts.write().await.add_account().await?;
+        let ctx = self.get_context(account_id).await?;
+        if let Err(err) = ctx.configure_from_qr(&qr_content).await {
+            let registered = matches!(
+                qr::check_qr(&ctx, &qr_content).await,
+                Ok(Qr::Account { .. })
+            ) && ctx.get_config(Config::MailPw).await?.is_some();
+            if registered {
+                return Err(err.context(format!(
+                    "Account {account_id} was created on the server, but configuring it failed"
+                )));
+            }
+            self.accounts
+                .write()
+                .await
+                .remove_account(account_id)
+                .await
+                .ok();
+            return Err(err);
+        }
+        ctx.start_io().await;
+        Ok(account_id)
+    }
+
     /// Imports/migrated an existing account from a database path into this account manager.
     /// Returns the ID of new account.
     async fn migrate_account(&self, path_to_db: String) -> Result<u32> {
@@ -292,6 +427,34 @@ async fn get_account_info(&self, account_id: u32) -> Result<Account> {
         }
     }
 
+    /// Returns the UI metadata of an account,
+    /// such as a custom name, a color tag and the sort order.
+    async fn get_account_metadata(&self, account_id: u32) -> Result<JsonrpcAccountMetadata> {
+        let metadata = self
+            .accounts
+            .read()
+            .await
+            .get_account_metadata(account_id)?;
+        Ok(metadata.into())
+    }
+
+    /// Sets the UI metadata of an account.
+    ///
+    /// The metadata is stored in `accounts.toml`
+    /// so that all frontends show the same account labels.
+    /// Emits `AccountsChanged`.
+    async fn set_account_metadata(
+        &self,
+        account_id: u32,
+        metadata: JsonrpcAccountMetadata,
+    ) -> Result<()> {
+        self.accounts
+            .write()
+            .await
+            .set_account_metadata(account_id, metadata.into())
+            .await
+    }
+
     /// Get the combined filesize of an account in bytes
     async fn get_account_file_size(&self, account_id: u32) -> Result<u64> {
         let ctx = self.get_context(account_id).await?;
@@ -335,12 +498,96 @@ async fn is_configured(&self, account_id: u32) -> Result<bool> {
         ctx.is_configured().await
     }
 
+    /// Checks if the do-not-disturb schedule set in the `dnd_schedule` config option is active.
+    ///
+    /// While it is active, new messages emit `MsgsChanged` instead of `IncomingMsg` events.
+    async fn is_dnd_active(&self, account_id: u32) -> Result<bool> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.is_dnd_active().await
+    }
+
     /// Get system info for an account.
     async fn get_info(&self, account_id: u32) -> Result<BTreeMap<&'static str, String>> {
         let ctx = self.get_context(account_id).await?;
         ctx.get_info().await
     }
 
+    /// Returns the progress of fetching messages from the server,
+    /// e.g. to show a progress bar while the initial sync of a new account is running.
+    ///
+    /// `SyncProgress` events are emitted whenever the progress changes.
+    async fn get_sync_state(&self, account_id: u32) -> Result<JsonrpcSyncState> {
+        let ctx = self.get_context(account_id).await?;
+        Ok(ctx.get_sync_state().await?.into())
+    }
+
+    /// Returns the changes found on the server during the first full sync
+    /// after starting I/O the last time,
+    /// or `null` if the first full sync is not done yet.
+    ///
+    /// The `SyncReport` event is emitted when the first full sync is done.
+    async fn get_sync_report(&self, account_id: u32) -> Result<Option<JsonrpcSyncReport>> {
+        let ctx = self.get_context(account_id).await?;
+        Ok(ctx.get_sync_report().map(Into::into))
+    }
+
+    /// Returns the messages waiting to be sent in the order they will be sent,
+    /// e.g. to show why a message is still being sent.
+    async fn get_outgoing_queue(&self, account_id: u32) -> Result<Vec<JsonrpcQueuedMessage>> {
+        let ctx = self.get_context(account_id).await?;
+        Ok(ctx
+            .get_outgoing_queue()
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    /// Removes a message from the outgoing queue and marks it as failed.
+    async fn cancel_queued_message(&self, account_id: u32, msg_id: u32) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.cancel_queued_message(MsgId::new(msg_id)).await
+    }
+
+    /// Moves a message to the front of the outgoing queue.
+    async fn prioritize_queued_message(&self, account_id: u32, msg_id: u32) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.prioritize_queued_message(MsgId::new(msg_id)).await
+    }
+
+    /// Returns the messages for which sending was given up after too many failed attempts.
+    async fn get_quarantined_messages(
+        &self,
+        account_id: u32,
+    ) -> Result<Vec<JsonrpcQuarantinedMessage>> {
+        let ctx = self.get_context(account_id).await?;
+        Ok(ctx
+            .get_quarantined_messages()
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    /// Moves a quarantined message back to the outgoing queue.
+    async fn retry_quarantined_message(&self, account_id: u32, msg_id: u32) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.retry_quarantined_message(MsgId::new(msg_id)).await
+    }
+
+    /// Removes a message from the quarantine without sending it, the message stays failed.
+    async fn discard_quarantined_message(&self, account_id: u32, msg_id: u32) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.discard_quarantined_message(MsgId::new(msg_id)).await
+    }
+
+    /// Returns the result of the key and backup health checks
+    /// to be shown in the settings.
+    async fn get_health_status(&self, account_id: u32) -> Result<JsonrpcHealthStatus> {
+        let ctx = self.get_context(account_id).await?;
+        Ok(deltachat::health::get_health_status(&ctx).await?.into())
+    }
+
     async fn get_blob_dir(&self, account_id: u32) -> Result<Option<String>> {
         let ctx = self.get_context(account_id).await?;
         Ok(ctx.get_blobdir().to_str().map(|s| s.to_owned()))
@@ -436,6 +683,110 @@ async fn configure(&self, account_id: u32) -> Result<()> {
         Ok(())
     }
 
+    /// Returns the steps tried by the current or the last `configure()` call,
+    /// such as autoconfig URLs, DNS lookups and servers, together with their errors.
+    ///
+    /// The trace is not persisted and is empty
+    /// if the account was not configured since it was opened.
+    async fn get_last_configure_trace(&self, account_id: u32) -> Result<Vec<ConfigureTraceEntry>> {
+        let ctx = self.get_context(account_id).await?;
+        Ok(ctx
+            .get_last_configure_trace()
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    /// Returns the profile directories listed in `profiles.ini`
+    /// of the given Thunderbird directory, the default profile first.
+    async fn get_thunderbird_profiles(&self, dir: String) -> Result<Vec<String>> {
+        Ok(thunderbird::get_thunderbird_profiles(Path::new(&dir))
+            .await?
+            .into_iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect())
+    }
+
+    /// Returns the IMAP accounts configured in the given Thunderbird profile directory.
+    async fn get_thunderbird_accounts(
+        &self,
+        profile: String,
+    ) -> Result<Vec<JsonrpcThunderbirdAccount>> {
+        Ok(thunderbird::get_thunderbird_accounts(Path::new(&profile))
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    /// Sets the login parameters of the Thunderbird account with the given address
+    /// from the given profile directory.
+    ///
+    /// Passwords are not imported,
+    /// set `mail_pw` before calling `configure()`.
+    async fn set_config_from_thunderbird_account(
+        &self,
+        account_id: u32,
+        profile: String,
+        addr: String,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        let account = thunderbird::get_thunderbird_accounts(Path::new(&profile))
+            .await?
+            .into_iter()
+            .find(|account| account.addr == addr)
+            .with_context(|| format!("No Thunderbird account for {addr}"))?;
+        thunderbird::set_config_from_thunderbird_account(&ctx, &account).await
+    }
+
+    /// Adds the contacts of the personal address book of the given Thunderbird profile directory.
+    ///
+    /// Returns the number of modified contacts.
+    async fn import_thunderbird_contacts(&self, account_id: u32, profile: String) -> Result<usize> {
+        let ctx = self.get_context(account_id).await?;
+        thunderbird::import_thunderbird_contacts(&ctx, Path::new(&profile)).await
+    }
+
+    /// Registers a bot command such as `/help`.
+    ///
+    /// Incoming messages starting with a registered command
+    /// produce a `BotCommand` event with the parsed command and arguments.
+    /// Registered commands are not persisted and need to be registered on every start.
+    async fn register_bot_command(&self, account_id: u32, command: String) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.register_bot_command(&command)
+    }
+
+    /// Unregisters a bot command.
+    ///
+    /// Returns true if the command was registered.
+    async fn unregister_bot_command(&self, account_id: u32, command: String) -> Result<bool> {
+        let ctx = self.get_context(account_id).await?;
+        Ok(ctx.unregister_bot_command(&command))
+    }
+
+    /// Returns the registered bot commands.
+    async fn get_bot_commands(&self, account_id: u32) -> Result<Vec<String>> {
+        let ctx = self.get_context(account_id).await?;
+        Ok(ctx.get_bot_commands())
+    }
+
+    /// Returns the DNS TXT record to publish for the configured DKIM key
+    /// or `null` if DKIM signing is not configured.
+    async fn get_dkim_dns_record(&self, account_id: u32) -> Result<Option<String>> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.get_dkim_dns_record().await
+    }
+
+    /// Signs a test message with the configured DKIM key
+    /// and verifies it against the public key published in DNS.
+    ///
+    /// Returns an error describing the problem if the check fails.
+    async fn dkim_self_test(&self, account_id: u32) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.dkim_self_test().await
+    }
+
     /// Signal an ongoing process to stop.
     async fn stop_ongoing_process(&self, account_id: u32) -> Result<()> {
         let ctx = self.get_context(account_id).await?;
@@ -475,6 +826,15 @@ async fn import_self_keys(
         .await
     }
 
+    /// Replaces the own key with a newly generated key
+    /// and announces it to verified contacts.
+    ///
+    /// The old key is kept for decrypting old messages.
+    async fn rotate_self_key(&self, account_id: u32) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        deltachat::key::rotate_self_key(&ctx).await
+    }
+
     /// Returns the message IDs of all _fresh_ messages of any chat.
     /// Typically used for implementing notification summaries
     /// or badge counters e.g. on the app icon.
@@ -609,6 +969,28 @@ async fn get_chatlist_entries(
         Ok(l)
     }
 
+    /// Returns the chatlist like `getChatlistEntries()`,
+    /// with the kind of each entry and metadata about the sections
+    /// (pinned chats, contact requests and the archive link).
+    async fn get_chatlist_with_sections(
+        &self,
+        account_id: u32,
+        list_flags: Option<u32>,
+        query_string: Option<String>,
+        query_contact_id: Option<u32>,
+    ) -> Result<JsonrpcChatlistWithSections> {
+        let ctx = self.get_context(account_id).await?;
+        let list = Chatlist::try_load(
+            &ctx,
+            list_flags.unwrap_or(0) as usize,
+            query_string.as_deref(),
+            query_contact_id.map(ContactId::new),
+        )
+        .await?;
+        let sections = list.get_sections(&ctx).await?;
+        Ok(JsonrpcChatlistWithSections::new(&list, sections))
+    }
+
     /// Returns chats similar to the given one.
     ///
     /// Experimental API, subject to change without notice.
@@ -647,6 +1029,49 @@ async fn get_chatlist_items_by_entries(
         Ok(result)
     }
 
+    /// Returns the chatlists of all accounts merged into a single list.
+    ///
+    /// Pinned chats come first, otherwise the chats are sorted by the timestamp
+    /// of their last message, newest first.
+    /// The archive link and other special entries are not included.
+    /// Use `getChatlistItemsByEntries()` with the chat IDs of each account to get the items.
+    async fn get_combined_chatlist(
+        &self,
+        list_flags: Option<u32>,
+        query_string: Option<String>,
+    ) -> Result<Vec<JsonrpcCombinedChatlistEntry>> {
+        let list = self
+            .accounts
+            .read()
+            .await
+            .get_combined_chatlist(list_flags.unwrap_or(0) as usize, query_string.as_deref())
+            .await?;
+        Ok(list.into_iter().map(Into::into).collect())
+    }
+
+    /// Returns the number of fresh messages in unmuted chats of all accounts.
+    async fn get_combined_fresh_msg_cnt(&self) -> Result<usize> {
+        self.accounts
+            .read()
+            .await
+            .get_combined_fresh_msg_cnt()
+            .await
+    }
+
+    /// Returns the summary of a chatlist item as a structured object.
+    ///
+    /// Unlike `summaryText1` and `summaryText2` of [`ChatListItemFetchResult`],
+    /// the sender, the viewtype and a reaction newer than the last message
+    /// are returned as separate fields.
+    async fn get_chatlist_item_summary(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+    ) -> Result<ChatListSummary> {
+        let ctx = self.get_context(account_id).await?;
+        get_chat_list_summary_by_id(&ctx, chat_id).await
+    }
+
     // ---------------------------------------------
     //  chat
     // ---------------------------------------------
@@ -706,6 +1131,31 @@ async fn get_chat_encryption_info(&self, account_id: u32, chat_id: u32) -> Resul
         ChatId::new(chat_id).get_encryption_info(&ctx).await
     }
 
+    /// Returns the encryption state of each chat member
+    /// and whether messages sent to the chat are end-to-end encrypted,
+    /// e.g. to explain in the compose view why a message would be sent unencrypted.
+    async fn get_chat_encryption_details(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+    ) -> Result<JsonrpcChatEncryptionInfo> {
+        let ctx = self.get_context(account_id).await?;
+        let details = ChatId::new(chat_id).get_encryption_details(&ctx).await?;
+        Ok(details.into())
+    }
+
+    /// Returns the audit trail of protection-relevant changes in a chat, oldest first,
+    /// e.g. to explain why a chat became unprotected.
+    async fn get_chat_protection_log(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+    ) -> Result<Vec<JsonrpcProtectionLogEntry>> {
+        let ctx = self.get_context(account_id).await?;
+        let log = ChatId::new(chat_id).get_protection_log(&ctx).await?;
+        Ok(log.into_iter().map(Into::into).collect())
+    }
+
     /// Get QR code text that will offer a [SecureJoin](https://securejoin.delta.chat/) invitation.
     ///
     /// If `chat_id` is a group chat ID, SecureJoin QR code for the group is returned.
@@ -721,6 +1171,70 @@ async fn get_chat_securejoin_qr_code(
         Ok(qr)
     }
 
+    /// Get a word-encoded setup-contact invite code.
+    ///
+    /// The code consists of the own address followed by 24 words,
+    /// so that it can be dictated or typed in by hand.
+    /// It is accepted by `checkQr()` and `secureJoin()` like a setup-contact QR code
+    /// and is valid for at least two days.
+    async fn get_securejoin_invite_words(&self, account_id: u32) -> Result<String> {
+        let ctx = self.get_context(account_id).await?;
+        securejoin::get_securejoin_invite_words(&ctx).await
+    }
+
+    /// Get QR code text of a one-time invitation to a group which requires approval.
+    ///
+    /// The QR code can be used by a single joiner only.
+    /// When the joiner scans the QR code, `SecurejoinApprovalRequest` event is emitted
+    /// and the joiner is added to the group only after `approve_join_request()` is called.
+    async fn get_chat_securejoin_qr_code_with_approval(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+    ) -> Result<String> {
+        let ctx = self.get_context(account_id).await?;
+        securejoin::get_securejoin_qr_with_approval(&ctx, ChatId::new(chat_id)).await
+    }
+
+    /// Get QR code text of a group invitation with an expiration time and/or a use limit.
+    ///
+    /// `expires` is the timestamp after which the QR code is not accepted anymore,
+    /// `max_uses` is the number of contacts that may join the group using the QR code.
+    /// Each call creates a new invitation which can be revoked with `revoke_group_invite()`.
+    async fn get_chat_securejoin_qr_code_limited(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+        expires: Option<i64>,
+        max_uses: Option<u32>,
+    ) -> Result<String> {
+        let ctx = self.get_context(account_id).await?;
+        securejoin::get_securejoin_qr_limited(&ctx, ChatId::new(chat_id), expires, max_uses).await
+    }
+
+    /// Returns the invitations of a group created by `get_chat_securejoin_qr_code_limited()`
+    /// which are not expired or used up yet.
+    async fn get_group_invites(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+    ) -> Result<Vec<JsonrpcGroupInvite>> {
+        let ctx = self.get_context(account_id).await?;
+        let invites = securejoin::get_group_invites(&ctx, ChatId::new(chat_id)).await?;
+        Ok(invites.into_iter().map(Into::into).collect())
+    }
+
+    /// Revokes a group invitation, so its QR code is not accepted anymore.
+    async fn revoke_group_invite(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+        invite_id: u32,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        securejoin::revoke_group_invite(&ctx, ChatId::new(chat_id), invite_id).await
+    }
+
     /// Get QR code (text and SVG) that will offer a Setup-Contact or Verified-Group invitation.
     /// The QR code is compatible to the OPENPGP4FPR format
     /// so that a basic fingerprint comparison also works e.g. with OpenKeychain.
@@ -774,6 +1288,37 @@ async fn secure_join(&self, account_id: u32, qr: String) -> Result<u32> {
         Ok(chat_id.to_u32())
     }
 
+    /// Returns requests to join groups via one-time invitations waiting for approval.
+    async fn get_join_requests(&self, account_id: u32) -> Result<Vec<JsonrpcJoinRequest>> {
+        let ctx = self.get_context(account_id).await?;
+        let requests = securejoin::get_join_requests(&ctx).await?;
+        Ok(requests.into_iter().map(Into::into).collect())
+    }
+
+    /// Approves the request of a contact to join a group and adds the contact to the group.
+    async fn approve_join_request(
+        &self,
+        account_id: u32,
+        contact_id: u32,
+        chat_id: u32,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        securejoin::approve_join_request(&ctx, ContactId::new(contact_id), ChatId::new(chat_id))
+            .await
+    }
+
+    /// Rejects the request of a contact to join a group.
+    async fn reject_join_request(
+        &self,
+        account_id: u32,
+        contact_id: u32,
+        chat_id: u32,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        securejoin::reject_join_request(&ctx, ContactId::new(contact_id), ChatId::new(chat_id))
+            .await
+    }
+
     async fn leave_group(&self, account_id: u32, chat_id: u32) -> Result<()> {
         let ctx = self.get_context(account_id).await?;
         remove_contact_from_chat(&ctx, ChatId::new(chat_id), ContactId::SELF).await
@@ -813,13 +1358,99 @@ async fn add_contact_to_chat(
         add_contact_to_chat(&ctx, ChatId::new(chat_id), ContactId::new(contact_id)).await
     }
 
-    /// Get the contact IDs belonging to a chat.
-    ///
-    /// - for normal chats, the function always returns exactly one contact,
-    ///   DC_CONTACT_ID_SELF is returned only for SELF-chats.
+    /// Allow only admins to change members, name and image of a group
+    /// or allow all members to change the group again.
     ///
-    /// - for group chats all members are returned, DC_CONTACT_ID_SELF is returned
-    ///   explicitly as it may happen that oneself gets removed from a still existing
+    /// Only admins can change moderation.
+    /// If the group is already _promoted_ (any message was sent to the group),
+    /// all group members are informed by a special status message that is sent automatically by this function.
+    async fn set_group_moderated(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+        moderated: bool,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        chat::set_group_moderated(&ctx, ChatId::new(chat_id), moderated).await
+    }
+
+    /// Make a group member an admin or revoke the admin rights of a member.
+    ///
+    /// Only admins can do this and the last admin of a group cannot be removed.
+    async fn set_group_admin(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+        contact_id: u32,
+        admin: bool,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        chat::set_group_admin(
+            &ctx,
+            ChatId::new(chat_id),
+            ContactId::new(contact_id),
+            admin,
+        )
+        .await
+    }
+
+    /// Returns the contact IDs of the admins of a group.
+    async fn get_group_admins(&self, account_id: u32, chat_id: u32) -> Result<Vec<u32>> {
+        let ctx = self.get_context(account_id).await?;
+        let admins = chat::get_group_admins(&ctx, ChatId::new(chat_id)).await?;
+        Ok(admins.iter().map(|id| id.to_u32()).collect())
+    }
+
+    /// Returns the members of a protected group whose key changed since they were verified.
+    async fn get_members_needing_reverification(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+    ) -> Result<Vec<u32>> {
+        let ctx = self.get_context(account_id).await?;
+        let members = chat::get_members_needing_reverification(&ctx, ChatId::new(chat_id)).await?;
+        Ok(members.iter().map(|id| id.to_u32()).collect())
+    }
+
+    /// Adds a member of a protected group back after their key changed.
+    ///
+    /// The member must be verified again first, e.g. by scanning their QR code.
+    async fn repair_verified_group_member(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+        contact_id: u32,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        chat::repair_verified_group_member(&ctx, ChatId::new(chat_id), ContactId::new(contact_id))
+            .await
+    }
+
+    /// Add several contacts to a group at once.
+    ///
+    /// If the group is promoted, a single status message is sent for all added members.
+    /// Contacts which are already members are skipped.
+    ///
+    /// Fails if the group would grow above the `group_member_hard_limit` config
+    /// or if too many members were added recently.
+    async fn add_contacts_to_chat(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+        contact_ids: Vec<u32>,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        let contact_ids: Vec<ContactId> = contact_ids.into_iter().map(ContactId::new).collect();
+        chat::add_contacts_to_chat(&ctx, ChatId::new(chat_id), &contact_ids).await
+    }
+
+    /// Get the contact IDs belonging to a chat.
+    ///
+    /// - for normal chats, the function always returns exactly one contact,
+    ///   DC_CONTACT_ID_SELF is returned only for SELF-chats.
+    ///
+    /// - for group chats all members are returned, DC_CONTACT_ID_SELF is returned
+    ///   explicitly as it may happen that oneself gets removed from a still existing
     ///   group
     ///
     /// - for broadcasts, all recipients are returned, DC_CONTACT_ID_SELF is not included
@@ -841,6 +1472,19 @@ async fn get_past_chat_contacts(&self, account_id: u32, chat_id: u32) -> Result<
         Ok(contacts.iter().map(|id| id.to_u32()).collect::<Vec<u32>>())
     }
 
+    /// Returns chat members matching `prefix` for @-mention pickers,
+    /// members who wrote recently first.
+    async fn get_mentionable_members(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+        prefix: String,
+    ) -> Result<Vec<JsonrpcMentionableMember>> {
+        let ctx = self.get_context(account_id).await?;
+        let members = chat::get_mentionable_members(&ctx, ChatId::new(chat_id), &prefix).await?;
+        Ok(members.into_iter().map(Into::into).collect())
+    }
+
     /// Create a new group chat.
     ///
     /// After creation,
@@ -887,6 +1531,30 @@ async fn create_broadcast_list(&self, account_id: u32) -> Result<u32> {
             .map(|id| id.to_u32())
     }
 
+    /// Returns delivery statistics of the messages sent to a broadcast list, oldest first.
+    ///
+    /// Read counts are built from read receipts,
+    /// so they are lower bounds as recipients may disable read receipts.
+    async fn get_broadcast_stats(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+    ) -> Result<Vec<JsonrpcBroadcastMsgStats>> {
+        let ctx = self.get_context(account_id).await?;
+        let stats = chat::get_broadcast_stats(&ctx, ChatId::new(chat_id)).await?;
+        Ok(stats.into_iter().map(Into::into).collect())
+    }
+
+    /// Converts a broadcast list into a group with the same name and members.
+    ///
+    /// The members learn about the group with the first message sent to it.
+    /// Unlike in the broadcast list, the members see each other's addresses in the group,
+    /// so the UI should ask the user for confirmation before converting.
+    async fn convert_broadcast_to_group(&self, account_id: u32, chat_id: u32) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        chat::convert_broadcast_to_group(&ctx, ChatId::new(chat_id)).await
+    }
+
     /// Set group name.
     ///
     /// If the group is already _promoted_ (any message was sent to the group),
@@ -922,6 +1590,73 @@ async fn set_chat_profile_image(
             .await
     }
 
+    /// Sets whether replies in a mailing list chat are sent to the list
+    /// or privately to the sender of the quoted message.
+    ///
+    /// Sends out #DC_EVENT_CHAT_MODIFIED.
+    async fn set_chat_mailinglist_reply_mode(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+        mode: JsonrpcMailinglistReplyMode,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ChatId::new(chat_id)
+            .set_mailinglist_reply_mode(&ctx, mode.into_core_type())
+            .await
+    }
+
+    /// Sets whether incoming reactions to own messages count towards
+    /// the fresh message counter of the chat until the chat is noticed.
+    ///
+    /// `null` uses the global `reactions_count_as_fresh` config option.
+    async fn set_chat_reactions_count_as_fresh(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+        count_as_fresh: Option<bool>,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ChatId::new(chat_id)
+            .set_reactions_count_as_fresh(&ctx, count_as_fresh)
+            .await
+    }
+
+    /// Sets the last read message of the chat.
+    ///
+    /// Incoming fresh messages sent not later than the given message are marked as noticed.
+    /// The marker is synchronized to other devices, so that fresh message counters match.
+    async fn set_last_read_msg(&self, account_id: u32, chat_id: u32, msg_id: u32) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ChatId::new(chat_id)
+            .set_last_read_msg(&ctx, MsgId::new(msg_id))
+            .await
+    }
+
+    /// Returns the last read message of the chat set on this or another device.
+    ///
+    /// Returns `null` if no marker is set or the message is not available on this device.
+    async fn get_last_read_msg(&self, account_id: u32, chat_id: u32) -> Result<Option<u32>> {
+        let ctx = self.get_context(account_id).await?;
+        let msg_id = ChatId::new(chat_id).get_last_read_msg(&ctx).await?;
+        Ok(msg_id.map(|msg_id| msg_id.to_u32()))
+    }
+
+    /// Subscribes to a mailing list using the `mailto:` URI of its `List-Subscribe` header.
+    async fn subscribe_mailinglist(&self, account_id: u32, chat_id: u32) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ChatId::new(chat_id).subscribe(&ctx).await
+    }
+
+    /// Unsubscribes from a mailing list,
+    /// see `canUnsubscribe` of [`FullChat`] whether this is supported.
+    ///
+    /// The result is also added to the chat as an info message.
+    async fn unsubscribe_mailinglist(&self, account_id: u32, chat_id: u32) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ChatId::new(chat_id).unsubscribe(&ctx).await
+    }
+
     async fn set_chat_visibility(
         &self,
         account_id: u32,
@@ -947,6 +1682,64 @@ async fn set_chat_ephemeral_timer(
             .await
     }
 
+    /// Enables or disables requesting read receipts for outgoing messages in a chat.
+    ///
+    /// This is independent from the `mdns_enabled` config,
+    /// read receipts for incoming messages are still sent if enabled.
+    async fn set_chat_mdn_requests_disabled(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+        disabled: bool,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ChatId::new(chat_id)
+            .set_mdn_requests_disabled(&ctx, disabled)
+            .await
+    }
+
+    /// Enables or disables requiring end-to-end encryption for outgoing messages in a chat.
+    ///
+    /// If enabled, sending fails if a message cannot be encrypted.
+    async fn set_chat_encryption_required(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+        required: bool,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ChatId::new(chat_id)
+            .set_encryption_required(&ctx, required)
+            .await
+    }
+
+    /// Sets a custom `X-` header added to outgoing messages of a chat,
+    /// or removes it if `value` is `null`.
+    ///
+    /// The header must be listed in the `allowed_custom_headers` config.
+    async fn set_chat_custom_header(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+        name: String,
+        value: Option<String>,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ChatId::new(chat_id)
+            .set_custom_header(&ctx, &name, value.as_deref())
+            .await
+    }
+
+    /// Returns the custom headers of a chat as `[name, value]` pairs.
+    async fn get_chat_custom_headers(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+    ) -> Result<Vec<(String, String)>> {
+        let ctx = self.get_context(account_id).await?;
+        ChatId::new(chat_id).get_custom_headers(&ctx).await
+    }
+
     async fn get_chat_ephemeral_timer(&self, account_id: u32, chat_id: u32) -> Result<u32> {
         let ctx = self.get_context(account_id).await?;
         Ok(ChatId::new(chat_id)
@@ -998,6 +1791,29 @@ async fn marknoticed_chat(&self, account_id: u32, chat_id: u32) -> Result<()> {
         marknoticed_chat(&ctx, ChatId::new(chat_id)).await
     }
 
+    /// Marks all messages in all chats as _noticed_
+    /// and removes all marks set by `set_chat_marked_unread()`.
+    ///
+    /// Other devices mark messages received until the time of the call as noticed, too.
+    async fn marknoticed_all_chats(&self, account_id: u32) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        chat::marknoticed_all_chats(&ctx).await
+    }
+
+    /// Marks a chat as unread intentionally or removes the mark.
+    ///
+    /// A marked chat has a `freshMessageCounter` of at least 1.
+    /// The mark is removed when the chat is noticed and is synchronized to other devices.
+    async fn set_chat_marked_unread(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+        unread: bool,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ChatId::new(chat_id).set_marked_unread(&ctx, unread).await
+    }
+
     async fn get_first_unread_message_of_chat(
         &self,
         account_id: u32,
@@ -1054,6 +1870,88 @@ async fn is_chat_muted(&self, account_id: u32, chat_id: u32) -> Result<bool> {
             .is_muted())
     }
 
+    /// Sets the local retention policy of the chat.
+    ///
+    /// Messages exceeding the policy are deleted from this device during housekeeping,
+    /// messages on the server are not affected.
+    /// The policy is synchronized to other devices.
+    ///
+    /// Sends out #DC_EVENT_CHAT_MODIFIED.
+    async fn set_chat_retention(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+        policy: JsonrpcRetentionPolicy,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ChatId::new(chat_id)
+            .set_retention(&ctx, policy.into_core_type())
+            .await
+    }
+
+    /// Returns the local retention policy of the chat.
+    async fn get_chat_retention(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+    ) -> Result<JsonrpcRetentionPolicy> {
+        let ctx = self.get_context(account_id).await?;
+        Ok(ChatId::new(chat_id).get_retention(&ctx).await?.into())
+    }
+
+    /// Sets the inactivity timer of the chat in days, 0 disables it.
+    ///
+    /// All messages of the chat are deleted from this device
+    /// once the chat was neither noticed nor archived for the given number of days.
+    /// The setting is synchronized to other devices, the countdown is not.
+    ///
+    /// Sends out #DC_EVENT_CHAT_MODIFIED.
+    async fn set_chat_inactivity_timer(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+        days: u32,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ChatId::new(chat_id).set_inactivity_timer(&ctx, days).await
+    }
+
+    /// Returns the inactivity timer of the chat in days, 0 if disabled.
+    async fn get_chat_inactivity_timer(&self, account_id: u32, chat_id: u32) -> Result<u32> {
+        let ctx = self.get_context(account_id).await?;
+        ChatId::new(chat_id).get_inactivity_timer(&ctx).await
+    }
+
+    /// Sets whether the archived chat is unarchived by new messages.
+    ///
+    /// The policy is synchronized to other devices.
+    ///
+    /// Sends out #DC_EVENT_CHAT_MODIFIED.
+    async fn set_chat_unarchive_policy(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+        policy: JsonrpcUnarchivePolicy,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ChatId::new(chat_id)
+            .set_unarchive_policy(&ctx, policy.into_core_type())
+            .await
+    }
+
+    /// Returns whether the archived chat is unarchived by new messages.
+    async fn get_chat_unarchive_policy(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+    ) -> Result<JsonrpcUnarchivePolicy> {
+        let ctx = self.get_context(account_id).await?;
+        Ok(ChatId::new(chat_id)
+            .get_unarchive_policy(&ctx)
+            .await?
+            .into())
+    }
+
     // ---------------------------------------------
     // message list
     // ---------------------------------------------
@@ -1086,12 +1984,15 @@ async fn markseen_msgs(&self, account_id: u32, msg_ids: Vec<u32>) -> Result<()>
         markseen_msgs(&ctx, msg_ids.into_iter().map(MsgId::new).collect()).await
     }
 
+    /// If `include_hidden_local` is true, messages hidden with `setMessageHiddenLocal()`
+    /// are returned as well.
     async fn get_message_ids(
         &self,
         account_id: u32,
         chat_id: u32,
         info_only: bool,
         add_daymarker: bool,
+        include_hidden_local: Option<bool>,
     ) -> Result<Vec<u32>> {
         let ctx = self.get_context(account_id).await?;
         let msg = get_chat_msgs_ex(
@@ -1100,6 +2001,7 @@ async fn get_message_ids(
             MessageListOptions {
                 info_only,
                 add_daymarker,
+                include_hidden_local: include_hidden_local.unwrap_or_default(),
             },
         )
         .await?;
@@ -1114,12 +2016,15 @@ async fn get_message_ids(
             .collect())
     }
 
+    /// If `include_hidden_local` is true, messages hidden with `setMessageHiddenLocal()`
+    /// are returned as well.
     async fn get_message_list_items(
         &self,
         account_id: u32,
         chat_id: u32,
         info_only: bool,
         add_daymarker: bool,
+        include_hidden_local: Option<bool>,
     ) -> Result<Vec<JSONRPCMessageListItem>> {
         let ctx = self.get_context(account_id).await?;
         let msg = get_chat_msgs_ex(
@@ -1128,6 +2033,7 @@ async fn get_message_list_items(
             MessageListOptions {
                 info_only,
                 add_daymarker,
+                include_hidden_local: include_hidden_local.unwrap_or_default(),
             },
         )
         .await?;
@@ -1137,6 +2043,54 @@ async fn get_message_list_items(
             .collect::<Vec<JSONRPCMessageListItem>>())
     }
 
+    /// Returns the day boundaries of messages with timestamps
+    /// from `start_timestamp` (inclusive) to `end_timestamp` (exclusive).
+    ///
+    /// Each boundary is the position of a day marker
+    /// that `get_message_list_items` adds if `add_daymarker` is set,
+    /// so virtualized lists can place the markers without loading all messages.
+    async fn get_day_boundaries(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+        start_timestamp: i64,
+        end_timestamp: i64,
+    ) -> Result<Vec<JSONRPCDayBoundary>> {
+        let ctx = self.get_context(account_id).await?;
+        let boundaries =
+            get_day_boundaries(&ctx, ChatId::new(chat_id), start_timestamp..end_timestamp).await?;
+        Ok(boundaries.into_iter().map(Into::into).collect())
+    }
+
+    /// Returns a window of the chat timeline around `anchor`
+    /// with up to `before` messages before the anchor
+    /// and the anchor message followed by up to `after` messages.
+    ///
+    /// Items are typed, so calls, membership changes and webxdc updates
+    /// can be rendered without loading each message first.
+    /// Day markers are added before the first message of each day
+    /// and do not count towards the limits.
+    /// Use the key of the first or last item as anchor to load more items.
+    async fn get_timeline(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+        anchor: JSONRPCTimelineAnchor,
+        before: u32,
+        after: u32,
+    ) -> Result<Vec<JSONRPCTimelineItem>> {
+        let ctx = self.get_context(account_id).await?;
+        let entries = get_timeline(
+            &ctx,
+            ChatId::new(chat_id),
+            anchor.into(),
+            before as usize,
+            after as usize,
+        )
+        .await?;
+        Ok(entries.into_iter().map(Into::into).collect())
+    }
+
     async fn get_message(&self, account_id: u32, msg_id: u32) -> Result<MessageObject> {
         let ctx = self.get_context(account_id).await?;
         let msg_id = MsgId::new(msg_id);
@@ -1164,23 +2118,60 @@ async fn get_messages(
         let ctx = self.get_context(account_id).await?;
         let mut messages: HashMap<u32, MessageLoadResult> = HashMap::new();
         for message_id in message_ids {
-            let message_result = MessageObject::from_msg_id(&ctx, MsgId::new(message_id)).await;
             messages.insert(
                 message_id,
-                match message_result {
-                    Ok(Some(message)) => MessageLoadResult::Message(message),
-                    Ok(None) => MessageLoadResult::LoadingError {
-                        error: "Message does not exist".to_string(),
-                    },
-                    Err(error) => MessageLoadResult::LoadingError {
-                        error: format!("{error:#}"),
-                    },
-                },
+                MessageLoadResult::load(&ctx, MsgId::new(message_id)).await,
             );
         }
         Ok(messages)
     }
 
+    /// Returns a page of the chat message list together with the messages,
+    /// saving a round-trip per message compared to `get_message_ids` and `get_messages`.
+    ///
+    /// Pages are returned from the newest to the oldest messages.
+    /// To get the newest messages, pass no `cursor`;
+    /// to get the previous page, pass the `nextCursor` of the current page.
+    /// Cursors stay valid when new messages arrive.
+    async fn message_list_page(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+        cursor: Option<String>,
+        page_size: u32,
+    ) -> Result<MessageListPage> {
+        let ctx = self.get_context(account_id).await?;
+        let before = match cursor {
+            Some(cursor) => {
+                let (timestamp, msg_id) = cursor
+                    .split_once(':')
+                    .with_context(|| format!("Invalid cursor {cursor:?}"))?;
+                Some((timestamp.parse()?, MsgId::new(msg_id.parse()?)))
+            }
+            None => None,
+        };
+        let page_size = usize::try_from(page_size)?;
+        let page = get_chat_msgs_before(&ctx, ChatId::new(chat_id), before, page_size).await?;
+
+        let next_cursor = match page.first() {
+            Some((timestamp, msg_id)) if page.len() == page_size => {
+                Some(format!("{timestamp}:{}", msg_id.to_u32()))
+            }
+            _ => None,
+        };
+        let mut message_ids = Vec::new();
+        let mut messages = HashMap::new();
+        for (_timestamp, msg_id) in page {
+            message_ids.push(msg_id.to_u32());
+            messages.insert(msg_id.to_u32(), MessageLoadResult::load(&ctx, msg_id).await);
+        }
+        Ok(MessageListPage {
+            message_ids,
+            messages,
+            next_cursor,
+        })
+    }
+
     /// Fetch info desktop needs for creating a notification for a message
     async fn get_message_notification_info(
         &self,
@@ -1199,6 +2190,40 @@ async fn delete_messages(&self, account_id: u32, message_ids: Vec<u32>) -> Resul
         delete_msgs(&ctx, &msgs).await
     }
 
+    /// Hides a message from the chat on this device without deleting it.
+    ///
+    /// Hidden messages are not returned by `getMessageIds()`, searches and media lists.
+    /// Hiding is not sent to other devices or chat members, but is kept in backups.
+    async fn set_message_hidden_local(
+        &self,
+        account_id: u32,
+        message_id: u32,
+        hidden: bool,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        message::set_hidden_local(&ctx, MsgId::new(message_id), hidden).await
+    }
+
+    /// Exports the raw MIME of messages as `.eml` files to the directory `destination`.
+    ///
+    /// Raw messages are only available for received messages
+    /// if the `save_mime_headers` option was set on receiving.
+    /// Returns the paths of the written files.
+    async fn export_messages_as_eml(
+        &self,
+        account_id: u32,
+        message_ids: Vec<u32>,
+        destination: String,
+    ) -> Result<Vec<String>> {
+        let ctx = self.get_context(account_id).await?;
+        let msgs: Vec<MsgId> = message_ids.into_iter().map(MsgId::new).collect();
+        let paths = export_eml(&ctx, &msgs, destination.as_ref()).await?;
+        Ok(paths
+            .into_iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect())
+    }
+
     /// Get an informational text for a single message. The text is multiline and may
     /// contain e.g. the raw text of the message.
     ///
@@ -1219,6 +2244,47 @@ async fn get_message_info_object(
         MessageInfo::from_msg_id(&ctx, MsgId::new(message_id)).await
     }
 
+    /// Returns recipients which likely did not receive the message
+    /// because their servers rejected it.
+    async fn get_message_failed_recipients(
+        &self,
+        account_id: u32,
+        message_id: u32,
+    ) -> Result<Vec<MessageFailedRecipient>> {
+        let ctx = self.get_context(account_id).await?;
+        let failed = MsgId::new(message_id)
+            .get_failed_recipients(&ctx)
+            .await?
+            .into_iter()
+            .map(|failed| MessageFailedRecipient {
+                addr: failed.addr,
+                error: failed.error,
+            })
+            .collect();
+        Ok(failed)
+    }
+
+    /// Returns the delivery status of the message per recipient,
+    /// as reported by positive delivery status notifications of the recipients' servers.
+    async fn get_message_delivery_status(
+        &self,
+        account_id: u32,
+        message_id: u32,
+    ) -> Result<Vec<MessageRecipientDelivery>> {
+        let ctx = self.get_context(account_id).await?;
+        let status = MsgId::new(message_id)
+            .get_delivery_status(&ctx)
+            .await?
+            .into_iter()
+            .map(|delivery| MessageRecipientDelivery {
+                addr: delivery.addr,
+                action: delivery.action.to_string(),
+                timestamp: delivery.timestamp,
+            })
+            .collect();
+        Ok(status)
+    }
+
     /// Returns contacts that sent read receipts and the time of reading.
     async fn get_message_read_receipts(
         &self,
@@ -1279,6 +2345,29 @@ async fn search_messages(
             .collect::<Vec<u32>>())
     }
 
+    /// Searches for messages like `search_messages`,
+    /// but if there are only few local results,
+    /// also searches the watched folders on the IMAP server.
+    ///
+    /// Matching messages that were not downloaded yet
+    /// are returned with `downloadState` set to `Available`.
+    /// This connects to the server, so it should not be called on each keystroke.
+    async fn search_messages_on_server(
+        &self,
+        account_id: u32,
+        query: String,
+        chat_id: Option<u32>,
+    ) -> Result<Vec<u32>> {
+        let ctx = self.get_context(account_id).await?;
+        let messages = ctx
+            .search_msgs_on_server(chat_id.map(ChatId::new), &query)
+            .await?;
+        Ok(messages
+            .iter()
+            .map(|msg_id| msg_id.to_u32())
+            .collect::<Vec<u32>>())
+    }
+
     async fn message_ids_to_search_results(
         &self,
         account_id: u32,
@@ -1429,6 +2518,103 @@ async fn delete_contact(&self, account_id: u32, contact_id: u32) -> Result<()> {
         Ok(())
     }
 
+    /// Returns all local contact labels ordered by name.
+    async fn get_contact_labels(&self, account_id: u32) -> Result<Vec<ContactLabel>> {
+        let ctx = self.get_context(account_id).await?;
+        let labels = contact_label::get_labels(&ctx).await?;
+        Ok(labels.into_iter().map(Into::into).collect())
+    }
+
+    /// Creates a local contact label and returns its ID.
+    ///
+    /// If a label with the same name exists, its ID is returned.
+    async fn create_contact_label(&self, account_id: u32, name: String) -> Result<u32> {
+        let ctx = self.get_context(account_id).await?;
+        let label_id = contact_label::create_label(&ctx, &name).await?;
+        Ok(label_id.to_u32())
+    }
+
+    async fn rename_contact_label(
+        &self,
+        account_id: u32,
+        label_id: u32,
+        new_name: String,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        contact_label::rename_label(&ctx, LabelId::new(label_id), &new_name).await
+    }
+
+    /// Deletes a contact label. The labeled contacts are not deleted.
+    async fn delete_contact_label(&self, account_id: u32, label_id: u32) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        contact_label::delete_label(&ctx, LabelId::new(label_id)).await
+    }
+
+    async fn add_contact_to_label(
+        &self,
+        account_id: u32,
+        label_id: u32,
+        contact_id: u32,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        contact_label::add_contact_to_label(
+            &ctx,
+            LabelId::new(label_id),
+            ContactId::new(contact_id),
+        )
+        .await
+    }
+
+    async fn remove_contact_from_label(
+        &self,
+        account_id: u32,
+        label_id: u32,
+        contact_id: u32,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        contact_label::remove_contact_from_label(
+            &ctx,
+            LabelId::new(label_id),
+            ContactId::new(contact_id),
+        )
+        .await
+    }
+
+    /// Returns IDs of the labels of the contact.
+    async fn get_labels_of_contact(&self, account_id: u32, contact_id: u32) -> Result<Vec<u32>> {
+        let ctx = self.get_context(account_id).await?;
+        let label_ids = contact_label::get_contact_labels(&ctx, ContactId::new(contact_id)).await?;
+        Ok(label_ids.into_iter().map(|id| id.to_u32()).collect())
+    }
+
+    /// Same as `get_contact_ids`, but returns only contacts having the label.
+    async fn get_contact_ids_with_label(
+        &self,
+        account_id: u32,
+        label_id: u32,
+        list_flags: u32,
+        query: Option<String>,
+    ) -> Result<Vec<u32>> {
+        let ctx = self.get_context(account_id).await?;
+        let contacts =
+            Contact::get_all_with_label(&ctx, LabelId::new(label_id), list_flags, query.as_deref())
+                .await?;
+        Ok(contacts.into_iter().map(|c| c.to_u32()).collect())
+    }
+
+    /// Creates an unpromoted group named after the label
+    /// with the labeled contacts as members and returns the chat ID.
+    async fn create_group_chat_from_contact_label(
+        &self,
+        account_id: u32,
+        label_id: u32,
+    ) -> Result<u32> {
+        let ctx = self.get_context(account_id).await?;
+        let chat_id =
+            contact_label::create_group_chat_from_label(&ctx, LabelId::new(label_id)).await?;
+        Ok(chat_id.to_u32())
+    }
+
     /// Resets contact encryption.
     async fn reset_contact_encryption(&self, account_id: u32, contact_id: u32) -> Result<()> {
         let ctx = self.get_context(account_id).await?;
@@ -1464,6 +2650,40 @@ async fn get_contact_encryption_info(
         Contact::get_encrinfo(&ctx, ContactId::new(contact_id)).await
     }
 
+    /// Returns structured information about the key of a contact,
+    /// e.g. to show encryption details or a fingerprint QR code.
+    ///
+    /// Returns `null` if no key of the contact is known.
+    async fn get_contact_key_info(
+        &self,
+        account_id: u32,
+        contact_id: u32,
+    ) -> Result<Option<JsonrpcKeyInfo>> {
+        let ctx = self.get_context(account_id).await?;
+        let key_info = contact::get_key_info(&ctx, ContactId::new(contact_id)).await?;
+        Ok(key_info.map(Into::into))
+    }
+
+    /// Sets whether messages to the contact are never encrypted,
+    /// e.g. because the contact's mail gateway breaks encrypted messages.
+    ///
+    /// Applies only to the 1:1 chat with the contact and read receipts,
+    /// groups containing the contact are still encrypted.
+    /// The setting is synchronized to other devices.
+    async fn set_contact_force_plaintext(
+        &self,
+        account_id: u32,
+        contact_id: u32,
+        force_plaintext: bool,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        let value = match force_plaintext {
+            true => contact::EncryptionOverride::ForcePlaintext,
+            false => contact::EncryptionOverride::Default,
+        };
+        contact::set_encryption_override(&ctx, ContactId::new(contact_id), value).await
+    }
+
     /// Check if an e-mail address belongs to a known and unblocked contact.
     /// To get a list of all known and unblocked contacts, use contacts_get_contacts().
     ///
@@ -1568,46 +2788,166 @@ async fn get_chat_media(
         let or_msg_type2 = or_message_type2.map_or(Viewtype::Unknown, |v| v.into());
         let or_msg_type3 = or_message_type3.map_or(Viewtype::Unknown, |v| v.into());
 
-        let media = get_chat_media(&ctx, chat_id, msg_type, or_msg_type2, or_msg_type3).await?;
-        Ok(media.iter().map(|msg_id| msg_id.to_u32()).collect())
+        let media = get_chat_media(&ctx, chat_id, msg_type, or_msg_type2, or_msg_type3).await?;
+        Ok(media.iter().map(|msg_id| msg_id.to_u32()).collect())
+    }
+
+    // ---------------------------------------------
+    //                   backup
+    // ---------------------------------------------
+
+    async fn export_backup(
+        &self,
+        account_id: u32,
+        destination: String,
+        passphrase: Option<String>,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        imex::imex(
+            &ctx,
+            imex::ImexMode::ExportBackup,
+            destination.as_ref(),
+            passphrase,
+        )
+        .await
+    }
+
+    async fn import_backup(
+        &self,
+        account_id: u32,
+        path: String,
+        passphrase: Option<String>,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        imex::imex(
+            &ctx,
+            imex::ImexMode::ImportBackup,
+            path.as_ref(),
+            passphrase,
+        )
+        .await
+    }
+
+    /// Exports a backup into a new transfer folder inside `destination`,
+    /// e.g. on a USB stick, for devices that cannot be paired over the network.
+    ///
+    /// The backup is written in chunks encrypted with `passphrase`.
+    async fn export_backup_to_folder(
+        &self,
+        account_id: u32,
+        destination: String,
+        passphrase: String,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        imex::imex(
+            &ctx,
+            imex::ImexMode::ExportBackupToFolder,
+            destination.as_ref(),
+            Some(passphrase),
+        )
+        .await
+    }
+
+    /// Imports a backup from a transfer folder created by `exportBackupToFolder()`
+    /// into an unconfigured account.
+    async fn import_backup_from_folder(
+        &self,
+        account_id: u32,
+        path: String,
+        passphrase: String,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        imex::imex(
+            &ctx,
+            imex::ImexMode::ImportBackupFromFolder,
+            path.as_ref(),
+            Some(passphrase),
+        )
+        .await
+    }
+
+    /// Exports the chat into a new folder inside `destination`
+    /// with the messages as canonical JSON, the attachments and a SHA-256 manifest.
+    ///
+    /// If `sign` is true, the manifest is signed with the account key.
+    /// Returns the path of the created folder.
+    async fn export_chat(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+        destination: String,
+        sign: bool,
+    ) -> Result<String> {
+        let ctx = self.get_context(account_id).await?;
+        let folder =
+            imex::export_chat(&ctx, ChatId::new(chat_id), destination.as_ref(), sign).await?;
+        Ok(folder.to_string_lossy().into_owned())
     }
 
-    // ---------------------------------------------
-    //                   backup
-    // ---------------------------------------------
+    /// Checks a folder created by `exportChat()` against its manifest and signature.
+    ///
+    /// The signature is only accepted if it is made by the key
+    /// with the fingerprint `signer_fingerprint`.
+    /// If it is null, only the hashes in the manifest are checked.
+    async fn verify_chat_export(
+        &self,
+        path: String,
+        signer_fingerprint: Option<String>,
+    ) -> Result<JsonrpcChatExportVerification> {
+        let signer = signer_fingerprint
+            .map(|fingerprint| fingerprint.parse::<Fingerprint>())
+            .transpose()?;
+        let verification = imex::verify_chat_export(path.as_ref(), signer.as_ref()).await?;
+        Ok(verification.into())
+    }
 
-    async fn export_backup(
+    /// Exports the settings of the account to the directory `destination`,
+    /// encrypted with `passphrase`.
+    ///
+    /// The file contains server settings, display name, avatar and preferences,
+    /// but no messages, keys, login names or passwords.
+    async fn export_settings(
         &self,
         account_id: u32,
         destination: String,
-        passphrase: Option<String>,
+        passphrase: String,
     ) -> Result<()> {
         let ctx = self.get_context(account_id).await?;
         imex::imex(
             &ctx,
-            imex::ImexMode::ExportBackup,
+            imex::ImexMode::ExportSettings,
             destination.as_ref(),
-            passphrase,
+            Some(passphrase),
         )
         .await
     }
 
-    async fn import_backup(
+    /// Imports settings exported with `exportSettings()` into an unconfigured account.
+    async fn import_settings(
         &self,
         account_id: u32,
         path: String,
-        passphrase: Option<String>,
+        passphrase: String,
     ) -> Result<()> {
         let ctx = self.get_context(account_id).await?;
         imex::imex(
             &ctx,
-            imex::ImexMode::ImportBackup,
+            imex::ImexMode::ImportSettings,
             path.as_ref(),
-            passphrase,
+            Some(passphrase),
         )
         .await
     }
 
+    /// Imports e-mails from other mail clients into chats.
+    ///
+    /// `path` is an mbox file, a single `.eml` file or a directory containing `.eml` files.
+    /// Messages that are already in the database are skipped.
+    async fn import_mails(&self, account_id: u32, path: String) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        imex::imex(&ctx, imex::ImexMode::ImportMails, path.as_ref(), None).await
+    }
+
     /// Offers a backup for remote devices to retrieve.
     ///
     /// Can be cancelled by stopping the ongoing process.  Success or failure can be tracked
@@ -1704,6 +3044,136 @@ async fn maybe_network(&self) -> Result<()> {
         Ok(())
     }
 
+    /// Sets the kind of the network the device is connected to for all accounts.
+    ///
+    /// Should be called from the platform hooks reporting network changes.
+    /// On metered networks large messages are only downloaded partially,
+    /// while roaming webxdc status updates are additionally queued
+    /// until the network changes.
+    async fn set_network_profile(&self, profile: JsonrpcNetworkProfile) -> Result<()> {
+        self.accounts
+            .read()
+            .await
+            .set_network_profile(profile.into())
+            .await;
+        Ok(())
+    }
+
+    /// Reports whether the device runs on battery.
+    ///
+    /// Periodic background jobs that are not urgent, such as housekeeping,
+    /// are deferred while the device runs on battery.
+    /// Jobs using the network are deferred on metered networks,
+    /// see `set_network_profile`.
+    async fn set_background_job_conditions(&self, on_battery: bool) -> Result<()> {
+        self.accounts
+            .read()
+            .await
+            .set_background_job_conditions(JobConditions { on_battery });
+        Ok(())
+    }
+
+    /// Returns the periodic background jobs scheduled for all accounts,
+    /// ordered by the time of their next run.
+    async fn get_upcoming_background_jobs(&self) -> Result<Vec<JsonrpcUpcomingJob>> {
+        Ok(self
+            .accounts
+            .read()
+            .await
+            .get_upcoming_background_jobs()
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    /// Returns the network profile last set for the account.
+    async fn get_network_profile(&self, account_id: u32) -> Result<JsonrpcNetworkProfile> {
+        let ctx = self.get_context(account_id).await?;
+        Ok(ctx.get_network_profile().into())
+    }
+
+    /// Sets the power mode for all accounts.
+    ///
+    /// In battery saver mode IMAP IDLE is restarted less often,
+    /// servers without IDLE are polled less often
+    /// and background jobs such as housekeeping are deferred.
+    /// The effects of the current mode are listed by `get_info`.
+    async fn set_power_mode(&self, mode: JsonrpcPowerMode) -> Result<()> {
+        self.accounts.read().await.set_power_mode(mode.into()).await;
+        Ok(())
+    }
+
+    /// Returns the power mode last set for the account.
+    async fn get_power_mode(&self, account_id: u32) -> Result<JsonrpcPowerMode> {
+        let ctx = self.get_context(account_id).await?;
+        Ok(ctx.get_power_mode().into())
+    }
+
+    /// Returns message, connection and queue metrics of the account.
+    ///
+    /// Counters start at zero when the account is opened.
+    async fn get_metrics(&self, account_id: u32) -> Result<JsonrpcMetrics> {
+        let ctx = self.get_context(account_id).await?;
+        Ok(ctx.get_metrics().await?.into())
+    }
+
+    /// Returns the metrics of all open accounts
+    /// in the OpenMetrics text format used by Prometheus.
+    async fn get_openmetrics(&self) -> Result<String> {
+        self.accounts.read().await.get_openmetrics().await
+    }
+
+    /// Adds a push notification registration of a device to the account
+    /// or updates the device name if the token is already registered.
+    ///
+    /// The registration is sent to the server or the UnifiedPush gateway in the background.
+    /// Returns the ID of the registration.
+    async fn add_push_registration(
+        &self,
+        account_id: u32,
+        transport: JsonrpcPushTransport,
+        token: String,
+        device_name: String,
+    ) -> Result<u32> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.add_push_registration(transport.into(), &token, &device_name)
+            .await
+    }
+
+    /// Returns the push notification registrations of the account.
+    async fn get_push_registrations(
+        &self,
+        account_id: u32,
+    ) -> Result<Vec<JsonrpcPushRegistration>> {
+        let ctx = self.get_context(account_id).await?;
+        let registrations = ctx.get_push_registrations().await?;
+        Ok(registrations.into_iter().map(Into::into).collect())
+    }
+
+    /// Revokes a push notification registration,
+    /// UnifiedPush registrations are unregistered from the gateway in the background.
+    async fn revoke_push_registration(&self, account_id: u32, registration_id: u32) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.revoke_push_registration(registration_id).await
+    }
+
+    /// Decrypts a notification received for a UnifiedPush registration.
+    ///
+    /// `payload` is the base64-encoded message body as received from the distributor.
+    /// Returns the decrypted notification text.
+    async fn decrypt_push_payload(
+        &self,
+        account_id: u32,
+        registration_id: u32,
+        payload: String,
+    ) -> Result<String> {
+        use base64::{engine::general_purpose, Engine as _};
+        let ctx = self.get_context(account_id).await?;
+        let payload = general_purpose::STANDARD.decode(payload)?;
+        let plaintext = ctx.decrypt_push_payload(registration_id, &payload).await?;
+        Ok(String::from_utf8(plaintext)?)
+    }
+
     /// Get the current connectivity, i.e. whether the device is connected to the IMAP server.
     /// One of:
     /// - DC_CONNECTIVITY_NOT_CONNECTED (1000-1999): Show e.g. the string "Not connected" or a red dot
@@ -1737,6 +3207,64 @@ async fn get_connectivity_html(&self, account_id: u32) -> Result<String> {
         ctx.get_connectivity_html().await
     }
 
+    /// Generates a self-diagnostics report to share with support,
+    /// including connectivity, folder configuration, quota and recent log messages
+    /// with email addresses removed.
+    ///
+    /// Returns the report as plain text or, if `json` is true, as JSON.
+    async fn generate_diagnostics(&self, account_id: u32, json: bool) -> Result<String> {
+        let ctx = self.get_context(account_id).await?;
+        let diagnostics = ctx.generate_diagnostics().await?;
+        if json {
+            Ok(serde_json::to_string_pretty(&diagnostics)?)
+        } else {
+            Ok(diagnostics.to_string())
+        }
+    }
+
+    /// Returns the most recently loaded quota usage for each quota root
+    /// together with the folders counted against it.
+    ///
+    /// Returns `null` if quota was not loaded yet
+    /// and an error if the provider does not support quota.
+    async fn get_quota_usage(&self, account_id: u32) -> Result<Option<Vec<JsonrpcQuotaRootUsage>>> {
+        let ctx = self.get_context(account_id).await?;
+        let roots = ctx.get_quota_usage().await?;
+        Ok(roots.map(|roots| roots.into_iter().map(Into::into).collect()))
+    }
+
+    /// Returns the blob files which housekeeping would quarantine or delete now
+    /// together with the reason, without touching any file.
+    async fn blob_gc_dry_run(&self, account_id: u32) -> Result<JsonrpcBlobGcReport> {
+        let ctx = self.get_context(account_id).await?;
+        Ok(ctx.blob_gc_dry_run().await?.into())
+    }
+
+    /// Returns the report of the last housekeeping run
+    /// or `null` if housekeeping has not collected unused blob files yet.
+    async fn get_last_blob_gc_report(
+        &self,
+        account_id: u32,
+    ) -> Result<Option<JsonrpcBlobGcReport>> {
+        let ctx = self.get_context(account_id).await?;
+        Ok(ctx.get_last_blob_gc_report().await?.map(Into::into))
+    }
+
+    /// Returns the unused blob files which are in quarantine before being deleted.
+    async fn get_quarantined_blobs(&self, account_id: u32) -> Result<Vec<JsonrpcQuarantinedBlob>> {
+        let ctx = self.get_context(account_id).await?;
+        let blobs = ctx.get_quarantined_blobs().await?;
+        Ok(blobs.into_iter().map(Into::into).collect())
+    }
+
+    /// Moves all blob files in quarantine back to the blob directory.
+    ///
+    /// Returns the number of restored files.
+    async fn restore_quarantined_blobs(&self, account_id: u32) -> Result<usize> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.restore_quarantined_blobs().await
+    }
+
     // ---------------------------------------------
     //                  locations
     // ---------------------------------------------
@@ -1779,6 +3307,200 @@ async fn send_webxdc_status_update(
             .await
     }
 
+    /// Requests sending a text message into the chat of a webxdc instance
+    /// on behalf of the webxdc app.
+    ///
+    /// Unless the user has decided permanently for the app,
+    /// the `WebxdcSendRequest` event is emitted
+    /// and the message is only sent after approveWebxdcSendRequest() is called.
+    async fn request_webxdc_send_message(
+        &self,
+        account_id: u32,
+        instance_msg_id: u32,
+        text: String,
+    ) -> Result<JsonrpcWebxdcSendOutcome> {
+        let ctx = self.get_context(account_id).await?;
+        let outcome = ctx
+            .request_webxdc_send_message(MsgId::new(instance_msg_id), &text)
+            .await?;
+        Ok(outcome.into())
+    }
+
+    /// Asks to notify the user about an event in a webxdc app,
+    /// called when the app uses `sendNotification(title, body)`.
+    ///
+    /// Emits the `WebxdcNotify` event unless the chat is muted
+    /// or the app sends notifications too often.
+    /// Returns true if the event is emitted.
+    async fn send_webxdc_notification(
+        &self,
+        account_id: u32,
+        instance_msg_id: u32,
+        title: String,
+        body: String,
+    ) -> Result<bool> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.send_webxdc_notification(MsgId::new(instance_msg_id), &title, &body)
+            .await
+    }
+
+    /// Imports the public key attached to a message as the key of the sender.
+    ///
+    /// Returns the ID of the sender.
+    /// Should only be called after the user confirmed the import,
+    /// see the `IncomingPublicKey` event.
+    async fn import_key_from_msg(&self, account_id: u32, msg_id: u32) -> Result<u32> {
+        let ctx = self.get_context(account_id).await?;
+        let contact_id = key_import::import_key_from_msg(&ctx, MsgId::new(msg_id)).await?;
+        Ok(contact_id.to_u32())
+    }
+
+    /// Enables automatic translation of incoming messages in a chat,
+    /// or disables it if `settings` is null.
+    ///
+    /// Translations are requested with the `TranslationRequest` event.
+    async fn set_chat_auto_translate(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+        settings: Option<JsonrpcAutoTranslate>,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        let settings = settings.map(Into::into);
+        ChatId::new(chat_id)
+            .set_auto_translate(&ctx, settings.as_ref())
+            .await
+    }
+
+    /// Returns the automatic translation settings of a chat,
+    /// null if automatic translation is disabled.
+    async fn get_chat_auto_translate(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+    ) -> Result<Option<JsonrpcAutoTranslate>> {
+        let ctx = self.get_context(account_id).await?;
+        let settings = ChatId::new(chat_id).get_auto_translate(&ctx).await?;
+        Ok(settings.map(Into::into))
+    }
+
+    /// Requests translation of a single message,
+    /// also in chats without automatic translation.
+    async fn request_msg_translation(
+        &self,
+        account_id: u32,
+        msg_id: u32,
+        settings: JsonrpcAutoTranslate,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.request_msg_translation(MsgId::new(msg_id), &settings.into())
+            .await
+    }
+
+    /// Stores the translation of a message requested with the `TranslationRequest` event.
+    async fn set_msg_translation(
+        &self,
+        account_id: u32,
+        msg_id: u32,
+        target_lang: String,
+        text: String,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.set_msg_translation(MsgId::new(msg_id), &target_lang, &text)
+            .await
+    }
+
+    /// Returns the translation of a message, null if no translation was requested.
+    async fn get_msg_translation(
+        &self,
+        account_id: u32,
+        msg_id: u32,
+    ) -> Result<Option<JsonrpcMsgTranslation>> {
+        let ctx = self.get_context(account_id).await?;
+        let translation = MsgId::new(msg_id).get_translation(&ctx).await?;
+        Ok(translation.map(Into::into))
+    }
+
+    /// Switches between showing the original text and the translation of a message.
+    async fn set_msg_show_original(
+        &self,
+        account_id: u32,
+        msg_id: u32,
+        show_original: bool,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        MsgId::new(msg_id)
+            .set_show_original(&ctx, show_original)
+            .await
+    }
+
+    /// Requests text recognition in the image attachment of a message.
+    ///
+    /// For new messages, this is done automatically if the `request_ocr` config option is set.
+    /// The `OcrRequest` event is emitted unless the text is recognized already.
+    async fn request_msg_ocr(&self, account_id: u32, msg_id: u32) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.request_msg_ocr(MsgId::new(msg_id)).await
+    }
+
+    /// Stores the text recognized in the image attachment of a message
+    /// as requested with the `OcrRequest` event.
+    ///
+    /// Pass an empty string if the image contains no text.
+    async fn set_msg_ocr_text(&self, account_id: u32, msg_id: u32, text: String) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.set_msg_ocr_text(MsgId::new(msg_id), &text).await
+    }
+
+    /// Returns the IDs of images in which text was not recognized yet, newest first.
+    async fn get_pending_ocr(&self, account_id: u32) -> Result<Vec<u32>> {
+        let ctx = self.get_context(account_id).await?;
+        let msg_ids = deltachat::ocr::get_pending_ocr(&ctx).await?;
+        Ok(msg_ids.into_iter().map(|msg_id| msg_id.to_u32()).collect())
+    }
+
+    /// Approves a pending request of a webxdc app to send a message and sends it.
+    ///
+    /// If `remember` is true, further requests of the app are sent without asking.
+    /// Returns the ID of the sent message.
+    async fn approve_webxdc_send_request(
+        &self,
+        account_id: u32,
+        request_id: u32,
+        remember: bool,
+    ) -> Result<u32> {
+        let ctx = self.get_context(account_id).await?;
+        let msg_id = ctx
+            .approve_webxdc_send_request(request_id, remember)
+            .await?;
+        Ok(msg_id.to_u32())
+    }
+
+    /// Denies a pending request of a webxdc app to send a message.
+    ///
+    /// If `remember` is true, further requests of the app are denied without asking.
+    async fn deny_webxdc_send_request(
+        &self,
+        account_id: u32,
+        request_id: u32,
+        remember: bool,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.deny_webxdc_send_request(request_id, remember).await
+    }
+
+    /// Sets the permission of a webxdc app to send messages on the user's behalf.
+    async fn set_webxdc_send_grant(
+        &self,
+        account_id: u32,
+        instance_msg_id: u32,
+        grant: JsonrpcWebxdcSendGrant,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.set_webxdc_send_grant(MsgId::new(instance_msg_id), grant.into())
+            .await
+    }
+
     async fn send_webxdc_realtime_data(
         &self,
         account_id: u32,
@@ -1834,6 +3556,40 @@ async fn get_webxdc_info(
         WebxdcMessageInfo::get_for_message(&ctx, MsgId::new(instance_msg_id)).await
     }
 
+    /// Sends all status updates of a webxdc instance to other devices,
+    /// so that devices added later reproduce the app state.
+    async fn sync_webxdc_status_updates(
+        &self,
+        account_id: u32,
+        instance_msg_id: u32,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.sync_webxdc_status_updates(MsgId::new(instance_msg_id))
+            .await
+    }
+
+    /// Returns the resource usage of a webxdc instance.
+    async fn get_webxdc_usage(
+        &self,
+        account_id: u32,
+        instance_msg_id: u32,
+    ) -> Result<JsonrpcWebxdcUsage> {
+        let ctx = self.get_context(account_id).await?;
+        let usage = ctx.get_webxdc_usage(MsgId::new(instance_msg_id)).await?;
+        Ok(usage.into())
+    }
+
+    /// Returns the summed up resource usage of all webxdc instances in a chat.
+    async fn get_chat_webxdc_usage(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+    ) -> Result<JsonrpcWebxdcUsage> {
+        let ctx = self.get_context(account_id).await?;
+        let usage = ctx.get_chat_webxdc_usage(ChatId::new(chat_id)).await?;
+        Ok(usage.into())
+    }
+
     /// Get href from a WebxdcInfoMessage which might include a hash holding
     /// information about a specific position or state in a webxdc app (optional)
     async fn get_webxdc_href(
@@ -1961,6 +3717,29 @@ async fn send_reaction(
         Ok(message_id.to_u32())
     }
 
+    /// Send a reaction containing custom emojis to message.
+    ///
+    /// Reaction is a list of emojis and shortcodes of custom emojis.
+    /// Custom emojis are sent as their fallback emoji to clients not supporting them.
+    async fn send_reaction_with_custom_emojis(
+        &self,
+        account_id: u32,
+        message_id: u32,
+        reaction: Vec<String>,
+        custom_emojis: Vec<JSONRPCCustomEmoji>,
+    ) -> Result<u32> {
+        let ctx = self.get_context(account_id).await?;
+        let custom_emojis: Vec<_> = custom_emojis.into_iter().map(Into::into).collect();
+        let message_id = send_reaction_with_custom_emojis(
+            &ctx,
+            MsgId::new(message_id),
+            &reaction.join(" "),
+            &custom_emojis,
+        )
+        .await?;
+        Ok(message_id.to_u32())
+    }
+
     /// Returns reactions to the message.
     async fn get_message_reactions(
         &self,
@@ -1,7 +1,8 @@
 use anyhow::Result;
+use deltachat::accounts::AccountMetadata;
 use deltachat::config::Config;
 use deltachat::contact::{Contact, ContactId};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use typescript_type_def::TypeDef;
 
 use super::color_int_to_hex_string;
@@ -48,3 +49,36 @@ pub async fn from_context(ctx: &deltachat::context::Context, id: u32) -> Result<
         }
     }
 }
+
+/// Metadata of an account set by the UI and stored in `accounts.toml`,
+/// so that all frontends show the same account labels.
+#[derive(Serialize, Deserialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "AccountMetadata", rename_all = "camelCase")]
+pub struct JsonrpcAccountMetadata {
+    /// Custom name of the account shown in the account list.
+    name: Option<String>,
+    /// Color tag of the account as `#rrggbb` hex string.
+    color: Option<String>,
+    /// Position of the account in the account list, lower values first.
+    sort_order: Option<i64>,
+}
+
+impl From<AccountMetadata> for JsonrpcAccountMetadata {
+    fn from(metadata: AccountMetadata) -> Self {
+        Self {
+            name: metadata.name,
+            color: metadata.color,
+            sort_order: metadata.sort_order,
+        }
+    }
+}
+
+impl From<JsonrpcAccountMetadata> for AccountMetadata {
+    fn from(metadata: JsonrpcAccountMetadata) -> Self {
+        Self {
+            name: metadata.name,
+            color: metadata.color,
+            sort_order: metadata.sort_order,
+        }
+    }
+}
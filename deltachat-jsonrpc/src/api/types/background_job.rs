@@ -0,0 +1,67 @@
+use deltachat::accounts::{JobKind, JobPriority, UpcomingJob};
+use serde::Serialize;
+use typescript_type_def::TypeDef;
+
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "BackgroundJobKind")]
+pub enum JsonrpcJobKind {
+    /// Requests an update of the storage quota from the IMAP server.
+    QuotaCheck,
+    /// Adds device messages warning about outdated app, missing backups and similar.
+    DeviceWarnings,
+    /// Removes unused blobs, old messages and optimizes the database.
+    Housekeeping,
+}
+
+impl From<JobKind> for JsonrpcJobKind {
+    fn from(kind: JobKind) -> Self {
+        match kind {
+            JobKind::QuotaCheck => JsonrpcJobKind::QuotaCheck,
+            JobKind::DeviceWarnings => JsonrpcJobKind::DeviceWarnings,
+            JobKind::Housekeeping => JsonrpcJobKind::Housekeeping,
+        }
+    }
+}
+
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "BackgroundJobPriority")]
+pub enum JsonrpcJobPriority {
+    High,
+    Normal,
+    Low,
+}
+
+impl From<JobPriority> for JsonrpcJobPriority {
+    fn from(priority: JobPriority) -> Self {
+        match priority {
+            JobPriority::High => JsonrpcJobPriority::High,
+            JobPriority::Normal => JsonrpcJobPriority::Normal,
+            JobPriority::Low => JsonrpcJobPriority::Low,
+        }
+    }
+}
+
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "UpcomingBackgroundJob", rename_all = "camelCase")]
+pub struct JsonrpcUpcomingJob {
+    account_id: u32,
+    kind: JsonrpcJobKind,
+    priority: JsonrpcJobPriority,
+    /// Unix timestamp of the earliest time the job runs at.
+    next_run: i64,
+    /// True if the job is deferred because the device runs on battery
+    /// or uses a metered network.
+    deferred: bool,
+}
+
+impl From<UpcomingJob> for JsonrpcUpcomingJob {
+    fn from(job: UpcomingJob) -> Self {
+        Self {
+            account_id: job.account_id,
+            kind: job.kind.into(),
+            priority: job.priority.into(),
+            next_run: job.next_run,
+            deferred: job.deferred,
+        }
+    }
+}
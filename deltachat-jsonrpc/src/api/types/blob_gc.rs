@@ -0,0 +1,103 @@
+use deltachat::blob_gc::{BlobGcCandidate, BlobGcReason, BlobGcReport, QuarantinedBlob};
+use serde::Serialize;
+use typescript_type_def::TypeDef;
+
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "BlobGcReason")]
+pub enum JsonrpcBlobGcReason {
+    /// The file is not referenced at all.
+    Unreferenced,
+    /// The file is only referenced by deleted messages.
+    DeletedMessage,
+    /// The file is left over from an interrupted backup import.
+    BackupLeftover,
+    /// The file has been in quarantine for longer than `blob_quarantine_days`.
+    QuarantineExpired,
+}
+
+impl From<BlobGcReason> for JsonrpcBlobGcReason {
+    fn from(reason: BlobGcReason) -> Self {
+        match reason {
+            BlobGcReason::Unreferenced => JsonrpcBlobGcReason::Unreferenced,
+            BlobGcReason::DeletedMessage => JsonrpcBlobGcReason::DeletedMessage,
+            BlobGcReason::BackupLeftover => JsonrpcBlobGcReason::BackupLeftover,
+            BlobGcReason::QuarantineExpired => JsonrpcBlobGcReason::QuarantineExpired,
+        }
+    }
+}
+
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "BlobGcCandidate", rename_all = "camelCase")]
+pub struct JsonrpcBlobGcCandidate {
+    name: String,
+    /// File size in bytes.
+    size: u64,
+    /// Modification time as a unix timestamp.
+    modified: i64,
+    reason: JsonrpcBlobGcReason,
+}
+
+impl From<BlobGcCandidate> for JsonrpcBlobGcCandidate {
+    fn from(candidate: BlobGcCandidate) -> Self {
+        Self {
+            name: candidate.name,
+            size: candidate.size,
+            modified: candidate.modified,
+            reason: candidate.reason.into(),
+        }
+    }
+}
+
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "BlobGcReport", rename_all = "camelCase")]
+pub struct JsonrpcBlobGcReport {
+    timestamp: i64,
+    dry_run: bool,
+    files_in_use: usize,
+    /// Number of unreferenced files kept because they were created recently.
+    kept_new: usize,
+    /// Files which are quarantined or deleted, or would be in a dry run.
+    candidates: Vec<JsonrpcBlobGcCandidate>,
+    quarantined: usize,
+    deleted: usize,
+    /// Number of bytes freed by deleting files.
+    reclaimed_bytes: u64,
+    /// Total size of the files in quarantine after the run.
+    quarantine_bytes: u64,
+}
+
+impl From<BlobGcReport> for JsonrpcBlobGcReport {
+    fn from(report: BlobGcReport) -> Self {
+        Self {
+            timestamp: report.timestamp,
+            dry_run: report.dry_run,
+            files_in_use: report.files_in_use,
+            kept_new: report.kept_new,
+            candidates: report.candidates.into_iter().map(Into::into).collect(),
+            quarantined: report.quarantined,
+            deleted: report.deleted,
+            reclaimed_bytes: report.reclaimed_bytes,
+            quarantine_bytes: report.quarantine_bytes,
+        }
+    }
+}
+
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "QuarantinedBlob", rename_all = "camelCase")]
+pub struct JsonrpcQuarantinedBlob {
+    name: String,
+    /// File size in bytes.
+    size: u64,
+    /// Unix timestamp of the time the file was quarantined.
+    quarantined: i64,
+}
+
+impl From<QuarantinedBlob> for JsonrpcQuarantinedBlob {
+    fn from(blob: QuarantinedBlob) -> Self {
+        Self {
+            name: blob.name,
+            size: blob.size,
+            quarantined: blob.quarantined,
+        }
+    }
+}
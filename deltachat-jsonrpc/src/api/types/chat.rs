@@ -2,16 +2,20 @@
 
 use anyhow::{bail, Context as _, Result};
 use deltachat::chat::{self, get_chat_contacts, get_past_chat_contacts, ChatVisibility};
-use deltachat::chat::{Chat, ChatId};
+use deltachat::chat::{
+    BroadcastMsgStats, BroadcastRecipientState, BroadcastRecipientStats, Chat, ChatEncryptionInfo,
+    ChatId, ProtectionLogEntry, ProtectionLogEvent,
+};
 use deltachat::constants::Chattype;
 use deltachat::contact::{Contact, ContactId};
 use deltachat::context::Context;
+use deltachat::securejoin::{GroupInvite, JoinRequest};
 use num_traits::cast::ToPrimitive;
 use serde::{Deserialize, Serialize};
 use typescript_type_def::TypeDef;
 
 use super::color_int_to_hex_string;
-use super::contact::ContactObject;
+use super::contact::{ContactObject, JsonrpcEncryptPreference};
 
 #[derive(Serialize, TypeDef, schemars::JsonSchema)]
 #[serde(rename_all = "camelCase")]
@@ -55,6 +59,24 @@ pub struct FullChat {
     can_send: bool,
     was_seen_recently: bool,
     mailing_list_address: Option<String>,
+
+    /// Where replies in a mailing list chat are sent to.
+    mailinglist_reply_mode: JsonrpcMailinglistReplyMode,
+
+    /// True if the chat is a mailing list that can be unsubscribed from.
+    can_unsubscribe: bool,
+
+    /// True if read receipts are not requested for outgoing messages.
+    is_mdn_requests_disabled: bool,
+
+    /// True if outgoing messages must be end-to-end encrypted.
+    is_encryption_required: bool,
+
+    /// True if only admins can change members, name and image of the group.
+    is_moderated: bool,
+
+    /// Contact IDs of the group admins.
+    admin_ids: Vec<u32>,
 }
 
 impl FullChat {
@@ -64,6 +86,7 @@ pub async fn try_from_dc_chat_id(context: &Context, chat_id: u32) -> Result<Self
 
         let contact_ids = get_chat_contacts(context, rust_chat_id).await?;
         let past_contact_ids = get_past_chat_contacts(context, rust_chat_id).await?;
+        let admin_ids = chat::get_group_admins(context, rust_chat_id).await?;
 
         let mut contacts = Vec::with_capacity(contact_ids.len());
 
@@ -128,6 +151,12 @@ pub async fn try_from_dc_chat_id(context: &Context, chat_id: u32) -> Result<Self
             can_send,
             was_seen_recently,
             mailing_list_address,
+            mailinglist_reply_mode: chat.get_mailinglist_reply_mode().into(),
+            can_unsubscribe: chat.can_unsubscribe(),
+            is_mdn_requests_disabled: chat.is_mdn_requests_disabled(),
+            is_encryption_required: chat.is_encryption_required(),
+            is_moderated: chat.is_moderated(),
+            admin_ids: admin_ids.iter().map(|id| id.to_u32()).collect(),
         })
     }
 }
@@ -228,6 +257,106 @@ pub fn try_into_core_type(self) -> Result<chat::MuteDuration> {
     }
 }
 
+/// Where replies in a mailing list chat are sent to.
+#[derive(Clone, Serialize, Deserialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "MailinglistReplyMode")]
+pub enum JsonrpcMailinglistReplyMode {
+    /// Send all messages to the `List-Post` address.
+    List,
+    /// Send replies quoting a message privately to its sender.
+    Sender,
+}
+
+impl JsonrpcMailinglistReplyMode {
+    pub fn into_core_type(self) -> chat::MailinglistReplyMode {
+        match self {
+            JsonrpcMailinglistReplyMode::List => chat::MailinglistReplyMode::List,
+            JsonrpcMailinglistReplyMode::Sender => chat::MailinglistReplyMode::Sender,
+        }
+    }
+}
+
+impl From<chat::MailinglistReplyMode> for JsonrpcMailinglistReplyMode {
+    fn from(mode: chat::MailinglistReplyMode) -> Self {
+        match mode {
+            chat::MailinglistReplyMode::List => JsonrpcMailinglistReplyMode::List,
+            chat::MailinglistReplyMode::Sender => JsonrpcMailinglistReplyMode::Sender,
+        }
+    }
+}
+
+/// Whether an archived chat is unarchived by new messages.
+#[derive(Clone, Serialize, Deserialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "UnarchivePolicy")]
+pub enum JsonrpcUnarchivePolicy {
+    /// Unarchive the chat on any new message unless the chat is muted.
+    AnyMessage,
+    /// Unarchive the chat only on incoming messages mentioning self.
+    Mention,
+    /// Keep the chat archived.
+    Never,
+}
+
+impl JsonrpcUnarchivePolicy {
+    pub fn into_core_type(self) -> chat::UnarchivePolicy {
+        match self {
+            JsonrpcUnarchivePolicy::AnyMessage => chat::UnarchivePolicy::AnyMessage,
+            JsonrpcUnarchivePolicy::Mention => chat::UnarchivePolicy::Mention,
+            JsonrpcUnarchivePolicy::Never => chat::UnarchivePolicy::Never,
+        }
+    }
+}
+
+impl From<chat::UnarchivePolicy> for JsonrpcUnarchivePolicy {
+    fn from(policy: chat::UnarchivePolicy) -> Self {
+        match policy {
+            chat::UnarchivePolicy::AnyMessage => JsonrpcUnarchivePolicy::AnyMessage,
+            chat::UnarchivePolicy::Mention => JsonrpcUnarchivePolicy::Mention,
+            chat::UnarchivePolicy::Never => JsonrpcUnarchivePolicy::Never,
+        }
+    }
+}
+
+/// Local retention policy of a chat.
+#[derive(Clone, Serialize, Deserialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "RetentionPolicy", tag = "kind")]
+pub enum JsonrpcRetentionPolicy {
+    /// Keep all messages.
+    KeepAll,
+    /// Keep only the given number of most recent messages.
+    KeepLastMessages { count: u32 },
+    /// Keep only messages of the given number of last days.
+    KeepLastDays { days: u32 },
+}
+
+impl JsonrpcRetentionPolicy {
+    pub fn into_core_type(self) -> chat::RetentionPolicy {
+        match self {
+            JsonrpcRetentionPolicy::KeepAll => chat::RetentionPolicy::KeepAll,
+            JsonrpcRetentionPolicy::KeepLastMessages { count } => {
+                chat::RetentionPolicy::KeepLastMessages(count)
+            }
+            JsonrpcRetentionPolicy::KeepLastDays { days } => {
+                chat::RetentionPolicy::KeepLastDays(days)
+            }
+        }
+    }
+}
+
+impl From<chat::RetentionPolicy> for JsonrpcRetentionPolicy {
+    fn from(policy: chat::RetentionPolicy) -> Self {
+        match policy {
+            chat::RetentionPolicy::KeepAll => JsonrpcRetentionPolicy::KeepAll,
+            chat::RetentionPolicy::KeepLastMessages(count) => {
+                JsonrpcRetentionPolicy::KeepLastMessages { count }
+            }
+            chat::RetentionPolicy::KeepLastDays(days) => {
+                JsonrpcRetentionPolicy::KeepLastDays { days }
+            }
+        }
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize, TypeDef, schemars::JsonSchema)]
 #[serde(rename = "ChatVisibility")]
 pub enum JSONRPCChatVisibility {
@@ -245,3 +374,226 @@ pub fn into_core_type(self) -> ChatVisibility {
         }
     }
 }
+
+/// Request to join a group waiting for approval of the inviter.
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "JoinRequest", rename_all = "camelCase")]
+pub struct JsonrpcJoinRequest {
+    /// ID of the contact that wants to join.
+    contact_id: u32,
+    /// ID of the group chat the contact wants to join.
+    chat_id: u32,
+    /// Timestamp of the request.
+    timestamp: i64,
+}
+
+impl From<JoinRequest> for JsonrpcJoinRequest {
+    fn from(request: JoinRequest) -> Self {
+        Self {
+            contact_id: request.contact_id.to_u32(),
+            chat_id: request.chat_id.to_u32(),
+            timestamp: request.timestamp,
+        }
+    }
+}
+
+/// Delivery statistics of a message sent to a broadcast list.
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "BroadcastMsgStats", rename_all = "camelCase")]
+pub struct JsonrpcBroadcastMsgStats {
+    /// ID of the message.
+    msg_id: u32,
+    /// Sending time of the message.
+    timestamp: i64,
+    /// Number of recipients the message was sent to.
+    recipients: u32,
+    /// Number of recipients the message was delivered to, including the readers.
+    delivered: u32,
+    /// Number of recipients that sent a read receipt.
+    read: u32,
+    /// Number of recipients whose servers rejected the message.
+    failed: u32,
+    /// Delivery state of the message for each member of the broadcast list
+    /// at the time the message was sent.
+    recipient_stats: Vec<JsonrpcBroadcastRecipientStats>,
+}
+
+/// Delivery state of a broadcast list message for a single recipient.
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "BroadcastRecipientState")]
+pub enum JsonrpcBroadcastRecipientState {
+    /// The message is not sent yet.
+    Pending,
+    /// The message was sent to the recipient.
+    Delivered,
+    /// The recipient sent a read receipt.
+    Read,
+    /// The server of the recipient rejected the message.
+    Failed,
+}
+
+impl From<BroadcastRecipientState> for JsonrpcBroadcastRecipientState {
+    fn from(state: BroadcastRecipientState) -> Self {
+        match state {
+            BroadcastRecipientState::Pending => Self::Pending,
+            BroadcastRecipientState::Delivered => Self::Delivered,
+            BroadcastRecipientState::Read => Self::Read,
+            BroadcastRecipientState::Failed => Self::Failed,
+        }
+    }
+}
+
+/// Delivery statistics of a broadcast list message for a single recipient.
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "BroadcastRecipientStats", rename_all = "camelCase")]
+pub struct JsonrpcBroadcastRecipientStats {
+    /// ID of the recipient.
+    contact_id: u32,
+    state: JsonrpcBroadcastRecipientState,
+}
+
+impl From<BroadcastRecipientStats> for JsonrpcBroadcastRecipientStats {
+    fn from(stats: BroadcastRecipientStats) -> Self {
+        Self {
+            contact_id: stats.contact_id.to_u32(),
+            state: stats.state.into(),
+        }
+    }
+}
+
+impl From<BroadcastMsgStats> for JsonrpcBroadcastMsgStats {
+    fn from(stats: BroadcastMsgStats) -> Self {
+        Self {
+            msg_id: stats.msg_id.to_u32(),
+            timestamp: stats.timestamp,
+            recipients: stats.recipients,
+            delivered: stats.delivered,
+            read: stats.read,
+            failed: stats.failed,
+            recipient_stats: stats.recipient_stats.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// Group invite with an expiration time and/or a use limit.
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "GroupInvite", rename_all = "camelCase")]
+pub struct JsonrpcGroupInvite {
+    /// ID of the invite, used to revoke it.
+    id: u32,
+    /// Timestamp of the invite creation.
+    timestamp: i64,
+    /// Timestamp after which the invite is not accepted anymore.
+    expires: Option<i64>,
+    /// Number of contacts that may join using the invite.
+    max_uses: Option<u32>,
+    /// Number of contacts that joined using the invite so far.
+    uses: u32,
+}
+
+impl From<GroupInvite> for JsonrpcGroupInvite {
+    fn from(invite: GroupInvite) -> Self {
+        Self {
+            id: invite.id,
+            timestamp: invite.timestamp,
+            expires: invite.expires,
+            max_uses: invite.max_uses,
+            uses: invite.uses,
+        }
+    }
+}
+
+/// Encryption state of a chat member.
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "MemberEncryptionInfo", rename_all = "camelCase")]
+pub struct JsonrpcMemberEncryptionInfo {
+    contact_id: u32,
+    addr: String,
+    /// True if a key of the member is known.
+    key_available: bool,
+    /// True if the member is verified.
+    verified: bool,
+    /// Encryption preference announced by the member, `Reset` if no key is known.
+    prefer_encrypt: JsonrpcEncryptPreference,
+    /// True if the user has forced plaintext for the member.
+    force_plaintext: bool,
+}
+
+/// Encryption state of a chat.
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "ChatEncryptionInfo", rename_all = "camelCase")]
+pub struct JsonrpcChatEncryptionInfo {
+    /// Encryption state of the chat members except self.
+    members: Vec<JsonrpcMemberEncryptionInfo>,
+    /// True if messages sent to the chat are end-to-end encrypted.
+    can_encrypt: bool,
+}
+
+impl From<ChatEncryptionInfo> for JsonrpcChatEncryptionInfo {
+    fn from(info: ChatEncryptionInfo) -> Self {
+        Self {
+            members: info
+                .members
+                .into_iter()
+                .map(|member| JsonrpcMemberEncryptionInfo {
+                    contact_id: member.contact_id.to_u32(),
+                    addr: member.addr,
+                    key_available: member.key_available,
+                    verified: member.verified,
+                    prefer_encrypt: member.prefer_encrypt.into(),
+                    force_plaintext: member.force_plaintext,
+                })
+                .collect(),
+            can_encrypt: info.can_encrypt,
+        }
+    }
+}
+
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "ProtectionLogEvent")]
+pub enum JsonrpcProtectionLogEvent {
+    ProtectionEnabled,
+    ProtectionDisabled,
+    ProtectionBroken,
+    MemberAdded,
+    MemberRemoved,
+    MemberVerified,
+}
+
+impl From<ProtectionLogEvent> for JsonrpcProtectionLogEvent {
+    fn from(event: ProtectionLogEvent) -> Self {
+        match event {
+            ProtectionLogEvent::ProtectionEnabled => Self::ProtectionEnabled,
+            ProtectionLogEvent::ProtectionDisabled => Self::ProtectionDisabled,
+            ProtectionLogEvent::ProtectionBroken => Self::ProtectionBroken,
+            ProtectionLogEvent::MemberAdded => Self::MemberAdded,
+            ProtectionLogEvent::MemberRemoved => Self::MemberRemoved,
+            ProtectionLogEvent::MemberVerified => Self::MemberVerified,
+        }
+    }
+}
+
+/// Entry in the protection log of a chat.
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "ProtectionLogEntry", rename_all = "camelCase")]
+pub struct JsonrpcProtectionLogEntry {
+    /// Timestamp of the change.
+    timestamp: i64,
+    /// Kind of the change.
+    event: JsonrpcProtectionLogEvent,
+    /// ID of the contact which caused the change.
+    contact_id: u32,
+    /// ID of the contact affected by the change, if any.
+    target_id: Option<u32>,
+}
+
+impl From<ProtectionLogEntry> for JsonrpcProtectionLogEntry {
+    fn from(entry: ProtectionLogEntry) -> Self {
+        Self {
+            timestamp: entry.timestamp,
+            event: entry.event.into(),
+            contact_id: entry.contact_id.to_u32(),
+            target_id: entry.target_id.map(|id| id.to_u32()),
+        }
+    }
+}
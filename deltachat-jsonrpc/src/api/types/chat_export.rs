@@ -0,0 +1,57 @@
+use deltachat::imex::{ChatExportSignature, ChatExportVerification};
+use serde::Serialize;
+use typescript_type_def::TypeDef;
+
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "ChatExportSignature", tag = "kind")]
+pub enum JsonrpcChatExportSignature {
+    /// The export is not signed and no expected signer was given.
+    Unsigned,
+    /// The export is signed, but no expected signer was given,
+    /// so the signature was not checked.
+    Unverified,
+    /// The manifest is signed by the expected key with the given fingerprint.
+    Valid { fingerprint: String },
+    /// The signature does not match the manifest, is not made by the expected key
+    /// or is missing although an expected signer was given.
+    Invalid,
+}
+
+impl From<ChatExportSignature> for JsonrpcChatExportSignature {
+    fn from(signature: ChatExportSignature) -> Self {
+        match signature {
+            ChatExportSignature::Unsigned => JsonrpcChatExportSignature::Unsigned,
+            ChatExportSignature::Unverified => JsonrpcChatExportSignature::Unverified,
+            ChatExportSignature::Valid(fingerprint) => JsonrpcChatExportSignature::Valid {
+                fingerprint: fingerprint.hex(),
+            },
+            ChatExportSignature::Invalid => JsonrpcChatExportSignature::Invalid,
+        }
+    }
+}
+
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "ChatExportVerification", rename_all = "camelCase")]
+pub struct JsonrpcChatExportVerification {
+    /// True if all files match the manifest and the signature, if any, is valid.
+    is_intact: bool,
+    /// Files whose content does not match the manifest.
+    modified: Vec<String>,
+    /// Files listed in the manifest which do not exist.
+    missing: Vec<String>,
+    /// Files which are not listed in the manifest.
+    unlisted: Vec<String>,
+    signature: JsonrpcChatExportSignature,
+}
+
+impl From<ChatExportVerification> for JsonrpcChatExportVerification {
+    fn from(verification: ChatExportVerification) -> Self {
+        Self {
+            is_intact: verification.is_intact(),
+            modified: verification.modified,
+            missing: verification.missing,
+            unlisted: verification.unlisted,
+            signature: verification.signature.into(),
+        }
+    }
+}
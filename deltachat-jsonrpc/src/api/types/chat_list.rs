@@ -1,8 +1,10 @@
 use anyhow::{Context, Result};
+use deltachat::accounts::CombinedChatlistEntry;
 use deltachat::chat::{Chat, ChatId};
-use deltachat::chatlist::get_last_message_for_chat;
+use deltachat::chatlist::{get_last_message_for_chat, ChatlistItemKind, ChatlistSections};
 use deltachat::constants::*;
 use deltachat::contact::{Contact, ContactId};
+use deltachat::summary::{Summary, SummaryPrefix};
 use deltachat::{
     chat::{get_chat_contacts, ChatVisibility},
     chatlist::Chatlist,
@@ -39,6 +41,8 @@ pub enum ChatListItemFetchResult {
         is_archived: bool,
         is_pinned: bool,
         is_muted: bool,
+        /// true if the chat was marked as unread by the user
+        is_marked_unread: bool,
         is_contact_request: bool,
         /// true when chat is a broadcastlist
         is_broadcast: bool,
@@ -146,6 +150,7 @@ pub(crate) async fn get_chat_list_item_by_id(
         is_archived: visibility == ChatVisibility::Archived,
         is_pinned: visibility == ChatVisibility::Pinned,
         is_muted: chat.is_muted(),
+        is_marked_unread: chat_id.is_marked_unread(ctx).await?,
         is_contact_request: chat.is_contact_request(),
         is_broadcast: chat.get_type() == Chattype::Broadcast,
         dm_chat_contact,
@@ -154,3 +159,192 @@ pub(crate) async fn get_chat_list_item_by_id(
         last_message_id: last_msgid.map(|id| id.to_u32()),
     })
 }
+
+/// Structured summary of a chatlist item,
+/// so that UIs don't have to parse `summaryText1` and `summaryText2`.
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "ChatListSummary", rename_all = "camelCase")]
+pub struct ChatListSummary {
+    /// Part displayed before ":", such as the sender name or "Draft".
+    prefix: Option<ChatListSummaryPrefix>,
+    /// Text preview of the last message or a description of the last reaction.
+    text: String,
+    /// Timestamp of the last message in seconds.
+    timestamp: i64,
+    /// State of the last message.
+    state: u32,
+    /// Preview image path if the last message is an image.
+    preview_image: Option<String>,
+    /// Viewtype of the last message, may be used to show an icon.
+    viewtype: MessageViewtype,
+    /// Set if a reaction is newer than the last message.
+    reaction: Option<ChatListSummaryReaction>,
+}
+
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(tag = "kind")]
+pub enum ChatListSummaryPrefix {
+    /// Display name of the sender in groups and mailing lists.
+    #[serde(rename_all = "camelCase")]
+    Username { name: String },
+    /// Stock string saying "Draft".
+    #[serde(rename_all = "camelCase")]
+    Draft { text: String },
+    /// Stock string saying "Me".
+    #[serde(rename_all = "camelCase")]
+    Me { text: String },
+}
+
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatListSummaryReaction {
+    contact_id: u32,
+    contact_name: String,
+    emoji: String,
+    /// Summary text of the message reacted to.
+    message_text: String,
+}
+
+impl From<SummaryPrefix> for ChatListSummaryPrefix {
+    fn from(prefix: SummaryPrefix) -> Self {
+        match prefix {
+            SummaryPrefix::Username(name) => ChatListSummaryPrefix::Username { name },
+            SummaryPrefix::Draft(text) => ChatListSummaryPrefix::Draft { text },
+            SummaryPrefix::Me(text) => ChatListSummaryPrefix::Me { text },
+        }
+    }
+}
+
+impl ChatListSummary {
+    pub async fn from_summary(ctx: &deltachat::context::Context, summary: Summary) -> Result<Self> {
+        let reaction = match summary.reaction {
+            Some(reaction) => {
+                let contact = Contact::get_by_id(ctx, reaction.contact_id).await?;
+                Some(ChatListSummaryReaction {
+                    contact_id: reaction.contact_id.to_u32(),
+                    contact_name: contact.get_display_name().to_owned(),
+                    emoji: reaction.emoji,
+                    message_text: reaction.msg_text,
+                })
+            }
+            None => None,
+        };
+        Ok(ChatListSummary {
+            prefix: summary.prefix.map(Into::into),
+            text: summary.text,
+            timestamp: summary.timestamp,
+            state: summary.state.to_u32().unwrap_or_default(),
+            preview_image: summary.thumbnail_path,
+            viewtype: summary.viewtype.into(),
+            reaction,
+        })
+    }
+}
+
+pub(crate) async fn get_chat_list_summary_by_id(
+    ctx: &deltachat::context::Context,
+    chat_id: u32,
+) -> Result<ChatListSummary> {
+    let chat_id = ChatId::new(chat_id);
+    let last_msgid = get_last_message_for_chat(ctx, chat_id).await?;
+    let summary = Chatlist::get_summary2(ctx, chat_id, last_msgid, None)
+        .await
+        .context("summary")?;
+    ChatListSummary::from_summary(ctx, summary).await
+}
+
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "CombinedChatListEntry", rename_all = "camelCase")]
+pub struct JsonrpcCombinedChatlistEntry {
+    account_id: u32,
+    chat_id: u32,
+    last_message_id: Option<u32>,
+    /// Timestamp of the last message or of the chat creation if the chat is empty.
+    timestamp: i64,
+}
+
+impl From<CombinedChatlistEntry> for JsonrpcCombinedChatlistEntry {
+    fn from(entry: CombinedChatlistEntry) -> Self {
+        Self {
+            account_id: entry.account_id,
+            chat_id: entry.chat_id.to_u32(),
+            last_message_id: entry.msg_id.map(|id| id.to_u32()),
+            timestamp: entry.timestamp,
+        }
+    }
+}
+
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "ChatListItemKind")]
+pub enum JsonrpcChatlistItemKind {
+    Pinned,
+    ContactRequest,
+    Archived,
+    Chat,
+    /// Link to the archived chats, displayed as a summary row.
+    ArchiveLink,
+    /// Hint that all chats are archived.
+    AllDoneHint,
+}
+
+impl From<ChatlistItemKind> for JsonrpcChatlistItemKind {
+    fn from(kind: ChatlistItemKind) -> Self {
+        match kind {
+            ChatlistItemKind::Pinned => JsonrpcChatlistItemKind::Pinned,
+            ChatlistItemKind::ContactRequest => JsonrpcChatlistItemKind::ContactRequest,
+            ChatlistItemKind::Archived => JsonrpcChatlistItemKind::Archived,
+            ChatlistItemKind::Chat => JsonrpcChatlistItemKind::Chat,
+            ChatlistItemKind::ArchiveLink => JsonrpcChatlistItemKind::ArchiveLink,
+            ChatlistItemKind::AllDoneHint => JsonrpcChatlistItemKind::AllDoneHint,
+        }
+    }
+}
+
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "ChatListEntry", rename_all = "camelCase")]
+pub struct JsonrpcChatlistEntry {
+    chat_id: u32,
+    kind: JsonrpcChatlistItemKind,
+}
+
+/// Chatlist with section metadata,
+/// so that UIs don't have to check for special chat IDs.
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "ChatListWithSections", rename_all = "camelCase")]
+pub struct JsonrpcChatlistWithSections {
+    entries: Vec<JsonrpcChatlistEntry>,
+    /// Number of pinned chats.
+    pinned_count: u32,
+    /// Index of the first pinned chat, pinned chats are always sorted to the top.
+    first_pinned_index: Option<u32>,
+    /// Number of contact requests in the list.
+    requests_count: u32,
+    /// Index of the archive link if it is contained in the list.
+    archive_link_index: Option<u32>,
+    /// Number of archived chats, to be displayed in the archive link row.
+    archived_count: u32,
+    /// Number of fresh messages in archived chats.
+    archived_fresh_count: u32,
+}
+
+impl JsonrpcChatlistWithSections {
+    pub fn new(list: &Chatlist, sections: ChatlistSections) -> Self {
+        let entries = list
+            .iter()
+            .zip(sections.kinds)
+            .map(|((chat_id, _msg_id), kind)| JsonrpcChatlistEntry {
+                chat_id: chat_id.to_u32(),
+                kind: kind.into(),
+            })
+            .collect();
+        Self {
+            entries,
+            pinned_count: sections.pinned_count as u32,
+            first_pinned_index: sections.first_pinned_index.map(|i| i as u32),
+            requests_count: sections.requests_count as u32,
+            archive_link_index: sections.archive_link_index.map(|i| i as u32),
+            archived_count: sections.archived_count as u32,
+            archived_fresh_count: sections.archived_fresh_count as u32,
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use anyhow::Result;
 use deltachat::color;
+use deltachat::contact::{EncryptPreference, EncryptionOverride, KeyInfo, KeyOrigin};
 use deltachat::context::Context;
 use serde::Serialize;
 use typescript_type_def::TypeDef;
@@ -10,6 +11,9 @@
 #[serde(rename = "Contact", rename_all = "camelCase")]
 pub struct ContactObject {
     address: String,
+    /// Address for display, with internationalized domain in Unicode
+    /// unless `display_punycode` config is set.
+    display_address: String,
     color: String,
     auth_name: String,
     status: String,
@@ -17,6 +21,9 @@ pub struct ContactObject {
     id: u32,
     name: String,
     profile_image: Option<String>, // BLOBS
+
+    /// True if the profile image was not updated for a long time and is likely outdated.
+    is_profile_image_stale: bool,
     name_and_addr: String,
     is_blocked: bool,
     e2ee_avail: bool,
@@ -50,6 +57,10 @@ pub struct ContactObject {
 
     /// If the contact is a bot.
     is_bot: bool,
+
+    /// True if messages to the contact are never encrypted,
+    /// see `setContactForcePlaintext()`.
+    force_plaintext: bool,
 }
 
 impl ContactObject {
@@ -71,6 +82,7 @@ pub async fn try_from_dc_contact(
 
         Ok(ContactObject {
             address: contact.get_addr().to_owned(),
+            display_address: contact.get_display_addr(context).await?,
             color: color_int_to_hex_string(contact.get_color()),
             auth_name: contact.get_authname().to_owned(),
             status: contact.get_status().to_owned(),
@@ -78,6 +90,7 @@ pub async fn try_from_dc_contact(
             id: contact.id.to_u32(),
             name: contact.get_name().to_owned(),
             profile_image, //BLOBS
+            is_profile_image_stale: contact.is_profile_image_stale(),
             name_and_addr: contact.get_name_n_addr(),
             is_blocked: contact.is_blocked(),
             e2ee_avail: contact.e2ee_avail(context).await?,
@@ -87,6 +100,8 @@ pub async fn try_from_dc_contact(
             last_seen: contact.last_seen(),
             was_seen_recently: contact.was_seen_recently(),
             is_bot: contact.is_bot(),
+            force_plaintext: contact.get_encryption_override()
+                == EncryptionOverride::ForcePlaintext,
         })
     }
 }
@@ -122,3 +137,116 @@ fn from(vc: deltachat_contact_tools::VcardContact) -> Self {
         }
     }
 }
+
+/// Local contact label such as "Family" or "Work".
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ContactLabel {
+    id: u32,
+    name: String,
+}
+
+impl From<deltachat::contact_label::Label> for ContactLabel {
+    fn from(label: deltachat::contact_label::Label) -> Self {
+        Self {
+            id: label.get_id().to_u32(),
+            name: label.get_name().to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "KeyOrigin")]
+pub enum JsonrpcKeyOrigin {
+    /// The key was received from the contact in the `Autocrypt` header.
+    Autocrypt,
+    /// The key was gossiped by another member of a group.
+    Gossip,
+}
+
+impl From<KeyOrigin> for JsonrpcKeyOrigin {
+    fn from(origin: KeyOrigin) -> Self {
+        match origin {
+            KeyOrigin::Autocrypt => JsonrpcKeyOrigin::Autocrypt,
+            KeyOrigin::Gossip => JsonrpcKeyOrigin::Gossip,
+        }
+    }
+}
+
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "EncryptPreference")]
+pub enum JsonrpcEncryptPreference {
+    NoPreference,
+    Mutual,
+    Reset,
+}
+
+impl From<EncryptPreference> for JsonrpcEncryptPreference {
+    fn from(prefer_encrypt: EncryptPreference) -> Self {
+        match prefer_encrypt {
+            EncryptPreference::NoPreference => JsonrpcEncryptPreference::NoPreference,
+            EncryptPreference::Mutual => JsonrpcEncryptPreference::Mutual,
+            EncryptPreference::Reset => JsonrpcEncryptPreference::Reset,
+        }
+    }
+}
+
+/// Information about the key of a contact.
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "KeyInfo", rename_all = "camelCase")]
+pub struct JsonrpcKeyInfo {
+    /// Hex-encoded fingerprint of the key without spaces.
+    fingerprint: String,
+    /// Timestamp of the key creation.
+    created_timestamp: i64,
+    /// Algorithm of the primary key, e.g. `EdDSA` or `RSA`.
+    algorithm: String,
+    /// Algorithm of the encryption subkey, if the key has one.
+    encryption_algorithm: Option<String>,
+    /// Where the key was received from.
+    origin: JsonrpcKeyOrigin,
+    /// Timestamp of the latest reception of the key from its origin.
+    origin_timestamp: i64,
+    /// Encryption preference announced by the contact.
+    prefer_encrypt: JsonrpcEncryptPreference,
+    /// True if the key is verified.
+    verified: bool,
+    /// Address of the contact that introduced the verified key, if known.
+    verifier_addr: Option<String>,
+}
+
+impl From<KeyInfo> for JsonrpcKeyInfo {
+    fn from(key_info: KeyInfo) -> Self {
+        Self {
+            fingerprint: key_info.fingerprint,
+            created_timestamp: key_info.created_timestamp,
+            algorithm: key_info.algorithm,
+            encryption_algorithm: key_info.encryption_algorithm,
+            origin: key_info.origin.into(),
+            origin_timestamp: key_info.origin_timestamp,
+            prefer_encrypt: key_info.prefer_encrypt.into(),
+            verified: key_info.verified,
+            verifier_addr: key_info.verifier_addr,
+        }
+    }
+}
+
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "MentionableMember", rename_all = "camelCase")]
+pub struct JsonrpcMentionableMember {
+    contact_id: u32,
+    display_name: String,
+    profile_image: Option<String>, // BLOBS
+}
+
+impl From<deltachat::chat::MentionableMember> for JsonrpcMentionableMember {
+    fn from(member: deltachat::chat::MentionableMember) -> Self {
+        Self {
+            contact_id: member.contact_id.to_u32(),
+            display_name: member.display_name,
+            profile_image: member
+                .profile_image
+                .and_then(|path| path.to_str().map(|s| s.to_owned())),
+        }
+    }
+}
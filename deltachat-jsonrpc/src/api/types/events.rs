@@ -1,4 +1,6 @@
-use deltachat::{Event as CoreEvent, EventType as CoreEventType};
+use deltachat::{
+    ConfigureTraceEntry as CoreConfigureTraceEntry, Event as CoreEvent, EventType as CoreEventType,
+};
 use serde::Serialize;
 use typescript_type_def::TypeDef;
 
@@ -101,6 +103,7 @@ pub enum EventType {
     /// Incoming reaction, should be notified.
     #[serde(rename_all = "camelCase")]
     IncomingReaction {
+        chat_id: u32,
         contact_id: u32,
         msg_id: u32,
         reaction: String,
@@ -116,6 +119,21 @@ pub enum EventType {
         href: Option<String>,
     },
 
+    /// An incoming message has a public key of the sender attached
+    /// which differs from the key currently used for the sender.
+    ///
+    /// The UI may show both fingerprints and offer importKeyFromMsg().
+    #[serde(rename_all = "camelCase")]
+    IncomingPublicKey {
+        chat_id: u32,
+        msg_id: u32,
+        contact_id: u32,
+        /// Fingerprint of the attached key, hex-encoded.
+        fingerprint: String,
+        /// Fingerprint of the key currently used for the sender, if any.
+        current_fingerprint: Option<String>,
+    },
+
     /// There is a fresh message. Typically, the user will show an notification
     /// when receiving this message.
     ///
@@ -123,6 +141,19 @@ pub enum EventType {
     #[serde(rename_all = "camelCase")]
     IncomingMsg { chat_id: u32, msg_id: u32 },
 
+    /// An incoming message starts with a command registered with `registerBotCommand()`.
+    ///
+    /// Emitted in addition to `IncomingMsg`.
+    #[serde(rename_all = "camelCase")]
+    BotCommand {
+        chat_id: u32,
+        msg_id: u32,
+        /// The command in lowercase, e.g. `/help`.
+        command: String,
+        /// Text following the command.
+        args: String,
+    },
+
     /// Downloading a bunch of messages just finished. This is an
     /// event to allow the UI to only show one notification per message bunch,
     /// instead of cluttering the user with many notifications.
@@ -142,7 +173,13 @@ pub enum EventType {
     /// A single message could not be sent. State changed from DC_STATE_OUT_PENDING or DC_STATE_OUT_DELIVERED to
     /// DC_STATE_OUT_FAILED, see `Message.state`.
     #[serde(rename_all = "camelCase")]
-    MsgFailed { chat_id: u32, msg_id: u32 },
+    MsgFailed {
+        chat_id: u32,
+        msg_id: u32,
+        /// Reason reported by a delivery status notification,
+        /// one of `mailbox_full`, `unknown_recipient`, `policy` or `other`.
+        reason: Option<String>,
+    },
 
     /// A single message is read by the receiver. State changed from DC_STATE_OUT_DELIVERED to
     /// DC_STATE_OUT_MDN_RCVD, see `Message.state`.
@@ -167,6 +204,13 @@ pub enum EventType {
     #[serde(rename_all = "camelCase")]
     ChatEphemeralTimerModified { chat_id: u32, timer: u32 },
 
+    /// The key of a member of a protected group changed since the member was verified.
+    ///
+    /// The member should be verified again and then added back
+    /// with `repair_verified_group_member`.
+    #[serde(rename_all = "camelCase")]
+    VerifiedGroupMemberKeyChanged { chat_id: u32, contact_id: u32 },
+
     /// Contact(s) created, renamed, blocked or deleted.
     ///
     /// @param data1 (int) If set, this is the contact_id of an added contact that should be selected.
@@ -192,6 +236,18 @@ pub enum EventType {
         comment: Option<String>,
     },
 
+    /// Configuration started by configure() has failed.
+    ///
+    /// Emitted in addition to `ConfigureProgress` with progress 0.
+    ConfigureFailed {
+        /// Error message.
+        error: String,
+
+        /// Steps tried during the configuration,
+        /// same as returned by `get_last_configure_trace()`.
+        trace: Vec<ConfigureTraceEntry>,
+    },
+
     /// Inform about the import/export progress started by imex().
     ///
     /// @param data1 (usize) 0=error, 1-999=progress in permille, 1000=success and done
@@ -199,6 +255,47 @@ pub enum EventType {
     #[serde(rename_all = "camelCase")]
     ImexProgress { progress: usize },
 
+    /// Inform about the number of bytes transferred
+    /// while sending or receiving a backup.
+    ///
+    /// If an interrupted transfer is resumed, `transferred` starts at the resumption point.
+    #[serde(rename_all = "camelCase")]
+    BackupTransferProgress {
+        /// Number of bytes transferred so far.
+        transferred: u64,
+        /// Total size of the backup in bytes.
+        total: u64,
+    },
+
+    /// Inform about the progress of fetching messages from the server,
+    /// e.g. fetching existing messages after adding an account with a big mailbox.
+    ///
+    /// Use `get_sync_state()` to get the state of all folders.
+    #[serde(rename_all = "camelCase")]
+    SyncProgress {
+        /// Name of the folder messages are fetched from.
+        folder: String,
+        /// Number of messages fetched so far.
+        fetched: usize,
+        /// Number of messages to fetch.
+        total: usize,
+    },
+
+    /// The first full sync after starting I/O is done.
+    ///
+    /// Emitted once per start of I/O,
+    /// UIs may use it to show that the account is up to date.
+    /// Use `get_sync_report()` to get the changes per folder.
+    #[serde(rename_all = "camelCase")]
+    SyncReport {
+        /// Number of new messages.
+        new_msgs: usize,
+        /// Number of messages deleted from the server by other clients.
+        expunged: usize,
+        /// Number of messages marked as seen by other clients.
+        flags_changed: usize,
+    },
+
     /// A file has been exported. A file has been written by imex().
     /// This event may be sent multiple times by a single call to imex().
     ///
@@ -235,6 +332,23 @@ pub enum EventType {
     #[serde(rename_all = "camelCase")]
     SecurejoinJoinerProgress { contact_id: u32, progress: usize },
 
+    /// A joiner scanned a one-time group invite QR code
+    /// generated by getChatSecurejoinQrCodeWithApproval()
+    /// and waits for approval of the inviter.
+    ///
+    /// The joiner is added to the group after approveJoinRequest() is called.
+    #[serde(rename_all = "camelCase")]
+    SecurejoinApprovalRequest {
+        /// ID of the contact that wants to join.
+        contact_id: u32,
+        /// ID of the group chat the contact wants to join.
+        chat_id: u32,
+        /// Fingerprint of the joiner's key.
+        fingerprint: String,
+        /// Display name of the joiner.
+        display_name: String,
+    },
+
     /// The connectivity to the server changed.
     /// This means that you should refresh the connectivity view
     /// and possibly the connectivtiy HTML; see getConnectivity() and
@@ -267,6 +381,57 @@ pub enum EventType {
     #[serde(rename_all = "camelCase")]
     WebxdcRealtimeAdvertisementReceived { msg_id: u32 },
 
+    /// Translation of a message is requested.
+    ///
+    /// The UI should translate the text with the given provider
+    /// and call setMsgTranslation() with the result.
+    #[serde(rename_all = "camelCase")]
+    TranslationRequest {
+        msg_id: u32,
+        /// Language to translate the message to.
+        target_lang: String,
+        /// ID of the translation provider, defined by the UI.
+        provider: String,
+        /// Text to translate.
+        text: String,
+    },
+
+    /// Text recognition in the image attachment of a message is requested.
+    ///
+    /// The UI should recognize the text in the image file
+    /// and call setMsgOcrText() with the result.
+    #[serde(rename_all = "camelCase")]
+    OcrRequest {
+        msg_id: u32,
+        /// Path to the image file.
+        path: String,
+    },
+
+    /// A webxdc app requests to send a message on the user's behalf.
+    ///
+    /// The UI should show the text to the user
+    /// and call approveWebxdcSendRequest() or denyWebxdcSendRequest().
+    #[serde(rename_all = "camelCase")]
+    WebxdcSendRequest {
+        /// ID of the webxdc instance.
+        msg_id: u32,
+        /// ID of the request.
+        request_id: u32,
+        /// Text of the message to send.
+        text: String,
+    },
+
+    /// A webxdc app asks to notify the user about an event in the app.
+    ///
+    /// The UI should show a system notification with the text.
+    #[serde(rename_all = "camelCase")]
+    WebxdcNotify {
+        /// ID of the webxdc instance.
+        msg_id: u32,
+        /// Text to notify.
+        text: String,
+    },
+
     /// Inform that a message containing a webxdc instance has been deleted
     #[serde(rename_all = "camelCase")]
     WebxdcInstanceDeleted { msg_id: u32 },
@@ -335,10 +500,12 @@ fn from(event: CoreEventType) -> Self {
                 contact_id: contact_id.to_u32(),
             },
             CoreEventType::IncomingReaction {
+                chat_id,
                 contact_id,
                 msg_id,
                 reaction,
             } => IncomingReaction {
+                chat_id: chat_id.to_u32(),
                 contact_id: contact_id.to_u32(),
                 msg_id: msg_id.to_u32(),
                 reaction: reaction.as_str().to_string(),
@@ -356,10 +523,34 @@ fn from(event: CoreEventType) -> Self {
                 text,
                 href,
             },
+            CoreEventType::IncomingPublicKey {
+                chat_id,
+                msg_id,
+                contact_id,
+                fingerprint,
+                current_fingerprint,
+            } => IncomingPublicKey {
+                chat_id: chat_id.to_u32(),
+                msg_id: msg_id.to_u32(),
+                contact_id: contact_id.to_u32(),
+                fingerprint,
+                current_fingerprint,
+            },
             CoreEventType::IncomingMsg { chat_id, msg_id } => IncomingMsg {
                 chat_id: chat_id.to_u32(),
                 msg_id: msg_id.to_u32(),
             },
+            CoreEventType::BotCommand {
+                chat_id,
+                msg_id,
+                command,
+                args,
+            } => BotCommand {
+                chat_id: chat_id.to_u32(),
+                msg_id: msg_id.to_u32(),
+                command,
+                args,
+            },
             CoreEventType::IncomingMsgBunch => IncomingMsgBunch,
             CoreEventType::MsgsNoticed(chat_id) => MsgsNoticed {
                 chat_id: chat_id.to_u32(),
@@ -368,9 +559,14 @@ fn from(event: CoreEventType) -> Self {
                 chat_id: chat_id.to_u32(),
                 msg_id: msg_id.to_u32(),
             },
-            CoreEventType::MsgFailed { chat_id, msg_id } => MsgFailed {
+            CoreEventType::MsgFailed {
+                chat_id,
+                msg_id,
+                reason,
+            } => MsgFailed {
                 chat_id: chat_id.to_u32(),
                 msg_id: msg_id.to_u32(),
+                reason: reason.map(|reason| reason.to_string()),
             },
             CoreEventType::MsgRead { chat_id, msg_id } => MsgRead {
                 chat_id: chat_id.to_u32(),
@@ -389,6 +585,13 @@ fn from(event: CoreEventType) -> Self {
                     timer: timer.to_u32(),
                 }
             }
+            CoreEventType::VerifiedGroupMemberKeyChanged {
+                chat_id,
+                contact_id,
+            } => VerifiedGroupMemberKeyChanged {
+                chat_id: chat_id.to_u32(),
+                contact_id: contact_id.to_u32(),
+            },
             CoreEventType::ContactsChanged(contact) => ContactsChanged {
                 contact_id: contact.map(|c| c.to_u32()),
             },
@@ -398,7 +601,32 @@ fn from(event: CoreEventType) -> Self {
             CoreEventType::ConfigureProgress { progress, comment } => {
                 ConfigureProgress { progress, comment }
             }
+            CoreEventType::ConfigureFailed { error, trace } => ConfigureFailed {
+                error,
+                trace: trace.into_iter().map(Into::into).collect(),
+            },
             CoreEventType::ImexProgress(progress) => ImexProgress { progress },
+            CoreEventType::BackupTransferProgress { transferred, total } => {
+                BackupTransferProgress { transferred, total }
+            }
+            CoreEventType::SyncProgress {
+                folder,
+                fetched,
+                total,
+            } => SyncProgress {
+                folder,
+                fetched,
+                total,
+            },
+            CoreEventType::SyncReport {
+                new_msgs,
+                expunged,
+                flags_changed,
+            } => SyncReport {
+                new_msgs,
+                expunged,
+                flags_changed,
+            },
             CoreEventType::ImexFileWritten(path) => ImexFileWritten {
                 path: path.to_str().unwrap_or_default().to_owned(),
             },
@@ -416,6 +644,17 @@ fn from(event: CoreEventType) -> Self {
                 contact_id: contact_id.to_u32(),
                 progress,
             },
+            CoreEventType::SecurejoinApprovalRequest {
+                contact_id,
+                chat_id,
+                fingerprint,
+                display_name,
+            } => SecurejoinApprovalRequest {
+                contact_id: contact_id.to_u32(),
+                chat_id: chat_id.to_u32(),
+                fingerprint,
+                display_name,
+            },
             CoreEventType::ConnectivityChanged => ConnectivityChanged,
             CoreEventType::SelfavatarChanged => SelfavatarChanged,
             CoreEventType::ConfigSynced { key } => ConfigSynced {
@@ -437,6 +676,34 @@ fn from(event: CoreEventType) -> Self {
                     msg_id: msg_id.to_u32(),
                 }
             }
+            CoreEventType::WebxdcSendRequest {
+                msg_id,
+                request_id,
+                text,
+            } => WebxdcSendRequest {
+                msg_id: msg_id.to_u32(),
+                request_id,
+                text,
+            },
+            CoreEventType::WebxdcNotify { msg_id, text } => WebxdcNotify {
+                msg_id: msg_id.to_u32(),
+                text,
+            },
+            CoreEventType::TranslationRequest {
+                msg_id,
+                target_lang,
+                provider,
+                text,
+            } => TranslationRequest {
+                msg_id: msg_id.to_u32(),
+                target_lang,
+                provider,
+                text,
+            },
+            CoreEventType::OcrRequest { msg_id, path } => OcrRequest {
+                msg_id: msg_id.to_u32(),
+                path,
+            },
             CoreEventType::WebxdcInstanceDeleted { msg_id } => WebxdcInstanceDeleted {
                 msg_id: msg_id.to_u32(),
             },
@@ -454,3 +721,28 @@ fn from(event: CoreEventType) -> Self {
         }
     }
 }
+
+/// Single step of a configuration attempt.
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigureTraceEntry {
+    /// Timestamp of the step.
+    pub timestamp: i64,
+
+    /// Description of the step, e.g. the autoconfig URL
+    /// or the server and port that was tried.
+    pub step: String,
+
+    /// Error if the step failed, `null` if it succeeded.
+    pub error: Option<String>,
+}
+
+impl From<CoreConfigureTraceEntry> for ConfigureTraceEntry {
+    fn from(entry: CoreConfigureTraceEntry) -> Self {
+        ConfigureTraceEntry {
+            timestamp: entry.timestamp,
+            step: entry.step,
+            error: entry.error,
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use serde::Serialize;
+use typescript_type_def::TypeDef;
+
+/// Result of the key and backup health checks.
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "HealthStatus", rename_all = "camelCase")]
+pub struct JsonrpcHealthStatus {
+    /// Timestamp of the last backup export, if any.
+    last_backup_timestamp: Option<i64>,
+    /// True if the backup reminder is due.
+    backup_overdue: bool,
+    /// Timestamp of the key creation, if known.
+    key_created_timestamp: Option<i64>,
+    /// True if the key is older than the configured age.
+    key_outdated: bool,
+    /// Size of the database file in bytes.
+    db_size: u64,
+    /// True if the database is larger than the configured size.
+    db_size_exceeded: bool,
+}
+
+impl From<deltachat::health::HealthStatus> for JsonrpcHealthStatus {
+    fn from(status: deltachat::health::HealthStatus) -> Self {
+        Self {
+            last_backup_timestamp: status.last_backup_timestamp,
+            backup_overdue: status.backup_overdue,
+            key_created_timestamp: status.key_created_timestamp,
+            key_outdated: status.key_outdated,
+            db_size: status.db_size,
+            db_size_exceeded: status.db_size_exceeded,
+        }
+    }
+}
@@ -1,15 +1,21 @@
+use std::collections::HashMap;
+
 use crate::api::VcardContact;
 use anyhow::{Context as _, Result};
 use deltachat::chat::Chat;
 use deltachat::chat::ChatItem;
 use deltachat::chat::ChatVisibility;
+use deltachat::chat::DayBoundary;
+use deltachat::chat::{TimelineAnchor, TimelineEntry, TimelineItem, TimelineKey};
 use deltachat::contact::Contact;
 use deltachat::context::Context;
 use deltachat::download;
+use deltachat::message;
 use deltachat::message::Message;
 use deltachat::message::MsgId;
 use deltachat::message::Viewtype;
 use deltachat::reaction::get_msg_reactions;
+use deltachat::webxdc;
 use num_traits::cast::ToPrimitive;
 use serde::{Deserialize, Serialize};
 use typescript_type_def::TypeDef;
@@ -26,6 +32,35 @@ pub enum MessageLoadResult {
     LoadingError { error: String },
 }
 
+impl MessageLoadResult {
+    pub async fn load(context: &Context, msg_id: MsgId) -> Self {
+        match MessageObject::from_msg_id(context, msg_id).await {
+            Ok(Some(message)) => MessageLoadResult::Message(message),
+            Ok(None) => MessageLoadResult::LoadingError {
+                error: "Message does not exist".to_string(),
+            },
+            Err(error) => MessageLoadResult::LoadingError {
+                error: format!("{error:#}"),
+            },
+        }
+    }
+}
+
+/// Page of a chat message list, see `message_list_page`.
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageListPage {
+    /// IDs of the messages in the page, oldest first.
+    pub message_ids: Vec<u32>,
+
+    /// Messages in the page by ID.
+    pub messages: HashMap<u32, MessageLoadResult>,
+
+    /// Cursor to pass to `message_list_page` to get the previous, older page.
+    /// `None` if there are no older messages.
+    pub next_cursor: Option<String>,
+}
+
 #[derive(Serialize, TypeDef, schemars::JsonSchema)]
 #[serde(rename = "Message", rename_all = "camelCase")]
 pub struct MessageObject {
@@ -58,6 +93,8 @@ pub struct MessageObject {
     show_padlock: bool,
     is_setupmessage: bool,
     is_info: bool,
+    /// True if the message is hidden locally with `setMessageHiddenLocal()`.
+    is_hidden_local: bool,
     is_forwarded: bool,
 
     /// True if the message was sent by a bot.
@@ -87,11 +124,26 @@ pub struct MessageObject {
 
     webxdc_href: Option<String>,
 
+    /// Text recognized in the image attachment, null if text recognition was not done.
+    ocr_text: Option<String>,
+
+    /// Why a `.xdc` attachment is shown as a file instead of a webxdc app.
+    webxdc_unsupported: Option<WebxdcUnsupported>,
+
     download_state: DownloadState,
 
     reactions: Option<JSONRPCReactions>,
 
     vcard_contact: Option<VcardContact>,
+
+    /// Allowed custom headers of the message as `[name, value]` pairs, names are lowercased.
+    custom_headers: Vec<(String, String)>,
+
+    /// Transports the message was sent over, e.g. `smtp`, `jmap` or `peer_channel`.
+    delivery_paths: Vec<String>,
+
+    /// True if the message was sent, but some recipients rejected it.
+    is_partially_failed: bool,
 }
 
 #[derive(Serialize, TypeDef, schemars::JsonSchema)]
@@ -110,9 +162,28 @@ enum MessageQuote {
         image: Option<String>,
         is_forwarded: bool,
         view_type: MessageViewtype,
+        /// Whether the quoted text matches the quoted message.
+        integrity: QuoteIntegrity,
     },
 }
 
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+pub enum QuoteIntegrity {
+    Unknown,
+    Verified,
+    Modified,
+}
+
+impl From<message::QuoteIntegrity> for QuoteIntegrity {
+    fn from(integrity: message::QuoteIntegrity) -> Self {
+        match integrity {
+            message::QuoteIntegrity::Unknown => QuoteIntegrity::Unknown,
+            message::QuoteIntegrity::Verified => QuoteIntegrity::Verified,
+            message::QuoteIntegrity::Modified => QuoteIntegrity::Modified,
+        }
+    }
+}
+
 impl MessageObject {
     pub async fn from_msg_id(context: &Context, msg_id: MsgId) -> Result<Option<Self>> {
         let Some(message) = Message::load_from_db_optional(context, msg_id).await? else {
@@ -163,6 +234,7 @@ pub async fn from_msg_id(context: &Context, msg_id: MsgId) -> Result<Option<Self
                         },
                         is_forwarded: quote.is_forwarded(),
                         view_type: quote.get_viewtype().into(),
+                        integrity: message.quote_integrity(context).await?.into(),
                     })
                 }
                 None => Some(MessageQuote::JustText { text: quoted_text }),
@@ -212,6 +284,7 @@ pub async fn from_msg_id(context: &Context, msg_id: MsgId) -> Result<Option<Self
             show_padlock: message.get_showpadlock(),
             is_setupmessage: message.is_setupmessage(),
             is_info: message.is_info(),
+            is_hidden_local: message.is_hidden_local(),
             is_forwarded: message.is_forwarded(),
             is_bot: message.is_bot(),
             system_message_type: message.get_info_type().into(),
@@ -247,11 +320,24 @@ pub async fn from_msg_id(context: &Context, msg_id: MsgId) -> Result<Option<Self
             // information about a specific position or state in a webxdc app
             webxdc_href: message.get_webxdc_href(),
 
+            ocr_text: message.get_ocr_text().map(ToString::to_string),
+            webxdc_unsupported: message.get_webxdc_unsupported().map(Into::into),
+
             download_state,
 
             reactions,
 
             vcard_contact: vcard_contacts.first().cloned(),
+
+            custom_headers: message.get_custom_headers(),
+
+            delivery_paths: message
+                .get_delivery_paths()
+                .iter()
+                .map(|path| path.to_string())
+                .collect(),
+
+            is_partially_failed: message.is_partially_failed(),
         };
         Ok(Some(message_object))
     }
@@ -343,6 +429,21 @@ fn from(viewtype: MessageViewtype) -> Self {
     }
 }
 
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+pub enum WebxdcUnsupported {
+    Oversized,
+    Invalid,
+}
+
+impl From<webxdc::WebxdcUnsupported> for WebxdcUnsupported {
+    fn from(unsupported: webxdc::WebxdcUnsupported) -> Self {
+        match unsupported {
+            webxdc::WebxdcUnsupported::Oversized => WebxdcUnsupported::Oversized,
+            webxdc::WebxdcUnsupported::Invalid => WebxdcUnsupported::Invalid,
+        }
+    }
+}
+
 #[derive(Serialize, TypeDef, schemars::JsonSchema)]
 pub enum DownloadState {
     Done,
@@ -406,6 +507,12 @@ pub enum SystemMessageType {
 
     /// This message contains a users iroh node address.
     IrohNodeAddr,
+
+    /// Hidden message announcing the new key to verified contacts.
+    KeyTransition,
+
+    /// Group admins changed or group moderation enabled or disabled.
+    GroupAdminsChanged,
 }
 
 impl From<deltachat::mimeparser::SystemMessage> for SystemMessageType {
@@ -429,6 +536,8 @@ fn from(system_message_type: deltachat::mimeparser::SystemMessage) -> Self {
             SystemMessage::WebxdcInfoMessage => SystemMessageType::WebxdcInfoMessage,
             SystemMessage::InvalidUnencryptedMail => SystemMessageType::InvalidUnencryptedMail,
             SystemMessage::IrohNodeAddr => SystemMessageType::IrohNodeAddr,
+            SystemMessage::KeyTransition => SystemMessageType::KeyTransition,
+            SystemMessage::GroupAdminsChanged => SystemMessageType::GroupAdminsChanged,
             SystemMessage::SecurejoinWait => SystemMessageType::SecurejoinWait,
             SystemMessage::SecurejoinWaitTimeout => SystemMessageType::SecurejoinWaitTimeout,
         }
@@ -512,6 +621,8 @@ pub struct MessageSearchResult {
     is_chat_archived: bool,
     message: String,
     timestamp: i64,
+    /// Messages found by the server-side search may be available for download only.
+    download_state: DownloadState,
 }
 
 impl MessageSearchResult {
@@ -552,6 +663,7 @@ pub async fn from_msg_id(context: &Context, msg_id: MsgId) -> Result<Self> {
             is_chat_archived: chat.get_visibility() == ChatVisibility::Archived,
             message: message.get_text(),
             timestamp: message.get_timestamp(),
+            download_state: message.download_state().into(),
         })
     }
 }
@@ -582,6 +694,163 @@ fn from(item: ChatItem) -> Self {
     }
 }
 
+/// Day boundary in a chat message list.
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase", rename = "DayBoundary")]
+pub struct JSONRPCDayBoundary {
+    /// Timestamp of the start of the day, in seconds.
+    pub timestamp: i64,
+
+    /// ID of the first message of the day.
+    pub msg_id: u32,
+}
+
+impl From<DayBoundary> for JSONRPCDayBoundary {
+    fn from(boundary: DayBoundary) -> Self {
+        JSONRPCDayBoundary {
+            timestamp: boundary.timestamp,
+            msg_id: boundary.msg_id.to_u32(),
+        }
+    }
+}
+
+/// Ordering key of a timeline item.
+///
+/// Items are sorted by timestamp and then by counter.
+#[derive(Clone, Copy, Serialize, Deserialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase", rename = "TimelineKey")]
+pub struct JSONRPCTimelineKey {
+    /// Sort timestamp, in seconds.
+    pub timestamp: i64,
+
+    /// Counter ordering items with the same timestamp.
+    pub counter: u32,
+}
+
+impl From<TimelineKey> for JSONRPCTimelineKey {
+    fn from(key: TimelineKey) -> Self {
+        JSONRPCTimelineKey {
+            timestamp: key.timestamp,
+            counter: key.counter,
+        }
+    }
+}
+
+/// Position to load a timeline window around.
+#[derive(Deserialize, Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase", rename = "TimelineAnchor", tag = "kind")]
+pub enum JSONRPCTimelineAnchor {
+    /// End of the chat.
+    Newest,
+
+    /// Message of the chat, e.g. to jump to a quoted or searched message.
+    #[serde(rename_all = "camelCase")]
+    Message { msg_id: u32 },
+
+    /// Key of a previously loaded item, e.g. to load more items while scrolling.
+    Key { key: JSONRPCTimelineKey },
+}
+
+impl From<JSONRPCTimelineAnchor> for TimelineAnchor {
+    fn from(anchor: JSONRPCTimelineAnchor) -> Self {
+        match anchor {
+            JSONRPCTimelineAnchor::Newest => TimelineAnchor::Newest,
+            JSONRPCTimelineAnchor::Message { msg_id } => {
+                TimelineAnchor::Message(MsgId::new(msg_id))
+            }
+            JSONRPCTimelineAnchor::Key { key } => TimelineAnchor::Key(TimelineKey {
+                timestamp: key.timestamp,
+                counter: key.counter,
+            }),
+        }
+    }
+}
+
+/// Typed item of a chat timeline.
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase", rename = "TimelineItem", tag = "kind")]
+pub enum JSONRPCTimelineItem {
+    /// Regular message.
+    #[serde(rename_all = "camelCase")]
+    Message {
+        key: JSONRPCTimelineKey,
+        msg_id: u32,
+    },
+
+    /// Video chat invitation.
+    #[serde(rename_all = "camelCase")]
+    Call {
+        key: JSONRPCTimelineKey,
+        msg_id: u32,
+    },
+
+    /// Member added to or removed from the group.
+    #[serde(rename_all = "camelCase")]
+    MembershipChange {
+        key: JSONRPCTimelineKey,
+        msg_id: u32,
+
+        /// True if a member was added, false if a member was removed or has left.
+        added: bool,
+    },
+
+    /// Info message of a webxdc app.
+    #[serde(rename_all = "camelCase")]
+    WebxdcUpdate {
+        key: JSONRPCTimelineKey,
+        msg_id: u32,
+    },
+
+    /// Other info message.
+    #[serde(rename_all = "camelCase")]
+    Info {
+        key: JSONRPCTimelineKey,
+        msg_id: u32,
+    },
+
+    /// Day marker, separating messages of different days.
+    DayMarker {
+        key: JSONRPCTimelineKey,
+
+        /// Timestamp of the start of the day, in seconds.
+        timestamp: i64,
+    },
+}
+
+impl From<TimelineEntry> for JSONRPCTimelineItem {
+    fn from(entry: TimelineEntry) -> Self {
+        let key = entry.key.into();
+        match entry.item {
+            TimelineItem::Message { msg_id } => JSONRPCTimelineItem::Message {
+                key,
+                msg_id: msg_id.to_u32(),
+            },
+            TimelineItem::Call { msg_id } => JSONRPCTimelineItem::Call {
+                key,
+                msg_id: msg_id.to_u32(),
+            },
+            TimelineItem::MembershipChange { msg_id, added } => {
+                JSONRPCTimelineItem::MembershipChange {
+                    key,
+                    msg_id: msg_id.to_u32(),
+                    added,
+                }
+            }
+            TimelineItem::WebxdcUpdate { msg_id } => JSONRPCTimelineItem::WebxdcUpdate {
+                key,
+                msg_id: msg_id.to_u32(),
+            },
+            TimelineItem::Info { msg_id } => JSONRPCTimelineItem::Info {
+                key,
+                msg_id: msg_id.to_u32(),
+            },
+            TimelineItem::DayMarker { timestamp } => {
+                JSONRPCTimelineItem::DayMarker { key, timestamp }
+            }
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize, TypeDef, schemars::JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct MessageData {
@@ -594,6 +863,8 @@ pub struct MessageData {
     /// Quoted message id. Takes preference over `quoted_text` (see below).
     pub quoted_message_id: Option<u32>,
     pub quoted_text: Option<String>,
+    /// Request positive delivery status notifications from the recipients' servers.
+    pub request_dsn: Option<bool>,
 }
 
 impl MessageData {
@@ -630,6 +901,9 @@ pub(crate) async fn create_message(self, context: &Context) -> Result<Message> {
             let protect = false;
             message.set_quote_text(Some((text, protect)));
         }
+        if let Some(request_dsn) = self.request_dsn {
+            message.request_dsn(request_dsn);
+        }
         Ok(message)
     }
 }
@@ -641,6 +915,23 @@ pub struct MessageReadReceipt {
     pub timestamp: i64,
 }
 
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageFailedRecipient {
+    pub addr: String,
+    pub error: String,
+}
+
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageRecipientDelivery {
+    pub addr: String,
+    /// Action reported by the recipient's server:
+    /// `delivered`, `relayed` or `expanded`.
+    pub action: String,
+    pub timestamp: i64,
+}
+
 #[derive(Serialize, TypeDef, schemars::JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct MessageInfo {
@@ -0,0 +1,39 @@
+use deltachat::metrics::Metrics;
+use serde::Serialize;
+use typescript_type_def::TypeDef;
+
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "Metrics", rename_all = "camelCase")]
+pub struct JsonrpcMetrics {
+    /// Number of messages received since the account was opened.
+    pub msgs_received: u64,
+    /// Number of messages sent over SMTP since the account was opened.
+    pub msgs_sent: u64,
+    pub imap_connections: u64,
+    pub smtp_connections: u64,
+    /// Number of times the rate limiter delayed IMAP connections or sending.
+    pub ratelimit_delays: u64,
+    /// Total time of the rate limiter delays in milliseconds.
+    pub ratelimit_delay_ms: u64,
+    pub smtp_queue: u64,
+    pub mdns_queue: u64,
+    pub status_updates_queue: u64,
+    pub download_queue: u64,
+}
+
+impl From<Metrics> for JsonrpcMetrics {
+    fn from(metrics: Metrics) -> Self {
+        Self {
+            msgs_received: metrics.msgs_received,
+            msgs_sent: metrics.msgs_sent,
+            imap_connections: metrics.imap_connections,
+            smtp_connections: metrics.smtp_connections,
+            ratelimit_delays: metrics.ratelimit_delays,
+            ratelimit_delay_ms: metrics.ratelimit_delay_ms,
+            smtp_queue: metrics.smtp_queue,
+            mdns_queue: metrics.mdns_queue,
+            status_updates_queue: metrics.status_updates_queue,
+            download_queue: metrics.download_queue,
+        }
+    }
+}
@@ -1,14 +1,27 @@
 pub mod account;
+pub mod background_job;
+pub mod blob_gc;
 pub mod chat;
+pub mod chat_export;
 pub mod chat_list;
 pub mod contact;
 pub mod events;
+pub mod health;
 pub mod http;
 pub mod location;
 pub mod message;
+pub mod metrics;
+pub mod network;
+pub mod outbox;
+pub mod power_mode;
 pub mod provider_info;
+pub mod push;
 pub mod qr;
+pub mod quota;
 pub mod reactions;
+pub mod sync_state;
+pub mod thunderbird;
+pub mod translate;
 pub mod webxdc;
 
 pub fn color_int_to_hex_string(color: u32) -> String {
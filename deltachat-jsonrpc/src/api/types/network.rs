@@ -0,0 +1,34 @@
+use deltachat::net::NetworkProfile;
+use serde::{Deserialize, Serialize};
+use typescript_type_def::TypeDef;
+
+#[derive(Clone, Copy, Serialize, Deserialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "NetworkProfile")]
+pub enum JsonrpcNetworkProfile {
+    /// Unmetered network, e.g. Wi-Fi or Ethernet.
+    Unmetered,
+    /// Metered network, e.g. mobile data.
+    Metered,
+    /// Mobile data while roaming.
+    Roaming,
+}
+
+impl From<JsonrpcNetworkProfile> for NetworkProfile {
+    fn from(profile: JsonrpcNetworkProfile) -> Self {
+        match profile {
+            JsonrpcNetworkProfile::Unmetered => NetworkProfile::Unmetered,
+            JsonrpcNetworkProfile::Metered => NetworkProfile::Metered,
+            JsonrpcNetworkProfile::Roaming => NetworkProfile::Roaming,
+        }
+    }
+}
+
+impl From<NetworkProfile> for JsonrpcNetworkProfile {
+    fn from(profile: NetworkProfile) -> Self {
+        match profile {
+            NetworkProfile::Unmetered => JsonrpcNetworkProfile::Unmetered,
+            NetworkProfile::Metered => JsonrpcNetworkProfile::Metered,
+            NetworkProfile::Roaming => JsonrpcNetworkProfile::Roaming,
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use serde::Serialize;
+use typescript_type_def::TypeDef;
+
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "QueuedMessage", rename_all = "camelCase")]
+pub struct JsonrpcQueuedMessage {
+    msg_id: u32,
+    /// Addresses the queue entry is sent to.
+    recipients: Vec<String>,
+    /// Number of attempts to send the entry.
+    retries: u32,
+    /// Error of the last failed attempt.
+    last_error: Option<String>,
+    /// Timestamp of the next attempt if sending is postponed after an error.
+    next_retry: Option<i64>,
+}
+
+impl From<deltachat::outbox::QueuedMessage> for JsonrpcQueuedMessage {
+    fn from(queued: deltachat::outbox::QueuedMessage) -> Self {
+        Self {
+            msg_id: queued.msg_id.to_u32(),
+            recipients: queued.recipients,
+            retries: queued.retries,
+            last_error: queued.last_error,
+            next_retry: queued.next_retry,
+        }
+    }
+}
+
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "QuarantinedMessage", rename_all = "camelCase")]
+pub struct JsonrpcQuarantinedMessage {
+    msg_id: u32,
+    /// Addresses the queue entry should be sent to.
+    recipients: Vec<String>,
+    /// Why sending was given up.
+    reason: String,
+    /// Timestamp of quarantining the entry.
+    timestamp: i64,
+}
+
+impl From<deltachat::outbox::QuarantinedMessage> for JsonrpcQuarantinedMessage {
+    fn from(quarantined: deltachat::outbox::QuarantinedMessage) -> Self {
+        Self {
+            msg_id: quarantined.msg_id.to_u32(),
+            recipients: quarantined.recipients,
+            reason: quarantined.reason,
+            timestamp: quarantined.timestamp,
+        }
+    }
+}
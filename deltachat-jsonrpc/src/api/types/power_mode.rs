@@ -0,0 +1,34 @@
+use deltachat::context::PowerMode;
+use serde::{Deserialize, Serialize};
+use typescript_type_def::TypeDef;
+
+#[derive(Clone, Copy, Serialize, Deserialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "PowerMode")]
+pub enum JsonrpcPowerMode {
+    /// Default mode.
+    Balanced,
+    /// Poll more often and prefetch more messages.
+    Performance,
+    /// Poll less often and defer background jobs.
+    BatterySaver,
+}
+
+impl From<JsonrpcPowerMode> for PowerMode {
+    fn from(mode: JsonrpcPowerMode) -> Self {
+        match mode {
+            JsonrpcPowerMode::Balanced => PowerMode::Balanced,
+            JsonrpcPowerMode::Performance => PowerMode::Performance,
+            JsonrpcPowerMode::BatterySaver => PowerMode::BatterySaver,
+        }
+    }
+}
+
+impl From<PowerMode> for JsonrpcPowerMode {
+    fn from(mode: PowerMode) -> Self {
+        match mode {
+            PowerMode::Balanced => JsonrpcPowerMode::Balanced,
+            PowerMode::Performance => JsonrpcPowerMode::Performance,
+            PowerMode::BatterySaver => JsonrpcPowerMode::BatterySaver,
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use deltachat::push::{PushRegistration, PushTransport};
+use serde::{Deserialize, Serialize};
+use typescript_type_def::TypeDef;
+
+#[derive(Clone, Copy, Serialize, Deserialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "PushTransport")]
+pub enum JsonrpcPushTransport {
+    /// Apple Push Notification service device token.
+    Apns,
+    /// Firebase Cloud Messaging registration token.
+    Fcm,
+    /// UnifiedPush endpoint URL.
+    UnifiedPush,
+}
+
+impl From<JsonrpcPushTransport> for PushTransport {
+    fn from(transport: JsonrpcPushTransport) -> Self {
+        match transport {
+            JsonrpcPushTransport::Apns => PushTransport::Apns,
+            JsonrpcPushTransport::Fcm => PushTransport::Fcm,
+            JsonrpcPushTransport::UnifiedPush => PushTransport::UnifiedPush,
+        }
+    }
+}
+
+impl From<PushTransport> for JsonrpcPushTransport {
+    fn from(transport: PushTransport) -> Self {
+        match transport {
+            PushTransport::Apns => JsonrpcPushTransport::Apns,
+            PushTransport::Fcm => JsonrpcPushTransport::Fcm,
+            PushTransport::UnifiedPush => JsonrpcPushTransport::UnifiedPush,
+        }
+    }
+}
+
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "PushRegistration", rename_all = "camelCase")]
+pub struct JsonrpcPushRegistration {
+    pub id: u32,
+    pub transport: JsonrpcPushTransport,
+    /// Device token or UnifiedPush endpoint URL.
+    pub token: String,
+    pub device_name: String,
+    pub created_timestamp: i64,
+    /// Timestamp of the last sync of the registration with the server,
+    /// `null` if the registration is not synced yet.
+    pub synced_timestamp: Option<i64>,
+}
+
+impl From<PushRegistration> for JsonrpcPushRegistration {
+    fn from(registration: PushRegistration) -> Self {
+        Self {
+            id: registration.id,
+            transport: registration.transport.into(),
+            token: registration.token,
+            device_name: registration.device_name,
+            created_timestamp: registration.created_timestamp,
+            synced_timestamp: Some(registration.synced_timestamp).filter(|ts| *ts != 0),
+        }
+    }
+}
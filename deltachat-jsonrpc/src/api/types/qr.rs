@@ -171,6 +171,7 @@ fn from(qr: Qr) -> Self {
                 fingerprint,
                 invitenumber,
                 authcode,
+                ..
             } => {
                 let contact_id = contact_id.to_u32();
                 let fingerprint = fingerprint.to_string();
@@ -188,6 +189,7 @@ fn from(qr: Qr) -> Self {
                 fingerprint,
                 invitenumber,
                 authcode,
+                ..
             } => {
                 let contact_id = contact_id.to_u32();
                 let fingerprint = fingerprint.to_string();
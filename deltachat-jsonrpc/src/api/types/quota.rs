@@ -0,0 +1,43 @@
+use serde::Serialize;
+use typescript_type_def::TypeDef;
+
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "QuotaResourceUsage", rename_all = "camelCase")]
+pub struct JsonrpcQuotaResourceUsage {
+    /// Name of the resource, e.g. `STORAGE` or `MESSAGE`.
+    name: String,
+    /// Current usage, in KiB for `STORAGE`.
+    usage: u64,
+    /// Limit of the resource, in KiB for `STORAGE`.
+    limit: u64,
+    percentage: u64,
+}
+
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "QuotaRootUsage", rename_all = "camelCase")]
+pub struct JsonrpcQuotaRootUsage {
+    /// Name of the quota root, may be empty.
+    name: String,
+    /// Watched folders counted against this root.
+    folders: Vec<String>,
+    resources: Vec<JsonrpcQuotaResourceUsage>,
+}
+
+impl From<deltachat::quota::QuotaRootUsage> for JsonrpcQuotaRootUsage {
+    fn from(root: deltachat::quota::QuotaRootUsage) -> Self {
+        Self {
+            name: root.name,
+            folders: root.folders,
+            resources: root
+                .resources
+                .into_iter()
+                .map(|resource| JsonrpcQuotaResourceUsage {
+                    name: resource.name,
+                    usage: resource.usage,
+                    limit: resource.limit,
+                    percentage: resource.percentage,
+                })
+                .collect(),
+        }
+    }
+}
@@ -1,8 +1,8 @@
 use std::collections::BTreeMap;
 
 use deltachat::contact::ContactId;
-use deltachat::reaction::Reactions;
-use serde::Serialize;
+use deltachat::reaction::{CustomEmoji, Reactions};
+use serde::{Deserialize, Serialize};
 use typescript_type_def::TypeDef;
 
 /// A single reaction emoji.
@@ -17,6 +17,46 @@ pub struct JSONRPCReaction {
 
     /// True if we reacted with this emoji.
     is_from_self: bool,
+
+    /// Custom emoji if `emoji` is the shortcode of a custom emoji.
+    ///
+    /// UI should display the image from the emoji pack
+    /// or the fallback emoji if the pack is not available.
+    custom_emoji: Option<JSONRPCCustomEmoji>,
+}
+
+/// Custom emoji referencing an image in an emoji pack.
+#[derive(Serialize, Deserialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "CustomEmoji", rename_all = "camelCase")]
+pub struct JSONRPCCustomEmoji {
+    /// Shortcode such as `:deltacat:`.
+    pub shortcode: String,
+
+    /// ID of the emoji pack containing the image.
+    pub pack: String,
+
+    /// Unicode emoji displayed if the pack is not available.
+    pub fallback: String,
+}
+
+impl From<CustomEmoji> for JSONRPCCustomEmoji {
+    fn from(custom: CustomEmoji) -> Self {
+        Self {
+            shortcode: custom.shortcode,
+            pack: custom.pack,
+            fallback: custom.fallback,
+        }
+    }
+}
+
+impl From<JSONRPCCustomEmoji> for CustomEmoji {
+    fn from(custom: JSONRPCCustomEmoji) -> Self {
+        Self {
+            shortcode: custom.shortcode,
+            pack: custom.pack,
+            fallback: custom.fallback,
+        }
+    }
 }
 
 /// Structure representing all reactions to a particular message.
@@ -56,10 +96,12 @@ fn from(reactions: Reactions) -> Self {
                 false
             };
 
+            let custom_emoji = reactions.custom_emoji(&emoji).cloned().map(Into::into);
             let reaction = JSONRPCReaction {
                 emoji,
                 count,
                 is_from_self,
+                custom_emoji,
             };
             reactions_v.push(reaction)
         }
@@ -0,0 +1,80 @@
+use serde::Serialize;
+use typescript_type_def::TypeDef;
+
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "FolderSyncState", rename_all = "camelCase")]
+pub struct JsonrpcFolderSyncState {
+    folder: String,
+    /// Number of messages fetched so far.
+    fetched: usize,
+    /// Number of messages to fetch.
+    total: usize,
+}
+
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "SyncState", rename_all = "camelCase")]
+pub struct JsonrpcSyncState {
+    /// True if the initial fetch of existing messages after configuration is done.
+    initial_sync_done: bool,
+    /// Folders messages are currently fetched from.
+    folders: Vec<JsonrpcFolderSyncState>,
+}
+
+impl From<deltachat::sync_state::SyncState> for JsonrpcSyncState {
+    fn from(state: deltachat::sync_state::SyncState) -> Self {
+        Self {
+            initial_sync_done: state.initial_sync_done,
+            folders: state
+                .folders
+                .into_iter()
+                .map(|folder| JsonrpcFolderSyncState {
+                    folder: folder.folder,
+                    fetched: folder.fetched,
+                    total: folder.total,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "FolderSyncReport", rename_all = "camelCase")]
+pub struct JsonrpcFolderSyncReport {
+    folder: String,
+    /// Number of new messages.
+    new_msgs: usize,
+    /// Number of messages deleted from the folder by other clients.
+    expunged: usize,
+    /// Number of messages marked as seen by other clients.
+    flags_changed: usize,
+}
+
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "SyncReport", rename_all = "camelCase")]
+pub struct JsonrpcSyncReport {
+    /// Timestamp of starting I/O.
+    started: i64,
+    /// Timestamp of finishing the first full sync.
+    finished: i64,
+    /// Folders with changes.
+    folders: Vec<JsonrpcFolderSyncReport>,
+}
+
+impl From<deltachat::sync_state::SyncReport> for JsonrpcSyncReport {
+    fn from(report: deltachat::sync_state::SyncReport) -> Self {
+        Self {
+            started: report.started,
+            finished: report.finished,
+            folders: report
+                .folders
+                .into_iter()
+                .map(|folder| JsonrpcFolderSyncReport {
+                    folder: folder.folder,
+                    new_msgs: folder.new_msgs,
+                    expunged: folder.expunged,
+                    flags_changed: folder.flags_changed,
+                })
+                .collect(),
+        }
+    }
+}
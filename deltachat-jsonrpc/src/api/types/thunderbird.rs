@@ -0,0 +1,74 @@
+use deltachat::provider::Socket;
+use deltachat::thunderbird::{ThunderbirdAccount, ThunderbirdServer};
+use serde::Serialize;
+use typescript_type_def::TypeDef;
+
+/// Socket security.
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "Socket")]
+pub enum JsonrpcSocket {
+    /// Unspecified socket security, select automatically.
+    Automatic,
+    /// TLS connection.
+    Ssl,
+    /// STARTTLS connection.
+    Starttls,
+    /// No TLS, plaintext connection.
+    Plain,
+}
+
+impl From<Socket> for JsonrpcSocket {
+    fn from(socket: Socket) -> Self {
+        match socket {
+            Socket::Automatic => JsonrpcSocket::Automatic,
+            Socket::Ssl => JsonrpcSocket::Ssl,
+            Socket::Starttls => JsonrpcSocket::Starttls,
+            Socket::Plain => JsonrpcSocket::Plain,
+        }
+    }
+}
+
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "ThunderbirdServer", rename_all = "camelCase")]
+pub struct JsonrpcThunderbirdServer {
+    hostname: String,
+    /// 0 if not specified.
+    port: u16,
+    security: JsonrpcSocket,
+    /// Empty string if not specified.
+    user: String,
+}
+
+impl From<ThunderbirdServer> for JsonrpcThunderbirdServer {
+    fn from(server: ThunderbirdServer) -> Self {
+        Self {
+            hostname: server.hostname,
+            port: server.port,
+            security: server.security.into(),
+            user: server.user,
+        }
+    }
+}
+
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "ThunderbirdAccount", rename_all = "camelCase")]
+pub struct JsonrpcThunderbirdAccount {
+    addr: String,
+    displayname: Option<String>,
+    imap: JsonrpcThunderbirdServer,
+    /// `None` if the identity has no outgoing server.
+    smtp: Option<JsonrpcThunderbirdServer>,
+    oauth2: bool,
+}
+
+impl From<ThunderbirdAccount> for JsonrpcThunderbirdAccount {
+    fn from(account: ThunderbirdAccount) -> Self {
+        Self {
+            addr: account.addr,
+            displayname: account.displayname,
+            imap: account.imap.into(),
+            smtp: account.smtp.map(Into::into),
+            oauth2: account.oauth2,
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use deltachat::translate::{AutoTranslate, MsgTranslation};
+use serde::{Deserialize, Serialize};
+use typescript_type_def::TypeDef;
+
+/// Automatic translation settings of a chat.
+#[derive(Serialize, Deserialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "AutoTranslate", rename_all = "camelCase")]
+pub struct JsonrpcAutoTranslate {
+    /// Language to translate incoming messages to, e.g. "de".
+    target_lang: String,
+
+    /// ID of the translation provider, defined by the UI.
+    provider: String,
+}
+
+impl From<AutoTranslate> for JsonrpcAutoTranslate {
+    fn from(settings: AutoTranslate) -> Self {
+        Self {
+            target_lang: settings.target_lang,
+            provider: settings.provider,
+        }
+    }
+}
+
+impl From<JsonrpcAutoTranslate> for AutoTranslate {
+    fn from(settings: JsonrpcAutoTranslate) -> Self {
+        Self {
+            target_lang: settings.target_lang,
+            provider: settings.provider,
+        }
+    }
+}
+
+/// Translation of a message.
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "MsgTranslation", rename_all = "camelCase")]
+pub struct JsonrpcMsgTranslation {
+    target_lang: String,
+    provider: String,
+
+    /// Translated text, null while the translation is pending.
+    text: Option<String>,
+
+    /// True if the user chose to see the original text instead of the translation.
+    show_original: bool,
+}
+
+impl From<MsgTranslation> for JsonrpcMsgTranslation {
+    fn from(translation: MsgTranslation) -> Self {
+        Self {
+            target_lang: translation.target_lang,
+            provider: translation.provider,
+            text: translation.text,
+            show_original: translation.show_original,
+        }
+    }
+}
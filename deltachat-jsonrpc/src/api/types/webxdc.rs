@@ -1,9 +1,9 @@
 use deltachat::{
     context::Context,
     message::{Message, MsgId},
-    webxdc::WebxdcInfo,
+    webxdc::{WebxdcInfo, WebxdcSendGrant, WebxdcSendOutcome, WebxdcUsage},
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use typescript_type_def::TypeDef;
 
 use super::maybe_empty_string_to_option;
@@ -77,3 +77,81 @@ pub async fn get_for_message(
         })
     }
 }
+
+/// Resource usage of a webxdc instance or of all webxdc instances in a chat.
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "WebxdcUsage", rename_all = "camelCase")]
+pub struct JsonrpcWebxdcUsage {
+    /// Size of the `.xdc` files in bytes.
+    blob_bytes: u64,
+    /// Number of stored status updates.
+    status_updates: u64,
+    /// Size of the stored status updates in bytes.
+    status_update_bytes: u64,
+    /// Number of realtime data bytes sent and received.
+    realtime_bytes: u64,
+    /// Number of bytes used in total.
+    total_bytes: u64,
+}
+
+impl From<WebxdcUsage> for JsonrpcWebxdcUsage {
+    fn from(usage: WebxdcUsage) -> Self {
+        Self {
+            blob_bytes: usage.blob_bytes,
+            status_updates: usage.status_updates,
+            status_update_bytes: usage.status_update_bytes,
+            realtime_bytes: usage.realtime_bytes,
+            total_bytes: usage.total_bytes(),
+        }
+    }
+}
+
+/// Result of a request of a webxdc app to send a message.
+#[derive(Serialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "WebxdcSendOutcome", tag = "kind")]
+pub enum JsonrpcWebxdcSendOutcome {
+    /// The app has a permanent grant, the message was sent.
+    #[serde(rename_all = "camelCase")]
+    Sent { msg_id: u32 },
+    /// The request waits for approval of the user.
+    #[serde(rename_all = "camelCase")]
+    Pending { request_id: u32 },
+    /// The user has denied sending messages for this app.
+    Denied,
+}
+
+impl From<WebxdcSendOutcome> for JsonrpcWebxdcSendOutcome {
+    fn from(outcome: WebxdcSendOutcome) -> Self {
+        match outcome {
+            WebxdcSendOutcome::Sent(msg_id) => JsonrpcWebxdcSendOutcome::Sent {
+                msg_id: msg_id.to_u32(),
+            },
+            WebxdcSendOutcome::Pending(request_id) => {
+                JsonrpcWebxdcSendOutcome::Pending { request_id }
+            }
+            WebxdcSendOutcome::Denied => JsonrpcWebxdcSendOutcome::Denied,
+        }
+    }
+}
+
+/// Permission of a webxdc app to send messages on the user's behalf.
+#[derive(Serialize, Deserialize, TypeDef, schemars::JsonSchema)]
+#[serde(rename = "WebxdcSendGrant")]
+pub enum JsonrpcWebxdcSendGrant {
+    /// Ask the user for every message.
+    Ask,
+    /// Send messages without asking.
+    Always,
+    /// Deny all requests without asking.
+    Never,
+}
+
+impl From<JsonrpcWebxdcSendGrant> for WebxdcSendGrant {
+    fn from(grant: JsonrpcWebxdcSendGrant) -> Self {
+        match grant {
+            JsonrpcWebxdcSendGrant::Ask => WebxdcSendGrant::Ask,
+            JsonrpcWebxdcSendGrant::Always => WebxdcSendGrant::Always,
+            JsonrpcWebxdcSendGrant::Never => WebxdcSendGrant::Never,
+        }
+    }
+}
@@ -74,6 +74,7 @@ async function run() {
         selectedAccount,
         chatId,
         false,
+        false,
         false
       );
       const messages = await client.rpc.getMessages(
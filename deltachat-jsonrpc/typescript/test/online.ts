@@ -95,6 +95,7 @@ describe("online tests", function () {
       accountId2,
       chatIdOnAccountB,
       false,
+      false,
       false
     );
 
@@ -129,6 +130,7 @@ describe("online tests", function () {
       accountId2,
       chatIdOnAccountB,
       false,
+      false,
       false
     );
     const message = await dc.rpc.getMessage(
@@ -143,7 +145,7 @@ describe("online tests", function () {
     await eventPromise2;
 
     const messageId = (
-      await dc.rpc.getMessageIds(accountId1, chatId, false, false)
+      await dc.rpc.getMessageIds(accountId1, chatId, false, false, false)
     ).reverse()[0];
     const message2 = await dc.rpc.getMessage(accountId1, messageId);
     expect(message2.text).equal("super secret message");
@@ -340,6 +340,7 @@ pub async fn cmdline(context: Context, line: &str, chat_id: &mut ChatId) -> Resu
                  receive-backup <qr>\n\
                  export-keys\n\
                  import-keys\n\
+                 import-mails <mbox-file>|<eml-file>|<folder>\n\
                  poke [<eml-file>|<folder>|<addr> <key-file>]\n\
                  reset <flags>\n\
                  stop\n\
@@ -507,6 +508,10 @@ pub async fn cmdline(context: Context, line: &str, chat_id: &mut ChatId) -> Resu
         "import-keys" => {
             imex(&context, ImexMode::ImportSelfKeys, arg1.as_ref(), None).await?;
         }
+        "import-mails" => {
+            ensure!(!arg1.is_empty(), "Argument <path> is missing.");
+            imex(&context, ImexMode::ImportMails, arg1.as_ref(), None).await?;
+        }
         "poke" => {
             ensure!(poke_spec(&context, Some(arg1)).await, "Poke failed");
         }
@@ -680,6 +685,7 @@ pub async fn cmdline(context: Context, line: &str, chat_id: &mut ChatId) -> Resu
                 chat::MessageListOptions {
                     info_only: false,
                     add_daymarker: true,
+                    include_hidden_local: false,
                 },
             )
             .await?;
@@ -161,6 +161,7 @@ fn complete(
     "receive-backup",
     "export-keys",
     "import-keys",
+    "import-mails",
     "poke",
     "reset",
     "stop",
@@ -418,7 +419,8 @@ async fn handle_cmd(
             ctx.stop_io().await;
         }
         "fetch" => {
-            ctx.background_fetch().await?;
+            ctx.background_fetch(std::time::Duration::from_secs(30))
+                .await?;
         }
         "configure" => {
             ctx.configure().await?;
@@ -343,6 +343,14 @@ def import_backup(self, path, passphrase: str = "") -> None:
         """Import backup."""
         self._rpc.import_backup(self.id, str(path), passphrase)
 
+    def export_backup_to_folder(self, path, passphrase: str) -> None:
+        """Export backup into a new transfer folder inside the given directory."""
+        self._rpc.export_backup_to_folder(self.id, str(path), passphrase)
+
+    def import_backup_from_folder(self, path, passphrase: str) -> None:
+        """Import backup from a transfer folder."""
+        self._rpc.import_backup_from_folder(self.id, str(path), passphrase)
+
     def export_self_keys(self, path) -> None:
         """Export keys."""
         passphrase = ""  # Setting passphrase is currently not supported.
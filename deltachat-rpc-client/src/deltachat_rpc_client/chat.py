@@ -195,11 +195,37 @@ def get_draft(self) -> Optional[AttrDict]:
         snapshot["message"] = Message(self.account, snapshot.id)
         return snapshot
 
-    def get_messages(self, info_only: bool = False, add_daymarker: bool = False) -> list[Message]:
+    def get_messages(
+        self,
+        info_only: bool = False,
+        add_daymarker: bool = False,
+        include_hidden_local: bool = False,
+    ) -> list[Message]:
         """get the list of messages in this chat."""
-        msgs = self._rpc.get_message_ids(self.account.id, self.id, info_only, add_daymarker)
+        msgs = self._rpc.get_message_ids(self.account.id, self.id, info_only, add_daymarker, include_hidden_local)
         return [Message(self.account, msg_id) for msg_id in msgs]
 
+    def get_messages_page(self, cursor: Optional[str] = None, page_size: int = 50) -> AttrDict:
+        """Get a page of message snapshots in a single request.
+
+        Pages are returned from the newest to the oldest messages.
+        The result has the ``messages`` of the page as snapshots, oldest first,
+        and the ``next_cursor`` to pass to get the previous page
+        or ``None`` if there are no older messages.
+        """
+        page = self._rpc.message_list_page(self.account.id, self.id, cursor, page_size)
+        messages = []
+        for msg_id in page["messageIds"]:
+            result = page["messages"][str(msg_id)]
+            if result["kind"] != "Message":
+                continue
+            snapshot = AttrDict(result)
+            snapshot["chat"] = self
+            snapshot["sender"] = Contact(self.account, snapshot.from_id)
+            snapshot["message"] = Message(self.account, msg_id)
+            messages.append(snapshot)
+        return AttrDict(messages=messages, next_cursor=page["nextCursor"])
+
     def get_fresh_message_count(self) -> int:
         """Get number of fresh messages in this chat"""
         return self._rpc.get_fresh_msg_cnt(self.account.id, self.id)
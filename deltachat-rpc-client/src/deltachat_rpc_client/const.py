@@ -40,20 +40,26 @@ class EventType(str, Enum):
     MSGS_CHANGED = "MsgsChanged"
     REACTIONS_CHANGED = "ReactionsChanged"
     INCOMING_MSG = "IncomingMsg"
+    BOT_COMMAND = "BotCommand"
     INCOMING_MSG_BUNCH = "IncomingMsgBunch"
     INCOMING_REACTION = "IncomingReaction"
+    INCOMING_PUBLIC_KEY = "IncomingPublicKey"
     MSGS_NOTICED = "MsgsNoticed"
     MSG_DELIVERED = "MsgDelivered"
     MSG_FAILED = "MsgFailed"
     MSG_READ = "MsgRead"
     MSG_DELETED = "MsgDeleted"
+    TRANSLATION_REQUEST = "TranslationRequest"
     CHAT_MODIFIED = "ChatModified"
     CHAT_EPHEMERAL_TIMER_MODIFIED = "ChatEphemeralTimerModified"
+    VERIFIED_GROUP_MEMBER_KEY_CHANGED = "VerifiedGroupMemberKeyChanged"
     CONTACTS_CHANGED = "ContactsChanged"
     LOCATION_CHANGED = "LocationChanged"
     CONFIGURE_PROGRESS = "ConfigureProgress"
+    CONFIGURE_FAILED = "ConfigureFailed"
     IMEX_PROGRESS = "ImexProgress"
     IMEX_FILE_WRITTEN = "ImexFileWritten"
+    BACKUP_TRANSFER_PROGRESS = "BackupTransferProgress"
     SECUREJOIN_INVITER_PROGRESS = "SecurejoinInviterProgress"
     SECUREJOIN_JOINER_PROGRESS = "SecurejoinJoinerProgress"
     CONNECTIVITY_CHANGED = "ConnectivityChanged"
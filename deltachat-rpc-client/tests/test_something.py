@@ -287,6 +287,22 @@ def test_message(acfactory) -> None:
     assert reactions == snapshot.reactions
 
 
+def test_get_messages_page(acfactory) -> None:
+    alice, bob = acfactory.get_online_accounts(2)
+
+    alice_chat_bob = alice.create_chat(bob)
+    for text in ["one", "two", "three"]:
+        alice_chat_bob.send_text(text)
+
+    page = alice_chat_bob.get_messages_page(page_size=2)
+    assert [snapshot.text for snapshot in page.messages] == ["two", "three"]
+    assert page.next_cursor
+
+    page = alice_chat_bob.get_messages_page(cursor=page.next_cursor, page_size=10)
+    assert [snapshot.text for snapshot in page.messages if not snapshot.is_info] == ["one"]
+    assert page.next_cursor is None
+
+
 def test_is_bot(acfactory) -> None:
     """Test that we can recognize messages submitted by bots."""
     alice, bob = acfactory.get_online_accounts(2)
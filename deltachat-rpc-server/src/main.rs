@@ -10,7 +10,8 @@
 use deltachat::constants::DC_VERSION_STR;
 use deltachat_jsonrpc::api::{Accounts, CommandApi};
 use futures_lite::stream::StreamExt;
-use tokio::io::{self, AsyncBufReadExt, BufReader};
+use tokio::io::{self, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
 use tracing_subscriber::EnvFilter;
 use yerpc::RpcServer as _;
 
@@ -81,6 +82,18 @@ async fn main_impl() -> Result<()> {
     let accounts = Arc::new(RwLock::new(accounts));
     let state = CommandApi::from_arc(accounts.clone()).await;
 
+    let metrics_addr = env::var("DC_METRICS_ADDR").ok();
+    let metrics_listener = match &metrics_addr {
+        Some(addr) => {
+            let listener = TcpListener::bind(addr)
+                .await
+                .with_context(|| format!("Failed to listen for metrics on {addr}"))?;
+            log::info!("Serving metrics on http://{addr}/metrics.");
+            Some(listener)
+        }
+        None => None,
+    };
+
     let (client, mut out_receiver) = RpcClient::new();
     let session = RpcSession::new(client.clone(), state.clone());
     let main_cancel = CancellationToken::new();
@@ -119,6 +132,34 @@ async fn main_impl() -> Result<()> {
         Ok(())
     });
 
+    // Metrics task serves OpenMetrics over HTTP if `DC_METRICS_ADDR` is set.
+    let cancel = main_cancel.clone();
+    let metrics_accounts = accounts.clone();
+    let metrics_task: JoinHandle<anyhow::Result<()>> = tokio::spawn(async move {
+        let Some(listener) = metrics_listener else {
+            return Ok(());
+        };
+        loop {
+            let stream = tokio::select! {
+                _ = cancel.cancelled() => break,
+                stream = listener.accept() => match stream {
+                    Ok((stream, _)) => stream,
+                    Err(err) => {
+                        log::warn!("Failed to accept metrics connection: {err:#}.");
+                        continue;
+                    }
+                }
+            };
+            let accounts = metrics_accounts.clone();
+            tokio::spawn(async move {
+                if let Err(err) = serve_metrics(stream, &accounts).await {
+                    log::warn!("Failed to serve metrics: {err:#}.");
+                }
+            });
+        }
+        Ok(())
+    });
+
     // Receiver task reads JSON requests from stdin.
     let cancel = main_cancel.clone();
     let recv_task: JoinHandle<anyhow::Result<()>> = tokio::spawn(async move {
@@ -157,6 +198,38 @@ async fn main_impl() -> Result<()> {
     send_task.await??;
     sigterm_task.await??;
     recv_task.await??;
+    metrics_task.await??;
 
     Ok(())
 }
+
+/// Answers a single HTTP request for the metrics.
+async fn serve_metrics(mut stream: TcpStream, accounts: &RwLock<Accounts>) -> Result<()> {
+    // Only the request line is needed, the rest of the request is ignored.
+    let mut buf = vec![0; 1024];
+    let mut len = 0;
+    while len < buf.len() && !buf[..len].contains(&b'\n') {
+        let n = stream.read(&mut buf[len..]).await?;
+        if n == 0 {
+            break;
+        }
+        len += n;
+    }
+    let request = String::from_utf8_lossy(&buf[..len]);
+    let response = match request.split_whitespace().take(2).collect::<Vec<_>>()[..] {
+        ["GET", "/metrics"] => {
+            let body = accounts.read().await.get_openmetrics().await?;
+            format!(
+                "HTTP/1.1 200 OK\r\n\
+                 Content-Type: application/openmetrics-text; version=1.0.0; charset=utf-8\r\n\
+                 Content-Length: {}\r\n\
+                 Connection: close\r\n\r\n{body}",
+                body.len()
+            )
+        }
+        _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+    };
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
@@ -18,11 +18,22 @@
 #[cfg(not(target_os = "ios"))]
 use tokio::time::{sleep, Duration};
 
-use crate::context::{Context, ContextBuilder};
-use crate::events::{Event, EventEmitter, EventType, Events};
+use crate::context::{Context, ContextBuilder, PowerMode};
+use crate::events::{Event, EventEmitter, EventFilter, EventType, Events};
+use crate::log::LogRecord;
+use crate::net::NetworkProfile;
 use crate::push::PushSubscriber;
 use crate::stock_str::StockStrings;
 
+mod combined_chatlist;
+mod jobs;
+mod metadata;
+
+pub use combined_chatlist::CombinedChatlistEntry;
+use jobs::JobScheduler;
+pub use jobs::{JobConditions, JobKind, JobPriority, UpcomingJob};
+pub use metadata::AccountMetadata;
+
 /// Account manager, that can handle multiple accounts in a single place.
 #[derive(Debug)]
 pub struct Accounts {
@@ -42,6 +53,9 @@ pub struct Accounts {
 
     /// Push notification subscriber shared between accounts.
     push_subscriber: PushSubscriber,
+
+    /// Scheduler of periodic background jobs of all accounts.
+    jobs: JobScheduler,
 }
 
 impl Accounts {
@@ -83,6 +97,8 @@ async fn open(dir: PathBuf, writable: bool) -> Result<Self> {
             .load_accounts(&events, &stockstrings, push_subscriber.clone(), &dir)
             .await
             .context("failed to load accounts")?;
+        let jobs = JobScheduler::default();
+        jobs.set_accounts(&accounts);
 
         Ok(Self {
             dir,
@@ -91,6 +107,7 @@ async fn open(dir: PathBuf, writable: bool) -> Result<Self> {
             events,
             stockstrings,
             push_subscriber,
+            jobs,
         })
     }
 
@@ -139,6 +156,7 @@ pub async fn add_account(&mut self) -> Result<u32> {
         ctx.open("".to_string()).await?;
 
         self.accounts.insert(account_config.id, ctx);
+        self.jobs.set_accounts(&self.accounts);
         self.emit_event(EventType::AccountsChanged);
 
         Ok(account_config.id)
@@ -157,6 +175,7 @@ pub async fn add_closed_account(&mut self) -> Result<u32> {
             .build()
             .await?;
         self.accounts.insert(account_config.id, ctx);
+        self.jobs.set_accounts(&self.accounts);
         self.emit_event(EventType::AccountsChanged);
 
         Ok(account_config.id)
@@ -192,6 +211,7 @@ pub async fn remove_account(&mut self, id: u32) -> Result<()> {
                 .context("failed to remove account data")?;
         }
         self.config.remove_account(id).await?;
+        self.jobs.set_accounts(&self.accounts);
         self.emit_event(EventType::AccountsChanged);
 
         Ok(())
@@ -248,6 +268,7 @@ pub async fn migrate_account(&mut self, dbfile: PathBuf) -> Result<u32> {
                 )
                 .await?;
                 self.accounts.insert(account_config.id, ctx);
+                self.jobs.set_accounts(&self.accounts);
                 Ok(account_config.id)
             }
             Err(err) => {
@@ -270,8 +291,10 @@ pub fn get_all(&self) -> Vec<u32> {
         self.accounts.keys().copied().collect()
     }
 
-    /// Starts background tasks such as IMAP and SMTP loops for all accounts.
+    /// Starts background tasks such as IMAP and SMTP loops for all accounts
+    /// and the scheduler of periodic background jobs.
     pub async fn start_io(&mut self) {
+        self.jobs.start();
         for account in self.accounts.values_mut() {
             account.start_io().await;
         }
@@ -282,6 +305,7 @@ pub async fn stop_io(&self) {
         // Sending an event here wakes up event loop even
         // if there are no accounts.
         info!(self, "Stopping IO for all accounts.");
+        self.jobs.stop();
         for account in self.accounts.values() {
             account.stop_io().await;
         }
@@ -301,13 +325,55 @@ pub async fn maybe_network_lost(&self) {
         }
     }
 
+    /// Sets the network profile for all accounts.
+    ///
+    /// See [`Context::set_network_profile`] for details.
+    pub async fn set_network_profile(&self, profile: NetworkProfile) {
+        for account in self.accounts.values() {
+            account.set_network_profile(profile).await;
+        }
+        self.jobs.notify();
+    }
+
+    /// Sets the power mode for all accounts.
+    ///
+    /// See [`Context::set_power_mode`] for details.
+    pub async fn set_power_mode(&self, mode: PowerMode) {
+        for account in self.accounts.values() {
+            account.set_power_mode(mode).await;
+        }
+        self.jobs.notify();
+    }
+
+    /// Sets the device conditions reported by the embedder.
+    ///
+    /// Background jobs that are not urgent are deferred while the device runs on battery.
+    /// Jobs using the network are also deferred on metered networks,
+    /// see [`Accounts::set_network_profile`].
+    pub fn set_background_job_conditions(&self, conditions: JobConditions) {
+        self.jobs.set_conditions(conditions);
+    }
+
+    /// Returns the background jobs scheduled for all accounts,
+    /// ordered by the time of their next run.
+    pub fn get_upcoming_background_jobs(&self) -> Vec<UpcomingJob> {
+        self.jobs.upcoming_jobs()
+    }
+
     /// Performs a background fetch for all accounts in parallel.
     ///
     /// This is an auxiliary function and not part of public API.
     /// Use [Accounts::background_fetch] instead.
-    async fn background_fetch_no_timeout(accounts: Vec<Context>, events: Events) {
-        async fn background_fetch_and_log_error(account: Context) {
-            if let Err(error) = account.background_fetch().await {
+    ///
+    /// Each account does its most important work first
+    /// and stops starting new work when the time `budget` is used up.
+    async fn background_fetch_no_timeout(
+        accounts: Vec<Context>,
+        events: Events,
+        budget: std::time::Duration,
+    ) {
+        async fn background_fetch_and_log_error(account: Context, budget: std::time::Duration) {
+            if let Err(error) = account.background_fetch(budget).await {
                 warn!(account, "{error:#}");
             }
         }
@@ -321,7 +387,7 @@ async fn background_fetch_and_log_error(account: Context) {
         });
         let mut futures_unordered: FuturesUnordered<_> = accounts
             .into_iter()
-            .map(background_fetch_and_log_error)
+            .map(|account| background_fetch_and_log_error(account, budget))
             .collect();
         while futures_unordered.next().await.is_some() {}
     }
@@ -334,7 +400,7 @@ async fn background_fetch_with_timeout(
     ) {
         if let Err(_err) = tokio::time::timeout(
             timeout,
-            Self::background_fetch_no_timeout(accounts, events.clone()),
+            Self::background_fetch_no_timeout(accounts, events.clone(), timeout),
         )
         .await
         {
@@ -368,11 +434,24 @@ pub fn emit_event(&self, event: EventType) {
         self.events.emit(Event { id: 0, typ: event })
     }
 
+    /// Emits a log record as a string event.
+    ///
+    /// Unlike [`Context::emit_log`], the record is not passed to a log sink.
+    pub fn emit_log(&self, record: LogRecord) {
+        self.emit_event(record.to_event())
+    }
+
     /// Returns event emitter.
     pub fn get_event_emitter(&self) -> EventEmitter {
         self.events.get_emitter()
     }
 
+    /// Returns an event emitter which only receives events matching `filter`,
+    /// e.g. only events of some accounts.
+    pub fn get_filtered_event_emitter(&self, filter: EventFilter) -> EventEmitter {
+        self.events.get_filtered_emitter(filter)
+    }
+
     /// Sets notification token for Apple Push Notification service.
     pub async fn set_push_device_token(&self, token: &str) -> Result<()> {
         self.push_subscriber.set_device_token(token).await;
@@ -599,6 +678,7 @@ async fn new_account(&mut self) -> Result<AccountConfig> {
                 id,
                 dir: target_dir,
                 uuid,
+                metadata: AccountMetadata::default(),
             });
             self.inner.next_id += 1;
             id
@@ -707,6 +787,10 @@ struct AccountConfig {
 
     /// Universally unique account identifier.
     pub uuid: Uuid,
+
+    /// Metadata set by the UI.
+    #[serde(default, skip_serializing_if = "AccountMetadata::is_empty")]
+    pub metadata: AccountMetadata,
 }
 
 impl AccountConfig {
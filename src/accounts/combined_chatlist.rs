@@ -0,0 +1,140 @@
+//! # Chatlist combining the chats of all accounts.
+
+use anyhow::Result;
+
+use super::Accounts;
+use crate::chat::{ChatId, ChatVisibility};
+use crate::chatlist::Chatlist;
+use crate::constants::{DC_GCL_ADD_ALLDONE_HINT, DC_GCL_NO_SPECIALS};
+use crate::message::MsgId;
+
+/// Entry of the combined chatlist of all accounts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CombinedChatlistEntry {
+    /// ID of the account the chat belongs to.
+    pub account_id: u32,
+
+    /// Chat ID in the account.
+    pub chat_id: ChatId,
+
+    /// ID of the last message or draft of the chat, `None` if the chat is empty.
+    pub msg_id: Option<MsgId>,
+
+    /// Timestamp of the last message,
+    /// or of the creation of the chat if the chat is empty.
+    pub timestamp: i64,
+}
+
+impl Accounts {
+    /// Returns the chatlists of all accounts merged into a single list.
+    ///
+    /// `listflags` and `query` are passed to [`Chatlist::try_load`] for every account.
+    /// Special entries such as the archive link are not included,
+    /// as they would only be meaningful for a single account.
+    /// Pinned chats of all accounts come first,
+    /// otherwise the chats are sorted by the timestamp of their last message, newest first.
+    /// Closed accounts are skipped.
+    pub async fn get_combined_chatlist(
+        &self,
+        listflags: usize,
+        query: Option<&str>,
+    ) -> Result<Vec<CombinedChatlistEntry>> {
+        let listflags = (listflags | DC_GCL_NO_SPECIALS) & !DC_GCL_ADD_ALLDONE_HINT;
+        let mut entries = Vec::new();
+        for (&account_id, context) in &self.accounts {
+            if !context.is_open().await {
+                continue;
+            }
+            let chatlist = Chatlist::try_load(context, listflags, query, None).await?;
+            for &(chat_id, msg_id) in chatlist.iter() {
+                let (pinned, timestamp) = context
+                    .sql
+                    .query_row(
+                        "SELECT c.archived=?, IFNULL((SELECT timestamp FROM msgs WHERE id=?), c.created_timestamp)
+                         FROM chats c WHERE c.id=?",
+                        (ChatVisibility::Pinned, msg_id, chat_id),
+                        |row| {
+                            let pinned: bool = row.get(0)?;
+                            let timestamp: i64 = row.get(1)?;
+                            Ok((pinned, timestamp))
+                        },
+                    )
+                    .await?;
+                let entry = CombinedChatlistEntry {
+                    account_id,
+                    chat_id,
+                    msg_id,
+                    timestamp,
+                };
+                entries.push((pinned, entry));
+            }
+        }
+        // The sort is stable, so the order of each chatlist is kept for equal timestamps.
+        entries.sort_by(|(pinned1, entry1), (pinned2, entry2)| {
+            pinned2
+                .cmp(pinned1)
+                .then(entry2.timestamp.cmp(&entry1.timestamp))
+        });
+        Ok(entries.into_iter().map(|(_, entry)| entry).collect())
+    }
+
+    /// Returns the number of fresh messages of all accounts.
+    ///
+    /// Like [`crate::context::Context::get_fresh_msgs`],
+    /// messages in muted chats are not counted.
+    /// Closed accounts are skipped.
+    pub async fn get_combined_fresh_msg_cnt(&self) -> Result<usize> {
+        let mut cnt = 0;
+        for context in self.accounts.values() {
+            if context.is_open().await {
+                cnt += context.get_fresh_msgs().await?.len();
+            }
+        }
+        Ok(cnt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::chat::add_device_msg;
+    use crate::message::Message;
+    use crate::tools::SystemTime;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_get_combined_chatlist() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut accounts = Accounts::new(dir.path().join("accounts"), true).await?;
+        let id1 = accounts.add_account().await?;
+        let id2 = accounts.add_account().await?;
+        let ctx1 = accounts.get_account(id1).unwrap();
+        let ctx2 = accounts.get_account(id2).unwrap();
+
+        let mut msg = Message::new_text("first".to_string());
+        let msg1 = add_device_msg(&ctx1, None, Some(&mut msg)).await?;
+        SystemTime::shift(Duration::from_secs(60));
+        let mut msg = Message::new_text("second".to_string());
+        let msg2 = add_device_msg(&ctx2, None, Some(&mut msg)).await?;
+
+        let chatlist = accounts.get_combined_chatlist(0, None).await?;
+        assert_eq!(chatlist.len(), 2);
+        assert_eq!(chatlist[0].account_id, id2);
+        assert_eq!(chatlist[0].msg_id, Some(msg2));
+        assert_eq!(chatlist[1].account_id, id1);
+        assert_eq!(chatlist[1].msg_id, Some(msg1));
+        assert!(chatlist[0].timestamp > chatlist[1].timestamp);
+
+        // Pinned chats come first.
+        chatlist[1]
+            .chat_id
+            .set_visibility(&ctx1, ChatVisibility::Pinned)
+            .await?;
+        let chatlist = accounts.get_combined_chatlist(0, None).await?;
+        assert_eq!(chatlist[0].account_id, id1);
+
+        assert_eq!(accounts.get_combined_fresh_msg_cnt().await?, 2);
+        Ok(())
+    }
+}
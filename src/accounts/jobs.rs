@@ -0,0 +1,433 @@
+//! # Background job scheduler of the account manager.
+//!
+//! Periodic jobs such as housekeeping, quota checks and device message warnings
+//! are scheduled centrally for all accounts instead of being triggered
+//! by the IMAP loop of each account.
+//! Jobs have priorities, so when several jobs are due,
+//! important jobs run first and jobs of different accounts do not run at the same time.
+//! A random jitter is added to the job intervals to spread the jobs of different accounts.
+//!
+//! The embedder can report whether the device runs on battery
+//! with [`Accounts::set_background_job_conditions`](super::Accounts::set_background_job_conditions).
+//! Whether the network is metered is taken from the [`NetworkProfile`] of each account.
+//! Jobs that are not urgent are deferred under such conditions.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use rand::Rng;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+
+use crate::config::Config;
+use crate::context::{Context, PowerMode};
+use crate::health;
+use crate::log::LogExt;
+use crate::net::NetworkProfile;
+use crate::sql;
+use crate::tools::{maybe_add_time_based_warnings, time};
+
+/// Maximum time to sleep between checking the jobs, in seconds.
+const MAX_SLEEP: i64 = 60 * 60;
+
+/// Priority of a background job.
+///
+/// If several jobs are due, jobs with higher priority run first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum JobPriority {
+    /// Jobs the user may notice if they are delayed.
+    High,
+
+    /// Regular jobs.
+    Normal,
+
+    /// Maintenance jobs that can be delayed without any effect for the user.
+    Low,
+}
+
+/// Kind of a background job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum JobKind {
+    /// Requests an update of the storage quota from the IMAP server.
+    QuotaCheck,
+
+    /// Adds device messages warning about outdated app, missing backups and similar.
+    DeviceWarnings,
+
+    /// Removes unused blobs, old messages and optimizes the database.
+    Housekeeping,
+}
+
+impl JobKind {
+    /// All job kinds.
+    const ALL: [JobKind; 3] = [
+        JobKind::QuotaCheck,
+        JobKind::DeviceWarnings,
+        JobKind::Housekeeping,
+    ];
+
+    /// Returns the priority of the job.
+    pub fn priority(self) -> JobPriority {
+        match self {
+            JobKind::QuotaCheck => JobPriority::High,
+            JobKind::DeviceWarnings => JobPriority::Normal,
+            JobKind::Housekeeping => JobPriority::Low,
+        }
+    }
+
+    /// Returns the interval between job runs in seconds.
+    fn interval(self) -> i64 {
+        match self {
+            JobKind::QuotaCheck => 60 * 60,
+            JobKind::DeviceWarnings => 12 * 60 * 60,
+            JobKind::Housekeeping => 24 * 60 * 60,
+        }
+    }
+
+    /// Returns the maximum random delay added to the interval in seconds.
+    fn jitter(self) -> i64 {
+        self.interval() / 10
+    }
+
+    /// Returns true if the job should be deferred under given conditions.
+    fn is_deferred(self, on_battery: bool, network_profile: NetworkProfile) -> bool {
+        match self {
+            JobKind::QuotaCheck => network_profile.is_metered(),
+            JobKind::DeviceWarnings => false,
+            JobKind::Housekeeping => on_battery,
+        }
+    }
+
+    async fn run(self, context: &Context) {
+        match self {
+            JobKind::QuotaCheck => {
+                context.quota_update_request.store(true, Ordering::Relaxed);
+                context.scheduler.interrupt_inbox().await;
+            }
+            JobKind::DeviceWarnings => {
+                maybe_add_time_based_warnings(context).await;
+                health::maybe_add_health_warnings(context).await;
+            }
+            JobKind::Housekeeping => {
+                let last_housekeeping = context
+                    .get_config_i64(Config::LastHousekeeping)
+                    .await
+                    .log_err(context)
+                    .unwrap_or_default();
+                if last_housekeeping.saturating_add(self.interval()) <= time() {
+                    sql::housekeeping(context).await.log_err(context).ok();
+                }
+            }
+        }
+    }
+}
+
+/// Device conditions reported by the embedder.
+///
+/// Whether the network is metered is not part of the conditions,
+/// it is taken from the network profile of each account,
+/// see [`Context::set_network_profile`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct JobConditions {
+    /// The device runs on battery.
+    pub on_battery: bool,
+}
+
+/// Background job scheduled to run in the future.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpcomingJob {
+    /// ID of the account the job runs for.
+    pub account_id: u32,
+
+    /// Kind of the job.
+    pub kind: JobKind,
+
+    /// Priority of the job.
+    pub priority: JobPriority,
+
+    /// Unix timestamp of the earliest time the job runs at.
+    pub next_run: i64,
+
+    /// True if the job is deferred because of the current [`JobConditions`]
+    /// or the network profile of the account.
+    pub deferred: bool,
+}
+
+#[derive(Debug, Default)]
+struct JobState {
+    conditions: JobConditions,
+
+    /// Accounts to run the jobs for.
+    accounts: BTreeMap<u32, Context>,
+
+    /// Timestamps of the next runs for each account and job kind.
+    next_runs: BTreeMap<(u32, JobKind), i64>,
+}
+
+impl JobState {
+    /// Schedules jobs of new accounts and forgets jobs of removed accounts.
+    fn update_schedule(&mut self, now: i64) {
+        let accounts = &self.accounts;
+        self.next_runs
+            .retain(|(account_id, _), _| accounts.contains_key(account_id));
+        for account_id in accounts.keys() {
+            for kind in JobKind::ALL {
+                self.next_runs
+                    .entry((*account_id, kind))
+                    .or_insert_with(|| now + rand::thread_rng().gen_range(0..=kind.jitter()));
+            }
+        }
+    }
+
+    /// Returns true if the job of the account should be deferred.
+    ///
+    /// The device is treated as running on battery if the account is in battery saver mode.
+    fn is_deferred(&self, account_id: u32, kind: JobKind) -> bool {
+        let context = self.accounts.get(&account_id);
+        let on_battery = self.conditions.on_battery
+            || context.is_some_and(|context| context.get_power_mode() == PowerMode::BatterySaver);
+        let network_profile = context
+            .map(|context| context.get_network_profile())
+            .unwrap_or_default();
+        kind.is_deferred(on_battery, network_profile)
+    }
+
+    /// Removes the most important due job from the schedule and schedules its next run.
+    fn pop_due_job(&mut self, now: i64) -> Option<(Context, JobKind)> {
+        let (account_id, kind) = self
+            .next_runs
+            .iter()
+            .filter(|((account_id, kind), next_run)| {
+                **next_run <= now && !self.is_deferred(*account_id, *kind)
+            })
+            .min_by_key(|((account_id, kind), next_run)| (kind.priority(), **next_run, *account_id))
+            .map(|(key, _)| *key)?;
+        let jitter = rand::thread_rng().gen_range(0..=kind.jitter());
+        self.next_runs
+            .insert((account_id, kind), now + kind.interval() + jitter);
+        let context = self.accounts.get(&account_id)?.clone();
+        Some((context, kind))
+    }
+
+    /// Returns the time of the next run of a job that is not deferred.
+    fn next_wakeup(&self) -> Option<i64> {
+        self.next_runs
+            .iter()
+            .filter(|((account_id, kind), _)| !self.is_deferred(*account_id, *kind))
+            .map(|(_, next_run)| *next_run)
+            .min()
+    }
+
+    fn upcoming_jobs(&self) -> Vec<UpcomingJob> {
+        let mut jobs: Vec<UpcomingJob> = self
+            .next_runs
+            .iter()
+            .map(|((account_id, kind), next_run)| UpcomingJob {
+                account_id: *account_id,
+                kind: *kind,
+                priority: kind.priority(),
+                next_run: *next_run,
+                deferred: self.is_deferred(*account_id, *kind),
+            })
+            .collect();
+        jobs.sort_by_key(|job| (job.next_run, job.priority, job.account_id));
+        jobs
+    }
+}
+
+/// Scheduler running background jobs of all accounts.
+#[derive(Debug, Default)]
+pub(crate) struct JobScheduler {
+    state: Arc<Mutex<JobState>>,
+    interrupt: Arc<Notify>,
+    handle: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl JobScheduler {
+    /// Starts the job loop if it is not running yet.
+    pub(crate) fn start(&self) {
+        let mut handle = self.handle.lock();
+        if handle.is_none() {
+            let state = self.state.clone();
+            set_managed(&state.lock().accounts, true);
+            let interrupt = self.interrupt.clone();
+            *handle = Some(tokio::task::spawn(job_loop(state, interrupt)));
+        }
+    }
+
+    /// Stops the job loop.
+    ///
+    /// IMAP loops of the accounts run the periodic jobs themselves while the job loop is stopped.
+    pub(crate) fn stop(&self) {
+        if let Some(handle) = self.handle.lock().take() {
+            handle.abort();
+            set_managed(&self.state.lock().accounts, false);
+        }
+    }
+
+    /// Sets the accounts to run the jobs for.
+    pub(crate) fn set_accounts(&self, accounts: &BTreeMap<u32, Context>) {
+        let running = self.handle.lock().is_some();
+        let mut state = self.state.lock();
+        set_managed(&state.accounts, false);
+        state.accounts = accounts.clone();
+        set_managed(&state.accounts, running);
+        drop(state);
+        self.interrupt.notify_one();
+    }
+
+    pub(crate) fn set_conditions(&self, conditions: JobConditions) {
+        self.state.lock().conditions = conditions;
+        self.interrupt.notify_one();
+    }
+
+    /// Wakes up the job loop to reconsider deferred jobs.
+    pub(crate) fn notify(&self) {
+        self.interrupt.notify_one();
+    }
+
+    pub(crate) fn upcoming_jobs(&self) -> Vec<UpcomingJob> {
+        let mut state = self.state.lock();
+        state.update_schedule(time());
+        state.upcoming_jobs()
+    }
+}
+
+impl Drop for JobScheduler {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn set_managed(accounts: &BTreeMap<u32, Context>, managed: bool) {
+    for context in accounts.values() {
+        context
+            .background_jobs_managed
+            .store(managed, Ordering::Relaxed);
+    }
+}
+
+async fn job_loop(state: Arc<Mutex<JobState>>, interrupt: Arc<Notify>) {
+    loop {
+        let now = time();
+        let (job, next_wakeup) = {
+            let mut state = state.lock();
+            state.update_schedule(now);
+            (state.pop_due_job(now), state.next_wakeup())
+        };
+
+        if let Some((context, kind)) = job {
+            if context.is_open().await {
+                info!(context, "Running background job {kind:?}.");
+                kind.run(&context).await;
+            }
+            continue;
+        }
+
+        let sleep = next_wakeup
+            .map_or(MAX_SLEEP, |next_wakeup| next_wakeup - now)
+            .clamp(1, MAX_SLEEP);
+        tokio::time::timeout(
+            std::time::Duration::from_secs(sleep.unsigned_abs()),
+            interrupt.notified(),
+        )
+        .await
+        .ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestContext;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_job_order() {
+        let t = TestContext::new().await;
+        let mut state = JobState::default();
+        state.accounts.insert(1, t.ctx.clone());
+        state.accounts.insert(2, t.ctx.clone());
+
+        let now = time();
+        state.update_schedule(now);
+        assert_eq!(state.upcoming_jobs().len(), 6);
+
+        // All jobs are due after the maximum jitter passes.
+        let later = now + JobKind::Housekeeping.jitter();
+        let mut kinds = Vec::new();
+        while let Some((_, kind)) = state.pop_due_job(later) {
+            kinds.push(kind);
+        }
+        assert_eq!(
+            kinds,
+            [
+                JobKind::QuotaCheck,
+                JobKind::QuotaCheck,
+                JobKind::DeviceWarnings,
+                JobKind::DeviceWarnings,
+                JobKind::Housekeeping,
+                JobKind::Housekeeping,
+            ]
+        );
+        assert!(state.next_wakeup().unwrap() > later);
+
+        // Housekeeping is deferred on battery.
+        state.conditions.on_battery = true;
+        let much_later = later + JobKind::Housekeeping.interval() * 2;
+        let mut kinds = Vec::new();
+        while let Some((_, kind)) = state.pop_due_job(much_later) {
+            kinds.push(kind);
+        }
+        assert!(!kinds.contains(&JobKind::Housekeeping));
+        assert!(state
+            .upcoming_jobs()
+            .iter()
+            .any(|job| job.kind == JobKind::Housekeeping && job.deferred));
+
+        // Removed accounts are forgotten.
+        state.accounts.remove(&2);
+        state.update_schedule(much_later);
+        assert!(state.upcoming_jobs().iter().all(|job| job.account_id == 1));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_battery_saver_defers_jobs() {
+        let t = TestContext::new().await;
+        let mut state = JobState::default();
+        state.accounts.insert(1, t.ctx.clone());
+        let now = time();
+        state.update_schedule(now);
+
+        t.set_power_mode(PowerMode::BatterySaver).await;
+        assert!(state
+            .upcoming_jobs()
+            .iter()
+            .any(|job| job.kind == JobKind::Housekeeping && job.deferred));
+
+        t.set_power_mode(PowerMode::Balanced).await;
+        assert!(state.upcoming_jobs().iter().all(|job| !job.deferred));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_metered_network_defers_quota_check() {
+        let t = TestContext::new().await;
+        let mut state = JobState::default();
+        state.accounts.insert(1, t.ctx.clone());
+        let now = time();
+        state.update_schedule(now);
+
+        t.set_network_profile(NetworkProfile::Metered).await;
+        let deferred: Vec<JobKind> = state
+            .upcoming_jobs()
+            .iter()
+            .filter(|job| job.deferred)
+            .map(|job| job.kind)
+            .collect();
+        assert_eq!(deferred, [JobKind::QuotaCheck]);
+
+        t.set_network_profile(NetworkProfile::Unmetered).await;
+        assert!(state.upcoming_jobs().iter().all(|job| !job.deferred));
+    }
+}
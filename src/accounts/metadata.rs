@@ -0,0 +1,144 @@
+//! # UI metadata of accounts.
+//!
+//! The metadata is stored in `accounts.toml`,
+//! so that all frontends using the same account manager directory
+//! show the same account labels.
+
+use anyhow::{ensure, Context as _, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{Accounts, Config};
+use crate::events::EventType;
+
+/// Metadata of an account set by the UI.
+///
+/// The core does not use the metadata otherwise.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct AccountMetadata {
+    /// Custom name of the account shown in the account list
+    /// instead of the display name or address.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// Color tag of the account as `#rrggbb` hex string.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+
+    /// Position of the account in the account list, lower values first.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort_order: Option<i64>,
+}
+
+impl AccountMetadata {
+    pub(super) fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+impl Accounts {
+    /// Returns the UI metadata of an account.
+    pub fn get_account_metadata(&self, id: u32) -> Result<AccountMetadata> {
+        let account = self
+            .config
+            .get_account(id)
+            .with_context(|| format!("Invalid account id {id}"))?;
+        Ok(account.metadata)
+    }
+
+    /// Sets the UI metadata of an account and saves it to `accounts.toml`.
+    ///
+    /// Empty names and colors are stored as unset.
+    pub async fn set_account_metadata(&mut self, id: u32, metadata: AccountMetadata) -> Result<()> {
+        let metadata = AccountMetadata {
+            name: metadata.name.filter(|name| !name.trim().is_empty()),
+            color: metadata.color.filter(|color| !color.is_empty()),
+            sort_order: metadata.sort_order,
+        };
+        if let Some(color) = &metadata.color {
+            ensure!(
+                is_hex_color(color),
+                "Invalid color {color:?}, expected #rrggbb"
+            );
+        }
+        self.config.set_account_metadata(id, metadata).await?;
+        self.emit_event(EventType::AccountsChanged);
+        Ok(())
+    }
+}
+
+impl Config {
+    async fn set_account_metadata(&mut self, id: u32, metadata: AccountMetadata) -> Result<()> {
+        let account = self
+            .inner
+            .accounts
+            .iter_mut()
+            .find(|account| account.id == id)
+            .with_context(|| format!("Invalid account id {id}"))?;
+        if account.metadata == metadata {
+            return Ok(());
+        }
+        account.metadata = metadata;
+        self.sync().await
+    }
+}
+
+fn is_hex_color(color: &str) -> bool {
+    color.len() == 7 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_account_metadata() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let p = dir.path().join("accounts");
+        let mut accounts = Accounts::new(p.clone(), true).await?;
+        let id = accounts.add_account().await?;
+        assert_eq!(
+            accounts.get_account_metadata(id)?,
+            AccountMetadata::default()
+        );
+
+        accounts
+            .set_account_metadata(
+                id,
+                AccountMetadata {
+                    name: Some(" ".to_string()),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        assert_eq!(
+            accounts.get_account_metadata(id)?,
+            AccountMetadata::default()
+        );
+        assert!(accounts
+            .set_account_metadata(
+                id,
+                AccountMetadata {
+                    color: Some("orange".to_string()),
+                    ..Default::default()
+                }
+            )
+            .await
+            .is_err());
+
+        let metadata = AccountMetadata {
+            name: Some("Work".to_string()),
+            color: Some("#ff8800".to_string()),
+            sort_order: Some(2),
+        };
+        assert!(accounts
+            .set_account_metadata(id + 1, metadata.clone())
+            .await
+            .is_err());
+        accounts.set_account_metadata(id, metadata.clone()).await?;
+        drop(accounts);
+
+        let accounts = Accounts::new(p, false).await?;
+        assert_eq!(accounts.get_account_metadata(id)?, metadata);
+        Ok(())
+    }
+}
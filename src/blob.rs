@@ -18,6 +18,7 @@
 use tokio::{fs, io, task};
 use tokio_stream::wrappers::ReadDirStream;
 
+use crate::blob_gc::BLOBS_QUARANTINE_NAME;
 use crate::config::Config;
 use crate::constants::{self, MediaQuality};
 use crate::context::Context;
@@ -740,6 +741,8 @@ pub(crate) async fn new(context: &'a Context) -> Result<BlobDirContents<'a>> {
             .filter_map(|entry| async move {
                 match entry.file_type().await.ok()?.is_file() {
                     true => Some(entry.path()),
+                    // Quarantined files are garbage and not exported.
+                    false if entry.file_name() == BLOBS_QUARANTINE_NAME => None,
                     false => {
                         warn!(
                             context,
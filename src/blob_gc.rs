@@ -0,0 +1,480 @@
+//! # Garbage collection of unreferenced blob files.
+//!
+//! Housekeeping collects files in the blobdir which are not referenced
+//! by messages, chats, contacts, the config or the HTTP cache.
+//! Instead of deleting them immediately, they are moved into a quarantine directory
+//! inside the blobdir and only deleted after [`Config::BlobQuarantineDays`],
+//! so that they can be restored with [`Context::restore_quarantined_blobs`]
+//! if they turn out to be needed.
+//!
+//! [`Context::blob_gc_dry_run`] lists the files that would be collected without touching them,
+//! [`Context::get_last_blob_gc_report`] returns what the last housekeeping run has done.
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{Context as _, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::context::Context;
+use crate::imex::BLOBS_BACKUP_NAME;
+use crate::param::Param;
+use crate::sql::{get_files_in_use, is_file_in_use, maybe_add_from_param};
+use crate::tools::{delete_file, time, SystemTime, Time};
+
+/// Name of the quarantine directory inside the blobdir.
+pub(crate) const BLOBS_QUARANTINE_NAME: &str = "blobs_quarantine";
+
+/// Raw config key of the JSON-serialized report of the last garbage collection.
+const LAST_REPORT_KEY: &str = "last_blob_gc_report";
+
+/// Unreferenced files created, modified or accessed more recently are kept
+/// as they may be just created to build a message object.
+const KEEP_NEW_FILES: Duration = Duration::from_secs(60 * 60);
+
+/// Why a file is collected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BlobGcReason {
+    /// The file is not referenced at all.
+    Unreferenced,
+
+    /// The file is only referenced by deleted messages.
+    DeletedMessage,
+
+    /// The file is left over from an interrupted backup import.
+    BackupLeftover,
+
+    /// The file has been in quarantine for longer than [`Config::BlobQuarantineDays`].
+    QuarantineExpired,
+}
+
+/// File collected by the garbage collection.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlobGcCandidate {
+    /// File name relative to the directory it is found in.
+    pub name: String,
+
+    /// File size in bytes.
+    pub size: u64,
+
+    /// Modification time as a unix timestamp.
+    ///
+    /// For files in quarantine, this is the time they were quarantined.
+    pub modified: i64,
+
+    /// Why the file is collected.
+    pub reason: BlobGcReason,
+}
+
+/// Report of a garbage collection run.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlobGcReport {
+    /// Unix timestamp of the run.
+    pub timestamp: i64,
+
+    /// Whether the run was a dry run which did not touch any files.
+    pub dry_run: bool,
+
+    /// Number of files referenced by the database.
+    pub files_in_use: usize,
+
+    /// Number of unreferenced files kept because they were created recently.
+    pub kept_new: usize,
+
+    /// Files which are quarantined or deleted, or would be in a dry run.
+    pub candidates: Vec<BlobGcCandidate>,
+
+    /// Number of files moved into quarantine.
+    pub quarantined: usize,
+
+    /// Number of deleted files.
+    pub deleted: usize,
+
+    /// Number of bytes freed by deleting files.
+    pub reclaimed_bytes: u64,
+
+    /// Total size of the files in quarantine after the run.
+    pub quarantine_bytes: u64,
+}
+
+/// File in the quarantine directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuarantinedBlob {
+    /// File name.
+    pub name: String,
+
+    /// File size in bytes.
+    pub size: u64,
+
+    /// Unix timestamp of the time the file was quarantined.
+    pub quarantined: i64,
+}
+
+impl Context {
+    /// Returns the files which housekeeping would quarantine or delete now,
+    /// without touching any file.
+    pub async fn blob_gc_dry_run(&self) -> Result<BlobGcReport> {
+        collect_garbage(self, true).await
+    }
+
+    /// Returns the report of the last housekeeping run
+    /// or `None` if housekeeping has not collected garbage yet.
+    pub async fn get_last_blob_gc_report(&self) -> Result<Option<BlobGcReport>> {
+        let Some(json) = self.sql.get_raw_config(LAST_REPORT_KEY).await? else {
+            return Ok(None);
+        };
+        let report = serde_json::from_str(&json).context("Invalid blob GC report")?;
+        Ok(Some(report))
+    }
+
+    /// Returns the files in quarantine.
+    pub async fn get_quarantined_blobs(&self) -> Result<Vec<QuarantinedBlob>> {
+        let dir = self.get_blobdir().join(BLOBS_QUARANTINE_NAME);
+        let mut blobs = Vec::new();
+        let Ok(mut dir_handle) = tokio::fs::read_dir(&dir).await else {
+            return Ok(blobs);
+        };
+        while let Some(entry) = dir_handle.next_entry().await? {
+            let stats = entry.metadata().await?;
+            if !stats.is_file() {
+                continue;
+            }
+            blobs.push(QuarantinedBlob {
+                name: entry.file_name().to_string_lossy().into_owned(),
+                size: stats.len(),
+                quarantined: stats.modified().map(to_timestamp).unwrap_or_default(),
+            });
+        }
+        blobs.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(blobs)
+    }
+
+    /// Moves all files in quarantine back to the blobdir.
+    ///
+    /// Files which are still unreferenced are quarantined again
+    /// by one of the next housekeeping runs.
+    /// Returns the number of restored files.
+    pub async fn restore_quarantined_blobs(&self) -> Result<usize> {
+        let blobdir = self.get_blobdir();
+        let mut restored = 0;
+        for blob in self.get_quarantined_blobs().await? {
+            let src = blobdir.join(BLOBS_QUARANTINE_NAME).join(&blob.name);
+            let dst = blobdir.join(&blob.name);
+            if tokio::fs::try_exists(&dst).await? {
+                warn!(
+                    self,
+                    "Not restoring {}, a file with the same name exists.", blob.name
+                );
+                continue;
+            }
+            tokio::fs::rename(&src, &dst)
+                .await
+                .with_context(|| format!("Failed to restore {}", blob.name))?;
+            touch(&dst).await?;
+            restored += 1;
+        }
+        info!(self, "Restored {restored} files from quarantine.");
+        Ok(restored)
+    }
+}
+
+/// Collects unreferenced files in the blobdir.
+///
+/// Unless `dry_run` is set, unreferenced files are moved into quarantine,
+/// files in quarantine for longer than [`Config::BlobQuarantineDays`]
+/// and leftovers of backup imports are deleted
+/// and the report is stored for [`Context::get_last_blob_gc_report`].
+pub(crate) async fn collect_garbage(context: &Context, dry_run: bool) -> Result<BlobGcReport> {
+    let files_in_use = get_files_in_use(context).await?;
+    info!(context, "{} files in use.", files_in_use.len());
+    let mut files_in_deleted_msgs = HashSet::new();
+    maybe_add_from_param(
+        &context.sql,
+        &mut files_in_deleted_msgs,
+        "SELECT param FROM msgs WHERE chat_id=3 AND type!=10;",
+        Param::File,
+    )
+    .await?;
+    let quarantine_days = context.get_config_u64(Config::BlobQuarantineDays).await?;
+
+    let mut gc = GarbageCollector {
+        context,
+        dry_run,
+        quarantine_days,
+        report: BlobGcReport {
+            timestamp: time(),
+            dry_run,
+            files_in_use: files_in_use.len(),
+            ..Default::default()
+        },
+    };
+    let blobdir = context.get_blobdir();
+
+    // Leftovers of backup imports are deleted without quarantine.
+    // The directory itself is removed below together with the other subdirectories.
+    let backup_dir = blobdir.join(BLOBS_BACKUP_NAME);
+    if let Ok(mut dir_handle) = tokio::fs::read_dir(&backup_dir).await {
+        while let Ok(Some(entry)) = dir_handle.next_entry().await {
+            gc.collect(&entry.path(), BlobGcReason::BackupLeftover)
+                .await;
+        }
+    }
+
+    /* go through the blobdir and collect unused files */
+    let keep_files_newer_than = SystemTime::now()
+        .checked_sub(KEEP_NEW_FILES)
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+    match tokio::fs::read_dir(blobdir).await {
+        Ok(mut dir_handle) => {
+            while let Ok(Some(entry)) = dir_handle.next_entry().await {
+                let name_f = entry.file_name();
+                let name_s = name_f.to_string_lossy();
+
+                if is_file_in_use(&files_in_use, None, &name_s)
+                    || is_file_in_use(&files_in_use, Some(".waveform"), &name_s)
+                    || is_file_in_use(&files_in_use, Some("-preview.jpg"), &name_s)
+                {
+                    continue;
+                }
+
+                let Ok(stats) = tokio::fs::metadata(entry.path()).await else {
+                    gc.collect(&entry.path(), BlobGcReason::Unreferenced).await;
+                    continue;
+                };
+                if stats.is_dir() {
+                    if name_s != BLOBS_QUARANTINE_NAME && !dry_run {
+                        if let Err(e) = tokio::fs::remove_dir(entry.path()).await {
+                            // The dir could be created not by a user, but by a desktop
+                            // environment f.e. So, no warning.
+                            info!(
+                                context,
+                                "Housekeeping: Cannot rmdir {}: {:#}.",
+                                entry.path().display(),
+                                e
+                            );
+                        }
+                    }
+                    continue;
+                }
+                let recently_created = stats.created().is_ok_and(|t| t > keep_files_newer_than);
+                let recently_modified = stats.modified().is_ok_and(|t| t > keep_files_newer_than);
+                let recently_accessed = stats.accessed().is_ok_and(|t| t > keep_files_newer_than);
+                if recently_created || recently_modified || recently_accessed {
+                    gc.report.kept_new += 1;
+                    info!(
+                        context,
+                        "Housekeeping: Keeping new unreferenced file #{}: {:?}.",
+                        gc.report.kept_new,
+                        entry.file_name(),
+                    );
+                    continue;
+                }
+
+                let reason = if files_in_deleted_msgs.contains(name_s.as_ref()) {
+                    BlobGcReason::DeletedMessage
+                } else {
+                    BlobGcReason::Unreferenced
+                };
+                gc.collect(&entry.path(), reason).await;
+            }
+        }
+        Err(err) => {
+            warn!(
+                context,
+                "Housekeeping: Cannot read dir {}: {:#}.",
+                blobdir.display(),
+                err
+            );
+        }
+    }
+
+    // Files quarantined by this run are recent and thus not purged.
+    let purge_before = time().saturating_sub(
+        i64::try_from(quarantine_days)
+            .unwrap_or(i64::MAX)
+            .saturating_mul(24 * 60 * 60),
+    );
+    for blob in context.get_quarantined_blobs().await? {
+        if quarantine_days == 0 || blob.quarantined < purge_before {
+            let path = blobdir.join(BLOBS_QUARANTINE_NAME).join(&blob.name);
+            gc.collect(&path, BlobGcReason::QuarantineExpired).await;
+        }
+    }
+
+    let mut report = gc.report;
+    report.quarantine_bytes = context
+        .get_quarantined_blobs()
+        .await?
+        .iter()
+        .map(|blob| blob.size)
+        .sum();
+    if !dry_run {
+        info!(
+            context,
+            "Housekeeping: Quarantined {} files, deleted {} files, reclaimed {} bytes.",
+            report.quarantined,
+            report.deleted,
+            report.reclaimed_bytes
+        );
+        context
+            .sql
+            .set_raw_config(LAST_REPORT_KEY, Some(&serde_json::to_string(&report)?))
+            .await?;
+    }
+    Ok(report)
+}
+
+struct GarbageCollector<'a> {
+    context: &'a Context,
+    dry_run: bool,
+    quarantine_days: u64,
+    report: BlobGcReport,
+}
+
+impl GarbageCollector<'_> {
+    /// Adds the file to the report and, unless this is a dry run,
+    /// moves it into quarantine or deletes it.
+    async fn collect(&mut self, path: &Path, reason: BlobGcReason) {
+        let context = self.context;
+        let stats = tokio::fs::metadata(path).await.ok();
+        let candidate = BlobGcCandidate {
+            name: path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+            size: stats.as_ref().map_or(0, |stats| stats.len()),
+            modified: stats
+                .as_ref()
+                .and_then(|stats| stats.modified().ok())
+                .map(to_timestamp)
+                .unwrap_or_default(),
+            reason,
+        };
+        if !self.dry_run {
+            let quarantine = self.quarantine_days > 0
+                && matches!(
+                    reason,
+                    BlobGcReason::Unreferenced | BlobGcReason::DeletedMessage
+                );
+            if quarantine {
+                info!(
+                    context,
+                    "Housekeeping: Quarantining unreferenced file {:?}.", candidate.name
+                );
+                match quarantine_file(context, path).await {
+                    Ok(()) => self.report.quarantined += 1,
+                    Err(err) => error!(
+                        context,
+                        "Failed to quarantine unused file {}: {:#}.",
+                        path.display(),
+                        err
+                    ),
+                }
+            } else {
+                info!(
+                    context,
+                    "Housekeeping: Deleting unreferenced file {:?}.", candidate.name
+                );
+                match delete_file(context, path).await {
+                    Ok(()) => {
+                        self.report.deleted += 1;
+                        self.report.reclaimed_bytes += candidate.size;
+                    }
+                    Err(err) => error!(
+                        context,
+                        "Failed to delete unused file {}: {:#}.",
+                        path.display(),
+                        err
+                    ),
+                }
+            }
+        }
+        self.report.candidates.push(candidate);
+    }
+}
+
+/// Moves a file from the blobdir into the quarantine directory.
+async fn quarantine_file(context: &Context, path: &Path) -> Result<()> {
+    let dir = context.get_blobdir().join(BLOBS_QUARANTINE_NAME);
+    tokio::fs::create_dir_all(&dir).await?;
+    let dst = dir.join(path.file_name().context("No file name")?);
+    tokio::fs::rename(path, &dst).await?;
+    // The modification time tells when the file was quarantined.
+    touch(&dst).await
+}
+
+/// Sets the modification time of a file to now.
+async fn touch(path: &Path) -> Result<()> {
+    let file = tokio::fs::OpenOptions::new()
+        .write(true)
+        .open(path)
+        .await?
+        .into_std()
+        .await;
+    file.set_modified(SystemTime::now())?;
+    Ok(())
+}
+
+fn to_timestamp(t: Time) -> i64 {
+    t.duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blob::BlobObject;
+    use crate::test_utils::TestContext;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_blob_gc_quarantine() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let blob = BlobObject::create_and_deduplicate_from_bytes(&t, b"hello", "unused.txt")?;
+        let path = blob.to_abs_path();
+        let name = blob.as_file_name().to_string();
+
+        // New files are kept.
+        let report = t.blob_gc_dry_run().await?;
+        assert_eq!(report.kept_new, 1);
+        assert!(report.candidates.is_empty());
+
+        SystemTime::shift(Duration::from_secs(65 * 60));
+        let report = t.blob_gc_dry_run().await?;
+        assert!(report.dry_run);
+        assert_eq!(report.candidates.len(), 1);
+        assert_eq!(report.candidates[0].name, name);
+        assert_eq!(report.candidates[0].size, 5);
+        assert_eq!(report.candidates[0].reason, BlobGcReason::Unreferenced);
+        assert!(path.exists());
+        assert_eq!(t.get_last_blob_gc_report().await?, None);
+
+        crate::sql::remove_unused_files(&t).await?;
+        assert!(!path.exists());
+        let report = t.get_last_blob_gc_report().await?.unwrap();
+        assert_eq!(report.quarantined, 1);
+        assert_eq!(report.quarantine_bytes, 5);
+        let quarantined = t.get_quarantined_blobs().await?;
+        assert_eq!(quarantined.len(), 1);
+        assert_eq!(quarantined[0].name, name);
+
+        assert_eq!(t.restore_quarantined_blobs().await?, 1);
+        assert!(path.exists());
+        assert!(t.get_quarantined_blobs().await?.is_empty());
+
+        // Without quarantine, files are deleted immediately.
+        t.set_config(Config::BlobQuarantineDays, Some("0")).await?;
+        SystemTime::shift(Duration::from_secs(65 * 60));
+        crate::sql::remove_unused_files(&t).await?;
+        assert!(!path.exists());
+        assert!(t.get_quarantined_blobs().await?.is_empty());
+        let report = t.get_last_blob_gc_report().await?.unwrap();
+        assert_eq!(report.deleted, 1);
+        assert_eq!(report.reclaimed_bytes, 5);
+        assert_eq!(report.candidates[0].reason, BlobGcReason::Unreferenced);
+        Ok(())
+    }
+}
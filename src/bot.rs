@@ -0,0 +1,139 @@
+//! # Bot command routing.
+//!
+//! Bots register commands such as `/help` with [`Context::register_bot_command`].
+//! Incoming messages starting with a registered command
+//! then produce an [`EventType::BotCommand`] event in addition to the usual events,
+//! so bots do not need to parse message texts themselves.
+
+use anyhow::{ensure, Result};
+
+use crate::chat::ChatId;
+use crate::context::Context;
+use crate::events::EventType;
+use crate::message::{Message, MsgId};
+
+impl Context {
+    /// Registers a bot command, e.g. `/help`.
+    ///
+    /// Commands must not be empty or contain whitespace.
+    /// They are matched case-insensitively.
+    pub fn register_bot_command(&self, command: &str) -> Result<()> {
+        ensure!(!command.is_empty(), "Bot command must not be empty");
+        ensure!(
+            !command.contains(char::is_whitespace),
+            "Bot command {command:?} must not contain whitespace"
+        );
+        self.bot_commands.write().insert(command.to_lowercase());
+        Ok(())
+    }
+
+    /// Unregisters a bot command.
+    ///
+    /// Returns true if the command was registered.
+    pub fn unregister_bot_command(&self, command: &str) -> bool {
+        self.bot_commands.write().remove(&command.to_lowercase())
+    }
+
+    /// Returns the registered bot commands.
+    pub fn get_bot_commands(&self) -> Vec<String> {
+        self.bot_commands.read().iter().cloned().collect()
+    }
+}
+
+/// Splits a message text into a registered command and its arguments.
+///
+/// A command may be followed by `@` and the bot address, e.g. `/help@bot.example.org`,
+/// as it is common in groups with several bots.
+fn parse_command(context: &Context, text: &str) -> Option<(String, String)> {
+    let text = text.trim_start();
+    let (word, args) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+    let command = word.split('@').next().unwrap_or(word).to_lowercase();
+    if context.bot_commands.read().contains(&command) {
+        Some((command, args.trim().to_string()))
+    } else {
+        None
+    }
+}
+
+/// Emits [`EventType::BotCommand`] if the incoming message starts with a registered command.
+pub(crate) async fn handle_incoming_msg(
+    context: &Context,
+    chat_id: ChatId,
+    msg_id: MsgId,
+) -> Result<()> {
+    if context.bot_commands.read().is_empty() {
+        return Ok(());
+    }
+    let Some(msg) = Message::load_from_db_optional(context, msg_id).await? else {
+        return Ok(());
+    };
+    if let Some((command, args)) = parse_command(context, &msg.get_text()) {
+        context.emit_event(EventType::BotCommand {
+            chat_id,
+            msg_id,
+            command,
+            args,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{TestContext, TestContextManager};
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_parse_command() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        assert!(t.register_bot_command("").is_err());
+        assert!(t.register_bot_command("/foo bar").is_err());
+        t.register_bot_command("/Help")?;
+        t.register_bot_command("/echo")?;
+        assert_eq!(t.get_bot_commands(), vec!["/echo", "/help"]);
+
+        assert_eq!(
+            parse_command(&t, "/help"),
+            Some(("/help".to_string(), "".to_string()))
+        );
+        assert_eq!(
+            parse_command(&t, " /echo  hello world "),
+            Some(("/echo".to_string(), "hello world".to_string()))
+        );
+        assert_eq!(
+            parse_command(&t, "/HELP@bot.example.org me"),
+            Some(("/help".to_string(), "me".to_string()))
+        );
+        assert_eq!(parse_command(&t, "/helpme"), None);
+        assert_eq!(parse_command(&t, "please /help"), None);
+
+        assert!(t.unregister_bot_command("/help"));
+        assert!(!t.unregister_bot_command("/help"));
+        assert_eq!(parse_command(&t, "/help"), None);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_bot_command_event() -> Result<()> {
+        let mut tcm = TestContextManager::new();
+        let alice = &tcm.alice().await;
+        let bot = &tcm.bob().await;
+        bot.register_bot_command("/echo")?;
+
+        let msg = tcm.send_recv_accept(alice, bot, "/echo hi").await;
+        let event = bot
+            .evtracker
+            .get_matching(|evt| matches!(evt, EventType::BotCommand { .. }))
+            .await;
+        assert_eq!(
+            event,
+            EventType::BotCommand {
+                chat_id: msg.chat_id,
+                msg_id: msg.id,
+                command: "/echo".to_string(),
+                args: "hi".to_string(),
+            }
+        );
+        Ok(())
+    }
+}
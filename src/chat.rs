@@ -4,13 +4,17 @@
 use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::marker::Sync;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::time::Duration;
 
 use anyhow::{anyhow, bail, ensure, Context as _, Result};
+use chrono::{FixedOffset, NaiveDateTime, NaiveTime, TimeDelta, TimeZone};
+use chrono_tz::Tz;
 use deltachat_contact_tools::{sanitize_bidi_characters, sanitize_single_line, ContactAddress};
 use deltachat_derive::{FromSql, ToSql};
+use num_traits::FromPrimitive;
 use serde::{Deserialize, Serialize};
 use strum_macros::EnumIter;
 use tokio::task;
@@ -23,35 +27,43 @@
 use crate::config::Config;
 use crate::constants::{
     self, Blocked, Chattype, DC_CHAT_ID_ALLDONE_HINT, DC_CHAT_ID_ARCHIVED_LINK,
-    DC_CHAT_ID_LAST_SPECIAL, DC_CHAT_ID_TRASH, DC_RESEND_USER_AVATAR_DAYS,
-    TIMESTAMP_SENT_TOLERANCE,
+    DC_CHAT_ID_LAST_SPECIAL, DC_CHAT_ID_TRASH, DC_LAZY_AVATAR_MIN_MEMBERS, DC_REQUEST_PROFILE_DAYS,
+    DC_RESEND_USER_AVATAR_DAYS, TIMESTAMP_SENT_TOLERANCE,
 };
-use crate::contact::{self, Contact, ContactId, Origin};
+use crate::contact::{self, Contact, ContactId, EncryptionOverride, Origin};
 use crate::context::Context;
 use crate::debug_logging::maybe_set_logging_xdc;
 use crate::download::DownloadState;
+use crate::e2ee::EncryptHelper;
 use crate::ephemeral::{start_chat_ephemeral_timers, Timer as EphemeralTimer};
 use crate::events::EventType;
 use crate::html::new_html_mimepart;
 use crate::location;
 use crate::log::LogExt;
-use crate::message::{self, Message, MessageState, MsgId, Viewtype};
-use crate::mimefactory::MimeFactory;
+use crate::message::{self, DeliveryPath, Message, MessageState, MsgId, Viewtype};
+use crate::mimefactory::{create_rfc724_mid, MimeFactory};
 use crate::mimeparser::SystemMessage;
+use crate::net::http;
+use crate::ocr;
 use crate::param::{Param, Params};
+use crate::peer_channels;
 use crate::peerstate::Peerstate;
 use crate::receive_imf::ReceivedMsg;
 use crate::securejoin::BobState;
-use crate::smtp::send_msg_to_smtp;
+use crate::smtp::{send_msg_to_smtp, SendPriority};
 use crate::stock_str;
 use crate::sync::{self, Sync::*, SyncData};
 use crate::tools::{
     buf_compress, create_id, create_outgoing_rfc724_mid, create_smeared_timestamp,
-    create_smeared_timestamps, get_abs_path, gm2local_offset, smeared_time, time,
-    truncate_msg_text, IsNoneOrEmpty, SystemTime,
+    create_smeared_timestamps, duration_to_str, get_abs_path, parse_mailto, smeared_time, time,
+    truncate_msg_text, IsNoneOrEmpty, MailTo, SystemTime,
 };
 use crate::webxdc::StatusUpdateSerial;
 
+/// Number of recent references kept in addition to the thread root
+/// if [`Config::MuaThreadingCompat`] is enabled.
+const MUA_COMPAT_RECENT_REFERENCES: usize = 8;
+
 /// An chat item, such as a message or a marker.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum ChatItem {
@@ -64,11 +76,101 @@ pub enum ChatItem {
     /// Day marker, separating messages that correspond to different
     /// days according to local time.
     DayMarker {
-        /// Marker timestamp, for day markers
+        /// Timestamp of the start of the day.
+        timestamp: i64,
+    },
+}
+
+/// Ordering key of a [`TimelineItem`], see [`get_timeline`].
+///
+/// Items are sorted by timestamp and then by counter,
+/// which is the message ID for messages and 0 for day markers.
+/// Keys of existing items do not change when new messages are added.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TimelineKey {
+    /// Sort timestamp.
+    pub timestamp: i64,
+
+    /// Counter ordering items with the same timestamp.
+    pub counter: u32,
+}
+
+/// Item of a chat timeline, see [`get_timeline`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TimelineItem {
+    /// Regular message.
+    Message {
+        /// Database ID of the message.
+        msg_id: MsgId,
+    },
+
+    /// Video chat invitation.
+    Call {
+        /// Database ID of the invitation message.
+        msg_id: MsgId,
+    },
+
+    /// Member added to or removed from the group.
+    MembershipChange {
+        /// Database ID of the info message.
+        msg_id: MsgId,
+
+        /// True if a member was added, false if a member was removed or has left.
+        added: bool,
+    },
+
+    /// Info message of a webxdc app.
+    WebxdcUpdate {
+        /// Database ID of the info message.
+        msg_id: MsgId,
+    },
+
+    /// Other info message, e.g. about a changed group name or ephemeral timer.
+    Info {
+        /// Database ID of the info message.
+        msg_id: MsgId,
+    },
+
+    /// Day marker, separating messages of different days.
+    DayMarker {
+        /// Timestamp of the start of the day.
         timestamp: i64,
     },
 }
 
+/// Timeline item together with its ordering key.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TimelineEntry {
+    /// Ordering key of the item.
+    pub key: TimelineKey,
+
+    /// The item.
+    pub item: TimelineItem,
+}
+
+/// Position to load a timeline window around, see [`get_timeline`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TimelineAnchor {
+    /// End of the chat.
+    Newest,
+
+    /// Message of the chat, e.g. to jump to a quoted or searched message.
+    Message(MsgId),
+
+    /// Key of a previously loaded item, e.g. to load more items while scrolling.
+    Key(TimelineKey),
+}
+
+/// Day boundary in a chat message list, see [`get_day_boundaries`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DayBoundary {
+    /// Timestamp of the start of the day.
+    pub timestamp: i64,
+
+    /// First message of the day.
+    pub msg_id: MsgId,
+}
+
 /// Chat protection status.
 #[derive(
     Debug,
@@ -106,6 +208,80 @@ pub enum ProtectionStatus {
     ProtectionBroken = 3, // `2` was never used as a value.
 }
 
+/// Returns true if `name` is an `X-` header name listed in [`Config::AllowedCustomHeaders`].
+pub(crate) async fn is_allowed_custom_header(context: &Context, name: &str) -> Result<bool> {
+    let is_x_header = name.len() > 2
+        && name
+            .get(..2)
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case("x-"))
+        && name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-');
+    if !is_x_header {
+        return Ok(false);
+    }
+    let allowed = context
+        .get_config(Config::AllowedCustomHeaders)
+        .await?
+        .unwrap_or_default();
+    Ok(allowed
+        .split(',')
+        .any(|allowed| allowed.trim().eq_ignore_ascii_case(name)))
+}
+
+/// Kind of an entry in the protection log of a chat.
+///
+/// See [`ChatId::get_protection_log`].
+#[derive(
+    Debug,
+    Display,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    FromPrimitive,
+    ToPrimitive,
+    FromSql,
+    ToSql,
+    Serialize,
+    Deserialize,
+)]
+#[repr(u32)]
+pub enum ProtectionLogEvent {
+    /// The chat became protected.
+    ProtectionEnabled = 1,
+
+    /// The chat became unprotected.
+    ProtectionDisabled = 2,
+
+    /// A message which was not encrypted / signed correctly broke the protection.
+    ProtectionBroken = 3,
+
+    /// The target contact was added to the chat.
+    MemberAdded = 4,
+
+    /// The target contact was removed from the chat.
+    MemberRemoved = 5,
+
+    /// The acting contact verified the target contact,
+    /// e.g. by gossiping its key in the protected chat.
+    MemberVerified = 6,
+}
+
+/// An entry in the protection log of a chat.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtectionLogEntry {
+    /// Timestamp of the change.
+    pub timestamp: i64,
+
+    /// Kind of the change.
+    pub event: ProtectionLogEvent,
+
+    /// Contact which caused the change, [`ContactId::SELF`] for own changes.
+    pub contact_id: ContactId,
+
+    /// Contact affected by the change, if any.
+    pub target_id: Option<ContactId>,
+}
+
 /// The reason why messages cannot be sent to the chat.
 ///
 /// The reason is mainly for logging and displaying in debug REPL, thus not translated.
@@ -157,6 +333,55 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     }
 }
 
+/// Encryption state of a chat member.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemberEncryptionInfo {
+    /// ID of the member.
+    pub contact_id: ContactId,
+
+    /// Email address of the member.
+    pub addr: String,
+
+    /// True if a key of the member is known.
+    pub key_available: bool,
+
+    /// True if the member is verified.
+    pub verified: bool,
+
+    /// Encryption preference announced by the member.
+    ///
+    /// [`EncryptPreference::Reset`] if no key is known.
+    pub prefer_encrypt: EncryptPreference,
+
+    /// True if the user has set [`EncryptionOverride::ForcePlaintext`] for the member.
+    pub force_plaintext: bool,
+}
+
+/// Encryption state of a chat.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChatEncryptionInfo {
+    /// Encryption state of the chat members except self.
+    pub members: Vec<MemberEncryptionInfo>,
+
+    /// True if messages sent to the chat are end-to-end encrypted.
+    pub can_encrypt: bool,
+}
+
+/// Chat member that can be mentioned in a message draft.
+///
+/// Returned by [`get_mentionable_members`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MentionableMember {
+    /// ID of the member.
+    pub contact_id: ContactId,
+
+    /// Display name of the member as returned by [`Contact::get_display_name`].
+    pub display_name: String,
+
+    /// Path to the avatar of the member, if any.
+    pub profile_image: Option<PathBuf>,
+}
+
 /// Chat ID, including reserved IDs.
 ///
 /// Some chat IDs are reserved to identify special chat types.  This
@@ -411,6 +636,15 @@ pub(crate) async fn block_ex(self, context: &Context, sync: sync::Sync) -> Resul
                             "Blocking the contact {contact_id} to block 1:1 chat."
                         );
                         contact::set_blocked(context, Nosync, contact_id, true).await?;
+                        if sync.into() {
+                            context
+                                .update_contacts_timestamp(
+                                    contact_id,
+                                    Param::BlockedTimestamp,
+                                    time(),
+                                )
+                                .await?;
+                        }
                     }
                 }
             }
@@ -451,6 +685,15 @@ pub(crate) async fn unblock_ex(self, context: &Context, sync: sync::Sync) -> Res
 
         if sync.into() {
             let chat = Chat::load_from_db(context, self).await?;
+            if chat.typ == Chattype::Single {
+                for contact_id in get_chat_contacts(context, self).await? {
+                    if contact_id != ContactId::SELF {
+                        context
+                            .update_contacts_timestamp(contact_id, Param::BlockedTimestamp, time())
+                            .await?;
+                    }
+                }
+            }
             // TODO: For a 1:1 chat this currently triggers `Contact::unblock()` on other devices.
             // Maybe we should unblock the contact locally too, this would also resolve discrepancy
             // with `block()` which also blocks the contact.
@@ -605,6 +848,19 @@ async fn set_protection_for_timestamp_sort(
         if protection_status_modified {
             self.add_protection_msg(context, protect, contact_id, timestamp_sort)
                 .await?;
+            let event = match protect {
+                ProtectionStatus::Protected => ProtectionLogEvent::ProtectionEnabled,
+                ProtectionStatus::Unprotected => ProtectionLogEvent::ProtectionDisabled,
+                ProtectionStatus::ProtectionBroken => ProtectionLogEvent::ProtectionBroken,
+            };
+            self.add_protection_log(
+                context,
+                event,
+                contact_id.unwrap_or(ContactId::SELF),
+                None,
+                timestamp_sort,
+            )
+            .await?;
             chatlist_events::emit_chatlist_item_changed(context, self);
         }
         Ok(())
@@ -671,6 +927,12 @@ pub(crate) async fn set_visibility_ex(
             "bad chat_id, can not be special chat: {}",
             self
         );
+        if sync == Sync {
+            // Remember when the user changed the visibility
+            // so that outdated changes from other devices are ignored.
+            self.update_timestamp(context, Param::VisibilityTimestamp, time())
+                .await?;
+        }
 
         context
             .sql
@@ -691,6 +953,7 @@ pub(crate) async fn set_visibility_ex(
 
         if visibility == ChatVisibility::Archived {
             start_chat_ephemeral_timers(context, self).await?;
+            self.restart_inactivity_countdown(context).await?;
         }
 
         context.emit_msgs_changed_without_ids();
@@ -707,6 +970,288 @@ pub(crate) async fn set_visibility_ex(
         Ok(())
     }
 
+    /// Sets the local retention policy of the chat.
+    ///
+    /// Messages exceeding the policy are deleted from this device during housekeeping,
+    /// but are not deleted from the server.
+    /// The policy is synchronized to other devices.
+    pub async fn set_retention(self, context: &Context, policy: RetentionPolicy) -> Result<()> {
+        self.set_retention_ex(context, Sync, policy).await
+    }
+
+    pub(crate) async fn set_retention_ex(
+        self,
+        context: &Context,
+        sync: sync::Sync,
+        policy: RetentionPolicy,
+    ) -> Result<()> {
+        ensure!(!self.is_special(), "Invalid chat ID");
+        let (retention_msgs, retention_days) = match policy {
+            RetentionPolicy::KeepAll => (0, 0),
+            RetentionPolicy::KeepLastMessages(count) => (count, 0),
+            RetentionPolicy::KeepLastDays(days) => (0, days),
+        };
+        context
+            .sql
+            .execute(
+                "UPDATE chats SET retention_msgs=?, retention_days=? WHERE id=?",
+                (retention_msgs, retention_days, self),
+            )
+            .await
+            .with_context(|| format!("Failed to set retention policy for {self}"))?;
+        context.emit_event(EventType::ChatModified(self));
+
+        if sync.into() {
+            let chat = Chat::load_from_db(context, self).await?;
+            chat.sync(context, SyncAction::SetRetention(policy))
+                .await
+                .log_err(context)
+                .ok();
+        }
+        Ok(())
+    }
+
+    /// Returns the local retention policy of the chat.
+    pub async fn get_retention(self, context: &Context) -> Result<RetentionPolicy> {
+        let policy = context
+            .sql
+            .query_row_optional(
+                "SELECT retention_msgs, retention_days FROM chats WHERE id=?",
+                (self,),
+                |row| {
+                    let retention_msgs: u32 = row.get(0)?;
+                    let retention_days: u32 = row.get(1)?;
+                    Ok(match (retention_msgs, retention_days) {
+                        (0, 0) => RetentionPolicy::KeepAll,
+                        (count, 0) => RetentionPolicy::KeepLastMessages(count),
+                        (_, days) => RetentionPolicy::KeepLastDays(days),
+                    })
+                },
+            )
+            .await?;
+        Ok(policy.unwrap_or_default())
+    }
+
+    /// Sets whether replies in the mailing list chat are sent to the list
+    /// or privately to the sender of the quoted message.
+    pub async fn set_mailinglist_reply_mode(
+        self,
+        context: &Context,
+        mode: MailinglistReplyMode,
+    ) -> Result<()> {
+        let mut chat = Chat::load_from_db(context, self).await?;
+        ensure!(
+            chat.is_mailing_list(),
+            "Reply mode can only be set for mailing lists"
+        );
+        chat.param.set_int(Param::MailinglistReplyMode, mode as i32);
+        chat.update_param(context).await?;
+        context.emit_event(EventType::ChatModified(self));
+        Ok(())
+    }
+
+    /// Sets whether incoming reactions to own messages count towards
+    /// the fresh message counter of the chat until the chat is noticed.
+    ///
+    /// `None` uses the global [`Config::ReactionsCountAsFresh`] setting.
+    pub async fn set_reactions_count_as_fresh(
+        self,
+        context: &Context,
+        count_as_fresh: Option<bool>,
+    ) -> Result<()> {
+        ensure!(!self.is_special(), "Invalid chat ID");
+        let mut chat = Chat::load_from_db(context, self).await?;
+        chat.param
+            .set_optional(Param::ReactionsCountAsFresh, count_as_fresh.map(i32::from));
+        chat.update_param(context).await?;
+        context.emit_event(EventType::ChatModified(self));
+        chatlist_events::emit_chatlist_item_changed(context, self);
+        Ok(())
+    }
+
+    /// Sets the last read message of the chat.
+    ///
+    /// Incoming fresh messages received not later than the given message are marked as noticed.
+    /// The marker is synchronized to other devices, so that fresh message counters match.
+    /// Moving the marker backwards is ignored.
+    pub async fn set_last_read_msg(self, context: &Context, msg_id: MsgId) -> Result<()> {
+        ensure!(!self.is_special(), "Invalid chat ID");
+        let msg = Message::load_from_db(context, msg_id).await?;
+        ensure!(
+            msg.chat_id == self,
+            "Message {msg_id} does not belong to {self}"
+        );
+        self.set_last_read_ex(context, Sync, &msg.rfc724_mid, msg.timestamp_sent)
+            .await
+    }
+
+    pub(crate) async fn set_last_read_ex(
+        self,
+        context: &Context,
+        sync: sync::Sync,
+        rfc724_mid: &str,
+        timestamp_sent: i64,
+    ) -> Result<()> {
+        let msg_id = self.get_msg_id_by_rfc724_mid(context, rfc724_mid).await?;
+        let mut chat = Chat::load_from_db(context, self).await?;
+        if let Some(last_rfc724_mid) = chat.param.get(Param::LastReadMsg) {
+            let last_timestamp_sent = chat
+                .param
+                .get_i64(Param::LastReadTimestamp)
+                .unwrap_or_default();
+            let last_msg_id = self
+                .get_msg_id_by_rfc724_mid(context, last_rfc724_mid)
+                .await?;
+            // Messages are compared by their local IDs, i.e. in the order they were received,
+            // because "sent" timestamps are controlled by the senders.
+            // If one of the messages is not available, the "sent" timestamps are compared
+            // with the Message-ID as a tie-breaker, so that all devices agree on the order.
+            let backwards = match (msg_id, last_msg_id) {
+                (Some(msg_id), Some(last_msg_id)) => msg_id <= last_msg_id,
+                _ => (timestamp_sent, rfc724_mid) <= (last_timestamp_sent, last_rfc724_mid),
+            };
+            if backwards {
+                info!(
+                    context,
+                    "Not moving last read marker of {self} backwards to {rfc724_mid}."
+                );
+                return Ok(());
+            }
+        }
+        chat.param.set(Param::LastReadMsg, rfc724_mid);
+        chat.param.set_i64(Param::LastReadTimestamp, timestamp_sent);
+        chat.update_param(context).await?;
+
+        // If the message is not available yet, messages are marked as noticed once it arrives.
+        if let Some(msg_id) = msg_id {
+            self.notice_until(context, msg_id).await?;
+        }
+
+        if sync.into() {
+            chat.sync(
+                context,
+                SyncAction::SetLastRead {
+                    msg: rfc724_mid.to_string(),
+                    timestamp: timestamp_sent,
+                },
+            )
+            .await
+            .log_err(context)
+            .ok();
+        }
+        Ok(())
+    }
+
+    /// Marks incoming fresh messages of the chat received not later than `msg_id` as noticed.
+    pub(crate) async fn notice_until(self, context: &Context, msg_id: MsgId) -> Result<()> {
+        let noticed_msgs = context
+            .sql
+            .execute(
+                "UPDATE msgs SET state=?
+                 WHERE state=? AND hidden=0 AND chat_id=? AND id<=?",
+                (MessageState::InNoticed, MessageState::InFresh, self, msg_id),
+            )
+            .await?;
+        if noticed_msgs > 0 {
+            start_chat_ephemeral_timers(context, self).await?;
+            context.emit_event(EventType::MsgsNoticed(self));
+            chatlist_events::emit_chatlist_item_changed(context, self);
+            context.on_archived_chats_maybe_noticed();
+        }
+        Ok(())
+    }
+
+    /// Returns the last read message of the chat set on this or another device.
+    ///
+    /// Returns `None` if no marker is set or the message is not available on this device.
+    pub async fn get_last_read_msg(self, context: &Context) -> Result<Option<MsgId>> {
+        let param = self.get_param(context).await?;
+        let Some(rfc724_mid) = param.get(Param::LastReadMsg) else {
+            return Ok(None);
+        };
+        self.get_msg_id_by_rfc724_mid(context, rfc724_mid).await
+    }
+
+    /// Returns whether `rfc724_mid` is the Message-ID of the last read message of the chat.
+    pub(crate) async fn is_last_read_msg(
+        self,
+        context: &Context,
+        rfc724_mid: &str,
+    ) -> Result<bool> {
+        let param = self.get_param(context).await?;
+        Ok(param.get(Param::LastReadMsg) == Some(rfc724_mid))
+    }
+
+    async fn get_msg_id_by_rfc724_mid(
+        self,
+        context: &Context,
+        rfc724_mid: &str,
+    ) -> Result<Option<MsgId>> {
+        context
+            .sql
+            .query_get_value(
+                "SELECT id FROM msgs WHERE chat_id=? AND rfc724_mid=? ORDER BY id LIMIT 1",
+                (self, rfc724_mid),
+            )
+            .await
+    }
+
+    /// Subscribes to the mailing list by sending a request
+    /// to the `mailto:` URI from the `List-Subscribe` header.
+    ///
+    /// An info message is added to the chat.
+    pub async fn subscribe(self, context: &Context) -> Result<()> {
+        let chat = Chat::load_from_db(context, self).await?;
+        ensure!(
+            chat.is_mailing_list(),
+            "Can only subscribe to mailing lists"
+        );
+        let mailto = chat
+            .get_mailinglist_uris(Param::ListSubscribe)
+            .find_map(parse_mailto)
+            .context("Mailing list does not support subscribing by e-mail")?;
+        send_mailinglist_request(context, self, mailto, "subscribe").await?;
+        let text = stock_str::mailinglist_subscribe_sent(context).await;
+        add_info_msg(context, self, &text, time()).await?;
+        Ok(())
+    }
+
+    /// Unsubscribes from the mailing list.
+    ///
+    /// Uses one-click unsubscribing as defined in RFC 8058 if the list supports it,
+    /// otherwise sends a request to the `mailto:` URI from the `List-Unsubscribe` header.
+    /// The result is added to the chat as an info message.
+    pub async fn unsubscribe(self, context: &Context) -> Result<()> {
+        let chat = Chat::load_from_db(context, self).await?;
+        ensure!(
+            chat.is_mailing_list(),
+            "Can only unsubscribe from mailing lists"
+        );
+        let res = if let Some(url) = chat.get_one_click_unsubscribe_url() {
+            match http::post_one_click_unsubscribe(context, url).await {
+                Ok(true) => Ok(()),
+                Ok(false) => Err(anyhow!("The server rejected the request")),
+                Err(err) => Err(err),
+            }
+        } else if let Some(mailto) = chat
+            .get_mailinglist_uris(Param::ListUnsubscribe)
+            .find_map(parse_mailto)
+        {
+            send_mailinglist_request(context, self, mailto, "unsubscribe").await
+        } else {
+            bail!("Mailing list does not support unsubscribing");
+        };
+        let text = match &res {
+            Ok(()) => stock_str::mailinglist_unsubscribed(context).await,
+            Err(err) => {
+                warn!(context, "Failed to unsubscribe from {self}: {err:#}.");
+                stock_str::mailinglist_unsubscribe_failed(context, &format!("{err:#}")).await
+            }
+        };
+        add_info_msg(context, self, &text, time()).await?;
+        res
+    }
+
     /// Unarchives a chat that is archived and not muted.
     /// Needed after a message is added to a chat so that the chat gets a normal visibility again.
     /// `msg_state` is the state of the message. Matters only for incoming messages currently. For
@@ -714,18 +1259,31 @@ pub(crate) async fn set_visibility_ex(
     /// Sending an appropriate event is up to the caller.
     /// Also emits DC_EVENT_MSGS_CHANGED for DC_CHAT_ID_ARCHIVED_LINK when the number of archived
     /// chats with unread messages increases (which is possible if the chat is muted).
+    ///
+    /// The [`UnarchivePolicy`] of the chat is respected.
     pub async fn unarchive_if_not_muted(
         self,
         context: &Context,
         msg_state: MessageState,
+    ) -> Result<()> {
+        self.unarchive_for_msg(context, msg_state, false).await
+    }
+
+    /// Like [`ChatId::unarchive_if_not_muted`],
+    /// `mention` is whether an incoming message mentions self.
+    pub(crate) async fn unarchive_for_msg(
+        self,
+        context: &Context,
+        msg_state: MessageState,
+        mention: bool,
     ) -> Result<()> {
         if msg_state != MessageState::InFresh {
             context
                 .sql
                 .execute(
                     "UPDATE chats SET archived=0 WHERE id=? AND archived=1 \
-                AND NOT(muted_until=-1 OR muted_until>?)",
-                    (self, time()),
+                AND NOT(muted_until=-1 OR muted_until>?) AND unarchive_policy=?",
+                    (self, time(), UnarchivePolicy::AnyMessage as u32),
                 )
                 .await?;
             return Ok(());
@@ -734,6 +1292,12 @@ pub async fn unarchive_if_not_muted(
         if chat.visibility != ChatVisibility::Archived {
             return Ok(());
         }
+        match self.get_unarchive_policy(context).await? {
+            UnarchivePolicy::AnyMessage => {}
+            UnarchivePolicy::Mention if mention => {}
+            // Such chats don't count towards the fresh messages of the archive link.
+            UnarchivePolicy::Mention | UnarchivePolicy::Never => return Ok(()),
+        }
         if chat.is_muted() {
             let unread_cnt = context
                 .sql
@@ -760,9 +1324,15 @@ pub async fn unarchive_if_not_muted(
     }
 
     /// Emits an appropriate event for a message. `important` is whether a notification should be
-    /// shown.
-    pub(crate) fn emit_msg_event(self, context: &Context, msg_id: MsgId, important: bool) {
-        if important {
+    /// shown, unless the do-not-disturb schedule is active, see [`Context::is_dnd_active`].
+    pub(crate) async fn emit_msg_event(self, context: &Context, msg_id: MsgId, important: bool) {
+        if important
+            && !context
+                .is_dnd_active()
+                .await
+                .log_err(context)
+                .unwrap_or_default()
+        {
             debug_assert!(!msg_id.is_unset());
 
             context.emit_incoming_msg(self, msg_id);
@@ -790,6 +1360,9 @@ pub async fn delete(self, context: &Context) -> Result<()> {
                 )?;
                 transaction.execute("DELETE FROM msgs WHERE chat_id=?", (self,))?;
                 transaction.execute("DELETE FROM chats_contacts WHERE chat_id=?", (self,))?;
+                transaction.execute("DELETE FROM chats_admins WHERE chat_id=?", (self,))?;
+                transaction.execute("DELETE FROM protection_log WHERE chat_id=?", (self,))?;
+                transaction.execute("DELETE FROM chat_custom_headers WHERE chat_id=?", (self,))?;
                 transaction.execute("DELETE FROM chats WHERE id=?", (self,))?;
                 Ok(())
             })
@@ -1007,6 +1580,13 @@ pub async fn get_msg_cnt(self, context: &Context) -> Result<usize> {
     }
 
     /// Returns the number of fresh messages in the chat.
+    ///
+    /// Incoming reactions to own messages which were not noticed yet are added
+    /// if [`Chat::reactions_count_as_fresh`] returns true.
+    /// Chats marked as unread with [`ChatId::set_marked_unread`] count at least 1.
+    /// For the archive link, only the number of archived chats
+    /// with fresh messages or marked as unread is returned,
+    /// chats with an [`UnarchivePolicy`] other than the default are only counted if marked.
     pub async fn get_fresh_msg_cnt(self, context: &Context) -> Result<usize> {
         // this function is typically used to show a badge counter beside _each_ chatlist item.
         // to make this as fast as possible, esp. on older devices, we added an combined index over the rows used for querying.
@@ -1022,34 +1602,183 @@ pub async fn get_fresh_msg_cnt(self, context: &Context) -> Result<usize> {
             context
                 .sql
                 .count(
-                    "SELECT COUNT(DISTINCT(m.chat_id))
+                    "SELECT COUNT(*) FROM (
+                    SELECT m.chat_id
                     FROM msgs m
                     LEFT JOIN chats c ON m.chat_id=c.id
                     WHERE m.state=10
                     and m.hidden=0
+                    AND m.hidden_local=0
                     AND m.chat_id>9
                     AND c.blocked=0
                     AND c.archived=1
-                    ",
+                    AND c.unarchive_policy=0
+                    UNION
+                    SELECT id FROM chats
+                    WHERE marked_unread=1
+                    AND blocked=0
+                    AND archived=1
+                    )",
                     (),
                 )
                 .await?
         } else {
-            context
+            // Fresh reactions and the unread mark are queried together with fresh messages
+            // so that there is only a single query per chatlist item.
+            let (msgs, reactions, marked_unread) = context
                 .sql
-                .count(
-                    "SELECT COUNT(*)
-                FROM msgs
-                WHERE state=?
-                AND hidden=0
-                AND chat_id=?;",
-                    (MessageState::InFresh, self),
+                .query_row(
+                    "SELECT
+                     (SELECT COUNT(*)
+                      FROM msgs
+                      WHERE state=?
+                      AND hidden=0
+                      AND hidden_local=0
+                      AND chat_id=?),
+                     (SELECT COUNT(*)
+                      FROM reactions r
+                      INNER JOIN msgs m ON r.msg_id=m.id
+                      WHERE r.fresh=1 AND m.chat_id=?),
+                     (SELECT marked_unread FROM chats WHERE id=?)",
+                    (MessageState::InFresh, self, self, self),
+                    |row| {
+                        let msgs: usize = row.get(0)?;
+                        let reactions: usize = row.get(1)?;
+                        let marked_unread: Option<bool> = row.get(2)?;
+                        Ok((msgs, reactions, marked_unread.unwrap_or_default()))
+                    },
                 )
-                .await?
+                .await?;
+            // The chat is only loaded if there are fresh reactions.
+            let reactions = if reactions > 0
+                && Chat::load_from_db(context, self)
+                    .await?
+                    .reactions_count_as_fresh(context)
+                    .await?
+            {
+                reactions
+            } else {
+                0
+            };
+            let count = msgs + reactions;
+            if count == 0 && marked_unread {
+                return Ok(1);
+            }
+            count
         };
         Ok(count)
     }
 
+    /// Marks the chat as unread intentionally or removes the mark.
+    ///
+    /// The mark is removed when the chat is noticed, see [`marknoticed_chat`],
+    /// and is synchronized to other devices.
+    pub async fn set_marked_unread(self, context: &Context, unread: bool) -> Result<()> {
+        self.set_marked_unread_ex(context, Sync, unread).await
+    }
+
+    pub(crate) async fn set_marked_unread_ex(
+        self,
+        context: &Context,
+        sync: sync::Sync,
+        unread: bool,
+    ) -> Result<()> {
+        ensure!(!self.is_special(), "Invalid chat ID");
+        context
+            .sql
+            .execute(
+                "UPDATE chats SET marked_unread=? WHERE id=?",
+                (unread, self),
+            )
+            .await?;
+        context.emit_event(EventType::ChatModified(self));
+        chatlist_events::emit_chatlist_item_changed(context, self);
+        context.on_archived_chats_maybe_noticed();
+
+        if sync.into() {
+            let chat = Chat::load_from_db(context, self).await?;
+            chat.sync(context, SyncAction::SetMarkedUnread(unread))
+                .await
+                .log_err(context)
+                .ok();
+        }
+        Ok(())
+    }
+
+    /// Returns true if the chat is marked as unread, see [`ChatId::set_marked_unread`].
+    pub async fn is_marked_unread(self, context: &Context) -> Result<bool> {
+        let unread = context
+            .sql
+            .query_get_value("SELECT marked_unread FROM chats WHERE id=?", (self,))
+            .await?
+            .unwrap_or_default();
+        Ok(unread)
+    }
+
+    /// Sets whether the chat is unarchived by new messages while it is archived.
+    ///
+    /// Archived chats with a policy other than [`UnarchivePolicy::AnyMessage`]
+    /// don't count towards the fresh messages of the archive link
+    /// and are not returned by [`Context::get_fresh_msgs`].
+    /// The policy is synchronized to other devices.
+    pub async fn set_unarchive_policy(
+        self,
+        context: &Context,
+        policy: UnarchivePolicy,
+    ) -> Result<()> {
+        self.set_unarchive_policy_ex(context, Sync, policy).await
+    }
+
+    pub(crate) async fn set_unarchive_policy_ex(
+        self,
+        context: &Context,
+        sync: sync::Sync,
+        policy: UnarchivePolicy,
+    ) -> Result<()> {
+        ensure!(!self.is_special(), "Invalid chat ID");
+        context
+            .sql
+            .execute(
+                "UPDATE chats SET unarchive_policy=? WHERE id=?",
+                (policy as u32, self),
+            )
+            .await?;
+        context.emit_event(EventType::ChatModified(self));
+        context.on_archived_chats_maybe_noticed();
+
+        if sync.into() {
+            let chat = Chat::load_from_db(context, self).await?;
+            chat.sync(context, SyncAction::SetUnarchivePolicy(policy))
+                .await
+                .log_err(context)
+                .ok();
+        }
+        Ok(())
+    }
+
+    /// Returns true if the chat is archived and only unarchived by mentions,
+    /// i.e. new messages need to be checked with [`is_mention`].
+    pub(crate) async fn is_archived_until_mention(self, context: &Context) -> Result<bool> {
+        context
+            .sql
+            .exists(
+                "SELECT COUNT(*) FROM chats WHERE id=? AND archived=1 AND unarchive_policy=?",
+                (self, UnarchivePolicy::Mention as u32),
+            )
+            .await
+    }
+
+    /// Returns the [`UnarchivePolicy`] of the chat.
+    pub async fn get_unarchive_policy(self, context: &Context) -> Result<UnarchivePolicy> {
+        let policy: Option<u32> = context
+            .sql
+            .query_get_value("SELECT unarchive_policy FROM chats WHERE id=?", (self,))
+            .await?;
+        Ok(policy
+            .and_then(UnarchivePolicy::from_u32)
+            .unwrap_or_default())
+    }
+
     /// Returns timestamp of the latest message in the chat.
     pub(crate) async fn get_timestamp(self, context: &Context) -> Result<Option<i64>> {
         let timestamp = context
@@ -1211,6 +1940,129 @@ pub(crate) async fn is_promoted(self, context: &Context) -> Result<bool> {
         Ok(promoted)
     }
 
+    /// Enables or disables requesting read receipts for outgoing messages in the chat.
+    ///
+    /// If disabled, outgoing messages do not contain `Chat-Disposition-Notification-To` header,
+    /// so the recipients never send read receipts for them.
+    /// This is independent from the `mdns_enabled` config
+    /// which also controls whether read receipts are sent.
+    pub async fn set_mdn_requests_disabled(self, context: &Context, disabled: bool) -> Result<()> {
+        ensure!(!self.is_special(), "Invalid chat ID");
+        let mut chat = Chat::load_from_db(context, self).await?;
+        if disabled {
+            chat.param.set_int(Param::DisableMdnRequests, 1);
+        } else {
+            chat.param.remove(Param::DisableMdnRequests);
+        }
+        chat.update_param(context).await?;
+        context.emit_event(EventType::ChatModified(self));
+        Ok(())
+    }
+
+    /// Enables or disables requiring end-to-end encryption for outgoing messages in the chat.
+    ///
+    /// If enabled, sending a message that cannot be encrypted fails
+    /// instead of sending it unencrypted,
+    /// and an info message is added when encryption becomes available or unavailable.
+    pub async fn set_encryption_required(self, context: &Context, required: bool) -> Result<()> {
+        ensure!(!self.is_special(), "Invalid chat ID");
+        let mut chat = Chat::load_from_db(context, self).await?;
+        if required {
+            chat.param.set_int(Param::EncryptionRequired, 1);
+        } else {
+            chat.param.remove(Param::EncryptionRequired);
+            chat.param.remove(Param::EncryptionAvailable);
+        }
+        chat.update_param(context).await?;
+        if required {
+            self.update_encryption_availability(context).await?;
+        }
+        context.emit_event(EventType::ChatModified(self));
+        Ok(())
+    }
+
+    /// Sets a custom header added to all outgoing messages of the chat,
+    /// or removes it if `value` is `None`.
+    ///
+    /// The header name must start with `X-`
+    /// and be listed in [`Config::AllowedCustomHeaders`].
+    /// The value must be a single line of at most 200 bytes.
+    pub async fn set_custom_header(
+        self,
+        context: &Context,
+        name: &str,
+        value: Option<&str>,
+    ) -> Result<()> {
+        ensure!(!self.is_special(), "Invalid chat ID");
+        ensure!(
+            is_allowed_custom_header(context, name).await?,
+            "Custom header {name:?} is not allowed"
+        );
+        if let Some(value) = value {
+            ensure!(
+                !value.is_empty() && value.len() <= 200 && !value.chars().any(char::is_control),
+                "Invalid value for custom header {name:?}"
+            );
+            context
+                .sql
+                .execute(
+                    "INSERT INTO chat_custom_headers (chat_id, name, value) VALUES (?, ?, ?)
+                     ON CONFLICT (chat_id, name) DO UPDATE SET name=excluded.name, value=excluded.value",
+                    (self, name, value),
+                )
+                .await?;
+        } else {
+            context
+                .sql
+                .execute(
+                    "DELETE FROM chat_custom_headers WHERE chat_id=? AND name=?",
+                    (self, name),
+                )
+                .await?;
+        }
+        context.emit_event(EventType::ChatModified(self));
+        Ok(())
+    }
+
+    /// Returns the custom headers added to outgoing messages of the chat
+    /// as `(name, value)` pairs.
+    ///
+    /// See [`ChatId::set_custom_header`].
+    pub async fn get_custom_headers(self, context: &Context) -> Result<Vec<(String, String)>> {
+        context
+            .sql
+            .query_map(
+                "SELECT name, value FROM chat_custom_headers WHERE chat_id=? ORDER BY name",
+                (self,),
+                |row| Ok((row.get(0)?, row.get(1)?)),
+                |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+            )
+            .await
+    }
+
+    /// Checks whether messages to a chat requiring encryption can be encrypted
+    /// and adds an info message if this changed since the last check.
+    pub(crate) async fn update_encryption_availability(self, context: &Context) -> Result<bool> {
+        let available = self.get_encryption_details(context).await?.can_encrypt;
+        let mut chat = Chat::load_from_db(context, self).await?;
+        let previous = chat.param.get_bool(Param::EncryptionAvailable);
+        if previous != Some(available) {
+            chat.param
+                .set_int(Param::EncryptionAvailable, i32::from(available));
+            chat.update_param(context).await?;
+            // Do not tell that encryption is available when the setting is just enabled.
+            if previous.is_some() || !available {
+                let text = if available {
+                    stock_str::encryption_available(context).await
+                } else {
+                    stock_str::encryption_unavailable(context).await
+                };
+                add_info_msg(context, self, &text, create_smeared_timestamp(context)).await?;
+            }
+        }
+        Ok(available)
+    }
+
     /// Returns true if chat is a saved messages chat.
     pub async fn is_self_talk(self, context: &Context) -> Result<bool> {
         Ok(self.get_param(context).await?.exists(Param::Selftalk))
@@ -1335,6 +2187,112 @@ pub async fn get_encryption_info(self, context: &Context) -> Result<String> {
         Ok(ret.trim().to_string())
     }
 
+    /// Returns the encryption state of each chat member
+    /// and whether messages sent to the chat are encrypted.
+    ///
+    /// Unlike [`ChatId::get_encryption_info`], which returns a text,
+    /// this allows UIs to explain why a message would be sent unencrypted.
+    pub async fn get_encryption_details(self, context: &Context) -> Result<ChatEncryptionInfo> {
+        let chat = Chat::load_from_db(context, self).await?;
+        let mut members = Vec::new();
+        let mut peerstates = Vec::new();
+        for contact_id in get_chat_contacts(context, self)
+            .await?
+            .into_iter()
+            .filter(|contact_id| !contact_id.is_special())
+        {
+            let contact = Contact::get_by_id(context, contact_id).await?;
+            let addr = contact.get_addr().to_string();
+            let peerstate = Peerstate::from_addr(context, &addr)
+                .await?
+                .filter(|peerstate| peerstate.peek_key(false).is_some());
+            members.push(MemberEncryptionInfo {
+                contact_id,
+                addr: addr.clone(),
+                key_available: peerstate.is_some(),
+                verified: contact.is_verified(context).await?,
+                prefer_encrypt: peerstate
+                    .as_ref()
+                    .map_or(EncryptPreference::Reset, |peerstate| {
+                        peerstate.prefer_encrypt
+                    }),
+                force_plaintext: contact.get_encryption_override()
+                    == EncryptionOverride::ForcePlaintext,
+            });
+            peerstates.push((peerstate, addr));
+        }
+
+        // Broadcast lists are always sent unencrypted, see `MimeFactory::should_force_plaintext()`.
+        let e2ee_guaranteed = chat.is_protected() || chat.is_encryption_required();
+        let can_encrypt = !chat.is_device_talk()
+            && chat.typ != Chattype::Broadcast
+            && (e2ee_guaranteed
+                || chat.typ != Chattype::Single
+                || !members.iter().any(|member| member.force_plaintext))
+            && members.iter().all(|member| member.key_available)
+            && EncryptHelper::new(context)
+                .await?
+                .should_encrypt(context, e2ee_guaranteed, &peerstates)
+                .await
+                .unwrap_or(false);
+        Ok(ChatEncryptionInfo {
+            members,
+            can_encrypt,
+        })
+    }
+
+    /// Returns the audit trail of protection-relevant changes in the chat,
+    /// oldest first.
+    ///
+    /// This allows UIs to explain why a chat became protected or unprotected
+    /// and who changed the members of a protected group.
+    pub async fn get_protection_log(self, context: &Context) -> Result<Vec<ProtectionLogEntry>> {
+        context
+            .sql
+            .query_map(
+                "SELECT timestamp, event, contact_id, target_id
+                 FROM protection_log WHERE chat_id=? ORDER BY id",
+                (self,),
+                |row| {
+                    let target_id: ContactId = row.get(3)?;
+                    Ok(ProtectionLogEntry {
+                        timestamp: row.get(0)?,
+                        event: row.get(1)?,
+                        contact_id: row.get(2)?,
+                        target_id: Some(target_id).filter(|id| *id != ContactId::UNDEFINED),
+                    })
+                },
+                |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+            )
+            .await
+    }
+
+    /// Adds an entry to the protection log of the chat.
+    pub(crate) async fn add_protection_log(
+        self,
+        context: &Context,
+        event: ProtectionLogEvent,
+        contact_id: ContactId,
+        target_id: Option<ContactId>,
+        timestamp: i64,
+    ) -> Result<()> {
+        context
+            .sql
+            .execute(
+                "INSERT INTO protection_log (chat_id, timestamp, event, contact_id, target_id)
+                 VALUES (?, ?, ?, ?, ?)",
+                (
+                    self,
+                    timestamp,
+                    event,
+                    contact_id,
+                    target_id.unwrap_or(ContactId::UNDEFINED),
+                ),
+            )
+            .await?;
+        Ok(())
+    }
+
     /// Bad evil escape hatch.
     ///
     /// Avoid using this, eventually types should be cleaned up enough
@@ -1816,6 +2774,55 @@ pub fn get_mailinglist_addr(&self) -> Option<&str> {
         self.param.get(Param::ListPost)
     }
 
+    /// Returns true if the mailing list can be unsubscribed from
+    /// using [`ChatId::unsubscribe`].
+    pub fn can_unsubscribe(&self) -> bool {
+        self.get_one_click_unsubscribe_url().is_some()
+            || self
+                .get_mailinglist_uris(Param::ListUnsubscribe)
+                .any(|uri| parse_mailto(uri).is_some())
+    }
+
+    /// Returns the URIs of a `List-*` header stored in the given param.
+    fn get_mailinglist_uris(&self, key: Param) -> impl Iterator<Item = &str> {
+        self.param
+            .get(key)
+            .unwrap_or_default()
+            .split_ascii_whitespace()
+    }
+
+    /// Returns the HTTPS URL for one-click unsubscribing as defined in RFC 8058
+    /// if the mailing list supports it.
+    fn get_one_click_unsubscribe_url(&self) -> Option<&str> {
+        if !self
+            .param
+            .get_bool(Param::ListUnsubscribeOneClick)
+            .unwrap_or_default()
+        {
+            return None;
+        }
+        self.get_mailinglist_uris(Param::ListUnsubscribe)
+            .find(|uri| uri.to_ascii_lowercase().starts_with("https:"))
+    }
+
+    /// Returns where replies in a mailing list chat are sent to.
+    pub fn get_mailinglist_reply_mode(&self) -> MailinglistReplyMode {
+        self.param
+            .get_int(Param::MailinglistReplyMode)
+            .and_then(MailinglistReplyMode::from_i32)
+            .unwrap_or_default()
+    }
+
+    /// Returns true if incoming reactions to own messages count towards
+    /// the fresh message counter of the chat,
+    /// see [`ChatId::set_reactions_count_as_fresh`].
+    pub async fn reactions_count_as_fresh(&self, context: &Context) -> Result<bool> {
+        match self.param.get_bool(Param::ReactionsCountAsFresh) {
+            Some(count_as_fresh) => Ok(count_as_fresh),
+            None => context.get_config_bool(Config::ReactionsCountAsFresh).await,
+        }
+    }
+
     /// Returns profile image path for the chat.
     pub async fn get_profile_image(&self, context: &Context) -> Result<Option<PathBuf>> {
         if let Some(image_rel) = self.param.get(Param::ProfileImage) {
@@ -1928,6 +2935,16 @@ pub fn is_protected(&self) -> bool {
         self.protected == ProtectionStatus::Protected
     }
 
+    /// Returns true if only admins may change members, name and avatar of the group,
+    /// see [`set_group_moderated`].
+    pub fn is_moderated(&self) -> bool {
+        self.typ == Chattype::Group
+            && self
+                .param
+                .get_bool(Param::GroupModerated)
+                .unwrap_or_default()
+    }
+
     /// Returns true if the chat was protected, and then an incoming message broke this protection.
     ///
     /// This function is only useful if the UI enabled the `verified_one_on_one_chats` feature flag,
@@ -1949,6 +2966,24 @@ pub fn is_protection_broken(&self) -> bool {
         }
     }
 
+    /// Returns true if read receipts are not requested for outgoing messages in the chat.
+    ///
+    /// See [`ChatId::set_mdn_requests_disabled`].
+    pub fn is_mdn_requests_disabled(&self) -> bool {
+        self.param
+            .get_bool(Param::DisableMdnRequests)
+            .unwrap_or_default()
+    }
+
+    /// Returns true if outgoing messages must be end-to-end encrypted.
+    ///
+    /// See [`ChatId::set_encryption_required`].
+    pub fn is_encryption_required(&self) -> bool {
+        self.param
+            .get_bool(Param::EncryptionRequired)
+            .unwrap_or_default()
+    }
+
     /// Returns true if location streaming is enabled in the chat.
     pub fn is_sending_locations(&self) -> bool {
         self.is_sending_locations
@@ -2006,7 +3041,7 @@ async fn prepare_msg_raw(
         let mut to_id = 0;
         let mut location_id = 0;
 
-        let new_rfc724_mid = create_outgoing_rfc724_mid();
+        let new_rfc724_mid = create_rfc724_mid(context);
 
         if self.typ == Chattype::Single {
             if let Some(id) = context
@@ -2029,6 +3064,7 @@ async fn prepare_msg_raw(
             && self.param.get_int(Param::Unpromoted).unwrap_or_default() == 1
         {
             msg.param.set_int(Param::AttachGroupImage, 1);
+            msg.param.set_int(Param::GroupPromotion, 1);
             self.param.remove(Param::Unpromoted);
             self.update_param(context).await?;
             // TODO: Remove this compat code needed because Core <= v1.143:
@@ -2088,10 +3124,30 @@ async fn prepare_msg_raw(
                 parent_references
             };
 
+            let mua_threading_compat = context.get_config_bool(Config::MuaThreadingCompat).await?;
+
             // The whole list of messages referenced may be huge.
-            // Only take 2 recent references and add third from `In-Reply-To`.
-            let mut references_vec: Vec<&str> = parent_references.rsplit(' ').take(2).collect();
-            references_vec.reverse();
+            let mut references_vec: Vec<&str> = if mua_threading_compat {
+                // Classic MUAs use the first reference to find the root of the thread,
+                // so keep it and take as many recent references as possible.
+                let parent_references: Vec<&str> =
+                    parent_references.split_ascii_whitespace().collect();
+                let recent_start = parent_references
+                    .len()
+                    .saturating_sub(MUA_COMPAT_RECENT_REFERENCES)
+                    .max(1);
+                parent_references
+                    .iter()
+                    .take(1)
+                    .chain(parent_references.iter().skip(recent_start))
+                    .copied()
+                    .collect()
+            } else {
+                // Only take 2 recent references and add third from `In-Reply-To`.
+                let mut references_vec: Vec<&str> = parent_references.rsplit(' ').take(2).collect();
+                references_vec.reverse();
+                references_vec
+            };
 
             if !parent_rfc724_mid.is_empty()
                 && !references_vec.contains(&parent_rfc724_mid.as_str())
@@ -2099,6 +3155,15 @@ async fn prepare_msg_raw(
                 references_vec.push(&parent_rfc724_mid)
             }
 
+            if mua_threading_compat {
+                // MUAs expect the message replied to at the end of `References:`,
+                // even if it is not the latest message in the chat.
+                if let Some(in_reply_to) = msg.in_reply_to.as_deref() {
+                    references_vec.retain(|mid| *mid != in_reply_to);
+                    references_vec.push(in_reply_to);
+                }
+            }
+
             if references_vec.is_empty() {
                 // As a fallback, use our Message-ID,
                 // same as in the case of top-level message.
@@ -2877,6 +3942,10 @@ pub async fn send_msg(context: &Context, chat_id: ChatId, msg: &mut Message) ->
 
         context.scheduler.interrupt_smtp().await;
     }
+    ocr::handle_new_msg(context, msg.id)
+        .await
+        .log_err(context)
+        .ok();
 
     Ok(msg.id)
 }
@@ -2911,6 +3980,13 @@ async fn prepare_send_msg(
     let mut chat = Chat::load_from_db(context, chat_id).await?;
 
     let skip_fn = |reason: &CantSendReason| match reason {
+        // Allow to subscribe and unsubscribe from mailing lists
+        // which are read-only or not accepted yet.
+        CantSendReason::ContactRequest | CantSendReason::ReadOnlyMailingList
+            if msg.param.exists(Param::MailinglistRecipient) =>
+        {
+            true
+        }
         CantSendReason::ProtectionBroken
         | CantSendReason::ContactRequest
         | CantSendReason::SecurejoinWait => {
@@ -2928,6 +4004,16 @@ async fn prepare_send_msg(
         bail!("Cannot send to {chat_id}: {reason}");
     }
 
+    if chat.is_encryption_required()
+        && !msg
+            .param
+            .get_bool(Param::ForcePlaintext)
+            .unwrap_or_default()
+        && !chat_id.update_encryption_availability(context).await?
+    {
+        bail!("Cannot send to {chat_id}: End-to-end encryption is required but not available");
+    }
+
     // Check a quote reply is not leaking data from other chats.
     // This is meant as a last line of defence, the UI should check that before as well.
     // (We allow Chattype::Single in general for "Reply Privately";
@@ -2984,7 +4070,15 @@ pub(crate) async fn create_send_msg_jobs(context: &Context, msg: &mut Message) -
     let needs_encryption = msg.param.get_bool(Param::GuaranteeE2ee).unwrap_or_default();
     let mimefactory = MimeFactory::from_msg(context, msg.clone()).await?;
     let attach_selfavatar = mimefactory.attach_selfavatar;
+    let request_profile_from = mimefactory.request_profile_from;
+    let has_avatar_requests = mimefactory.avatar_requests.is_some();
     let mut recipients = mimefactory.recipients();
+    if mimefactory.is_broadcast() {
+        // Recipients of broadcast lists are hidden, remember their number for the statistics.
+        msg.param
+            .set_int(Param::BroadcastRecipients, recipients.len().try_into()?);
+        msg.update_param(context).await?;
+    }
 
     let from = context.get_primary_self_addr().await?;
     let lowercase_from = from.to_lowercase();
@@ -3066,14 +4160,42 @@ pub(crate) async fn create_send_msg_jobs(context: &Context, msg: &mut Message) -
         }
     }
 
+    if let Some(contact_id) = request_profile_from {
+        context
+            .update_contacts_timestamp(contact_id, Param::ProfileRequestTimestamp, now)
+            .await?;
+    }
+
+    if has_avatar_requests {
+        let mut chat = Chat::load_from_db(context, msg.chat_id).await?;
+        chat.param.remove(Param::AvatarRequests);
+        chat.update_param(context).await?;
+    }
+
     if rendered_msg.is_encrypted && !needs_encryption {
         msg.param.set_int(Param::GuaranteeE2ee, 1);
         msg.update_param(context).await?;
     }
 
+    if msg.param.get_cmd() == SystemMessage::SecurejoinMessage
+        || (msg.param.get_cmd() == SystemMessage::MemberAddedToGroup
+            && msg.param.get_bool(Param::Arg2).unwrap_or_default())
+    {
+        // The email is sent as usual, but may be received over the local network earlier.
+        if peer_channels::send_securejoin_local(context, msg, rendered_msg.message.as_bytes())
+            .await
+            .log_err(context)
+            .unwrap_or_default()
+            && msg.add_delivery_path(DeliveryPath::PeerChannel)
+        {
+            msg.update_param(context).await?;
+        }
+    }
+
     msg.subject.clone_from(&rendered_msg.subject);
     msg.update_subject(context).await?;
     let chunk_size = context.get_max_smtp_rcpt_to().await?;
+    let priority = SendPriority::of_msg(msg).to_i64();
     let trans_fn = |t: &mut rusqlite::Transaction| {
         let mut row_ids = Vec::<i64>::new();
         if let Some(sync_ids) = rendered_msg.sync_ids_to_delete {
@@ -3089,13 +4211,14 @@ pub(crate) async fn create_send_msg_jobs(context: &Context, msg: &mut Message) -
             for recipients_chunk in recipients.chunks(chunk_size) {
                 let recipients_chunk = recipients_chunk.join(" ");
                 let row_id = t.execute(
-                    "INSERT INTO smtp (rfc724_mid, recipients, mime, msg_id) \
-                    VALUES            (?1,         ?2,         ?3,   ?4)",
+                    "INSERT INTO smtp (rfc724_mid, recipients, mime, msg_id, priority) \
+                    VALUES            (?1,         ?2,         ?3,   ?4,     ?5)",
                     (
                         &rendered_msg.rfc724_mid,
                         recipients_chunk,
                         &rendered_msg.message,
                         msg.id,
+                        priority,
                     ),
                 )?;
                 row_ids.push(row_id.try_into()?);
@@ -3158,33 +4281,124 @@ pub struct MessageListOptions {
     /// Return only info messages.
     pub info_only: bool,
 
-    /// Add day markers before each date regarding the local timezone.
+    /// Add day markers before each date regarding the local timezone
+    /// or [`Config::Timezone`] if set.
     pub add_daymarker: bool,
+
+    /// Include messages hidden locally with [`message::set_hidden_local`].
+    pub include_hidden_local: bool,
 }
 
-/// Returns all messages belonging to the chat.
-pub async fn get_chat_msgs(context: &Context, chat_id: ChatId) -> Result<Vec<ChatItem>> {
-    get_chat_msgs_ex(
-        context,
-        chat_id,
-        MessageListOptions {
-            info_only: false,
-            add_daymarker: false,
-        },
-    )
-    .await
+/// Calendar splitting chat messages into days.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum DayCalendar {
+    /// System timezone, taking daylight saving time changes into account.
+    Local,
+
+    /// Timezone set with [`Config::Timezone`] as an IANA name,
+    /// taking daylight saving time changes into account.
+    Zone(Tz),
+
+    /// Fixed UTC offset set with [`Config::Timezone`].
+    Fixed(FixedOffset),
 }
 
-/// Returns messages belonging to the chat according to the given options.
-pub async fn get_chat_msgs_ex(
-    context: &Context,
-    chat_id: ChatId,
-    options: MessageListOptions,
-) -> Result<Vec<ChatItem>> {
-    let MessageListOptions {
-        info_only,
-        add_daymarker,
+impl DayCalendar {
+    pub(crate) async fn load(context: &Context) -> Result<Self> {
+        let Some(timezone) = context.get_config(Config::Timezone).await? else {
+            return Ok(Self::Local);
+        };
+        match Self::parse(&timezone) {
+            Ok(calendar) => Ok(calendar),
+            Err(err) => {
+                warn!(context, "Ignoring invalid timezone {timezone:?}: {err:#}.");
+                Ok(Self::Local)
+            }
+        }
+    }
+
+    /// Parses [`Config::Timezone`] value,
+    /// either an IANA timezone name such as `Europe/Berlin`
+    /// or a fixed UTC offset such as `+02:00`.
+    pub(crate) fn parse(timezone: &str) -> Result<Self> {
+        if let Ok(tz) = timezone.parse::<Tz>() {
+            return Ok(Self::Zone(tz));
+        }
+        match timezone.parse::<FixedOffset>() {
+            Ok(offset) => Ok(Self::Fixed(offset)),
+            Err(_) => bail!("Timezone must be an IANA timezone name such as Europe/Berlin"),
+        }
+    }
+
+    /// Returns the timestamp of the start of the day containing `timestamp`.
+    fn day_start(self, timestamp: i64) -> i64 {
+        match self {
+            Self::Local => day_start(&chrono::Local, timestamp),
+            Self::Zone(tz) => day_start(&tz, timestamp),
+            Self::Fixed(offset) => day_start(&offset, timestamp),
+        }
+    }
+
+    /// Returns the local date and time of `timestamp`.
+    pub(crate) fn local_datetime(self, timestamp: i64) -> Option<NaiveDateTime> {
+        match self {
+            Self::Local => chrono::Local
+                .timestamp_opt(timestamp, 0)
+                .single()
+                .map(|datetime| datetime.naive_local()),
+            Self::Zone(tz) => tz
+                .timestamp_opt(timestamp, 0)
+                .single()
+                .map(|datetime| datetime.naive_local()),
+            Self::Fixed(offset) => offset
+                .timestamp_opt(timestamp, 0)
+                .single()
+                .map(|datetime| datetime.naive_local()),
+        }
+    }
+}
+
+fn day_start<Tz: TimeZone>(tz: &Tz, timestamp: i64) -> i64 {
+    let Some(datetime) = tz.timestamp_opt(timestamp, 0).single() else {
+        return timestamp;
+    };
+    let midnight = datetime.date_naive().and_time(NaiveTime::MIN);
+    // Where clocks are set forward at midnight,
+    // the day starts with the first local time that exists.
+    (0..24)
+        .find_map(|hour| {
+            tz.from_local_datetime(&(midnight + TimeDelta::hours(hour)))
+                .earliest()
+        })
+        .map_or(timestamp, |start| start.timestamp())
+}
+
+/// Returns all messages belonging to the chat.
+pub async fn get_chat_msgs(context: &Context, chat_id: ChatId) -> Result<Vec<ChatItem>> {
+    get_chat_msgs_ex(
+        context,
+        chat_id,
+        MessageListOptions {
+            info_only: false,
+            add_daymarker: false,
+            include_hidden_local: false,
+        },
+    )
+    .await
+}
+
+/// Returns messages belonging to the chat according to the given options.
+pub async fn get_chat_msgs_ex(
+    context: &Context,
+    chat_id: ChatId,
+    options: MessageListOptions,
+) -> Result<Vec<ChatItem>> {
+    let MessageListOptions {
+        info_only,
+        add_daymarker,
+        include_hidden_local,
     } = options;
+    let calendar = DayCalendar::load(context).await?;
     let process_row = if info_only {
         |row: &rusqlite::Row| {
             // is_info logic taken from Message.is_info()
@@ -3231,18 +4445,16 @@ pub async fn get_chat_msgs_ex(
         sorted_rows.sort_unstable();
 
         let mut ret = Vec::new();
-        let mut last_day = 0;
-        let cnv_to_local = gm2local_offset();
+        let mut last_day_start = None;
 
         for (ts, curr_id) in sorted_rows {
             if add_daymarker {
-                let curr_local_timestamp = ts + cnv_to_local;
-                let curr_day = curr_local_timestamp / 86400;
-                if curr_day != last_day {
+                let day_start = calendar.day_start(ts);
+                if last_day_start != Some(day_start) {
                     ret.push(ChatItem::DayMarker {
-                        timestamp: curr_day * 86400, // Convert day back to Unix timestamp
+                        timestamp: day_start,
                     });
-                    last_day = curr_day;
+                    last_day_start = Some(day_start);
                 }
             }
             ret.push(ChatItem::Message { msg_id: curr_id });
@@ -3259,12 +4471,18 @@ pub async fn get_chat_msgs_ex(
                FROM msgs m
               WHERE m.chat_id=?
                 AND m.hidden=0
+                AND (m.hidden_local=0 OR ?)
                 AND (
                     m.param GLOB \"*S=*\"
                     OR m.from_id == ?
                     OR m.to_id == ?
                 );",
-                (chat_id, ContactId::INFO, ContactId::INFO),
+                (
+                    chat_id,
+                    include_hidden_local,
+                    ContactId::INFO,
+                    ContactId::INFO,
+                ),
                 process_row,
                 process_rows,
             )
@@ -3276,8 +4494,9 @@ pub async fn get_chat_msgs_ex(
                 "SELECT m.id AS id, m.timestamp AS timestamp
                FROM msgs m
               WHERE m.chat_id=?
-                AND m.hidden=0;",
-                (chat_id,),
+                AND m.hidden=0
+                AND (m.hidden_local=0 OR ?);",
+                (chat_id, include_hidden_local),
                 process_row,
                 process_rows,
             )
@@ -3286,12 +4505,273 @@ pub async fn get_chat_msgs_ex(
     Ok(items)
 }
 
+/// Returns up to `limit` messages of the chat sorted before `before`,
+/// as pairs of timestamp and message ID, oldest first.
+///
+/// Messages are sorted by timestamp and ID like in [`get_chat_msgs`].
+/// If `before` is `None`, the newest messages are returned.
+/// Passing the first returned pair as `before` returns the previous page,
+/// which is not affected by messages added to the end of the chat meanwhile.
+pub async fn get_chat_msgs_before(
+    context: &Context,
+    chat_id: ChatId,
+    before: Option<(i64, MsgId)>,
+    limit: usize,
+) -> Result<Vec<(i64, MsgId)>> {
+    let (before_timestamp, before_id) = before.unwrap_or((i64::MAX, MsgId::new(u32::MAX)));
+    let mut msgs = context
+        .sql
+        .query_map(
+            "SELECT timestamp, id FROM msgs
+             WHERE chat_id=? AND hidden=0 AND hidden_local=0 AND (timestamp, id) < (?, ?)
+             ORDER BY timestamp DESC, id DESC
+             LIMIT ?",
+            (
+                chat_id,
+                before_timestamp,
+                before_id,
+                i64::try_from(limit).unwrap_or(i64::MAX),
+            ),
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, MsgId>(1)?)),
+            |rows| {
+                rows.collect::<std::result::Result<Vec<_>, _>>()
+                    .map_err(Into::into)
+            },
+        )
+        .await?;
+    msgs.reverse();
+    Ok(msgs)
+}
+
+/// Returns the day boundaries of chat messages with timestamps in the given range.
+///
+/// Each boundary corresponds to a day marker which [`get_chat_msgs_ex`] adds
+/// before the first message of a day.
+/// This allows UIs to place day markers in virtualized lists
+/// without loading the whole message list.
+pub async fn get_day_boundaries(
+    context: &Context,
+    chat_id: ChatId,
+    range: Range<i64>,
+) -> Result<Vec<DayBoundary>> {
+    let calendar = DayCalendar::load(context).await?;
+
+    // The last message before the range tells
+    // whether the first message in the range starts a new day.
+    let prev_timestamp: Option<i64> = context
+        .sql
+        .query_get_value(
+            "SELECT timestamp FROM msgs
+             WHERE chat_id=? AND hidden=0 AND timestamp<?
+             ORDER BY timestamp DESC LIMIT 1",
+            (chat_id, range.start),
+        )
+        .await?;
+    let mut last_day_start = prev_timestamp.map(|ts| calendar.day_start(ts));
+
+    let msgs = context
+        .sql
+        .query_map(
+            "SELECT id, timestamp FROM msgs
+             WHERE chat_id=? AND hidden=0 AND timestamp>=? AND timestamp<?
+             ORDER BY timestamp, id",
+            (chat_id, range.start, range.end),
+            |row| Ok((row.get::<_, MsgId>(0)?, row.get::<_, i64>(1)?)),
+            |rows| {
+                rows.collect::<std::result::Result<Vec<_>, _>>()
+                    .map_err(Into::into)
+            },
+        )
+        .await?;
+
+    let mut boundaries = Vec::new();
+    for (msg_id, ts) in msgs {
+        let day_start = calendar.day_start(ts);
+        if last_day_start != Some(day_start) {
+            boundaries.push(DayBoundary {
+                timestamp: day_start,
+                msg_id,
+            });
+            last_day_start = Some(day_start);
+        }
+    }
+    Ok(boundaries)
+}
+
+/// Returns a window of the chat timeline around `anchor`
+/// with up to `before` messages before the anchor
+/// and the anchor message followed by up to `after` messages.
+///
+/// Unlike [`get_chat_msgs_ex`], items are typed,
+/// so UIs can render calls, membership changes and webxdc updates
+/// without loading each message first.
+/// Day markers are added before the first message of each day
+/// and do not count towards the limits.
+/// Items are sorted by [`TimelineKey`], oldest first.
+pub async fn get_timeline(
+    context: &Context,
+    chat_id: ChatId,
+    anchor: TimelineAnchor,
+    before: usize,
+    after: usize,
+) -> Result<Vec<TimelineEntry>> {
+    let calendar = DayCalendar::load(context).await?;
+    let anchor = match anchor {
+        TimelineAnchor::Newest => None,
+        TimelineAnchor::Message(msg_id) => {
+            let timestamp: i64 = context
+                .sql
+                .query_get_value(
+                    "SELECT timestamp FROM msgs WHERE id=? AND chat_id=? AND hidden=0",
+                    (msg_id, chat_id),
+                )
+                .await?
+                .with_context(|| format!("{msg_id} is not a message of {chat_id}"))?;
+            Some(TimelineKey {
+                timestamp,
+                counter: msg_id.to_u32(),
+            })
+        }
+        TimelineAnchor::Key(key) => Some(key),
+    };
+    let (anchor_timestamp, anchor_counter) =
+        anchor.map_or((i64::MAX, u32::MAX), |key| (key.timestamp, key.counter));
+    let to_limit = |n: usize| i64::try_from(n).unwrap_or(i64::MAX);
+    let collect_rows = |rows: rusqlite::MappedRows<_>| {
+        rows.collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(Into::into)
+    };
+
+    // One more message is loaded to tell whether the window starts a new day.
+    let mut older: Vec<(TimelineKey, TimelineItem)> = context
+        .sql
+        .query_map(
+            "SELECT id, timestamp, type, param, from_id, to_id FROM msgs
+             WHERE chat_id=? AND hidden=0 AND hidden_local=0 AND (timestamp, id) < (?, ?)
+             ORDER BY timestamp DESC, id DESC
+             LIMIT ?",
+            (
+                chat_id,
+                anchor_timestamp,
+                anchor_counter,
+                to_limit(before.saturating_add(1)),
+            ),
+            timeline_row,
+            collect_rows,
+        )
+        .await?;
+    let prev = if older.len() > before {
+        older.pop()
+    } else {
+        None
+    };
+    older.reverse();
+
+    let mut newer: Vec<(TimelineKey, TimelineItem)> = match anchor {
+        None => Vec::new(),
+        Some(anchor) => {
+            let mut newer: Vec<(TimelineKey, TimelineItem)> = context
+                .sql
+                .query_map(
+                    "SELECT id, timestamp, type, param, from_id, to_id FROM msgs
+                     WHERE chat_id=? AND hidden=0 AND hidden_local=0 AND (timestamp, id) >= (?, ?)
+                     ORDER BY timestamp, id
+                     LIMIT ?",
+                    (
+                        chat_id,
+                        anchor.timestamp,
+                        anchor.counter,
+                        to_limit(after.saturating_add(1)),
+                    ),
+                    timeline_row,
+                    collect_rows,
+                )
+                .await?;
+            let includes_anchor = newer.first().is_some_and(|(key, _)| *key == anchor);
+            newer.truncate(after.saturating_add(usize::from(includes_anchor)));
+            newer
+        }
+    };
+    older.append(&mut newer);
+
+    let mut entries = Vec::new();
+    let mut last_day_start = prev.map(|(key, _)| calendar.day_start(key.timestamp));
+    for (key, item) in older {
+        let day_start = calendar.day_start(key.timestamp);
+        if last_day_start != Some(day_start) {
+            entries.push(TimelineEntry {
+                key: TimelineKey {
+                    timestamp: day_start,
+                    counter: 0,
+                },
+                item: TimelineItem::DayMarker {
+                    timestamp: day_start,
+                },
+            });
+            last_day_start = Some(day_start);
+        }
+        entries.push(TimelineEntry { key, item });
+    }
+    Ok(entries)
+}
+
+/// Converts a `msgs` row into a timeline item.
+fn timeline_row(row: &rusqlite::Row) -> rusqlite::Result<(TimelineKey, TimelineItem)> {
+    let msg_id: MsgId = row.get("id")?;
+    let key = TimelineKey {
+        timestamp: row.get("timestamp")?,
+        counter: msg_id.to_u32(),
+    };
+    let viewtype: Viewtype = row.get("type")?;
+    let from_id: ContactId = row.get("from_id")?;
+    let to_id: ContactId = row.get("to_id")?;
+    let cmd = Params::from_str(&row.get::<_, String>("param")?)
+        .map(|params| params.get_cmd())
+        .unwrap_or_default();
+
+    let item = if viewtype == Viewtype::VideochatInvitation {
+        TimelineItem::Call { msg_id }
+    } else {
+        match cmd {
+            SystemMessage::MemberAddedToGroup => TimelineItem::MembershipChange {
+                msg_id,
+                added: true,
+            },
+            SystemMessage::MemberRemovedFromGroup => TimelineItem::MembershipChange {
+                msg_id,
+                added: false,
+            },
+            SystemMessage::WebxdcInfoMessage => TimelineItem::WebxdcUpdate { msg_id },
+            // Same logic as in `Message::is_info()`.
+            SystemMessage::Unknown | SystemMessage::AutocryptSetupMessage
+                if from_id != ContactId::INFO && to_id != ContactId::INFO =>
+            {
+                TimelineItem::Message { msg_id }
+            }
+            _ => TimelineItem::Info { msg_id },
+        }
+    };
+    Ok((key, item))
+}
+
 /// Marks all messages in the chat as noticed.
 /// If the given chat-id is the archive-link, marks all messages in all archived chats as noticed.
 pub async fn marknoticed_chat(context: &Context, chat_id: ChatId) -> Result<()> {
     // "WHERE" below uses the index `(state, hidden, chat_id)`, see get_fresh_msg_cnt() for reasoning
     // the additional SELECT statement may speed up things as no write-blocking is needed.
     if chat_id.is_archived_link() {
+        context
+            .sql
+            .execute(
+                "UPDATE reactions SET fresh=0
+                 WHERE fresh=1 AND msg_id IN (
+                     SELECT m.id FROM msgs m
+                     INNER JOIN chats c ON m.chat_id=c.id
+                     WHERE c.archived=1
+                 )",
+                (),
+            )
+            .await?;
         let chat_ids_in_archive = context
             .sql
             .query_map(
@@ -3303,6 +4783,18 @@ pub async fn marknoticed_chat(context: &Context, chat_id: ChatId) -> Result<()>
                 |ids| ids.collect::<Result<Vec<_>, _>>().map_err(Into::into),
             )
             .await?;
+        let marked_unread_in_archive = context
+            .sql
+            .query_map(
+                "SELECT id FROM chats WHERE marked_unread=1 AND archived=1",
+                (),
+                |row| row.get::<_, ChatId>(0),
+                |ids| ids.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+            )
+            .await?;
+        for marked_chat_id in marked_unread_in_archive {
+            marked_chat_id.set_marked_unread(context, false).await?;
+        }
         if chat_ids_in_archive.is_empty() {
             return Ok(());
         }
@@ -3327,8 +4819,12 @@ pub async fn marknoticed_chat(context: &Context, chat_id: ChatId) -> Result<()>
         }
     } else {
         start_chat_ephemeral_timers(context, chat_id).await?;
+        chat_id.restart_inactivity_countdown(context).await?;
+        if chat_id.is_marked_unread(context).await? {
+            chat_id.set_marked_unread(context, false).await?;
+        }
 
-        if context
+        let noticed_msgs = context
             .sql
             .execute(
                 "UPDATE msgs
@@ -3338,9 +4834,16 @@ pub async fn marknoticed_chat(context: &Context, chat_id: ChatId) -> Result<()>
             AND chat_id=?;",
                 (MessageState::InNoticed, MessageState::InFresh, chat_id),
             )
-            .await?
-            == 0
-        {
+            .await?;
+        let noticed_reactions = context
+            .sql
+            .execute(
+                "UPDATE reactions SET fresh=0
+                 WHERE fresh=1 AND msg_id IN (SELECT id FROM msgs WHERE chat_id=?)",
+                (chat_id,),
+            )
+            .await?;
+        if noticed_msgs == 0 && noticed_reactions == 0 {
             return Ok(());
         }
     }
@@ -3351,6 +4854,65 @@ pub async fn marknoticed_chat(context: &Context, chat_id: ChatId) -> Result<()>
     Ok(())
 }
 
+/// Marks all fresh messages and reactions in all chats as noticed
+/// and removes all marks set by [`ChatId::set_marked_unread`].
+///
+/// Other devices do the same for messages received until the time of the call.
+pub async fn marknoticed_all_chats(context: &Context) -> Result<()> {
+    marknoticed_all_chats_ex(context, Sync, time()).await
+}
+
+/// Marks fresh messages sent until `timestamp` in all chats as noticed.
+pub(crate) async fn marknoticed_all_chats_ex(
+    context: &Context,
+    sync: sync::Sync,
+    timestamp: i64,
+) -> Result<()> {
+    let chat_ids = context
+        .sql
+        .query_map(
+            "SELECT DISTINCT(chat_id) FROM msgs
+              WHERE state=? AND hidden=0 AND chat_id>9 AND timestamp<=?
+             UNION
+             SELECT id FROM chats WHERE marked_unread=1",
+            (MessageState::InFresh, timestamp),
+            |row| row.get::<_, ChatId>(0),
+            |ids| ids.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+        )
+        .await?;
+
+    context
+        .sql
+        .transaction(|transaction| {
+            transaction.execute(
+                "UPDATE msgs SET state=?
+                  WHERE state=? AND hidden=0 AND chat_id>9 AND timestamp<=?",
+                (MessageState::InNoticed, MessageState::InFresh, timestamp),
+            )?;
+            transaction.execute(
+                "UPDATE reactions SET fresh=0
+                  WHERE fresh=1 AND msg_id IN (SELECT id FROM msgs WHERE timestamp<=?)",
+                (timestamp,),
+            )?;
+            transaction.execute("UPDATE chats SET marked_unread=0 WHERE marked_unread=1", ())?;
+            Ok(())
+        })
+        .await?;
+
+    for chat_id in chat_ids {
+        start_chat_ephemeral_timers(context, chat_id).await?;
+        context.emit_event(EventType::MsgsNoticed(chat_id));
+        chatlist_events::emit_chatlist_item_changed(context, chat_id);
+    }
+    context.on_archived_chats_maybe_noticed();
+
+    if sync.into() {
+        context.add_sync_item(SyncData::MarknoticedAllChats).await?;
+        context.send_sync_msg().await?;
+    }
+    Ok(())
+}
+
 /// Marks messages preceding outgoing messages as noticed.
 ///
 /// In a chat, if there is an outgoing message, it can be assumed that all previous
@@ -3445,6 +5007,7 @@ pub async fn get_chat_media(
                 AND chat_id != ?
                 AND (type=? OR type=? OR type=?)
                 AND hidden=0
+                AND hidden_local=0
               ORDER BY timestamp, id;",
             (
                 chat_id.is_none(),
@@ -3515,6 +5078,102 @@ pub async fn get_past_chat_contacts(context: &Context, chat_id: ChatId) -> Resul
     Ok(list)
 }
 
+/// Returns true if an incoming message text mentions self,
+/// i.e. contains `@` followed by the self address or display name as a whole word, ignoring case.
+pub(crate) async fn is_mention(context: &Context, text: &str) -> Result<bool> {
+    if !text.contains('@') {
+        return Ok(false);
+    }
+    let text = text.to_lowercase();
+    let addr = context.get_primary_self_addr().await?;
+    let displayname = context
+        .get_config(Config::Displayname)
+        .await?
+        .unwrap_or_default();
+    Ok([addr, displayname]
+        .iter()
+        .map(|name| name.trim())
+        .filter(|name| !name.is_empty())
+        .any(|name| contains_mention(&text, &name.to_lowercase())))
+}
+
+/// Returns true if `text` contains `@name` which is neither a part of a longer word
+/// nor of an address, e.g. `@alice` is not found in `@alice2` or `bob@alice.org`.
+fn contains_mention(text: &str, name: &str) -> bool {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_' || c == '@';
+    let needle = format!("@{name}");
+    text.match_indices(&needle).any(|(start, _)| {
+        if text[..start].chars().next_back().is_some_and(is_word_char) {
+            return false;
+        }
+        let mut rest = text[start + needle.len()..].chars();
+        match rest.next() {
+            None => true,
+            // Allow punctuation after the mention, but not `.example.org` or `-smith`.
+            Some('.' | '-') => !rest.next().is_some_and(char::is_alphanumeric),
+            Some(c) => !is_word_char(c),
+        }
+    })
+}
+
+/// Returns members of the chat that can be mentioned in a message draft.
+///
+/// Only members whose display name or one of its words,
+/// or whose address starts with `prefix` are returned,
+/// ignoring case for ASCII characters.
+/// Members who wrote to the chat recently come first,
+/// the rest is sorted by name. Self is never returned.
+pub async fn get_mentionable_members(
+    context: &Context,
+    chat_id: ChatId,
+    prefix: &str,
+) -> Result<Vec<MentionableMember>> {
+    let pattern = format!(
+        "{}%",
+        prefix
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_")
+    );
+    let members = context
+        .sql
+        .query_map(
+            "SELECT c.id,
+                    IFNULL(NULLIF(c.name, ''), IFNULL(NULLIF(c.authname, ''), c.addr)) AS display_name,
+                    (SELECT MAX(m.timestamp) FROM msgs m
+                     WHERE m.chat_id=cc.chat_id AND m.from_id=c.id) AS last_written
+             FROM chats_contacts cc
+             JOIN contacts c ON c.id=cc.contact_id
+             WHERE cc.chat_id=?1
+               AND cc.add_timestamp >= cc.remove_timestamp
+               AND c.id!=?2
+               AND (display_name LIKE ?3 ESCAPE '\\'
+                    OR display_name LIKE '% ' || ?3 ESCAPE '\\'
+                    OR c.addr LIKE ?3 ESCAPE '\\')
+             ORDER BY last_written IS NULL, last_written DESC,
+                      display_name COLLATE NOCASE, c.id",
+            (chat_id, ContactId::SELF, pattern),
+            |row| {
+                let contact_id: ContactId = row.get(0)?;
+                let display_name: String = row.get(1)?;
+                Ok((contact_id, display_name))
+            },
+            |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+        )
+        .await?;
+
+    let mut res = Vec::with_capacity(members.len());
+    for (contact_id, display_name) in members {
+        let contact = Contact::get_by_id(context, contact_id).await?;
+        res.push(MentionableMember {
+            contact_id,
+            display_name,
+            profile_image: contact.get_profile_image(context).await?,
+        });
+    }
+    Ok(res)
+}
+
 /// Creates a group chat with a given `name`.
 pub async fn create_group_chat(
     context: &Context,
@@ -3539,6 +5198,7 @@ pub async fn create_group_chat(
 
     let chat_id = ChatId::new(u32::try_from(row_id)?);
     add_to_chat_contacts_table(context, timestamp, chat_id, &[ContactId::SELF]).await?;
+    set_group_admins(context, chat_id, &[ContactId::SELF]).await?;
 
     context.emit_msgs_changed_without_ids();
     chatlist_events::emit_chatlist_changed(context);
@@ -3641,6 +5301,213 @@ pub(crate) async fn create_broadcast_list_ex(
     Ok(chat_id)
 }
 
+/// Delivery statistics of a message sent to a broadcast list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BroadcastMsgStats {
+    /// ID of the message.
+    pub msg_id: MsgId,
+
+    /// Sending time of the message.
+    pub timestamp: i64,
+
+    /// Number of recipients the message was sent to.
+    pub recipients: u32,
+
+    /// Number of recipients the message was delivered to,
+    /// including the recipients that read it.
+    pub delivered: u32,
+
+    /// Number of recipients that sent a read receipt.
+    pub read: u32,
+
+    /// Number of recipients whose servers rejected the message.
+    pub failed: u32,
+
+    /// Delivery state of the message for each member of the broadcast list
+    /// at the time the message was sent.
+    pub recipient_stats: Vec<BroadcastRecipientStats>,
+}
+
+/// Delivery state of a broadcast list message for a single recipient.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BroadcastRecipientState {
+    /// The message is not sent yet.
+    Pending,
+
+    /// The message was sent to the recipient.
+    Delivered,
+
+    /// The recipient sent a read receipt.
+    Read,
+
+    /// The server of the recipient rejected the message.
+    Failed,
+}
+
+/// Delivery statistics of a broadcast list message for a single recipient.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BroadcastRecipientStats {
+    /// ID of the recipient.
+    pub contact_id: ContactId,
+
+    /// Delivery state of the message for the recipient.
+    pub state: BroadcastRecipientState,
+}
+
+/// Returns delivery statistics of the messages sent to a broadcast list, oldest first,
+/// both as totals and per recipient.
+///
+/// Read counts are built from read receipts (MDNs),
+/// so they are lower bounds as recipients may disable read receipts.
+pub async fn get_broadcast_stats(
+    context: &Context,
+    chat_id: ChatId,
+) -> Result<Vec<BroadcastMsgStats>> {
+    let chat = Chat::load_from_db(context, chat_id).await?;
+    ensure!(
+        chat.typ == Chattype::Broadcast,
+        "{chat_id} is not a broadcast list"
+    );
+    let msgs = context
+        .sql
+        .query_map(
+            "SELECT m.id, m.timestamp, m.state, m.param,
+             (SELECT COUNT(*) FROM msgs_mdns WHERE msg_id=m.id),
+             (SELECT COUNT(*) FROM msgs_failed_recipients WHERE msg_id=m.id)
+             FROM msgs m
+             WHERE m.chat_id=? AND m.from_id=? AND m.hidden=0 AND m.state IN (?, ?, ?, ?)
+             ORDER BY m.timestamp, m.id",
+            (
+                chat_id,
+                ContactId::SELF,
+                MessageState::OutPending,
+                MessageState::OutFailed,
+                MessageState::OutDelivered,
+                MessageState::OutMdnRcvd,
+            ),
+            |row| {
+                let msg_id: MsgId = row.get(0)?;
+                let timestamp: i64 = row.get(1)?;
+                let state: MessageState = row.get(2)?;
+                let param: Params = row.get::<_, String>(3)?.parse().unwrap_or_default();
+                let read: u32 = row.get(4)?;
+                let failed: u32 = row.get(5)?;
+                let recipients = param
+                    .get_int(Param::BroadcastRecipients)
+                    .and_then(|n| u32::try_from(n).ok())
+                    .unwrap_or_default()
+                    .max(read + failed);
+                let sent = matches!(state, MessageState::OutDelivered | MessageState::OutMdnRcvd)
+                    || (state == MessageState::OutFailed && failed > 0);
+                let delivered = match sent {
+                    true => recipients - failed,
+                    false => read,
+                };
+                let stats = BroadcastMsgStats {
+                    msg_id,
+                    timestamp,
+                    recipients,
+                    delivered,
+                    read,
+                    failed,
+                    recipient_stats: Vec::new(),
+                };
+                Ok((stats, sent))
+            },
+            |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+        )
+        .await?;
+
+    let mut res = Vec::with_capacity(msgs.len());
+    for (mut stats, sent) in msgs {
+        stats.recipient_stats = context
+            .sql
+            .query_map(
+                "SELECT cc.contact_id,
+                 EXISTS(SELECT 1 FROM msgs_mdns WHERE msg_id=?1 AND contact_id=cc.contact_id),
+                 EXISTS(SELECT 1 FROM msgs_failed_recipients
+                        WHERE msg_id=?1 AND addr=c.addr COLLATE NOCASE)
+                 FROM chats_contacts cc
+                 INNER JOIN contacts c ON c.id=cc.contact_id
+                 WHERE cc.chat_id=?2 AND cc.contact_id!=?3 AND cc.add_timestamp<=?4
+                 AND (cc.remove_timestamp<=cc.add_timestamp OR cc.remove_timestamp>?4)
+                 ORDER BY cc.contact_id",
+                (stats.msg_id, chat_id, ContactId::SELF, stats.timestamp),
+                |row| {
+                    let contact_id: ContactId = row.get(0)?;
+                    let read: bool = row.get(1)?;
+                    let failed: bool = row.get(2)?;
+                    let state = if read {
+                        BroadcastRecipientState::Read
+                    } else if failed {
+                        BroadcastRecipientState::Failed
+                    } else if sent {
+                        BroadcastRecipientState::Delivered
+                    } else {
+                        BroadcastRecipientState::Pending
+                    };
+                    Ok(BroadcastRecipientStats { contact_id, state })
+                },
+                |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+            )
+            .await?;
+        res.push(stats);
+    }
+    Ok(res)
+}
+
+/// Converts a broadcast list into a group with the same name and members.
+///
+/// The group is not promoted yet,
+/// the members learn about it with the first message sent to the group.
+/// The group gets a new ID as the recipients may know the ID of the broadcast list.
+///
+/// Unlike in the broadcast list, the members see each other's addresses in the group,
+/// so UIs should ask for confirmation before converting.
+/// An info message warning about this is added to the chat.
+pub async fn convert_broadcast_to_group(context: &Context, chat_id: ChatId) -> Result<()> {
+    convert_broadcast_to_group_ex(context, Sync, chat_id, create_id()).await
+}
+
+async fn convert_broadcast_to_group_ex(
+    context: &Context,
+    sync: sync::Sync,
+    chat_id: ChatId,
+    grpid: String,
+) -> Result<()> {
+    let chat = Chat::load_from_db(context, chat_id).await?;
+    ensure!(
+        chat.typ == Chattype::Broadcast,
+        "{chat_id} is not a broadcast list"
+    );
+    let mut members = get_chat_contacts(context, chat_id).await?;
+    members.push(ContactId::SELF);
+
+    context
+        .sql
+        .execute(
+            "UPDATE chats SET type=?, grpid=? WHERE id=?",
+            (Chattype::Group, &grpid, chat_id),
+        )
+        .await?;
+    let timestamp = create_smeared_timestamp(context);
+    add_to_chat_contacts_table(context, timestamp, chat_id, &members).await?;
+    set_group_admins(context, chat_id, &[ContactId::SELF]).await?;
+    info!(context, chat_id = chat_id; "Converted broadcast list {chat_id} into a group.");
+    let text = stock_str::broadcast_converted_to_group(context).await;
+    add_info_msg(context, chat_id, &text, create_smeared_timestamp(context)).await?;
+
+    context.emit_event(EventType::ChatModified(chat_id));
+    chatlist_events::emit_chatlist_item_changed(context, chat_id);
+
+    if sync.into() {
+        let id = SyncId::Grpid(chat.grpid);
+        let action = SyncAction::ConvertToGroup(grpid);
+        self::sync(context, id, action).await.log_err(context).ok();
+    }
+    Ok(())
+}
+
 /// Set chat contacts in the `chats_contacts` table.
 pub(crate) async fn update_chat_contacts_table(
     context: &Context,
@@ -3698,44 +5565,255 @@ pub(crate) async fn add_to_chat_contacts_table(
                  DO UPDATE SET add_timestamp=MAX(remove_timestamp, ?3)",
             )?;
 
-            for contact_id in contact_ids {
-                add_statement.execute((chat_id, contact_id, timestamp))?;
-            }
-            Ok(())
-        })
-        .await?;
+            for contact_id in contact_ids {
+                add_statement.execute((chat_id, contact_id, timestamp))?;
+            }
+            Ok(())
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Removes a contact from the chat
+/// by updating the `remove_timestamp`.
+pub(crate) async fn remove_from_chat_contacts_table(
+    context: &Context,
+    chat_id: ChatId,
+    contact_id: ContactId,
+) -> Result<()> {
+    let now = time();
+    context
+        .sql
+        .execute(
+            "UPDATE chats_contacts
+             SET remove_timestamp=MAX(add_timestamp+1, ?)
+             WHERE chat_id=? AND contact_id=?",
+            (now, chat_id, contact_id),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Adds a contact to the chat.
+/// If the group is promoted, also sends out a system message to all group members
+pub async fn add_contact_to_chat(
+    context: &Context,
+    chat_id: ChatId,
+    contact_id: ContactId,
+) -> Result<()> {
+    add_contact_to_chat_ex(context, Sync, chat_id, contact_id, false).await?;
+    Ok(())
+}
+
+/// Returns the members of a protected group whose key changed since they were verified,
+/// e.g. because they reinstalled Delta Chat without a backup.
+///
+/// Messages from these members cannot be verified
+/// and they cannot read messages sent to the group.
+/// See [`repair_verified_group_member`] to add them back.
+pub async fn get_members_needing_reverification(
+    context: &Context,
+    chat_id: ChatId,
+) -> Result<Vec<ContactId>> {
+    let chat = Chat::load_from_db(context, chat_id).await?;
+    if chat.typ != Chattype::Group || !chat.is_protected() {
+        return Ok(Vec::new());
+    }
+    let mut members = Vec::new();
+    for contact_id in get_chat_contacts(context, chat_id).await? {
+        if contact_id == ContactId::SELF {
+            continue;
+        }
+        let contact = Contact::get_by_id(context, contact_id).await?;
+        if !contact.is_forward_verified(context).await? {
+            members.push(contact_id);
+        }
+    }
+    Ok(members)
+}
+
+/// Repairs the membership of a contact in a protected group
+/// after the contact's key changed.
+///
+/// The contact must be verified again first, e.g. by scanning their QR code.
+/// Then a "member added" message is sent to the group,
+/// so that the contact's new setup can recreate the group
+/// and the other members learn about the new verified key.
+/// The group keeps its protection status and does not need to be recreated.
+pub async fn repair_verified_group_member(
+    context: &Context,
+    chat_id: ChatId,
+    contact_id: ContactId,
+) -> Result<()> {
+    let chat = Chat::load_from_db(context, chat_id).await?;
+    ensure!(
+        chat.typ == Chattype::Group && chat.is_protected(),
+        "{chat_id} is not a protected group"
+    );
+    ensure!(
+        is_contact_in_chat(context, chat_id, contact_id).await?,
+        "Contact {contact_id} is not a member of {chat_id}"
+    );
+    let contact = Contact::get_by_id(context, contact_id).await?;
+    ensure!(
+        contact.is_verified(context).await?,
+        "Contact {contact_id} must be verified again first"
+    );
+    ensure!(
+        chat.is_self_in_chat(context).await?,
+        "Cannot repair membership, self is not in {chat_id}"
+    );
+
+    // The "member added" message always gossips the keys of all members.
+    chat_id.reset_gossiped_timestamp(context).await?;
+    let mut msg = Message::new(Viewtype::Text);
+    let contact_addr = contact.get_addr().to_lowercase();
+    msg.text = stock_str::msg_add_member_local(context, &contact_addr, ContactId::SELF).await;
+    msg.param.set_cmd(SystemMessage::MemberAddedToGroup);
+    msg.param.set(Param::Arg, contact_addr);
+    send_msg(context, chat_id, &mut msg).await?;
+    info!(
+        context,
+        "Repaired membership of {contact_id} in protected group {chat_id}."
+    );
+    Ok(())
+}
+
+/// Adds several contacts to the chat.
+///
+/// Unlike calling [`add_contact_to_chat`] for every contact,
+/// a promoted group gets a single system message for all added members,
+/// the other members learn about the additions from the member list of the message.
+/// Contacts which are already members are skipped.
+pub async fn add_contacts_to_chat(
+    context: &Context,
+    chat_id: ChatId,
+    contact_ids: &[ContactId],
+) -> Result<()> {
+    ensure!(!chat_id.is_special(), "can not add member to special chats");
+    let chat = Chat::load_from_db(context, chat_id).await?;
+    if contact_ids.len() < 2 || chat.typ != Chattype::Group || !chat.is_promoted() {
+        for &contact_id in contact_ids {
+            add_contact_to_chat_ex(context, Sync, chat_id, contact_id, false).await?;
+        }
+        return Ok(());
+    }
+    if !chat.is_self_in_chat(context).await? {
+        context.emit_event(EventType::ErrorSelfNotInGroup(
+            "Cannot add contact to group; self not in group.".into(),
+        ));
+        bail!("can not add contacts because the account is not part of the group");
+    }
+
+    let mut added = Vec::new();
+    let mut addrs = Vec::new();
+    for &contact_id in contact_ids {
+        ensure!(
+            Contact::real_exists_by_id(context, contact_id).await?,
+            "invalid contact_id {} for adding to group",
+            contact_id
+        );
+        let contact = Contact::get_by_id(context, contact_id).await?;
+        if context.is_self_addr(contact.get_addr()).await?
+            || added.contains(&contact_id)
+            || is_contact_in_chat(context, chat_id, contact_id).await?
+        {
+            continue;
+        }
+        if chat.is_protected() && !contact.is_verified(context).await? {
+            error!(
+                context,
+                "Cannot add non-bidirectionally verified contact {contact_id} to protected chat {chat_id}."
+            );
+            continue;
+        }
+        added.push(contact_id);
+        addrs.push(contact.get_addr().to_lowercase());
+    }
+    if added.is_empty() {
+        return Ok(());
+    }
+    check_member_limit(context, chat_id, added.len()).await?;
+    check_member_add_ratelimit(context).await?;
+
+    chat_id.reset_gossiped_timestamp(context).await?;
+    let timestamp = time();
+    add_to_chat_contacts_table(context, timestamp, chat_id, &added).await?;
+    if chat.is_protected() {
+        for &contact_id in &added {
+            chat_id
+                .add_protection_log(
+                    context,
+                    ProtectionLogEvent::MemberAdded,
+                    ContactId::SELF,
+                    Some(contact_id),
+                    timestamp,
+                )
+                .await?;
+        }
+    }
+    members_added(context, &chat, added.len(), true).await?;
+
+    let mut msg = Message::new(Viewtype::Text);
+    msg.text = stock_str::msg_add_member_local(context, &addrs.join(", "), ContactId::SELF).await;
+    msg.param.set_cmd(SystemMessage::MemberAddedToGroup);
+    // Only a single address fits into the `Chat-Group-Member-Added` header.
+    msg.param.set(Param::Arg, &addrs[0]);
+    send_msg(context, chat_id, &mut msg).await?;
 
+    context.emit_event(EventType::ChatModified(chat_id));
     Ok(())
 }
 
-/// Removes a contact from the chat
-/// by updating the `remove_timestamp`.
-pub(crate) async fn remove_from_chat_contacts_table(
-    context: &Context,
-    chat_id: ChatId,
-    contact_id: ContactId,
-) -> Result<()> {
-    let now = time();
-    context
-        .sql
-        .execute(
-            "UPDATE chats_contacts
-             SET remove_timestamp=MAX(add_timestamp+1, ?)
-             WHERE chat_id=? AND contact_id=?",
-            (now, chat_id, contact_id),
-        )
-        .await?;
+/// Fails if adding `count` members to the group
+/// would exceed [`Config::GroupMemberHardLimit`].
+async fn check_member_limit(context: &Context, chat_id: ChatId, count: usize) -> Result<()> {
+    let limit = context.get_config_u64(Config::GroupMemberHardLimit).await?;
+    if limit == 0 {
+        return Ok(());
+    }
+    let members = get_chat_contacts(context, chat_id).await?.len();
+    ensure!(
+        (members + count) as u64 <= limit,
+        "Cannot add {count} member(s) to a group of {members} members, the limit is {limit}."
+    );
     Ok(())
 }
 
-/// Adds a contact to the chat.
-/// If the group is promoted, also sends out a system message to all group members
-pub async fn add_contact_to_chat(
-    context: &Context,
-    chat_id: ChatId,
-    contact_id: ContactId,
-) -> Result<()> {
-    add_contact_to_chat_ex(context, Sync, chat_id, contact_id, false).await?;
+/// Fails if the user added too many members to promoted groups recently.
+///
+/// Every added member causes a message to all group members,
+/// so adding members is throttled to protect the users and their providers.
+async fn check_member_add_ratelimit(context: &Context) -> Result<()> {
+    let until_can_add = context.member_add_ratelimit.read().await.until_can_send();
+    ensure!(
+        until_can_add.is_zero(),
+        "Too many members were added recently, try again in {}.",
+        duration_to_str(until_can_add)
+    );
+    Ok(())
+}
+
+/// Accounts `count` members added to a group
+/// and warns in the chat if the group grew above [`Config::GroupMemberSoftLimit`].
+async fn members_added(context: &Context, chat: &Chat, count: usize, by_user: bool) -> Result<()> {
+    if by_user && chat.is_promoted() {
+        let mut ratelimit = context.member_add_ratelimit.write().await;
+        for _ in 0..count {
+            ratelimit.send();
+        }
+    }
+    let limit = context.get_config_u64(Config::GroupMemberSoftLimit).await?;
+    let members = get_chat_contacts(context, chat.id).await?.len() as u64;
+    if limit > 0 && members > limit && members.saturating_sub(count as u64) <= limit {
+        warn!(
+            context,
+            "Group {} has grown above {limit} members.", chat.id
+        );
+        let text = stock_str::group_member_soft_limit(context, limit).await;
+        add_info_msg(context, chat.id, &text, time()).await?;
+    }
     Ok(())
 }
 
@@ -3776,6 +5854,10 @@ pub(crate) async fn add_contact_to_chat_ex(
         ));
         bail!("can not add contact because the account is not part of the group/broadcast");
     }
+    ensure!(
+        may_manage_group(context, &chat, ContactId::SELF).await?,
+        "Only admins may add members to {chat_id}"
+    );
 
     let sync_qr_code_tokens;
     if from_handshake && chat.param.get_int(Param::Unpromoted).unwrap_or_default() == 1 {
@@ -3812,7 +5894,27 @@ pub(crate) async fn add_contact_to_chat_ex(
         if is_contact_in_chat(context, chat_id, contact_id).await? {
             return Ok(false);
         }
+        if chat.typ == Chattype::Group {
+            check_member_limit(context, chat_id, 1).await?;
+            if !from_handshake && chat.is_promoted() {
+                check_member_add_ratelimit(context).await?;
+            }
+        }
         add_to_chat_contacts_table(context, time(), chat_id, &[contact_id]).await?;
+        if chat.is_protected() {
+            chat_id
+                .add_protection_log(
+                    context,
+                    ProtectionLogEvent::MemberAdded,
+                    ContactId::SELF,
+                    Some(contact_id),
+                    time(),
+                )
+                .await?;
+        }
+        if chat.typ == Chattype::Group {
+            members_added(context, &chat, 1, !from_handshake).await?;
+        }
     }
     if chat.typ == Chattype::Group && chat.is_promoted() {
         msg.viewtype = Viewtype::Text;
@@ -3852,6 +5954,11 @@ pub(crate) async fn add_contact_to_chat_ex(
 /// This function does not check if the avatar is set.
 /// If avatar is not set and this function returns `true`,
 /// a `Chat-User-Avatar: 0` header should be sent to reset the avatar.
+///
+/// In chats with at least [`DC_LAZY_AVATAR_MIN_MEMBERS`] members,
+/// the avatar is not resent periodically,
+/// but only if it was never sent to some member or a member asked for it.
+/// Instead, the avatar hash is sent, see [`uses_lazy_selfavatar`].
 pub(crate) async fn shall_attach_selfavatar(context: &Context, chat_id: ChatId) -> Result<bool> {
     let timestamp_some_days_ago = time() - DC_RESEND_USER_AVATAR_DAYS * 24 * 60 * 60;
     let needs_attach = context
@@ -3864,21 +5971,80 @@ pub(crate) async fn shall_attach_selfavatar(context: &Context, chat_id: ChatId)
             (chat_id, ContactId::SELF),
             |row| Ok(row.get::<_, i64>(0)),
             |rows| {
+                let mut members = 0;
+                let mut never_sent = false;
                 let mut needs_attach = false;
                 for row in rows {
                     let row = row?;
                     let selfavatar_sent = row?;
+                    members += 1;
+                    if selfavatar_sent == 0 {
+                        never_sent = true;
+                    }
                     if selfavatar_sent < timestamp_some_days_ago {
                         needs_attach = true;
                     }
                 }
-                Ok(needs_attach)
+                if members >= DC_LAZY_AVATAR_MIN_MEMBERS {
+                    Ok(never_sent)
+                } else {
+                    Ok(needs_attach)
+                }
             },
         )
         .await?;
     Ok(needs_attach)
 }
 
+/// Returns true if the chat is large enough
+/// to announce the avatar hash instead of resending the avatar periodically.
+pub(crate) async fn uses_lazy_selfavatar(context: &Context, chat_id: ChatId) -> Result<bool> {
+    let members: usize = context
+        .sql
+        .count(
+            "SELECT COUNT(*) FROM chats_contacts
+             WHERE chat_id=? AND contact_id!=? AND add_timestamp >= remove_timestamp",
+            (chat_id, ContactId::SELF),
+        )
+        .await?;
+    Ok(members >= DC_LAZY_AVATAR_MIN_MEMBERS)
+}
+
+/// Returns the contact that should be asked to resend the profile data
+/// with a message sent to the given chat.
+///
+/// Only contacts of 1:1 chats with a stale profile image are asked,
+/// and not more often than every [`DC_REQUEST_PROFILE_DAYS`] days.
+pub(crate) async fn shall_request_profile(
+    context: &Context,
+    chat: &Chat,
+) -> Result<Option<ContactId>> {
+    if chat.typ != Chattype::Single
+        || chat.is_self_talk()
+        || chat.is_device_talk()
+        || !context
+            .get_config_bool(Config::RequestStaleProfiles)
+            .await?
+    {
+        return Ok(None);
+    }
+    let Some(contact_id) = get_chat_contacts(context, chat.id).await?.pop() else {
+        return Ok(None);
+    };
+    let contact = Contact::get_by_id(context, contact_id).await?;
+    let last_request = contact
+        .param
+        .get_i64(Param::ProfileRequestTimestamp)
+        .unwrap_or_default();
+    if contact.is_profile_image_stale()
+        && last_request < time() - DC_REQUEST_PROFILE_DAYS * 24 * 60 * 60
+    {
+        Ok(Some(contact_id))
+    } else {
+        Ok(None)
+    }
+}
+
 /// Chat mute duration.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MuteDuration {
@@ -3927,6 +6093,83 @@ fn column_result(value: rusqlite::types::ValueRef) -> rusqlite::types::FromSqlRe
     }
 }
 
+/// Where replies in a mailing list chat are sent to,
+/// see [`ChatId::set_mailinglist_reply_mode`].
+#[derive(Debug, Default, Display, Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive)]
+#[repr(u32)]
+pub enum MailinglistReplyMode {
+    /// Send all messages to the `List-Post` address.
+    #[default]
+    List = 0,
+
+    /// Send replies quoting a message privately to its sender,
+    /// or to the `Reply-To` address of the quoted message if it is set.
+    /// Messages without a quote are still sent to the list.
+    Sender = 1,
+}
+
+/// Whether an archived chat is unarchived by new messages,
+/// see [`ChatId::set_unarchive_policy`].
+#[derive(
+    Debug,
+    Default,
+    Display,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    FromPrimitive,
+    ToPrimitive,
+    Serialize,
+    Deserialize,
+)]
+#[repr(u32)]
+pub enum UnarchivePolicy {
+    /// Unarchive the chat on any new message unless the chat is muted.
+    #[default]
+    AnyMessage = 0,
+
+    /// Unarchive the chat only on incoming messages mentioning self,
+    /// see [`is_mention`].
+    Mention = 1,
+
+    /// Keep the chat archived.
+    Never = 2,
+}
+
+/// Sends a hidden subscribe or unsubscribe request of a mailing list chat
+/// to the address of a `mailto:` URI.
+///
+/// `command` is used as subject and text unless the URI specifies them.
+async fn send_mailinglist_request(
+    context: &Context,
+    chat_id: ChatId,
+    mailto: MailTo,
+    command: &str,
+) -> Result<()> {
+    let addr = mailto.to.first().context("No address in mailto: URI")?;
+    let mut msg = Message::new_text(mailto.body.unwrap_or_else(|| command.to_string()));
+    msg.subject = mailto.subject.unwrap_or_else(|| command.to_string());
+    msg.hidden = true;
+    msg.param.set(Param::MailinglistRecipient, addr.to_string());
+    send_msg(context, chat_id, &mut msg).await?;
+    Ok(())
+}
+
+/// Local retention policy of a chat, see [`ChatId::set_retention`].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RetentionPolicy {
+    /// Keep all messages.
+    #[default]
+    KeepAll,
+
+    /// Keep only the given number of most recent messages.
+    KeepLastMessages(u32),
+
+    /// Keep only messages of the given number of last days.
+    KeepLastDays(u32),
+}
+
 /// Mutes the chat for a given duration or unmutes it.
 pub async fn set_muted(context: &Context, chat_id: ChatId, duration: MuteDuration) -> Result<()> {
     set_muted_ex(context, Sync, chat_id, duration).await
@@ -3939,6 +6182,11 @@ pub(crate) async fn set_muted_ex(
     duration: MuteDuration,
 ) -> Result<()> {
     ensure!(!chat_id.is_special(), "Invalid chat ID");
+    if sync == Sync {
+        chat_id
+            .update_timestamp(context, Param::MuteTimestamp, time())
+            .await?;
+    }
     context
         .sql
         .execute(
@@ -3986,6 +6234,11 @@ pub async fn remove_contact_from_chat(
             context.emit_event(EventType::ErrorSelfNotInGroup(err_msg.clone()));
             bail!("{}", err_msg);
         } else {
+            ensure!(
+                contact_id == ContactId::SELF
+                    || may_manage_group(context, &chat, ContactId::SELF).await?,
+                "Only admins may remove members from {chat_id}"
+            );
             let mut sync = Nosync;
 
             if chat.is_promoted() {
@@ -4000,6 +6253,17 @@ pub async fn remove_contact_from_chat(
                     )
                     .await?;
             }
+            if chat.is_protected() {
+                chat_id
+                    .add_protection_log(
+                        context,
+                        ProtectionLogEvent::MemberRemoved,
+                        ContactId::SELF,
+                        Some(contact_id),
+                        time(),
+                    )
+                    .await?;
+            }
 
             // We do not return an error if the contact does not exist in the database.
             // This allows to delete dangling references to deleted contacts
@@ -4024,7 +6288,7 @@ pub async fn remove_contact_from_chat(
                         res?;
                         set_group_explicitly_left(context, &chat.grpid).await?;
                     } else if let Err(e) = res {
-                        warn!(context, "remove_contact_from_chat({chat_id}, {contact_id}): send_msg() failed: {e:#}.");
+                        warn!(context, chat_id = chat_id; "remove_contact_from_chat({chat_id}, {contact_id}): send_msg() failed: {e:#}.");
                     }
                 } else {
                     sync = Sync;
@@ -4092,6 +6356,8 @@ async fn rename_ex(
             context.emit_event(EventType::ErrorSelfNotInGroup(
                 "Cannot set chat name; self not in group".into(),
             ));
+        } else if !may_manage_group(context, &chat, ContactId::SELF).await? {
+            bail!("Only admins may rename {chat_id}");
         } else {
             context
                 .sql
@@ -4158,6 +6424,10 @@ pub async fn set_chat_profile_image(
         ));
         bail!("Failed to set profile image");
     }
+    ensure!(
+        may_manage_group(context, &chat, ContactId::SELF).await?,
+        "Only admins may change the profile image of {chat_id}"
+    );
     let mut msg = Message::new(Viewtype::Text);
     msg.param
         .set_int(Param::Cmd, SystemMessage::GroupImageChanged as i32);
@@ -4186,6 +6456,201 @@ pub async fn set_chat_profile_image(
     Ok(())
 }
 
+/// Returns true if the contact is an admin of the group.
+///
+/// Admins only have additional rights in moderated groups, see [`set_group_moderated`].
+pub async fn is_group_admin(
+    context: &Context,
+    chat_id: ChatId,
+    contact_id: ContactId,
+) -> Result<bool> {
+    let exists = context
+        .sql
+        .exists(
+            "SELECT COUNT(*) FROM chats_admins WHERE chat_id=? AND contact_id=?",
+            (chat_id, contact_id),
+        )
+        .await?;
+    Ok(exists)
+}
+
+/// Returns the admins of the group.
+pub async fn get_group_admins(context: &Context, chat_id: ChatId) -> Result<Vec<ContactId>> {
+    context
+        .sql
+        .query_map(
+            "SELECT contact_id FROM chats_admins WHERE chat_id=? ORDER BY contact_id",
+            (chat_id,),
+            |row| row.get::<_, ContactId>(0),
+            |ids| ids.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+        )
+        .await
+}
+
+/// Replaces the admins of the group in the `chats_admins` table.
+pub(crate) async fn set_group_admins(
+    context: &Context,
+    chat_id: ChatId,
+    admins: &[ContactId],
+) -> Result<()> {
+    context
+        .sql
+        .transaction(move |transaction| {
+            transaction.execute("DELETE FROM chats_admins WHERE chat_id=?", (chat_id,))?;
+            let mut statement = transaction.prepare(
+                "INSERT OR IGNORE INTO chats_admins (chat_id, contact_id) VALUES (?, ?)",
+            )?;
+            for contact_id in admins {
+                statement.execute((chat_id, contact_id))?;
+            }
+            Ok(())
+        })
+        .await?;
+    Ok(())
+}
+
+/// Returns true if the contact may change members, name and avatar of the chat.
+///
+/// This is always the case unless the chat is a moderated group
+/// and the contact is not one of its admins.
+pub(crate) async fn may_manage_group(
+    context: &Context,
+    chat: &Chat,
+    contact_id: ContactId,
+) -> Result<bool> {
+    Ok(!chat.is_moderated() || is_group_admin(context, chat.id, contact_id).await?)
+}
+
+/// Enables or disables moderation of a group.
+///
+/// In a moderated group only admins may add and remove members
+/// and change the group name and image.
+/// Other members can still leave the group.
+/// Changes by other members are ignored by all members
+/// and an info message about the rejected change is added instead.
+///
+/// Only admins may change the moderation;
+/// initially the creator of the group is the only admin,
+/// see [`set_group_admin`] to promote more members.
+/// Groups without admins, e.g. created by older versions,
+/// get the user as their first admin.
+/// If the group is promoted, all members are informed by a system message.
+pub async fn set_group_moderated(
+    context: &Context,
+    chat_id: ChatId,
+    moderated: bool,
+) -> Result<()> {
+    ensure!(!chat_id.is_special(), "Invalid chat ID");
+    let mut chat = Chat::load_from_db(context, chat_id).await?;
+    ensure!(chat.typ == Chattype::Group, "{chat_id} is not a group");
+    if !chat.is_self_in_chat(context).await? {
+        context.emit_event(EventType::ErrorSelfNotInGroup(
+            "Cannot change group moderation; self not in group.".into(),
+        ));
+        bail!("Failed to change moderation of {chat_id}");
+    }
+    let admins = get_group_admins(context, chat_id).await?;
+    ensure!(
+        admins.is_empty() || admins.contains(&ContactId::SELF),
+        "Only admins may change moderation of {chat_id}"
+    );
+    if chat.is_moderated() == moderated {
+        return Ok(());
+    }
+    if admins.is_empty() {
+        set_group_admins(context, chat_id, &[ContactId::SELF]).await?;
+    }
+
+    if moderated {
+        chat.param.set_int(Param::GroupModerated, 1);
+    } else {
+        chat.param.remove(Param::GroupModerated);
+    }
+    chat.param
+        .update_timestamp(Param::GroupAdminsTimestamp, time())?;
+    chat.update_param(context).await?;
+
+    let text = if moderated {
+        stock_str::msg_grp_moderated(context, ContactId::SELF).await
+    } else {
+        stock_str::msg_grp_unmoderated(context, ContactId::SELF).await
+    };
+    send_group_admins_changed(context, &chat, text).await
+}
+
+/// Promotes a group member to admin or revokes their admin rights.
+///
+/// Only admins may do this and the last admin cannot be removed.
+/// In groups without admins, e.g. created by older versions,
+/// the user becomes the first admin.
+/// Members without admins accept this first admin
+/// from the system message sent to promoted groups.
+/// If the group is promoted, all members are informed by a system message.
+pub async fn set_group_admin(
+    context: &Context,
+    chat_id: ChatId,
+    contact_id: ContactId,
+    admin: bool,
+) -> Result<()> {
+    ensure!(!chat_id.is_special(), "Invalid chat ID");
+    let mut chat = Chat::load_from_db(context, chat_id).await?;
+    ensure!(chat.typ == Chattype::Group, "{chat_id} is not a group");
+    if !chat.is_self_in_chat(context).await? {
+        context.emit_event(EventType::ErrorSelfNotInGroup(
+            "Cannot change group admins; self not in group.".into(),
+        ));
+        bail!("Failed to change admins of {chat_id}");
+    }
+    let mut admins = get_group_admins(context, chat_id).await?;
+    let first_admin = admins.is_empty();
+    ensure!(
+        first_admin || admins.contains(&ContactId::SELF),
+        "Only admins may change admins of {chat_id}"
+    );
+    ensure!(
+        is_contact_in_chat(context, chat_id, contact_id).await?,
+        "{contact_id} is not a member of {chat_id}"
+    );
+
+    if admins.contains(&contact_id) == admin {
+        return Ok(());
+    }
+    if first_admin {
+        admins.push(ContactId::SELF);
+    }
+    if !admin {
+        admins.retain(|id| *id != contact_id);
+    } else if !admins.contains(&contact_id) {
+        admins.push(contact_id);
+    }
+    ensure!(
+        !admins.is_empty(),
+        "Cannot remove the last admin of {chat_id}"
+    );
+    set_group_admins(context, chat_id, &admins).await?;
+    chat.param
+        .update_timestamp(Param::GroupAdminsTimestamp, time())?;
+    chat.update_param(context).await?;
+
+    let text = stock_str::msg_grp_admins_changed(context, ContactId::SELF).await;
+    send_group_admins_changed(context, &chat, text).await
+}
+
+/// Informs the members of a promoted group about changed admins or moderation.
+///
+/// The admins are sent in the `Chat-Group-Admins` header added by `MimeFactory`.
+async fn send_group_admins_changed(context: &Context, chat: &Chat, text: String) -> Result<()> {
+    if chat.is_promoted() {
+        let mut msg = Message::new_text(text);
+        msg.param.set_cmd(SystemMessage::GroupAdminsChanged);
+        msg.id = send_msg(context, chat.id, &mut msg).await?;
+        context.emit_msgs_changed(chat.id, msg.id);
+    }
+    context.emit_event(EventType::ChatModified(chat.id));
+    chatlist_events::emit_chatlist_item_changed(context, chat.id);
+    Ok(())
+}
+
 /// Forwards multiple messages to a chat.
 pub async fn forward_msgs(context: &Context, msg_ids: &[MsgId], chat_id: ChatId) -> Result<()> {
     ensure!(!msg_ids.is_empty(), "empty msgs_ids: nothing to forward");
@@ -4555,7 +7020,7 @@ pub async fn add_device_msg_with_importance(
     }
 
     if !msg_id.is_unset() {
-        chat_id.emit_msg_event(context, msg_id, important);
+        chat_id.emit_msg_event(context, msg_id, important).await;
     }
 
     Ok(msg_id)
@@ -4763,11 +7228,38 @@ pub(crate) enum SyncAction {
     Rename(String),
     /// Set chat contacts by their addresses.
     SetContacts(Vec<String>),
+    SetRetention(RetentionPolicy),
+    /// Set the encryption preference of a contact.
+    SetEncryptionOverride(EncryptionOverride),
+    /// Convert broadcast list into a group with the given group ID.
+    ConvertToGroup(String),
+    /// Set the last read message by its Message-ID and "sent" timestamp.
+    SetLastRead {
+        msg: String,
+        #[serde(default)]
+        timestamp: i64,
+    },
+    /// Set the inactivity timer in days.
+    SetInactivityTimer(u32),
+    /// Mark the chat as unread or remove the mark.
+    SetMarkedUnread(bool),
+    /// Set whether the archived chat is unarchived by new messages.
+    SetUnarchivePolicy(UnarchivePolicy),
 }
 
 impl Context {
     /// Executes [`SyncData::AlterChat`] item sent by other device.
-    pub(crate) async fn sync_alter_chat(&self, id: &SyncId, action: &SyncAction) -> Result<()> {
+    ///
+    /// `timestamp` is the time the item was created on the other device.
+    /// Visibility, mute and contact blocking changes
+    /// older than the last change on this device are ignored,
+    /// so that devices converge to the most recent state even if sync messages are reordered.
+    pub(crate) async fn sync_alter_chat(
+        &self,
+        id: &SyncId,
+        action: &SyncAction,
+        timestamp: i64,
+    ) -> Result<()> {
         let chat_id = match id {
             SyncId::ContactAddr(addr) => {
                 if let SyncAction::Rename(to) = action {
@@ -4778,11 +7270,29 @@ pub(crate) async fn sync_alter_chat(&self, id: &SyncId, action: &SyncAction) ->
                 let (contact_id, _) =
                     Contact::add_or_lookup(self, "", &addr, Origin::Hidden).await?;
                 match action {
-                    SyncAction::Block => {
-                        return contact::set_blocked(self, Nosync, contact_id, true).await
+                    SyncAction::Block | SyncAction::Unblock => {
+                        if !self
+                            .update_contacts_timestamp(
+                                contact_id,
+                                Param::BlockedTimestamp,
+                                timestamp,
+                            )
+                            .await?
+                        {
+                            info!(
+                                self,
+                                "Ignoring outdated sync item {action:?} for {contact_id}, changed locally later."
+                            );
+                            return Ok(());
+                        }
+                        let new_blocking = *action == SyncAction::Block;
+                        return contact::set_blocked(self, Nosync, contact_id, new_blocking).await;
                     }
-                    SyncAction::Unblock => {
-                        return contact::set_blocked(self, Nosync, contact_id, false).await
+                    SyncAction::SetEncryptionOverride(value) => {
+                        return contact::set_encryption_override_ex(
+                            self, Nosync, contact_id, *value,
+                        )
+                        .await
                     }
                     _ => (),
                 }
@@ -4810,17 +7320,51 @@ pub(crate) async fn sync_alter_chat(&self, id: &SyncId, action: &SyncAction) ->
                     .with_context(|| format!("No chat found for Message-IDs {msgids:?}"))?
             }
         };
+        let timestamp_scope = match action {
+            SyncAction::SetVisibility(_) => Some(Param::VisibilityTimestamp),
+            SyncAction::SetMuted(_) => Some(Param::MuteTimestamp),
+            _ => None,
+        };
+        if let Some(scope) = timestamp_scope {
+            if !chat_id.update_timestamp(self, scope, timestamp).await? {
+                info!(
+                    self,
+                    "Ignoring outdated sync item {action:?} for {chat_id}, changed locally later."
+                );
+                return Ok(());
+            }
+        }
         match action {
             SyncAction::Block => chat_id.block_ex(self, Nosync).await,
             SyncAction::Unblock => chat_id.unblock_ex(self, Nosync).await,
             SyncAction::Accept => chat_id.accept_ex(self, Nosync).await,
             SyncAction::SetVisibility(v) => chat_id.set_visibility_ex(self, Nosync, *v).await,
             SyncAction::SetMuted(duration) => set_muted_ex(self, Nosync, chat_id, *duration).await,
-            SyncAction::CreateBroadcast(_) => {
+            SyncAction::CreateBroadcast(_) | SyncAction::SetEncryptionOverride(_) => {
                 Err(anyhow!("sync_alter_chat({id:?}, {action:?}): Bad request."))
             }
             SyncAction::Rename(to) => rename_ex(self, Nosync, chat_id, to).await,
             SyncAction::SetContacts(addrs) => set_contacts_by_addrs(self, chat_id, addrs).await,
+            SyncAction::SetRetention(policy) => {
+                chat_id.set_retention_ex(self, Nosync, *policy).await
+            }
+            SyncAction::ConvertToGroup(grpid) => {
+                convert_broadcast_to_group_ex(self, Nosync, chat_id, grpid.clone()).await
+            }
+            SyncAction::SetLastRead { msg, timestamp } => {
+                chat_id
+                    .set_last_read_ex(self, Nosync, msg, *timestamp)
+                    .await
+            }
+            SyncAction::SetInactivityTimer(days) => {
+                chat_id.set_inactivity_timer_ex(self, Nosync, *days).await
+            }
+            SyncAction::SetMarkedUnread(unread) => {
+                chat_id.set_marked_unread_ex(self, Nosync, *unread).await
+            }
+            SyncAction::SetUnarchivePolicy(policy) => {
+                chat_id.set_unarchive_policy_ex(self, Nosync, *policy).await
+            }
         }
     }
 
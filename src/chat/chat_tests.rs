@@ -1,11 +1,12 @@
 use super::*;
 use crate::chatlist::get_archived_cnt;
-use crate::constants::{DC_GCL_ARCHIVED_ONLY, DC_GCL_NO_SPECIALS};
+use crate::constants::{DC_GCL_ARCHIVED_ONLY, DC_GCL_NO_SPECIALS, DC_STALE_USER_AVATAR_DAYS};
 use crate::headerdef::HeaderDef;
 use crate::imex::{has_backup, imex, ImexMode};
 use crate::message::{delete_msgs, MessengerMessage};
 use crate::receive_imf::receive_imf;
 use crate::test_utils::{sync, TestContext, TestContextManager, TimeShiftFalsePositiveNote};
+use ratelimit::Ratelimit;
 use strum::IntoEnumIterator;
 use tokio::fs;
 
@@ -288,6 +289,65 @@ async fn test_add_contact_to_chat_ex_add_self() {
     assert_eq!(added, false);
 }
 
+/// Tests member limits, throttling and batching of member additions.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_add_contacts_to_chat_limits() -> Result<()> {
+    let mut tcm = TestContextManager::new();
+    let alice = &tcm.alice().await;
+    let bob = &tcm.bob().await;
+    alice
+        .set_config_u32(Config::GroupMemberSoftLimit, 2)
+        .await?;
+    alice
+        .set_config_u32(Config::GroupMemberHardLimit, 4)
+        .await?;
+
+    let alice_chat_id = create_group_chat(alice, ProtectionStatus::Unprotected, "grp").await?;
+    let alice_bob_id = alice.add_or_lookup_contact_id(bob).await;
+    add_contact_to_chat(alice, alice_chat_id, alice_bob_id).await?;
+    let sent = alice.send_text(alice_chat_id, "Hi!").await;
+    let bob_chat_id = bob.recv_msg(&sent).await.chat_id;
+
+    let fiona_id = Contact::create(alice, "", "fiona@example.net").await?;
+    let claire_id = Contact::create(alice, "", "claire@example.org").await?;
+    let dom_id = Contact::create(alice, "", "dom@example.net").await?;
+    let res = add_contacts_to_chat(alice, alice_chat_id, &[fiona_id, claire_id, dom_id]).await;
+    assert!(res.is_err());
+    assert_eq!(get_chat_contacts(alice, alice_chat_id).await?.len(), 2);
+
+    // A single message is sent for all added members.
+    add_contacts_to_chat(alice, alice_chat_id, &[fiona_id, claire_id, alice_bob_id]).await?;
+    assert_eq!(get_chat_contacts(alice, alice_chat_id).await?.len(), 4);
+    let sent = alice.pop_sent_msg().await;
+    assert!(alice.pop_sent_msg_opt(Duration::ZERO).await.is_none());
+    bob.recv_msg(&sent).await;
+    assert_eq!(get_chat_contacts(bob, bob_chat_id).await?.len(), 4);
+
+    // The group grew above the soft limit.
+    let msgs = get_chat_msgs(alice, alice_chat_id).await?;
+    let ChatItem::Message { msg_id } = msgs[msgs.len() - 2] else {
+        panic!("Wrong item type");
+    };
+    let msg = Message::load_from_db(alice, msg_id).await?;
+    assert!(msg.is_info());
+    assert!(msg.get_text().contains("more than 2 members"));
+
+    // Adding members is throttled.
+    alice
+        .set_config_u32(Config::GroupMemberHardLimit, 0)
+        .await?;
+    {
+        let mut ratelimit = alice.member_add_ratelimit.write().await;
+        *ratelimit = Ratelimit::new(Duration::from_secs(60), 1.0);
+        ratelimit.send();
+    }
+    assert!(add_contact_to_chat(alice, alice_chat_id, dom_id)
+        .await
+        .is_err());
+    assert!(!is_contact_in_chat(alice, alice_chat_id, dom_id).await?);
+    Ok(())
+}
+
 /// Test adding and removing members in a group chat.
 ///
 /// Make sure messages sent outside contain authname
@@ -1143,6 +1203,85 @@ async fn msg_from_bob(t: &TestContext, num: u32) -> Result<()> {
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_unarchive_policy() -> Result<()> {
+    let mut tcm = TestContextManager::new();
+    let alice = &tcm.alice().await;
+    let bob = &tcm.bob().await;
+
+    let bob_chat_id = tcm.send_recv_accept(alice, bob, "Hi").await.chat_id;
+    assert_eq!(
+        bob_chat_id.get_unarchive_policy(bob).await?,
+        UnarchivePolicy::AnyMessage
+    );
+    bob_chat_id
+        .set_visibility(bob, ChatVisibility::Archived)
+        .await?;
+    bob_chat_id
+        .set_unarchive_policy(bob, UnarchivePolicy::Never)
+        .await?;
+    assert_eq!(
+        bob_chat_id.get_unarchive_policy(bob).await?,
+        UnarchivePolicy::Never
+    );
+    tcm.send_recv(alice, bob, "Still there?").await;
+    let chat = Chat::load_from_db(bob, bob_chat_id).await?;
+    assert_eq!(chat.get_visibility(), ChatVisibility::Archived);
+    assert_eq!(bob_chat_id.get_fresh_msg_cnt(bob).await?, 2);
+    assert_eq!(DC_CHAT_ID_ARCHIVED_LINK.get_fresh_msg_cnt(bob).await?, 0);
+    assert!(bob.get_fresh_msgs().await?.is_empty());
+
+    bob_chat_id
+        .set_unarchive_policy(bob, UnarchivePolicy::Mention)
+        .await?;
+    tcm.send_recv(alice, bob, "Hello").await;
+    let chat = Chat::load_from_db(bob, bob_chat_id).await?;
+    assert_eq!(chat.get_visibility(), ChatVisibility::Archived);
+    tcm.send_recv(alice, bob, "Hello @bob@example.network")
+        .await;
+    let chat = Chat::load_from_db(bob, bob_chat_id).await?;
+    assert_eq!(chat.get_visibility(), ChatVisibility::Archived);
+    tcm.send_recv(alice, bob, "Hello @BOB@example.net").await;
+    let chat = Chat::load_from_db(bob, bob_chat_id).await?;
+    assert_eq!(chat.get_visibility(), ChatVisibility::Normal);
+    assert_eq!(bob.get_fresh_msgs().await?.len(), 5);
+
+    // Replies to own messages are mentions, too.
+    bob_chat_id
+        .set_visibility(bob, ChatVisibility::Archived)
+        .await?;
+    let sent = bob.send_text(bob_chat_id, "Yes").await;
+    let alice_msg = alice.recv_msg(&sent).await;
+    let mut reply = Message::new_text("Good".to_string());
+    reply.set_quote(alice, Some(&alice_msg)).await?;
+    let sent = alice.send_msg(alice_msg.chat_id, &mut reply).await;
+    bob.recv_msg(&sent).await;
+    let chat = Chat::load_from_db(bob, bob_chat_id).await?;
+    assert_eq!(chat.get_visibility(), ChatVisibility::Normal);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_is_mention() -> Result<()> {
+    let t = TestContext::new_bob().await;
+    t.set_config(Config::Displayname, Some("Bob")).await?;
+    for (text, expected) in [
+        ("Hi @bob", true),
+        ("Hi @Bob, how are you?", true),
+        ("@BOB@example.net.", true),
+        ("(@bob)", true),
+        ("Hi bob", false),
+        ("Hi @bobby", false),
+        ("Hi @bob_", false),
+        ("Hi @bob-smith", false),
+        ("Write to alice@bob.example.org", false),
+        ("Write to @bob@example.network", false),
+    ] {
+        assert_eq!(is_mention(&t, text).await?, expected, "{text}");
+    }
+    Ok(())
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn test_archive_fresh_msgs() -> Result<()> {
     let t = TestContext::new_alice().await;
@@ -1418,6 +1557,124 @@ async fn test_shall_attach_selfavatar() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_request_stale_profile() -> Result<()> {
+    let mut tcm = TestContextManager::new();
+    let alice = &tcm.alice().await;
+    let bob = &tcm.bob().await;
+
+    // Bob's avatar state is sent with the first message.
+    tcm.send_recv_accept(bob, alice, "Hi").await;
+    let mut bob_contact = alice.add_or_lookup_contact(bob).await;
+    assert!(!bob_contact.is_profile_image_stale());
+    let alice_chat = alice.create_chat(bob).await;
+    let sent = alice.send_text(alice_chat.id, "Hello").await;
+    let msg = bob.parse_msg(&sent).await;
+    assert!(msg.get_header(HeaderDef::ChatProfileRequest).is_none());
+
+    let old_timestamp = time() - (DC_STALE_USER_AVATAR_DAYS + 1) * 24 * 60 * 60;
+    bob_contact
+        .param
+        .set_i64(Param::AvatarTimestamp, old_timestamp);
+    bob_contact.update_param(alice).await?;
+    let bob_contact = Contact::get_by_id(alice, bob_contact.id).await?;
+    assert!(bob_contact.is_profile_image_stale());
+
+    // Bob sent the avatar two days ago, so the request is handled.
+    let bob_chat = bob.create_chat(alice).await;
+    bob.sql
+        .execute(
+            "UPDATE contacts SET selfavatar_sent=?",
+            (time() - 2 * 24 * 60 * 60,),
+        )
+        .await?;
+    assert!(!shall_attach_selfavatar(bob, bob_chat.id).await?);
+
+    let sent = alice.send_text(alice_chat.id, "Hello again").await;
+    let msg = bob.parse_msg(&sent).await;
+    assert!(msg.get_header(HeaderDef::ChatProfileRequest).is_some());
+    bob.recv_msg(&sent).await;
+    assert!(shall_attach_selfavatar(bob, bob_chat.id).await?);
+
+    // The request is not repeated.
+    let sent = alice.send_text(alice_chat.id, "Hello once more").await;
+    let msg = bob.parse_msg(&sent).await;
+    assert!(msg.get_header(HeaderDef::ChatProfileRequest).is_none());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_lazy_selfavatar_in_large_group() -> Result<()> {
+    let mut tcm = TestContextManager::new();
+    let alice = &tcm.alice().await;
+    let bob = &tcm.bob().await;
+
+    let chat_id = create_group_chat(alice, ProtectionStatus::Unprotected, "large").await?;
+    add_contact_to_chat(alice, chat_id, alice.add_or_lookup_contact_id(bob).await).await?;
+    for i in 1..DC_LAZY_AVATAR_MIN_MEMBERS {
+        let (contact_id, _) = Contact::add_or_lookup(
+            alice,
+            "",
+            &ContactAddress::new(&format!("member{i}@example.org"))?,
+            Origin::IncomingUnknownTo,
+        )
+        .await?;
+        add_contact_to_chat(alice, chat_id, contact_id).await?;
+    }
+    assert!(uses_lazy_selfavatar(alice, chat_id).await?);
+
+    let file = alice.get_blobdir().join("avatar.png");
+    fs::write(
+        &file,
+        include_bytes!("../../test-data/image/avatar64x64.png"),
+    )
+    .await?;
+    alice
+        .set_config(Config::Selfavatar, Some(file.to_str().unwrap()))
+        .await?;
+    assert!(shall_attach_selfavatar(alice, chat_id).await?);
+
+    // The avatar was sent long ago, but it is not resent periodically in large groups.
+    alice
+        .sql
+        .execute(
+            "UPDATE contacts SET selfavatar_sent=?",
+            (time() - (DC_RESEND_USER_AVATAR_DAYS + 1) * 24 * 60 * 60,),
+        )
+        .await?;
+    assert!(!shall_attach_selfavatar(alice, chat_id).await?);
+    let sent = alice.send_text(chat_id, "Hi all").await;
+    let msg = bob.parse_msg(&sent).await;
+    assert!(msg.get_header(HeaderDef::ChatUserAvatar).is_none());
+    assert!(msg.get_header(HeaderDef::ChatUserAvatarHash).is_some());
+
+    // Bob does not have Alice's avatar and requests it.
+    let bob_chat_id = bob.recv_msg(&sent).await.chat_id;
+    bob_chat_id.accept(bob).await?;
+    let sent = bob.send_text(bob_chat_id, "Hello").await;
+    let msg = alice.parse_msg(&sent).await;
+    assert_eq!(
+        msg.get_header(HeaderDef::ChatUserAvatarRequest),
+        Some("alice@example.org")
+    );
+    let bob_chat = Chat::load_from_db(bob, bob_chat_id).await?;
+    assert!(bob_chat.param.get(Param::AvatarRequests).is_none());
+
+    alice.recv_msg(&sent).await;
+    assert!(shall_attach_selfavatar(alice, chat_id).await?);
+    let sent = alice.send_text(chat_id, "My avatar").await;
+    bob.recv_msg(&sent).await;
+    let alice_contact = bob.add_or_lookup_contact(alice).await;
+    assert!(alice_contact.get_profile_image(bob).await?.is_some());
+
+    // Now the hash matches and the avatar is not requested again.
+    let sent = alice.send_text(chat_id, "Hi again").await;
+    bob.recv_msg(&sent).await;
+    let bob_chat = Chat::load_from_db(bob, bob_chat_id).await?;
+    assert!(bob_chat.param.get(Param::AvatarRequests).is_none());
+    Ok(())
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn test_set_mute_duration() {
     let t = TestContext::new().await;
@@ -1486,6 +1743,211 @@ async fn test_add_info_msg() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_daymarkers_timezone() -> Result<()> {
+    let t = TestContext::new().await;
+    assert!(t
+        .set_config(Config::Timezone, Some("Mars/Olympus_Mons"))
+        .await
+        .is_err());
+    t.set_config(Config::Timezone, Some("+02:00")).await?;
+    let chat_id = create_group_chat(&t, ProtectionStatus::Unprotected, "foo").await?;
+
+    // 2024-01-01 00:00 at UTC+2.
+    let day1 = 1704060000;
+    let day2 = day1 + 86400;
+    let msg1 = add_info_msg(&t, chat_id, "one", day1 + 3600).await?;
+    let msg2 = add_info_msg(&t, chat_id, "two", day1 + 23 * 3600).await?;
+    let msg3 = add_info_msg(&t, chat_id, "three", day2 + 3600).await?;
+
+    let items = get_chat_msgs_ex(
+        &t,
+        chat_id,
+        MessageListOptions {
+            info_only: false,
+            add_daymarker: true,
+            include_hidden_local: false,
+        },
+    )
+    .await?;
+    assert_eq!(
+        items,
+        vec![
+            ChatItem::DayMarker { timestamp: day1 },
+            ChatItem::Message { msg_id: msg1 },
+            ChatItem::Message { msg_id: msg2 },
+            ChatItem::DayMarker { timestamp: day2 },
+            ChatItem::Message { msg_id: msg3 },
+        ]
+    );
+
+    let boundaries = get_day_boundaries(&t, chat_id, 0..i64::MAX).await?;
+    assert_eq!(
+        boundaries,
+        vec![
+            DayBoundary {
+                timestamp: day1,
+                msg_id: msg1
+            },
+            DayBoundary {
+                timestamp: day2,
+                msg_id: msg3
+            },
+        ]
+    );
+    let boundaries = get_day_boundaries(&t, chat_id, day1 + 7200..i64::MAX).await?;
+    assert_eq!(
+        boundaries,
+        vec![DayBoundary {
+            timestamp: day2,
+            msg_id: msg3
+        }]
+    );
+    assert!(get_day_boundaries(&t, chat_id, day1 + 7200..day2)
+        .await?
+        .is_empty());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_daymarkers_timezone_dst() -> Result<()> {
+    let t = TestContext::new().await;
+    t.set_config(Config::Timezone, Some("Europe/Berlin"))
+        .await?;
+    let chat_id = create_group_chat(&t, ProtectionStatus::Unprotected, "foo").await?;
+
+    // 2024-03-31 00:00 CET, clocks are set forward at 02:00.
+    let day1 = 1711839600;
+    // 2024-04-01 00:00 CEST, 23 hours later.
+    let day2 = day1 + 23 * 3600;
+    let msg1 = add_info_msg(&t, chat_id, "one", day1 + 3600).await?;
+    // 23:30 at UTC+1, but already the next day in CEST.
+    let msg2 = add_info_msg(&t, chat_id, "two", day2 + 1800).await?;
+
+    let items = get_chat_msgs_ex(
+        &t,
+        chat_id,
+        MessageListOptions {
+            info_only: false,
+            add_daymarker: true,
+            include_hidden_local: false,
+        },
+    )
+    .await?;
+    assert_eq!(
+        items,
+        vec![
+            ChatItem::DayMarker { timestamp: day1 },
+            ChatItem::Message { msg_id: msg1 },
+            ChatItem::DayMarker { timestamp: day2 },
+            ChatItem::Message { msg_id: msg2 },
+        ]
+    );
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_get_chat_msgs_before() -> Result<()> {
+    let t = TestContext::new().await;
+    let chat_id = create_group_chat(&t, ProtectionStatus::Unprotected, "foo").await?;
+    let msg1 = add_info_msg(&t, chat_id, "one", 1000).await?;
+    let msg2 = add_info_msg(&t, chat_id, "two", 2000).await?;
+    let msg3 = add_info_msg(&t, chat_id, "three", 2000).await?;
+    let msg4 = add_info_msg(&t, chat_id, "four", 3000).await?;
+
+    let page = get_chat_msgs_before(&t, chat_id, None, 2).await?;
+    assert_eq!(page, vec![(2000, msg3), (3000, msg4)]);
+
+    // New messages do not shift the next page.
+    add_info_msg(&t, chat_id, "five", 4000).await?;
+    let page = get_chat_msgs_before(&t, chat_id, Some(page[0]), 2).await?;
+    assert_eq!(page, vec![(1000, msg1), (2000, msg2)]);
+    let page = get_chat_msgs_before(&t, chat_id, Some(page[0]), 2).await?;
+    assert!(page.is_empty());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_get_timeline() -> Result<()> {
+    let t = TestContext::new_alice().await;
+    t.set_config(Config::Timezone, Some("+00:00")).await?;
+    let chat_id = t
+        .create_chat_with_contact("Bob", "bob@example.net")
+        .await
+        .id;
+    let day1 = 10 * 86400;
+    let day2 = 11 * 86400;
+    let mut info_msgs = Vec::new();
+    for (cmd, timestamp) in [
+        (SystemMessage::EphemeralTimerChanged, day1 + 100),
+        (SystemMessage::MemberAddedToGroup, day1 + 200),
+        (SystemMessage::WebxdcInfoMessage, day2 + 100),
+        (SystemMessage::MemberRemovedFromGroup, day2 + 200),
+    ] {
+        let msg_id =
+            add_info_msg_with_cmd(&t, chat_id, "info", cmd, timestamp, None, None, None).await?;
+        info_msgs.push(msg_id);
+    }
+    let [timer, added, webxdc, removed] = info_msgs[..] else {
+        unreachable!();
+    };
+    let msg = t.send_text(chat_id, "Hi!").await.sender_msg_id;
+
+    let items = |entries: &[TimelineEntry]| entries.iter().map(|e| e.item).collect::<Vec<_>>();
+    let entries = get_timeline(&t, chat_id, TimelineAnchor::Newest, 10, 0).await?;
+    assert!(entries.windows(2).all(|w| w[0].key < w[1].key));
+    let today = Message::load_from_db(&t, msg).await?.timestamp_sort / 86400 * 86400;
+    assert_eq!(
+        items(&entries),
+        vec![
+            TimelineItem::DayMarker { timestamp: day1 },
+            TimelineItem::Info { msg_id: timer },
+            TimelineItem::MembershipChange {
+                msg_id: added,
+                added: true
+            },
+            TimelineItem::DayMarker { timestamp: day2 },
+            TimelineItem::WebxdcUpdate { msg_id: webxdc },
+            TimelineItem::MembershipChange {
+                msg_id: removed,
+                added: false
+            },
+            TimelineItem::DayMarker { timestamp: today },
+            TimelineItem::Message { msg_id: msg },
+        ]
+    );
+
+    // Jump to a message.
+    let entries = get_timeline(&t, chat_id, TimelineAnchor::Message(webxdc), 1, 1).await?;
+    assert_eq!(
+        items(&entries),
+        vec![
+            TimelineItem::MembershipChange {
+                msg_id: added,
+                added: true
+            },
+            TimelineItem::DayMarker { timestamp: day2 },
+            TimelineItem::WebxdcUpdate { msg_id: webxdc },
+            TimelineItem::MembershipChange {
+                msg_id: removed,
+                added: false
+            },
+        ]
+    );
+
+    // Continue loading from a day marker.
+    let anchor = TimelineAnchor::Key(entries[1].key);
+    let entries = get_timeline(&t, chat_id, anchor, 0, 1).await?;
+    assert_eq!(
+        items(&entries),
+        vec![
+            TimelineItem::DayMarker { timestamp: day2 },
+            TimelineItem::WebxdcUpdate { msg_id: webxdc },
+        ]
+    );
+    Ok(())
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn test_add_info_msg_with_cmd() -> Result<()> {
     let t = TestContext::new().await;
@@ -2563,6 +3025,83 @@ async fn test_broadcast_multidev() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_broadcast_stats_and_convert() -> Result<()> {
+    let mut tcm = TestContextManager::new();
+    let alice = &tcm.alice().await;
+    let bob = &tcm.bob().await;
+    let fiona = &tcm.fiona().await;
+
+    let broadcast_id = create_broadcast_list(alice).await?;
+    let alice_bob_id = alice.add_or_lookup_contact_id(bob).await;
+    let alice_fiona_id = alice.add_or_lookup_contact_id(fiona).await;
+    for contact_id in [alice_bob_id, alice_fiona_id] {
+        add_contact_to_chat(alice, broadcast_id, contact_id).await?;
+    }
+    let sent = alice.send_text(broadcast_id, "Newsletter").await;
+    let stats = get_broadcast_stats(alice, broadcast_id).await?;
+    assert_eq!(stats.len(), 1);
+    assert_eq!(stats[0].msg_id, sent.sender_msg_id);
+    assert_eq!(stats[0].recipients, 2);
+    assert_eq!(stats[0].delivered, 2);
+    assert_eq!(stats[0].read, 0);
+    assert!(stats[0]
+        .recipient_stats
+        .iter()
+        .all(|recipient| recipient.state == BroadcastRecipientState::Delivered));
+
+    // Bob sends a read receipt.
+    let received = bob.recv_msg(&sent).await;
+    let mdn = crate::mimefactory::MimeFactory::from_mdn(
+        bob,
+        received.from_id,
+        received.rfc724_mid,
+        vec![],
+    )
+    .await?
+    .render(bob)
+    .await?;
+    receive_imf(alice, mdn.message.as_bytes(), false).await?;
+    let stats = get_broadcast_stats(alice, broadcast_id).await?;
+    assert_eq!(stats[0].read, 1);
+    let mut expected = vec![
+        BroadcastRecipientStats {
+            contact_id: alice_bob_id,
+            state: BroadcastRecipientState::Read,
+        },
+        BroadcastRecipientStats {
+            contact_id: alice_fiona_id,
+            state: BroadcastRecipientState::Delivered,
+        },
+    ];
+    expected.sort_by_key(|recipient| recipient.contact_id);
+    assert_eq!(stats[0].recipient_stats, expected);
+
+    convert_broadcast_to_group(alice, broadcast_id).await?;
+    let chat = Chat::load_from_db(alice, broadcast_id).await?;
+    assert_eq!(chat.typ, Chattype::Group);
+    assert!(chat.is_unpromoted());
+    assert_eq!(get_chat_contacts(alice, broadcast_id).await?.len(), 3);
+    assert!(is_group_admin(alice, broadcast_id, ContactId::SELF).await?);
+    // The user is warned that the members will see each other's addresses.
+    let info = alice.get_last_msg_in(broadcast_id).await;
+    assert!(info.is_info());
+    assert!(info.get_text().contains("see each other's addresses"));
+    assert!(get_broadcast_stats(alice, broadcast_id).await.is_err());
+    assert!(convert_broadcast_to_group(alice, broadcast_id)
+        .await
+        .is_err());
+
+    let msg = bob
+        .recv_msg(&alice.send_text(broadcast_id, "We are a group now").await)
+        .await;
+    let bob_chat = Chat::load_from_db(bob, msg.chat_id).await?;
+    assert_eq!(bob_chat.typ, Chattype::Group);
+    assert_ne!(bob_chat.id, received.chat_id);
+    assert_eq!(get_chat_contacts(bob, bob_chat.id).await?.len(), 3);
+    Ok(())
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn test_create_for_contact_with_blocked() -> Result<()> {
     let t = TestContext::new().await;
@@ -2657,6 +3196,48 @@ async fn test_chat_get_encryption_info() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_chat_get_encryption_details() -> Result<()> {
+    let alice = TestContext::new_alice().await;
+    let bob = TestContext::new_bob().await;
+
+    let contact_bob = Contact::create(&alice, "Bob", "bob@example.net").await?;
+    let contact_fiona = Contact::create(&alice, "", "fiona@example.net").await?;
+    let chat_id = create_group_chat(&alice, ProtectionStatus::Unprotected, "Group").await?;
+    add_contact_to_chat(&alice, chat_id, contact_bob).await?;
+    add_contact_to_chat(&alice, chat_id, contact_fiona).await?;
+
+    let details = chat_id.get_encryption_details(&alice).await?;
+    assert_eq!(details.members.len(), 2);
+    assert!(details
+        .members
+        .iter()
+        .all(|member| !member.key_available && member.prefer_encrypt == EncryptPreference::Reset));
+    assert!(!details.can_encrypt);
+
+    let direct_chat = bob.create_chat(&alice).await;
+    send_text_msg(&bob, direct_chat.id, "Hello!".to_string()).await?;
+    alice.recv_msg(&bob.pop_sent_msg().await).await;
+
+    // Fiona's key is still missing.
+    let details = chat_id.get_encryption_details(&alice).await?;
+    let bob_details = details
+        .members
+        .iter()
+        .find(|member| member.contact_id == contact_bob)
+        .unwrap();
+    assert!(bob_details.key_available);
+    assert!(!bob_details.verified);
+    assert_eq!(bob_details.prefer_encrypt, EncryptPreference::Mutual);
+    assert!(!details.can_encrypt);
+
+    let alice_bob_chat = alice.create_chat(&bob).await;
+    let details = alice_bob_chat.id.get_encryption_details(&alice).await?;
+    assert_eq!(details.members.len(), 1);
+    assert!(details.can_encrypt);
+    Ok(())
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn test_get_chat_media() -> Result<()> {
     let t = TestContext::new_alice().await;
@@ -3059,6 +3640,59 @@ async fn test_sync_visibility() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_retention_keep_last_messages() -> Result<()> {
+    let mut tcm = TestContextManager::new();
+    let alice0 = &tcm.alice().await;
+    let alice1 = &tcm.alice().await;
+    for a in [alice0, alice1] {
+        a.set_config_bool(Config::SyncMsgs, true).await?;
+    }
+    let bob = &tcm.bob().await;
+    let a0b_chat_id = alice0.create_chat(bob).await.id;
+    alice1.create_chat(bob).await;
+    let b_chat_id = bob.create_chat(alice0).await.id;
+    for i in 0..5 {
+        let sent = bob.send_text(b_chat_id, &format!("Message {i}")).await;
+        alice0.recv_msg(&sent).await;
+    }
+
+    assert_eq!(
+        a0b_chat_id.get_retention(alice0).await?,
+        RetentionPolicy::KeepAll
+    );
+    let policy = RetentionPolicy::KeepLastMessages(2);
+    a0b_chat_id.set_retention(alice0, policy).await?;
+    sync(alice0, alice1).await;
+    assert_eq!(
+        alice0.get_chat(bob).await.id.get_retention(alice0).await?,
+        policy
+    );
+    assert_eq!(
+        alice1.get_chat(bob).await.id.get_retention(alice1).await?,
+        policy
+    );
+
+    crate::sql::housekeeping(alice0).await?;
+    let mut texts = Vec::new();
+    for item in get_chat_msgs(alice0, a0b_chat_id).await? {
+        if let ChatItem::Message { msg_id } = item {
+            texts.push(Message::load_from_db(alice0, msg_id).await?.get_text());
+        }
+    }
+    assert_eq!(texts, vec!["Message 3", "Message 4"]);
+
+    // Removing the policy stops deleting messages.
+    a0b_chat_id
+        .set_retention(alice0, RetentionPolicy::KeepAll)
+        .await?;
+    let sent = bob.send_text(b_chat_id, "Message 5").await;
+    alice0.recv_msg(&sent).await;
+    crate::sql::housekeeping(alice0).await?;
+    assert_eq!(get_chat_msgs(alice0, a0b_chat_id).await?.len(), 3);
+    Ok(())
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn test_sync_muted() -> Result<()> {
     let alice0 = &TestContext::new_alice().await;
@@ -3094,6 +3728,186 @@ async fn test_sync_muted() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_sync_last_read() -> Result<()> {
+    let mut tcm = TestContextManager::new();
+    let alice0 = &tcm.alice().await;
+    let alice1 = &tcm.alice().await;
+    for a in [alice0, alice1] {
+        a.set_config_bool(Config::SyncMsgs, true).await?;
+    }
+    let bob = &tcm.bob().await;
+    let b_chat_id = bob.create_chat(alice0).await.id;
+
+    let mut sent_msgs = Vec::new();
+    for i in 0..4 {
+        sent_msgs.push(bob.send_text(b_chat_id, &format!("Message {i}")).await);
+    }
+    let mut a0_msg_ids = Vec::new();
+    for sent in &sent_msgs {
+        a0_msg_ids.push(alice0.recv_msg(sent).await.id);
+    }
+    // The last message is not yet received by the second device.
+    let mut a1_msg_ids = Vec::new();
+    for sent in &sent_msgs[..2] {
+        a1_msg_ids.push(alice1.recv_msg(sent).await.id);
+    }
+    let a0_chat_id = alice0.get_chat(bob).await.id;
+    let a1_chat_id = alice1.get_chat(bob).await.id;
+    a0_chat_id.accept(alice0).await?;
+    a1_chat_id.accept(alice1).await?;
+    assert_eq!(a0_chat_id.get_fresh_msg_cnt(alice0).await?, 4);
+    assert_eq!(a1_chat_id.get_last_read_msg(alice1).await?, None);
+
+    a0_chat_id.set_last_read_msg(alice0, a0_msg_ids[2]).await?;
+    assert_eq!(a0_chat_id.get_fresh_msg_cnt(alice0).await?, 1);
+    assert_eq!(
+        a0_chat_id.get_last_read_msg(alice0).await?,
+        Some(a0_msg_ids[2])
+    );
+    sync(alice0, alice1).await;
+    // The marker is not available on the second device yet.
+    assert_eq!(a1_chat_id.get_fresh_msg_cnt(alice1).await?, 2);
+    assert_eq!(a1_chat_id.get_last_read_msg(alice1).await?, None);
+
+    // The marker is not moved backwards although the marked message is not available.
+    a1_chat_id.set_last_read_msg(alice1, a1_msg_ids[1]).await?;
+    assert_eq!(a1_chat_id.get_fresh_msg_cnt(alice1).await?, 2);
+    assert_eq!(a1_chat_id.get_last_read_msg(alice1).await?, None);
+
+    // Once the marker arrives, it and the messages received before are noticed.
+    let a1_msg = alice1.recv_msg(&sent_msgs[2]).await;
+    assert_eq!(a1_msg.state, MessageState::InNoticed);
+    assert_eq!(a1_chat_id.get_fresh_msg_cnt(alice1).await?, 0);
+    assert_eq!(a1_chat_id.get_last_read_msg(alice1).await?, Some(a1_msg.id));
+    alice1.recv_msg(&sent_msgs[3]).await;
+    assert_eq!(a1_chat_id.get_fresh_msg_cnt(alice1).await?, 1);
+
+    // Moving the marker backwards is ignored.
+    a0_chat_id.set_last_read_msg(alice0, a0_msg_ids[0]).await?;
+    assert_eq!(
+        a0_chat_id.get_last_read_msg(alice0).await?,
+        Some(a0_msg_ids[2])
+    );
+
+    // Messages from other chats can't be used as a marker.
+    let self_chat_id = alice0.get_self_chat().await.id;
+    assert!(self_chat_id
+        .set_last_read_msg(alice0, a0_msg_ids[3])
+        .await
+        .is_err());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_marked_unread_and_marknoticed_all() -> Result<()> {
+    let mut tcm = TestContextManager::new();
+    let alice0 = &tcm.alice().await;
+    let alice1 = &tcm.alice().await;
+    for a in [alice0, alice1] {
+        a.set_config_bool(Config::SyncMsgs, true).await?;
+    }
+    let bob = &tcm.bob().await;
+    let fiona = &tcm.fiona().await;
+
+    let b_chat_id = bob.create_chat(alice0).await.id;
+    let sent = bob.send_text(b_chat_id, "Hi").await;
+    let a0_chat_id = alice0.recv_msg(&sent).await.chat_id;
+    let a1_chat_id = alice1.recv_msg(&sent).await.chat_id;
+    a0_chat_id.accept(alice0).await?;
+    a1_chat_id.accept(alice1).await?;
+    marknoticed_chat(alice0, a0_chat_id).await?;
+    marknoticed_chat(alice1, a1_chat_id).await?;
+    assert_eq!(a0_chat_id.get_fresh_msg_cnt(alice0).await?, 0);
+
+    a0_chat_id.set_marked_unread(alice0, true).await?;
+    assert!(a0_chat_id.is_marked_unread(alice0).await?);
+    assert_eq!(a0_chat_id.get_fresh_msg_cnt(alice0).await?, 1);
+    sync(alice0, alice1).await;
+    assert!(a1_chat_id.is_marked_unread(alice1).await?);
+    assert_eq!(a1_chat_id.get_fresh_msg_cnt(alice1).await?, 1);
+
+    // Archived chats marked as unread are counted for the archive link.
+    a0_chat_id
+        .set_visibility(alice0, ChatVisibility::Archived)
+        .await?;
+    assert_eq!(DC_CHAT_ID_ARCHIVED_LINK.get_fresh_msg_cnt(alice0).await?, 1);
+    marknoticed_chat(alice0, DC_CHAT_ID_ARCHIVED_LINK).await?;
+    assert!(!a0_chat_id.is_marked_unread(alice0).await?);
+    assert_eq!(DC_CHAT_ID_ARCHIVED_LINK.get_fresh_msg_cnt(alice0).await?, 0);
+    sync(alice0, alice1).await;
+    assert!(!a1_chat_id.is_marked_unread(alice1).await?);
+
+    // Marking all chats as noticed also removes the marks.
+    a1_chat_id.set_marked_unread(alice1, true).await?;
+    let f_chat_id = fiona.create_chat(alice0).await.id;
+    let sent = fiona.send_text(f_chat_id, "Hello").await;
+    let a0_f_chat_id = alice0.recv_msg(&sent).await.chat_id;
+    let a1_f_chat_id = alice1.recv_msg(&sent).await.chat_id;
+    a1_f_chat_id.accept(alice1).await?;
+    assert_eq!(a1_f_chat_id.get_fresh_msg_cnt(alice1).await?, 1);
+    marknoticed_all_chats(alice1).await?;
+    assert!(!a1_chat_id.is_marked_unread(alice1).await?);
+    assert_eq!(a1_chat_id.get_fresh_msg_cnt(alice1).await?, 0);
+    assert_eq!(a1_f_chat_id.get_fresh_msg_cnt(alice1).await?, 0);
+
+    a0_f_chat_id.accept(alice0).await?;
+    assert_eq!(a0_f_chat_id.get_fresh_msg_cnt(alice0).await?, 1);
+    sync(alice1, alice0).await;
+    assert_eq!(a0_f_chat_id.get_fresh_msg_cnt(alice0).await?, 0);
+
+    // Special chats can't be marked as unread.
+    assert!(DC_CHAT_ID_ARCHIVED_LINK
+        .set_marked_unread(alice0, true)
+        .await
+        .is_err());
+    Ok(())
+}
+
+/// Tests that outdated visibility and mute changes from another device
+/// do not overwrite more recent local changes, so that both devices converge.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_sync_chat_state_conflict() -> Result<()> {
+    let mut tcm = TestContextManager::new();
+    let alice0 = &tcm.alice().await;
+    let alice1 = &tcm.alice().await;
+    for a in [alice0, alice1] {
+        a.set_config_bool(Config::SyncMsgs, true).await?;
+    }
+    let bob = &tcm.bob().await;
+    let a0_chat_id = alice0
+        .create_group_with_members(ProtectionStatus::Unprotected, "Group", &[bob])
+        .await;
+    let sent = alice0.send_text(a0_chat_id, "hi").await;
+    let a1_chat_id = alice1.recv_msg(&sent).await.chat_id;
+
+    // Both devices change the state before receiving the sync message of the other one.
+    a0_chat_id
+        .set_visibility(alice0, ChatVisibility::Archived)
+        .await?;
+    set_muted(alice0, a0_chat_id, MuteDuration::Forever).await?;
+    SystemTime::shift(Duration::from_secs(60));
+    a1_chat_id
+        .set_visibility(alice1, ChatVisibility::Pinned)
+        .await?;
+    set_muted(alice1, a1_chat_id, MuteDuration::NotMuted).await?;
+
+    sync(alice0, alice1).await;
+    sync(alice1, alice0).await;
+    for (a, chat_id) in [(alice0, a0_chat_id), (alice1, a1_chat_id)] {
+        let chat = Chat::load_from_db(a, chat_id).await?;
+        assert_eq!(chat.get_visibility(), ChatVisibility::Pinned);
+        assert_eq!(chat.mute_duration, MuteDuration::NotMuted);
+    }
+
+    // Later changes still apply.
+    set_muted(alice0, a0_chat_id, MuteDuration::Forever).await?;
+    sync(alice0, alice1).await;
+    let chat = Chat::load_from_db(alice1, a1_chat_id).await?;
+    assert_eq!(chat.mute_duration, MuteDuration::Forever);
+    Ok(())
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn test_sync_broadcast() -> Result<()> {
     let alice0 = &TestContext::new_alice().await;
@@ -3547,3 +4361,360 @@ async fn test_one_to_one_chat_no_group_member_timestamps() {
     let payload = sent.payload;
     assert!(!payload.contains("Chat-Group-Member-Timestamps:"));
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_disable_mdn_requests() -> Result<()> {
+    let t = TestContext::new_alice().await;
+    let chat = t.create_chat_with_contact("bob", "bob@example.net").await;
+    assert!(!chat.is_mdn_requests_disabled());
+
+    let sent = t.send_text(chat.id, "Hi!").await;
+    assert!(sent
+        .payload
+        .contains("Chat-Disposition-Notification-To: alice@example.org"));
+
+    chat.id.set_mdn_requests_disabled(&t, true).await?;
+    let chat = Chat::load_from_db(&t, chat.id).await?;
+    assert!(chat.is_mdn_requests_disabled());
+    let sent = t.send_text(chat.id, "Hi again!").await;
+    assert!(!sent.payload.contains("Chat-Disposition-Notification-To:"));
+
+    chat.id.set_mdn_requests_disabled(&t, false).await?;
+    let sent = t.send_text(chat.id, "Hi for the third time!").await;
+    assert!(sent.payload.contains("Chat-Disposition-Notification-To:"));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_encryption_required() -> Result<()> {
+    let mut tcm = TestContextManager::new();
+    let alice = &tcm.alice().await;
+    let bob = &tcm.bob().await;
+
+    let chat = alice
+        .create_chat_with_contact("bob", "bob@example.net")
+        .await;
+    assert!(!chat.is_encryption_required());
+    chat.id.set_encryption_required(alice, true).await?;
+    let chat = Chat::load_from_db(alice, chat.id).await?;
+    assert!(chat.is_encryption_required());
+    let info = alice.get_last_msg_in(chat.id).await;
+    assert!(info.is_info());
+    assert_eq!(info.text, stock_str::encryption_unavailable(alice).await);
+    assert!(send_text_msg(alice, chat.id, "Hi!".to_string())
+        .await
+        .is_err());
+
+    // Bob's message contains his key.
+    let sent = bob
+        .send_text(bob.create_chat(alice).await.id, "Hello")
+        .await;
+    alice.recv_msg(&sent).await;
+    let info = alice.get_last_msg_in(chat.id).await;
+    assert!(info.is_info());
+    assert_eq!(info.text, stock_str::encryption_available(alice).await);
+
+    let sent = alice.send_text(chat.id, "Hi!").await;
+    let msg = bob.recv_msg(&sent).await;
+    assert!(msg.get_showpadlock());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_protection_log() -> Result<()> {
+    let mut tcm = TestContextManager::new();
+    let alice = &tcm.alice().await;
+    let bob = &tcm.bob().await;
+    tcm.execute_securejoin(bob, alice).await;
+    let alice_bob_id = alice.add_or_lookup_contact_id(bob).await;
+
+    let chat_id = create_group_chat(alice, ProtectionStatus::Protected, "grp").await?;
+    add_contact_to_chat(alice, chat_id, alice_bob_id).await?;
+    remove_contact_from_chat(alice, chat_id, alice_bob_id).await?;
+
+    let log = chat_id.get_protection_log(alice).await?;
+    let events: Vec<_> = log
+        .iter()
+        .map(|entry| (entry.event, entry.contact_id, entry.target_id))
+        .collect();
+    assert_eq!(
+        events,
+        vec![
+            (ProtectionLogEvent::ProtectionEnabled, ContactId::SELF, None),
+            (
+                ProtectionLogEvent::MemberAdded,
+                ContactId::SELF,
+                Some(alice_bob_id)
+            ),
+            (
+                ProtectionLogEvent::MemberRemoved,
+                ContactId::SELF,
+                Some(alice_bob_id)
+            ),
+        ]
+    );
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_custom_headers() -> Result<()> {
+    let mut tcm = TestContextManager::new();
+    let alice = &tcm.alice().await;
+    let bob = &tcm.bob().await;
+    let chat_id = alice.create_chat(bob).await.id;
+
+    // Not whitelisted.
+    assert!(chat_id
+        .set_custom_header(alice, "X-Ticket-Id", Some("123"))
+        .await
+        .is_err());
+    alice
+        .set_config(Config::AllowedCustomHeaders, Some("X-Ticket-Id, Subject"))
+        .await?;
+    assert!(chat_id
+        .set_custom_header(alice, "Subject", Some("123"))
+        .await
+        .is_err());
+    assert!(chat_id
+        .set_custom_header(alice, "X-Ticket-Id", Some("1\r\n2"))
+        .await
+        .is_err());
+    chat_id
+        .set_custom_header(alice, "X-Ticket-Id", Some("123"))
+        .await?;
+    assert_eq!(
+        chat_id.get_custom_headers(alice).await?,
+        vec![("X-Ticket-Id".to_string(), "123".to_string())]
+    );
+
+    let sent = alice.send_text(chat_id, "Hi!").await;
+    let parsed = alice.parse_msg(&sent).await;
+    assert!(parsed
+        .x_headers()
+        .any(|header| header == ("x-ticket-id", "123")));
+
+    let msg = bob.recv_msg(&sent).await;
+    assert!(msg.get_custom_headers().is_empty());
+    bob.set_config(Config::AllowedCustomHeaders, Some("x-ticket-id"))
+        .await?;
+    let sent = alice.send_text(chat_id, "Hi again!").await;
+    let msg = bob.recv_msg(&sent).await;
+    assert_eq!(
+        msg.get_custom_headers(),
+        vec![("x-ticket-id".to_string(), "123".to_string())]
+    );
+
+    chat_id
+        .set_custom_header(alice, "x-ticket-id", None)
+        .await?;
+    assert!(chat_id.get_custom_headers(alice).await?.is_empty());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_get_mentionable_members() -> Result<()> {
+    let mut tcm = TestContextManager::new();
+    let alice = &tcm.alice().await;
+    let bob = &tcm.bob().await;
+    let fiona = &tcm.fiona().await;
+
+    let chat_id = alice
+        .create_group_with_members(ProtectionStatus::Unprotected, "Group", &[bob, fiona])
+        .await;
+    let bob_id = Contact::create(alice, "Bob Smith", "bob@example.net").await?;
+    let fiona_id = Contact::create(alice, "Fiona", "fiona@example.net").await?;
+    let claire_id = Contact::create(alice, "Claire", "claire@example.org").await?;
+    add_contact_to_chat(alice, chat_id, claire_id).await?;
+
+    // Without messages, members are sorted by name.
+    let members = get_mentionable_members(alice, chat_id, "").await?;
+    let ids: Vec<ContactId> = members.iter().map(|m| m.contact_id).collect();
+    assert_eq!(ids, vec![bob_id, claire_id, fiona_id]);
+    assert_eq!(members[0].display_name, "Bob Smith");
+    assert_eq!(members[0].profile_image, None);
+
+    // Recent writers come first.
+    let sent = alice.send_text(chat_id, "Hello").await;
+    let fiona_chat_id = fiona.recv_msg(&sent).await.chat_id;
+    fiona_chat_id.accept(fiona).await?;
+    let sent = fiona.send_text(fiona_chat_id, "Hi").await;
+    alice.recv_msg(&sent).await;
+    let members = get_mentionable_members(alice, chat_id, "").await?;
+    let ids: Vec<ContactId> = members.iter().map(|m| m.contact_id).collect();
+    assert_eq!(ids, vec![fiona_id, bob_id, claire_id]);
+
+    // Prefix matches the beginning of the name, its words and the address.
+    let members = get_mentionable_members(alice, chat_id, "sm").await?;
+    assert_eq!(members.len(), 1);
+    assert_eq!(members[0].contact_id, bob_id);
+    let members = get_mentionable_members(alice, chat_id, "CLAIRE@").await?;
+    assert_eq!(members.len(), 1);
+    assert_eq!(members[0].contact_id, claire_id);
+    assert!(get_mentionable_members(alice, chat_id, "%")
+        .await?
+        .is_empty());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_moderated_group() -> Result<()> {
+    let mut tcm = TestContextManager::new();
+    let alice = &tcm.alice().await;
+    let bob = &tcm.bob().await;
+
+    let alice_chat_id = alice
+        .create_group_with_members(ProtectionStatus::Unprotected, "Group", &[bob])
+        .await;
+    assert!(is_group_admin(alice, alice_chat_id, ContactId::SELF).await?);
+    let sent = alice.send_text(alice_chat_id, "Hi").await;
+    let bob_chat_id = bob.recv_msg(&sent).await.chat_id;
+    bob_chat_id.accept(bob).await?;
+    assert!(!is_group_admin(bob, bob_chat_id, ContactId::SELF).await?);
+    // Admins are known from the creation of the group on.
+    let bob_alice_id = bob.add_or_lookup_contact_id(alice).await;
+    assert_eq!(
+        get_group_admins(bob, bob_chat_id).await?,
+        vec![bob_alice_id]
+    );
+
+    // Bob cannot make himself an admin.
+    set_group_admins(bob, bob_chat_id, &[ContactId::SELF]).await?;
+    let mut bob_chat = Chat::load_from_db(bob, bob_chat_id).await?;
+    bob_chat.param.set_int(Param::GroupModerated, 1);
+    bob_chat.update_param(bob).await?;
+    alice
+        .recv_msg(&bob.send_text(bob_chat_id, "I am the admin now").await)
+        .await;
+    let alice_bob_id = alice.add_or_lookup_contact_id(bob).await;
+    assert!(!is_group_admin(alice, alice_chat_id, alice_bob_id).await?);
+    assert!(!Chat::load_from_db(alice, alice_chat_id)
+        .await?
+        .is_moderated());
+    set_group_admins(bob, bob_chat_id, &[bob_alice_id]).await?;
+    bob_chat.param.remove(Param::GroupModerated);
+    bob_chat.update_param(bob).await?;
+
+    // Alice moderates the group, but Bob did not receive it yet and renames the group.
+    set_group_moderated(alice, alice_chat_id, true).await?;
+    let sent_moderated = alice.pop_sent_msg().await;
+    set_chat_name(bob, bob_chat_id, "Renamed").await?;
+    let sent_rename = bob.pop_sent_msg().await;
+
+    let msg = alice.recv_msg(&sent_rename).await;
+    assert!(msg.is_info());
+    assert!(msg.get_text().contains("only admins may change this group"));
+    assert_eq!(
+        Chat::load_from_db(alice, alice_chat_id).await?.get_name(),
+        "Group"
+    );
+
+    // Bob receives the moderation and cannot change the group anymore.
+    let msg = bob.recv_msg(&sent_moderated).await;
+    assert_eq!(msg.get_info_type(), SystemMessage::GroupAdminsChanged);
+    let bob_chat = Chat::load_from_db(bob, bob_chat_id).await?;
+    assert!(bob_chat.is_moderated());
+    assert_eq!(
+        get_group_admins(bob, bob_chat_id).await?,
+        vec![bob_alice_id]
+    );
+    assert!(set_chat_profile_image(bob, bob_chat_id, "").await.is_err());
+    assert!(set_chat_name(bob, bob_chat_id, "Renamed again")
+        .await
+        .is_err());
+    assert!(remove_contact_from_chat(bob, bob_chat_id, bob_alice_id)
+        .await
+        .is_err());
+
+    // Bob can still leave the group.
+    remove_contact_from_chat(bob, bob_chat_id, ContactId::SELF).await?;
+    alice.recv_msg(&bob.pop_sent_msg().await).await;
+    assert!(!is_contact_in_chat(alice, alice_chat_id, alice_bob_id).await?);
+
+    Ok(())
+}
+
+/// Tests that a member cannot become admin for members creating the group from their message.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_group_admins_only_from_creator() -> Result<()> {
+    let mut tcm = TestContextManager::new();
+    let alice = &tcm.alice().await;
+    let bob = &tcm.bob().await;
+    let fiona = &tcm.fiona().await;
+
+    let alice_chat_id = alice
+        .create_group_with_members(ProtectionStatus::Unprotected, "Group", &[bob, fiona])
+        .await;
+    let sent_hi = alice.send_text(alice_chat_id, "Hi").await;
+    let bob_chat_id = bob.recv_msg(&sent_hi).await.chat_id;
+    bob_chat_id.accept(bob).await?;
+
+    // Bob makes himself an admin locally and writes to Fiona before she receives the group.
+    set_group_admins(bob, bob_chat_id, &[ContactId::SELF]).await?;
+    let sent_claim = bob.send_text(bob_chat_id, "I am the admin").await;
+    let fiona_chat_id = fiona.recv_msg(&sent_claim).await.chat_id;
+    assert!(get_group_admins(fiona, fiona_chat_id).await?.is_empty());
+
+    // The message of the creator promoting the group sets the admins.
+    fiona.recv_msg(&sent_hi).await;
+    let fiona_alice_id = fiona.add_or_lookup_contact_id(alice).await;
+    assert_eq!(
+        get_group_admins(fiona, fiona_chat_id).await?,
+        vec![fiona_alice_id]
+    );
+    Ok(())
+}
+
+/// Tests that the first member setting an admin in a group without admins becomes the admin.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_legacy_group_first_admin() -> Result<()> {
+    let mut tcm = TestContextManager::new();
+    let alice = &tcm.alice().await;
+    let bob = &tcm.bob().await;
+
+    // Groups created by older versions have no admins.
+    let alice_chat_id = alice
+        .create_group_with_members(ProtectionStatus::Unprotected, "Group", &[bob])
+        .await;
+    set_group_admins(alice, alice_chat_id, &[]).await?;
+    let bob_chat_id = bob
+        .recv_msg(&alice.send_text(alice_chat_id, "Hi").await)
+        .await
+        .chat_id;
+    bob_chat_id.accept(bob).await?;
+    assert!(get_group_admins(bob, bob_chat_id).await?.is_empty());
+
+    // Ordinary messages do not make the sender an admin.
+    set_group_admins(bob, bob_chat_id, &[ContactId::SELF]).await?;
+    alice
+        .recv_msg(&bob.send_text(bob_chat_id, "I am the admin").await)
+        .await;
+    assert!(get_group_admins(alice, alice_chat_id).await?.is_empty());
+    set_group_admins(bob, bob_chat_id, &[]).await?;
+
+    // Moderating the group makes Bob the first admin for all members.
+    set_group_moderated(bob, bob_chat_id, true).await?;
+    let bob_alice_id = bob.add_or_lookup_contact_id(alice).await;
+    assert_eq!(
+        get_group_admins(bob, bob_chat_id).await?,
+        vec![ContactId::SELF]
+    );
+    let msg = alice.recv_msg(&bob.pop_sent_msg().await).await;
+    assert_eq!(msg.get_info_type(), SystemMessage::GroupAdminsChanged);
+    let alice_bob_id = alice.add_or_lookup_contact_id(bob).await;
+    assert_eq!(
+        get_group_admins(alice, alice_chat_id).await?,
+        vec![alice_bob_id]
+    );
+    assert!(Chat::load_from_db(alice, alice_chat_id)
+        .await?
+        .is_moderated());
+
+    // Now Alice cannot make herself an admin anymore.
+    assert!(set_group_admin(alice, alice_chat_id, ContactId::SELF, true)
+        .await
+        .is_err());
+    assert!(!is_group_admin(bob, bob_chat_id, bob_alice_id).await?);
+    Ok(())
+}
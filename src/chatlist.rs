@@ -140,7 +140,7 @@ pub async fn try_load(
                                SELECT id
                                  FROM msgs
                                 WHERE chat_id=c.id
-                                  AND (hidden=0 OR state=?1)
+                                  AND hidden_local=0 AND (hidden=0 OR state=?1)
                                   ORDER BY timestamp DESC, id DESC LIMIT 1)
                  WHERE c.id>9
                    AND c.blocked!=1
@@ -167,7 +167,7 @@ pub async fn try_load(
                                SELECT id
                                  FROM msgs
                                 WHERE chat_id=c.id
-                                  AND (hidden=0 OR state=?)
+                                  AND hidden_local=0 AND (hidden=0 OR state=?)
                                   ORDER BY timestamp DESC, id DESC LIMIT 1)
                  WHERE c.id>9
                    AND c.blocked!=1
@@ -203,12 +203,12 @@ pub async fn try_load(
                                SELECT id
                                  FROM msgs
                                 WHERE chat_id=c.id
-                                  AND (hidden=0 OR state=?1)
+                                  AND hidden_local=0 AND (hidden=0 OR state=?1)
                                   ORDER BY timestamp DESC, id DESC LIMIT 1)
                  WHERE c.id>9 AND c.id!=?2
                    AND c.blocked!=1
                    AND c.name LIKE ?3
-                   AND (NOT ?4 OR EXISTS (SELECT 1 FROM msgs m WHERE m.chat_id = c.id AND m.state == ?5 AND hidden=0))
+                   AND (NOT ?4 OR EXISTS (SELECT 1 FROM msgs m WHERE m.chat_id = c.id AND m.state == ?5 AND hidden=0 AND hidden_local=0))
                  GROUP BY c.id
                  ORDER BY IFNULL(m.timestamp,c.created_timestamp) DESC, m.id DESC;",
                     (MessageState::OutDraft, skip_id, str_like_cmd, only_unread, MessageState::InFresh),
@@ -256,7 +256,7 @@ pub async fn try_load(
                                    SELECT id
                                      FROM msgs
                                     WHERE chat_id=c.id
-                                      AND (hidden=0 OR state=?)
+                                      AND hidden_local=0 AND (hidden=0 OR state=?)
                                       ORDER BY timestamp DESC, id DESC LIMIT 1)
                      WHERE c.id>9 AND c.id!=?
                        AND c.blocked=0
@@ -283,7 +283,7 @@ pub async fn try_load(
                                    SELECT id
                                      FROM msgs
                                     WHERE chat_id=c.id
-                                      AND (hidden=0 OR state=?)
+                                      AND hidden_local=0 AND (hidden=0 OR state=?)
                                       ORDER BY timestamp DESC, id DESC LIMIT 1)
                      WHERE c.id>9 AND c.id!=?
                        AND (c.blocked=0 OR c.blocked=2)
@@ -317,7 +317,7 @@ pub(crate) async fn from_chat_ids(context: &Context, chat_ids: &[ChatId]) -> Res
                     "SELECT id
                    FROM msgs
                   WHERE chat_id=?1
-                    AND (hidden=0 OR state=?2)
+                    AND hidden_local=0 AND (hidden=0 OR state=?2)
                   ORDER BY timestamp DESC, id DESC LIMIT 1",
                     (chat_id, MessageState::OutDraft),
                 )
@@ -443,6 +443,110 @@ pub fn get_index_for_id(&self, id: ChatId) -> Option<usize> {
     pub fn iter(&self) -> impl Iterator<Item = &(ChatId, Option<MsgId>)> {
         self.ids.iter()
     }
+
+    /// Returns section metadata of the chatlist,
+    /// so that UIs do not need to check for special chat IDs
+    /// or load every chat to group the list.
+    pub async fn get_sections(&self, context: &Context) -> Result<ChatlistSections> {
+        let mut kinds = Vec::with_capacity(self.ids.len());
+        for (chat_id, _msg_id) in &self.ids {
+            let kind = if chat_id.is_archived_link() {
+                ChatlistItemKind::ArchiveLink
+            } else if chat_id.is_alldone_hint() {
+                ChatlistItemKind::AllDoneHint
+            } else {
+                let (visibility, blocked): (ChatVisibility, Blocked) = context
+                    .sql
+                    .query_row_optional(
+                        "SELECT archived, blocked FROM chats WHERE id=?",
+                        (chat_id,),
+                        |row| Ok((row.get(0)?, row.get(1)?)),
+                    )
+                    .await?
+                    .unwrap_or((ChatVisibility::Normal, Blocked::Not));
+                if visibility == ChatVisibility::Pinned {
+                    ChatlistItemKind::Pinned
+                } else if blocked == Blocked::Request {
+                    ChatlistItemKind::ContactRequest
+                } else if visibility == ChatVisibility::Archived {
+                    ChatlistItemKind::Archived
+                } else {
+                    ChatlistItemKind::Chat
+                }
+            };
+            kinds.push(kind);
+        }
+
+        let count = |kind| kinds.iter().filter(|k| **k == kind).count();
+        let pinned_count = count(ChatlistItemKind::Pinned);
+        let requests_count = count(ChatlistItemKind::ContactRequest);
+        let first_pinned_index = kinds.iter().position(|k| *k == ChatlistItemKind::Pinned);
+        let archive_link_index = kinds
+            .iter()
+            .position(|k| *k == ChatlistItemKind::ArchiveLink);
+        let archived_count = get_archived_cnt(context).await?;
+        let archived_fresh_count = DC_CHAT_ID_ARCHIVED_LINK.get_fresh_msg_cnt(context).await?;
+
+        Ok(ChatlistSections {
+            kinds,
+            pinned_count,
+            first_pinned_index,
+            requests_count,
+            archive_link_index,
+            archived_count,
+            archived_fresh_count,
+        })
+    }
+}
+
+/// Kind of a chatlist item, see [`Chatlist::get_sections`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChatlistItemKind {
+    /// Pinned chat.
+    Pinned,
+
+    /// Chat that is a contact request.
+    ContactRequest,
+
+    /// Archived chat, only contained in the list of archived chats.
+    Archived,
+
+    /// Any other chat.
+    Chat,
+
+    /// Link to the archived chats, [`DC_CHAT_ID_ARCHIVED_LINK`].
+    ArchiveLink,
+
+    /// Hint that all chats are archived, [`DC_CHAT_ID_ALLDONE_HINT`].
+    AllDoneHint,
+}
+
+/// Section metadata of a chatlist.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChatlistSections {
+    /// Kinds of the chatlist items, in the order of the chatlist.
+    pub kinds: Vec<ChatlistItemKind>,
+
+    /// Number of pinned chats.
+    pub pinned_count: usize,
+
+    /// Index of the first pinned chat.
+    ///
+    /// Pinned chats are always sorted to the top,
+    /// only the archive link may be placed before them.
+    pub first_pinned_index: Option<usize>,
+
+    /// Number of contact requests in the list.
+    pub requests_count: usize,
+
+    /// Index of the archive link if it is contained in the list.
+    pub archive_link_index: Option<usize>,
+
+    /// Number of archived chats, to be displayed in the archive link row.
+    pub archived_count: usize,
+
+    /// Number of fresh messages in archived chats, to be displayed in the archive link row.
+    pub archived_fresh_count: usize,
 }
 
 /// Returns the number of archived chats
@@ -469,7 +573,7 @@ pub async fn get_last_message_for_chat(
             "SELECT id
                 FROM msgs
                 WHERE chat_id=?2
-                AND (hidden=0 OR state=?1)
+                AND hidden_local=0 AND (hidden=0 OR state=?1)
                 ORDER BY timestamp DESC, id DESC LIMIT 1",
             (MessageState::OutDraft, chat_id),
         )
@@ -567,6 +671,61 @@ async fn test_try_load() {
         assert_eq!(chats.len(), 1);
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_get_sections() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let chat_id1 = create_group_chat(&t, ProtectionStatus::Unprotected, "a").await?;
+        let chat_id2 = create_group_chat(&t, ProtectionStatus::Unprotected, "b").await?;
+        let chat_id3 = create_group_chat(&t, ProtectionStatus::Unprotected, "c").await?;
+        chat_id2.set_visibility(&t, ChatVisibility::Pinned).await?;
+        chat_id3
+            .set_visibility(&t, ChatVisibility::Archived)
+            .await?;
+
+        let chats = Chatlist::try_load(&t, 0, None, None).await?;
+        let sections = chats.get_sections(&t).await?;
+        assert_eq!(
+            sections.kinds,
+            vec![
+                ChatlistItemKind::ArchiveLink,
+                ChatlistItemKind::Pinned,
+                ChatlistItemKind::Chat
+            ]
+        );
+        assert_eq!(chats.get_chat_id(1)?, chat_id2);
+        assert_eq!(chats.get_chat_id(2)?, chat_id1);
+        assert_eq!(sections.pinned_count, 1);
+        assert_eq!(sections.first_pinned_index, Some(1));
+        assert_eq!(sections.requests_count, 0);
+        assert_eq!(sections.archive_link_index, Some(0));
+        assert_eq!(sections.archived_count, 1);
+        assert_eq!(sections.archived_fresh_count, 0);
+
+        let chats = Chatlist::try_load(&t, DC_GCL_ARCHIVED_ONLY, None, None).await?;
+        let sections = chats.get_sections(&t).await?;
+        assert_eq!(sections.kinds, vec![ChatlistItemKind::Archived]);
+        assert_eq!(sections.archive_link_index, None);
+
+        // A message from a stranger is a contact request.
+        receive_imf(
+            &t,
+            b"From: Bob <bob@example.net>\n\
+              To: alice@example.org\n\
+              Message-ID: <request@example.net>\n\
+              Date: Sun, 22 Mar 2020 22:37:57 +0000\n\
+              \n\
+              Hi\n",
+            false,
+        )
+        .await?;
+        let chats = Chatlist::try_load(&t, DC_GCL_NO_SPECIALS, None, None).await?;
+        let sections = chats.get_sections(&t).await?;
+        assert_eq!(sections.requests_count, 1);
+        assert_eq!(sections.archive_link_index, None);
+        assert_eq!(sections.kinds.len(), 3);
+        Ok(())
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_sort_self_talk_up_on_forward() {
         let t = TestContext::new().await;
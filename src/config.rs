@@ -18,7 +18,7 @@
 use crate::events::EventType;
 use crate::log::LogExt;
 use crate::mimefactory::RECOMMENDED_FILE_SIZE;
-use crate::provider::{get_provider_by_id, Provider};
+use crate::provider::{get_provider_by_id, ChatmailCapabilities, Provider};
 use crate::sync::{self, Sync::*, SyncData};
 use crate::tools::get_abs_path;
 
@@ -158,6 +158,11 @@ pub enum Config {
     #[strum(props(default = "1"))]
     MdnsEnabled,
 
+    /// True if contacts whose avatars were not updated for a long time
+    /// should be asked to resend their profile data.
+    #[strum(props(default = "1"))]
+    RequestStaleProfiles,
+
     /// True if "Sent" folder should be watched for changes.
     #[strum(props(default = "0"))]
     SentboxWatch,
@@ -193,6 +198,13 @@ pub enum Config {
     #[strum(props(default = "1"))]
     FetchedExistingMsgs,
 
+    /// Maximum number of IMAP connections used to fetch existing messages
+    /// and to do background fetch from several folders in parallel.
+    ///
+    /// Set to "1" to fetch the folders sequentially over a single connection.
+    #[strum(props(default = "2"))]
+    ImapConnections,
+
     /// Type of the OpenPGP key to generate.
     #[strum(props(default = "0"))]
     KeyGenType,
@@ -312,6 +324,17 @@ pub enum Config {
     /// ID of the configured provider from the provider database.
     ConfiguredProvider,
 
+    /// JMAP session URL if the server advertises JMAP support.
+    ///
+    /// Detected during configuration.
+    ConfiguredJmapUrl,
+
+    /// Capabilities of the chatmail server, comma-separated.
+    ///
+    /// Detected during configuration, unset for non-chatmail servers.
+    /// Use [`Context::get_chatmail_capabilities`] to read it.
+    ConfiguredChatmailCapabilities,
+
     /// True if account is configured.
     Configured,
 
@@ -361,6 +384,12 @@ pub enum Config {
     /// Unset, when quota falls below minimal warning threshold again.
     QuotaExceeding,
 
+    /// Quota usage in percent at which a device message warns about a nearly full mailbox.
+    ///
+    /// Another warning is always added at 95%.
+    #[strum(props(default = "80"))]
+    QuotaWarnThreshold,
+
     /// address to webrtc instance to use for videochats
     WebrtcInstance,
 
@@ -370,6 +399,21 @@ pub enum Config {
     /// Timestamp of the last `CantDecryptOutgoingMsgs` notification.
     LastCantDecryptOutgoingMsgs,
 
+    /// Number of days without a backup after which a reminder device message is added.
+    /// 0=disable the reminder (default).
+    #[strum(props(default = "0"))]
+    BackupReminderDays,
+
+    /// Age of the key in years after which a warning device message is added.
+    /// 0=disable the warning.
+    #[strum(props(default = "0"))]
+    KeyAgeWarningYears,
+
+    /// Database size in megabytes after which a warning device message is added.
+    /// 0=disable the warning.
+    #[strum(props(default = "2000"))]
+    DbSizeWarningMb,
+
     /// To how many seconds to debounce scan_all_folders. Used mainly in tests, to disable debouncing completely.
     #[strum(props(default = "60"))]
     ScanAllFoldersDebounceSecs,
@@ -450,11 +494,137 @@ pub enum Config {
     #[strum(props(default = "1"))]
     WebxdcRealtimeEnabled,
 
+    /// Whether incoming reactions to own messages count towards the fresh message counters
+    /// until the chat is noticed.
+    /// Can be overridden per chat, see [`crate::chat::ChatId::set_reactions_count_as_fresh`].
+    ReactionsCountAsFresh,
+
+    /// Whether text recognition in new image attachments is requested from the UI,
+    /// see [`crate::ocr`].
+    RequestOcr,
+
+    /// Do-not-disturb schedule as comma-separated time ranges such as `mon 22:00-07:00`,
+    /// see [`crate::dnd`].
+    DndSchedule,
+
+    /// Number of bytes used by a webxdc instance
+    /// after which a warning is emitted.
+    #[strum(props(default = "52428800"))]
+    WebxdcUsageWarnBytes,
+
+    /// Number of status updates of a webxdc instance
+    /// after which a warning is emitted.
+    #[strum(props(default = "10000"))]
+    WebxdcUsageWarnUpdates,
+
+    /// Maximum size of received webxdc apps in bytes, 0 for no limit.
+    ///
+    /// Larger apps are shown as files with [`crate::webxdc::WebxdcUnsupported::Oversized`].
+    /// Unencrypted messages with larger apps are not downloaded automatically.
+    /// Encrypted messages are downloaded as usual, as their attachments are not known
+    /// before download, and their apps are checked after decryption.
+    #[strum(props(default = "0"))]
+    WebxdcMaxSize,
+
+    /// Comma-separated list of `X-` headers
+    /// which may be set for chats using [`crate::chat::ChatId::set_custom_header`]
+    /// and are stored in received messages.
+    AllowedCustomHeaders,
+
+    /// Allow completing Secure-Join handshakes over a direct peer channel
+    /// when both devices are on the same local network.
+    ///
+    /// Handshake messages are still sent by email as usual.
+    #[strum(props(default = "0"))]
+    SecurejoinLocalNetwork,
+
+    /// Make threading headers and subjects of outgoing messages
+    /// friendly to classic MUAs such as Thunderbird.
+    ///
+    /// If enabled, `References:` keeps the root of the thread
+    /// and ends with the message replied to,
+    /// and only a single reply prefix is used in the subject.
+    #[strum(props(default = "0"))]
+    MuaThreadingCompat,
+
+    /// Delay in seconds before the first retry after a failed SMTP send.
+    #[strum(props(default = "30"))]
+    SmtpRetryInitialDelay,
+
+    /// Maximum delay in seconds between SMTP retries.
+    #[strum(props(default = "86400"))]
+    SmtpRetryMaxDelay,
+
+    /// Number of retries after which sending a message is given up
+    /// and the message is marked as failed.
+    #[strum(props(default = "6"))]
+    SmtpMaxRetries,
+
+    /// Delay in seconds before reconnecting after a failed IMAP connection attempt.
+    #[strum(props(default = "2"))]
+    ImapRetryInitialDelay,
+
+    /// Maximum delay in seconds between IMAP connection attempts.
+    #[strum(props(default = "80"))]
+    ImapRetryMaxDelay,
+
+    /// Number of days unreferenced blob files are kept in quarantine
+    /// before housekeeping deletes them.
+    ///
+    /// 0 means that unreferenced files are deleted immediately.
+    #[strum(props(default = "7"))]
+    BlobQuarantineDays,
+
+    /// Number of group members above which adding members
+    /// adds a warning about the traffic of large groups to the chat.
+    ///
+    /// 0 means no warning.
+    #[strum(props(default = "200"))]
+    GroupMemberSoftLimit,
+
+    /// Maximum number of group members.
+    /// Adding members to a group of this size fails.
+    ///
+    /// The limit does not apply to members added by other group members.
+    /// 0 means no limit.
+    #[strum(props(default = "1000"))]
+    GroupMemberHardLimit,
+
     /// Last device token stored on the chatmail server.
     ///
     /// If it has not changed, we do not store
     /// the device token again.
     DeviceToken,
+
+    /// URL of a self-hosted UnifiedPush gateway.
+    ///
+    /// UnifiedPush endpoints and their encryption keys are registered at `<url>/register`.
+    /// If unset, UnifiedPush registrations are not sent anywhere.
+    UnifiedPushGateway,
+
+    /// Timezone used to place day markers and evaluate [`Config::DndSchedule`],
+    /// as an IANA timezone name such as `Europe/Berlin`.
+    ///
+    /// Daylight saving time changes of the timezone are taken into account.
+    /// Fixed UTC offsets such as `+02:00` are accepted as well.
+    /// If unset, the system timezone is used,
+    /// taking daylight saving time changes into account.
+    Timezone,
+
+    /// DKIM selector used to sign outgoing messages.
+    ///
+    /// The public key must be published as a TXT record at `<selector>._domainkey.<domain>`.
+    /// Messages are only signed if [`Config::DkimPrivateKey`] is set as well.
+    DkimSelector,
+
+    /// PEM-encoded RSA private key used to sign outgoing messages with DKIM,
+    /// for self-hosted servers that do not sign messages themselves.
+    DkimPrivateKey,
+
+    /// Whether to display internationalized domains of addresses in their ASCII (punycode) form.
+    ///
+    /// Off by default, then domains are displayed in Unicode.
+    DisplayPunycode,
 }
 
 impl Config {
@@ -475,7 +645,8 @@ pub(crate) fn is_synced(&self) -> bool {
                 | Self::MvboxMove
                 | Self::ShowEmails
                 | Self::Selfavatar
-                | Self::Selfstatus,
+                | Self::Selfstatus
+                | Self::DndSchedule,
         )
     }
 
@@ -607,12 +778,16 @@ pub(crate) async fn should_watch_mvbox(&self) -> Result<bool> {
     }
 
     /// Returns true if sentbox ("Sent" folder) should be watched.
+    ///
+    /// Chatmail servers do not put copies of sent messages into the "Sent" folder,
+    /// they arrive in the Inbox instead, so the sentbox is never watched there.
     pub(crate) async fn should_watch_sentbox(&self) -> Result<bool> {
         Ok(self.get_config_bool(Config::SentboxWatch).await?
             && self
                 .get_config(Config::ConfiguredSentboxFolder)
                 .await?
-                .is_some())
+                .is_some()
+            && self.get_chatmail_capabilities().await?.is_none())
     }
 
     /// Returns true if sync messages should be sent.
@@ -669,6 +844,17 @@ pub async fn get_configured_provider(&self) -> Result<Option<&'static Provider>>
         Ok(None)
     }
 
+    /// Gets the capabilities of the chatmail server detected during configuration.
+    ///
+    /// Returns `None` if the account is not on a chatmail server
+    /// or was configured before capabilities were detected.
+    pub async fn get_chatmail_capabilities(&self) -> Result<Option<ChatmailCapabilities>> {
+        Ok(self
+            .get_config(Config::ConfiguredChatmailCapabilities)
+            .await?
+            .map(|value| ChatmailCapabilities::from_config_value(&value)))
+    }
+
     /// Gets configured "delete_device_after" value.
     ///
     /// `None` means never delete the message, `Some(x)` means delete
@@ -715,12 +901,40 @@ fn check_config(key: Config, value: Option<&str>) -> Result<()> {
             | Config::NotifyAboutWrongPw
             | Config::SyncMsgs
             | Config::SignUnencrypted
+            | Config::ReactionsCountAsFresh
+            | Config::DisplayPunycode
             | Config::DisableIdle => {
                 ensure!(
                     matches!(value, None | Some("0") | Some("1")),
                     "Boolean value must be either 0 or 1"
                 );
             }
+            Config::Timezone => {
+                if let Some(value) = value {
+                    crate::chat::DayCalendar::parse(value)?;
+                }
+            }
+            Config::DndSchedule => {
+                if let Some(value) = value {
+                    crate::dnd::parse_schedule(value)?;
+                }
+            }
+            Config::DkimSelector => {
+                if let Some(value) = value {
+                    ensure!(
+                        !value.is_empty()
+                            && value
+                                .chars()
+                                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.'),
+                        "DKIM selector must consist of letters, digits, dots and hyphens"
+                    );
+                }
+            }
+            Config::DkimPrivateKey => {
+                if let Some(value) = value {
+                    crate::dkim::parse_private_key(value)?;
+                }
+            }
             _ => (),
         }
         Ok(())
@@ -1349,4 +1563,30 @@ async fn test_event_config_synced() -> Result<()> {
 
         Ok(())
     }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_chatmail_capabilities() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        t.set_config(Config::ConfiguredSentboxFolder, Some("Sent"))
+            .await?;
+        t.set_config_bool(Config::SentboxWatch, true).await?;
+        assert_eq!(t.get_chatmail_capabilities().await?, None);
+        assert!(t.should_watch_sentbox().await?);
+
+        t.set_config(
+            Config::ConfiguredChatmailCapabilities,
+            Some("push,instant_account"),
+        )
+        .await?;
+        assert_eq!(
+            t.get_chatmail_capabilities().await?,
+            Some(ChatmailCapabilities {
+                push: true,
+                instant_account: true,
+                strict_tls: false,
+            })
+        );
+        assert!(!t.should_watch_sentbox().await?);
+        Ok(())
+    }
 }
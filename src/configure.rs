@@ -13,6 +13,9 @@
 mod auto_outlook;
 pub(crate) mod server_params;
 
+use std::fmt;
+use std::time::Duration;
+
 use anyhow::{bail, ensure, format_err, Context as _, Result};
 use auto_mozilla::moz_autoconfigure;
 use auto_outlook::outlk_autodiscover;
@@ -20,12 +23,14 @@
 use futures::FutureExt;
 use futures_lite::FutureExt as _;
 use percent_encoding::utf8_percent_encode;
+use serde::{Deserialize, Serialize};
 use server_params::{expand_param_vector, ServerParams};
 use tokio::task;
 
 use crate::config::{self, Config};
 use crate::constants::NON_ALPHANUMERIC_WITHOUT_DOT;
 use crate::context::Context;
+use crate::imap::session::Session;
 use crate::imap::Imap;
 use crate::log::LogExt;
 use crate::login_param::{
@@ -33,8 +38,10 @@
     ConnectionCandidate, EnteredCertificateChecks, EnteredLoginParam,
 };
 use crate::message::Message;
+use crate::net::{http, jmap};
 use crate::oauth2::get_oauth2_addr;
-use crate::provider::{Protocol, Socket, UsernamePattern};
+use crate::provider::{ChatmailCapabilities, Protocol, Socket, UsernamePattern};
+use crate::qr::{check_qr, set_config_from_qr, Qr};
 use crate::smtp::Smtp;
 use crate::sync::Sync::*;
 use crate::tools::time;
@@ -58,6 +65,30 @@ macro_rules! progress {
     };
 }
 
+/// Single step of a configuration attempt,
+/// see [`Context::get_last_configure_trace`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigureTraceEntry {
+    /// Timestamp of the step.
+    pub timestamp: i64,
+
+    /// Description of the step, e.g. the autoconfig URL
+    /// or the server and port that was tried.
+    pub step: String,
+
+    /// Error if the step failed, `None` if it succeeded.
+    pub error: Option<String>,
+}
+
+/// Trace of the current or the last configuration attempt.
+#[derive(Debug, Default)]
+pub(crate) struct ConfigureTrace {
+    /// True while configuration is running.
+    recording: bool,
+
+    entries: Vec<ConfigureTraceEntry>,
+}
+
 impl Context {
     /// Checks if the context is already configured.
     pub async fn is_configured(&self) -> Result<bool> {
@@ -66,6 +97,11 @@ pub async fn is_configured(&self) -> Result<bool> {
 
     /// Configures this account with the currently set parameters.
     pub async fn configure(&self) -> Result<()> {
+        self.configure_ex(None).await
+    }
+
+    /// Configures the account, setting the parameters from `qr` first if given.
+    async fn configure_ex(&self, qr: Option<&str>) -> Result<()> {
         ensure!(
             !self.scheduler.is_running().await,
             "cannot configure, already running"
@@ -76,14 +112,23 @@ pub async fn configure(&self) -> Result<()> {
         );
         let cancel_channel = self.alloc_ongoing().await?;
 
+        *self.configure_trace.lock() = ConfigureTrace {
+            recording: true,
+            entries: Vec::new(),
+        };
         let res = self
-            .inner_configure()
+            .inner_configure(qr)
             .race(cancel_channel.recv().map(|_| Err(format_err!("Cancelled"))))
             .await;
+        self.configure_trace.lock().recording = false;
 
         self.free_ongoing().await;
 
         if let Err(err) = res.as_ref() {
+            self.emit_event(EventType::ConfigureFailed {
+                error: format!("{err:#}"),
+                trace: self.get_last_configure_trace(),
+            });
             progress!(
                 self,
                 0,
@@ -104,9 +149,59 @@ pub async fn configure(&self) -> Result<()> {
         res
     }
 
-    async fn inner_configure(&self) -> Result<()> {
+    /// Configures the account from a `DCACCOUNT:` or `DCLOGIN:` QR code or URL
+    /// in a single call.
+    ///
+    /// For `DCACCOUNT:` codes a new account is created on the chatmail server first.
+    /// The server account and its credentials are kept
+    /// even if configuring fails afterwards, so configuring can be retried with
+    /// [`Context::configure`] without creating another account on the server.
+    ///
+    /// Codes that do not contain an account are rejected without any events.
+    /// Otherwise progress is reported with [`EventType::ConfigureProgress`] events
+    /// and the whole process can be cancelled with [`Context::stop_ongoing`]
+    /// as for [`Context::configure`].
+    pub async fn configure_from_qr(&self, qr: &str) -> Result<()> {
+        match check_qr(self, qr).await? {
+            Qr::Account { .. } | Qr::Login { .. } => {}
+            _ => bail!("QR code does not contain an account"),
+        }
+        self.configure_ex(Some(qr)).await
+    }
+
+    /// Returns the steps of the current or the last configuration attempt,
+    /// such as the tried autoconfig URLs, DNS lookups and servers, with their errors.
+    ///
+    /// The trace is kept in memory only and is empty if the account
+    /// was not configured since the context was opened.
+    pub fn get_last_configure_trace(&self) -> Vec<ConfigureTraceEntry> {
+        self.configure_trace.lock().entries.clone()
+    }
+
+    /// Adds a step to the configuration trace if configuration is running.
+    pub(crate) fn trace_configure_step<T, E: fmt::Display>(
+        &self,
+        step: String,
+        res: &Result<T, E>,
+    ) {
+        let mut trace = self.configure_trace.lock();
+        if trace.recording {
+            trace.entries.push(ConfigureTraceEntry {
+                timestamp: time(),
+                step,
+                error: res.as_ref().err().map(|err| format!("{err:#}")),
+            });
+        }
+    }
+
+    async fn inner_configure(&self, qr: Option<&str>) -> Result<()> {
         info!(self, "Configure ...");
 
+        if let Some(qr) = qr {
+            progress!(self, 1);
+            set_config_from_qr(self, qr).await?;
+        }
+
         let param = EnteredLoginParam::load(self).await?;
         let old_addr = self.get_config(Config::ConfiguredAddr).await?;
         let configured_param = configure(self, &param).await?;
@@ -230,6 +325,10 @@ async fn get_configured_param(
         );
 
         provider = provider::get_provider_info(ctx, &param_domain, proxy_enabled).await;
+        ctx.trace_configure_step(
+            format!("Provider database lookup for {param_domain}"),
+            &provider.context("Provider not found"),
+        );
         if let Some(provider) = provider {
             if provider.server.is_empty() {
                 info!(ctx, "Offline autoconfig found, but no servers defined.");
@@ -360,13 +459,51 @@ async fn get_configured_param(
     Ok(configured_login_param)
 }
 
+/// Detects capabilities of a chatmail server.
+///
+/// `strict_tls` tells whether the IMAP connection has been established
+/// with strict TLS certificate checks.
+async fn detect_chatmail_capabilities(
+    ctx: &Context,
+    imap_session: &Session,
+    addr: &str,
+    strict_tls: bool,
+) -> ChatmailCapabilities {
+    // Chatmail servers creating accounts on first login
+    // redirect GET requests of the account creation URL to a `dcaccount:` URL,
+    // only POST requests create an account.
+    let probe = match EmailAddress::new(addr) {
+        Ok(email) => {
+            let url = format!("https://{}/new", email.domain);
+            http::get_redirect_location(ctx, &url).await
+        }
+        Err(err) => Err(err),
+    };
+    let instant_account = match &probe {
+        Ok(location) => location
+            .as_deref()
+            .is_some_and(|location| location.starts_with("dcaccount:")),
+        Err(err) => {
+            info!(ctx, "Cannot check for instant account creation: {err:#}.");
+            false
+        }
+    };
+    ChatmailCapabilities {
+        push: imap_session.can_metadata() && imap_session.can_push(),
+        instant_account,
+        // Either the IMAP connection or the HTTPS request
+        // has verified the server certificate.
+        strict_tls: strict_tls || probe.is_ok(),
+    }
+}
+
 async fn configure(ctx: &Context, param: &EnteredLoginParam) -> Result<ConfiguredLoginParam> {
     progress!(ctx, 1);
 
     let ctx2 = ctx.clone();
     let update_device_chats_handle = task::spawn(async move { ctx2.update_device_chats().await });
 
-    let configured_param = get_configured_param(ctx, param).await?;
+    let mut configured_param = get_configured_param(ctx, param).await?;
     let strict_tls = configured_param.strict_tls();
 
     progress!(ctx, 550);
@@ -445,6 +582,34 @@ async fn configure(ctx: &Context, param: &EnteredLoginParam) -> Result<Configure
         ctx.set_config(Config::E2eeEnabled, Some("1")).await?;
     }
 
+    let chatmail_capabilities = if is_chatmail {
+        Some(
+            detect_chatmail_capabilities(ctx, &imap_session, &configured_param.addr, strict_tls)
+                .await,
+        )
+    } else {
+        None
+    };
+    if let Some(capabilities) = chatmail_capabilities {
+        info!(
+            ctx,
+            "Detected chatmail server capabilities: {capabilities:?}."
+        );
+        if capabilities.strict_tls
+            && configured_param.certificate_checks == ConfiguredCertificateChecks::Automatic
+        {
+            // Do not fall back to relaxed checks later, e.g. when a proxy is enabled.
+            configured_param.certificate_checks = ConfiguredCertificateChecks::Strict;
+        }
+    }
+    ctx.set_config_internal(
+        Config::ConfiguredChatmailCapabilities,
+        chatmail_capabilities
+            .map(|capabilities| capabilities.to_config_value())
+            .as_deref(),
+    )
+    .await?;
+
     let create_mvbox = !is_chatmail;
     imap.configure_folders(ctx, &mut imap_session, create_mvbox)
         .await?;
@@ -471,6 +636,23 @@ async fn configure(ctx: &Context, param: &EnteredLoginParam) -> Result<Configure
     ctx.set_config_internal(Config::ConfiguredTimestamp, Some(&time().to_string()))
         .await?;
 
+    // Fetch and submit over JMAP if the server supports it.
+    let jmap_url = match tokio::time::timeout(
+        Duration::from_secs(10),
+        jmap::discover(ctx, &configured_param),
+    )
+    .await
+    {
+        Ok(Ok(jmap_url)) => jmap_url,
+        Ok(Err(err)) => {
+            warn!(ctx, "JMAP discovery failed: {err:#}.");
+            None
+        }
+        Err(_) => None,
+    };
+    ctx.set_config_internal(Config::ConfiguredJmapUrl, jmap_url.as_deref())
+        .await?;
+
     progress!(ctx, 920);
 
     e2ee::ensure_secret_key_exists(ctx).await?;
@@ -508,66 +690,53 @@ async fn get_autoconfig(
     let param_addr_urlencoded =
         utf8_percent_encode(&param.addr, NON_ALPHANUMERIC_WITHOUT_DOT).to_string();
 
-    if let Ok(res) = moz_autoconfigure(
-        ctx,
-        &format!(
-            "https://autoconfig.{param_domain}/mail/config-v1.1.xml?emailaddress={param_addr_urlencoded}"
-        ),
-        &param.addr,
-    )
-    .await
-    {
+    let url = format!(
+        "https://autoconfig.{param_domain}/mail/config-v1.1.xml?emailaddress={param_addr_urlencoded}"
+    );
+    let res = moz_autoconfigure(ctx, &url, &param.addr).await;
+    ctx.trace_configure_step(format!("Autoconfig {url}"), &res);
+    if let Ok(res) = res {
         return Some(res);
     }
     progress!(ctx, 300);
 
-    if let Ok(res) = moz_autoconfigure(
-        ctx,
-        // the doc does not mention `emailaddress=`, however, Thunderbird adds it, see <https://releases.mozilla.org/pub/thunderbird/>,  which makes some sense
-        &format!(
-            "https://{}/.well-known/autoconfig/mail/config-v1.1.xml?emailaddress={}",
-            &param_domain, &param_addr_urlencoded
-        ),
-        &param.addr,
-    )
-    .await
-    {
+    // the doc does not mention `emailaddress=`, however, Thunderbird adds it, see <https://releases.mozilla.org/pub/thunderbird/>,  which makes some sense
+    let url = format!(
+        "https://{}/.well-known/autoconfig/mail/config-v1.1.xml?emailaddress={}",
+        &param_domain, &param_addr_urlencoded
+    );
+    let res = moz_autoconfigure(ctx, &url, &param.addr).await;
+    ctx.trace_configure_step(format!("Autoconfig {url}"), &res);
+    if let Ok(res) = res {
         return Some(res);
     }
     progress!(ctx, 310);
 
     // Outlook uses always SSL but different domains (this comment describes the next two steps)
-    if let Ok(res) = outlk_autodiscover(
-        ctx,
-        format!("https://{}/autodiscover/autodiscover.xml", &param_domain),
-    )
-    .await
-    {
+    let url = format!("https://{}/autodiscover/autodiscover.xml", &param_domain);
+    let res = outlk_autodiscover(ctx, url.clone()).await;
+    ctx.trace_configure_step(format!("Autodiscover {url}"), &res);
+    if let Ok(res) = res {
         return Some(res);
     }
     progress!(ctx, 320);
 
-    if let Ok(res) = outlk_autodiscover(
-        ctx,
-        format!(
-            "https://autodiscover.{}/autodiscover/autodiscover.xml",
-            &param_domain
-        ),
-    )
-    .await
-    {
+    let url = format!(
+        "https://autodiscover.{}/autodiscover/autodiscover.xml",
+        &param_domain
+    );
+    let res = outlk_autodiscover(ctx, url.clone()).await;
+    ctx.trace_configure_step(format!("Autodiscover {url}"), &res);
+    if let Ok(res) = res {
         return Some(res);
     }
     progress!(ctx, 330);
 
     // always SSL for Thunderbird's database
-    if let Ok(res) = moz_autoconfigure(
-        ctx,
-        &format!("https://autoconfig.thunderbird.net/v1.1/{}", &param_domain),
-        &param.addr,
-    )
-    .await
-    {
+    let url = format!("https://autoconfig.thunderbird.net/v1.1/{}", &param_domain);
+    let res = moz_autoconfigure(ctx, &url, &param.addr).await;
+    ctx.trace_configure_step(format!("Autoconfig {url}"), &res);
+    if let Ok(res) = res {
         return Some(res);
     }
 
@@ -625,6 +794,60 @@ async fn test_no_panic_on_bad_credentials() {
         assert!(t.configure().await.is_err());
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_configure_trace() -> Result<()> {
+        let t = TestContext::new().await;
+        assert!(t.get_last_configure_trace().is_empty());
+
+        // Steps are only recorded while configuring.
+        t.trace_configure_step("Not configuring".to_string(), &Ok::<_, Error>(()));
+        assert!(t.get_last_configure_trace().is_empty());
+
+        t.set_config(Config::Addr, Some("probably@unexistant.addr"))
+            .await?;
+        t.set_config(Config::MailPw, Some("123456")).await?;
+        assert!(t.configure().await.is_err());
+
+        let trace = t.get_last_configure_trace();
+        let provider_step = trace
+            .iter()
+            .find(|entry| entry.step == "Provider database lookup for unexistant.addr")
+            .unwrap();
+        assert_eq!(provider_step.error.as_deref(), Some("Provider not found"));
+
+        let EventType::ConfigureFailed {
+            trace: event_trace, ..
+        } = t
+            .evtracker
+            .get_matching(|e| matches!(e, EventType::ConfigureFailed { .. }))
+            .await
+        else {
+            unreachable!();
+        };
+        assert_eq!(event_trace, trace);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_configure_from_qr_rejects_other_qr() -> Result<()> {
+        let t = TestContext::new().await;
+        assert!(t
+            .configure_from_qr("https://example.org/not-an-account")
+            .await
+            .is_err());
+        // The code is rejected before configuration starts.
+        assert!(t
+            .evtracker
+            .get_matching_opt(&t, |e| matches!(
+                e,
+                EventType::ConfigureProgress { .. } | EventType::ConfigureFailed { .. }
+            ))
+            .await
+            .is_none());
+        assert!(!t.is_configured().await?);
+        Ok(())
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_get_configured_param() -> Result<()> {
         let t = &TestContext::new().await;
@@ -111,6 +111,16 @@ pub enum VideochatType {
 // unchanged user avatars are resent to the recipients every some days
 pub(crate) const DC_RESEND_USER_AVATAR_DAYS: i64 = 14;
 
+// in groups with at least this number of members, the avatar is not resent periodically,
+// but only when a member asks for it after seeing an unknown avatar hash
+pub(crate) const DC_LAZY_AVATAR_MIN_MEMBERS: usize = 20;
+
+// avatars of contacts are considered stale if they were not updated for a given number of days
+pub(crate) const DC_STALE_USER_AVATAR_DAYS: i64 = 90;
+
+// contacts with stale avatars are asked to resend their profile data at most every some days
+pub(crate) const DC_REQUEST_PROFILE_DAYS: i64 = 30;
+
 // warn about an outdated app after a given number of days.
 // as we use the "provider-db generation date" as reference (that might not be updated very often)
 // and as not all system get speedy updates,
@@ -15,17 +15,24 @@
     ContactAddress, VcardContact,
 };
 use deltachat_derive::{FromSql, ToSql};
+use num_traits::FromPrimitive;
+use pgp::types::PublicKeyTrait;
 use rusqlite::OptionalExtension;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use tokio::task;
 use tokio::time::{timeout, Duration};
 
-use crate::aheader::{Aheader, EncryptPreference};
+use crate::aheader::Aheader;
+pub use crate::aheader::EncryptPreference;
 use crate::blob::BlobObject;
 use crate::chat::{ChatId, ChatIdBlocked, ProtectionStatus};
 use crate::color::str_to_color;
 use crate::config::Config;
-use crate::constants::{Blocked, Chattype, DC_GCL_ADD_SELF, DC_GCL_VERIFIED_ONLY};
+use crate::constants::{
+    Blocked, Chattype, DC_GCL_ADD_SELF, DC_GCL_VERIFIED_ONLY, DC_STALE_USER_AVATAR_DAYS,
+};
+use crate::contact_label::{self, LabelId};
 use crate::context::Context;
 use crate::events::EventType;
 use crate::key::{load_self_public_key, DcKey, SignedPublicKey};
@@ -609,6 +616,14 @@ pub fn is_blocked(&self) -> bool {
         self.blocked
     }
 
+    /// Returns the encryption preference set by the user for this contact.
+    pub fn get_encryption_override(&self) -> EncryptionOverride {
+        self.param
+            .get_int(Param::EncryptionOverride)
+            .and_then(EncryptionOverride::from_i32)
+            .unwrap_or_default()
+    }
+
     /// Returns last seen timestamp.
     pub fn last_seen(&self) -> i64 {
         self.last_seen
@@ -1146,6 +1161,24 @@ pub async fn get_all(
         Ok(ret)
     }
 
+    /// Returns known and unblocked contacts having the label.
+    ///
+    /// `listflags` and `query` filter the list as in [`Contact::get_all`].
+    pub async fn get_all_with_label(
+        context: &Context,
+        label_id: LabelId,
+        listflags: u32,
+        query: Option<&str>,
+    ) -> Result<Vec<ContactId>> {
+        let labeled: HashSet<ContactId> = contact_label::get_label_contacts(context, label_id)
+            .await?
+            .into_iter()
+            .collect();
+        let mut ret = Contact::get_all(context, listflags, query).await?;
+        ret.retain(|contact_id| labeled.contains(contact_id));
+        Ok(ret)
+    }
+
     /// Adds blocked mailinglists as contacts
     /// to allow unblocking them as if they are contacts
     /// (this way, only one unblock-ffi is needed and only one set of ui-functions,
@@ -1310,6 +1343,10 @@ pub async fn delete(context: &Context, contact_id: ContactId) -> Result<()> {
                         (Origin::Hidden, contact_id),
                     )?;
                 }
+                transaction.execute(
+                    "DELETE FROM contact_labels_contacts WHERE contact_id=?",
+                    (contact_id,),
+                )?;
                 Ok(())
             })
             .await?;
@@ -1352,6 +1389,18 @@ pub fn get_addr(&self) -> &str {
         &self.addr
     }
 
+    /// Get email address for display.
+    ///
+    /// Internationalized domains are stored in ASCII (punycode) form
+    /// and converted to Unicode here unless [`Config::DisplayPunycode`] is set.
+    pub async fn get_display_addr(&self, context: &Context) -> Result<String> {
+        if context.get_config_bool(Config::DisplayPunycode).await? {
+            Ok(self.addr.clone())
+        } else {
+            Ok(contact_tools::addr_with_unicode_domain(&self.addr))
+        }
+    }
+
     /// Get name authorized by the contact.
     pub fn get_authname(&self) -> &str {
         &self.authname
@@ -1432,6 +1481,21 @@ pub async fn get_profile_image(&self, context: &Context) -> Result<Option<PathBu
         Ok(None)
     }
 
+    /// Returns true if the contact's profile image was not updated for a long time.
+    ///
+    /// Delta Chat resends the profile image every few days with the sent messages,
+    /// so if no update was received for a long time, the image is likely outdated.
+    /// Returns false if the contact never sent a profile image.
+    pub fn is_profile_image_stale(&self) -> bool {
+        self.id != ContactId::SELF
+            && self
+                .param
+                .get_i64(Param::AvatarTimestamp)
+                .is_some_and(|timestamp| {
+                    timestamp < time() - DC_STALE_USER_AVATAR_DAYS * 24 * 60 * 60
+                })
+    }
+
     /// Get a color for the contact.
     /// The color is calculated from the contact's email address
     /// and can be used for an fallback avatar with white initials
@@ -1593,6 +1657,168 @@ pub async fn real_exists_by_id(context: &Context, contact_id: ContactId) -> Resu
     }
 }
 
+/// Origin of the key used to encrypt messages to a contact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyOrigin {
+    /// The key was received from the contact in the `Autocrypt` header.
+    Autocrypt,
+
+    /// The key was gossiped by another member of a group in the `Autocrypt-Gossip` header.
+    Gossip,
+}
+
+/// Structured information about the key of a contact.
+///
+/// Unlike [`Contact::get_encrinfo`], which returns a formatted text,
+/// this allows UIs to build their own encryption detail screens.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyInfo {
+    /// Hex-encoded fingerprint of the key without spaces.
+    pub fingerprint: String,
+
+    /// Timestamp of the key creation.
+    pub created_timestamp: i64,
+
+    /// Algorithm of the primary key, e.g. `EdDSA` or `RSA`.
+    pub algorithm: String,
+
+    /// Algorithm of the encryption subkey, if the key has one.
+    pub encryption_algorithm: Option<String>,
+
+    /// Where the key was received from.
+    pub origin: KeyOrigin,
+
+    /// Timestamp of the latest reception of the key from its origin.
+    pub origin_timestamp: i64,
+
+    /// Encryption preference announced by the contact.
+    pub prefer_encrypt: EncryptPreference,
+
+    /// True if the key is verified.
+    pub verified: bool,
+
+    /// Address of the contact that introduced the verified key, if known.
+    pub verifier_addr: Option<String>,
+}
+
+/// Returns structured information about the key of a contact.
+///
+/// Returns `None` if no key of the contact is known.
+pub async fn get_key_info(context: &Context, contact_id: ContactId) -> Result<Option<KeyInfo>> {
+    ensure!(
+        !contact_id.is_special(),
+        "Can not provide key info for special contact"
+    );
+
+    let contact = Contact::get_by_id(context, contact_id).await?;
+    let Some(peerstate) = Peerstate::from_addr(context, &contact.addr).await? else {
+        return Ok(None);
+    };
+    let (key, origin, origin_timestamp) = if let Some(key) = &peerstate.public_key {
+        (key, KeyOrigin::Autocrypt, peerstate.last_seen_autocrypt)
+    } else if let Some(key) = &peerstate.gossip_key {
+        (key, KeyOrigin::Gossip, peerstate.gossip_timestamp)
+    } else {
+        return Ok(None);
+    };
+
+    let fingerprint = key.dc_fingerprint();
+    let verified = peerstate.verified_key_fingerprint.as_ref() == Some(&fingerprint);
+    let encryption_algorithm = key
+        .public_subkeys
+        .iter()
+        .find(|subkey| subkey.is_encryption_key())
+        .map(|subkey| format!("{:?}", subkey.algorithm()));
+
+    Ok(Some(KeyInfo {
+        fingerprint: fingerprint.hex(),
+        created_timestamp: key.created_at().timestamp(),
+        algorithm: format!("{:?}", key.algorithm()),
+        encryption_algorithm,
+        origin,
+        origin_timestamp,
+        prefer_encrypt: peerstate.prefer_encrypt,
+        verified,
+        verifier_addr: verified.then_some(peerstate.verifier).flatten(),
+    }))
+}
+
+/// Encryption preference set by the user for a contact.
+#[derive(
+    Debug,
+    Default,
+    Display,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    FromPrimitive,
+    ToPrimitive,
+    Serialize,
+    Deserialize,
+)]
+#[repr(u32)]
+pub enum EncryptionOverride {
+    /// Encrypt according to the Autocrypt preference of the contact.
+    #[default]
+    Default = 0,
+
+    /// Never encrypt messages to the contact,
+    /// e.g. because the contact's mail gateway breaks encrypted messages.
+    ForcePlaintext = 1,
+}
+
+/// Sets the encryption preference of a contact, overriding its Autocrypt preference.
+///
+/// With [`EncryptionOverride::ForcePlaintext`], messages to the 1:1 chat
+/// with the contact and read receipts to the contact are sent unencrypted.
+/// Groups containing the contact are still encrypted
+/// so that other members are not affected, and protected chats ignore the override.
+/// The setting is synchronized to other devices.
+pub async fn set_encryption_override(
+    context: &Context,
+    contact_id: ContactId,
+    value: EncryptionOverride,
+) -> Result<()> {
+    set_encryption_override_ex(context, Sync, contact_id, value).await
+}
+
+pub(crate) async fn set_encryption_override_ex(
+    context: &Context,
+    sync: sync::Sync,
+    contact_id: ContactId,
+    value: EncryptionOverride,
+) -> Result<()> {
+    ensure!(
+        !contact_id.is_special(),
+        "Can't set encryption override for special contact {contact_id}"
+    );
+    let mut contact = Contact::get_by_id(context, contact_id).await?;
+    if contact.get_encryption_override() == value {
+        return Ok(());
+    }
+    match value {
+        EncryptionOverride::Default => contact.param.remove(Param::EncryptionOverride),
+        _ => contact
+            .param
+            .set_int(Param::EncryptionOverride, value as i32),
+    };
+    contact.update_param(context).await?;
+    context.emit_event(EventType::ContactsChanged(Some(contact_id)));
+
+    if sync.into() {
+        chat::sync(
+            context,
+            chat::SyncId::ContactAddr(contact.addr.clone()),
+            chat::SyncAction::SetEncryptionOverride(value),
+        )
+        .await
+        .log_err(context)
+        .ok();
+    }
+    Ok(())
+}
+
 pub(crate) async fn set_blocked(
     context: &Context,
     sync: sync::Sync,
@@ -1650,6 +1876,9 @@ pub(crate) async fn set_blocked(
         }
 
         if sync.into() {
+            context
+                .update_contacts_timestamp(contact_id, Param::BlockedTimestamp, time())
+                .await?;
             let action = match new_blocking {
                 true => chat::SyncAction::Block,
                 false => chat::SyncAction::Unblock,
@@ -1721,6 +1950,88 @@ pub(crate) async fn set_profile_image(
     Ok(())
 }
 
+/// Returns the hash of an avatar file as sent in the `Chat-User-Avatar-Hash` header,
+/// or `0` if there is no avatar.
+pub(crate) async fn avatar_hash(path: Option<&Path>) -> Result<String> {
+    let Some(path) = path else {
+        return Ok("0".to_string());
+    };
+    let data = tokio::fs::read(path)
+        .await
+        .with_context(|| format!("Failed to read avatar {}", path.display()))?;
+    Ok(hex::encode(&Sha256::digest(data)[..16]))
+}
+
+/// Handles the avatar hash announced by a contact in a large group.
+///
+/// If the local copy of the contact's avatar does not match,
+/// the avatar is requested with the next message sent to the chat.
+/// To limit the traffic, the avatar is requested from each contact at most once a day.
+pub(crate) async fn handle_avatar_hash(
+    context: &Context,
+    chat_id: ChatId,
+    contact_id: ContactId,
+    hash: &str,
+) -> Result<()> {
+    let contact = Contact::get_by_id(context, contact_id).await?;
+    let profile_image = contact.get_profile_image(context).await?;
+    if avatar_hash(profile_image.as_deref()).await.ok().as_deref() == Some(hash) {
+        return Ok(());
+    }
+    let now = time();
+    let last_request = contact
+        .param
+        .get_i64(Param::ProfileRequestTimestamp)
+        .unwrap_or_default();
+    if last_request > now - 24 * 60 * 60 {
+        return Ok(());
+    }
+    context
+        .update_contacts_timestamp(contact_id, Param::ProfileRequestTimestamp, now)
+        .await?;
+
+    let mut chat = chat::Chat::load_from_db(context, chat_id).await?;
+    let mut requests: Vec<&str> = chat
+        .param
+        .get(Param::AvatarRequests)
+        .unwrap_or_default()
+        .split_ascii_whitespace()
+        .collect();
+    let addr = contact.get_addr();
+    if !requests.contains(&addr) {
+        requests.push(addr);
+    }
+    let requests = requests.join(" ");
+    chat.param.set(Param::AvatarRequests, requests);
+    chat.update_param(context).await?;
+    info!(
+        context,
+        "Avatar of {contact_id} does not match the announced hash, will request it in {chat_id}."
+    );
+    Ok(())
+}
+
+/// Handles a request of a contact to resend the profile data.
+///
+/// The avatar is attached to the next message sent to the contact.
+/// To limit the traffic, requests are ignored if the avatar was sent to the contact recently.
+pub(crate) async fn handle_profile_request(context: &Context, contact_id: ContactId) -> Result<()> {
+    let updated = context
+        .sql
+        .execute(
+            "UPDATE contacts SET selfavatar_sent=0 WHERE id=? AND selfavatar_sent>0 AND selfavatar_sent<?",
+            (contact_id, time() - 24 * 60 * 60),
+        )
+        .await?;
+    if updated > 0 {
+        info!(
+            context,
+            "Contact {contact_id} requested profile data, will attach it to the next message."
+        );
+    }
+    Ok(())
+}
+
 /// Sets contact status.
 ///
 /// For contact SELF, the status is not saved in the contact table, but as Config::Selfstatus.  This
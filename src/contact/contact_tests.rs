@@ -776,6 +776,48 @@ async fn test_contact_get_encrinfo() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_get_key_info() -> Result<()> {
+    let mut tcm = TestContextManager::new();
+    let alice = &tcm.alice().await;
+    let bob = &tcm.bob().await;
+    let fiona = &tcm.fiona().await;
+
+    assert!(get_key_info(alice, ContactId::SELF).await.is_err());
+    let bob_id = alice.add_or_lookup_contact_id(bob).await;
+    assert_eq!(get_key_info(alice, bob_id).await?, None);
+
+    tcm.send_recv(bob, alice, "Hello").await;
+    let key_info = get_key_info(alice, bob_id).await?.unwrap();
+    let bob_key = load_self_public_key(bob).await?;
+    assert_eq!(key_info.fingerprint, bob_key.dc_fingerprint().hex());
+    assert_eq!(key_info.created_timestamp, bob_key.created_at().timestamp());
+    assert!(!key_info.algorithm.is_empty());
+    assert!(key_info.encryption_algorithm.is_some());
+    assert_eq!(key_info.origin, KeyOrigin::Autocrypt);
+    assert!(key_info.origin_timestamp > 0);
+    assert_eq!(key_info.prefer_encrypt, EncryptPreference::Mutual);
+    assert!(!key_info.verified);
+    assert_eq!(key_info.verifier_addr, None);
+
+    // Alice learns the key of Fiona from the group message gossip.
+    tcm.send_recv(alice, bob, "Hi").await;
+    tcm.send_recv(fiona, bob, "Hi").await;
+    let group_id = bob
+        .create_group_with_members(ProtectionStatus::Unprotected, "Group", &[alice, fiona])
+        .await;
+    let sent = bob.send_text(group_id, "Hello group").await;
+    alice.recv_msg(&sent).await;
+    let fiona_id = alice.add_or_lookup_contact_id(fiona).await;
+    let key_info = get_key_info(alice, fiona_id).await?.unwrap();
+    assert_eq!(
+        key_info.fingerprint,
+        load_self_public_key(fiona).await?.dc_fingerprint().hex()
+    );
+    assert_eq!(key_info.origin, KeyOrigin::Gossip);
+    Ok(())
+}
+
 /// Tests that status is synchronized when sending encrypted BCC-self messages and not
 /// synchronized when the message is not encrypted.
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
@@ -1019,6 +1061,36 @@ async fn test_verified_by_none() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_sync_blocked_conflict() -> Result<()> {
+    let alice0 = &TestContext::new_alice().await;
+    let alice1 = &TestContext::new_alice().await;
+    for a in [alice0, alice1] {
+        a.set_config_bool(Config::SyncMsgs, true).await?;
+    }
+    let a0b_contact_id = Contact::create(alice0, "", "bob@example.net").await?;
+    let a1b_contact_id = Contact::create(alice1, "", "bob@example.net").await?;
+
+    // Alice blocks Bob on the first device, then changes her mind on the second one,
+    // but the first device only learns about it after sending its own outdated sync item.
+    Contact::block(alice0, a0b_contact_id).await?;
+    SystemTime::shift(Duration::from_secs(60));
+    Contact::block(alice1, a1b_contact_id).await?;
+    Contact::unblock(alice1, a1b_contact_id).await?;
+
+    test_utils::sync(alice0, alice1).await;
+    assert!(!Contact::get_by_id(alice1, a1b_contact_id)
+        .await?
+        .is_blocked());
+
+    test_utils::sync(alice1, alice0).await;
+    assert!(!Contact::get_by_id(alice0, a0b_contact_id)
+        .await?
+        .is_blocked());
+
+    Ok(())
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn test_sync_create() -> Result<()> {
     let alice0 = &TestContext::new_alice().await;
@@ -1271,3 +1343,72 @@ async fn test_self_is_verified() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_encryption_override() -> Result<()> {
+    let mut tcm = TestContextManager::new();
+    let alice0 = &tcm.alice().await;
+    let alice1 = &tcm.alice().await;
+    let bob = &tcm.bob().await;
+    for a in [alice0, alice1] {
+        a.set_config_bool(Config::SyncMsgs, true).await?;
+    }
+    tcm.send_recv_accept(bob, alice0, "Hi").await;
+    let msg = tcm.send_recv(alice0, bob, "Encrypted").await;
+    assert!(msg.get_showpadlock());
+
+    let bob_id = alice0.add_or_lookup_contact_id(bob).await;
+    set_encryption_override(alice0, bob_id, EncryptionOverride::ForcePlaintext).await?;
+    test_utils::sync(alice0, alice1).await;
+    let a1_bob_id = Contact::lookup_id_by_addr(alice1, "bob@example.net", Origin::Unknown)
+        .await?
+        .unwrap();
+    let a1_bob = Contact::get_by_id(alice1, a1_bob_id).await?;
+    assert_eq!(
+        a1_bob.get_encryption_override(),
+        EncryptionOverride::ForcePlaintext
+    );
+
+    let chat_id = ChatId::get_for_contact(alice0, bob_id).await?;
+    let info = chat_id.get_encryption_details(alice0).await?;
+    assert!(info.members[0].force_plaintext);
+    assert!(!info.can_encrypt);
+    let msg = tcm.send_recv(alice0, bob, "Plaintext").await;
+    assert!(!msg.get_showpadlock());
+
+    // Groups are not affected.
+    let group_id = alice0
+        .create_group_with_members(ProtectionStatus::Unprotected, "Group", &[bob])
+        .await;
+    let info = group_id.get_encryption_details(alice0).await?;
+    assert!(info.members.iter().any(|member| member.force_plaintext));
+    assert!(info.can_encrypt);
+    let sent = alice0.send_text(group_id, "Encrypted group message").await;
+    assert!(bob.recv_msg(&sent).await.get_showpadlock());
+
+    set_encryption_override(alice0, bob_id, EncryptionOverride::Default).await?;
+    let msg = tcm.send_recv(alice0, bob, "Encrypted again").await;
+    assert!(msg.get_showpadlock());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_internationalized_addr() -> Result<()> {
+    let t = TestContext::new_alice().await;
+    let contact_id = Contact::create(&t, "", "Alice@München.de").await?;
+    let contact = Contact::get_by_id(&t, contact_id).await?;
+    assert_eq!(contact.get_addr(), "alice@xn--mnchen-3ya.de");
+    assert_eq!(contact.get_display_addr(&t).await?, "alice@münchen.de");
+
+    t.set_config_bool(Config::DisplayPunycode, true).await?;
+    assert_eq!(
+        contact.get_display_addr(&t).await?,
+        "alice@xn--mnchen-3ya.de"
+    );
+
+    assert_eq!(
+        Contact::lookup_id_by_addr(&t, "alice@münchen.de", Origin::Unknown).await?,
+        Some(contact_id)
+    );
+    Ok(())
+}
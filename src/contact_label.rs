@@ -0,0 +1,475 @@
+//! # Contact labels.
+//!
+//! Labels are local groups of contacts such as "Family" or "Work".
+//! They are never sent to the labeled contacts and are independent from chats,
+//! but are synchronized to other devices of the user.
+
+use std::fmt;
+
+use anyhow::{ensure, Context as _, Result};
+use deltachat_contact_tools::{sanitize_single_line, ContactAddress};
+use serde::{Deserialize, Serialize};
+
+use crate::chat::{self, ChatId, ProtectionStatus};
+use crate::contact::{Contact, ContactId, Origin};
+use crate::context::Context;
+use crate::events::EventType;
+use crate::sync::{self, Sync::*, SyncData};
+
+/// Contact label ID.
+#[derive(
+    Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct LabelId(u32);
+
+impl LabelId {
+    /// Creates a new [`LabelId`].
+    pub const fn new(id: u32) -> LabelId {
+        LabelId(id)
+    }
+
+    /// Numerical representation of the label ID.
+    pub fn to_u32(self) -> u32 {
+        self.0
+    }
+}
+
+impl fmt::Display for LabelId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Label#{}", self.0)
+    }
+}
+
+impl rusqlite::types::ToSql for LabelId {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput> {
+        let val = rusqlite::types::Value::Integer(i64::from(self.0));
+        let out = rusqlite::types::ToSqlOutput::Owned(val);
+        Ok(out)
+    }
+}
+
+impl rusqlite::types::FromSql for LabelId {
+    fn column_result(value: rusqlite::types::ValueRef) -> rusqlite::types::FromSqlResult<Self> {
+        i64::column_result(value).and_then(|val| {
+            val.try_into()
+                .map(LabelId::new)
+                .map_err(|_| rusqlite::types::FromSqlError::OutOfRange(val))
+        })
+    }
+}
+
+/// Contact label.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    id: LabelId,
+    name: String,
+}
+
+impl Label {
+    /// Loads a label from the database.
+    pub async fn load_from_db(context: &Context, id: LabelId) -> Result<Self> {
+        let name = context
+            .sql
+            .query_get_value("SELECT name FROM contact_labels WHERE id=?", (id,))
+            .await?
+            .with_context(|| format!("{id} does not exist"))?;
+        Ok(Self { id, name })
+    }
+
+    /// Returns the label ID.
+    pub fn get_id(&self) -> LabelId {
+        self.id
+    }
+
+    /// Returns the label name.
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Action on a contact label synchronized to other devices.
+///
+/// Labels are identified by name on other devices
+/// because label IDs are local to the database.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum SyncAction {
+    Create,
+    Rename(String),
+    Delete,
+    AddContact(String),
+    RemoveContact(String),
+}
+
+/// Creates a new contact label.
+///
+/// If a label with the same name already exists, its ID is returned.
+pub async fn create_label(context: &Context, name: &str) -> Result<LabelId> {
+    create_label_ex(context, Sync, name).await
+}
+
+async fn create_label_ex(context: &Context, sync: sync::Sync, name: &str) -> Result<LabelId> {
+    let name = sanitize_single_line(name);
+    ensure!(!name.is_empty(), "Label name must not be empty");
+    let label_id = context
+        .sql
+        .transaction({
+            let name = name.clone();
+            move |transaction| {
+                transaction.execute(
+                    "INSERT INTO contact_labels (name) VALUES (?)
+                     ON CONFLICT (name) DO NOTHING",
+                    (&name,),
+                )?;
+                let label_id = transaction.query_row(
+                    "SELECT id FROM contact_labels WHERE name=?",
+                    (&name,),
+                    |row| row.get(0),
+                )?;
+                Ok(label_id)
+            }
+        })
+        .await?;
+    context.emit_event(EventType::ContactsChanged(None));
+    if sync.into() {
+        sync_label(context, name, SyncAction::Create).await?;
+    }
+    Ok(label_id)
+}
+
+/// Renames a contact label.
+pub async fn rename_label(context: &Context, label_id: LabelId, new_name: &str) -> Result<()> {
+    rename_label_ex(context, Sync, label_id, new_name).await
+}
+
+async fn rename_label_ex(
+    context: &Context,
+    sync: sync::Sync,
+    label_id: LabelId,
+    new_name: &str,
+) -> Result<()> {
+    let label = Label::load_from_db(context, label_id).await?;
+    let new_name = sanitize_single_line(new_name);
+    ensure!(!new_name.is_empty(), "Label name must not be empty");
+    if new_name == label.name {
+        return Ok(());
+    }
+    context
+        .sql
+        .execute(
+            "UPDATE contact_labels SET name=? WHERE id=?",
+            (&new_name, label_id),
+        )
+        .await
+        .with_context(|| format!("Cannot rename label to {new_name:?}"))?;
+    context.emit_event(EventType::ContactsChanged(None));
+    if sync.into() {
+        sync_label(context, label.name, SyncAction::Rename(new_name)).await?;
+    }
+    Ok(())
+}
+
+/// Deletes a contact label.
+///
+/// The labeled contacts are not deleted.
+pub async fn delete_label(context: &Context, label_id: LabelId) -> Result<()> {
+    delete_label_ex(context, Sync, label_id).await
+}
+
+async fn delete_label_ex(context: &Context, sync: sync::Sync, label_id: LabelId) -> Result<()> {
+    let label = Label::load_from_db(context, label_id).await?;
+    context
+        .sql
+        .transaction(move |transaction| {
+            transaction.execute(
+                "DELETE FROM contact_labels_contacts WHERE label_id=?",
+                (label_id,),
+            )?;
+            transaction.execute("DELETE FROM contact_labels WHERE id=?", (label_id,))?;
+            Ok(())
+        })
+        .await?;
+    context.emit_event(EventType::ContactsChanged(None));
+    if sync.into() {
+        sync_label(context, label.name, SyncAction::Delete).await?;
+    }
+    Ok(())
+}
+
+/// Returns all contact labels ordered by name.
+pub async fn get_labels(context: &Context) -> Result<Vec<Label>> {
+    context
+        .sql
+        .query_map(
+            "SELECT id, name FROM contact_labels ORDER BY name COLLATE NOCASE",
+            (),
+            |row| {
+                Ok(Label {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                })
+            },
+            |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+        )
+        .await
+}
+
+/// Adds a label to a contact.
+pub async fn add_contact_to_label(
+    context: &Context,
+    label_id: LabelId,
+    contact_id: ContactId,
+) -> Result<()> {
+    add_contact_to_label_ex(context, Sync, label_id, contact_id).await
+}
+
+async fn add_contact_to_label_ex(
+    context: &Context,
+    sync: sync::Sync,
+    label_id: LabelId,
+    contact_id: ContactId,
+) -> Result<()> {
+    ensure!(!contact_id.is_special(), "Cannot label special contact");
+    let label = Label::load_from_db(context, label_id).await?;
+    let contact = Contact::get_by_id(context, contact_id).await?;
+    context
+        .sql
+        .execute(
+            "INSERT OR IGNORE INTO contact_labels_contacts (label_id, contact_id) VALUES (?, ?)",
+            (label_id, contact_id),
+        )
+        .await?;
+    context.emit_event(EventType::ContactsChanged(Some(contact_id)));
+    if sync.into() {
+        let addr = contact.get_addr().to_string();
+        sync_label(context, label.name, SyncAction::AddContact(addr)).await?;
+    }
+    Ok(())
+}
+
+/// Removes a label from a contact.
+pub async fn remove_contact_from_label(
+    context: &Context,
+    label_id: LabelId,
+    contact_id: ContactId,
+) -> Result<()> {
+    remove_contact_from_label_ex(context, Sync, label_id, contact_id).await
+}
+
+async fn remove_contact_from_label_ex(
+    context: &Context,
+    sync: sync::Sync,
+    label_id: LabelId,
+    contact_id: ContactId,
+) -> Result<()> {
+    let label = Label::load_from_db(context, label_id).await?;
+    let contact = Contact::get_by_id(context, contact_id).await?;
+    context
+        .sql
+        .execute(
+            "DELETE FROM contact_labels_contacts WHERE label_id=? AND contact_id=?",
+            (label_id, contact_id),
+        )
+        .await?;
+    context.emit_event(EventType::ContactsChanged(Some(contact_id)));
+    if sync.into() {
+        let addr = contact.get_addr().to_string();
+        sync_label(context, label.name, SyncAction::RemoveContact(addr)).await?;
+    }
+    Ok(())
+}
+
+/// Returns IDs of the contacts having the label.
+///
+/// To apply the usual contact list filtering,
+/// use [`Contact::get_all_with_label`] instead.
+pub async fn get_label_contacts(context: &Context, label_id: LabelId) -> Result<Vec<ContactId>> {
+    context
+        .sql
+        .query_map(
+            "SELECT contact_id FROM contact_labels_contacts WHERE label_id=?",
+            (label_id,),
+            |row| row.get::<_, ContactId>(0),
+            |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+        )
+        .await
+}
+
+/// Returns IDs of the labels of the contact.
+pub async fn get_contact_labels(context: &Context, contact_id: ContactId) -> Result<Vec<LabelId>> {
+    context
+        .sql
+        .query_map(
+            "SELECT l.id FROM contact_labels l
+             INNER JOIN contact_labels_contacts lc ON l.id=lc.label_id
+             WHERE lc.contact_id=?
+             ORDER BY l.name COLLATE NOCASE",
+            (contact_id,),
+            |row| row.get::<_, LabelId>(0),
+            |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+        )
+        .await
+}
+
+/// Creates an unpromoted group chat named after the label
+/// with all unblocked contacts having the label as members.
+pub async fn create_group_chat_from_label(context: &Context, label_id: LabelId) -> Result<ChatId> {
+    let label = Label::load_from_db(context, label_id).await?;
+    let chat_id =
+        chat::create_group_chat(context, ProtectionStatus::Unprotected, &label.name).await?;
+    for contact_id in get_label_contacts(context, label_id).await? {
+        let contact = Contact::get_by_id(context, contact_id).await?;
+        if contact.is_blocked() {
+            continue;
+        }
+        chat::add_contact_to_chat(context, chat_id, contact_id).await?;
+    }
+    Ok(chat_id)
+}
+
+async fn lookup_label(context: &Context, name: &str) -> Result<Option<LabelId>> {
+    context
+        .sql
+        .query_get_value("SELECT id FROM contact_labels WHERE name=?", (name,))
+        .await
+}
+
+async fn sync_label(context: &Context, name: String, action: SyncAction) -> Result<()> {
+    context
+        .add_sync_item(SyncData::AlterContactLabel { name, action })
+        .await?;
+    context.scheduler.interrupt_inbox().await;
+    Ok(())
+}
+
+impl Context {
+    /// Executes [`SyncData::AlterContactLabel`] item sent by other device.
+    pub(crate) async fn sync_alter_contact_label(
+        &self,
+        name: &str,
+        action: &SyncAction,
+    ) -> Result<()> {
+        let label_id = match action {
+            // Labels created before enabling synchronization may be missing,
+            // so adding a contact creates the label if needed.
+            SyncAction::Create | SyncAction::AddContact(_) => {
+                create_label_ex(self, Nosync, name).await?
+            }
+            _ => lookup_label(self, name)
+                .await?
+                .with_context(|| format!("No contact label {name:?}"))?,
+        };
+        match action {
+            SyncAction::Create => Ok(()),
+            SyncAction::Rename(to) => rename_label_ex(self, Nosync, label_id, to).await,
+            SyncAction::Delete => delete_label_ex(self, Nosync, label_id).await,
+            SyncAction::AddContact(addr) => {
+                let addr = ContactAddress::new(addr).context("Invalid address")?;
+                let (contact_id, _) =
+                    Contact::add_or_lookup(self, "", &addr, Origin::Hidden).await?;
+                add_contact_to_label_ex(self, Nosync, label_id, contact_id).await
+            }
+            SyncAction::RemoveContact(addr) => {
+                let Some(contact_id) =
+                    Contact::lookup_id_by_addr_ex(self, addr, Origin::Unknown, None).await?
+                else {
+                    return Ok(());
+                };
+                remove_contact_from_label_ex(self, Nosync, label_id, contact_id).await
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::{get_chat_contacts, Chat};
+    use crate::config::Config;
+    use crate::test_utils::{sync, TestContext};
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_contact_labels() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let bob_id = Contact::create(&t, "Bob", "bob@example.net").await?;
+        let fiona_id = Contact::create(&t, "Fiona", "fiona@example.net").await?;
+
+        let family = create_label(&t, "Family").await?;
+        assert_eq!(create_label(&t, " Family ").await?, family);
+        let work = create_label(&t, "Work").await?;
+        assert!(create_label(&t, "").await.is_err());
+        assert_eq!(get_labels(&t).await?.len(), 2);
+
+        add_contact_to_label(&t, family, bob_id).await?;
+        add_contact_to_label(&t, work, bob_id).await?;
+        add_contact_to_label(&t, work, fiona_id).await?;
+        assert!(add_contact_to_label(&t, work, ContactId::SELF)
+            .await
+            .is_err());
+
+        assert_eq!(
+            Contact::get_all_with_label(&t, family, 0, None).await?,
+            vec![bob_id]
+        );
+        assert_eq!(
+            Contact::get_all_with_label(&t, work, 0, Some("fiona")).await?,
+            vec![fiona_id]
+        );
+        assert_eq!(get_contact_labels(&t, bob_id).await?, vec![family, work]);
+
+        let chat_id = create_group_chat_from_label(&t, work).await?;
+        assert_eq!(Chat::load_from_db(&t, chat_id).await?.get_name(), "Work");
+        assert_eq!(get_chat_contacts(&t, chat_id).await?.len(), 3);
+
+        remove_contact_from_label(&t, work, bob_id).await?;
+        assert_eq!(get_contact_labels(&t, bob_id).await?, vec![family]);
+
+        assert!(rename_label(&t, family, "Work").await.is_err());
+        rename_label(&t, family, "Relatives").await?;
+        assert_eq!(
+            Label::load_from_db(&t, family).await?.get_name(),
+            "Relatives"
+        );
+
+        delete_label(&t, family).await?;
+        assert!(Label::load_from_db(&t, family).await.is_err());
+        assert!(get_contact_labels(&t, bob_id).await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_sync_contact_labels() -> Result<()> {
+        let alice0 = &TestContext::new_alice().await;
+        let alice1 = &TestContext::new_alice().await;
+        for a in [alice0, alice1] {
+            a.set_config_bool(Config::SyncMsgs, true).await?;
+        }
+        let a0_bob_id = Contact::create(alice0, "Bob", "bob@example.net").await?;
+
+        let label_id = create_label(alice0, "Family").await?;
+        add_contact_to_label(alice0, label_id, a0_bob_id).await?;
+        sync(alice0, alice1).await;
+        let labels = get_labels(alice1).await?;
+        assert_eq!(labels.len(), 1);
+        assert_eq!(labels[0].get_name(), "Family");
+        let a1_label_id = labels[0].get_id();
+        let a1_bob_id = Contact::lookup_id_by_addr(alice1, "bob@example.net", Origin::Unknown)
+            .await?
+            .unwrap();
+        assert_eq!(
+            get_label_contacts(alice1, a1_label_id).await?,
+            vec![a1_bob_id]
+        );
+
+        rename_label(alice0, label_id, "Relatives").await?;
+        remove_contact_from_label(alice0, label_id, a0_bob_id).await?;
+        sync(alice0, alice1).await;
+        let label = Label::load_from_db(alice1, a1_label_id).await?;
+        assert_eq!(label.get_name(), "Relatives");
+        assert!(get_label_contacts(alice1, a1_label_id).await?.is_empty());
+
+        delete_label(alice0, label_id).await?;
+        sync(alice0, alice1).await;
+        assert!(get_labels(alice1).await?.is_empty());
+        Ok(())
+    }
+}
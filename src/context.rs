@@ -1,6 +1,6 @@
 //! Context module.
 
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::ffi::OsString;
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
@@ -19,27 +19,36 @@
 use crate::chat::{get_chat_cnt, ChatId, ProtectionStatus};
 use crate::chatlist_events;
 use crate::config::Config;
-use crate::constants::{
-    self, DC_BACKGROUND_FETCH_QUOTA_CHECK_RATELIMIT, DC_CHAT_ID_TRASH, DC_VERSION_STR,
-};
+use crate::configure::ConfigureTrace;
+use crate::constants::{self, DC_CHAT_ID_TRASH, DC_VERSION_STR};
 use crate::contact::{Contact, ContactId};
 use crate::debug_logging::DebugLogging;
+use crate::decrypt::Predecryption;
 use crate::download::DownloadState;
-use crate::events::{Event, EventEmitter, EventType, Events};
-use crate::imap::{FolderMeaning, Imap, ServerMetadata};
+use crate::events::{Event, EventEmitter, EventFilter, EventType, Events};
+use crate::imap::search::{search_on_server, SERVER_SEARCH_THRESHOLD};
+use crate::imap::{ConnectionPool, FolderMeaning, Imap, ServerMetadata};
 use crate::key::{load_self_public_key, load_self_secret_key, DcKey as _};
+use crate::log::{LogRecord, LogSink, RECENT_LOG_LEN};
 use crate::login_param::{ConfiguredLoginParam, EnteredLoginParam};
 use crate::message::{self, Message, MessageState, MsgId};
+use crate::metrics::MetricsCounters;
+#[cfg(any(test, feature = "internals"))]
+use crate::mimefactory::DeterministicMime;
+use crate::mimefactory::DeterministicMimeState;
+use crate::net::NetworkProfile;
 use crate::param::{Param, Params};
 use crate::peer_channels::Iroh;
 use crate::peerstate::Peerstate;
 use crate::push::PushSubscriber;
 use crate::quota::QuotaInfo;
-use crate::scheduler::{convert_folder_meaning, SchedulerState};
+use crate::scheduler::SchedulerState;
 use crate::sql::Sql;
 use crate::stock_str::StockStrings;
+use crate::sync_state::SyncProgress;
 use crate::timesmearing::SmearedTimestamp;
 use crate::tools::{self, create_id, duration_to_str, time, time_elapsed};
+use crate::webxdc::WebxdcRealtimeUsage;
 
 /// Builder for the [`Context`].
 ///
@@ -243,6 +252,12 @@ pub struct InnerContext {
     pub(crate) scheduler: SchedulerState,
     pub(crate) ratelimit: RwLock<Ratelimit>,
 
+    /// Rate limiter for members added to promoted groups by the user.
+    pub(crate) member_add_ratelimit: RwLock<Ratelimit>,
+
+    /// Rate limiters for notifications of webxdc apps, by instance.
+    pub(crate) webxdc_notify_ratelimits: RwLock<HashMap<MsgId, Ratelimit>>,
+
     /// Recently loaded quota information, if any.
     /// Set to `None` if quota was never tried to load.
     pub(crate) quota: RwLock<Option<QuotaInfo>>,
@@ -250,6 +265,17 @@ pub struct InnerContext {
     /// IMAP UID resync request.
     pub(crate) resync_request: AtomicBool,
 
+    /// Progress of fetching messages from the server.
+    pub(crate) sync_progress: SyncProgress,
+
+    /// Quota update request from the background job scheduler.
+    pub(crate) quota_update_request: AtomicBool,
+
+    /// True if periodic jobs such as housekeeping are run
+    /// by the background job scheduler of the account manager
+    /// rather than by the IMAP loop.
+    pub(crate) background_jobs_managed: AtomicBool,
+
     /// Notify about new messages.
     ///
     /// This causes [`Context::wait_next_msgs`] to wake up.
@@ -284,6 +310,19 @@ pub struct InnerContext {
     /// because the lock is used from synchronous [`Context::emit_event`].
     pub(crate) debug_logging: std::sync::RwLock<Option<DebugLogging>>,
 
+    /// Callback receiving structured log records, see [`Context::set_log_sink`].
+    pub(crate) log_sink: parking_lot::RwLock<Option<LogSink>>,
+
+    /// Recent log records and their timestamps, used for the diagnostics report.
+    pub(crate) recent_log: parking_lot::Mutex<VecDeque<(i64, LogRecord)>>,
+
+    /// Steps of the current or the last configuration attempt,
+    /// see [`Context::get_last_configure_trace`].
+    pub(crate) configure_trace: parking_lot::Mutex<ConfigureTrace>,
+
+    /// Commands registered by a bot, see [`Context::register_bot_command`].
+    pub(crate) bot_commands: parking_lot::RwLock<BTreeSet<String>>,
+
     /// Push subscriber to store device token
     /// and register for heartbeat notifications.
     pub(crate) push_subscriber: PushSubscriber,
@@ -291,8 +330,92 @@ pub struct InnerContext {
     /// True if account has subscribed to push notifications via IMAP.
     pub(crate) push_subscribed: AtomicBool,
 
+    /// Network profile last reported by the UI.
+    pub(crate) network_profile: parking_lot::RwLock<NetworkProfile>,
+
+    /// Power mode last set by the UI.
+    pub(crate) power_mode: parking_lot::RwLock<PowerMode>,
+
     /// Iroh for realtime peer channels.
     pub(crate) iroh: Arc<RwLock<Option<Iroh>>>,
+
+    /// Realtime data bytes not yet written to the database,
+    /// see [`Context::flush_webxdc_realtime_bytes`].
+    pub(crate) webxdc_realtime_usage: parking_lot::Mutex<WebxdcRealtimeUsage>,
+
+    /// Deterministic rendering state of outgoing messages, used for snapshot tests.
+    pub(crate) deterministic_mime: parking_lot::Mutex<Option<DeterministicMimeState>>,
+
+    /// Timestamp of the next attempt to send queued messages
+    /// if sending is postponed after an error.
+    pub(crate) smtp_retry_timestamp: parking_lot::Mutex<Option<i64>>,
+
+    /// Counters for [`Context::get_metrics`].
+    pub(crate) metrics: MetricsCounters,
+}
+
+/// Power mode of the device, set by the embedder
+/// using [`Context::set_power_mode`].
+///
+/// The mode trades off latency of receiving messages against battery usage.
+#[derive(
+    Debug,
+    Default,
+    Display,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    FromPrimitive,
+    ToPrimitive,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[repr(u32)]
+pub enum PowerMode {
+    /// Default mode.
+    #[default]
+    Balanced = 0,
+
+    /// Poll more often and prefetch more messages,
+    /// e.g. while the device is charging.
+    Performance = 1,
+
+    /// Keep IDLE connections longer, poll less often,
+    /// do not decrypt messages in parallel
+    /// and defer background jobs such as housekeeping.
+    BatterySaver = 2,
+}
+
+impl PowerMode {
+    /// Returns the time after which IMAP IDLE is restarted
+    /// if the server sends nothing.
+    pub(crate) fn idle_timeout(self) -> Duration {
+        match self {
+            PowerMode::Performance => Duration::from_secs(2 * 60),
+            PowerMode::Balanced => Duration::from_secs(5 * 60),
+            PowerMode::BatterySaver => Duration::from_secs(25 * 60),
+        }
+    }
+
+    /// Returns the polling interval if the server does not support IMAP IDLE.
+    pub(crate) fn fake_idle_interval(self) -> Duration {
+        match self {
+            PowerMode::Performance => Duration::from_secs(30),
+            PowerMode::Balanced => Duration::from_secs(60),
+            PowerMode::BatterySaver => Duration::from_secs(5 * 60),
+        }
+    }
+
+    /// Returns the number of fetched messages to decrypt ahead in parallel
+    /// given the number of available threads.
+    pub(crate) fn prefetch_lookahead(self, parallelism: usize) -> usize {
+        match self {
+            PowerMode::Performance => parallelism.saturating_mul(2),
+            PowerMode::Balanced => parallelism,
+            PowerMode::BatterySaver => 0,
+        }
+    }
 }
 
 /// The state of ongoing process.
@@ -439,8 +562,13 @@ pub(crate) fn with_blobdir(
             events,
             scheduler: SchedulerState::new(),
             ratelimit: RwLock::new(Ratelimit::new(Duration::new(60, 0), 6.0)), // Allow at least 1 message every 10 seconds + a burst of 6.
+            member_add_ratelimit: RwLock::new(Ratelimit::new(Duration::new(60, 0), 30.0)),
+            webxdc_notify_ratelimits: RwLock::new(HashMap::new()),
             quota: RwLock::new(None),
             resync_request: AtomicBool::new(false),
+            sync_progress: SyncProgress::default(),
+            quota_update_request: AtomicBool::new(false),
+            background_jobs_managed: AtomicBool::new(false),
             new_msgs_notify,
             server_id: RwLock::new(None),
             metadata: RwLock::new(None),
@@ -448,9 +576,19 @@ pub(crate) fn with_blobdir(
             last_full_folder_scan: Mutex::new(None),
             last_error: parking_lot::RwLock::new("".to_string()),
             debug_logging: std::sync::RwLock::new(None),
+            log_sink: parking_lot::RwLock::new(None),
+            recent_log: parking_lot::Mutex::new(VecDeque::with_capacity(RECENT_LOG_LEN)),
+            configure_trace: parking_lot::Mutex::new(ConfigureTrace::default()),
+            bot_commands: parking_lot::RwLock::new(BTreeSet::new()),
             push_subscriber,
             push_subscribed: AtomicBool::new(false),
+            network_profile: parking_lot::RwLock::new(NetworkProfile::default()),
+            power_mode: parking_lot::RwLock::new(PowerMode::default()),
             iroh: Arc::new(RwLock::new(None)),
+            webxdc_realtime_usage: parking_lot::Mutex::new(WebxdcRealtimeUsage::default()),
+            deterministic_mime: parking_lot::Mutex::new(None),
+            smtp_retry_timestamp: parking_lot::Mutex::new(None),
+            metrics: MetricsCounters::default(),
         };
 
         let ctx = Context {
@@ -515,6 +653,60 @@ pub async fn maybe_network(&self) {
         self.scheduler.maybe_network().await;
     }
 
+    /// Sets the kind of the network the device is connected to.
+    ///
+    /// UIs should call this from the platform hooks reporting network changes.
+    /// When switching to a metered network, large messages are only downloaded partially
+    /// and, while roaming, webxdc status updates are queued until the network changes.
+    /// When switching back to an unmetered network, deferred work is resumed.
+    pub async fn set_network_profile(&self, profile: NetworkProfile) {
+        let old_profile = std::mem::replace(&mut *self.network_profile.write(), profile);
+        if old_profile == profile {
+            return;
+        }
+        info!(
+            self,
+            "Network profile changed from {old_profile} to {profile}."
+        );
+        self.emit_event(EventType::ConnectivityChanged);
+        if old_profile == NetworkProfile::Roaming {
+            self.scheduler.interrupt_smtp().await;
+        }
+        if old_profile.is_metered() && !profile.is_metered() {
+            self.scheduler.interrupt_inbox().await;
+            self.scheduler.interrupt_oboxes().await;
+        }
+    }
+
+    /// Returns the network profile last set with [`Context::set_network_profile`].
+    pub fn get_network_profile(&self) -> NetworkProfile {
+        *self.network_profile.read()
+    }
+
+    /// Sets the power mode of the device.
+    ///
+    /// UIs should call this e.g. when the device starts or stops charging
+    /// or the user enables battery saving.
+    /// The mode changes IMAP IDLE and polling intervals,
+    /// parallel decryption of fetched messages
+    /// and whether background jobs are deferred.
+    pub async fn set_power_mode(&self, mode: PowerMode) {
+        let old_mode = std::mem::replace(&mut *self.power_mode.write(), mode);
+        if old_mode == mode {
+            return;
+        }
+        info!(self, "Power mode changed from {old_mode} to {mode}.");
+
+        // Restart IDLE and polling with the new intervals.
+        self.scheduler.interrupt_inbox().await;
+        self.scheduler.interrupt_oboxes().await;
+    }
+
+    /// Returns the power mode last set with [`Context::set_power_mode`].
+    pub fn get_power_mode(&self) -> PowerMode {
+        *self.power_mode.read()
+    }
+
     /// Returns true if an account is on a chatmail server.
     pub async fn is_chatmail(&self) -> Result<bool> {
         self.get_config_bool(Config::IsChatmail).await
@@ -541,19 +733,35 @@ pub(crate) async fn get_max_smtp_rcpt_to(&self) -> Result<usize> {
     ///
     /// Can be used even if I/O is currently stopped.
     /// If I/O is currently stopped, starts a new IMAP connection
-    /// and fetches from Inbox and DeltaChat folders.
-    pub async fn background_fetch(&self) -> Result<()> {
+    /// and does the work in the order of priority:
+    /// fetches new messages from Inbox and DeltaChat folders first,
+    /// then sends queued read receipts,
+    /// then moves and deletes messages on the server
+    /// and finally updates quota and does housekeeping.
+    ///
+    /// Work that does not fit into the time `budget`
+    /// is left for the next fetch.
+    pub async fn background_fetch(&self, budget: Duration) -> Result<()> {
         if !(self.is_configured().await?) {
             return Ok(());
         }
 
         let address = self.get_primary_self_addr().await?;
         let time_start = tools::Time::now();
+        let deadline = tokio::time::Instant::now() + budget;
         info!(self, "background_fetch started fetching {address}.");
 
         if self.scheduler.is_running().await {
             self.scheduler.maybe_network().await;
-            self.wait_for_all_work_done().await;
+            if tokio::time::timeout_at(deadline, self.wait_for_all_work_done())
+                .await
+                .is_err()
+            {
+                info!(
+                    self,
+                    "background_fetch budget exhausted before all work is done."
+                );
+            }
         } else {
             // Pause the scheduler to ensure another connection does not start
             // while we are fetching on a dedicated connection.
@@ -563,27 +771,24 @@ pub async fn background_fetch(&self) -> Result<()> {
             let mut connection = Imap::new_configured(self, channel::bounded(1).1).await?;
             let mut session = connection.prepare(self).await?;
 
-            // Fetch IMAP folders.
-            // Inbox is fetched before Mvbox because fetching from Inbox
-            // may result in moving some messages to Mvbox.
-            for folder_meaning in [FolderMeaning::Inbox, FolderMeaning::Mvbox] {
-                if let Some((_folder_config, watch_folder)) =
-                    convert_folder_meaning(self, folder_meaning).await?
-                {
-                    connection
-                        .fetch_move_delete(self, &mut session, &watch_folder, folder_meaning)
-                        .await?;
-                }
-            }
-
-            // Update quota (to send warning if full) - but only check it once in a while.
+            // Inbox and Mvbox are fetched concurrently if additional connections are allowed.
+            let mut folders = vec![FolderMeaning::Inbox];
             if self
-                .quota_needs_update(DC_BACKGROUND_FETCH_QUOTA_CHECK_RATELIMIT)
-                .await
+                .get_config(Config::ConfiguredMvboxFolder)
+                .await?
+                .is_some()
             {
-                if let Err(err) = self.update_recent_quota(&mut session).await {
-                    warn!(self, "Failed to update quota: {err:#}.");
-                }
+                folders.push(FolderMeaning::Mvbox);
+            }
+            let pool = ConnectionPool::open(self, folders.len()).await?;
+            let skipped = pool
+                .run_background_work(self, &mut connection, &mut session, &folders, deadline)
+                .await?;
+            if skipped > 0 {
+                info!(
+                    self,
+                    "background_fetch budget exhausted, {skipped} work items left for the next time."
+                );
             }
         }
 
@@ -610,6 +815,14 @@ pub fn sql(&self) -> &Sql {
         &self.inner.sql
     }
 
+    /// Enables or disables deterministic rendering of outgoing messages.
+    ///
+    /// Warning: this is only here for snapshot tests, not part of the public API.
+    #[cfg(any(test, feature = "internals"))]
+    pub fn set_deterministic_mime(&self, options: Option<DeterministicMime>) {
+        *self.deterministic_mime.lock() = options.map(DeterministicMimeState::new);
+    }
+
     /// Returns database file path.
     pub fn get_dbfile(&self) -> &Path {
         self.sql.dbfile.as_path()
@@ -703,6 +916,14 @@ pub fn get_event_emitter(&self) -> EventEmitter {
         self.events.get_emitter()
     }
 
+    /// Returns an event emitter which only receives events matching `filter`.
+    ///
+    /// Unlike emitters returned by [`Context::get_event_emitter`],
+    /// filtered emitters receive a copy of each matching event.
+    pub fn get_filtered_event_emitter(&self, filter: EventFilter) -> EventEmitter {
+        self.events.get_filtered_emitter(filter)
+    }
+
     /// Get the ID of this context.
     pub fn get_id(&self) -> u32 {
         self.id
@@ -881,6 +1102,12 @@ pub async fn get_info(&self) -> Result<BTreeMap<&'static str, String>> {
                 .await?
                 .to_string(),
         );
+        res.insert(
+            "chatmail_capabilities",
+            self.get_config(Config::ConfiguredChatmailCapabilities)
+                .await?
+                .unwrap_or_else(|| "<unset>".to_string()),
+        );
         res.insert(
             "is_muted",
             self.get_config_bool(Config::IsMuted).await?.to_string(),
@@ -1042,12 +1269,201 @@ pub async fn get_info(&self) -> Result<BTreeMap<&'static str, String>> {
                 .await?
                 .to_string(),
         );
+        res.insert("network_profile", self.get_network_profile().to_string());
+        let power_mode = self.get_power_mode();
+        res.insert("power_mode", power_mode.to_string());
+        res.insert(
+            "power_mode_idle_timeout",
+            duration_to_str(power_mode.idle_timeout()),
+        );
+        res.insert(
+            "power_mode_poll_interval",
+            duration_to_str(power_mode.fake_idle_interval()),
+        );
+        res.insert(
+            "power_mode_prefetch_lookahead",
+            power_mode
+                .prefetch_lookahead(Predecryption::lookahead())
+                .to_string(),
+        );
         res.insert(
             "webxdc_realtime_enabled",
             self.get_config_bool(Config::WebxdcRealtimeEnabled)
                 .await?
                 .to_string(),
         );
+        res.insert(
+            "reactions_count_as_fresh",
+            self.get_config_bool(Config::ReactionsCountAsFresh)
+                .await?
+                .to_string(),
+        );
+        res.insert(
+            "request_stale_profiles",
+            self.get_config_bool(Config::RequestStaleProfiles)
+                .await?
+                .to_string(),
+        );
+        res.insert(
+            "imap_connections",
+            self.get_config_u64(Config::ImapConnections)
+                .await?
+                .to_string(),
+        );
+        res.insert(
+            "quota_warn_threshold",
+            self.get_config_u64(Config::QuotaWarnThreshold)
+                .await?
+                .to_string(),
+        );
+        res.insert(
+            "backup_reminder_days",
+            self.get_config_u64(Config::BackupReminderDays)
+                .await?
+                .to_string(),
+        );
+        res.insert(
+            "key_age_warning_years",
+            self.get_config_u64(Config::KeyAgeWarningYears)
+                .await?
+                .to_string(),
+        );
+        res.insert(
+            "db_size_warning_mb",
+            self.get_config_u64(Config::DbSizeWarningMb)
+                .await?
+                .to_string(),
+        );
+        res.insert(
+            "webxdc_usage_warn_bytes",
+            self.get_config_u64(Config::WebxdcUsageWarnBytes)
+                .await?
+                .to_string(),
+        );
+        res.insert(
+            "webxdc_usage_warn_updates",
+            self.get_config_u64(Config::WebxdcUsageWarnUpdates)
+                .await?
+                .to_string(),
+        );
+        res.insert(
+            "webxdc_max_size",
+            self.get_config_u64(Config::WebxdcMaxSize)
+                .await?
+                .to_string(),
+        );
+        res.insert(
+            "allowed_custom_headers",
+            self.get_config(Config::AllowedCustomHeaders)
+                .await?
+                .unwrap_or_default(),
+        );
+        res.insert(
+            "securejoin_local_network",
+            self.get_config_bool(Config::SecurejoinLocalNetwork)
+                .await?
+                .to_string(),
+        );
+        res.insert(
+            "mua_threading_compat",
+            self.get_config_bool(Config::MuaThreadingCompat)
+                .await?
+                .to_string(),
+        );
+        res.insert(
+            "smtp_retry_initial_delay",
+            self.get_config_u64(Config::SmtpRetryInitialDelay)
+                .await?
+                .to_string(),
+        );
+        res.insert(
+            "smtp_retry_max_delay",
+            self.get_config_u64(Config::SmtpRetryMaxDelay)
+                .await?
+                .to_string(),
+        );
+        res.insert(
+            "smtp_max_retries",
+            self.get_config_u64(Config::SmtpMaxRetries)
+                .await?
+                .to_string(),
+        );
+        res.insert(
+            "smtp_queued",
+            self.sql
+                .count("SELECT COUNT(*) FROM smtp", ())
+                .await?
+                .to_string(),
+        );
+        res.insert(
+            "smtp_quarantined",
+            self.sql
+                .count("SELECT COUNT(*) FROM smtp_quarantine", ())
+                .await?
+                .to_string(),
+        );
+        res.insert(
+            "imap_retry_initial_delay",
+            self.get_config_u64(Config::ImapRetryInitialDelay)
+                .await?
+                .to_string(),
+        );
+        res.insert(
+            "imap_retry_max_delay",
+            self.get_config_u64(Config::ImapRetryMaxDelay)
+                .await?
+                .to_string(),
+        );
+        res.insert(
+            "blob_quarantine_days",
+            self.get_config_u64(Config::BlobQuarantineDays)
+                .await?
+                .to_string(),
+        );
+        res.insert(
+            "group_member_soft_limit",
+            self.get_config_u64(Config::GroupMemberSoftLimit)
+                .await?
+                .to_string(),
+        );
+        res.insert(
+            "group_member_hard_limit",
+            self.get_config_u64(Config::GroupMemberHardLimit)
+                .await?
+                .to_string(),
+        );
+        res.insert(
+            "unified_push_gateway",
+            self.get_config(Config::UnifiedPushGateway)
+                .await?
+                .unwrap_or_default(),
+        );
+        res.insert(
+            "timezone",
+            self.get_config(Config::Timezone).await?.unwrap_or_default(),
+        );
+        res.insert(
+            "dkim_selector",
+            self.get_config(Config::DkimSelector)
+                .await?
+                .unwrap_or_default(),
+        );
+        res.insert(
+            "display_punycode",
+            self.get_config_bool(Config::DisplayPunycode)
+                .await?
+                .to_string(),
+        );
+        res.insert(
+            "request_ocr",
+            self.get_config_bool(Config::RequestOcr).await?.to_string(),
+        );
+        res.insert(
+            "dnd_schedule",
+            self.get_config(Config::DndSchedule)
+                .await?
+                .unwrap_or_default(),
+        );
 
         let elapsed = time_elapsed(&self.creation_time);
         res.insert("uptime", duration_to_str(elapsed));
@@ -1225,6 +1641,8 @@ pub async fn draft_self_report(&self) -> Result<ChatId> {
     /// and is typically used to show notifications.
     /// Moreover, the number of returned messages
     /// can be used for a badge counter on the app icon.
+    /// Messages of archived chats that are not unarchived by any message
+    /// are not returned, see [`crate::chat::UnarchivePolicy`].
     pub async fn get_fresh_msgs(&self) -> Result<Vec<MsgId>> {
         let list = self
             .sql
@@ -1238,10 +1656,12 @@ pub async fn get_fresh_msgs(&self) -> Result<Vec<MsgId>> {
                     "        ON m.chat_id=c.id",
                     " WHERE m.state=?",
                     "   AND m.hidden=0",
+                    "   AND m.hidden_local=0",
                     "   AND m.chat_id>9",
                     "   AND ct.blocked=0",
                     "   AND c.blocked=0",
                     "   AND NOT(c.muted_until=-1 OR c.muted_until>?)",
+                    "   AND NOT(c.archived=1 AND c.unarchive_policy!=0)",
                     " ORDER BY m.timestamp DESC,m.id DESC;"
                 ),
                 (MessageState::InFresh, time()),
@@ -1335,6 +1755,8 @@ pub async fn wait_next_msgs(&self) -> Result<Vec<MsgId>> {
 
     /// Searches for messages containing the query string case-insensitively.
     ///
+    /// Text recognized in images is searched as well, see [`crate::ocr`].
+    ///
     /// If `chat_id` is provided this searches only for messages in this chat, if `chat_id`
     /// is `None` this searches messages from all chats.
     ///
@@ -1357,10 +1779,12 @@ pub async fn search_msgs(&self, chat_id: Option<ChatId>, query: &str) -> Result<
                  FROM msgs m
                  LEFT JOIN contacts ct
                         ON m.from_id=ct.id
-                 WHERE m.chat_id=?
+                 WHERE m.chat_id=?1
                    AND m.hidden=0
+                   AND m.hidden_local=0
                    AND ct.blocked=0
-                   AND IFNULL(txt_normalized, txt) LIKE ?
+                   AND (IFNULL(txt_normalized, txt) LIKE ?2
+                        OR m.id IN (SELECT rowid FROM msgs_ocr WHERE txt_ocr LIKE ?2))
                  ORDER BY m.timestamp,m.id;",
                     (chat_id, str_like_in_text),
                     |row| row.get::<_, MsgId>("id"),
@@ -1394,9 +1818,11 @@ pub async fn search_msgs(&self, chat_id: Option<ChatId>, query: &str) -> Result<
                         ON m.chat_id=c.id
                  WHERE m.chat_id>9
                    AND m.hidden=0
+                   AND m.hidden_local=0
                    AND c.blocked!=1
                    AND ct.blocked=0
-                   AND IFNULL(txt_normalized, txt) LIKE ?
+                   AND (IFNULL(txt_normalized, txt) LIKE ?1
+                        OR m.id IN (SELECT rowid FROM msgs_ocr WHERE txt_ocr LIKE ?1))
                  ORDER BY m.id DESC LIMIT 1000",
                     (str_like_in_text,),
                     |row| row.get::<_, MsgId>("id"),
@@ -1414,6 +1840,36 @@ pub async fn search_msgs(&self, chat_id: Option<ChatId>, query: &str) -> Result<
         Ok(list)
     }
 
+    /// Searches for messages like [`Context::search_msgs`],
+    /// but if there are only few local results,
+    /// also searches the watched folders on the IMAP server.
+    ///
+    /// Matching messages that were not downloaded yet are added as partially downloaded messages
+    /// and can be downloaded using [`MsgId::download_full`].
+    /// This function connects to the server, so it should not be called on each keystroke.
+    pub async fn search_msgs_on_server(
+        &self,
+        chat_id: Option<ChatId>,
+        query: &str,
+    ) -> Result<Vec<MsgId>> {
+        let mut list = self.search_msgs(chat_id, query).await?;
+        let query = query.trim();
+        if query.is_empty() || list.len() >= SERVER_SEARCH_THRESHOLD {
+            return Ok(list);
+        }
+
+        for msg_id in search_on_server(self, chat_id, query).await? {
+            if !list.contains(&msg_id) {
+                list.push(msg_id);
+            }
+        }
+        if chat_id.is_none() {
+            // Same order as in `search_msgs()`.
+            list.sort_unstable_by(|a, b| b.cmp(a));
+        }
+        Ok(list)
+    }
+
     /// Returns true if given folder name is the name of the inbox.
     pub async fn is_inbox(&self, folder_name: &str) -> Result<bool> {
         let inbox = self.get_config(Config::ConfiguredInboxFolder).await?;
@@ -1732,6 +2188,12 @@ async fn test_get_info() {
 
         let info = t.get_info().await.unwrap();
         assert!(info.contains_key("database_dir"));
+        assert_eq!(info.get("power_mode").unwrap(), "Balanced");
+
+        t.set_power_mode(PowerMode::BatterySaver).await;
+        let info = t.get_info().await.unwrap();
+        assert_eq!(info.get("power_mode").unwrap(), "BatterySaver");
+        assert_eq!(info.get("power_mode_prefetch_lookahead").unwrap(), "0");
     }
 
     #[test]
@@ -1778,6 +2240,7 @@ async fn test_get_info_completeness() {
             "key_id",
             "webxdc_integration",
             "device_token",
+            "dkim_private_key", // Secret key, don't leak it to the logs.
         ];
         let t = TestContext::new().await;
         let info = t.get_info().await.unwrap();
@@ -1,17 +1,106 @@
 //! End-to-end decryption support.
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 
 use anyhow::Result;
 use deltachat_contact_tools::addr_cmp;
 use mailparse::ParsedMail;
+use sha2::{Digest, Sha256};
+use tokio::sync::Semaphore;
+use tokio::task::JoinHandle;
 
 use crate::aheader::Aheader;
 use crate::context::Context;
-use crate::key::{DcKey, Fingerprint, SignedPublicKey, SignedSecretKey};
+use crate::key::{load_self_secret_keyring, DcKey, Fingerprint, SignedPublicKey, SignedSecretKey};
+use crate::log::LogExt;
 use crate::peerstate::Peerstate;
 use crate::pgp;
 
+/// Result of decrypting a message, see [`try_decrypt`].
+pub(crate) type DecryptionResult = Result<Option<::pgp::composed::Message>>;
+
+/// Decrypts messages of a single FETCH ahead of time on the blocking thread pool.
+///
+/// While a fetched message is processed, the messages following it are decrypted in parallel.
+/// Messages are still received one after another in the order they were fetched,
+/// the decryption result is passed to `receive_imf` if it was started.
+///
+/// Decryptions which were not picked up, e.g. because the message turned out to be a duplicate,
+/// are aborted when the `Predecryption` is dropped.
+#[derive(Debug)]
+pub(crate) struct Predecryption {
+    /// Limits the number of parallel decryptions.
+    semaphore: Arc<Semaphore>,
+
+    /// Running decryptions by SHA-256 hash of the raw message.
+    pending: HashMap<[u8; 32], JoinHandle<DecryptionResult>>,
+}
+
+impl Default for Predecryption {
+    fn default() -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(Self::lookahead())),
+            pending: HashMap::new(),
+        }
+    }
+}
+
+impl Drop for Predecryption {
+    fn drop(&mut self) {
+        for (_, handle) in self.pending.drain() {
+            handle.abort();
+        }
+    }
+}
+
+impl Predecryption {
+    /// Returns how many messages should be decrypted ahead of the current one,
+    /// this is the number of available threads.
+    pub(crate) fn lookahead() -> usize {
+        std::thread::available_parallelism().map_or(1, |n| n.get())
+    }
+
+    /// Starts decrypting the raw message `body` in the background.
+    pub(crate) fn start(&mut self, context: &Context, body: &[u8]) {
+        let key = hash(body);
+        if self.pending.contains_key(&key) {
+            return;
+        }
+        let semaphore = Arc::clone(&self.semaphore);
+        let context = context.clone();
+        let body = body.to_vec();
+        let handle = tokio::spawn(async move {
+            let _permit = semaphore.acquire_owned().await?;
+            let private_keyring = load_self_secret_keyring(&context).await?;
+            tokio::task::spawn_blocking(move || {
+                let mail = mailparse::parse_mail(&body)?;
+                try_decrypt(&mail, &private_keyring)
+            })
+            .await?
+        });
+        self.pending.insert(key, handle);
+    }
+
+    /// Returns the result of a decryption started for the raw message `body`.
+    ///
+    /// Waits for the decryption to finish if it is still running.
+    /// Returns `None` if decryption was not started or failed to run,
+    /// the caller then has to decrypt the message itself.
+    pub(crate) async fn take(
+        &mut self,
+        context: &Context,
+        body: &[u8],
+    ) -> Option<DecryptionResult> {
+        let handle = self.pending.remove(&hash(body))?;
+        handle.await.log_err(context).ok()
+    }
+}
+
+fn hash(body: &[u8]) -> [u8; 32] {
+    Sha256::digest(body).into()
+}
+
 /// Tries to decrypt a message, but only if it is structured as an Autocrypt message.
 ///
 /// If successful and the message is encrypted, returns decrypted body.
@@ -223,8 +312,55 @@ pub(crate) async fn get_autocrypt_peerstate(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::receive_imf::receive_imf;
-    use crate::test_utils::TestContext;
+    use crate::receive_imf::{receive_imf, receive_imf_inner};
+    use crate::test_utils::{TestContext, TestContextManager};
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_predecryption() -> Result<()> {
+        let mut tcm = TestContextManager::new();
+        let alice = &tcm.alice().await;
+        let bob = &tcm.bob().await;
+
+        // Alice learns Bob's key.
+        let bob_chat = bob.create_chat(alice).await;
+        alice
+            .recv_msg(&bob.send_text(bob_chat.id, "hi").await)
+            .await;
+
+        let chat = alice.create_chat(bob).await;
+        let sent = alice.send_text(chat.id, "hello").await;
+        assert!(sent.load_from_db().await.get_showpadlock());
+
+        let body = sent.payload().as_bytes();
+        let mut predecryption = Predecryption::default();
+        predecryption.start(bob, body);
+        assert_eq!(predecryption.pending.len(), 1);
+
+        // Decryptions started for one fetch are not visible to others.
+        assert!(Predecryption::default().take(bob, body).await.is_none());
+
+        let predecrypted = predecryption.take(bob, body).await;
+        assert!(predecryption.pending.is_empty());
+        assert!(matches!(predecrypted, Some(Ok(Some(_)))));
+        receive_imf_inner(
+            bob,
+            "INBOX",
+            0,
+            0,
+            &sent.load_from_db().await.rfc724_mid,
+            body,
+            false,
+            None,
+            false,
+            predecrypted,
+        )
+        .await?;
+        let msg = bob.get_last_msg().await;
+        assert_eq!(msg.text, "hello");
+        assert!(msg.get_showpadlock());
+
+        Ok(())
+    }
 
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_mixed_up_mime() -> Result<()> {
@@ -0,0 +1,283 @@
+//! # Self-diagnostics report.
+//!
+//! Collects the information support teams usually ask for
+//! into a single report that users can share as text or JSON:
+//! connectivity and connection errors, folder configuration, quota,
+//! provider, key counts and the most recent log messages.
+//!
+//! Email addresses are removed from the log messages and connection errors,
+//! connection errors are truncated as they may contain whole server responses.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::constants::DC_VERSION_STR;
+use crate::context::Context;
+use crate::log::LogRecord;
+use crate::tools::{time, timestamp_to_str, truncate};
+
+/// Config options describing the folder configuration.
+const FOLDER_CONFIGS: &[Config] = &[
+    Config::ConfiguredInboxFolder,
+    Config::ConfiguredMvboxFolder,
+    Config::ConfiguredSentboxFolder,
+    Config::ConfiguredTrashFolder,
+    Config::MvboxMove,
+    Config::OnlyFetchMvbox,
+    Config::SentboxWatch,
+    Config::DeleteServerAfter,
+];
+
+/// Approximate maximum number of characters of a connection error in the report.
+const MAX_CONNECTION_ERROR_CHARS: usize = 300;
+
+/// State of a single IMAP or SMTP connection.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConnectionDiagnostics {
+    /// Name of the connection, e.g. `IMAP Inbox` or `SMTP`.
+    pub name: String,
+
+    /// Current state of the connection.
+    pub state: String,
+
+    /// Last error of the connection, even if it has recovered since then.
+    pub last_error: Option<String>,
+
+    /// Timestamp of the last error.
+    pub last_error_timestamp: Option<i64>,
+}
+
+/// Self-diagnostics report, see [`Context::generate_diagnostics`].
+///
+/// Use [`ToString::to_string`] to get a text report
+/// or serialize it to get a JSON report.
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostics {
+    /// Version of the core library.
+    pub core_version: String,
+
+    /// Timestamp of the report generation.
+    pub timestamp: i64,
+
+    /// True if the account is configured.
+    pub configured: bool,
+
+    /// Provider ID from the provider database, if the provider is known.
+    pub provider: Option<String>,
+
+    /// Overall connectivity.
+    pub connectivity: String,
+
+    /// State of each connection, empty if I/O is not started.
+    pub connections: Vec<ConnectionDiagnostics>,
+
+    /// Folder configuration.
+    pub folders: BTreeMap<String, String>,
+
+    /// Quota usage of each resource or the error loading it.
+    pub quota: Vec<String>,
+
+    /// Number of own key pairs.
+    pub self_keys: usize,
+
+    /// Number of contacts with known keys.
+    pub peer_keys: usize,
+
+    /// Recent log messages with email addresses removed, oldest first.
+    pub log: Vec<String>,
+}
+
+impl Context {
+    /// Generates a self-diagnostics report that users can share with support.
+    pub async fn generate_diagnostics(&self) -> Result<Diagnostics> {
+        let provider = self
+            .get_configured_provider()
+            .await?
+            .map(|provider| provider.id.to_string());
+
+        let mut folders = BTreeMap::new();
+        for config in FOLDER_CONFIGS {
+            let value = self.get_config(*config).await?.unwrap_or_default();
+            folders.insert(config.as_ref().to_string(), value);
+        }
+
+        let quota = match self.get_quota_usage().await {
+            Ok(Some(roots)) => roots
+                .iter()
+                .flat_map(|root| {
+                    let prefix = if root.name.is_empty() {
+                        String::new()
+                    } else {
+                        format!("{} ", root.name)
+                    };
+                    root.resources.iter().map(move |resource| {
+                        format!(
+                            "{prefix}{}: {} of {} ({}%)",
+                            resource.name, resource.usage, resource.limit, resource.percentage
+                        )
+                    })
+                })
+                .collect(),
+            Ok(None) => vec!["Not loaded yet".to_string()],
+            Err(err) => vec![format!("Error: {err:#}")],
+        };
+
+        let self_keys = self.sql.count("SELECT COUNT(*) FROM keypairs", ()).await?;
+        let peer_keys = self
+            .sql
+            .count(
+                "SELECT COUNT(*) FROM acpeerstates
+                 WHERE public_key IS NOT NULL OR gossip_key IS NOT NULL",
+                (),
+            )
+            .await?;
+
+        let log = self
+            .recent_log
+            .lock()
+            .iter()
+            .map(|(timestamp, record)| format_log_line(*timestamp, record))
+            .collect();
+
+        Ok(Diagnostics {
+            core_version: DC_VERSION_STR.to_string(),
+            timestamp: time(),
+            configured: self.is_configured().await?,
+            provider,
+            connectivity: format!("{:?}", self.get_connectivity().await),
+            connections: self.get_connection_diagnostics().await,
+            folders,
+            quota,
+            self_keys,
+            peer_keys,
+            log,
+        })
+    }
+}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Delta Chat core {}", self.core_version)?;
+        writeln!(f, "Generated: {}", timestamp_to_str(self.timestamp))?;
+        writeln!(f, "Configured: {}", self.configured)?;
+        writeln!(
+            f,
+            "Provider: {}",
+            self.provider.as_deref().unwrap_or("unknown")
+        )?;
+        writeln!(
+            f,
+            "Keys: {} own, {} of contacts",
+            self.self_keys, self.peer_keys
+        )?;
+
+        writeln!(f, "\nConnectivity: {}", self.connectivity)?;
+        for connection in &self.connections {
+            write!(f, "- {}: {}", connection.name, connection.state)?;
+            if let (Some(err), Some(timestamp)) =
+                (&connection.last_error, connection.last_error_timestamp)
+            {
+                write!(f, " (last error at {}: {err})", timestamp_to_str(timestamp))?;
+            }
+            writeln!(f)?;
+        }
+
+        writeln!(f, "\nFolders:")?;
+        for (key, value) in &self.folders {
+            writeln!(f, "- {key}: {value}")?;
+        }
+
+        writeln!(f, "\nQuota:")?;
+        for line in &self.quota {
+            writeln!(f, "- {line}")?;
+        }
+
+        writeln!(f, "\nLog:")?;
+        for line in &self.log {
+            writeln!(f, "{line}")?;
+        }
+        Ok(())
+    }
+}
+
+fn format_log_line(timestamp: i64, record: &LogRecord) -> String {
+    format!(
+        "{} {:?} {}:{}: {}",
+        timestamp_to_str(timestamp),
+        record.level,
+        record.file,
+        record.line,
+        sanitize_log_message(&record.message)
+    )
+}
+
+/// Removes email addresses from a log message, keeping the domain.
+fn sanitize_log_message(message: &str) -> String {
+    static EMAIL_ADDRESS: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"[\w.+-]+@([\w-]+(\.[\w-]+)+)").unwrap());
+    EMAIL_ADDRESS.replace_all(message, "***@$1").into_owned()
+}
+
+/// Sanitizes a connection error for the report
+/// by removing email addresses and truncating it.
+pub(crate) fn sanitize_connection_error(err: &str) -> String {
+    let err = sanitize_log_message(err);
+    truncate(&err, MAX_CONNECTION_ERROR_CHARS).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestContext;
+
+    #[test]
+    fn test_sanitize_log_message() {
+        assert_eq!(
+            sanitize_log_message("Sending to <alice@example.org> and bob.smith@mail.example.net."),
+            "Sending to <***@example.org> and ***@mail.example.net."
+        );
+        assert_eq!(sanitize_log_message("IMAP IDLE."), "IMAP IDLE.");
+    }
+
+    #[test]
+    fn test_sanitize_connection_error() {
+        assert_eq!(
+            sanitize_connection_error("Login failed for alice@example.org"),
+            "Login failed for ***@example.org"
+        );
+        let err = "NO ".repeat(1000);
+        assert!(sanitize_connection_error(&err).chars().count() < 400);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_generate_diagnostics() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        warn!(t, "Cannot reach alice@example.org.");
+
+        let diagnostics = t.generate_diagnostics().await?;
+        assert!(diagnostics.configured);
+        assert_eq!(diagnostics.self_keys, 1);
+        assert_eq!(diagnostics.connectivity, "NotConnected");
+        assert!(diagnostics.connections.is_empty());
+        assert!(diagnostics.folders.contains_key("configured_inbox_folder"));
+        let log_line = diagnostics
+            .log
+            .iter()
+            .find(|line| line.contains("Cannot reach"))
+            .unwrap();
+        assert!(log_line.ends_with("Cannot reach ***@example.org."));
+
+        let text = diagnostics.to_string();
+        assert!(text.contains("Connectivity: NotConnected"));
+        assert!(!text.contains("alice@example.org"));
+        let json = serde_json::to_value(&diagnostics)?;
+        assert_eq!(json["self_keys"], 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,493 @@
+//! # DKIM signing of outgoing messages.
+//!
+//! Self-hosted mail servers do not always sign outgoing messages with DKIM,
+//! so other servers are more likely to reject them or sort them into spam.
+//! If [`Config::DkimSelector`] and [`Config::DkimPrivateKey`] are set,
+//! outgoing messages are signed with RSA-SHA256 as described in [RFC 6376]
+//! using relaxed canonicalization for both the header and the body.
+//!
+//! The signature is computed over the final message,
+//! i.e. after encryption, exactly as it is submitted over SMTP.
+//!
+//! [RFC 6376]: https://www.rfc-editor.org/rfc/rfc6376
+
+use anyhow::{bail, ensure, Context as _, Result};
+use base64::Engine as _;
+use deltachat_contact_tools::EmailAddress;
+use rsa::pkcs1::DecodeRsaPrivateKey;
+use rsa::pkcs1v15::{Signature, SigningKey, VerifyingKey};
+use rsa::pkcs8::{DecodePrivateKey, DecodePublicKey, EncodePublicKey};
+use rsa::signature::{SignatureEncoding, Signer, Verifier};
+use rsa::{RsaPrivateKey, RsaPublicKey};
+use sha2::{Digest, Sha256};
+
+use crate::config::Config;
+use crate::context::Context;
+use crate::provider::get_resolver;
+use crate::tools::time;
+
+/// Headers covered by the signature if they are present.
+const SIGNED_HEADERS: &[&str] = &[
+    "from",
+    "to",
+    "cc",
+    "reply-to",
+    "subject",
+    "date",
+    "message-id",
+    "in-reply-to",
+    "references",
+    "mime-version",
+    "content-type",
+    "autocrypt",
+    "chat-version",
+];
+
+const SIGNATURE_HEADER: &str = "DKIM-Signature";
+
+/// Parses a PEM-encoded RSA private key in PKCS#8 or PKCS#1 format.
+pub(crate) fn parse_private_key(pem: &str) -> Result<RsaPrivateKey> {
+    let pem = pem.trim();
+    RsaPrivateKey::from_pkcs8_pem(pem)
+        .or_else(|_| RsaPrivateKey::from_pkcs1_pem(pem))
+        .context("DKIM private key must be a PEM-encoded RSA key")
+}
+
+/// Signs outgoing messages with the configured key.
+pub(crate) struct DkimSigner {
+    domain: String,
+    selector: String,
+    key: RsaPrivateKey,
+}
+
+impl DkimSigner {
+    /// Loads the signer for messages sent from `from_addr`.
+    ///
+    /// Returns `None` if DKIM signing is not configured.
+    pub(crate) async fn load(context: &Context, from_addr: &str) -> Result<Option<Self>> {
+        let Some(selector) = context.get_config(Config::DkimSelector).await? else {
+            return Ok(None);
+        };
+        let Some(key) = context.get_config(Config::DkimPrivateKey).await? else {
+            return Ok(None);
+        };
+        let domain = EmailAddress::new(from_addr)?.domain;
+        Ok(Some(Self {
+            domain,
+            selector,
+            key: parse_private_key(&key)?,
+        }))
+    }
+
+    /// Returns the message with a `DKIM-Signature` header prepended.
+    pub(crate) fn sign(&self, message: &str, timestamp: i64) -> Result<String> {
+        let (headers, body) = split_message(message);
+        let headers = parse_headers(headers);
+
+        let mut signed_names = Vec::new();
+        let mut data = String::new();
+        for name in SIGNED_HEADERS {
+            // Multiple instances of a header are selected from the bottom up.
+            for (_, value) in headers
+                .iter()
+                .rev()
+                .filter(|(header_name, _)| header_name.eq_ignore_ascii_case(name))
+            {
+                signed_names.push(*name);
+                data += &canonicalize_header(name, value);
+            }
+        }
+        ensure!(
+            signed_names.contains(&"from"),
+            "Cannot DKIM-sign a message without From header"
+        );
+
+        let body_hash = base64::engine::general_purpose::STANDARD
+            .encode(Sha256::digest(canonicalize_body(body)));
+        let value = format!(
+            "v=1; a=rsa-sha256; c=relaxed/relaxed; d={}; s={}; t={timestamp}; h={}; bh={body_hash}; b=",
+            self.domain,
+            self.selector,
+            signed_names.join(":")
+        );
+        data += canonicalize_header(SIGNATURE_HEADER, &value).trim_end_matches("\r\n");
+
+        let signing_key = SigningKey::<Sha256>::new(self.key.clone());
+        let signature = base64::engine::general_purpose::STANDARD
+            .encode(signing_key.sign(data.as_bytes()).to_bytes());
+
+        let newline = if message.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        };
+        let folded_value = value.replace("; ", &format!(";{newline} "));
+        let folded_signature = signature
+            .as_bytes()
+            .chunks(72)
+            .map(|chunk| std::str::from_utf8(chunk).unwrap_or_default())
+            .collect::<Vec<_>>()
+            .join(&format!("{newline} "));
+        Ok(format!(
+            "{SIGNATURE_HEADER}: {folded_value}{newline} {folded_signature}{newline}{message}"
+        ))
+    }
+
+    /// Returns the TXT record to publish at `<selector>._domainkey.<domain>`.
+    pub(crate) fn dns_record(&self) -> Result<String> {
+        let public_key = RsaPublicKey::from(&self.key).to_public_key_der()?;
+        Ok(format!(
+            "v=DKIM1; k=rsa; p={}",
+            base64::engine::general_purpose::STANDARD.encode(public_key.as_bytes())
+        ))
+    }
+
+    /// Returns the DNS name of the public key record.
+    fn dns_name(&self) -> String {
+        format!("{}._domainkey.{}", self.selector, self.domain)
+    }
+}
+
+/// Verifies the topmost `DKIM-Signature` of a message with the given public key.
+pub(crate) fn verify(message: &str, public_key: &RsaPublicKey) -> Result<()> {
+    let (headers, body) = split_message(message);
+    let headers = parse_headers(headers);
+    let Some((_, signature_value)) = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(SIGNATURE_HEADER))
+    else {
+        bail!("Message has no DKIM signature");
+    };
+    let tags = parse_tags(signature_value);
+    let tag = |name: &str| {
+        tags.iter()
+            .find(|(tag_name, _)| *tag_name == name)
+            .map(|(_, value)| value.as_str())
+            .with_context(|| format!("DKIM signature has no {name}= tag"))
+    };
+    ensure!(tag("a")? == "rsa-sha256", "Unsupported DKIM algorithm");
+    ensure!(
+        tag("c")? == "relaxed/relaxed",
+        "Unsupported DKIM canonicalization"
+    );
+
+    let body_hash =
+        base64::engine::general_purpose::STANDARD.encode(Sha256::digest(canonicalize_body(body)));
+    ensure!(tag("bh")? == body_hash, "DKIM body hash does not match");
+
+    let mut data = String::new();
+    let mut used = vec![false; headers.len()];
+    for name in tag("h")?.split(':') {
+        let name = name.trim();
+        // Each occurrence of a name in h= selects the next instance from the bottom up.
+        if let Some((i, (_, value))) = headers
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(i, (header_name, _))| header_name.eq_ignore_ascii_case(name) && !used[*i])
+        {
+            used[i] = true;
+            data += &canonicalize_header(name, value);
+        }
+    }
+    let unsigned_value = remove_signature_tag(signature_value);
+    data += canonicalize_header(SIGNATURE_HEADER, &unsigned_value).trim_end_matches("\r\n");
+
+    let signature = base64::engine::general_purpose::STANDARD.decode(tag("b")?)?;
+    let signature = Signature::try_from(signature.as_slice())?;
+    VerifyingKey::<Sha256>::new(public_key.clone())
+        .verify(data.as_bytes(), &signature)
+        .context("DKIM signature is invalid")?;
+    Ok(())
+}
+
+impl Context {
+    /// Returns the DNS TXT record to publish for the configured DKIM key
+    /// or `None` if DKIM signing is not configured.
+    pub async fn get_dkim_dns_record(&self) -> Result<Option<String>> {
+        let addr = self.get_primary_self_addr().await?;
+        let Some(signer) = DkimSigner::load(self, &addr).await? else {
+            return Ok(None);
+        };
+        Ok(Some(format!(
+            "{} TXT {}",
+            signer.dns_name(),
+            signer.dns_record()?
+        )))
+    }
+
+    /// Checks that outgoing messages are signed correctly.
+    ///
+    /// Signs a test message with the configured DKIM key
+    /// and verifies it against the public key published in DNS.
+    /// Returns an error describing the problem if DKIM signing is not configured,
+    /// the public key is not published or does not match the private key.
+    pub async fn dkim_self_test(&self) -> Result<()> {
+        let addr = self.get_primary_self_addr().await?;
+        let Some(signer) = DkimSigner::load(self, &addr).await? else {
+            bail!("DKIM signing is not configured");
+        };
+
+        let message = format!(
+            "From: <{addr}>\r\nTo: <{addr}>\r\nSubject: DKIM self-test\r\n\r\nDKIM self-test.\r\n"
+        );
+        let signed = signer.sign(&message, time())?;
+
+        let dns_name = signer.dns_name();
+        let resolver = get_resolver()?;
+        let txt_records = resolver
+            .txt_lookup(format!("{dns_name}."))
+            .await
+            .with_context(|| format!("Cannot look up DKIM record at {dns_name}"))?;
+        let mut published_key = None;
+        for txt in txt_records.iter() {
+            let record: String = txt
+                .txt_data()
+                .iter()
+                .map(|chunk| String::from_utf8_lossy(chunk))
+                .collect();
+            if let Some((_, p)) = parse_tags(&record).into_iter().find(|(tag, _)| *tag == "p") {
+                published_key = Some(p);
+            }
+        }
+        let published_key =
+            published_key.with_context(|| format!("No DKIM public key found at {dns_name}"))?;
+        let public_key = RsaPublicKey::from_public_key_der(
+            &base64::engine::general_purpose::STANDARD.decode(published_key)?,
+        )
+        .with_context(|| format!("Invalid DKIM public key at {dns_name}"))?;
+        ensure!(
+            public_key == RsaPublicKey::from(&signer.key),
+            "DKIM public key at {dns_name} does not match the configured private key"
+        );
+        verify(&signed, &public_key)
+    }
+}
+
+/// Splits the message into the header and the body.
+fn split_message(message: &str) -> (&str, &str) {
+    if let Some(i) = message.find("\r\n\r\n") {
+        (&message[..i + 2], &message[i + 4..])
+    } else if let Some(i) = message.find("\n\n") {
+        (&message[..i + 1], &message[i + 2..])
+    } else {
+        (message, "")
+    }
+}
+
+/// Parses the header into names and unfolded values.
+fn parse_headers(headers: &str) -> Vec<(&str, String)> {
+    let mut res: Vec<(&str, String)> = Vec::new();
+    for line in headers.lines() {
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = res.last_mut() {
+                value.push_str(line);
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            res.push((name.trim_end(), value.to_string()));
+        }
+    }
+    res
+}
+
+/// Applies "relaxed" header canonicalization, see RFC 6376 section 3.4.2.
+fn canonicalize_header(name: &str, value: &str) -> String {
+    let value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+    format!("{}:{value}\r\n", name.to_ascii_lowercase())
+}
+
+/// Applies "relaxed" body canonicalization, see RFC 6376 section 3.4.4.
+fn canonicalize_body(body: &str) -> String {
+    let mut res = String::new();
+    let mut empty_lines = 0;
+    for line in body.lines() {
+        let mut canonical_line = String::new();
+        let mut whitespace = false;
+        for c in line.chars() {
+            if c == ' ' || c == '\t' {
+                whitespace = true;
+            } else {
+                if whitespace {
+                    canonical_line.push(' ');
+                    whitespace = false;
+                }
+                canonical_line.push(c);
+            }
+        }
+
+        // Empty lines at the end of the body are ignored.
+        if canonical_line.is_empty() {
+            empty_lines += 1;
+        } else {
+            res += &"\r\n".repeat(empty_lines);
+            empty_lines = 0;
+            res += &canonical_line;
+            res += "\r\n";
+        }
+    }
+    res
+}
+
+/// Parses `tag=value` list of the DKIM signature or DNS record.
+fn parse_tags(value: &str) -> Vec<(&str, String)> {
+    value
+        .split(';')
+        .filter_map(|tag| tag.split_once('='))
+        .map(|(name, value)| (name.trim(), value.split_whitespace().collect()))
+        .collect()
+}
+
+/// Removes the value of the `b=` tag, keeping the rest of the signature header as is.
+fn remove_signature_tag(value: &str) -> String {
+    value
+        .split(';')
+        .map(|tag| match tag.split_once('=') {
+            Some((name, _)) if name.trim() == "b" => format!("{name}="),
+            _ => tag.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+#[cfg(test)]
+mod tests {
+    use rsa::pkcs8::{EncodePrivateKey, LineEnding};
+
+    use super::*;
+    use crate::test_utils::TestContext;
+
+    fn generate_key() -> RsaPrivateKey {
+        RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap()
+    }
+
+    #[test]
+    fn test_canonicalization() {
+        // Example from RFC 6376 section 3.4.5.
+        let (headers, body) =
+            split_message("A: X\r\nB : Y\t\r\n\tZ  \r\n\r\n C \r\nD \t E\r\n\r\n\r\n");
+        let canonical_headers: String = parse_headers(headers)
+            .iter()
+            .map(|(name, value)| canonicalize_header(name, value))
+            .collect();
+        assert_eq!(canonical_headers, "a:X\r\nb:Y Z\r\n");
+        assert_eq!(canonicalize_body(body), " C\r\nD E\r\n");
+        assert_eq!(canonicalize_body("\r\n\r\n"), "");
+    }
+
+    #[test]
+    fn test_sign_and_verify() -> Result<()> {
+        let signer = DkimSigner {
+            domain: "example.org".to_string(),
+            selector: "dc".to_string(),
+            key: generate_key(),
+        };
+        let public_key = RsaPublicKey::from(&signer.key);
+        let message = "From: <alice@example.org>\r\n\
+                       To: <bob@example.net>\r\n\
+                       Subject: Hello\r\n\
+                       \r\n\
+                       Hello  Bob!\r\n";
+        let signed = signer.sign(message, 1700000000)?;
+        assert!(signed.starts_with("DKIM-Signature: v=1;\r\n a=rsa-sha256;"));
+        assert!(signed.ends_with(message));
+        assert!(signed.contains("h=from:to:subject;"));
+        verify(&signed, &public_key)?;
+
+        // Whitespace changes are allowed by relaxed canonicalization.
+        verify(&signed.replace("Hello  Bob!", "Hello Bob! "), &public_key)?;
+
+        assert!(verify(&signed.replace("Hello  Bob!", "Hello Eve!"), &public_key).is_err());
+        assert!(verify(
+            &signed.replace("Subject: Hello", "Subject: Hi"),
+            &public_key
+        )
+        .is_err());
+        assert!(verify(&signed, &RsaPublicKey::from(&generate_key())).is_err());
+        assert!(verify(message, &public_key).is_err());
+        Ok(())
+    }
+
+    /// Signs the example message of RFC 6376 Appendix A.
+    ///
+    /// The body hash is the one published in the RFC.
+    /// The RFC does not publish its private key, so the signature was computed
+    /// with `openssl dgst -sha256 -sign` over the canonicalized headers
+    /// for a test key instead.
+    #[test]
+    fn test_sign_rfc6376_example() -> Result<()> {
+        let signer = DkimSigner {
+            domain: "football.example.com".to_string(),
+            selector: "brisbane".to_string(),
+            key: parse_private_key(include_str!("../test-data/key/dkim-private.pem"))?,
+        };
+        // The body contains double space
+        // which is reduced to a single space by relaxed canonicalization.
+        let message = "From: Joe SixPack <joe@football.example.com>\r\n\
+                       To: Suzie Q <suzie@shopping.example.net>\r\n\
+                       Subject: Is dinner ready?\r\n\
+                       Date: Fri, 11 Jul 2003 21:00:37 -0700 (PDT)\r\n\
+                       Message-ID: <20030712040037.46341.5F8J@football.example.com>\r\n\
+                       \r\n\
+                       Hi.\r\n\
+                       \r\n\
+                       We lost the game.  Are you hungry yet?\r\n\
+                       \r\n\
+                       Joe.\r\n";
+        let signed = signer.sign(message, 1528637909)?;
+        verify(&signed, &RsaPublicKey::from(&signer.key))?;
+
+        let (headers, _) = split_message(&signed);
+        let headers = parse_headers(headers);
+        let (name, value) = &headers[0];
+        assert_eq!(*name, SIGNATURE_HEADER);
+        let tags = parse_tags(value);
+        let tag = |name: &str| {
+            tags.iter()
+                .find(|(tag_name, _)| *tag_name == name)
+                .map(|(_, value)| value.as_str())
+                .unwrap()
+        };
+        assert_eq!(tag("d"), "football.example.com");
+        assert_eq!(tag("s"), "brisbane");
+        assert_eq!(tag("t"), "1528637909");
+        assert_eq!(tag("h"), "from:to:subject:date:message-id");
+        assert_eq!(tag("bh"), "2jUSOH9NhtVGCQWNr9BrIAPreKQjO6Sn7XIkfJVOzv8=");
+        assert_eq!(
+            tag("b"),
+            "Zzr7h7Uwhr4k5YiHmNB3ijHxUEXIhdpnaY9LS3TU6z4BYUnT+SYF+5SURHsIQMtap7A8pEaaS5njRJH0yXQsJMEkeHR1plc3VuBa7P1CpaG+Sx9zpEt6FxCaUcgV4MOjTHma9ZwX3GBS7Ow6ZGkJ8Ig1NR3JDnckd3LQygExUNI="
+        );
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_sign_outgoing_message() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let key = generate_key();
+
+        alice.set_config(Config::DkimSelector, Some("dc")).await?;
+        assert!(alice
+            .set_config(Config::DkimPrivateKey, Some("not a key"))
+            .await
+            .is_err());
+        alice
+            .set_config(
+                Config::DkimPrivateKey,
+                Some(&key.to_pkcs8_pem(LineEnding::LF)?),
+            )
+            .await?;
+        let record = alice.get_dkim_dns_record().await?.unwrap();
+        assert!(record.starts_with("dc._domainkey.example.org TXT v=DKIM1; k=rsa; p="));
+
+        let chat = alice
+            .create_chat_with_contact("Bob", "bob@example.net")
+            .await;
+        let sent = alice.send_text(chat.id, "Hello!").await;
+        assert!(sent.payload().starts_with("DKIM-Signature: "));
+        verify(sent.payload(), &RsaPublicKey::from(&key))?;
+
+        alice.set_config(Config::DkimSelector, None).await?;
+        let sent = alice.send_text(chat.id, "Unsigned").await;
+        assert!(!sent.payload().contains("DKIM-Signature"));
+        Ok(())
+    }
+}
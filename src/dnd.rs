@@ -0,0 +1,280 @@
+//! # Do-not-disturb schedule.
+//!
+//! The schedule is stored in [`Config::DndSchedule`] as comma-separated time ranges
+//! such as `mon 22:00-07:00,sat 00:00-24:00` and synchronized to other devices.
+//! Ranges ending not later than they start end on the next day.
+//! Times are local times according to [`Config::Timezone`] or the system timezone,
+//! taking daylight saving time changes into account.
+//!
+//! While the schedule is active, new messages emit [`crate::EventType::MsgsChanged`]
+//! instead of [`crate::EventType::IncomingMsg`],
+//! so that UIs show no notifications but still update chats and counters.
+
+use anyhow::{bail, ensure, Context as _, Result};
+use chrono::{Datelike, NaiveDateTime, Timelike, Weekday};
+
+use crate::chat::DayCalendar;
+use crate::config::Config;
+use crate::context::Context;
+use crate::tools::time;
+
+/// Number of minutes in a day, also used for the end time `24:00`.
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+/// Time range of the do-not-disturb schedule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DndRange {
+    /// Day of the week the range starts on.
+    pub weekday: Weekday,
+
+    /// Start time in minutes after midnight.
+    pub start: u32,
+
+    /// End time in minutes after midnight, up to `1440` for the end of the day.
+    /// If not later than `start`, the range ends on the next day.
+    pub end: u32,
+}
+
+impl DndRange {
+    /// Returns true if the range contains the given local date and time.
+    fn contains(&self, datetime: NaiveDateTime) -> bool {
+        let weekday = datetime.weekday();
+        let minute = datetime.hour() * 60 + datetime.minute();
+        if self.start < self.end {
+            weekday == self.weekday && self.start <= minute && minute < self.end
+        } else {
+            (weekday == self.weekday && self.start <= minute)
+                || (weekday == self.weekday.succ() && minute < self.end)
+        }
+    }
+}
+
+/// Parses time such as `07:30` into minutes after midnight.
+fn parse_time(s: &str) -> Result<u32> {
+    let (hours, minutes) = s.split_once(':').context("Missing colon")?;
+    let hours: u32 = hours.parse()?;
+    let minutes: u32 = minutes.parse()?;
+    ensure!(minutes < 60, "Invalid minutes");
+    let time = hours * 60 + minutes;
+    ensure!(time <= MINUTES_PER_DAY, "Invalid hours");
+    Ok(time)
+}
+
+fn format_time(time: u32) -> String {
+    format!("{:02}:{:02}", time / 60, time % 60)
+}
+
+/// Parses a do-not-disturb schedule such as `mon 22:00-07:00,tue 22:00-07:00`.
+pub fn parse_schedule(s: &str) -> Result<Vec<DndRange>> {
+    let mut ranges = Vec::new();
+    for item in s.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+        let parse_range = || -> Result<DndRange> {
+            let (weekday, times) = item.split_once(' ').context("Missing time range")?;
+            let Ok(weekday) = weekday.parse::<Weekday>() else {
+                bail!("Invalid weekday");
+            };
+            let (start, end) = times.trim().split_once('-').context("Missing hyphen")?;
+            let start = parse_time(start)?;
+            let end = parse_time(end)?;
+            ensure!(start < MINUTES_PER_DAY, "Range must not start at 24:00");
+            ensure!(start != end, "Range must not be empty");
+            Ok(DndRange {
+                weekday,
+                start,
+                end,
+            })
+        };
+        ranges
+            .push(parse_range().with_context(|| format!("Invalid do-not-disturb range {item:?}"))?);
+    }
+    Ok(ranges)
+}
+
+/// Formats a do-not-disturb schedule for [`Config::DndSchedule`].
+pub fn format_schedule(ranges: &[DndRange]) -> String {
+    ranges
+        .iter()
+        .map(|range| {
+            format!(
+                "{} {}-{}",
+                range.weekday.to_string().to_lowercase(),
+                format_time(range.start),
+                format_time(range.end)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+impl Context {
+    /// Returns the do-not-disturb schedule.
+    pub async fn get_dnd_schedule(&self) -> Result<Vec<DndRange>> {
+        let schedule = self.get_config(Config::DndSchedule).await?;
+        parse_schedule(schedule.as_deref().unwrap_or_default())
+    }
+
+    /// Sets the do-not-disturb schedule, an empty schedule disables it.
+    /// The schedule is synchronized to other devices.
+    pub async fn set_dnd_schedule(&self, ranges: &[DndRange]) -> Result<()> {
+        let schedule = format_schedule(ranges);
+        self.set_config(
+            Config::DndSchedule,
+            Some(schedule.as_str()).filter(|schedule| !schedule.is_empty()),
+        )
+        .await
+    }
+
+    /// Returns true if the do-not-disturb schedule is active at the moment,
+    /// i.e. no notifications should be shown for new messages.
+    pub async fn is_dnd_active(&self) -> Result<bool> {
+        let ranges = self.get_dnd_schedule().await?;
+        if ranges.is_empty() {
+            return Ok(false);
+        }
+        let calendar = DayCalendar::load(self).await?;
+        let Some(datetime) = calendar.local_datetime(time()) else {
+            return Ok(false);
+        };
+        Ok(ranges.iter().any(|range| range.contains(datetime)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::events::EventType;
+    use crate::test_utils::TestContextManager;
+
+    fn datetime(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        // 2024-01-01 is a Monday.
+        NaiveDate::from_ymd_opt(2024, 1, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_schedule() -> Result<()> {
+        let ranges = parse_schedule("mon 22:00-07:30, Sat 00:00-24:00")?;
+        assert_eq!(
+            ranges,
+            vec![
+                DndRange {
+                    weekday: Weekday::Mon,
+                    start: 22 * 60,
+                    end: 7 * 60 + 30,
+                },
+                DndRange {
+                    weekday: Weekday::Sat,
+                    start: 0,
+                    end: MINUTES_PER_DAY,
+                },
+            ]
+        );
+        assert_eq!(format_schedule(&ranges), "mon 22:00-07:30,sat 00:00-24:00");
+        assert!(parse_schedule("")?.is_empty());
+
+        for invalid in [
+            "mon",
+            "foo 10:00-11:00",
+            "mon 10:00",
+            "mon 10:00-10:00",
+            "mon 24:00-01:00",
+            "mon 10:60-11:00",
+            "mon 25:00-01:00",
+        ] {
+            assert!(parse_schedule(invalid).is_err(), "{invalid}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_dnd_range_contains() -> Result<()> {
+        let ranges = parse_schedule("mon 22:00-07:00,wed 12:00-13:00,sun 23:00-24:00")?;
+        let contains = |datetime| ranges.iter().any(|range| range.contains(datetime));
+        assert!(contains(datetime(1, 22, 0)));
+        assert!(contains(datetime(2, 6, 59)));
+        assert!(!contains(datetime(2, 7, 0)));
+        assert!(!contains(datetime(1, 21, 59)));
+        assert!(!contains(datetime(1, 6, 0)));
+        assert!(contains(datetime(3, 12, 30)));
+        assert!(!contains(datetime(3, 13, 0)));
+        assert!(contains(datetime(7, 23, 59)));
+        assert!(!contains(datetime(8, 0, 0)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_dnd_timezone_dst() -> Result<()> {
+        let ranges = parse_schedule("mon 22:00-07:00")?;
+        let calendar = DayCalendar::parse("Europe/Berlin")?;
+        let contains = |timestamp| {
+            let datetime = calendar.local_datetime(timestamp).unwrap();
+            ranges.iter().any(|range| range.contains(datetime))
+        };
+        // 2024-01-01 21:30 UTC is 22:30 CET.
+        assert!(contains(1704144600));
+        // 2024-07-01 21:30 UTC is 23:30 CEST.
+        assert!(contains(1719869400));
+        // 2024-07-02 05:30 UTC is 07:30 CEST.
+        assert!(!contains(1719898200));
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_dnd_suppresses_incoming_msg() -> Result<()> {
+        let mut tcm = TestContextManager::new();
+        let alice = &tcm.alice().await;
+        let bob = &tcm.bob().await;
+        assert!(!bob.is_dnd_active().await?);
+
+        let whole_week: Vec<DndRange> = [
+            Weekday::Mon,
+            Weekday::Tue,
+            Weekday::Wed,
+            Weekday::Thu,
+            Weekday::Fri,
+            Weekday::Sat,
+            Weekday::Sun,
+        ]
+        .into_iter()
+        .map(|weekday| DndRange {
+            weekday,
+            start: 0,
+            end: MINUTES_PER_DAY,
+        })
+        .collect();
+        bob.set_dnd_schedule(&whole_week).await?;
+        assert_eq!(bob.get_dnd_schedule().await?, whole_week);
+        assert!(bob.is_dnd_active().await?);
+
+        let msg = tcm.send_recv_accept(alice, bob, "Hi").await;
+        let event = bob
+            .evtracker
+            .get_matching(|evt| {
+                matches!(
+                    evt,
+                    EventType::MsgsChanged { msg_id, .. } if *msg_id == msg.id
+                ) || matches!(evt, EventType::IncomingMsg { .. })
+            })
+            .await;
+        assert!(matches!(event, EventType::MsgsChanged { .. }));
+        // The message is still counted as fresh.
+        assert_eq!(msg.chat_id.get_fresh_msg_cnt(bob).await?, 1);
+
+        bob.set_dnd_schedule(&[]).await?;
+        assert!(!bob.is_dnd_active().await?);
+        let msg = tcm.send_recv(alice, bob, "Hi again").await;
+        bob.evtracker
+            .get_matching(|evt| {
+                matches!(
+                    evt,
+                    EventType::IncomingMsg { msg_id, .. } if *msg_id == msg.id
+                )
+            })
+            .await;
+        Ok(())
+    }
+}
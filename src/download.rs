@@ -66,7 +66,13 @@ pub enum DownloadState {
 
 impl Context {
     // Returns validated download limit or `None` for "no limit".
+    //
+    // On metered networks the limit is lowered to `MIN_DOWNLOAD_LIMIT`
+    // so that large messages are only downloaded on request.
     pub(crate) async fn download_limit(&self) -> Result<Option<u32>> {
+        if self.get_network_profile().is_metered() {
+            return Ok(Some(MIN_DOWNLOAD_LIMIT));
+        }
         let download_limit = self.get_config_int(Config::DownloadLimit).await?;
         if download_limit <= 0 {
             Ok(None)
@@ -275,6 +281,7 @@ mod tests {
     use super::*;
     use crate::chat::{get_chat_msgs, send_msg};
     use crate::ephemeral::Timer;
+    use crate::net::NetworkProfile;
     use crate::receive_imf::receive_imf_from_inbox;
     use crate::test_utils::TestContext;
 
@@ -317,6 +324,23 @@ async fn test_download_limit() -> Result<()> {
         Ok(())
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_download_limit_metered() -> Result<()> {
+        let t = TestContext::new_alice().await;
+
+        t.set_network_profile(NetworkProfile::Metered).await;
+        assert_eq!(t.download_limit().await?, Some(MIN_DOWNLOAD_LIMIT));
+
+        t.set_config(Config::DownloadLimit, Some("500000")).await?;
+        t.set_network_profile(NetworkProfile::Roaming).await;
+        assert_eq!(t.download_limit().await?, Some(MIN_DOWNLOAD_LIMIT));
+
+        t.set_network_profile(NetworkProfile::Unmetered).await;
+        assert_eq!(t.download_limit().await?, Some(500000));
+
+        Ok(())
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_update_download_state() -> Result<()> {
         let t = TestContext::new_alice().await;
@@ -0,0 +1,154 @@
+//! # Emoji utilities.
+//!
+//! Helpers to split text into grapheme clusters, detect messages consisting only of emojis
+//! and normalize emoji variants, so that all clients take the same decisions
+//! e.g. about displaying large emojis or deduplicating reactions.
+//!
+//! Emojis are looked up in the emoji data of the Unicode Consortium
+//! as provided by the `emojis` crate,
+//! so only actual emojis including their
+//! modifier, keycap, flag, tag and ZWJ sequences are detected
+//! rather than all pictographic symbols.
+
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Variation selector requesting emoji presentation of the preceding character.
+const EMOJI_PRESENTATION_SELECTOR: char = '\u{FE0F}';
+
+/// Variation selector requesting text presentation of the preceding character.
+const TEXT_PRESENTATION_SELECTOR: char = '\u{FE0E}';
+
+/// Splits text into extended grapheme clusters,
+/// i.e. the units perceived as single characters by the user.
+pub fn split_graphemes(text: &str) -> Vec<&str> {
+    text.graphemes(true).collect()
+}
+
+/// Returns true if the character is a skin tone modifier.
+fn is_skin_tone_modifier(c: char) -> bool {
+    ('\u{1F3FB}'..='\u{1F3FF}').contains(&c)
+}
+
+/// Returns true if the grapheme cluster is a single emoji.
+///
+/// Characters which are displayed as text by default, such as `©` or `❤`,
+/// count as emojis unless they are followed by the text presentation selector.
+/// Digits, `#` and `*` only count as emojis in keycap sequences.
+pub fn is_emoji(grapheme: &str) -> bool {
+    if grapheme.contains(TEXT_PRESENTATION_SELECTOR) {
+        return false;
+    }
+    // The emoji data also contains the unqualified forms without variation selector.
+    emojis::get(grapheme).is_some()
+}
+
+/// Returns the number of emojis if the text consists only of emojis and whitespace.
+///
+/// Returns `None` if the text contains anything else or contains no emoji at all.
+/// UIs may use this to display short emoji-only messages larger.
+pub fn emoji_only_count(text: &str) -> Option<usize> {
+    let mut count = 0;
+    for grapheme in text.graphemes(true) {
+        if grapheme.trim().is_empty() {
+            continue;
+        }
+        if !is_emoji(grapheme) {
+            return None;
+        }
+        count += 1;
+    }
+    Some(count).filter(|&count| count > 0)
+}
+
+/// Returns true if the text consists only of emojis and whitespace.
+pub fn is_emoji_only(text: &str) -> bool {
+    emoji_only_count(text).is_some()
+}
+
+/// Normalizes an emoji by removing skin tone modifiers and variation selectors.
+///
+/// The result is meant for comparison, e.g. `👍🏽` and `👍` both normalize to `👍`,
+/// and may not be displayed properly itself.
+pub fn normalize(emoji: &str) -> String {
+    emoji
+        .chars()
+        .filter(|&c| {
+            !is_skin_tone_modifier(c)
+                && c != EMOJI_PRESENTATION_SELECTOR
+                && c != TEXT_PRESENTATION_SELECTOR
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_graphemes() {
+        assert_eq!(split_graphemes("a👍🏽b"), vec!["a", "👍🏽", "b"]);
+        assert_eq!(split_graphemes("👨‍👩‍👧"), vec!["👨‍👩‍👧"]);
+        assert_eq!(split_graphemes("🇩🇪🇫🇷"), vec!["🇩🇪", "🇫🇷"]);
+        assert!(split_graphemes("").is_empty());
+    }
+
+    #[test]
+    fn test_is_emoji() {
+        for emoji in [
+            "😀",
+            "👍🏽",
+            "❤️",
+            "❤",
+            "👨‍👩‍👧",
+            "🏳️‍🌈",
+            "🇩🇪",
+            "1️⃣",
+            "#️⃣",
+            "©️",
+            "🏴󠁧󠁢󠁳󠁣󠁴󠁿",
+            "🫠",
+        ] {
+            assert!(is_emoji(emoji), "{emoji} is an emoji");
+        }
+        // Pictographic symbols which are no emojis.
+        for text in [
+            "",
+            "a",
+            "1",
+            "#",
+            "ä",
+            "❤︎",
+            "→x",
+            " ",
+            "☇",
+            "⚿",
+            "➘",
+            "\u{1F0A1}",
+        ] {
+            assert!(!is_emoji(text), "{text} is not an emoji");
+        }
+    }
+
+    #[test]
+    fn test_emoji_only_count() {
+        assert_eq!(emoji_only_count("😀"), Some(1));
+        assert_eq!(emoji_only_count(" 👍🏽 ❤️\n🇩🇪 "), Some(3));
+        assert_eq!(emoji_only_count("👨‍👩‍👧👨‍👩‍👧"), Some(2));
+        assert_eq!(emoji_only_count("😀 ok"), None);
+        assert_eq!(emoji_only_count("123"), None);
+        assert_eq!(emoji_only_count("  "), None);
+        assert_eq!(emoji_only_count(""), None);
+        assert!(is_emoji_only("🎉🎉"));
+        assert!(!is_emoji_only("Hi 🎉"));
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("👍🏽"), "👍");
+        assert_eq!(normalize("👍🏿"), normalize("👍"));
+        assert_eq!(normalize("❤️"), normalize("❤"));
+        assert_eq!(normalize("👩🏽‍💻"), "👩‍💻");
+        assert_eq!(normalize(":deltacat:"), ":deltacat:");
+        assert_ne!(normalize("👍"), normalize("👎"));
+    }
+}
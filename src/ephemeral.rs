@@ -58,6 +58,16 @@
 //! `delete_expired_messages` which in turn emits `MsgsChanged` events
 //! when deleting local messages to make UIs reload displayed messages.
 //!
+//! ## Inactivity timer
+//!
+//! Independently of ephemeral timers, each chat has a local
+//! inactivity timer setting in days. If it is set, all messages of
+//! the chat are deleted from the device once the chat was neither
+//! noticed, which usually happens when it is opened, nor archived
+//! for the given number of days. Messages received during this
+//! period are kept for at least the same time. This allows to
+//! automatically clean up old conversations.
+//!
 //! Server deletion happens by updating the `imap` table based on
 //! the database entries which are expired either according to their
 //! ephemeral message timers or global `delete_server_after` setting.
@@ -74,7 +84,7 @@
 use serde::{Deserialize, Serialize};
 use tokio::time::timeout;
 
-use crate::chat::{send_msg, ChatId, ChatIdBlocked};
+use crate::chat::{send_msg, Chat, ChatId, ChatIdBlocked, SyncAction};
 use crate::constants::{DC_CHAT_ID_LAST_SPECIAL, DC_CHAT_ID_TRASH};
 use crate::contact::ContactId;
 use crate::context::Context;
@@ -85,6 +95,7 @@
 use crate::message::{Message, MessageState, MsgId, Viewtype};
 use crate::mimeparser::SystemMessage;
 use crate::stock_str;
+use crate::sync::{self, Sync::*};
 use crate::tools::{duration_to_str, time, SystemTime};
 
 /// Ephemeral timer value.
@@ -235,6 +246,70 @@ pub async fn set_ephemeral_timer(self, context: &Context, timer: Timer) -> Resul
         }
         Ok(())
     }
+
+    /// Returns the inactivity timer of the chat in days, 0 if disabled.
+    pub async fn get_inactivity_timer(self, context: &Context) -> Result<u32> {
+        let days = context
+            .sql
+            .query_get_value("SELECT inactivity_days FROM chats WHERE id=?", (self,))
+            .await?
+            .with_context(|| format!("Chat {self} not found"))?;
+        Ok(days)
+    }
+
+    /// Sets the inactivity timer of the chat in days.
+    ///
+    /// All messages of the chat are deleted from this device
+    /// once the chat was neither noticed nor archived for the given number of days.
+    /// The countdown starts now and restarts every time the chat is noticed or archived.
+    /// Messages on the server are not affected.
+    /// The setting is synchronized to other devices, the countdown is not.
+    ///
+    /// If `days` is 0, disable the inactivity timer.
+    pub async fn set_inactivity_timer(self, context: &Context, days: u32) -> Result<()> {
+        self.set_inactivity_timer_ex(context, Sync, days).await
+    }
+
+    pub(crate) async fn set_inactivity_timer_ex(
+        self,
+        context: &Context,
+        sync: sync::Sync,
+        days: u32,
+    ) -> Result<()> {
+        ensure!(!self.is_special(), "Invalid chat ID");
+        context
+            .sql
+            .execute(
+                "UPDATE chats SET inactivity_days=?, last_activity=? WHERE id=?",
+                (days, time(), self),
+            )
+            .await
+            .with_context(|| format!("Failed to set inactivity timer for {self}"))?;
+        context.emit_event(EventType::ChatModified(self));
+        context.scheduler.interrupt_ephemeral_task().await;
+
+        if sync.into() {
+            let chat = Chat::load_from_db(context, self).await?;
+            chat.sync(context, SyncAction::SetInactivityTimer(days))
+                .await
+                .log_err(context)
+                .ok();
+        }
+        Ok(())
+    }
+
+    /// Restarts the inactivity countdown of the chat
+    /// if the chat has an inactivity timer.
+    pub(crate) async fn restart_inactivity_countdown(self, context: &Context) -> Result<()> {
+        context
+            .sql
+            .execute(
+                "UPDATE chats SET last_activity=? WHERE id=? AND inactivity_days>0",
+                (time(), self),
+            )
+            .await?;
+        Ok(())
+    }
 }
 
 /// Returns a stock message saying that ephemeral timer is changed to `timer` by `from_id`.
@@ -372,7 +447,8 @@ pub(crate) async fn start_chat_ephemeral_timers(context: &Context, chat_id: Chat
 }
 
 /// Selects messages which are expired according to
-/// `delete_device_after` setting or `ephemeral_timestamp` column.
+/// `delete_device_after` setting, `ephemeral_timestamp` column
+/// or the retention policy of the chat.
 ///
 /// For each message a row ID, chat id, viewtype and location ID is returned.
 async fn select_expired_messages(
@@ -447,11 +523,87 @@ async fn select_expired_messages(
         rows.extend(rows_expired);
     }
 
+    // Messages exceeding the local retention policy of their chat.
+    let rows_retention = context
+        .sql
+        .query_map(
+            r#"
+SELECT id, chat_id, type, location_id
+FROM (
+  SELECT m.id, m.chat_id, m.type, m.location_id, m.timestamp, m.timestamp_rcvd,
+    c.retention_msgs, c.retention_days,
+    ROW_NUMBER() OVER (PARTITION BY m.chat_id ORDER BY m.timestamp DESC, m.id DESC) AS n
+  FROM msgs m
+  INNER JOIN chats c ON c.id=m.chat_id
+  WHERE m.chat_id > ?1
+    AND (c.retention_msgs > 0 OR c.retention_days > 0)
+)
+WHERE
+  (retention_msgs > 0 AND n > retention_msgs)
+  OR (retention_days > 0
+      AND timestamp < ?2 - retention_days * 86400
+      AND timestamp_rcvd < ?2 - retention_days * 86400)
+"#,
+            (DC_CHAT_ID_LAST_SPECIAL, now),
+            |row| {
+                let id: MsgId = row.get("id")?;
+                let chat_id: ChatId = row.get("chat_id")?;
+                let viewtype: Viewtype = row.get("type")?;
+                let location_id: u32 = row.get("location_id")?;
+                Ok((id, chat_id, viewtype, location_id))
+            },
+            |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+        )
+        .await?;
+    rows.extend(rows_retention);
+
+    // Messages of chats which were inactive for longer than their inactivity timer.
+    // Outgoing messages which are not delivered yet are kept.
+    let rows_inactive = context
+        .sql
+        .query_map(
+            r#"
+SELECT m.id, m.chat_id, m.type, m.location_id
+FROM msgs m
+INNER JOIN chats c ON c.id=m.chat_id
+WHERE
+  m.chat_id > ?1
+  AND c.inactivity_days > 0
+  AND c.last_activity < ?2 - c.inactivity_days * 86400
+  AND m.timestamp < ?2 - c.inactivity_days * 86400
+  AND m.timestamp_rcvd < ?2 - c.inactivity_days * 86400
+  AND m.state IN (?3, ?4, ?5, ?6, ?7)
+"#,
+            (
+                DC_CHAT_ID_LAST_SPECIAL,
+                now,
+                MessageState::InFresh,
+                MessageState::InNoticed,
+                MessageState::InSeen,
+                MessageState::OutDelivered,
+                MessageState::OutMdnRcvd,
+            ),
+            |row| {
+                let id: MsgId = row.get("id")?;
+                let chat_id: ChatId = row.get("chat_id")?;
+                let viewtype: Viewtype = row.get("type")?;
+                let location_id: u32 = row.get("location_id")?;
+                Ok((id, chat_id, viewtype, location_id))
+            },
+            |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+        )
+        .await?;
+    rows.extend(rows_inactive);
+
+    rows.sort_unstable_by_key(|(id, ..)| *id);
+    rows.dedup_by_key(|(id, ..)| *id);
+
     Ok(rows)
 }
 
 /// Deletes messages which are expired according to
-/// `delete_device_after` setting or `ephemeral_timestamp` column.
+/// `delete_device_after` setting, `ephemeral_timestamp` column,
+/// the retention policy or the inactivity timer of the chat.
 ///
 /// Emits relevant `MsgsChanged` and `WebxdcInstanceDeleted` events
 /// if messages are deleted.
@@ -472,7 +624,7 @@ pub(crate) async fn delete_expired_messages(context: &Context, now: i64) -> Resu
                 for (msg_id, chat_id, viewtype, location_id) in rows {
                     transaction.execute(
                         "UPDATE msgs
-                     SET chat_id=?, txt='', txt_normalized=NULL, subject='', txt_raw='',
+                     SET chat_id=?, txt='', txt_normalized=NULL, txt_ocr=NULL, subject='', txt_raw='',
                          mime_headers='', from_id=0, to_id=0, param=''
                      WHERE id=?",
                         (DC_CHAT_ID_TRASH, msg_id),
@@ -547,10 +699,39 @@ async fn next_delete_device_after_timestamp(context: &Context) -> Result<Option<
     }
 }
 
+/// Calculates the next timestamp when messages will be deleted
+/// due to the inactivity timer of their chat.
+async fn next_inactivity_timestamp(context: &Context) -> Result<Option<i64>> {
+    let timestamp = context
+        .sql
+        .query_get_value(
+            r#"
+            SELECT min(max(c.last_activity, m.timestamp, m.timestamp_rcvd) + c.inactivity_days * 86400)
+            FROM msgs m
+            INNER JOIN chats c ON c.id=m.chat_id
+            WHERE m.chat_id > ?
+              AND c.inactivity_days > 0
+              AND m.state IN (?, ?, ?, ?, ?)
+            HAVING count(*) > 0
+            "#,
+            (
+                DC_CHAT_ID_LAST_SPECIAL,
+                MessageState::InFresh,
+                MessageState::InNoticed,
+                MessageState::InSeen,
+                MessageState::OutDelivered,
+                MessageState::OutMdnRcvd,
+            ),
+        )
+        .await?;
+    Ok(timestamp)
+}
+
 /// Calculates next timestamp when expiration of some message will happen.
 ///
-/// Expiration can happen either because user has set `delete_device_after` setting or because the
-/// message itself has an ephemeral timer.
+/// Expiration can happen either because user has set `delete_device_after` setting,
+/// because the message itself has an ephemeral timer
+/// or because of the inactivity timer of the chat.
 async fn next_expiration_timestamp(context: &Context) -> Option<i64> {
     let ephemeral_timestamp: Option<i64> = match context
         .sql
@@ -585,9 +766,21 @@ async fn next_expiration_timestamp(context: &Context) -> Option<i64> {
             Ok(timestamp) => timestamp,
         };
 
+    let inactivity_timestamp: Option<i64> = match next_inactivity_timestamp(context).await {
+        Err(err) => {
+            warn!(
+                context,
+                "Can't calculate timestamp of the next inactivity expiration: {err:#}."
+            );
+            None
+        }
+        Ok(timestamp) => timestamp,
+    };
+
     ephemeral_timestamp
         .into_iter()
         .chain(delete_device_after_timestamp)
+        .chain(inactivity_timestamp)
         .min()
 }
 
@@ -1517,4 +1710,58 @@ async fn test_archived_ephemeral_timer() -> Result<()> {
 
         Ok(())
     }
+
+    /// Tests that messages are deleted from the device
+    /// once the chat is inactive for longer than its inactivity timer.
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_inactivity_timer() -> Result<()> {
+        let mut tcm = TestContextManager::new();
+        let alice = &tcm.alice().await;
+        let bob = &tcm.bob().await;
+
+        let received = tcm.send_recv_accept(alice, bob, "Hi!").await;
+        let chat_id = received.chat_id;
+        assert_eq!(chat_id.get_inactivity_timer(bob).await?, 0);
+        let now = time();
+        chat_id.set_inactivity_timer(bob, 2).await?;
+        assert_eq!(chat_id.get_inactivity_timer(bob).await?, 2);
+        let expiration = next_expiration_timestamp(bob).await.unwrap();
+        assert!(expiration >= now + 2 * 86400);
+
+        // Drafts and other messages not delivered yet are not deleted.
+        let mut draft = Message::new_text("Draft".to_string());
+        chat_id.set_draft(bob, Some(&mut draft)).await?;
+
+        // Noticing the chat restarts the countdown.
+        SystemTime::shift(Duration::from_secs(86400));
+        marknoticed_chat(bob, chat_id).await?;
+        SystemTime::shift(Duration::from_secs(36 * 3600));
+        delete_expired_messages(bob, time()).await?;
+        assert!(Message::load_from_db_optional(bob, received.id)
+            .await?
+            .is_some());
+
+        // Messages received during the countdown are kept for the whole timer duration.
+        let received2 = tcm.send_recv(alice, bob, "Still there?").await;
+        SystemTime::shift(Duration::from_secs(86400));
+        delete_expired_messages(bob, time()).await?;
+        assert!(Message::load_from_db_optional(bob, received.id)
+            .await?
+            .is_none());
+        assert!(Message::load_from_db_optional(bob, received2.id)
+            .await?
+            .is_some());
+        assert!(chat_id.get_draft(bob).await?.is_some());
+
+        // Disabling the timer stops deleting messages.
+        chat_id.set_inactivity_timer(bob, 0).await?;
+        assert_eq!(next_expiration_timestamp(bob).await, None);
+        SystemTime::shift(Duration::from_secs(10 * 86400));
+        delete_expired_messages(bob, time()).await?;
+        assert!(Message::load_from_db_optional(bob, received2.id)
+            .await?
+            .is_some());
+
+        Ok(())
+    }
 }
@@ -1,8 +1,15 @@
 //! # Events specification.
 
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
 use anyhow::Result;
 use tokio::sync::Mutex;
 
+use crate::chat::ChatId;
+
 pub(crate) mod chatlist_events;
 mod payload;
 
@@ -16,6 +23,9 @@ pub struct Events {
 
     /// Sender side of the event channel.
     sender: async_broadcast::Sender<Event>,
+
+    /// Channels of emitters created with [`Events::get_filtered_emitter`].
+    filtered: Arc<parking_lot::Mutex<Vec<FilteredSender>>>,
 }
 
 impl Default for Events {
@@ -38,19 +48,240 @@ pub fn new() -> Self {
         // Remove oldest event on overflow.
         sender.set_overflow(true);
 
-        Self { _receiver, sender }
+        Self {
+            _receiver,
+            sender,
+            filtered: Default::default(),
+        }
     }
 
     /// Emits an event into event channel.
     ///
     /// If the channel is full, deletes the oldest event first.
     pub fn emit(&self, event: Event) {
+        {
+            let mut filtered = self.filtered.lock();
+            // Remove channels of dropped emitters.
+            filtered.retain(|sender| sender.sender.receiver_count() > 0);
+            for sender in filtered.iter() {
+                sender.emit(&event);
+            }
+        }
         self.sender.try_broadcast(event).ok();
     }
 
     /// Creates an event emitter.
     pub fn get_emitter(&self) -> EventEmitter {
-        EventEmitter(Mutex::new(self.sender.new_receiver()))
+        EventEmitter {
+            receiver: Mutex::new(self.sender.new_receiver()),
+            pending: None,
+        }
+    }
+
+    /// Creates an event emitter which only receives events matching `filter`.
+    ///
+    /// The emitter has its own channel,
+    /// so unlike emitters created with [`Events::get_emitter`]
+    /// it does not take events away from other emitters.
+    /// Events not matching the filter are dropped before they are queued.
+    pub fn get_filtered_emitter(&self, filter: EventFilter) -> EventEmitter {
+        let (mut sender, receiver) = async_broadcast::broadcast(1_000);
+        sender.set_overflow(true);
+        let pending: Arc<parking_lot::Mutex<Vec<Event>>> = Default::default();
+        self.filtered.lock().push(FilteredSender {
+            filter,
+            sender,
+            pending: pending.clone(),
+            windows: Default::default(),
+        });
+        EventEmitter {
+            receiver: Mutex::new(receiver),
+            pending: Some(pending),
+        }
+    }
+}
+
+/// Filter of the events delivered to an emitter
+/// created with [`Events::get_filtered_emitter`].
+#[derive(Clone, Default)]
+pub struct EventFilter {
+    event_types: Option<Arc<dyn Fn(&EventType) -> bool + Send + Sync>>,
+    account_ids: Option<BTreeSet<u32>>,
+    coalesce: bool,
+    coalescing_windows: BTreeMap<String, Duration>,
+}
+
+impl fmt::Debug for EventFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventFilter")
+            .field("event_types", &self.event_types.is_some())
+            .field("account_ids", &self.account_ids)
+            .field("coalesce", &self.coalesce)
+            .field("coalescing_windows", &self.coalescing_windows)
+            .finish()
+    }
+}
+
+impl EventFilter {
+    /// Creates a filter which lets all events pass.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only delivers events for which `predicate` returns true.
+    ///
+    /// [`EventType::EventChannelOverflow`] is always delivered.
+    pub fn with_event_types(
+        mut self,
+        predicate: impl Fn(&EventType) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.event_types = Some(Arc::new(predicate));
+        self
+    }
+
+    /// Only delivers events with the given names as returned by [`EventType::name`].
+    pub fn with_event_names(self, names: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        let names: BTreeSet<String> = names.into_iter().map(Into::into).collect();
+        self.with_event_types(move |typ| names.contains(typ.name()))
+    }
+
+    /// Only delivers events of the given accounts.
+    ///
+    /// Events of the account manager itself, which have the ID 0, are always delivered.
+    pub fn with_accounts(mut self, account_ids: impl IntoIterator<Item = u32>) -> Self {
+        self.account_ids = Some(account_ids.into_iter().collect());
+        self
+    }
+
+    /// Coalesces frequent events such as [`EventType::MsgsChanged`]:
+    /// such an event is not queued if an equal event is already queued
+    /// and has not been received yet.
+    pub fn with_coalescing(mut self) -> Self {
+        self.coalesce = true;
+        self
+    }
+
+    /// Rate-limits events with the given name as returned by [`EventType::name`]
+    /// to one event per chat and `window`.
+    ///
+    /// The first event for a chat is delivered immediately.
+    /// Further events for the same chat arriving within the window
+    /// are delivered when the window ends.
+    /// [`EventType::MsgsChanged`] events for different messages
+    /// are merged into one with `msg_id` 0,
+    /// other events are only merged if they are equal so that no IDs are lost.
+    ///
+    /// Only events which merely notify the UI to reload data can be rate-limited,
+    /// other events such as [`EventType::IncomingMsg`] are always delivered one by one.
+    pub fn with_coalescing_window(mut self, name: impl Into<String>, window: Duration) -> Self {
+        self.coalescing_windows.insert(name.into(), window);
+        self
+    }
+
+    fn matches(&self, event: &Event) -> bool {
+        if let Some(account_ids) = &self.account_ids {
+            if event.id != 0 && !account_ids.contains(&event.id) {
+                return false;
+            }
+        }
+        match &self.event_types {
+            Some(predicate) => {
+                matches!(event.typ, EventType::EventChannelOverflow { .. }) || predicate(&event.typ)
+            }
+            None => true,
+        }
+    }
+}
+
+/// Identifies the events merged within a coalescing window:
+/// account ID, event name and chat ID.
+type WindowKey = (u32, &'static str, Option<ChatId>);
+
+/// Sender side of the channel of a filtered emitter.
+struct FilteredSender {
+    filter: EventFilter,
+    sender: async_broadcast::Sender<Event>,
+
+    /// Coalesced events which are queued but not received yet.
+    pending: Arc<parking_lot::Mutex<Vec<Event>>>,
+
+    /// Open coalescing windows
+    /// with the summary of the events suppressed so far.
+    ///
+    /// Events that cannot be merged, e.g. [`EventType::ReactionsChanged`]
+    /// for different messages, are all kept.
+    windows: Arc<parking_lot::Mutex<HashMap<WindowKey, Vec<Event>>>>,
+}
+
+impl fmt::Debug for FilteredSender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FilteredSender")
+            .field("filter", &self.filter)
+            .finish_non_exhaustive()
+    }
+}
+
+impl FilteredSender {
+    fn emit(&self, event: &Event) {
+        if !self.filter.matches(event) {
+            return;
+        }
+        if self.rate_limit(event) {
+            return;
+        }
+        if self.filter.coalesce && event.typ.is_coalescable() {
+            let mut pending = self.pending.lock();
+            if pending.contains(event) {
+                return;
+            }
+            pending.push(event.clone());
+        }
+        self.sender.try_broadcast(event.clone()).ok();
+    }
+
+    /// Returns true if the event is suppressed because a coalescing window is open.
+    ///
+    /// Otherwise opens a new window if one is configured for the event,
+    /// the event itself is then delivered by the caller.
+    fn rate_limit(&self, event: &Event) -> bool {
+        if !event.typ.is_coalescable() {
+            return false;
+        }
+        let Some(window) = self.filter.coalescing_windows.get(event.typ.name()) else {
+            return false;
+        };
+        // Windows are closed by a timer task, so a runtime is needed.
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return false;
+        };
+
+        let key = (event.id, event.typ.name(), event.typ.chat_id());
+        let mut windows = self.windows.lock();
+        if let Some(summary) = windows.get_mut(&key) {
+            let merged = summary
+                .iter_mut()
+                .find_map(|previous| Some((previous.typ.merge(&event.typ)?, previous)));
+            if let Some((merged, previous)) = merged {
+                previous.typ = merged;
+            } else {
+                summary.push(event.clone());
+            }
+            return true;
+        }
+        windows.insert(key, Vec::new());
+        drop(windows);
+
+        let window = *window;
+        let windows = self.windows.clone();
+        let sender = self.sender.clone();
+        runtime.spawn(async move {
+            tokio::time::sleep(window).await;
+            let summary = windows.lock().remove(&key).unwrap_or_default();
+            for event in summary {
+                sender.try_broadcast(event).ok();
+            }
+        });
+        false
     }
 }
 
@@ -63,21 +294,43 @@ pub fn get_emitter(&self) -> EventEmitter {
 /// [`Context`]: crate::context::Context
 /// [`Context::get_event_emitter`]: crate::context::Context::get_event_emitter
 #[derive(Debug)]
-pub struct EventEmitter(Mutex<async_broadcast::Receiver<Event>>);
+pub struct EventEmitter {
+    receiver: Mutex<async_broadcast::Receiver<Event>>,
+
+    /// Coalesced events which are queued but not received yet,
+    /// shared with the sender if the emitter is filtered.
+    pending: Option<Arc<parking_lot::Mutex<Vec<Event>>>>,
+}
 
 impl EventEmitter {
     /// Async recv of an event. Return `None` if the `Sender` has been dropped.
     ///
     /// [`try_recv`]: Self::try_recv
     pub async fn recv(&self) -> Option<Event> {
-        let mut lock = self.0.lock().await;
-        match lock.recv().await {
-            Err(async_broadcast::RecvError::Overflowed(n)) => Some(Event {
+        let mut lock = self.receiver.lock().await;
+        let event = match lock.recv().await {
+            Err(async_broadcast::RecvError::Overflowed(n)) => Event {
                 id: 0,
                 typ: EventType::EventChannelOverflow { n },
-            }),
-            Err(async_broadcast::RecvError::Closed) => None,
-            Ok(event) => Some(event),
+            },
+            Err(async_broadcast::RecvError::Closed) => return None,
+            Ok(event) => event,
+        };
+        self.received(&event);
+        Some(event)
+    }
+
+    /// Allows coalesced events equal to the received one to be queued again.
+    fn received(&self, event: &Event) {
+        let Some(pending) = &self.pending else {
+            return;
+        };
+        let mut pending = pending.lock();
+        if let EventType::EventChannelOverflow { .. } = event.typ {
+            // Coalesced events may have been dropped from the queue.
+            pending.clear();
+        } else {
+            pending.retain(|pending_event| pending_event != event);
         }
     }
 
@@ -92,20 +345,22 @@ pub fn try_recv(&self) -> Result<Event> {
         // Using `try_lock` instead of `lock`
         // to avoid blocking
         // in case there is a concurrent call to `recv`.
-        let mut lock = self.0.try_lock()?;
-        match lock.try_recv() {
+        let mut lock = self.receiver.try_lock()?;
+        let event = match lock.try_recv() {
             Err(async_broadcast::TryRecvError::Overflowed(n)) => {
                 // Some events have been lost,
                 // but the channel is not closed.
-                Ok(Event {
+                Event {
                     id: 0,
                     typ: EventType::EventChannelOverflow { n },
-                })
+                }
             }
             res @ (Err(async_broadcast::TryRecvError::Empty)
             | Err(async_broadcast::TryRecvError::Closed)
-            | Ok(_)) => Ok(res?),
-        }
+            | Ok(_)) => res?,
+        };
+        self.received(&event);
+        Ok(event)
     }
 }
 
@@ -130,3 +385,132 @@ pub struct Event {
     /// These are documented in `deltachat.h` as the `DC_EVENT_*` constants.
     pub typ: EventType,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contact::ContactId;
+    use crate::message::MsgId;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_filtered_emitter() -> Result<()> {
+        let events = Events::new();
+        let unfiltered = events.get_emitter();
+        let emitter = events.get_filtered_emitter(
+            EventFilter::new()
+                .with_event_names(["MsgsChanged", "AccountsChanged"])
+                .with_accounts([1])
+                .with_coalescing(),
+        );
+        let msgs_changed = Event {
+            id: 1,
+            typ: EventType::MsgsChanged {
+                chat_id: ChatId::new(10),
+                msg_id: MsgId::new(0),
+            },
+        };
+        events.emit(msgs_changed.clone());
+        events.emit(msgs_changed.clone());
+        events.emit(Event {
+            id: 2,
+            typ: msgs_changed.typ.clone(),
+        });
+        events.emit(Event {
+            id: 1,
+            typ: EventType::Info("Info.".to_string()),
+        });
+        events.emit(Event {
+            id: 0,
+            typ: EventType::AccountsChanged,
+        });
+
+        assert_eq!(emitter.try_recv()?, msgs_changed);
+        assert_eq!(emitter.try_recv()?.typ, EventType::AccountsChanged);
+        assert!(emitter.try_recv().is_err());
+
+        // Received events are not coalesced with new ones.
+        events.emit(msgs_changed.clone());
+        assert_eq!(emitter.try_recv()?, msgs_changed);
+
+        // The unfiltered emitter still receives all events.
+        assert_eq!(unfiltered.try_recv()?, msgs_changed);
+        assert_eq!(unfiltered.try_recv()?, msgs_changed);
+
+        // Channels of dropped emitters are removed.
+        drop(emitter);
+        events.emit(msgs_changed);
+        assert!(events.filtered.lock().is_empty());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_coalescing_window() -> Result<()> {
+        let events = Events::new();
+        let emitter = events.get_filtered_emitter(
+            EventFilter::new().with_coalescing_window("MsgsChanged", Duration::from_millis(100)),
+        );
+        let msgs_changed = |chat_id, msg_id| Event {
+            id: 1,
+            typ: EventType::MsgsChanged {
+                chat_id: ChatId::new(chat_id),
+                msg_id: MsgId::new(msg_id),
+            },
+        };
+        for msg_id in 1..=100 {
+            events.emit(msgs_changed(10, msg_id));
+        }
+        events.emit(msgs_changed(11, 1));
+        events.emit(Event {
+            id: 1,
+            typ: EventType::IncomingMsg {
+                chat_id: ChatId::new(10),
+                msg_id: MsgId::new(100),
+            },
+        });
+
+        // The first event of each chat and events which are not rate-limited are delivered immediately.
+        assert_eq!(emitter.try_recv()?, msgs_changed(10, 1));
+        assert_eq!(emitter.try_recv()?, msgs_changed(11, 1));
+        assert_eq!(emitter.try_recv()?.typ.name(), "IncomingMsg");
+        assert!(emitter.try_recv().is_err());
+
+        // Suppressed events are merged into a summary event when the window ends.
+        assert_eq!(emitter.recv().await, Some(msgs_changed(10, 0)));
+        assert!(emitter.try_recv().is_err());
+
+        // After the window, the next event is delivered immediately again.
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        events.emit(msgs_changed(10, 5));
+        assert_eq!(emitter.try_recv()?, msgs_changed(10, 5));
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_coalescing_window_keeps_ids() -> Result<()> {
+        let events = Events::new();
+        let emitter = events.get_filtered_emitter(
+            EventFilter::new()
+                .with_coalescing_window("ReactionsChanged", Duration::from_millis(100)),
+        );
+        let reactions_changed = |msg_id, contact_id| Event {
+            id: 1,
+            typ: EventType::ReactionsChanged {
+                chat_id: ChatId::new(10),
+                msg_id: MsgId::new(msg_id),
+                contact_id: ContactId::new(contact_id),
+            },
+        };
+        events.emit(reactions_changed(1, 20));
+        events.emit(reactions_changed(2, 20));
+        events.emit(reactions_changed(3, 21));
+        events.emit(reactions_changed(2, 20));
+        assert_eq!(emitter.try_recv()?, reactions_changed(1, 20));
+        assert!(emitter.try_recv().is_err());
+
+        // Events for different messages are not merged, duplicates are.
+        assert_eq!(emitter.recv().await, Some(reactions_changed(2, 20)));
+        assert_eq!(emitter.recv().await, Some(reactions_changed(3, 21)));
+        assert!(emitter.try_recv().is_err());
+        Ok(())
+    }
+}
@@ -5,9 +5,10 @@
 
 use crate::chat::ChatId;
 use crate::config::Config;
+use crate::configure::ConfigureTraceEntry;
 use crate::contact::ContactId;
 use crate::ephemeral::Timer as EphemeralTimer;
-use crate::message::MsgId;
+use crate::message::{DeliveryFailure, MsgId};
 use crate::reaction::Reaction;
 use crate::webxdc::StatusUpdateSerial;
 
@@ -97,6 +98,9 @@ pub enum EventType {
 
     /// Reactions for the message changed.
     IncomingReaction {
+        /// ID of the chat containing the message reacted to.
+        chat_id: ChatId,
+
         /// ID of the contact whose reaction set is changed.
         contact_id: ContactId,
 
@@ -137,6 +141,24 @@ pub enum EventType {
         msg_id: MsgId,
     },
 
+    /// An incoming message starts with a command registered by
+    /// `Context::register_bot_command`.
+    ///
+    /// Emitted in addition to [`EventType::IncomingMsg`].
+    BotCommand {
+        /// ID of the chat where the message is assigned.
+        chat_id: ChatId,
+
+        /// ID of the message.
+        msg_id: MsgId,
+
+        /// The command in lowercase, e.g. `/help`.
+        command: String,
+
+        /// Text following the command, with surrounding whitespace removed.
+        args: String,
+    },
+
     /// Downloading a bunch of messages just finished.
     IncomingMsgBunch,
 
@@ -162,6 +184,9 @@ pub enum EventType {
 
         /// ID of the message that could not be sent.
         msg_id: MsgId,
+
+        /// Reason reported by a delivery status notification, if any.
+        reason: Option<DeliveryFailure>,
     },
 
     /// A single message is read by the receiver. State changed from DC_STATE_OUT_DELIVERED to
@@ -204,6 +229,19 @@ pub enum EventType {
     /// is a separate event.
     ChatModified(ChatId),
 
+    /// The key of a member of a protected group changed since the member was verified,
+    /// e.g. because the member reinstalled Delta Chat without a backup.
+    ///
+    /// The member should be verified again, e.g. by scanning their QR code,
+    /// and then added back with `chat::repair_verified_group_member`.
+    VerifiedGroupMemberKeyChanged {
+        /// ID of the protected group.
+        chat_id: ChatId,
+
+        /// ID of the member whose key changed.
+        contact_id: ContactId,
+    },
+
     /// Chat ephemeral timer changed.
     ChatEphemeralTimerModified {
         /// Chat ID.
@@ -236,12 +274,68 @@ pub enum EventType {
         comment: Option<String>,
     },
 
+    /// Configuration started by configure() has failed.
+    ///
+    /// Emitted in addition to [`EventType::ConfigureProgress`] with progress 0.
+    ConfigureFailed {
+        /// Error message.
+        error: String,
+
+        /// Steps tried during the configuration,
+        /// same as returned by `Context::get_last_configure_trace`.
+        trace: Vec<ConfigureTraceEntry>,
+    },
+
     /// Inform about the import/export progress started by imex().
     ///
     /// @param data1 (usize) 0=error, 1-999=progress in permille, 1000=success and done
     /// @param data2 0
     ImexProgress(usize),
 
+    /// Inform about the number of bytes transferred
+    /// while sending or receiving a backup with [`crate::imex::BackupProvider`]
+    /// or [`crate::imex::get_backup`].
+    ///
+    /// If an interrupted transfer is resumed, `transferred` starts at the resumption point.
+    BackupTransferProgress {
+        /// Number of bytes transferred so far.
+        transferred: u64,
+
+        /// Total size of the backup in bytes.
+        total: u64,
+    },
+
+    /// Inform about the progress of fetching messages from the server,
+    /// e.g. fetching existing messages after adding an account with a big mailbox.
+    ///
+    /// See [`crate::context::Context::get_sync_state`] for the state of all folders.
+    SyncProgress {
+        /// Name of the folder messages are fetched from.
+        folder: String,
+
+        /// Number of messages fetched so far.
+        fetched: usize,
+
+        /// Number of messages to fetch.
+        total: usize,
+    },
+
+    /// The first full sync after starting I/O is done.
+    ///
+    /// Emitted once per start of I/O,
+    /// UIs may use it to show that the account is up to date.
+    /// See [`crate::context::Context::get_sync_report`] for the changes per folder.
+    SyncReport {
+        /// Number of new messages.
+        new_msgs: usize,
+
+        /// Number of messages deleted from the server by other clients.
+        expunged: usize,
+
+        /// Number of messages marked as seen by other clients.
+        flags_changed: usize,
+    },
+
     /// A file has been exported. A file has been written by imex().
     /// This event may be sent multiple times by a single call to imex().
     ///
@@ -283,6 +377,25 @@ pub enum EventType {
         progress: usize,
     },
 
+    /// A joiner scanned a one-time group invite QR code
+    /// generated by `get_securejoin_qr_with_approval()`
+    /// and waits for approval of the inviter.
+    ///
+    /// The joiner is added to the group after `approve_join_request()` is called.
+    SecurejoinApprovalRequest {
+        /// ID of the contact that wants to join.
+        contact_id: ContactId,
+
+        /// ID of the group chat the contact wants to join.
+        chat_id: ChatId,
+
+        /// Fingerprint of the joiner's key.
+        fingerprint: String,
+
+        /// Display name of the joiner.
+        display_name: String,
+    },
+
     /// The connectivity to the server changed.
     /// This means that you should refresh the connectivity view
     /// and possibly the connectivtiy HTML; see dc_get_connectivity() and
@@ -326,6 +439,88 @@ pub enum EventType {
         msg_id: MsgId,
     },
 
+    /// A webxdc app requests to send a message on the user's behalf,
+    /// see `Context::request_webxdc_send_message()`.
+    ///
+    /// The UI should show the text to the user
+    /// and call `approve_webxdc_send_request()` or `deny_webxdc_send_request()`.
+    WebxdcSendRequest {
+        /// ID of the webxdc instance.
+        msg_id: MsgId,
+
+        /// ID of the request.
+        request_id: u32,
+
+        /// Text of the message to send.
+        text: String,
+    },
+
+    /// A webxdc app asks to notify the user about an event in the app,
+    /// see `Context::send_webxdc_notification()`.
+    ///
+    /// The UI should show a system notification with the text.
+    WebxdcNotify {
+        /// ID of the webxdc instance.
+        msg_id: MsgId,
+
+        /// Text to notify.
+        text: String,
+    },
+
+    /// An incoming message has a public key of the sender attached
+    /// which differs from the key currently used for the sender.
+    ///
+    /// The UI may show both fingerprints to the user
+    /// and offer importing the key with `key_import::import_key_from_msg()`.
+    IncomingPublicKey {
+        /// ID of the chat the message was received in.
+        chat_id: ChatId,
+
+        /// ID of the message with the key attachment.
+        msg_id: MsgId,
+
+        /// ID of the sender.
+        contact_id: ContactId,
+
+        /// Fingerprint of the attached key, hex-encoded.
+        fingerprint: String,
+
+        /// Fingerprint of the key currently used for the sender, if any.
+        current_fingerprint: Option<String>,
+    },
+
+    /// Translation of a message is requested,
+    /// see `ChatId::set_auto_translate()` and `Context::request_msg_translation()`.
+    ///
+    /// The UI should translate the text with the given provider
+    /// and call `Context::set_msg_translation()` with the result.
+    TranslationRequest {
+        /// ID of the message to translate.
+        msg_id: MsgId,
+
+        /// Language to translate the message to.
+        target_lang: String,
+
+        /// ID of the translation provider, defined by the UI.
+        provider: String,
+
+        /// Text to translate.
+        text: String,
+    },
+
+    /// Text recognition in the image attachment of a message is requested,
+    /// see `Context::request_msg_ocr()`.
+    ///
+    /// The UI should recognize the text in the image file
+    /// and call `Context::set_msg_ocr_text()` with the result.
+    OcrRequest {
+        /// ID of the message with the image attachment.
+        msg_id: MsgId,
+
+        /// Path to the image file.
+        path: String,
+    },
+
     /// Inform that a message containing a webxdc instance has been deleted.
     WebxdcInstanceDeleted {
         /// ID of the deleted message.
@@ -350,7 +545,8 @@ pub enum EventType {
         chat_id: Option<ChatId>,
     },
 
-    /// Inform that the list of accounts has changed (an account removed or added or (not yet implemented) the account order changes)
+    /// Inform that the list of accounts has changed (an account removed or added)
+    /// or that the metadata of an account, such as the sort order, changed.
     ///
     /// This event is only emitted by the account manager
     AccountsChanged,
@@ -374,3 +570,131 @@ pub enum EventType {
         n: u64,
     },
 }
+
+impl EventType {
+    /// Returns the name of the event type, e.g. `"IncomingMsg"`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            EventType::Info(_) => "Info",
+            EventType::SmtpConnected(_) => "SmtpConnected",
+            EventType::ImapConnected(_) => "ImapConnected",
+            EventType::SmtpMessageSent(_) => "SmtpMessageSent",
+            EventType::ImapMessageDeleted(_) => "ImapMessageDeleted",
+            EventType::ImapMessageMoved(_) => "ImapMessageMoved",
+            EventType::ImapInboxIdle => "ImapInboxIdle",
+            EventType::NewBlobFile(_) => "NewBlobFile",
+            EventType::DeletedBlobFile(_) => "DeletedBlobFile",
+            EventType::Warning(_) => "Warning",
+            EventType::Error(_) => "Error",
+            EventType::ErrorSelfNotInGroup(_) => "ErrorSelfNotInGroup",
+            EventType::MsgsChanged { .. } => "MsgsChanged",
+            EventType::ReactionsChanged { .. } => "ReactionsChanged",
+            EventType::IncomingReaction { .. } => "IncomingReaction",
+            EventType::IncomingWebxdcNotify { .. } => "IncomingWebxdcNotify",
+            EventType::IncomingMsg { .. } => "IncomingMsg",
+            EventType::BotCommand { .. } => "BotCommand",
+            EventType::IncomingMsgBunch { .. } => "IncomingMsgBunch",
+            EventType::MsgsNoticed { .. } => "MsgsNoticed",
+            EventType::MsgDelivered { .. } => "MsgDelivered",
+            EventType::MsgFailed { .. } => "MsgFailed",
+            EventType::MsgRead { .. } => "MsgRead",
+            EventType::MsgDeleted { .. } => "MsgDeleted",
+            EventType::ChatModified(_) => "ChatModified",
+            EventType::VerifiedGroupMemberKeyChanged { .. } => "VerifiedGroupMemberKeyChanged",
+            EventType::ChatEphemeralTimerModified { .. } => "ChatEphemeralTimerModified",
+            EventType::ContactsChanged(_) => "ContactsChanged",
+            EventType::LocationChanged(_) => "LocationChanged",
+            EventType::ConfigureProgress { .. } => "ConfigureProgress",
+            EventType::ConfigureFailed { .. } => "ConfigureFailed",
+            EventType::ImexProgress(_) => "ImexProgress",
+            EventType::ImexFileWritten(_) => "ImexFileWritten",
+            EventType::BackupTransferProgress { .. } => "BackupTransferProgress",
+            EventType::SyncProgress { .. } => "SyncProgress",
+            EventType::SyncReport { .. } => "SyncReport",
+            EventType::SecurejoinInviterProgress { .. } => "SecurejoinInviterProgress",
+            EventType::SecurejoinJoinerProgress { .. } => "SecurejoinJoinerProgress",
+            EventType::SecurejoinApprovalRequest { .. } => "SecurejoinApprovalRequest",
+            EventType::ConnectivityChanged => "ConnectivityChanged",
+            EventType::SelfavatarChanged => "SelfavatarChanged",
+            EventType::ConfigSynced { .. } => "ConfigSynced",
+            EventType::WebxdcStatusUpdate { .. } => "WebxdcStatusUpdate",
+            EventType::WebxdcInstanceDeleted { .. } => "WebxdcInstanceDeleted",
+            EventType::WebxdcSendRequest { .. } => "WebxdcSendRequest",
+            EventType::WebxdcNotify { .. } => "WebxdcNotify",
+            EventType::TranslationRequest { .. } => "TranslationRequest",
+            EventType::OcrRequest { .. } => "OcrRequest",
+            EventType::IncomingPublicKey { .. } => "IncomingPublicKey",
+            EventType::WebxdcRealtimeData { .. } => "WebxdcRealtimeData",
+            EventType::WebxdcRealtimeAdvertisementReceived { .. } => {
+                "WebxdcRealtimeAdvertisementReceived"
+            }
+            EventType::AccountsBackgroundFetchDone => "AccountsBackgroundFetchDone",
+            EventType::ChatlistChanged => "ChatlistChanged",
+            EventType::ChatlistItemChanged { .. } => "ChatlistItemChanged",
+            EventType::AccountsChanged => "AccountsChanged",
+            EventType::AccountsItemChanged => "AccountsItemChanged",
+            EventType::EventChannelOverflow { .. } => "EventChannelOverflow",
+            #[cfg(test)]
+            EventType::Test => "Test",
+        }
+    }
+
+    /// Returns true for frequent events which only notify the UI to reload some data.
+    ///
+    /// Equal events of this kind can be coalesced,
+    /// see [`super::EventFilter::with_coalescing`].
+    pub(crate) fn is_coalescable(&self) -> bool {
+        matches!(
+            self,
+            EventType::MsgsChanged { .. }
+                | EventType::ReactionsChanged { .. }
+                | EventType::ChatModified(_)
+                | EventType::ContactsChanged(_)
+                | EventType::ConnectivityChanged
+                | EventType::ChatlistChanged
+                | EventType::ChatlistItemChanged { .. }
+                | EventType::AccountsItemChanged
+        )
+    }
+
+    /// Returns the chat the event is about, used to coalesce events per chat.
+    pub(crate) fn chat_id(&self) -> Option<ChatId> {
+        match self {
+            EventType::MsgsChanged { chat_id, .. }
+            | EventType::ReactionsChanged { chat_id, .. }
+            | EventType::ChatModified(chat_id) => Some(*chat_id),
+            EventType::ChatlistItemChanged { chat_id } => *chat_id,
+            _ => None,
+        }
+    }
+
+    /// Merges a coalescable event with a newer one of the same kind
+    /// into a single event covering both.
+    ///
+    /// Returns `None` if the events cannot be merged without losing IDs,
+    /// then both events must be delivered.
+    pub(crate) fn merge(&self, newer: &EventType) -> Option<EventType> {
+        match (self, newer) {
+            (
+                EventType::MsgsChanged { chat_id, msg_id },
+                EventType::MsgsChanged {
+                    chat_id: newer_chat_id,
+                    msg_id: newer_msg_id,
+                },
+            ) => Some(EventType::MsgsChanged {
+                chat_id: if chat_id == newer_chat_id {
+                    *chat_id
+                } else {
+                    ChatId::new(0)
+                },
+                msg_id: if msg_id == newer_msg_id {
+                    *msg_id
+                } else {
+                    MsgId::new(0)
+                },
+            }),
+            (older, newer) if older == newer => Some(newer.clone()),
+            _ => None,
+        }
+    }
+}
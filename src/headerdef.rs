@@ -42,11 +42,23 @@ pub enum HeaderDef {
 
     /// List-Help header defined in [RFC 2369](https://datatracker.ietf.org/doc/html/rfc2369).
     ListHelp,
+
+    /// List-Subscribe header defined in [RFC 2369](https://datatracker.ietf.org/doc/html/rfc2369).
+    ListSubscribe,
+
+    /// List-Unsubscribe header defined in [RFC 2369](https://datatracker.ietf.org/doc/html/rfc2369).
+    ListUnsubscribe,
+
+    /// List-Unsubscribe-Post header defined in [RFC 8058](https://datatracker.ietf.org/doc/html/rfc8058).
+    ListUnsubscribePost,
     References,
 
     /// In-Reply-To header containing Message-ID of the parent message.
     InReplyTo,
 
+    /// Reply-To header containing the address replies should be sent to.
+    ReplyTo,
+
     /// Used to detect mailing lists if contains "list" value
     /// as described in [RFC 3834](https://tools.ietf.org/html/rfc3834)
     Precedence,
@@ -74,6 +86,38 @@ pub enum HeaderDef {
     /// for members listed in the `Chat-Group-Past-Members` field.
     ChatGroupMemberTimestamps,
 
+    /// Space-separated addresses of the admins of a group.
+    ChatGroupAdmins,
+
+    /// Set on the message promoting a group by its creator,
+    /// recipients creating the group accept the admins only from this message
+    /// or from the message adding them to the group.
+    ChatGroupCreated,
+
+    /// Set if only admins may change the group,
+    /// see [`crate::chat::set_group_moderated`].
+    ChatGroupModerated,
+
+    /// Asks the recipient to attach the profile data such as avatar
+    /// to the next message because the sender's copy is stale.
+    ChatProfileRequest,
+
+    /// Hash of the sender's avatar, sent to large groups instead of the avatar itself,
+    /// see `contact::avatar_hash()`.
+    ChatUserAvatarHash,
+
+    /// Space-separated addresses of group members
+    /// that are asked to attach their avatar to the next message
+    /// because the sender's copy does not match the announced hash.
+    ChatUserAvatarRequest,
+
+    /// Custom emojis used in a reaction, see `reaction::CustomEmoji`.
+    ChatCustomEmojis,
+
+    /// Statement signed with the old key of the sender announcing the new key,
+    /// see `key::rotate_self_key()`.
+    ChatKeyTransition,
+
     /// Duration of the attached media file.
     ChatDuration,
 
@@ -0,0 +1,222 @@
+//! # Key and backup health checks.
+//!
+//! Periodically checks whether the user risks losing access to the profile
+//! and warns with device messages:
+//! - if no backup was made for [`Config::BackupReminderDays`],
+//! - if the key is older than [`Config::KeyAgeWarningYears`],
+//! - if the database is larger than [`Config::DbSizeWarningMb`].
+//!
+//! Setting any of these options to 0 disables the corresponding check.
+//! UIs can show the same information in the settings using [`get_health_status`].
+
+use anyhow::Result;
+use pgp::types::PublicKeyTrait;
+
+use crate::chat::add_device_msg;
+use crate::config::Config;
+use crate::context::Context;
+use crate::key::load_self_public_key;
+use crate::log::LogExt;
+use crate::message::Message;
+use crate::stock_str;
+use crate::tools::{time, timestamp_to_str};
+
+/// Result of the key and backup health checks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthStatus {
+    /// Timestamp of the last backup export, if any.
+    ///
+    /// Setting up a second device exports a backup as well.
+    pub last_backup_timestamp: Option<i64>,
+
+    /// True if the backup reminder is due.
+    pub backup_overdue: bool,
+
+    /// Timestamp of the key creation, if known.
+    pub key_created_timestamp: Option<i64>,
+
+    /// True if the key is older than the configured age.
+    pub key_outdated: bool,
+
+    /// Size of the database file in bytes.
+    pub db_size: u64,
+
+    /// True if the database is larger than the configured size.
+    pub db_size_exceeded: bool,
+}
+
+/// Returns the result of the health checks.
+pub async fn get_health_status(context: &Context) -> Result<HealthStatus> {
+    get_health_status_at(context, time()).await
+}
+
+async fn get_health_status_at(context: &Context, now: i64) -> Result<HealthStatus> {
+    let last_backup_timestamp = context
+        .sql
+        .get_raw_config_int64("backup_time")
+        .await?
+        .filter(|timestamp| *timestamp > 0);
+    let backup_reminder_days = context.get_config_i64(Config::BackupReminderDays).await?;
+    let backup_overdue = if backup_reminder_days > 0 {
+        // Do not remind users who have just set up the profile.
+        let configured_timestamp = context.get_config_i64(Config::ConfiguredTimestamp).await?;
+        let reference = last_backup_timestamp
+            .unwrap_or_default()
+            .max(configured_timestamp);
+        reference > 0 && now > reference.saturating_add(backup_reminder_days * 24 * 60 * 60)
+    } else {
+        false
+    };
+
+    let key_created_timestamp = if context.get_config_i64(Config::KeyId).await? > 0 {
+        let public_key = load_self_public_key(context).await?;
+        Some(public_key.created_at().timestamp())
+    } else {
+        None
+    };
+    let key_age_warning_years = context.get_config_i64(Config::KeyAgeWarningYears).await?;
+    let key_outdated = key_age_warning_years > 0
+        && key_created_timestamp.is_some_and(|created| {
+            now > created.saturating_add(key_age_warning_years * 365 * 24 * 60 * 60)
+        });
+
+    let db_size = tokio::fs::metadata(context.get_dbfile()).await?.len();
+    let db_size_warning_mb = context.get_config_u64(Config::DbSizeWarningMb).await?;
+    let db_size_exceeded = db_size_warning_mb > 0 && db_size > db_size_warning_mb * 1024 * 1024;
+
+    Ok(HealthStatus {
+        last_backup_timestamp,
+        backup_overdue,
+        key_created_timestamp,
+        key_outdated,
+        db_size,
+        db_size_exceeded,
+    })
+}
+
+/// Runs the health checks and adds device messages for failed checks.
+pub(crate) async fn maybe_add_health_warnings(context: &Context) {
+    add_health_warnings(context, time())
+        .await
+        .log_err(context)
+        .ok();
+}
+
+async fn add_health_warnings(context: &Context, now: i64) -> Result<()> {
+    if context.get_config_bool(Config::Bot).await? {
+        return Ok(());
+    }
+    let status = get_health_status_at(context, now).await?;
+    let Some(timestamp) = chrono::DateTime::<chrono::Utc>::from_timestamp(now, 0) else {
+        return Ok(());
+    };
+
+    if status.backup_overdue {
+        let mut msg = Message::new_text(stock_str::backup_reminder_msg_body(context).await);
+        let label = format!("backup-reminder-{}", timestamp.format("%Y-%m")); // repeat every month
+        add_device_msg(context, Some(&label), Some(&mut msg)).await?;
+    }
+
+    if let (true, Some(created)) = (status.key_outdated, status.key_created_timestamp) {
+        let mut msg = Message::new_text(
+            stock_str::key_age_warning_msg_body(context, &timestamp_to_str(created)).await,
+        );
+        let label = format!("key-age-warning-{}", timestamp.format("%Y")); // repeat every year
+        add_device_msg(context, Some(&label), Some(&mut msg)).await?;
+    }
+
+    if status.db_size_exceeded {
+        let db_size = format!("{} MB", status.db_size / 1024 / 1024);
+        let mut msg =
+            Message::new_text(stock_str::db_size_warning_msg_body(context, &db_size).await);
+        let label = format!("db-size-warning-{}", timestamp.format("%Y-%m")); // repeat every month
+        add_device_msg(context, Some(&label), Some(&mut msg)).await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat;
+    use crate::chatlist::Chatlist;
+    use crate::test_utils::TestContext;
+
+    async fn device_msg_cnt(t: &TestContext) -> Result<usize> {
+        let chats = Chatlist::try_load(t, 0, None, None).await?;
+        let Some(chat_id) = chats.iter().map(|(chat_id, _)| *chat_id).next() else {
+            return Ok(0);
+        };
+        Ok(chat::get_chat_msgs(t, chat_id).await?.len())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_backup_reminder() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let now = time();
+        t.set_config(Config::ConfiguredTimestamp, Some(&now.to_string()))
+            .await?;
+
+        // The reminder is disabled by default.
+        let later = now + 31 * 24 * 60 * 60;
+        assert!(!get_health_status_at(&t, later).await?.backup_overdue);
+
+        t.set_config(Config::BackupReminderDays, Some("30")).await?;
+        let status = get_health_status_at(&t, now + 29 * 24 * 60 * 60).await?;
+        assert_eq!(status.last_backup_timestamp, None);
+        assert!(!status.backup_overdue);
+        assert!(get_health_status_at(&t, later).await?.backup_overdue);
+        add_health_warnings(&t, later).await?;
+        assert_eq!(device_msg_cnt(&t).await?, 1);
+
+        // The reminder is not repeated within the same month.
+        add_health_warnings(&t, later).await?;
+        assert_eq!(device_msg_cnt(&t).await?, 1);
+
+        t.sql.set_raw_config_int64("backup_time", later).await?;
+        let status = get_health_status_at(&t, later).await?;
+        assert_eq!(status.last_backup_timestamp, Some(later));
+        assert!(!status.backup_overdue);
+
+        t.set_config(Config::BackupReminderDays, Some("0")).await?;
+        assert!(
+            !get_health_status_at(&t, later + 365 * 24 * 60 * 60)
+                .await?
+                .backup_overdue
+        );
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_key_age_and_db_size() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let now = time();
+
+        let status = get_health_status_at(&t, now).await?;
+        let created = status.key_created_timestamp.unwrap();
+        assert!(status.db_size > 0);
+        assert!(!status.db_size_exceeded);
+
+        // Key age check is disabled by default.
+        let later = created + 6 * 365 * 24 * 60 * 60;
+        assert!(!get_health_status_at(&t, later).await?.key_outdated);
+
+        t.set_config(Config::KeyAgeWarningYears, Some("5")).await?;
+        let earlier = created + 4 * 365 * 24 * 60 * 60;
+        assert!(!get_health_status_at(&t, earlier).await?.key_outdated);
+        assert!(get_health_status_at(&t, later).await?.key_outdated);
+        add_health_warnings(&t, later).await?;
+        assert_eq!(device_msg_cnt(&t).await?, 1);
+
+        t.set_config(Config::KeyAgeWarningYears, Some("0")).await?;
+        assert!(!get_health_status_at(&t, later).await?.key_outdated);
+
+        t.set_config(Config::DbSizeWarningMb, Some("1")).await?;
+        assert_eq!(
+            get_health_status_at(&t, now).await?.db_size_exceeded,
+            status.db_size > 1024 * 1024
+        );
+        Ok(())
+    }
+}
@@ -15,12 +15,12 @@
 
 use anyhow::{bail, ensure, format_err, Context as _, Result};
 use async_channel::Receiver;
+use async_imap::imap_proto::{BodyStructure, ContentEncoding};
 use async_imap::types::{Fetch, Flag, Name, NameAttribute, UnsolicitedResponse};
 use deltachat_contact_tools::ContactAddress;
 use futures::{FutureExt as _, StreamExt, TryStreamExt};
 use futures_lite::FutureExt;
 use num_traits::FromPrimitive;
-use rand::Rng;
 use ratelimit::Ratelimit;
 use url::Url;
 
@@ -30,6 +30,7 @@
 use crate::constants::{self, Blocked, Chattype, ShowEmails};
 use crate::contact::{Contact, ContactId, Modifier, Origin};
 use crate::context::Context;
+use crate::decrypt::Predecryption;
 use crate::events::EventType;
 use crate::headerdef::{HeaderDef, HeaderDefMap};
 use crate::log::LogExt;
@@ -40,24 +41,29 @@
 use crate::mimeparser;
 use crate::net::proxy::ProxyConfig;
 use crate::net::session::SessionStream;
-use crate::oauth2::get_oauth2_access_token;
+use crate::oauth2::{get_oauth2_access_token, invalidate_oauth2_access_token};
+use crate::param::Param;
 use crate::push::encrypt_device_token;
 use crate::receive_imf::{
     from_field_to_contact_id, get_prefetch_parent_message, receive_imf_inner, ReceivedMsg,
 };
 use crate::scheduler::connectivity::ConnectivityStore;
 use crate::stock_str;
-use crate::tools::{self, create_id, duration_to_str};
+use crate::tools::{self, create_id, duration_to_str, next_backoff};
+use crate::webxdc::{WebxdcUnsupported, WEBXDC_SUFFIX};
 
 pub(crate) mod capabilities;
 mod client;
 mod idle;
+mod pool;
 pub mod scan_folders;
+pub(crate) mod search;
 pub mod select_folder;
 pub(crate) mod session;
 
 use client::{determine_capabilities, Client};
 use mailparse::SingleInfo;
+pub(crate) use pool::ConnectionPool;
 use session::Session;
 
 pub(crate) const GENERATED_PREFIX: &str = "GEN_";
@@ -310,6 +316,7 @@ pub(crate) async fn connect(
                 "IMAP got rate limited, waiting for {} until can connect.",
                 duration_to_str(ratelimit_duration),
             );
+            context.metrics.count_ratelimit_delay(ratelimit_duration);
             let interrupted = async {
                 tokio::time::sleep(ratelimit_duration).await;
                 false
@@ -328,27 +335,30 @@ pub(crate) async fn connect(
         self.connectivity.set_connecting(context).await;
 
         self.conn_last_try = tools::Time::now();
-        const BACKOFF_MIN_MS: u64 = 2000;
-        const BACKOFF_MAX_MS: u64 = 80_000;
-        self.conn_backoff_ms = min(self.conn_backoff_ms, BACKOFF_MAX_MS / 2);
-        self.conn_backoff_ms = self.conn_backoff_ms.saturating_add(
-            rand::thread_rng().gen_range((self.conn_backoff_ms / 2)..=self.conn_backoff_ms),
-        );
-        self.conn_backoff_ms = max(BACKOFF_MIN_MS, self.conn_backoff_ms);
+        let backoff_min_ms = context
+            .get_config_u64(Config::ImapRetryInitialDelay)
+            .await?
+            .saturating_mul(1000);
+        let backoff_max_ms = context
+            .get_config_u64(Config::ImapRetryMaxDelay)
+            .await?
+            .saturating_mul(1000);
+        self.conn_backoff_ms = next_backoff(self.conn_backoff_ms, backoff_min_ms, backoff_max_ms);
 
         let login_params = prioritize_server_login_params(&context.sql, &self.lp, "imap").await?;
         let mut first_error = None;
         for lp in login_params {
             info!(context, "IMAP trying to connect to {}.", &lp.connection);
             let connection_candidate = lp.connection.clone();
-            let client = match Client::connect(
+            let res = Client::connect(
                 context,
                 self.proxy_config.clone(),
                 self.strict_tls,
                 connection_candidate,
             )
-            .await
-            {
+            .await;
+            context.trace_configure_step(format!("IMAP connection to {}", lp.connection), &res);
+            let client = match res {
                 Ok(client) => client,
                 Err(err) => {
                     warn!(context, "IMAP failed to connect: {err:#}.");
@@ -357,7 +367,7 @@ pub(crate) async fn connect(
                 }
             };
 
-            self.conn_backoff_ms = BACKOFF_MIN_MS;
+            self.conn_backoff_ms = backoff_min_ms;
             self.ratelimit.send();
 
             let imap_user: &str = lp.user.as_ref();
@@ -367,7 +377,7 @@ pub(crate) async fn connect(
                 info!(context, "Logging into IMAP server with OAuth 2.");
                 let addr: &str = self.addr.as_ref();
 
-                let token = get_oauth2_access_token(context, addr, imap_pw, true)
+                let token = get_oauth2_access_token(context, addr, imap_pw, false)
                     .await?
                     .context("IMAP could not get OAUTH token")?;
                 let auth = OAuth2 {
@@ -379,6 +389,7 @@ pub(crate) async fn connect(
                 info!(context, "Logging into IMAP server with LOGIN.");
                 client.login(imap_user, imap_pw).await
             };
+            context.trace_configure_step(format!("IMAP login as {}", lp.user), &login_res);
 
             match login_res {
                 Ok(mut session) => {
@@ -403,6 +414,7 @@ pub(crate) async fn connect(
                     lock.clone_from(&session.capabilities.server_id);
 
                     self.authentication_failed_once = false;
+                    context.metrics.count_imap_connection();
                     context.emit_event(EventType::ImapConnected(format!(
                         "IMAP-LOGIN as {}",
                         lp.user
@@ -418,6 +430,9 @@ pub(crate) async fn connect(
 
                     warn!(context, "IMAP failed to login: {err:#}.");
                     first_error.get_or_insert(format_err!("{message} ({err:#})"));
+                    if self.oauth2 {
+                        invalidate_oauth2_access_token(context).await?;
+                    }
 
                     // If it looks like the password is wrong, send a notification:
                     let _lock = context.wrong_pw_warning_mutex.lock().await;
@@ -569,6 +584,8 @@ pub(crate) async fn fetch_new_messages(
         let read_cnt = msgs.len();
 
         let download_limit = context.download_limit().await?;
+        let webxdc_max_size = context.get_config_u64(Config::WebxdcMaxSize).await?;
+        let mut oversized_webxdc_msgs = Vec::new();
         let mut uids_fetch = Vec::<(_, bool /* partially? */)>::with_capacity(msgs.len() + 1);
         let mut uid_message_ids = BTreeMap::new();
         let mut largest_uid_skipped = None;
@@ -685,13 +702,32 @@ pub(crate) async fn fetch_new_messages(
                 )
                 .await.context("prefetch_should_download")?
             {
-                match download_limit {
-                    Some(download_limit) => uids_fetch.push((
-                        uid,
-                        fetch_response.size.unwrap_or_default() > download_limit,
-                    )),
-                    None => uids_fetch.push((uid, false)),
+                let size = fetch_response.size.unwrap_or_default();
+                let mut partially = download_limit.is_some_and(|limit| size > limit);
+                // Only messages exceeding the maximum webxdc size may contain an oversized app,
+                // so the body structure is fetched only for them.
+                // The attachments of encrypted messages are not known before download,
+                // their apps are checked after decryption instead.
+                if !partially
+                    && webxdc_max_size > 0
+                    && u64::from(size) > webxdc_max_size
+                    && !is_encrypted(&headers)
+                    && session
+                        .fetch_bodystructure(uid)
+                        .await?
+                        .as_ref()
+                        .and_then(|fetch| fetch.bodystructure())
+                        .is_some_and(|body| has_oversized_webxdc(body, webxdc_max_size))
+                {
+                    // Download only the headers, the message is shown as partially downloaded.
+                    info!(
+                        context,
+                        "Message {message_id} contains an oversized webxdc app, not downloading it."
+                    );
+                    partially = true;
+                    oversized_webxdc_msgs.push(message_id.clone());
                 }
+                uids_fetch.push((uid, partially));
                 uid_message_ids.insert(uid, message_id);
             } else {
                 largest_uid_skipped = Some(uid);
@@ -701,6 +737,7 @@ pub(crate) async fn fetch_new_messages(
         if !uids_fetch.is_empty() {
             self.connectivity.set_working(context).await;
         }
+        context.start_sync_progress(folder, uids_fetch.len(), fetch_existing_msgs);
 
         // Actually download messages.
         let mut largest_uid_fetched: u32 = 0;
@@ -710,7 +747,7 @@ pub(crate) async fn fetch_new_messages(
         uids_fetch.push((0, !uids_fetch.last().unwrap_or(&(0, false)).1));
         for (uid, fp) in uids_fetch {
             if fp != fetch_partially {
-                let (largest_uid_fetched_in_batch, received_msgs_in_batch) = session
+                let res = session
                     .fetch_many_msgs(
                         context,
                         folder,
@@ -721,7 +758,14 @@ pub(crate) async fn fetch_new_messages(
                         fetch_existing_msgs,
                     )
                     .await
-                    .context("fetch_many_msgs")?;
+                    .context("fetch_many_msgs");
+                let (largest_uid_fetched_in_batch, received_msgs_in_batch) = match res {
+                    Ok(res) => res,
+                    Err(err) => {
+                        context.finish_sync_progress(folder);
+                        return Err(err);
+                    }
+                };
                 received_msgs.extend(received_msgs_in_batch);
                 largest_uid_fetched = max(
                     largest_uid_fetched,
@@ -731,6 +775,14 @@ pub(crate) async fn fetch_new_messages(
             }
             uids_fetch_in_batch.push(uid);
         }
+        context.finish_sync_progress(folder);
+
+        for rfc724_mid in oversized_webxdc_msgs {
+            mark_oversized_webxdc(context, &rfc724_mid)
+                .await
+                .log_err(context)
+                .ok();
+        }
 
         // Advance uid_next to the maximum of the largest known UID plus 1
         // and mailbox UIDNEXT.
@@ -758,6 +810,14 @@ pub(crate) async fn fetch_new_messages(
             context.emit_event(EventType::IncomingMsgBunch);
         }
 
+        let new_msgs = received_msgs
+            .iter()
+            .filter(|msg| !msg.chat_id.is_trash())
+            .count();
+        if new_msgs > 0 {
+            context.add_to_sync_report(folder, |report| report.new_msgs += new_msgs);
+        }
+
         chat::mark_old_messages_as_noticed(context, received_msgs).await?;
 
         Ok(read_cnt > 0)
@@ -772,6 +832,18 @@ pub(crate) async fn fetch_existing_msgs(
         &mut self,
         context: &Context,
         session: &mut Session,
+    ) -> Result<()> {
+        let fetching_existing_msgs = &context.sync_progress.fetching_existing_msgs;
+        fetching_existing_msgs.store(true, Ordering::Relaxed);
+        let res = self.fetch_existing_msgs_inner(context, session).await;
+        fetching_existing_msgs.store(false, Ordering::Relaxed);
+        res
+    }
+
+    async fn fetch_existing_msgs_inner(
+        &mut self,
+        context: &Context,
+        session: &mut Session,
     ) -> Result<()> {
         add_all_recipients_as_contacts(context, session, Config::ConfiguredSentboxFolder)
             .await
@@ -784,6 +856,7 @@ pub(crate) async fn fetch_existing_msgs(
             .context("failed to get recipients from the inbox")?;
 
         if context.get_config_bool(Config::FetchExistingMsgs).await? {
+            let mut folders = Vec::new();
             for meaning in [
                 FolderMeaning::Mvbox,
                 FolderMeaning::Inbox,
@@ -794,15 +867,20 @@ pub(crate) async fn fetch_existing_msgs(
                     None => continue,
                 };
                 if let Some(folder) = context.get_config(config).await? {
-                    info!(
-                        context,
-                        "Fetching existing messages from folder {folder:?}."
-                    );
-                    self.fetch_new_messages(context, session, &folder, meaning, true)
-                        .await
-                        .context("could not fetch existing messages")?;
+                    folders.push((folder, meaning));
                 }
             }
+
+            let pool = ConnectionPool::open(context, folders.len()).await?;
+            info!(
+                context,
+                "Fetching existing messages from {} folders over {} connections.",
+                folders.len(),
+                pool.size()
+            );
+            pool.fetch_existing_msgs(context, self, session, &folders)
+                .await
+                .context("could not fetch existing messages")?;
         }
 
         info!(context, "Done fetching existing messages.");
@@ -842,6 +920,8 @@ pub(crate) async fn resync_folder_uids(
         let uid_validity;
         // Collect pairs of UID and Message-ID.
         let mut msgs = BTreeMap::new();
+        // All UIDs in the folder, including messages without Message-ID.
+        let mut uids = BTreeSet::new();
 
         let create = false;
         let folder_exists = self
@@ -853,6 +933,10 @@ pub(crate) async fn resync_folder_uids(
                 .await
                 .with_context(|| format!("Can't resync folder {folder}"))?;
             while let Some(fetch) = list.try_next().await? {
+                let Some(uid) = fetch.uid else {
+                    continue;
+                };
+                uids.insert(uid);
                 let headers = match get_fetch_headers(&fetch) {
                     Ok(headers) => headers,
                     Err(err) => {
@@ -860,9 +944,7 @@ pub(crate) async fn resync_folder_uids(
                         continue;
                     }
                 };
-                let message_id = prefetch_get_message_id(&headers);
-
-                if let (Some(uid), Some(rfc724_mid)) = (fetch.uid, message_id) {
+                if let Some(rfc724_mid) = prefetch_get_message_id(&headers) {
                     msgs.insert(
                         uid,
                         (
@@ -889,16 +971,18 @@ pub(crate) async fn resync_folder_uids(
         context
             .sql
             .transaction(move |transaction| {
-                transaction.execute("DELETE FROM imap WHERE folder=?", (folder,))?;
+                remove_expunged_uids(transaction, folder, uid_validity, &uids)?;
                 for (uid, (rfc724_mid, target)) in &msgs {
                     // This may detect previously undetected moved
                     // messages, so we update server_folder too.
+                    //
+                    // Pending deletions and moves are kept.
                     transaction.execute(
                         "INSERT INTO imap (rfc724_mid, folder, uid, uidvalidity, target)
                          VALUES           (?1,         ?2,     ?3,  ?4,          ?5)
                          ON CONFLICT(folder, uid, uidvalidity)
                          DO UPDATE SET rfc724_mid=excluded.rfc724_mid,
-                                       target=excluded.target",
+                                       target=IIF(target=folder, excluded.target, target)",
                         (rfc724_mid, folder, uid, uid_validity, target),
                     )?;
                 }
@@ -908,6 +992,57 @@ pub(crate) async fn resync_folder_uids(
         Ok(())
     }
 
+    /// Detects messages deleted from `folder` by other clients, e.g. while we were offline.
+    ///
+    /// If the folder on the server contains fewer messages than known from the `imap` table,
+    /// rows of messages not existing on the server anymore are removed from the table.
+    /// Other rows, including pending deletions and moves, are not changed.
+    ///
+    /// Returns the number of deleted messages.
+    pub(crate) async fn reconcile_expunged(
+        &mut self,
+        context: &Context,
+        folder: &str,
+    ) -> Result<usize> {
+        let create = false;
+        let folder_exists = self
+            .select_with_uidvalidity(context, folder, create)
+            .await?;
+        if !folder_exists {
+            return Ok(0);
+        }
+        let exists: usize = self
+            .selected_mailbox
+            .as_ref()
+            .with_context(|| format!("Expected {folder:?} to be selected"))?
+            .exists
+            .try_into()?;
+        let uid_validity = get_uidvalidity(context, folder).await?;
+        let known = context
+            .sql
+            .count(
+                "SELECT COUNT(*) FROM imap WHERE folder=? AND uidvalidity=?",
+                (folder, uid_validity),
+            )
+            .await?;
+        if known <= exists {
+            return Ok(0);
+        }
+
+        info!(
+            context,
+            "Folder {folder:?} has {exists} messages on the server but {known} are known, removing deleted ones."
+        );
+        let uids: BTreeSet<u32> = self.uid_search("ALL").await?.into_iter().collect();
+        let expunged = context
+            .sql
+            .transaction(move |transaction| {
+                remove_expunged_uids(transaction, folder, uid_validity, &uids)
+            })
+            .await?;
+        Ok(expunged)
+    }
+
     /// Deletes batch of messages identified by their UID from the currently
     /// selected folder.
     async fn delete_message_batch(
@@ -1026,7 +1161,11 @@ async fn move_message_batch(
     /// Moves and deletes messages as planned in the `imap` table.
     ///
     /// This is the only place where messages are moved or deleted on the IMAP server.
-    async fn move_delete_messages(&mut self, context: &Context, folder: &str) -> Result<()> {
+    pub(crate) async fn move_delete_messages(
+        &mut self,
+        context: &Context,
+        folder: &str,
+    ) -> Result<()> {
         let rows = context
             .sql
             .query_map(
@@ -1234,6 +1373,7 @@ pub(crate) async fn sync_seen_flags(&mut self, context: &Context, folder: &str)
             .context("failed to fetch flags")?;
 
         let mut got_unsolicited_fetch = false;
+        let mut flags_changed = 0;
 
         while let Some(fetch) = list
             .try_next()
@@ -1256,6 +1396,7 @@ pub(crate) async fn sync_seen_flags(&mut self, context: &Context, folder: &str)
                     })?
                 {
                     updated_chat_ids.insert(chat_id);
+                    flags_changed += 1;
                 }
             }
 
@@ -1280,6 +1421,9 @@ pub(crate) async fn sync_seen_flags(&mut self, context: &Context, folder: &str)
         set_modseq(context, folder, highest_modseq)
             .await
             .with_context(|| format!("failed to set MODSEQ for folder {folder}"))?;
+        if flags_changed > 0 {
+            context.add_to_sync_report(folder, |report| report.flags_changed += flags_changed);
+        }
         if !updated_chat_ids.is_empty() {
             context.on_archived_chats_maybe_noticed();
         }
@@ -1374,47 +1518,61 @@ pub(crate) async fn fetch_many_msgs(
             // when we want to process other messages first.
             let mut uid_msgs = HashMap::with_capacity(request_uids.len());
 
+            // UIDs for which decryption was started in the background.
+            let mut predecrypting = BTreeSet::new();
+            let mut predecryption = Predecryption::default();
+            let mut fetch_responses_done = false;
+
             let mut count = 0;
-            for &request_uid in &request_uids {
-                // Check if FETCH response is already in `uid_msgs`.
-                let mut fetch_response = uid_msgs.remove(&request_uid);
-
-                // Try to find a requested UID in returned FETCH responses.
-                while fetch_response.is_none() {
-                    let next_fetch_response =
-                        if let Some(next_fetch_response) = fetch_responses.next().await {
-                            next_fetch_response
+            for (i, &request_uid) in request_uids.iter().enumerate() {
+                // Read FETCH responses ahead so that the following messages
+                // are decrypted in parallel while this one is processed.
+                let lookahead = context
+                    .get_power_mode()
+                    .prefetch_lookahead(Predecryption::lookahead());
+                for &uid in request_uids.iter().skip(i).take(lookahead + 1) {
+                    // Try to find a requested UID in returned FETCH responses.
+                    while !fetch_responses_done && !uid_msgs.contains_key(&uid) {
+                        let next_fetch_response =
+                            if let Some(next_fetch_response) = fetch_responses.next().await {
+                                next_fetch_response
+                            } else {
+                                // No more FETCH responses received from the server.
+                                fetch_responses_done = true;
+                                break;
+                            };
+
+                        let next_fetch_response =
+                            next_fetch_response.context("Failed to process IMAP FETCH result")?;
+
+                        if let Some(next_uid) = next_fetch_response.uid {
+                            if !request_uids.contains(&next_uid) {
+                                // (size of `request_uids` is bounded by IMAP command length limit,
+                                // search in this vector is always fast)
+
+                                // Unwanted UIDs are possible because of unsolicited responses, e.g. if
+                                // another client changes \Seen flag on a message after we do a prefetch but
+                                // before fetch. It's not an error if we receive such unsolicited response.
+                                info!(
+                                    context,
+                                    "Skipping not requested FETCH response for UID {}.", next_uid
+                                );
+                            } else if uid_msgs.insert(next_uid, next_fetch_response).is_some() {
+                                warn!(context, "Got duplicated UID {}.", next_uid);
+                            }
                         } else {
-                            // No more FETCH responses received from the server.
-                            break;
-                        };
-
-                    let next_fetch_response =
-                        next_fetch_response.context("Failed to process IMAP FETCH result")?;
-
-                    if let Some(next_uid) = next_fetch_response.uid {
-                        if next_uid == request_uid {
-                            fetch_response = Some(next_fetch_response);
-                        } else if !request_uids.contains(&next_uid) {
-                            // (size of `request_uids` is bounded by IMAP command length limit,
-                            // search in this vector is always fast)
-
-                            // Unwanted UIDs are possible because of unsolicited responses, e.g. if
-                            // another client changes \Seen flag on a message after we do a prefetch but
-                            // before fetch. It's not an error if we receive such unsolicited response.
-                            info!(
-                                context,
-                                "Skipping not requested FETCH response for UID {}.", next_uid
-                            );
-                        } else if uid_msgs.insert(next_uid, next_fetch_response).is_some() {
-                            warn!(context, "Got duplicated UID {}.", next_uid);
+                            info!(context, "Skipping FETCH response without UID.");
+                        }
+                    }
+
+                    if uid != request_uid && !fetch_partially && predecrypting.insert(uid) {
+                        if let Some(body) = uid_msgs.get(&uid).and_then(|fetch| fetch.body()) {
+                            predecryption.start(context, body);
                         }
-                    } else {
-                        info!(context, "Skipping FETCH response without UID.");
                     }
                 }
 
-                let fetch_response = match fetch_response {
+                let fetch_response = match uid_msgs.remove(&request_uid) {
                     Some(fetch) => fetch,
                     None => {
                         warn!(
@@ -1425,6 +1583,7 @@ pub(crate) async fn fetch_many_msgs(
                     }
                 };
                 count += 1;
+                context.advance_sync_progress(folder);
 
                 let is_deleted = fetch_response.flags().any(|flag| flag == Flag::Deleted);
                 let (body, partial) = if fetch_partially {
@@ -1475,6 +1634,7 @@ pub(crate) async fn fetch_many_msgs(
                     is_seen,
                     partial,
                     fetching_existing_messages,
+                    predecryption.take(context, body).await,
                 )
                 .await
                 {
@@ -1490,6 +1650,9 @@ pub(crate) async fn fetch_many_msgs(
                 last_uid = Some(request_uid)
             }
 
+            // Decryptions of messages which were not processed are not needed anymore.
+            drop(predecryption);
+
             // If we don't process the whole response, IMAP client is left in a broken state where
             // it will try to process the rest of response as the next response.
             while fetch_responses.next().await.is_some() {}
@@ -1578,6 +1741,48 @@ pub(crate) async fn fetch_metadata(&mut self, context: &Context) -> Result<()> {
         Ok(())
     }
 
+    /// Stores encrypted `device_token` into /private/devicetoken IMAP METADATA of the Inbox.
+    ///
+    /// The server must support `METADATA` and `XDELTAPUSH` capabilities.
+    /// Returns false if the token is too long to be stored.
+    pub(crate) async fn set_device_token_metadata(
+        &mut self,
+        context: &Context,
+        device_token: &str,
+    ) -> Result<bool> {
+        let folder = context
+            .get_config(Config::ConfiguredInboxFolder)
+            .await?
+            .context("INBOX is not configured")?;
+
+        let encrypted_device_token =
+            encrypt_device_token(device_token).context("Failed to encrypt device token")?;
+
+        // We expect that the server supporting `XDELTAPUSH` capability
+        // has non-synchronizing literals support as well:
+        // <https://www.rfc-editor.org/rfc/rfc7888>.
+        let encrypted_device_token_len = encrypted_device_token.len();
+
+        if encrypted_device_token_len > 4096 {
+            // If Apple or Google (FCM) gives us a very large token,
+            // do not even try to give it to IMAP servers.
+            //
+            // Limit of 4096 is arbitrarily selected
+            // to be the same as required by LITERAL- IMAP extension.
+            //
+            // Dovecot supports LITERAL+ and non-synchronizing literals
+            // of any length, but there is no reason for tokens
+            // to be that large even after OpenPGP encryption.
+            warn!(context, "Device token is too long for LITERAL-, ignoring.");
+            return Ok(false);
+        }
+
+        self.run_command_and_check_ok(&format_setmetadata(&folder, &encrypted_device_token))
+            .await
+            .context("SETMETADATA command failed")?;
+        Ok(true)
+    }
+
     /// Stores device token into /private/devicetoken IMAP METADATA of the Inbox.
     pub(crate) async fn register_token(&mut self, context: &Context) -> Result<()> {
         if context.push_subscribed.load(Ordering::Relaxed) {
@@ -1592,48 +1797,19 @@ pub(crate) async fn register_token(&mut self, context: &Context) -> Result<()> {
             let device_token_changed =
                 context.get_config(Config::DeviceToken).await?.as_ref() != Some(&device_token);
 
-            if device_token_changed {
-                let folder = context
-                    .get_config(Config::ConfiguredInboxFolder)
+            if device_token_changed
+                && self
+                    .set_device_token_metadata(context, &device_token)
                     .await?
-                    .context("INBOX is not configured")?;
-
-                let encrypted_device_token = encrypt_device_token(&device_token)
-                    .context("Failed to encrypt device token")?;
-
-                // We expect that the server supporting `XDELTAPUSH` capability
-                // has non-synchronizing literals support as well:
-                // <https://www.rfc-editor.org/rfc/rfc7888>.
-                let encrypted_device_token_len = encrypted_device_token.len();
-
-                if encrypted_device_token_len <= 4096 {
-                    self.run_command_and_check_ok(&format_setmetadata(
-                        &folder,
-                        &encrypted_device_token,
-                    ))
-                    .await
-                    .context("SETMETADATA command failed")?;
-
-                    // Store device token saved on the server
-                    // to prevent storing duplicate tokens.
-                    // The server cannot deduplicate on its own
-                    // because encryption gives a different
-                    // result each time.
-                    context
-                        .set_config_internal(Config::DeviceToken, Some(&device_token))
-                        .await?;
-                } else {
-                    // If Apple or Google (FCM) gives us a very large token,
-                    // do not even try to give it to IMAP servers.
-                    //
-                    // Limit of 4096 is arbitrarily selected
-                    // to be the same as required by LITERAL- IMAP extension.
-                    //
-                    // Dovecot supports LITERAL+ and non-synchronizing literals
-                    // of any length, but there is no reason for tokens
-                    // to be that large even after OpenPGP encryption.
-                    warn!(context, "Device token is too long for LITERAL-, ignoring.");
-                }
+            {
+                // Store device token saved on the server
+                // to prevent storing duplicate tokens.
+                // The server cannot deduplicate on its own
+                // because encryption gives a different
+                // result each time.
+                context
+                    .set_config_internal(Config::DeviceToken, Some(&device_token))
+                    .await?;
             }
             context.push_subscribed.store(true, Ordering::Relaxed);
         } else if !context.push_subscriber.heartbeat_subscribed().await {
@@ -2192,6 +2368,70 @@ pub(crate) fn prefetch_get_message_id(headers: &[mailparse::MailHeader]) -> Opti
         .and_then(|msgid| mimeparser::parse_message_id(&msgid).ok())
 }
 
+/// Returns true if the prefetched headers belong to an encrypted message.
+fn is_encrypted(headers: &[mailparse::MailHeader]) -> bool {
+    headers
+        .get_header_value(HeaderDef::ContentType)
+        .is_some_and(|value| {
+            value
+                .trim_start()
+                .to_ascii_lowercase()
+                .starts_with("multipart/encrypted")
+        })
+}
+
+/// Returns true if the body structure contains a `.xdc` attachment
+/// larger than `max_size` bytes when decoded.
+fn has_oversized_webxdc(body: &BodyStructure<'_>, max_size: u64) -> bool {
+    let (common, other) = match body {
+        BodyStructure::Multipart { bodies, .. } => {
+            return bodies
+                .iter()
+                .any(|body| has_oversized_webxdc(body, max_size));
+        }
+        BodyStructure::Basic { common, other, .. } | BodyStructure::Text { common, other, .. } => {
+            (common, other)
+        }
+        BodyStructure::Message { .. } => return false,
+    };
+    let is_webxdc = common
+        .disposition
+        .as_ref()
+        .and_then(|disposition| disposition.params.as_ref())
+        .into_iter()
+        .chain(common.ty.params.as_ref())
+        .flatten()
+        .any(|(key, value)| {
+            (key.eq_ignore_ascii_case("filename") || key.eq_ignore_ascii_case("name"))
+                && value
+                    .to_ascii_lowercase()
+                    .ends_with(&format!(".{WEBXDC_SUFFIX}"))
+        });
+    if !is_webxdc {
+        return false;
+    }
+    let size = match other.transfer_encoding {
+        ContentEncoding::Base64 => u64::from(other.octets) / 4 * 3,
+        _ => u64::from(other.octets),
+    };
+    size > max_size
+}
+
+/// Marks the partially downloaded message `rfc724_mid` as containing an oversized webxdc app.
+async fn mark_oversized_webxdc(context: &Context, rfc724_mid: &str) -> Result<()> {
+    let Some(msg_id) = message::rfc724_mid_exists(context, rfc724_mid).await? else {
+        return Ok(());
+    };
+    let mut msg = Message::load_from_db(context, msg_id).await?;
+    msg.param.set_int(
+        Param::WebxdcUnsupported,
+        WebxdcUnsupported::Oversized as i32,
+    );
+    msg.update_param(context).await?;
+    context.emit_msgs_changed(msg.chat_id, msg_id);
+    Ok(())
+}
+
 pub(crate) fn create_message_id() -> String {
     format!("{}{}", GENERATED_PREFIX, create_id())
 }
@@ -2424,6 +2664,38 @@ pub(crate) async fn set_uidvalidity(
     Ok(())
 }
 
+/// Removes rows of `folder` from the `imap` table
+/// which have another UIDVALIDITY or a UID not contained in `uids`,
+/// i.e. the messages don't exist on the server anymore.
+///
+/// Returns the number of removed rows.
+fn remove_expunged_uids(
+    transaction: &rusqlite::Transaction<'_>,
+    folder: &str,
+    uid_validity: u32,
+    uids: &BTreeSet<u32>,
+) -> Result<usize> {
+    let mut removed = transaction.execute(
+        "DELETE FROM imap WHERE folder=? AND uidvalidity!=?",
+        (folder, uid_validity),
+    )?;
+    let rows = transaction
+        .prepare("SELECT id, uid FROM imap WHERE folder=?")?
+        .query_map((folder,), |row| {
+            let id: i64 = row.get(0)?;
+            let uid: u32 = row.get(1)?;
+            Ok((id, uid))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let mut stmt = transaction.prepare("DELETE FROM imap WHERE id=?")?;
+    for (id, uid) in rows {
+        if !uids.contains(&uid) {
+            removed += stmt.execute((id,))?;
+        }
+    }
+    Ok(removed)
+}
+
 async fn get_uidvalidity(context: &Context, folder: &str) -> Result<u32> {
     Ok(context
         .sql
@@ -2658,6 +2930,62 @@ async fn test_set_uid_next_validity() {
         assert_eq!(get_uidvalidity(&t.ctx, "Inbox").await.unwrap(), 6);
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_remove_expunged_uids() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        for (rfc724_mid, folder, uid, uidvalidity, target) in [
+            ("1@example.org", "INBOX", 1, 5, "INBOX"),
+            // Pending deletion.
+            ("2@example.org", "INBOX", 2, 5, ""),
+            // Pending move, deleted by another client.
+            ("3@example.org", "INBOX", 3, 5, "DeltaChat"),
+            // Old UIDVALIDITY.
+            ("4@example.org", "INBOX", 4, 4, "INBOX"),
+            ("5@example.org", "DeltaChat", 5, 5, "DeltaChat"),
+        ] {
+            t.sql
+                .execute(
+                    "INSERT INTO imap (rfc724_mid, folder, uid, uidvalidity, target)
+                     VALUES (?, ?, ?, ?, ?)",
+                    (rfc724_mid, folder, uid, uidvalidity, target),
+                )
+                .await?;
+        }
+
+        let uids = BTreeSet::from([1, 2, 4, 5]);
+        let removed = t
+            .sql
+            .transaction(move |transaction| remove_expunged_uids(transaction, "INBOX", 5, &uids))
+            .await?;
+        assert_eq!(removed, 2);
+
+        let rows = t
+            .sql
+            .query_map(
+                "SELECT rfc724_mid, target FROM imap ORDER BY id",
+                (),
+                |row| {
+                    let rfc724_mid: String = row.get(0)?;
+                    let target: String = row.get(1)?;
+                    Ok((rfc724_mid, target))
+                },
+                |rows| {
+                    rows.collect::<std::result::Result<Vec<_>, _>>()
+                        .map_err(Into::into)
+                },
+            )
+            .await?;
+        assert_eq!(
+            rows,
+            [
+                ("1@example.org".to_string(), "INBOX".to_string()),
+                ("2@example.org".to_string(), "".to_string()),
+                ("5@example.org".to_string(), "DeltaChat".to_string()),
+            ]
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_build_sequence_sets() {
         assert_eq!(build_sequence_sets(&[]).unwrap(), vec![]);
@@ -2958,4 +3286,49 @@ fn test_setmetadata_device_token() {
             "SETMETADATA \"INBOX\" (/private/devicetoken {15+}\r\nfoo\r\nbar\r\nbaz\r\n)"
         );
     }
+
+    #[test]
+    fn test_has_oversized_webxdc() {
+        use async_imap::imap_proto::{parser::parse_response, AttributeValue, Response};
+
+        let fetch = |attachment_name: &str| {
+            format!(
+                "* 1 FETCH (BODYSTRUCTURE (\
+                 (\"text\" \"plain\" (\"charset\" \"utf-8\") NIL NIL \"7bit\" 5 1 NIL NIL NIL NIL)\
+                 (\"application\" \"octet-stream\" (\"name\" \"{attachment_name}\") NIL NIL \"base64\" 4000 \
+                 NIL (\"attachment\" (\"filename\" \"{attachment_name}\")) NIL NIL) \
+                 \"mixed\" (\"boundary\" \"xyz\") NIL NIL NIL))\r\n"
+            )
+        };
+        let check = |attachment_name: &str, max_size: u64| {
+            let response = fetch(attachment_name);
+            let Ok((_, Response::Fetch(_, attrs))) = parse_response(response.as_bytes()) else {
+                panic!("Failed to parse {response}");
+            };
+            let Some(AttributeValue::BodyStructure(body)) = attrs.first() else {
+                panic!("No body structure");
+            };
+            has_oversized_webxdc(body, max_size)
+        };
+
+        // 4000 base64 bytes are 3000 bytes decoded.
+        assert!(check("app.xdc", 2999));
+        assert!(check("App.XDC", 2999));
+        assert!(!check("app.xdc", 3000));
+        assert!(!check("image.png", 100));
+    }
+
+    #[test]
+    fn test_is_encrypted() {
+        let check = |raw: &[u8]| {
+            let (headers, _) = mailparse::parse_headers(raw).unwrap();
+            is_encrypted(&headers)
+        };
+        assert!(check(
+            b"Content-Type: multipart/encrypted; protocol=\"application/pgp-encrypted\";\r\n\r\n"
+        ));
+        assert!(check(b"Content-Type: Multipart/Encrypted\r\n\r\n"));
+        assert!(!check(b"Content-Type: multipart/mixed\r\n\r\n"));
+        assert!(!check(b"Subject: Hi\r\n\r\n"));
+    }
 }
@@ -29,6 +29,11 @@ pub(crate) struct Capabilities {
     /// <https://tools.ietf.org/html/rfc4978>
     pub can_compress: bool,
 
+    /// True if the server has LITERAL+ or LITERAL- capability as defined in
+    /// <https://tools.ietf.org/html/rfc7888>,
+    /// so non-synchronizing literals of at least 4096 octets can be sent.
+    pub can_literal_plus: bool,
+
     /// True if the server supports XDELTAPUSH capability.
     /// This capability means setting /private/devicetoken IMAP METADATA
     /// on the INBOX results in new mail notifications
@@ -69,6 +69,7 @@ pub(crate) async fn determine_capabilities(
         can_condstore: caps.has_str("CONDSTORE"),
         can_metadata: caps.has_str("METADATA"),
         can_compress: caps.has_str("COMPRESS=DEFLATE"),
+        can_literal_plus: caps.has_str("LITERAL+") || caps.has_str("LITERAL-"),
         can_push: caps.has_str("XDELTAPUSH"),
         is_chatmail: caps.has_str("XCHATMAIL"),
         server_id,
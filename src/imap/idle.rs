@@ -12,14 +12,6 @@
 use crate::net::TIMEOUT;
 use crate::tools::{self, time_elapsed};
 
-/// Timeout after which IDLE is finished
-/// if there are no responses from the server.
-///
-/// If `* OK Still here` keepalives are sent more frequently
-/// than this duration, timeout should never be triggered.
-/// For example, Dovecot sends keepalives every 2 minutes by default.
-const IDLE_TIMEOUT: Duration = Duration::from_secs(5 * 60);
-
 impl Session {
     pub async fn idle(
         mut self,
@@ -57,11 +49,16 @@ pub async fn idle(
             .with_context(|| format!("IMAP IDLE protocol failed to init in folder {folder:?}"))?;
 
         // At this point IDLE command was sent and we received a "+ idling" response. We will now
-        // read from the stream without getting any data for up to `IDLE_TIMEOUT`. If we don't
-        // disable read timeout, we would get a timeout after `crate::net::TIMEOUT`, which is a lot
-        // shorter than `IDLE_TIMEOUT`.
+        // read from the stream without getting any data for up to the IDLE timeout of the power
+        // mode. If we don't disable read timeout, we would get a timeout after
+        // `crate::net::TIMEOUT`, which is a lot shorter than the IDLE timeout.
+        //
+        // If `* OK Still here` keepalives are sent more frequently
+        // than the IDLE timeout, timeout should never be triggered.
+        // For example, Dovecot sends keepalives every 2 minutes by default.
         handle.as_mut().set_read_timeout(None);
-        let (idle_wait, interrupt) = handle.wait_with_timeout(IDLE_TIMEOUT);
+        let idle_timeout = context.get_power_mode().idle_timeout();
+        let (idle_wait, interrupt) = handle.wait_with_timeout(idle_timeout);
 
         enum Event {
             IdleResponse(IdleResponse),
@@ -124,8 +121,9 @@ pub(crate) async fn fake_idle(
 
         info!(context, "IMAP-fake-IDLEing folder={:?}", watch_folder);
 
-        // Wait for 60 seconds or until we are interrupted.
-        match timeout(Duration::from_secs(60), self.idle_interrupt_receiver.recv()).await {
+        // Wait for the polling interval of the power mode or until we are interrupted.
+        let interval = context.get_power_mode().fake_idle_interval();
+        match timeout(interval, self.idle_interrupt_receiver.recv()).await {
             Err(_) => info!(context, "Fake IDLE finished."),
             Ok(_) => info!(context, "Fake IDLE interrupted."),
         }
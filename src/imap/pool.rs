@@ -0,0 +1,222 @@
+//! # Pool of additional IMAP connections.
+//!
+//! The watched folders are normally fetched over one connection each by the scheduler,
+//! but fetching existing messages after configuring a large account
+//! and background fetch use a single connection and would fetch all folders sequentially.
+//! The pool opens additional connections, up to [`Config::ImapConnections`] in total,
+//! so that e.g. `DeltaChat` folder and `INBOX` are fetched concurrently.
+//!
+//! Messages moved from `INBOX` to `DeltaChat` folder while fetching concurrently
+//! are not downloaded from `INBOX` and are fetched from `DeltaChat` folder
+//! the next time it is fetched, so the order of fetching the folders does not matter.
+//!
+//! Many servers limit the number of concurrent connections per user,
+//! and the scheduler already keeps one connection per watched folder.
+//! If an additional connection cannot be established, the pool does not retry
+//! and the remaining folders are fetched over the connections opened so far.
+
+use anyhow::{Context as _, Result};
+use futures::future::{join_all, try_join_all};
+use tokio::time::Instant;
+
+use super::session::Session;
+use super::{FolderMeaning, Imap};
+use crate::config::Config;
+use crate::context::Context;
+use crate::scheduler::work_queue::{run_work_queue, Work, WorkQueue};
+
+/// Upper bound for [`Config::ImapConnections`].
+const MAX_CONNECTIONS: usize = 4;
+
+/// Additional IMAP connections, opened in addition to the connection of the caller.
+pub(crate) struct ConnectionPool {
+    connections: Vec<(Imap, Session)>,
+}
+
+impl ConnectionPool {
+    /// Opens additional connections to fetch `folder_cnt` folders.
+    ///
+    /// No more connections are opened than there are folders
+    /// and the connection of the caller is counted against the limit.
+    pub(crate) async fn open(context: &Context, folder_cnt: usize) -> Result<Self> {
+        let limit = usize::try_from(context.get_config_int(Config::ImapConnections).await?)
+            .unwrap_or_default()
+            .clamp(1, MAX_CONNECTIONS)
+            .min(folder_cnt.max(1));
+
+        let mut connections = Vec::new();
+        for _ in 1..limit {
+            match Self::connect(context).await {
+                Ok(connection) => connections.push(connection),
+                Err(err) => {
+                    warn!(
+                        context,
+                        "Failed to open additional IMAP connection: {err:#}."
+                    );
+                    break;
+                }
+            }
+        }
+        Ok(Self { connections })
+    }
+
+    pub(super) async fn connect(context: &Context) -> Result<(Imap, Session)> {
+        // Additional connections never IDLE, so nobody needs to interrupt them.
+        let (_idle_interrupt_sender, idle_interrupt_receiver) = async_channel::bounded(1);
+        let mut imap = Imap::new_configured(context, idle_interrupt_receiver).await?;
+        let session = imap.prepare(context).await?;
+        Ok((imap, session))
+    }
+
+    /// Returns the total number of connections including the connection of the caller.
+    pub(crate) fn size(&self) -> usize {
+        self.connections.len() + 1
+    }
+
+    /// Fetches existing messages from `folders`,
+    /// distributing the folders over the connection of the caller and the pool connections
+    /// as returned by [`assign_folders`].
+    pub(crate) async fn fetch_existing_msgs(
+        mut self,
+        context: &Context,
+        imap: &mut Imap,
+        session: &mut Session,
+        folders: &[(String, FolderMeaning)],
+    ) -> Result<()> {
+        let queues = assign_folders(folders, self.size());
+        let workers = std::iter::once((imap, session))
+            .chain(
+                self.connections
+                    .iter_mut()
+                    .map(|(imap, session)| (imap, session)),
+            )
+            .zip(queues)
+            .map(|((imap, session), queue)| async move {
+                for (folder, meaning) in queue {
+                    info!(
+                        context,
+                        "Fetching existing messages from folder {folder:?}."
+                    );
+                    imap.fetch_new_messages(context, session, folder, *meaning, true)
+                        .await
+                        .with_context(|| {
+                            format!("Failed to fetch existing messages from {folder:?}")
+                        })?;
+                }
+                Ok::<(), anyhow::Error>(())
+            });
+        try_join_all(workers).await?;
+        Ok(())
+    }
+}
+
+impl ConnectionPool {
+    /// Does the background work for `folders` until the `deadline`,
+    /// see [`run_work_queue`].
+    ///
+    /// Work for the folders is distributed over the connection of the caller
+    /// and the pool connections as returned by [`assign_folders`],
+    /// other work is done over the connection of the caller.
+    /// Returns the number of work items that were not done.
+    pub(crate) async fn run_background_work(
+        mut self,
+        context: &Context,
+        imap: &mut Imap,
+        session: &mut Session,
+        folders: &[FolderMeaning],
+        deadline: Instant,
+    ) -> Result<usize> {
+        let size = self.size();
+        let workers = std::iter::once((imap, session))
+            .chain(
+                self.connections
+                    .iter_mut()
+                    .map(|(imap, session)| (imap, session)),
+            )
+            .zip(assign_folders(folders, size))
+            .enumerate()
+            .map(|(i, ((imap, session), folders))| {
+                let mut queue = WorkQueue::new();
+                for folder_meaning in folders {
+                    queue.push(Work::FetchNewMessages(*folder_meaning));
+                    queue.push(Work::MoveDelete(*folder_meaning));
+                }
+                if i == 0 {
+                    queue.push(Work::SendMdns);
+                    queue.push(Work::Housekeeping);
+                }
+                run_work_queue(context, imap, session, queue, deadline)
+            });
+        let mut skipped = 0;
+        for res in join_all(workers).await {
+            skipped += res?;
+        }
+        Ok(skipped)
+    }
+}
+
+/// Assigns folders to `size` connections, the connection of the caller first,
+/// in a round-robin fashion, so the first folders of the list are fetched concurrently.
+fn assign_folders<T>(folders: &[T], size: usize) -> Vec<Vec<&T>> {
+    let mut queues = vec![Vec::new(); size.max(1)];
+    let queue_cnt = queues.len();
+    for (i, folder) in folders.iter().enumerate() {
+        queues[i % queue_cnt].push(folder);
+    }
+    queues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestContext;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_pool_size_limit() -> Result<()> {
+        let t = TestContext::new_alice().await;
+
+        // No additional connections are needed for a single folder.
+        let pool = ConnectionPool::open(&t, 1).await?;
+        assert_eq!(pool.size(), 1);
+
+        t.set_config(Config::ImapConnections, Some("1")).await?;
+        let pool = ConnectionPool::open(&t, 2).await?;
+        assert_eq!(pool.size(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_assign_folders() {
+        let folders = [
+            ("DeltaChat".to_string(), FolderMeaning::Mvbox),
+            ("INBOX".to_string(), FolderMeaning::Inbox),
+            ("Sent".to_string(), FolderMeaning::Sent),
+        ];
+        let names = |queues: Vec<Vec<&(String, FolderMeaning)>>| -> Vec<Vec<String>> {
+            queues
+                .into_iter()
+                .map(|queue| queue.into_iter().map(|(name, _)| name.clone()).collect())
+                .collect()
+        };
+
+        assert_eq!(
+            names(assign_folders(&folders, 1)),
+            vec![vec!["DeltaChat", "INBOX", "Sent"]]
+        );
+        assert_eq!(
+            names(assign_folders(&folders, 2)),
+            vec![vec!["DeltaChat", "Sent"], vec!["INBOX"]]
+        );
+
+        let folders = [FolderMeaning::Inbox, FolderMeaning::Mvbox];
+        assert_eq!(
+            assign_folders(&folders, 4),
+            vec![
+                vec![&FolderMeaning::Inbox],
+                vec![&FolderMeaning::Mvbox],
+                vec![],
+                vec![]
+            ]
+        );
+    }
+}
@@ -0,0 +1,226 @@
+//! # Server-side search.
+//!
+//! Local search only finds messages that were downloaded,
+//! so messages that were never fetched or only partially downloaded
+//! because of the download limit are not found by their body.
+//! Server-side search delegates the query to the IMAP server using `SEARCH` command
+//! on the watched folders and prefetches the headers of unknown matches.
+//! Prefetched messages are added as partially downloaded messages,
+//! so the user can download them on demand.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::{Context as _, Result};
+
+use super::pool::ConnectionPool;
+use super::session::Session;
+use super::{get_fetch_headers, get_uidvalidity, prefetch_get_message_id, FolderMeaning};
+use crate::chat::ChatId;
+use crate::config::Config;
+use crate::context::Context;
+use crate::message::{self, Message, MsgId};
+
+/// Maximum number of unknown matches prefetched per folder.
+const MAX_PREFETCHED_RESULTS: usize = 50;
+
+/// Local search results below this number are completed by the server-side search.
+pub(crate) const SERVER_SEARCH_THRESHOLD: usize = 10;
+
+/// Maximum size of a non-synchronizing literal allowed by LITERAL-.
+const MAX_LITERAL_MINUS_SIZE: usize = 4096;
+
+/// Formats `query` as an IMAP search string.
+///
+/// Quoted strings may only contain 7-bit characters,
+/// so non-ASCII queries are sent as a non-synchronizing literal
+/// if the server supports it, see <https://tools.ietf.org/html/rfc7888>.
+/// Otherwise the query is sent as a quoted string,
+/// which most servers accept in UTF-8 anyway.
+fn quote_query(query: &str, can_literal_plus: bool) -> String {
+    let query: String = query.chars().filter(|c| *c != '\r' && *c != '\n').collect();
+    if !query.is_ascii() && can_literal_plus && query.len() <= MAX_LITERAL_MINUS_SIZE {
+        let len = query.len();
+        return format!("{{{len}+}}\r\n{query}");
+    }
+    let escaped = query.chars().fold(String::new(), |mut s, c| {
+        if c == '\\' || c == '"' {
+            s.push('\\');
+        }
+        s.push(c);
+        s
+    });
+    format!("\"{escaped}\"")
+}
+
+impl Session {
+    /// Searches `folder` for messages containing `query`
+    /// and returns IDs of matching messages, prefetching unknown ones.
+    async fn search_folder(
+        &mut self,
+        context: &Context,
+        folder: &str,
+        query: &str,
+    ) -> Result<Vec<MsgId>> {
+        let create = false;
+        if !self
+            .select_with_uidvalidity(context, folder, create)
+            .await
+            .with_context(|| format!("Failed to select folder {folder:?}"))?
+        {
+            return Ok(Vec::new());
+        }
+        let uid_validity = get_uidvalidity(context, folder).await?;
+
+        let uids: BTreeSet<u32> = self
+            .uid_search(format!(
+                "CHARSET UTF-8 TEXT {}",
+                quote_query(query, self.can_literal_plus())
+            ))
+            .await
+            .with_context(|| format!("Failed to search folder {folder:?}"))?
+            .into_iter()
+            .collect();
+
+        let mut msg_ids = Vec::new();
+        let mut unknown_uids = Vec::new();
+        for uid in uids.into_iter().rev() {
+            let rfc724_mid: Option<String> = context
+                .sql
+                .query_get_value(
+                    "SELECT rfc724_mid FROM imap WHERE folder=? AND uid=? AND uidvalidity=?",
+                    (folder, uid, uid_validity),
+                )
+                .await?;
+            match rfc724_mid {
+                Some(rfc724_mid) => {
+                    if let Some((msg_id, _)) =
+                        message::rfc724_mid_exists(context, &rfc724_mid).await?
+                    {
+                        msg_ids.push(msg_id);
+                    }
+                }
+                None if unknown_uids.len() < MAX_PREFETCHED_RESULTS => unknown_uids.push(uid),
+                None => {}
+            }
+        }
+        unknown_uids.sort_unstable();
+
+        let mut uid_message_ids = BTreeMap::new();
+        for (uid, fetch_response) in self.prefetch_uids(&unknown_uids).await? {
+            let headers = match get_fetch_headers(&fetch_response) {
+                Ok(headers) => headers,
+                Err(err) => {
+                    warn!(context, "Failed to parse FETCH headers: {err:#}.");
+                    continue;
+                }
+            };
+            let Some(message_id) = prefetch_get_message_id(&headers) else {
+                // Messages without Message-ID cannot be downloaded on demand.
+                continue;
+            };
+            if let Some((msg_id, _)) = message::rfc724_mid_exists(context, &message_id).await? {
+                msg_ids.push(msg_id);
+                continue;
+            }
+
+            // Store the message location, so it can be downloaded on demand,
+            // but do not move it anywhere.
+            context
+                .sql
+                .execute(
+                    "INSERT INTO imap (rfc724_mid, folder, uid, uidvalidity, target)
+                       VALUES         (?1,         ?2,     ?3,  ?4,          ?2)
+                       ON CONFLICT(folder, uid, uidvalidity)
+                       DO UPDATE SET rfc724_mid=excluded.rfc724_mid",
+                    (&message_id, folder, uid, uid_validity),
+                )
+                .await?;
+            uid_message_ids.insert(uid, message_id);
+        }
+
+        let fetch_partially = true;
+        let fetching_existing_messages = true;
+        let (_, received_msgs) = self
+            .fetch_many_msgs(
+                context,
+                folder,
+                uid_validity,
+                uid_message_ids.keys().copied().collect(),
+                &uid_message_ids,
+                fetch_partially,
+                fetching_existing_messages,
+            )
+            .await
+            .context("fetch_many_msgs")?;
+        msg_ids.extend(received_msgs.into_iter().flat_map(|msg| msg.msg_ids));
+        Ok(msg_ids)
+    }
+}
+
+/// Searches the watched folders on the server for messages containing `query`.
+///
+/// Returns IDs of visible matching messages, optionally limited to the given chat.
+pub(crate) async fn search_on_server(
+    context: &Context,
+    chat_id: Option<ChatId>,
+    query: &str,
+) -> Result<Vec<MsgId>> {
+    let mut folders = Vec::new();
+    for meaning in [FolderMeaning::Inbox, FolderMeaning::Mvbox] {
+        let Some(config) = meaning.to_config() else {
+            continue;
+        };
+        if let Some(folder) = context.get_config(config).await? {
+            if !folders.contains(&folder) {
+                folders.push(folder);
+            }
+        }
+    }
+    if context.get_config_bool(Config::SentboxWatch).await? {
+        if let Some(folder) = context.get_config(Config::ConfiguredSentboxFolder).await? {
+            if !folders.contains(&folder) {
+                folders.push(folder);
+            }
+        }
+    }
+
+    let (_imap, mut session) = ConnectionPool::connect(context).await?;
+    let mut msg_ids = Vec::new();
+    for folder in folders {
+        match session.search_folder(context, &folder, query).await {
+            Ok(ids) => msg_ids.extend(ids),
+            Err(err) => warn!(context, "Server-side search failed: {err:#}."),
+        }
+    }
+
+    let mut res = Vec::new();
+    for msg_id in msg_ids {
+        let msg = Message::load_from_db(context, msg_id).await?;
+        if msg.chat_id.is_special()
+            || msg.hidden
+            || chat_id.is_some_and(|chat_id| chat_id != msg.chat_id)
+            || res.contains(&msg_id)
+        {
+            continue;
+        }
+        res.push(msg_id);
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_query() {
+        assert_eq!(quote_query("hello", true), "\"hello\"");
+        assert_eq!(quote_query("say \"hi\"", true), "\"say \\\"hi\\\"\"");
+        assert_eq!(quote_query("a\\b\r\nc", true), "\"a\\\\bc\"");
+
+        // Non-ASCII queries are sent as literals counting octets.
+        assert_eq!(quote_query("grüße", true), "{7+}\r\ngrüße");
+        assert_eq!(quote_query("grü\r\nße", true), "{7+}\r\ngrüße");
+        assert_eq!(quote_query("grüße", false), "\"grüße\"");
+    }
+}
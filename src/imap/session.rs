@@ -17,6 +17,7 @@
 /// - Chat-Version to check if a message is a chat message
 /// - Autocrypt-Setup-Message to check if a message is an autocrypt setup message,
 ///   not necessarily sent by Delta Chat.
+/// - Content-Type to check if a message is encrypted.
 const PREFETCH_FLAGS: &str = "(UID INTERNALDATE RFC822.SIZE BODY.PEEK[HEADER.FIELDS (\
                               MESSAGE-ID \
                               DATE \
@@ -25,7 +26,8 @@
                               IN-REPLY-TO REFERENCES \
                               CHAT-VERSION \
                               AUTO-SUBMITTED \
-                              AUTOCRYPT-SETUP-MESSAGE\
+                              AUTOCRYPT-SETUP-MESSAGE \
+                              CONTENT-TYPE\
                               )])";
 
 #[derive(Debug)]
@@ -97,6 +99,10 @@ pub fn can_metadata(&self) -> bool {
         self.capabilities.can_metadata
     }
 
+    pub fn can_literal_plus(&self) -> bool {
+        self.capabilities.can_literal_plus
+    }
+
     pub fn can_push(&self) -> bool {
         self.capabilities.can_push
     }
@@ -144,6 +150,49 @@ pub(crate) async fn prefetch(
         Ok(msgs.into_iter().map(|((_, uid), msg)| (uid, msg)).collect())
     }
 
+    /// Fetches the body structure of the message `uid`,
+    /// e.g. to check attachment sizes before downloading the message.
+    pub(crate) async fn fetch_bodystructure(
+        &mut self,
+        uid: u32,
+    ) -> Result<Option<async_imap::types::Fetch>> {
+        let mut list = self
+            .uid_fetch(uid.to_string(), "(UID BODYSTRUCTURE)")
+            .await
+            .context("IMAP could not fetch body structure")?;
+        let mut res = None;
+        while let Some(msg) = list.try_next().await? {
+            if msg.uid == Some(uid) {
+                res = Some(msg);
+            }
+        }
+        Ok(res)
+    }
+
+    /// Like prefetch(), but for the messages with given UIDs,
+    /// e.g. the results of a server-side search.
+    pub(crate) async fn prefetch_uids(
+        &mut self,
+        uids: &[u32],
+    ) -> Result<Vec<(u32, async_imap::types::Fetch)>> {
+        let mut msgs = BTreeMap::new();
+        for (_, set) in super::build_sequence_sets(uids)? {
+            let mut list = self
+                .uid_fetch(set, PREFETCH_FLAGS)
+                .await
+                .context("IMAP could not fetch")?;
+            while let Some(msg) = list.try_next().await? {
+                if let Some(msg_uid) = msg.uid {
+                    if uids.contains(&msg_uid) {
+                        msgs.insert((msg.internal_date(), msg_uid), msg);
+                    }
+                }
+            }
+        }
+
+        Ok(msgs.into_iter().map(|((_, uid), msg)| (uid, msg)).collect())
+    }
+
     /// Like prefetch(), but not for new messages but existing ones (the DC_FETCH_EXISTING_MSGS_COUNT newest messages)
     pub(crate) async fn prefetch_existing_msgs(
         &mut self,
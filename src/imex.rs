@@ -11,7 +11,7 @@
 use pin_project::pin_project;
 
 use tokio::fs::{self, File};
-use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, ReadBuf};
 use tokio_tar::Archive;
 
 use crate::blob::BlobDirContents;
@@ -29,10 +29,20 @@
     create_folder, delete_file, get_filesuffix_lc, read_file, time, write_file, TempPathGuard,
 };
 
+mod chat_export;
+mod compare;
+mod folder_transfer;
 mod key_transfer;
+mod mail_import;
+mod settings_transfer;
 mod transfer;
 
+pub use chat_export::{
+    export_chat, verify_chat_export, ChatExportSignature, ChatExportVerification,
+};
+pub use compare::{compare_backup, BackupDiff, ChatSettingsDiff, ConfigDiff, CountComparison};
 pub use key_transfer::{continue_key_transfer, initiate_key_transfer};
+pub(crate) use transfer::remove_stale_transfer_parts;
 pub use transfer::{get_backup, BackupProvider};
 
 // Name of the database file in the backup.
@@ -65,6 +75,36 @@ pub enum ImexMode {
     /// created by DC_IMEX_EXPORT_BACKUP and detected by imex_has_backup(). Importing a backup
     /// is only possible as long as the context is not configured or used in another way.
     ImportBackup = 12,
+
+    /// Export a backup for transferring it to another device through a folder,
+    /// e.g. on a USB stick, if the devices cannot connect to each other directly.
+    /// A new transfer folder is created inside the directory given as `path`,
+    /// the backup is written to it in chunks encrypted with the given `passphrase`,
+    /// which must not be empty.
+    /// The name of the transfer folder is `delta-chat-transfer-<day>-<time>-<addr>`.
+    ExportBackupToFolder = 13,
+
+    /// Import a backup from the transfer folder given as `path`,
+    /// created by [`ImexMode::ExportBackupToFolder`], using the given `passphrase`.
+    /// Importing a backup is only possible as long as the context is not configured.
+    ImportBackupFromFolder = 14,
+
+    /// Import e-mails from other mail clients into chats.
+    /// `path` is an mbox file, a single `.eml` file or a directory containing `.eml` files.
+    /// Messages that are already in the database are skipped.
+    ImportMails = 21,
+
+    /// Export the settings of the account to the directory given as `path`,
+    /// encrypted with the given `passphrase`.
+    /// The file contains server settings, display name, avatar and preferences,
+    /// but no messages, keys, login names or passwords.
+    /// The name of the file is `delta-chat-settings-<day>.asc`.
+    ExportSettings = 31,
+
+    /// Import settings from the file given as `path`, created by [`ImexMode::ExportSettings`],
+    /// decrypting it with the given `passphrase`.
+    /// Importing settings is only possible as long as the account is not configured.
+    ImportSettings = 32,
 }
 
 /// Import/export things.
@@ -156,6 +196,7 @@ async fn set_self_key(context: &Context, armored: &str, set_default: bool) -> Re
     // try hard to only modify key-state
     let (private_key, header) = SignedSecretKey::from_asc(armored)?;
     let public_key = private_key.split_public_key()?;
+    key::check_imported_key(context, &public_key)?;
     if let Some(preferencrypt) = header.get("Autocrypt-Prefer-Encrypt") {
         let e2ee_enabled = match preferencrypt.as_str() {
             "nopreference" => 0,
@@ -209,15 +250,25 @@ async fn imex_inner(
         context,
         "{} path: {}",
         match what {
-            ImexMode::ExportSelfKeys | ImexMode::ExportBackup => "Export",
-            ImexMode::ImportSelfKeys | ImexMode::ImportBackup => "Import",
+            ImexMode::ExportSelfKeys
+            | ImexMode::ExportBackup
+            | ImexMode::ExportBackupToFolder
+            | ImexMode::ExportSettings => "Export",
+            ImexMode::ImportSelfKeys
+            | ImexMode::ImportBackup
+            | ImexMode::ImportBackupFromFolder
+            | ImexMode::ImportMails
+            | ImexMode::ImportSettings => "Import",
         },
         path.display()
     );
     ensure!(context.sql.is_open().await, "Database not opened.");
     context.emit_event(EventType::ImexProgress(1));
 
-    if what == ImexMode::ExportBackup || what == ImexMode::ExportSelfKeys {
+    if what == ImexMode::ExportBackup
+        || what == ImexMode::ExportBackupToFolder
+        || what == ImexMode::ExportSelfKeys
+    {
         // before we export anything, make sure the private key exists
         e2ee::ensure_secret_key_exists(context)
             .await
@@ -236,6 +287,41 @@ async fn imex_inner(
         ImexMode::ImportBackup => {
             import_backup(context, path, passphrase.unwrap_or_default()).await
         }
+        ImexMode::ExportBackupToFolder => {
+            folder_transfer::export_backup_to_folder(
+                context,
+                path,
+                &passphrase.unwrap_or_default(),
+                folder_transfer::CHUNK_SIZE,
+            )
+            .await?;
+            Ok(())
+        }
+        ImexMode::ImportBackupFromFolder => {
+            folder_transfer::import_backup_from_folder(
+                context,
+                path,
+                &passphrase.unwrap_or_default(),
+            )
+            .await
+        }
+        ImexMode::ImportMails => {
+            ensure!(
+                context.is_configured().await?,
+                "Cannot import mails to unconfigured accounts."
+            );
+            mail_import::import_mails(context, path).await?;
+            Ok(())
+        }
+        ImexMode::ExportSettings => {
+            create_folder(context, &path).await?;
+            settings_transfer::export_settings(context, path, &passphrase.unwrap_or_default())
+                .await?;
+            Ok(())
+        }
+        ImexMode::ImportSettings => {
+            settings_transfer::import_settings(context, path, &passphrase.unwrap_or_default()).await
+        }
     }
 }
 
@@ -612,6 +698,20 @@ pub(crate) async fn export_backup_stream<'a, W>(
     Ok(())
 }
 
+/// Reads from `reader` until `buf` is full or the end of the stream is reached.
+/// Returns the number of bytes read.
+async fn read_chunk<R: AsyncRead + Unpin>(reader: &mut R, buf: &mut [u8]) -> Result<usize> {
+    let mut len = 0;
+    while len < buf.len() {
+        let n = reader.read(&mut buf[len..]).await?;
+        if n == 0 {
+            break;
+        }
+        len += n;
+    }
+    Ok(len)
+}
+
 /// Imports secret key from a file.
 async fn import_secret_key(context: &Context, path: &Path, set_default: bool) -> Result<()> {
     let buf = read_file(context, &path).await?;
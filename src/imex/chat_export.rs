@@ -0,0 +1,453 @@
+//! # Verifiable export of a single chat.
+//!
+//! The export is a folder that can be handed over to third parties,
+//! e.g. as evidence, and checked for integrity later.
+//! It contains the chat metadata and the messages as canonical JSON,
+//! copies of all attachments in the `blobs/` subfolder
+//! and a manifest listing the SHA-256 hashes of all these files
+//! in the format of `sha256sum`, so it can also be checked with standard tools.
+//!
+//! Optionally the manifest is signed with the account key.
+//! The signature is stored as a detached OpenPGP signature next to the manifest
+//! and the public key needed to check it is exported into the folder as well.
+//! As anyone can replace both the key and the signature,
+//! the signature is only checked against the fingerprint of the expected signer
+//! which the verifier has to know beforehand, e.g. from a verified contact.
+//!
+//! The output only depends on the chat content,
+//! so exporting the same chat twice results in identical files.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{ensure, Context as _, Result};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tokio::fs;
+
+use crate::chat::{self, Chat, ChatId, ChatItem};
+use crate::contact::Contact;
+use crate::context::Context;
+use crate::events::EventType;
+use crate::key::{self, DcKey, Fingerprint, SignedPublicKey};
+use crate::message::{Message, Viewtype};
+use crate::pgp;
+use crate::tools::{create_folder, time};
+
+/// Name of the file containing the chat metadata.
+const CHAT_NAME: &str = "chat.json";
+
+/// Name of the file containing the messages.
+const MESSAGES_NAME: &str = "messages.json";
+
+/// Name of the subfolder containing the attachments.
+const BLOBS_NAME: &str = "blobs";
+
+/// Name of the file containing the public key of the signer.
+const PUBLIC_KEY_NAME: &str = "public-key.asc";
+
+/// Name of the manifest with the hashes of all other files.
+const MANIFEST_NAME: &str = "manifest.sha256";
+
+/// Name of the detached signature of the manifest.
+const SIGNATURE_NAME: &str = "manifest.sha256.asc";
+
+// The fields of the exported structs are sorted alphabetically
+// so that the compact serialization is canonical.
+
+#[derive(Debug, Serialize)]
+struct ExportedChat {
+    id: u32,
+    members: Vec<String>,
+    name: String,
+    #[serde(rename = "type")]
+    typ: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+struct ExportedMessage {
+    encrypted: bool,
+    file: Option<String>,
+    file_name: Option<String>,
+    file_sha256: Option<String>,
+    from: String,
+    from_name: String,
+    info: bool,
+    rfc724_mid: String,
+    text: String,
+    timestamp_received: i64,
+    timestamp_sent: i64,
+    viewtype: Viewtype,
+}
+
+/// Result of checking the signature of a chat export.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChatExportSignature {
+    /// The export is not signed and no expected signer was given.
+    Unsigned,
+
+    /// The export is signed, but no expected signer was given,
+    /// so the signature was not checked.
+    Unverified,
+
+    /// The manifest is signed by the expected key with the given fingerprint.
+    Valid(Fingerprint),
+
+    /// The signature does not match the manifest, is not made by the expected key
+    /// or is missing although an expected signer was given.
+    Invalid,
+}
+
+/// Result of [`verify_chat_export`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChatExportVerification {
+    /// Files whose content does not match the manifest.
+    pub modified: Vec<String>,
+
+    /// Files listed in the manifest which do not exist.
+    pub missing: Vec<String>,
+
+    /// Files which exist in the export, but are not listed in the manifest.
+    pub unlisted: Vec<String>,
+
+    /// Signature of the manifest.
+    pub signature: ChatExportSignature,
+}
+
+impl ChatExportVerification {
+    /// Returns true if all files match the manifest
+    /// and the signature, if any, is valid.
+    pub fn is_intact(&self) -> bool {
+        self.modified.is_empty()
+            && self.missing.is_empty()
+            && self.unlisted.is_empty()
+            && self.signature != ChatExportSignature::Invalid
+    }
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// Exports the chat into a new folder inside `dir`.
+///
+/// If `sign` is true, the manifest is signed with the account key.
+/// Returns the path of the created folder,
+/// which can be checked with [`verify_chat_export`].
+pub async fn export_chat(
+    context: &Context,
+    chat_id: ChatId,
+    dir: &Path,
+    sign: bool,
+) -> Result<PathBuf> {
+    let chat = Chat::load_from_db(context, chat_id).await?;
+    let date = chrono::DateTime::<chrono::Utc>::from_timestamp(time(), 0)
+        .context("Invalid export time")?
+        .format("%Y-%m-%d")
+        .to_string();
+    let folder = dir.join(format!("delta-chat-export-{}-{date}", chat_id.to_u32()));
+    ensure!(
+        !folder.exists(),
+        "Export folder {} already exists.",
+        folder.display()
+    );
+    create_folder(context, folder.join(BLOBS_NAME)).await?;
+
+    // Hashes of all written files by their path relative to the export folder.
+    let mut hashes = BTreeMap::new();
+
+    let mut members = Vec::new();
+    for contact_id in chat::get_chat_contacts(context, chat_id).await? {
+        let contact = Contact::get_by_id(context, contact_id).await?;
+        members.push(contact.get_addr().to_string());
+    }
+    members.sort();
+    let exported_chat = ExportedChat {
+        id: chat_id.to_u32(),
+        members,
+        name: chat.get_name().to_string(),
+        typ: chat.get_type().into(),
+    };
+    let data = serde_json::to_vec(&exported_chat)?;
+    fs::write(folder.join(CHAT_NAME), &data).await?;
+    hashes.insert(CHAT_NAME.to_string(), sha256_hex(&data));
+
+    let mut messages = Vec::new();
+    for item in chat::get_chat_msgs(context, chat_id).await? {
+        let ChatItem::Message { msg_id } = item else {
+            continue;
+        };
+        let msg = Message::load_from_db(context, msg_id).await?;
+        let from = Contact::get_by_id(context, msg.get_from_id()).await?;
+        let (file, file_sha256) = match msg.get_file(context) {
+            Some(path) => {
+                let blob_name = path
+                    .file_name()
+                    .context("Attachment without file name")?
+                    .to_string_lossy()
+                    .to_string();
+                let data = fs::read(&path)
+                    .await
+                    .with_context(|| format!("Failed to read {}", path.display()))?;
+                let hash = sha256_hex(&data);
+                let file = format!("{BLOBS_NAME}/{blob_name}");
+                fs::write(folder.join(&file), &data).await?;
+                hashes.insert(file.clone(), hash.clone());
+                (Some(file), Some(hash))
+            }
+            None => (None, None),
+        };
+        let file_name = file.as_ref().and_then(|_| msg.get_filename());
+        messages.push(ExportedMessage {
+            encrypted: msg.get_showpadlock(),
+            file,
+            file_name,
+            file_sha256,
+            from: from.get_addr().to_string(),
+            from_name: msg
+                .get_override_sender_name()
+                .unwrap_or_else(|| from.get_display_name().to_string()),
+            info: msg.is_info(),
+            rfc724_mid: msg.rfc724_mid().to_string(),
+            text: msg.get_text(),
+            timestamp_received: msg.timestamp_rcvd,
+            timestamp_sent: msg.timestamp_sent,
+            viewtype: msg.get_viewtype(),
+        });
+    }
+    let data = serde_json::to_vec(&messages)?;
+    fs::write(folder.join(MESSAGES_NAME), &data).await?;
+    hashes.insert(MESSAGES_NAME.to_string(), sha256_hex(&data));
+
+    if sign {
+        let public_key = key::load_self_public_key(context).await?.to_asc(None);
+        fs::write(folder.join(PUBLIC_KEY_NAME), &public_key).await?;
+        hashes.insert(
+            PUBLIC_KEY_NAME.to_string(),
+            sha256_hex(public_key.as_bytes()),
+        );
+    }
+
+    let manifest: String = hashes
+        .iter()
+        .map(|(path, hash)| format!("{hash}  {path}\n"))
+        .collect();
+    fs::write(folder.join(MANIFEST_NAME), &manifest).await?;
+    if sign {
+        let secret_key = key::load_self_secret_key(context).await?;
+        let signature = pgp::pk_calc_signature(manifest.as_bytes(), &secret_key)?;
+        fs::write(folder.join(SIGNATURE_NAME), signature).await?;
+    }
+
+    info!(
+        context,
+        "Exported {} messages of {chat_id} to {}.",
+        messages.len(),
+        folder.display()
+    );
+    context.emit_event(EventType::ImexFileWritten(folder.clone()));
+    Ok(folder)
+}
+
+/// Returns the paths of all files in `folder` relative to it, using `/` as separator.
+async fn list_files(folder: &Path) -> Result<Vec<String>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![(folder.to_path_buf(), String::new())];
+    while let Some((dir, prefix)) = dirs.pop() {
+        let mut entries = fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let name = format!("{prefix}{}", entry.file_name().to_string_lossy());
+            if entry.file_type().await?.is_dir() {
+                dirs.push((entry.path(), format!("{name}/")));
+            } else {
+                files.push(name);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Checks a folder created by [`export_chat`] against its manifest and signature.
+///
+/// The signature is only accepted if it is made by the key with the fingerprint `signer`.
+/// If `signer` is given, an export without signature is reported as invalid,
+/// as removing the signature must not make a tampered export look intact.
+/// If `signer` is `None`, only the hashes in the manifest are checked.
+///
+/// This does not need any account, so the export can be checked on any device.
+pub async fn verify_chat_export(
+    folder: &Path,
+    signer: Option<&Fingerprint>,
+) -> Result<ChatExportVerification> {
+    let manifest = fs::read_to_string(folder.join(MANIFEST_NAME))
+        .await
+        .with_context(|| format!("No manifest in {}", folder.display()))?;
+    let mut listed = BTreeMap::new();
+    for line in manifest.lines() {
+        let (hash, path) = line
+            .split_once("  ")
+            .with_context(|| format!("Invalid manifest line {line:?}"))?;
+        ensure!(
+            !path.split('/').any(|part| part == ".." || part.is_empty()),
+            "Invalid path {path:?} in manifest."
+        );
+        listed.insert(path.to_string(), hash.to_string());
+    }
+
+    let mut modified = Vec::new();
+    let mut missing = Vec::new();
+    for (path, hash) in &listed {
+        match fs::read(folder.join(path)).await {
+            Ok(data) => {
+                if sha256_hex(&data) != *hash {
+                    modified.push(path.clone());
+                }
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => missing.push(path.clone()),
+            Err(err) => return Err(err).with_context(|| format!("Failed to read {path}")),
+        }
+    }
+    let unlisted = list_files(folder)
+        .await?
+        .into_iter()
+        .filter(|path| {
+            path != MANIFEST_NAME && path != SIGNATURE_NAME && !listed.contains_key(path)
+        })
+        .collect();
+
+    let signature = match fs::read(folder.join(SIGNATURE_NAME)).await {
+        Ok(signature) => match signer {
+            Some(signer) => verify_signature(folder, manifest, &signature, signer).await,
+            None => ChatExportSignature::Unverified,
+        },
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => match signer {
+            Some(_) => ChatExportSignature::Invalid,
+            None => ChatExportSignature::Unsigned,
+        },
+        Err(err) => return Err(err).context("Failed to read signature"),
+    };
+
+    Ok(ChatExportVerification {
+        modified,
+        missing,
+        unlisted,
+        signature,
+    })
+}
+
+async fn verify_signature(
+    folder: &Path,
+    manifest: String,
+    signature: &[u8],
+    signer: &Fingerprint,
+) -> ChatExportSignature {
+    let Ok(public_key) = fs::read_to_string(folder.join(PUBLIC_KEY_NAME)).await else {
+        return ChatExportSignature::Invalid;
+    };
+    let Ok((public_key, _)) = SignedPublicKey::from_asc(&public_key) else {
+        return ChatExportSignature::Invalid;
+    };
+    // The key in the export can be replaced together with the signature.
+    if public_key.dc_fingerprint() != *signer {
+        return ChatExportSignature::Invalid;
+    }
+    // `pk_validate` expects the signed content to be followed by CRLF.
+    let content = manifest + "\r\n";
+    match pgp::pk_validate(content.as_bytes(), signature, &[public_key]) {
+        Ok(fingerprints) => match fingerprints.into_iter().next() {
+            Some(fingerprint) => ChatExportSignature::Valid(fingerprint),
+            None => ChatExportSignature::Invalid,
+        },
+        Err(_) => ChatExportSignature::Invalid,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::send_text_msg;
+    use crate::test_utils::TestContextManager;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_chat_export() -> Result<()> {
+        let mut tcm = TestContextManager::new();
+        let alice = &tcm.alice().await;
+        let bob = &tcm.bob().await;
+        let dir = tempfile::tempdir()?;
+
+        let chat_id = alice.create_chat(bob).await.id;
+        send_text_msg(alice, chat_id, "Hi Bob".to_string()).await?;
+        let mut msg = Message::new(Viewtype::File);
+        msg.set_file_from_bytes(alice, "contract.txt", b"the agreement", None)?;
+        chat::send_msg(alice, chat_id, &mut msg).await?;
+
+        let folder = export_chat(alice, chat_id, dir.path(), true).await?;
+        let alice_fp = key::load_self_public_key(alice).await?.dc_fingerprint();
+        let verification = verify_chat_export(&folder, Some(&alice_fp)).await?;
+        assert!(verification.is_intact());
+        assert_eq!(
+            verification.signature,
+            ChatExportSignature::Valid(alice_fp.clone())
+        );
+        let verification = verify_chat_export(&folder, None).await?;
+        assert!(verification.is_intact());
+        assert_eq!(verification.signature, ChatExportSignature::Unverified);
+
+        // A signature by another key is not accepted.
+        let bob_fp = key::load_self_public_key(bob).await?.dc_fingerprint();
+        let verification = verify_chat_export(&folder, Some(&bob_fp)).await?;
+        assert!(!verification.is_intact());
+        assert_eq!(verification.signature, ChatExportSignature::Invalid);
+        let messages = fs::read_to_string(folder.join(MESSAGES_NAME)).await?;
+        assert!(messages.contains("\"text\":\"Hi Bob\""));
+        assert!(messages.contains("\"file_name\":\"contract.txt\""));
+
+        // The export is deterministic.
+        let dir2 = tempfile::tempdir()?;
+        let folder2 = export_chat(alice, chat_id, dir2.path(), false).await?;
+        assert_eq!(
+            fs::read(folder2.join(MESSAGES_NAME)).await?,
+            messages.as_bytes()
+        );
+        let verification = verify_chat_export(&folder2, None).await?;
+        assert!(verification.is_intact());
+        assert_eq!(verification.signature, ChatExportSignature::Unsigned);
+
+        // A missing signature is not accepted if a signer is expected.
+        let verification = verify_chat_export(&folder2, Some(&alice_fp)).await?;
+        assert!(!verification.is_intact());
+        assert_eq!(verification.signature, ChatExportSignature::Invalid);
+
+        // Removing the signature of a signed export is detected as well.
+        let dir3 = tempfile::tempdir()?;
+        let folder3 = export_chat(alice, chat_id, dir3.path(), true).await?;
+        fs::remove_file(folder3.join(SIGNATURE_NAME)).await?;
+        let verification = verify_chat_export(&folder3, Some(&alice_fp)).await?;
+        assert!(!verification.is_intact());
+        assert_eq!(verification.signature, ChatExportSignature::Invalid);
+
+        // Tampering is detected.
+        fs::write(
+            folder.join(MESSAGES_NAME),
+            messages.replace("Hi Bob", "Hi Eve"),
+        )
+        .await?;
+        fs::write(folder.join(BLOBS_NAME).join("extra.txt"), b"extra").await?;
+        let verification = verify_chat_export(&folder, Some(&alice_fp)).await?;
+        assert!(!verification.is_intact());
+        assert_eq!(verification.modified, vec![MESSAGES_NAME.to_string()]);
+        assert_eq!(verification.unlisted, vec!["blobs/extra.txt".to_string()]);
+
+        let manifest = fs::read_to_string(folder.join(MANIFEST_NAME)).await?;
+        fs::write(
+            folder.join(MANIFEST_NAME),
+            manifest.replace("chat.json", "chat2.json"),
+        )
+        .await?;
+        let verification = verify_chat_export(&folder, Some(&alice_fp)).await?;
+        assert_eq!(verification.signature, ChatExportSignature::Invalid);
+        assert_eq!(verification.missing, vec!["chat2.json".to_string()]);
+        Ok(())
+    }
+}
@@ -0,0 +1,422 @@
+//! Comparison of a backup with the live account.
+//!
+//! Before restoring a backup over an existing account
+//! or when diagnosing reports like "my settings changed",
+//! it is useful to know how the backup differs from the current state.
+//! [`compare_backup`] unpacks only the database from the backup
+//! and compares chat, contact and message counts,
+//! config values, own keys and per-chat ephemeral timers and protection.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::ffi::OsStr;
+use std::path::Path;
+
+use anyhow::{bail, Context as _, Result};
+use futures::TryStreamExt;
+use rusqlite::{Connection, OpenFlags};
+use tokio::fs::File;
+use tokio_tar::Archive;
+
+use super::DBFILE_BACKUP_NAME;
+use crate::chat::{ChatId, ProtectionStatus};
+use crate::config::Config;
+use crate::constants::DC_CHAT_ID_LAST_SPECIAL;
+use crate::contact::ContactId;
+use crate::context::Context;
+use crate::ephemeral::Timer as EphemeralTimer;
+use crate::key::{DcKey, SignedPublicKey};
+use crate::tools::{create_id, TempPathGuard};
+
+/// Config keys which change during normal operation and are not worth reporting.
+const SKIPPED_CONFIG_KEYS: &[&str] = &["last_housekeeping", "last_cant_decrypt_outgoing_msgs"];
+
+/// Number of items in the live account and in the backup.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CountComparison {
+    /// Number of items in the live account.
+    pub live: usize,
+
+    /// Number of items in the backup.
+    pub backup: usize,
+}
+
+impl CountComparison {
+    /// Returns true if the counts differ.
+    pub fn differs(&self) -> bool {
+        self.live != self.backup
+    }
+}
+
+/// Config value which differs between the live account and the backup.
+///
+/// Values of passwords are never reported, only the fact that they differ.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigDiff {
+    /// Config key as stored in the database.
+    pub key: String,
+
+    /// Value in the live account, `None` if unset.
+    pub live: Option<String>,
+
+    /// Value in the backup, `None` if unset.
+    pub backup: Option<String>,
+}
+
+/// Chat existing in both the live account and the backup with different settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChatSettingsDiff {
+    /// Chat ID.
+    pub chat_id: ChatId,
+
+    /// Chat name in the live account.
+    pub name: String,
+
+    /// Ephemeral timer in the live account.
+    pub live_ephemeral_timer: EphemeralTimer,
+
+    /// Ephemeral timer in the backup.
+    pub backup_ephemeral_timer: EphemeralTimer,
+
+    /// Protection status in the live account.
+    pub live_protected: ProtectionStatus,
+
+    /// Protection status in the backup.
+    pub backup_protected: ProtectionStatus,
+}
+
+/// Differences between a backup and the live account, see [`compare_backup`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BackupDiff {
+    /// Number of chats, not counting special chats.
+    pub chats: CountComparison,
+
+    /// Number of contacts, not counting special contacts.
+    pub contacts: CountComparison,
+
+    /// Number of messages in non-special chats.
+    pub msgs: CountComparison,
+
+    /// Differing config values, sorted by key.
+    pub config: Vec<ConfigDiff>,
+
+    /// Fingerprints of own keys only present in the live account.
+    pub keys_only_live: Vec<String>,
+
+    /// Fingerprints of own keys only present in the backup.
+    pub keys_only_backup: Vec<String>,
+
+    /// Whether the default key differs.
+    pub default_key_differs: bool,
+
+    /// Chats with differing ephemeral timer or protection status.
+    pub chat_settings: Vec<ChatSettingsDiff>,
+}
+
+impl BackupDiff {
+    /// Returns true if no differences were found.
+    pub fn is_empty(&self) -> bool {
+        !self.chats.differs()
+            && !self.contacts.differs()
+            && !self.msgs.differs()
+            && self.config.is_empty()
+            && self.keys_only_live.is_empty()
+            && self.keys_only_backup.is_empty()
+            && !self.default_key_differs
+            && self.chat_settings.is_empty()
+    }
+}
+
+/// Chat settings relevant for the comparison.
+#[derive(Debug)]
+struct ChatSettings {
+    name: String,
+    ephemeral_timer: EphemeralTimer,
+    protected: ProtectionStatus,
+}
+
+/// State of an account database relevant for the comparison.
+#[derive(Debug)]
+struct Snapshot {
+    chats: usize,
+    contacts: usize,
+    msgs: usize,
+    config: BTreeMap<String, String>,
+    keys: BTreeSet<String>,
+    default_key: Option<String>,
+    chat_settings: BTreeMap<ChatId, ChatSettings>,
+}
+
+impl Snapshot {
+    fn read(conn: &Connection) -> Result<Self> {
+        let count = |query: &str, param: u32| -> Result<usize> {
+            let n: i64 = conn.query_row(query, (param,), |row| row.get(0))?;
+            Ok(usize::try_from(n)?)
+        };
+        let chats = count(
+            "SELECT COUNT(*) FROM chats WHERE id>?",
+            DC_CHAT_ID_LAST_SPECIAL.to_u32(),
+        )?;
+        let contacts = count(
+            "SELECT COUNT(*) FROM contacts WHERE id>?",
+            ContactId::LAST_SPECIAL.to_u32(),
+        )?;
+        let msgs = count(
+            "SELECT COUNT(*) FROM msgs WHERE chat_id>?",
+            DC_CHAT_ID_LAST_SPECIAL.to_u32(),
+        )?;
+
+        let mut config = BTreeMap::new();
+        let mut stmt = conn.prepare("SELECT keyname, value FROM config")?;
+        let mut rows = stmt.query(())?;
+        while let Some(row) = rows.next()? {
+            let key: String = row.get(0)?;
+            let value: Option<String> = row.get(1)?;
+            if SKIPPED_CONFIG_KEYS.contains(&key.as_str()) {
+                continue;
+            }
+            config.insert(key, value.unwrap_or_default());
+        }
+
+        let mut keys = BTreeSet::new();
+        let mut default_key = None;
+        let mut stmt = conn.prepare("SELECT id, public_key FROM keypairs")?;
+        let mut rows = stmt.query(())?;
+        let default_key_id = config.get("key_id").cloned();
+        while let Some(row) = rows.next()? {
+            let id: i64 = row.get(0)?;
+            let bytes: Vec<u8> = row.get(1)?;
+            let fingerprint = SignedPublicKey::from_slice(&bytes)?.dc_fingerprint().hex();
+            if default_key_id.as_deref() == Some(id.to_string().as_str()) {
+                default_key = Some(fingerprint.clone());
+            }
+            keys.insert(fingerprint);
+        }
+
+        let mut chat_settings = BTreeMap::new();
+        let mut stmt =
+            conn.prepare("SELECT id, name, ephemeral_timer, protected FROM chats WHERE id>?")?;
+        let mut rows = stmt.query((DC_CHAT_ID_LAST_SPECIAL,))?;
+        while let Some(row) = rows.next()? {
+            let chat_id: ChatId = row.get(0)?;
+            chat_settings.insert(
+                chat_id,
+                ChatSettings {
+                    name: row.get(1)?,
+                    ephemeral_timer: row.get(2)?,
+                    protected: row
+                        .get::<_, Option<ProtectionStatus>>(3)?
+                        .unwrap_or_default(),
+                },
+            );
+        }
+
+        Ok(Self {
+            chats,
+            contacts,
+            msgs,
+            config,
+            keys,
+            default_key,
+            chat_settings,
+        })
+    }
+}
+
+/// Returns true if the value of the config key must not be reported.
+fn is_secret_config_key(key: &str) -> bool {
+    key.ends_with("_pw")
+        || [
+            Config::Socks5Password,
+            Config::ProxyUrl,
+            Config::DkimPrivateKey,
+        ]
+        .iter()
+        .any(|secret| key == secret.as_ref())
+        || key == "key_id"
+}
+
+fn diff_snapshots(live: Snapshot, backup: Snapshot) -> BackupDiff {
+    let mut config = Vec::new();
+    let keys: BTreeSet<&String> = live.config.keys().chain(backup.config.keys()).collect();
+    for key in keys {
+        let live_value = live.config.get(key);
+        let backup_value = backup.config.get(key);
+        if live_value == backup_value {
+            continue;
+        }
+        let redact = |value: Option<&String>| {
+            value.map(|v| {
+                if is_secret_config_key(key) {
+                    "***".to_string()
+                } else {
+                    v.clone()
+                }
+            })
+        };
+        config.push(ConfigDiff {
+            key: key.clone(),
+            live: redact(live_value),
+            backup: redact(backup_value),
+        });
+    }
+
+    let mut chat_settings = Vec::new();
+    for (chat_id, live_chat) in &live.chat_settings {
+        let Some(backup_chat) = backup.chat_settings.get(chat_id) else {
+            continue;
+        };
+        if live_chat.ephemeral_timer != backup_chat.ephemeral_timer
+            || live_chat.protected != backup_chat.protected
+        {
+            chat_settings.push(ChatSettingsDiff {
+                chat_id: *chat_id,
+                name: live_chat.name.clone(),
+                live_ephemeral_timer: live_chat.ephemeral_timer,
+                backup_ephemeral_timer: backup_chat.ephemeral_timer,
+                live_protected: live_chat.protected,
+                backup_protected: backup_chat.protected,
+            });
+        }
+    }
+
+    BackupDiff {
+        chats: CountComparison {
+            live: live.chats,
+            backup: backup.chats,
+        },
+        contacts: CountComparison {
+            live: live.contacts,
+            backup: backup.contacts,
+        },
+        msgs: CountComparison {
+            live: live.msgs,
+            backup: backup.msgs,
+        },
+        config,
+        keys_only_live: live.keys.difference(&backup.keys).cloned().collect(),
+        keys_only_backup: backup.keys.difference(&live.keys).cloned().collect(),
+        default_key_differs: live.default_key != backup.default_key,
+        chat_settings,
+    }
+}
+
+/// Compares the backup at `path` with the live account.
+///
+/// Only the database is unpacked from the backup, blobs are skipped.
+/// Neither the backup nor the live account are modified.
+pub async fn compare_backup(
+    context: &Context,
+    path: &Path,
+    passphrase: String,
+) -> Result<BackupDiff> {
+    let unpacked_database = TempPathGuard::new(
+        context
+            .get_blobdir()
+            .join(format!("compare-{}.sqlite", create_id())),
+    );
+
+    let backup_file = File::open(path)
+        .await
+        .with_context(|| format!("Cannot open backup {}", path.display()))?;
+    let mut archive = Archive::new(backup_file);
+    let mut entries = archive.entries()?;
+    let mut found = false;
+    while let Some(mut entry) = entries.try_next().await? {
+        if entry.path()?.file_name() == Some(OsStr::new(DBFILE_BACKUP_NAME)) {
+            entry
+                .unpack(&*unpacked_database)
+                .await
+                .context("Failed to unpack database")?;
+            found = true;
+            break;
+        }
+    }
+    if !found {
+        bail!("No database found in backup {}.", path.display());
+    }
+
+    let backup = tokio::task::block_in_place(|| -> Result<Snapshot> {
+        let conn = Connection::open_with_flags(
+            &*unpacked_database,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        if !passphrase.is_empty() {
+            conn.pragma_update(None, "key", &passphrase)?;
+        }
+        conn.query_row("SELECT count(*) FROM sqlite_master", [], |_row| Ok(()))
+            .context("Backup passphrase is not correct")?;
+        Snapshot::read(&conn)
+    })?;
+    let live = context.sql.call(true, |conn| Snapshot::read(conn)).await?;
+
+    Ok(diff_snapshots(live, backup))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::{self, ProtectionStatus};
+    use crate::ephemeral::Timer;
+    use crate::imex::{has_backup, imex, ImexMode};
+    use crate::test_utils::TestContext;
+
+    #[test]
+    fn test_is_secret_config_key() {
+        assert!(is_secret_config_key("mail_pw"));
+        assert!(is_secret_config_key("configured_send_pw"));
+        assert!(is_secret_config_key("socks5_password"));
+        assert!(is_secret_config_key("proxy_url"));
+        assert!(is_secret_config_key("dkim_private_key"));
+        assert!(is_secret_config_key("key_id"));
+        assert!(!is_secret_config_key("addr"));
+        assert!(!is_secret_config_key("displayname"));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_compare_backup() -> Result<()> {
+        let backup_dir = tempfile::tempdir()?;
+        let alice = TestContext::new_alice().await;
+        let chat_id =
+            chat::create_group_chat(&alice, ProtectionStatus::Unprotected, "Group").await?;
+        imex(&alice, ImexMode::ExportBackup, backup_dir.path(), None).await?;
+        let backup = has_backup(&alice, backup_dir.path()).await?;
+
+        let diff = compare_backup(&alice, backup.as_ref(), String::new()).await?;
+        assert!(diff.is_empty(), "{diff:?}");
+
+        assert!(
+            compare_backup(&alice, backup.as_ref(), "foobar".to_string())
+                .await
+                .is_err()
+        );
+
+        alice.set_config(Config::Displayname, Some("Alice")).await?;
+        alice.set_config(Config::MailPw, Some("secret")).await?;
+        chat_id
+            .inner_set_ephemeral_timer(&alice, Timer::Enabled { duration: 60 })
+            .await?;
+        chat::create_group_chat(&alice, ProtectionStatus::Unprotected, "Other").await?;
+
+        let diff = compare_backup(&alice, backup.as_ref(), String::new()).await?;
+        assert_eq!(diff.chats.live, diff.chats.backup + 1);
+        assert!(!diff.msgs.differs());
+        let displayname = diff.config.iter().find(|d| d.key == "displayname").unwrap();
+        assert_eq!(displayname.live.as_deref(), Some("Alice"));
+        assert_eq!(displayname.backup, None);
+        let mail_pw = diff.config.iter().find(|d| d.key == "mail_pw").unwrap();
+        assert_eq!(mail_pw.live.as_deref(), Some("***"));
+        assert!(diff.keys_only_live.is_empty());
+        assert!(!diff.default_key_differs);
+        assert_eq!(diff.chat_settings.len(), 1);
+        assert_eq!(diff.chat_settings[0].chat_id, chat_id);
+        assert_eq!(
+            diff.chat_settings[0].live_ephemeral_timer,
+            Timer::Enabled { duration: 60 }
+        );
+        assert_eq!(
+            diff.chat_settings[0].backup_ephemeral_timer,
+            Timer::Disabled
+        );
+        Ok(())
+    }
+}
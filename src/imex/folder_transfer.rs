@@ -0,0 +1,295 @@
+//! # Transfer of a backup through a folder.
+//!
+//! This is an alternative to [`BackupProvider`](super::BackupProvider)
+//! for cases where the devices cannot connect to each other directly,
+//! e.g. to move a large account using a USB stick or a folder shared by other means.
+//!
+//! The provider writes the backup in the same tar format as used by the network transfer
+//! into a transfer folder as a sequence of chunk files.
+//! Each chunk is symmetrically encrypted with the passphrase
+//! in the same way as the Autocrypt Setup Message
+//! and starts with its index so that missing or reordered chunks are detected.
+//! The manifest is written last, so a transfer folder without a manifest is incomplete.
+//!
+//! The receiver decrypts the chunks one by one and imports the backup from the resulting stream,
+//! so neither side needs to store the whole unencrypted backup.
+
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+use anyhow::{ensure, Context as _, Result};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+use crate::blob::BlobDirContents;
+use crate::context::Context;
+use crate::events::EventType;
+use crate::pgp;
+use crate::tools::{create_folder, time, TempPathGuard};
+
+use super::{
+    export_backup_stream, export_database, import_backup_stream, read_chunk, DBFILE_BACKUP_NAME,
+};
+
+/// Version of the transfer folder format.
+const TRANSFER_VERSION: u32 = 1;
+
+/// Name of the manifest file in the transfer folder.
+const MANIFEST_NAME: &str = "manifest.json";
+
+/// Size of the unencrypted backup data in a single chunk.
+pub(crate) const CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+/// Size of the chunk index prepended to the data of each chunk.
+const CHUNK_INDEX_SIZE: usize = 8;
+
+/// Buffer size of the pipe between the tar stream and the chunk files.
+const PIPE_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+
+    /// Number of chunk files.
+    chunks: u64,
+
+    /// Size of all files in the backup, used to report progress.
+    file_size: u64,
+}
+
+pub(super) fn chunk_path(folder: &Path, index: u64) -> PathBuf {
+    folder.join(format!("{index:06}.chunk"))
+}
+
+/// Exports a backup into a new transfer folder inside `dir`, encrypted with `passphrase`.
+///
+/// Returns the path of the transfer folder,
+/// which is to be passed to [`import_backup_from_folder`] on the other device.
+pub(crate) async fn export_backup_to_folder(
+    context: &Context,
+    dir: &Path,
+    passphrase: &str,
+    chunk_size: usize,
+) -> Result<PathBuf> {
+    ensure!(
+        !passphrase.is_empty(),
+        "Passphrase is required for transfer folders."
+    );
+    let now = time();
+    let self_addr = context.get_primary_self_addr().await?;
+    let stem = chrono::DateTime::<chrono::Utc>::from_timestamp(now, 0)
+        .context("Invalid backup time")?
+        .format("delta-chat-transfer-%Y-%m-%d-%H%M%S")
+        .to_string();
+    let folder = dir.join(format!("{stem}-{self_addr}"));
+    ensure!(
+        !folder.exists(),
+        "Transfer folder {} already exists.",
+        folder.display()
+    );
+    create_folder(context, &folder).await?;
+
+    let context_dir = context
+        .get_blobdir()
+        .parent()
+        .context("Context dir not found")?;
+    let dbfile = TempPathGuard::new(context_dir.join(DBFILE_BACKUP_NAME));
+    export_database(context, &dbfile, String::new(), now)
+        .await
+        .context("Database export failed")?;
+
+    let blobdir = BlobDirContents::new(context).await?;
+    let mut file_size = dbfile.metadata()?.len();
+    for blob in blobdir.iter() {
+        file_size += blob.to_abs_path().metadata()?.len();
+    }
+
+    info!(
+        context,
+        "Writing backup to transfer folder {}.",
+        folder.display()
+    );
+    let (writer, mut reader) = tokio::io::duplex(PIPE_SIZE);
+    let export = async {
+        export_backup_stream(context, &dbfile, blobdir, writer, file_size)
+            .await
+            .context("Failed to write backup into stream")
+    };
+    // The reader is moved into the future so that the export stops if writing chunks fails.
+    let folder_ref = &folder;
+    let write_chunks = async move {
+        let mut buf = vec![0u8; CHUNK_INDEX_SIZE + chunk_size];
+        let mut chunks = 0u64;
+        loop {
+            let len = read_chunk(&mut reader, &mut buf[CHUNK_INDEX_SIZE..]).await?;
+            if len == 0 {
+                break;
+            }
+            buf[..CHUNK_INDEX_SIZE].copy_from_slice(&chunks.to_be_bytes());
+            let encrypted = pgp::symm_encrypt(passphrase, &buf[..CHUNK_INDEX_SIZE + len]).await?;
+            fs::write(chunk_path(folder_ref, chunks), encrypted).await?;
+            chunks += 1;
+        }
+        Ok::<_, anyhow::Error>(chunks)
+    };
+    let (export_res, chunks_res) = tokio::join!(export, write_chunks);
+    let chunks = chunks_res.context("Failed to write chunks")?;
+    export_res?;
+
+    let manifest = Manifest {
+        version: TRANSFER_VERSION,
+        chunks,
+        file_size,
+    };
+    fs::write(folder.join(MANIFEST_NAME), serde_json::to_vec(&manifest)?).await?;
+    info!(context, "Wrote {chunks} chunks to {}.", folder.display());
+    context.emit_event(EventType::ImexFileWritten(folder.clone()));
+    Ok(folder)
+}
+
+/// Imports a backup from a transfer folder created by [`export_backup_to_folder`].
+pub(crate) async fn import_backup_from_folder(
+    context: &Context,
+    folder: &Path,
+    passphrase: &str,
+) -> Result<()> {
+    ensure!(
+        !context.is_configured().await?,
+        "Cannot import backups to accounts in use."
+    );
+    ensure!(
+        !context.scheduler.is_running().await,
+        "Cannot import backup, IO is running."
+    );
+    let manifest = fs::read(folder.join(MANIFEST_NAME))
+        .await
+        .with_context(|| {
+            format!(
+                "No manifest in {}, transfer is incomplete",
+                folder.display()
+            )
+        })?;
+    let manifest: Manifest =
+        serde_json::from_slice(&manifest).context("Failed to parse manifest")?;
+    ensure!(
+        manifest.version <= TRANSFER_VERSION,
+        "Transfer folder version {} is not supported.",
+        manifest.version
+    );
+    info!(
+        context,
+        "Importing {} chunks from {}.",
+        manifest.chunks,
+        folder.display()
+    );
+
+    let Manifest {
+        chunks, file_size, ..
+    } = manifest;
+    let (mut writer, reader) = tokio::io::duplex(PIPE_SIZE);
+    let import = async {
+        import_backup_stream(context, reader, file_size, String::new())
+            .await
+            .context("Failed to import backup from stream")
+    };
+    // The writer is moved into the future
+    // so that the import sees the end of the stream if reading chunks fails.
+    let read_chunks = async move {
+        for index in 0..chunks {
+            let path = chunk_path(folder, index);
+            let encrypted = fs::read(&path)
+                .await
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let plain = pgp::symm_decrypt(passphrase, Cursor::new(encrypted))
+                .await
+                .with_context(|| format!("Failed to decrypt {}", path.display()))?;
+            ensure!(
+                plain.len() >= CHUNK_INDEX_SIZE && plain[..CHUNK_INDEX_SIZE] == index.to_be_bytes(),
+                "Unexpected chunk {}.",
+                path.display()
+            );
+            if writer.write_all(&plain[CHUNK_INDEX_SIZE..]).await.is_err() {
+                // The import stopped reading and reports its own error.
+                return Ok(());
+            }
+        }
+        writer.shutdown().await.ok();
+        Ok(())
+    };
+    let (import_res, chunks_res) = tokio::join!(import, read_chunks);
+    // If reading chunks fails, the import fails as well because of the truncated stream,
+    // but the chunk error is more useful.
+    chunks_res?;
+    import_res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::{get_chat_msgs, send_msg, ChatItem};
+    use crate::imex::{imex, ImexMode};
+    use crate::message::{Message, Viewtype};
+    use crate::test_utils::TestContextManager;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_transfer_folder() -> Result<()> {
+        let mut tcm = TestContextManager::new();
+        let alice = &tcm.alice().await;
+        let dir = tempfile::tempdir()?;
+
+        let self_chat = alice.get_self_chat().await;
+        let mut msg = Message::new(Viewtype::File);
+        msg.set_file_from_bytes(alice, "hello.txt", b"i am attachment", None)?;
+        send_msg(alice, self_chat.id, &mut msg).await?;
+
+        // Use small chunks to get many of them.
+        let folder = export_backup_to_folder(alice, dir.path(), "secret", 4096).await?;
+        assert!(chunk_path(&folder, 2).exists());
+
+        let alice2 = &tcm.unconfigured().await;
+        assert!(import_backup_from_folder(alice2, &folder, "wrong")
+            .await
+            .is_err());
+
+        let alice2 = &tcm.unconfigured().await;
+        imex(
+            alice2,
+            ImexMode::ImportBackupFromFolder,
+            &folder,
+            Some("secret".to_string()),
+        )
+        .await?;
+        assert!(alice2.is_configured().await?);
+        let self_chat = alice2.get_self_chat().await;
+        let msgs = get_chat_msgs(alice2, self_chat.id).await?;
+        let msg_id = match msgs.last().unwrap() {
+            ChatItem::Message { msg_id } => *msg_id,
+            _ => panic!("wrong chat item"),
+        };
+        let msg = Message::load_from_db(alice2, msg_id).await?;
+        let path = msg.get_file(alice2).unwrap();
+        assert_eq!(fs::read(path).await?, b"i am attachment");
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_transfer_folder_incomplete() -> Result<()> {
+        let mut tcm = TestContextManager::new();
+        let alice = &tcm.alice().await;
+        let dir = tempfile::tempdir()?;
+
+        let folder = export_backup_to_folder(alice, dir.path(), "secret", 4096).await?;
+        fs::remove_file(chunk_path(&folder, 1)).await?;
+        let alice2 = &tcm.unconfigured().await;
+        assert!(import_backup_from_folder(alice2, &folder, "secret")
+            .await
+            .is_err());
+
+        fs::remove_file(folder.join(MANIFEST_NAME)).await?;
+        assert!(import_backup_from_folder(alice2, &folder, "secret")
+            .await
+            .is_err());
+        Ok(())
+    }
+}
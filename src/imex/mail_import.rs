@@ -0,0 +1,295 @@
+//! Import of e-mails from other mail clients.
+//!
+//! Supports a single mbox file, a single `.eml` file
+//! or a directory containing `.eml` files.
+//! The messages are passed to [`receive_imf_inner`] in the order of their `Date` header,
+//! so that replies are assigned to the chats of the messages they reply to.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{ensure, Context as _, Result};
+use sha2::{Digest, Sha256};
+
+use crate::chatlist_events;
+use crate::context::Context;
+use crate::events::EventType;
+use crate::imap;
+use crate::message::rfc724_mid_exists;
+use crate::receive_imf::receive_imf_inner;
+use crate::tools::{get_filesuffix_lc, time};
+
+/// Summary of [`import_mails`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct MailImportStats {
+    /// Number of imported messages.
+    pub imported: usize,
+
+    /// Number of messages skipped because they are already in the database.
+    pub duplicates: usize,
+
+    /// Number of messages that could not be imported.
+    pub failed: usize,
+}
+
+/// Source of a single message to import.
+enum MailSource<'a> {
+    /// Message contained in an already loaded mbox file.
+    Mbox(&'a [u8]),
+
+    /// Path to an `.eml` file.
+    File(PathBuf),
+}
+
+impl MailSource<'_> {
+    async fn read(&self) -> Result<Vec<u8>> {
+        match self {
+            MailSource::Mbox(raw) => Ok(unescape_mbox_message(raw)),
+            MailSource::File(path) => tokio::fs::read(path)
+                .await
+                .with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+}
+
+/// Imports all messages from an mbox file, an `.eml` file or a directory of `.eml` files.
+pub(crate) async fn import_mails(context: &Context, path: &Path) -> Result<MailImportStats> {
+    let attr = tokio::fs::metadata(path).await?;
+    let mbox;
+    let sources = if attr.is_dir() {
+        let mut sources = Vec::new();
+        let mut dir_handle = tokio::fs::read_dir(path).await?;
+        while let Some(entry) = dir_handle.next_entry().await? {
+            let name = entry.file_name();
+            if get_filesuffix_lc(&name.to_string_lossy()).as_deref() == Some("eml") {
+                sources.push(MailSource::File(entry.path()));
+            }
+        }
+        sources
+    } else {
+        mbox = tokio::fs::read(path).await?;
+        if mbox.starts_with(b"From ") {
+            split_mbox(&mbox)
+                .into_iter()
+                .map(MailSource::Mbox)
+                .collect()
+        } else {
+            vec![MailSource::File(path.to_path_buf())]
+        }
+    };
+    ensure!(
+        !sources.is_empty(),
+        "No messages found in {}.",
+        path.display()
+    );
+
+    // Receive parents before replies.
+    let mut order = Vec::with_capacity(sources.len());
+    for (i, source) in sources.iter().enumerate() {
+        let timestamp = match source.read().await {
+            Ok(raw) => get_date(&raw),
+            Err(_) => 0,
+        };
+        order.push((timestamp, i));
+    }
+    order.sort_unstable();
+
+    let mut stats = MailImportStats::default();
+    let total = order.len();
+    for (done, (_, i)) in order.into_iter().enumerate() {
+        match import_mail(context, &sources[i]).await {
+            Ok(true) => stats.imported += 1,
+            Ok(false) => stats.duplicates += 1,
+            Err(err) => {
+                warn!(context, "Failed to import message: {err:#}.");
+                stats.failed += 1;
+            }
+        }
+        let progress = 10 + 980 * (done + 1) / total;
+        context.emit_event(EventType::ImexProgress(progress));
+    }
+    info!(
+        context,
+        "Imported {} messages from {}, skipped {} duplicates, {} failed.",
+        stats.imported,
+        path.display(),
+        stats.duplicates,
+        stats.failed
+    );
+
+    context.emit_msgs_changed_without_ids();
+    chatlist_events::emit_chatlist_changed(context);
+    Ok(stats)
+}
+
+/// Imports a single message.
+///
+/// Returns false if the message is already in the database.
+async fn import_mail(context: &Context, source: &MailSource<'_>) -> Result<bool> {
+    let raw = source.read().await?;
+    let headers = mailparse::parse_headers(&raw)
+        .context("Failed to parse headers")?
+        .0;
+    // Messages without Message-ID get a stable ID,
+    // so that importing the same archive twice does not duplicate them.
+    let rfc724_mid = imap::prefetch_get_message_id(&headers).unwrap_or_else(|| {
+        format!(
+            "{}@import.delta.chat",
+            hex::encode(&Sha256::digest(&raw)[..16])
+        )
+    });
+    if rfc724_mid_exists(context, &rfc724_mid).await?.is_some() {
+        return Ok(false);
+    }
+
+    let seen = true;
+    let fetching_existing_messages = true;
+    let Some(received) = receive_imf_inner(
+        context,
+        "",
+        0,
+        0,
+        &rfc724_mid,
+        &raw,
+        seen,
+        None,
+        fetching_existing_messages,
+        None,
+    )
+    .await?
+    else {
+        return Ok(false);
+    };
+
+    // Received messages are normally sorted after the last seen message of the chat,
+    // but imported history should be sorted by the date of sending.
+    for msg_id in &received.msg_ids {
+        context
+            .sql
+            .execute(
+                "UPDATE msgs SET timestamp=MIN(timestamp_sent, ?) WHERE id=? AND timestamp_sent>0",
+                (time(), msg_id),
+            )
+            .await?;
+    }
+    Ok(true)
+}
+
+/// Returns the `Date` header of a message as a unix timestamp or 0 if it can't be parsed.
+fn get_date(raw: &[u8]) -> i64 {
+    let Ok((headers, _)) = mailparse::parse_headers(raw) else {
+        return 0;
+    };
+    headers
+        .iter()
+        .find(|header| header.get_key_ref().eq_ignore_ascii_case("Date"))
+        .and_then(|header| mailparse::dateparse(&header.get_value()).ok())
+        .unwrap_or_default()
+}
+
+/// Splits an mbox file into messages.
+///
+/// Each message starts with a `From ` line at the beginning of the file or after an empty line.
+/// The returned messages do not contain the `From ` lines.
+fn split_mbox(mbox: &[u8]) -> Vec<&[u8]> {
+    let mut messages = Vec::new();
+    let mut start = None;
+    let mut pos = 0;
+    let mut prev_line_empty = true;
+    while pos < mbox.len() {
+        let line_end = mbox[pos..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(mbox.len(), |i| pos + i + 1);
+        let line = &mbox[pos..line_end];
+        if prev_line_empty && line.starts_with(b"From ") {
+            if let Some(start) = start {
+                messages.push(&mbox[start..pos]);
+            }
+            start = Some(line_end);
+        }
+        prev_line_empty = line == b"\n" || line == b"\r\n";
+        pos = line_end;
+    }
+    if let Some(start) = start {
+        messages.push(&mbox[start..]);
+    }
+    messages
+}
+
+/// Removes one `>` from lines escaped as `>From ` in mboxrd format.
+fn unescape_mbox_message(raw: &[u8]) -> Vec<u8> {
+    let mut res = Vec::with_capacity(raw.len());
+    for line in raw.split_inclusive(|&b| b == b'\n') {
+        let quotes = line.iter().take_while(|&&b| b == b'>').count();
+        if quotes > 0 && line[quotes..].starts_with(b"From ") {
+            res.extend_from_slice(&line[1..]);
+        } else {
+            res.extend_from_slice(line);
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::Chat;
+    use crate::imex::{imex, ImexMode};
+    use crate::test_utils::TestContext;
+
+    const MBOX: &[u8] = b"From bob@example.net Mon Mar  2 10:00:00 2020\n\
+        From: Bob <bob@example.net>\n\
+        To: alice@example.org\n\
+        Subject: Re: Plans\n\
+        Message-ID: <2@example.net>\n\
+        In-Reply-To: <1@example.org>\n\
+        Date: Mon, 2 Mar 2020 10:00:00 +0000\n\
+        \n\
+        Sounds good.\n\
+        >From now on, let's meet on mondays.\n\
+        \n\
+        From alice@example.org Sun Mar  1 10:00:00 2020\n\
+        From: Alice <alice@example.org>\n\
+        To: bob@example.net\n\
+        Subject: Plans\n\
+        Message-ID: <1@example.org>\n\
+        Date: Sun, 1 Mar 2020 10:00:00 +0000\n\
+        \n\
+        Let's meet tomorrow.\n";
+
+    #[test]
+    fn test_split_mbox() {
+        let messages = split_mbox(MBOX);
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].starts_with(b"From: Bob"));
+        assert!(messages[1].starts_with(b"From: Alice"));
+        assert!(String::from_utf8(unescape_mbox_message(messages[0]))
+            .unwrap()
+            .contains("\nFrom now on"));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_import_mbox() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("archive.mbox");
+        tokio::fs::write(&path, MBOX).await?;
+
+        imex(&t, ImexMode::ImportMails, &path, None).await?;
+        let msg = t.get_last_msg().await;
+        assert_eq!(
+            msg.get_text(),
+            "Sounds good.\nFrom now on, let's meet on mondays."
+        );
+        assert_eq!(msg.get_timestamp(), 1583143200);
+        let chat = Chat::load_from_db(&t, msg.chat_id).await?;
+        let msgs = crate::chat::get_chat_msgs(&t, chat.id).await?;
+        assert_eq!(msgs.len(), 2);
+
+        // Importing again does not duplicate messages.
+        let stats = import_mails(&t, &path).await?;
+        assert_eq!(stats.imported, 0);
+        assert_eq!(stats.duplicates, 2);
+        Ok(())
+    }
+}
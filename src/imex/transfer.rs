@@ -18,39 +18,72 @@
 //! download to an impersonated getter.
 //!
 //! Protocol starts by getter opening a bidirectional QUIC stream
-//! to the provider and sending authentication token.
+//! to the provider and sending authentication token,
+//! followed by the offset to resume the transfer at
+//! as an unsigned 64-bit big endian integer
+//! and the BLAKE3 hash of the chunk preceding this offset.
+//! Offset and hash are zero if the getter has no data yet.
 //! Provider verifies received authentication token,
 //! sends the size of all files in a backup (database and all blobs)
-//! as an unsigned 64-bit big endian integer and streams the backup in tar format.
-//! Getter receives the backup and acknowledges successful reception
+//! as an unsigned 64-bit big endian integer
+//! and the offset it starts streaming at,
+//! which is the requested offset if the hash matches and zero otherwise.
+//! Then the provider streams the backup in tar format split into chunks of [`CHUNK_SIZE`] bytes,
+//! each preceded by its length as an unsigned 32-bit big endian integer
+//! and followed by its BLAKE3 hash.
+//! A chunk of zero length marks the end of the backup.
+//! Getter verifies each chunk and writes it to a file,
+//! so an interrupted transfer is resumed by connecting again,
+//! also when the same QR code is scanned again after a restart.
+//! After importing the backup, the getter acknowledges successful reception
 //! by sending a single byte.
 //! Provider closes the endpoint after receiving an acknowledgment.
 
 use std::future::Future;
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::Poll;
+use std::time::Duration;
 
-use anyhow::{bail, format_err, Context as _, Result};
+use anyhow::{bail, ensure, format_err, Context as _, Result};
 use futures_lite::FutureExt;
+use iroh::endpoint::{Connection, SendStream};
 use iroh::{Endpoint, RelayMode};
 use tokio::fs;
+use tokio::io::AsyncWriteExt;
 use tokio::task::JoinHandle;
 use tokio_util::sync::CancellationToken;
 
 use crate::chat::add_device_msg;
 use crate::context::Context;
 use crate::imex::BlobDirContents;
+use crate::log::LogExt;
 use crate::message::Message;
 use crate::qr::Qr;
 use crate::stock_str::backup_transfer_msg_body;
 use crate::tools::{create_id, time, TempPathGuard};
 use crate::EventType;
 
-use super::{export_backup_stream, export_database, import_backup_stream, DBFILE_BACKUP_NAME};
+use super::folder_transfer::chunk_path;
+use super::{
+    export_backup_stream, export_database, import_backup_stream, read_chunk, DBFILE_BACKUP_NAME,
+};
 
 /// ALPN protocol identifier for the backup transfer protocol.
-const BACKUP_ALPN: &[u8] = b"/deltachat/backup";
+const BACKUP_ALPN: &[u8] = b"/deltachat/backup2";
+
+/// Size of the chunks the backup is split into for verification and resumption.
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Size of the BLAKE3 hash of a chunk.
+const HASH_SIZE: usize = 32;
+
+/// How many times the getter reconnects after the transfer was interrupted.
+const MAX_RECONNECT_ATTEMPTS: usize = 5;
+
+/// Delay before reconnecting after the transfer was interrupted.
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
 
 /// Provide or send a backup of this device.
 ///
@@ -177,22 +210,39 @@ async fn handle_connection(
         }
 
         info!(context, "Received valid backup authentication token.");
+        let mut offset_buf = [0u8; 8];
+        recv_stream.read_exact(&mut offset_buf).await?;
+        let offset = u64::from_be_bytes(offset_buf);
+        let mut hash = [0u8; HASH_SIZE];
+        recv_stream.read_exact(&mut hash).await?;
+
         // Emit a nonzero progress so that UIs can display smth like "Transferring...".
         context.emit_event(EventType::ImexProgress(1));
 
-        let blobdir = BlobDirContents::new(&context).await?;
-
         let mut file_size = 0;
         file_size += dbfile.metadata()?.len();
-        for blob in blobdir.iter() {
+        for blob in BlobDirContents::new(&context).await?.iter() {
             file_size += blob.to_abs_path().metadata()?.len()
         }
 
         send_stream.write_all(&file_size.to_be_bytes()).await?;
 
-        export_backup_stream(&context, &dbfile, blobdir, send_stream, file_size)
-            .await
-            .context("Failed to write backup into QUIC stream")?;
+        let resumed = offset > 0
+            && offset % CHUNK_SIZE as u64 == 0
+            && Self::send_chunks(
+                &context,
+                &dbfile,
+                file_size,
+                &mut send_stream,
+                offset,
+                Some(hash),
+            )
+            .await?;
+        if resumed {
+            info!(context, "Resumed backup transfer at {offset} bytes.");
+        } else {
+            Self::send_chunks(&context, &dbfile, file_size, &mut send_stream, 0, None).await?;
+        }
         info!(context, "Finished writing backup into QUIC stream.");
         let mut buf = [0u8; 1];
         info!(context, "Waiting for acknowledgment.");
@@ -206,6 +256,70 @@ async fn handle_connection(
         Ok(())
     }
 
+    /// Streams the backup into `send_stream` in chunks, starting at `offset`.
+    ///
+    /// If `expected_hash` is given, the chunk preceding `offset` must have this hash,
+    /// otherwise nothing is sent and false is returned.
+    async fn send_chunks(
+        context: &Context,
+        dbfile: &Path,
+        file_size: u64,
+        send_stream: &mut SendStream,
+        offset: u64,
+        expected_hash: Option<[u8; HASH_SIZE]>,
+    ) -> Result<bool> {
+        let blobdir = BlobDirContents::new(context).await?;
+        let (writer, mut reader) = tokio::io::duplex(CHUNK_SIZE);
+        let export = export_backup_stream(context, dbfile, blobdir, writer, file_size);
+        // The reader is moved into the future so that the export stops if sending fails.
+        let send = async move {
+            let mut buf = vec![0u8; CHUNK_SIZE];
+            let mut position = 0;
+            while position < offset {
+                // Skip the chunks the getter already has.
+                let len = read_chunk(&mut reader, &mut buf).await?;
+                if len < CHUNK_SIZE {
+                    return Ok(false);
+                }
+                position += CHUNK_SIZE as u64;
+                if position == offset {
+                    if let Some(expected_hash) = expected_hash {
+                        if *blake3::hash(&buf).as_bytes() != expected_hash {
+                            return Ok(false);
+                        }
+                    }
+                }
+            }
+            send_stream.write_all(&offset.to_be_bytes()).await?;
+            loop {
+                let len = read_chunk(&mut reader, &mut buf).await?;
+                let chunk = &buf[..len];
+                send_stream
+                    .write_all(&u32::try_from(len)?.to_be_bytes())
+                    .await?;
+                if len == 0 {
+                    break;
+                }
+                send_stream.write_all(chunk).await?;
+                send_stream
+                    .write_all(blake3::hash(chunk).as_bytes())
+                    .await?;
+                position += len as u64;
+                context.emit_event(EventType::BackupTransferProgress {
+                    transferred: position.min(file_size),
+                    total: file_size,
+                });
+            }
+            Ok::<_, anyhow::Error>(true)
+        };
+        let (export_res, send_res) = tokio::join!(export, send);
+        let sent = send_res?;
+        if sent {
+            export_res.context("Failed to write backup into QUIC stream")?;
+        }
+        Ok(sent)
+    }
+
     async fn accept_loop(
         context: Context,
         endpoint: Endpoint,
@@ -232,23 +346,33 @@ async fn accept_loop(
                         let context = context.clone();
                         let auth_token = auth_token.clone();
                         let dbfile = dbfile.clone();
-                        if let Err(err) = Self::handle_connection(context.clone(), conn, auth_token, dbfile).race(
+                        let res = async {
+                            Some(Self::handle_connection(context.clone(), conn, auth_token, dbfile).await)
+                        }.race(
                             async {
                                 cancel_token.recv().await.ok();
-                                Err(format_err!("Backup transfer cancelled"))
+                                None
                             }
                         ).race(
                             async {
                                 drop_token.cancelled().await;
-                                Err(format_err!("Backup provider dropped"))
+                                None
+                            }
+                        ).await;
+                        match res {
+                            Some(Ok(())) => {
+                                info!(context, "Backup transfer finished successfully.");
+                                break;
+                            }
+                            Some(Err(err)) => {
+                                // Keep accepting connections so that the getter can resume the transfer.
+                                warn!(context, "Error while handling backup connection: {err:#}.");
+                            }
+                            None => {
+                                info!(context, "Backup transfer cancelled, stopping accept loop.");
+                                context.emit_event(EventType::ImexProgress(0));
+                                break;
                             }
-                        ).await {
-                            warn!(context, "Error while handling backup connection: {err:#}.");
-                            context.emit_event(EventType::ImexProgress(0));
-                            break;
-                        } else {
-                            info!(context, "Backup transfer finished successfully.");
-                            break;
                         }
                     } else {
                         break;
@@ -289,23 +413,106 @@ fn poll(mut self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Self:
     }
 }
 
-pub async fn get_backup2(
+/// Prefix of the directories interrupted backup transfers are kept in.
+const PART_DIR_PREFIX: &str = "backup-transfer-";
+
+/// Suffix of the directories interrupted backup transfers are kept in.
+const PART_DIR_SUFFIX: &str = ".part";
+
+/// Returns the path of the directory the backup identified by `auth_token` is received into.
+///
+/// The directory is named after the authentication token,
+/// so that scanning the same QR code again resumes the transfer.
+fn part_dir_path(context: &Context, auth_token: &str) -> Result<PathBuf> {
+    let context_dir = context
+        .get_blobdir()
+        .parent()
+        .context("Context dir not found")?;
+    let hash = blake3::hash(auth_token.as_bytes()).to_hex();
+    Ok(context_dir.join(format!("{PART_DIR_PREFIX}{}{PART_DIR_SUFFIX}", &hash[..16])))
+}
+
+/// Removes the data of interrupted backup transfers except for the one in `keep`.
+///
+/// The data of a transfer can't be used anymore
+/// once the provider shows a new QR code, so it would only waste disk space.
+pub(crate) async fn remove_stale_transfer_parts(
     context: &Context,
-    node_addr: iroh::NodeAddr,
-    auth_token: String,
+    keep: Option<&Path>,
 ) -> Result<()> {
-    let relay_mode = RelayMode::Disabled;
+    let context_dir = context
+        .get_blobdir()
+        .parent()
+        .context("Context dir not found")?;
+    let mut dir = fs::read_dir(context_dir).await?;
+    while let Some(entry) = dir.next_entry().await? {
+        let name = entry.file_name();
+        let Some(name) = name.to_str() else {
+            continue;
+        };
+        if !name.starts_with(PART_DIR_PREFIX) || !name.ends_with(PART_DIR_SUFFIX) {
+            continue;
+        }
+        let path = entry.path();
+        if Some(path.as_path()) == keep {
+            continue;
+        }
+        info!(context, "Removing stale backup transfer data {name}.");
+        fs::remove_dir_all(&path).await?;
+    }
+    Ok(())
+}
 
-    let endpoint = Endpoint::builder().relay_mode(relay_mode).bind().await?;
+/// Returns the number of complete chunks received into `part_dir`
+/// and the hash of the last of them.
+///
+/// Incomplete chunks left from an interruption are removed.
+async fn complete_chunks(part_dir: &Path) -> Result<(u64, [u8; HASH_SIZE])> {
+    let mut count = 0;
+    let mut hash = [0u8; HASH_SIZE];
+    loop {
+        let path = chunk_path(part_dir, count);
+        match fs::read(&path).await {
+            Ok(chunk) if chunk.len() == CHUNK_SIZE => {
+                hash = *blake3::hash(&chunk).as_bytes();
+                count += 1;
+            }
+            Ok(_) => {
+                // Only complete chunks can be verified by the provider.
+                fs::remove_file(&path).await?;
+                break;
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => break,
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok((count, hash))
+}
+
+/// Receives the backup into chunk files in `part_dir`,
+/// resuming after the chunks already in the directory if possible.
+///
+/// Returns the connection and the stream to acknowledge the reception on
+/// together with the size of all files in the backup.
+async fn receive_backup(
+    context: &Context,
+    endpoint: &Endpoint,
+    node_addr: iroh::NodeAddr,
+    auth_token: &str,
+    part_dir: &Path,
+) -> Result<(Connection, SendStream, u64)> {
+    fs::create_dir_all(part_dir).await?;
+    let (count, hash) = complete_chunks(part_dir).await?;
+    let offset = count * CHUNK_SIZE as u64;
 
     let conn = endpoint.connect(node_addr, BACKUP_ALPN).await?;
     let (mut send_stream, mut recv_stream) = conn.open_bi().await?;
     info!(context, "Sending backup authentication token.");
     send_stream.write_all(auth_token.as_bytes()).await?;
+    send_stream.write_all(&offset.to_be_bytes()).await?;
+    send_stream.write_all(&hash).await?;
 
-    let passphrase = String::new();
     info!(context, "Starting to read backup from the stream.");
-
     let mut file_size_buf = [0u8; 8];
     recv_stream.read_exact(&mut file_size_buf).await?;
     let file_size = u64::from_be_bytes(file_size_buf);
@@ -313,10 +520,128 @@ pub async fn get_backup2(
     // Emit a nonzero progress so that UIs can display smth like "Transferring...".
     context.emit_event(EventType::ImexProgress(1));
 
-    import_backup_stream(context, recv_stream, file_size, passphrase)
+    let mut start_buf = [0u8; 8];
+    recv_stream.read_exact(&mut start_buf).await?;
+    let start = u64::from_be_bytes(start_buf);
+    if start > 0 {
+        ensure!(
+            start == offset,
+            "Provider resumed backup transfer at unexpected offset {start}"
+        );
+        info!(context, "Resuming backup transfer at {start} bytes.");
+    } else if offset > 0 {
+        info!(
+            context,
+            "Received data does not match the backup, restarting transfer."
+        );
+        fs::remove_dir_all(part_dir).await?;
+        fs::create_dir_all(part_dir).await?;
+    }
+
+    let mut index = start / CHUNK_SIZE as u64;
+    let mut transferred = start;
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    loop {
+        let mut len_buf = [0u8; 4];
+        recv_stream.read_exact(&mut len_buf).await?;
+        let len = usize::try_from(u32::from_be_bytes(len_buf))?;
+        if len == 0 {
+            break;
+        }
+        ensure!(
+            len <= CHUNK_SIZE,
+            "Backup chunk of {len} bytes is too large"
+        );
+        let chunk = &mut buf[..len];
+        recv_stream.read_exact(chunk).await?;
+        let mut chunk_hash = [0u8; HASH_SIZE];
+        recv_stream.read_exact(&mut chunk_hash).await?;
+        ensure!(
+            *blake3::hash(chunk).as_bytes() == chunk_hash,
+            "Hash mismatch for backup chunk at {transferred} bytes"
+        );
+        let mut file = fs::File::create(chunk_path(part_dir, index)).await?;
+        file.write_all(chunk).await?;
+        file.sync_all().await?;
+        index += 1;
+        transferred += len as u64;
+        context.emit_event(EventType::BackupTransferProgress {
+            transferred: transferred.min(file_size),
+            total: file_size,
+        });
+    }
+    info!(context, "Received backup of {transferred} bytes.");
+    Ok((conn, send_stream, file_size))
+}
+
+/// Imports the backup received into chunk files in `part_dir`.
+///
+/// Each chunk is removed as soon as it is passed to the import,
+/// so the received backup does not take up disk space
+/// a second time while it is unpacked.
+async fn import_chunks(context: &Context, part_dir: &Path, file_size: u64) -> Result<()> {
+    let (mut writer, reader) = tokio::io::duplex(CHUNK_SIZE);
+    let feed = async move {
+        let mut index = 0;
+        loop {
+            let path = chunk_path(part_dir, index);
+            let chunk = match fs::read(&path).await {
+                Ok(chunk) => chunk,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => break,
+                Err(err) => return Err(err.into()),
+            };
+            fs::remove_file(&path).await?;
+            writer.write_all(&chunk).await?;
+            index += 1;
+        }
+        Ok::<_, anyhow::Error>(())
+    };
+    let passphrase = String::new();
+    let import = import_backup_stream(context, reader, file_size, passphrase);
+    let (feed_res, import_res) = tokio::join!(feed, import);
+    import_res?;
+    feed_res
+}
+
+pub async fn get_backup2(
+    context: &Context,
+    node_addr: iroh::NodeAddr,
+    auth_token: String,
+) -> Result<()> {
+    let relay_mode = RelayMode::Disabled;
+
+    let endpoint = Endpoint::builder().relay_mode(relay_mode).bind().await?;
+    let part_dir = part_dir_path(context, &auth_token)?;
+    remove_stale_transfer_parts(context, Some(&part_dir)).await?;
+
+    let mut attempts = 0;
+    let (_conn, mut send_stream, file_size) = loop {
+        match receive_backup(
+            context,
+            &endpoint,
+            node_addr.clone(),
+            &auth_token,
+            &part_dir,
+        )
         .await
-        .context("Failed to import backup from QUIC stream")?;
-    info!(context, "Finished importing backup from the stream.");
+        {
+            Ok(res) => break res,
+            Err(err) if attempts < MAX_RECONNECT_ATTEMPTS => {
+                attempts += 1;
+                warn!(
+                    context,
+                    "Backup transfer interrupted, reconnecting (attempt {attempts}): {err:#}."
+                );
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+            Err(err) => return Err(err),
+        }
+    };
+
+    let res = import_chunks(context, &part_dir, file_size).await;
+    fs::remove_dir_all(&part_dir).await.log_err(context).ok();
+    res.context("Failed to import received backup")?;
+    info!(context, "Finished importing backup.");
     context.emit_event(EventType::ImexProgress(1000));
 
     // Send an acknowledgement, but ignore the errors.
@@ -454,6 +779,114 @@ async fn test_send_receive() {
         }
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_resume_with_mismatching_data() -> Result<()> {
+        let mut tcm = TestContextManager::new();
+        let ctx0 = tcm.alice().await;
+        let provider = BackupProvider::prepare(&ctx0).await?;
+        let qr = provider.qr();
+        let Qr::Backup2 { auth_token, .. } = &qr else {
+            unreachable!();
+        };
+
+        // Data left from an interrupted transfer of another backup
+        // must not end up in the received backup.
+        let ctx1 = tcm.unconfigured().await;
+        let part_dir = part_dir_path(&ctx1, auth_token)?;
+        fs::create_dir_all(&part_dir).await?;
+        fs::write(chunk_path(&part_dir, 0), vec![b'x'; CHUNK_SIZE]).await?;
+        fs::write(chunk_path(&part_dir, 1), vec![b'x'; 100]).await?;
+
+        get_backup(&ctx1, qr).await?;
+        tokio::time::timeout(Duration::from_secs(30), provider)
+            .await
+            .expect("timed out")
+            .expect("error in provider");
+        assert!(ctx1.is_configured().await?);
+        assert!(!part_dir.exists());
+        ctx1.evtracker
+            .get_matching(|ev| matches!(ev, EventType::BackupTransferProgress { .. }))
+            .await;
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_resume_interrupted_transfer() -> Result<()> {
+        let mut tcm = TestContextManager::new();
+        let ctx0 = tcm.alice().await;
+
+        // Send an attachment spanning several chunks.
+        let data: Vec<u8> = (0..CHUNK_SIZE * 7 / 2).map(|i| (i % 251) as u8).collect();
+        let file = ctx0.get_blobdir().join("large.bin");
+        fs::write(&file, &data).await?;
+        let mut msg = Message::new(Viewtype::File);
+        msg.set_file_and_deduplicate(&ctx0, &file, Some("large.bin"), None)?;
+        let self_chat = ctx0.get_self_chat().await;
+        send_msg(&ctx0, self_chat.id, &mut msg).await?;
+
+        let provider = BackupProvider::prepare(&ctx0).await?;
+        let qr = provider.qr();
+        let Qr::Backup2 {
+            node_addr,
+            auth_token,
+        } = &qr
+        else {
+            unreachable!();
+        };
+
+        // Receive the backup, but close the connection without acknowledging it.
+        let ctx1 = tcm.unconfigured().await;
+        let part_dir = part_dir_path(&ctx1, auth_token)?;
+        let endpoint = Endpoint::builder()
+            .relay_mode(RelayMode::Disabled)
+            .bind()
+            .await?;
+        let (conn, send_stream, _file_size) =
+            receive_backup(&ctx1, &endpoint, node_addr.clone(), auth_token, &part_dir).await?;
+        drop(send_stream);
+        drop(conn);
+
+        // Simulate an interruption in the middle of the third chunk.
+        let mut index = 2;
+        while chunk_path(&part_dir, index).exists() {
+            fs::remove_file(chunk_path(&part_dir, index)).await?;
+            index += 1;
+        }
+        fs::write(chunk_path(&part_dir, 2), &data[..100]).await?;
+
+        // Data left from another transfer is removed.
+        let stale_dir = part_dir_path(&ctx1, "stale")?;
+        fs::create_dir_all(&stale_dir).await?;
+
+        ctx1.evtracker.clear_events();
+        get_backup(&ctx1, qr).await?;
+        tokio::time::timeout(Duration::from_secs(30), provider)
+            .await
+            .expect("timed out")
+            .expect("error in provider");
+        assert!(!part_dir.exists());
+        assert!(!stale_dir.exists());
+
+        // The transfer continues after the two complete chunks.
+        let EventType::BackupTransferProgress { transferred, .. } = ctx1
+            .evtracker
+            .get_matching(|ev| matches!(ev, EventType::BackupTransferProgress { .. }))
+            .await
+        else {
+            unreachable!();
+        };
+        assert_eq!(transferred, 3 * CHUNK_SIZE as u64);
+
+        let self_chat = ctx1.get_self_chat().await;
+        let msgs = get_chat_msgs(&ctx1, self_chat.id).await?;
+        let ChatItem::Message { msg_id } = msgs.last().unwrap() else {
+            panic!("wrong chat item");
+        };
+        let msg = Message::load_from_db(&ctx1, *msg_id).await?;
+        assert_eq!(fs::read(msg.get_file(&ctx1).unwrap()).await?, data);
+        Ok(())
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_drop_provider() {
         let mut tcm = TestContextManager::new();
@@ -15,10 +15,15 @@
 use rand::thread_rng;
 use tokio::runtime::Handle;
 
+use crate::chat::{self, ChatIdBlocked};
 use crate::config::Config;
-use crate::constants::KeyGenType;
+use crate::constants::{Blocked, KeyGenType, DC_GCL_VERIFIED_ONLY};
+use crate::contact::Contact;
 use crate::context::Context;
 use crate::log::LogExt;
+use crate::message::{Message, Viewtype};
+use crate::mimeparser::SystemMessage;
+use crate::param::Param;
 use crate::pgp::KeyPair;
 use crate::tools::{self, time_elapsed};
 
@@ -404,11 +409,99 @@ pub(crate) async fn store_self_keypair(
 pub async fn preconfigure_keypair(context: &Context, secret_data: &str) -> Result<()> {
     let secret = SignedSecretKey::from_asc(secret_data)?.0;
     let public = secret.split_public_key()?;
+    check_imported_key(context, &public)?;
     let keypair = KeyPair { public, secret };
     store_self_keypair(context, &keypair, KeyPairUse::Default).await?;
     Ok(())
 }
 
+/// Checks that an imported key can be used for encryption
+/// and warns about expired encryption subkeys.
+///
+/// All user IDs and subkeys of the key are kept as is.
+pub(crate) fn check_imported_key(context: &Context, public: &SignedPublicKey) -> Result<()> {
+    ensure!(
+        crate::pgp::has_encryption_key(public),
+        "Key {} has no usable encryption subkey",
+        public.dc_fingerprint()
+    );
+    for fingerprint in crate::pgp::expired_encryption_subkeys(public) {
+        warn!(
+            context,
+            "Encryption subkey {} of imported key is expired.",
+            fingerprint.hex()
+        );
+    }
+    Ok(())
+}
+
+/// Replaces the own key with a newly generated key.
+///
+/// The old key is kept for decrypting old messages
+/// and is exported together with the new key by [`crate::imex::imex`].
+/// The new key is announced in the Autocrypt headers and gossiped in all groups.
+/// Verified contacts additionally receive a statement signed with the old key,
+/// so they can keep the verification for the new key.
+///
+/// Other devices of the account do not know the new key
+/// and have to be set up again.
+pub async fn rotate_self_key(context: &Context) -> Result<()> {
+    ensure!(context.is_configured().await?, "Not configured");
+    let old_keypair = load_keypair(context).await?.context("No key to rotate")?;
+    let addr = EmailAddress::new(&context.get_primary_self_addr().await?)?;
+    let keytype =
+        KeyGenType::from_i32(context.get_config_int(Config::KeyGenType).await?).unwrap_or_default();
+
+    let new_keypair = {
+        let _guard = context.generating_key_mutex.lock().await;
+        info!(context, "Generating new keypair with type {keytype}.");
+        let keypair = Handle::current()
+            .spawn_blocking(move || crate::pgp::create_keypair(addr, keytype))
+            .await??;
+        store_self_keypair(context, &keypair, KeyPairUse::Default).await?;
+        keypair
+    };
+
+    let statement = key_transition_statement(
+        &old_keypair.public.dc_fingerprint(),
+        &new_keypair.public.dc_fingerprint(),
+    );
+    let signature = crate::pgp::pk_calc_signature(statement.as_bytes(), &old_keypair.secret)?;
+    let transition = base64::engine::general_purpose::STANDARD.encode(signature);
+
+    // Gossip the new key with the next message in each group.
+    context
+        .sql
+        .execute("UPDATE chats SET gossiped_timestamp=0", ())
+        .await?;
+
+    for contact_id in Contact::get_all(context, DC_GCL_VERIFIED_ONLY, None).await? {
+        let mut msg = Message {
+            viewtype: Viewtype::Text,
+            text: "Key transition".to_string(),
+            hidden: true,
+            ..Default::default()
+        };
+        msg.param.set_cmd(SystemMessage::KeyTransition);
+        msg.param.set(Param::Arg, &transition);
+        msg.param.set_int(Param::GuaranteeE2ee, 1);
+        let chat_id = ChatIdBlocked::get_for_contact(context, contact_id, Blocked::Yes)
+            .await?
+            .id;
+        chat::send_msg(context, chat_id, &mut msg)
+            .await
+            .log_err(context)
+            .ok();
+    }
+    info!(context, "Rotated own key.");
+    Ok(())
+}
+
+/// Returns the statement signed with the old key to announce the new key.
+pub(crate) fn key_transition_statement(old: &Fingerprint, new: &Fingerprint) -> String {
+    format!("Key transition from {} to {}", old.hex(), new.hex())
+}
+
 /// A key fingerprint
 #[derive(Clone, Eq, PartialEq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct Fingerprint(Vec<u8>);
@@ -427,6 +520,11 @@ pub fn new(v: Vec<u8>) -> Fingerprint {
     pub fn hex(&self) -> String {
         hex::encode_upper(&self.0)
     }
+
+    /// Returns the raw bytes of the fingerprint.
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
 }
 
 impl From<pgp::types::Fingerprint> for Fingerprint {
@@ -483,7 +581,9 @@ mod tests {
     use once_cell::sync::Lazy;
 
     use super::*;
-    use crate::test_utils::{alice_keypair, TestContext};
+    use crate::peerstate::Peerstate;
+    use crate::test_utils::{alice_keypair, TestContext, TestContextManager};
+    use crate::EventType;
 
     static KEYPAIR: Lazy<KeyPair> = Lazy::new(alice_keypair);
 
@@ -724,6 +824,64 @@ async fn test_save_self_key_twice() {
         assert_eq!(nrows().await, 1);
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_import_gnupg_key() -> Result<()> {
+        let t = TestContext::new().await;
+        t.configure_addr("alice@example.org").await;
+        preconfigure_keypair(
+            &t,
+            include_str!("../test-data/key/gnupg-multi-uid-secret.asc"),
+        )
+        .await?;
+        t.evtracker
+            .get_matching(|evt| {
+                matches!(evt, EventType::Warning(msg) if msg.contains("2738D4E156C7A99DF0BAF818778B4E1FDAC0622F"))
+            })
+            .await;
+
+        let public = load_self_public_key(&t).await?;
+        assert_eq!(public.details.users.len(), 2);
+        assert_eq!(
+            public.dc_fingerprint().hex(),
+            "16C4927369C3EB9EC454BB348F664CFE7C235BED"
+        );
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_rotate_self_key() -> Result<()> {
+        let mut tcm = TestContextManager::new();
+        let alice = &tcm.alice().await;
+        let bob = &tcm.bob().await;
+        tcm.execute_securejoin(bob, alice).await;
+
+        // Queue an automatic message that must not delay the key transition.
+        let chat_id = alice.create_chat(bob).await.id;
+        let mut update = Message::new_text("update".to_string());
+        update.hidden = true;
+        let update_id = chat::send_msg(alice, chat_id, &mut update).await?;
+
+        let old_fingerprint = load_self_public_key(alice).await?.dc_fingerprint();
+        rotate_self_key(alice).await?;
+        let new_fingerprint = load_self_public_key(alice).await?.dc_fingerprint();
+        assert_ne!(old_fingerprint, new_fingerprint);
+        assert_eq!(load_self_secret_keyring(alice).await?.len(), 2);
+
+        let queue = alice.get_outgoing_queue().await?;
+        assert_eq!(queue.len(), 2);
+        let transition = Message::load_from_db(alice, queue[0].msg_id).await?;
+        assert_eq!(transition.param.get_cmd(), SystemMessage::KeyTransition);
+        assert_eq!(queue[1].msg_id, update_id);
+
+        let sent = alice.pop_sent_msg().await;
+        bob.recv_msg_trash(&sent).await;
+        let peerstate = Peerstate::from_addr(bob, "alice@example.org")
+            .await?
+            .unwrap();
+        assert_eq!(peerstate.verified_key_fingerprint, Some(new_fingerprint));
+        Ok(())
+    }
+
     #[test]
     fn test_fingerprint_from_str() {
         let res = Fingerprint::new(vec![
@@ -0,0 +1,205 @@
+//! # Import of public keys attached to messages.
+//!
+//! Contacts using other email clients sometimes send their public key
+//! as an ASCII-armored `.asc` attachment instead of an Autocrypt header.
+//! Keys are only considered if the message is encrypted
+//! and signed with a valid signature of the sender,
+//! so that the key cannot be injected by someone else.
+//! If such a key differs from the key we know,
+//! [`EventType::IncomingPublicKey`] is emitted so that the UI can offer importing it
+//! with [`import_key_from_msg`] after comparing fingerprints.
+
+use anyhow::{ensure, Context as _, Result};
+use deltachat_contact_tools::addr_cmp;
+
+use crate::aheader::EncryptPreference;
+use crate::chat::ChatId;
+use crate::contact::{Contact, ContactId};
+use crate::context::Context;
+use crate::events::EventType;
+use crate::key::{DcKey, SignedPublicKey};
+use crate::message::{Message, MsgId, Viewtype};
+use crate::peerstate::Peerstate;
+use crate::tools::time;
+
+/// Returns true if the message has an attachment that looks like an ASCII-armored public key.
+fn has_key_attachment(msg: &Message) -> bool {
+    msg.viewtype == Viewtype::File
+        && (msg.get_filemime().as_deref() == Some("application/pgp-keys")
+            || msg
+                .get_filename()
+                .is_some_and(|name| name.to_lowercase().ends_with(".asc")))
+}
+
+/// Loads and validates the public key attached to a message.
+///
+/// The message must be encrypted and signed,
+/// the key must be correctly self-signed
+/// and have a user ID with the address of the message sender.
+async fn load_key_from_msg(context: &Context, msg: &Message) -> Result<SignedPublicKey> {
+    ensure!(has_key_attachment(msg), "{} has no key attachment", msg.id);
+    ensure!(
+        msg.get_showpadlock(),
+        "{} is not encrypted and signed",
+        msg.id
+    );
+    let path = msg
+        .get_file(context)
+        .with_context(|| format!("{} has no file", msg.id))?;
+    let data = tokio::fs::read(&path).await?;
+    let armored = std::str::from_utf8(&data).context("Key attachment is not UTF-8")?;
+    let (key, _headers) = SignedPublicKey::from_asc(armored)?;
+    key.verify().context("Key verification failed")?;
+
+    let sender = Contact::get_by_id(context, msg.from_id).await?;
+    ensure!(
+        key.details.users.iter().any(|user| {
+            let id = String::from_utf8_lossy(user.id.id());
+            let addr = match (id.rfind('<'), id.rfind('>')) {
+                (Some(start), Some(end)) if start < end => &id[start + 1..end],
+                _ => id.trim(),
+            };
+            addr_cmp(addr, sender.get_addr())
+        }),
+        "Key does not belong to the sender {}",
+        sender.get_addr()
+    );
+    Ok(key)
+}
+
+/// Emits [`EventType::IncomingPublicKey`]
+/// if the incoming message has a valid public key of the sender attached
+/// that differs from the known key.
+pub(crate) async fn handle_incoming_msg(
+    context: &Context,
+    chat_id: ChatId,
+    msg_id: MsgId,
+) -> Result<()> {
+    let msg = Message::load_from_db(context, msg_id).await?;
+    if msg.from_id.is_special() || !has_key_attachment(&msg) {
+        return Ok(());
+    }
+    let key = match load_key_from_msg(context, &msg).await {
+        Ok(key) => key,
+        Err(err) => {
+            info!(context, "Ignoring key attached to {msg_id}: {err:#}.");
+            return Ok(());
+        }
+    };
+    let sender = Contact::get_by_id(context, msg.from_id).await?;
+    let peerstate = Peerstate::from_addr(context, sender.get_addr()).await?;
+    let current_fingerprint = peerstate
+        .as_ref()
+        .and_then(|peerstate| peerstate.peek_key(false))
+        .map(|key| key.dc_fingerprint());
+    let fingerprint = key.dc_fingerprint();
+    if current_fingerprint.as_ref() == Some(&fingerprint) {
+        return Ok(());
+    }
+    context.emit_event(EventType::IncomingPublicKey {
+        chat_id,
+        msg_id,
+        contact_id: msg.from_id,
+        fingerprint: fingerprint.hex(),
+        current_fingerprint: current_fingerprint.map(|fp| fp.hex()),
+    });
+    Ok(())
+}
+
+/// Imports the public key attached to a message as the key of the sender.
+///
+/// This should only be called after the user confirmed the import,
+/// e.g. after comparing the fingerprints from [`EventType::IncomingPublicKey`].
+/// Verification status of the contact is not changed.
+pub async fn import_key_from_msg(context: &Context, msg_id: MsgId) -> Result<ContactId> {
+    let msg = Message::load_from_db(context, msg_id).await?;
+    ensure!(!msg.from_id.is_special(), "Cannot import keys sent by self");
+    let key = load_key_from_msg(context, &msg).await?;
+    let sender = Contact::get_by_id(context, msg.from_id).await?;
+    let addr = sender.get_addr();
+
+    let timestamp = time();
+    let mut peerstate = match Peerstate::from_addr(context, addr).await? {
+        Some(mut peerstate) => {
+            if peerstate.public_key.as_ref() != Some(&key) {
+                peerstate.public_key = Some(key);
+                peerstate.recalc_fingerprint();
+            }
+            peerstate
+        }
+        None => Peerstate::from_public_key(addr, timestamp, EncryptPreference::Mutual, &key),
+    };
+    peerstate.prefer_encrypt = EncryptPreference::Mutual;
+    peerstate.save_to_db(&context.sql).await?;
+    peerstate
+        .handle_fingerprint_change(context, timestamp)
+        .await?;
+    info!(context, "Imported key of {} from {msg_id}.", msg.from_id);
+    context.emit_event(EventType::ContactsChanged(Some(msg.from_id)));
+    Ok(msg.from_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::load_self_public_key;
+    use crate::param::Param;
+    use crate::test_utils::TestContextManager;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_import_key_from_msg() -> Result<()> {
+        let mut tcm = TestContextManager::new();
+        let alice = &tcm.alice().await;
+        let bob = &tcm.bob().await;
+        let fiona = &tcm.fiona().await;
+
+        // Bob sends his key as an attachment of an encrypted message.
+        tcm.send_recv_accept(alice, bob, "Hi").await;
+        let bob_key = load_self_public_key(bob).await?;
+        let chat_id = bob.create_chat(alice).await.id;
+        let mut msg = Message::new(Viewtype::File);
+        msg.set_file_from_bytes(bob, "bob.asc", bob_key.to_asc(None).as_bytes(), None)?;
+        let sent = bob.send_msg(chat_id, &mut msg).await;
+        let msg = alice.recv_msg(&sent).await;
+        assert!(msg.get_showpadlock());
+
+        // Alice already knows Bob's key from the Autocrypt header.
+        assert!(alice
+            .evtracker
+            .get_matching_opt(alice, |evt| matches!(
+                evt,
+                EventType::IncomingPublicKey { .. }
+            ))
+            .await
+            .is_none());
+
+        // Fiona's key attached by Bob is not accepted.
+        let fiona_key = load_self_public_key(fiona).await?;
+        let mut fiona_msg = Message::new(Viewtype::File);
+        fiona_msg.set_file_from_bytes(bob, "fiona.asc", fiona_key.to_asc(None).as_bytes(), None)?;
+        let sent = bob.send_msg(chat_id, &mut fiona_msg).await;
+        let fiona_msg = alice.recv_msg(&sent).await;
+        assert!(import_key_from_msg(alice, fiona_msg.id).await.is_err());
+
+        // Keys from unencrypted messages are not accepted.
+        let mut plain_msg = Message::new(Viewtype::File);
+        plain_msg.set_file_from_bytes(bob, "bob.asc", bob_key.to_asc(None).as_bytes(), None)?;
+        plain_msg.param.set_int(Param::ForcePlaintext, 1);
+        let sent = bob.send_msg(chat_id, &mut plain_msg).await;
+        let plain_msg = alice.recv_msg(&sent).await;
+        assert!(!plain_msg.get_showpadlock());
+        assert!(import_key_from_msg(alice, plain_msg.id).await.is_err());
+
+        // Importing Bob's own key again is fine.
+        let contact_id = import_key_from_msg(alice, msg.id).await?;
+        assert_eq!(contact_id, msg.from_id);
+        let peerstate = Peerstate::from_addr(alice, "bob@example.net")
+            .await?
+            .unwrap();
+        assert_eq!(
+            peerstate.public_key_fingerprint,
+            Some(bob_key.dc_fingerprint())
+        );
+        Ok(())
+    }
+}
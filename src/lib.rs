@@ -53,26 +53,38 @@
 
 mod aheader;
 mod blob;
+pub mod blob_gc;
+pub mod bot;
 pub mod chat;
 pub mod chatlist;
 pub mod config;
 mod configure;
+pub use configure::ConfigureTraceEntry;
 pub mod constants;
 pub mod contact;
+pub mod contact_label;
 pub mod context;
 mod decrypt;
+pub mod diagnostics;
+mod dkim;
 pub mod download;
 mod e2ee;
+pub mod emoji;
 pub mod ephemeral;
+pub mod health;
 mod imap;
 pub mod imex;
 pub mod key;
 pub mod location;
 mod login_param;
 pub mod message;
+pub mod metrics;
 mod mimefactory;
+#[cfg(feature = "internals")]
+pub use mimefactory::DeterministicMime;
 pub mod mimeparser;
 pub mod oauth2;
+pub mod outbox;
 mod param;
 pub mod peerstate;
 mod pgp;
@@ -87,6 +99,7 @@
 mod smtp;
 pub mod stock_str;
 mod sync;
+pub mod sync_state;
 mod timesmearing;
 mod token;
 mod update_helper;
@@ -98,7 +111,7 @@
 pub mod html;
 pub mod net;
 pub mod plaintext;
-mod push;
+pub mod push;
 pub mod summary;
 
 mod debug_logging;
@@ -106,8 +119,13 @@
 pub mod tools;
 
 pub mod accounts;
+pub mod dnd;
+pub mod key_import;
+pub mod ocr;
 pub mod peer_channels;
 pub mod reaction;
+pub mod thunderbird;
+pub mod translate;
 
 /// If set IMAP/incoming and SMTP/outgoing MIME messages will be printed.
 pub const DCC_MIME_DEBUG: &str = "DCC_MIME_DEBUG";
@@ -2,50 +2,186 @@
 
 #![allow(missing_docs)]
 
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::chat::ChatId;
 use crate::context::Context;
+use crate::events::EventType;
+use crate::message::MsgId;
+use crate::tools::time;
 
 #[macro_export]
 macro_rules! info {
-    ($ctx:expr,  $msg:expr) => {
-        info!($ctx, $msg,)
+    ($ctx:expr, chat_id = $chat_id:expr, msg_id = $msg_id:expr; $($rest:tt)*) => {
+        $crate::log_record!($crate::log::LogLevel::Info, $ctx, Some($chat_id), Some($msg_id), $($rest)*)
+    };
+    ($ctx:expr, chat_id = $chat_id:expr; $($rest:tt)*) => {
+        $crate::log_record!($crate::log::LogLevel::Info, $ctx, Some($chat_id), None, $($rest)*)
+    };
+    ($ctx:expr, msg_id = $msg_id:expr; $($rest:tt)*) => {
+        $crate::log_record!($crate::log::LogLevel::Info, $ctx, None, Some($msg_id), $($rest)*)
+    };
+    ($ctx:expr, $($rest:tt)*) => {
+        $crate::log_record!($crate::log::LogLevel::Info, $ctx, None, None, $($rest)*)
     };
-    ($ctx:expr, $msg:expr, $($args:expr),* $(,)?) => {{
-        let formatted = format!($msg, $($args),*);
-        let full = format!("{file}:{line}: {msg}",
-                           file = file!(),
-                           line = line!(),
-                           msg = &formatted);
-        $ctx.emit_event($crate::EventType::Info(full));
-    }};
 }
 
 #[macro_export]
 macro_rules! warn {
-    ($ctx:expr, $msg:expr) => {
-        warn!($ctx, $msg,)
+    ($ctx:expr, chat_id = $chat_id:expr, msg_id = $msg_id:expr; $($rest:tt)*) => {
+        $crate::log_record!($crate::log::LogLevel::Warning, $ctx, Some($chat_id), Some($msg_id), $($rest)*)
+    };
+    ($ctx:expr, chat_id = $chat_id:expr; $($rest:tt)*) => {
+        $crate::log_record!($crate::log::LogLevel::Warning, $ctx, Some($chat_id), None, $($rest)*)
+    };
+    ($ctx:expr, msg_id = $msg_id:expr; $($rest:tt)*) => {
+        $crate::log_record!($crate::log::LogLevel::Warning, $ctx, None, Some($msg_id), $($rest)*)
+    };
+    ($ctx:expr, $($rest:tt)*) => {
+        $crate::log_record!($crate::log::LogLevel::Warning, $ctx, None, None, $($rest)*)
     };
-    ($ctx:expr, $msg:expr, $($args:expr),* $(,)?) => {{
-        let formatted = format!($msg, $($args),*);
-        let full = format!("{file}:{line}: {msg}",
-                           file = file!(),
-                           line = line!(),
-                           msg = &formatted);
-        $ctx.emit_event($crate::EventType::Warning(full));
-    }};
 }
 
 #[macro_export]
 macro_rules! error {
-    ($ctx:expr, $msg:expr) => {
-        error!($ctx, $msg,)
+    ($ctx:expr, chat_id = $chat_id:expr, msg_id = $msg_id:expr; $($rest:tt)*) => {
+        $crate::log_record!($crate::log::LogLevel::Error, $ctx, Some($chat_id), Some($msg_id), $($rest)*)
+    };
+    ($ctx:expr, chat_id = $chat_id:expr; $($rest:tt)*) => {
+        $crate::log_record!($crate::log::LogLevel::Error, $ctx, Some($chat_id), None, $($rest)*)
+    };
+    ($ctx:expr, msg_id = $msg_id:expr; $($rest:tt)*) => {
+        $crate::log_record!($crate::log::LogLevel::Error, $ctx, None, Some($msg_id), $($rest)*)
+    };
+    ($ctx:expr, $($rest:tt)*) => {
+        $crate::log_record!($crate::log::LogLevel::Error, $ctx, None, None, $($rest)*)
     };
-    ($ctx:expr, $msg:expr, $($args:expr),* $(,)?) => {{
+}
+
+/// Creates a [`LogRecord`] and emits it, used by the [`info!`], [`warn!`] and [`error!`] macros.
+///
+/// The logging macros optionally take `chat_id = ...` and `msg_id = ...`
+/// followed by a semicolon before the format string
+/// to relate the record to a chat or message, e.g.
+/// `warn!(context, msg_id = msg_id; "Failed to send: {err:#}.")`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! log_record {
+    ($level:expr, $ctx:expr, $chat_id:expr, $msg_id:expr, $msg:expr $(, $args:expr)* $(,)?) => {{
         let formatted = format!($msg, $($args),*);
-        $ctx.set_last_error(&formatted);
-        $ctx.emit_event($crate::EventType::Error(formatted));
+        let level = $level;
+        if level == $crate::log::LogLevel::Error {
+            $ctx.set_last_error(&formatted);
+        }
+        let mut record = $crate::log::LogRecord::new(
+            level,
+            Some(module_path!()),
+            file!(),
+            line!(),
+            formatted,
+        );
+        record.chat_id = $chat_id;
+        record.msg_id = $msg_id;
+        $ctx.emit_log(record);
     }};
 }
 
+/// Severity of a [`LogRecord`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Info,
+    Warning,
+    Error,
+}
+
+/// Structured log record.
+///
+/// Log records are passed to the log sink set with [`Context::set_log_sink`]
+/// and emitted as [`EventType::Info`], [`EventType::Warning`] and [`EventType::Error`] events.
+#[derive(Debug, Clone, Serialize)]
+pub struct LogRecord {
+    pub level: LogLevel,
+
+    /// Module path of the code that created the record, e.g. `deltachat::imap`.
+    ///
+    /// Not known for records created by [`LogExt::log_err`].
+    pub module: Option<&'static str>,
+
+    /// Source file of the code that created the record.
+    pub file: &'static str,
+
+    /// Source line of the code that created the record.
+    pub line: u32,
+
+    pub message: String,
+
+    /// Chat the record relates to.
+    pub chat_id: Option<ChatId>,
+
+    /// Message the record relates to.
+    pub msg_id: Option<MsgId>,
+}
+
+impl LogRecord {
+    pub fn new(
+        level: LogLevel,
+        module: Option<&'static str>,
+        file: &'static str,
+        line: u32,
+        message: String,
+    ) -> Self {
+        Self {
+            level,
+            module,
+            file,
+            line,
+            message,
+            chat_id: None,
+            msg_id: None,
+        }
+    }
+
+    /// Relates the record to a chat.
+    pub fn with_chat_id(mut self, chat_id: ChatId) -> Self {
+        self.chat_id = Some(chat_id);
+        self
+    }
+
+    /// Relates the record to a message.
+    pub fn with_msg_id(mut self, msg_id: MsgId) -> Self {
+        self.msg_id = Some(msg_id);
+        self
+    }
+
+    /// Converts the record into the string event emitted for it.
+    pub(crate) fn to_event(&self) -> EventType {
+        match self.level {
+            LogLevel::Info => EventType::Info(format!(
+                "{file}:{line}: {msg}",
+                file = self.file,
+                line = self.line,
+                msg = self.message
+            )),
+            LogLevel::Warning => EventType::Warning(format!(
+                "{file}:{line}: {msg}",
+                file = self.file,
+                line = self.line,
+                msg = self.message
+            )),
+            LogLevel::Error => EventType::Error(self.message.clone()),
+        }
+    }
+}
+
+/// Callback receiving structured log records, see [`Context::set_log_sink`].
+pub type LogSink = Arc<dyn Fn(&LogRecord) + Send + Sync>;
+
+/// Number of recent log records kept for the diagnostics report.
+pub(crate) const RECENT_LOG_LEN: usize = 200;
+
 impl Context {
     /// Set last error string.
     /// Implemented as blocking as used from macros in different, not always async blocks.
@@ -59,6 +195,34 @@ pub fn get_last_error(&self) -> String {
         let last_error = &*self.last_error.read();
         last_error.clone()
     }
+
+    /// Sets a callback receiving structured log records in addition to the log events.
+    ///
+    /// The callback is called synchronously from the code that logs,
+    /// so it should return quickly, e.g. by sending the record to a channel.
+    /// `None` removes the callback.
+    pub fn set_log_sink(&self, sink: Option<LogSink>) {
+        *self.log_sink.write() = sink;
+    }
+
+    /// Passes a log record to the log sink
+    /// and emits it as a string event, which is also written to the debug logging webxdc.
+    pub fn emit_log(&self, record: LogRecord) {
+        // Clone the sink so that it can log itself without deadlocking.
+        let sink = self.log_sink.read().clone();
+        if let Some(sink) = sink {
+            sink(&record);
+        }
+        let event = record.to_event();
+        {
+            let mut recent_log = self.recent_log.lock();
+            if recent_log.len() >= RECENT_LOG_LEN {
+                recent_log.pop_front();
+            }
+            recent_log.push_back((time(), record.clone()));
+        }
+        self.emit_event(event);
+    }
 }
 
 pub trait LogExt<T, E>
@@ -85,16 +249,16 @@ fn log_err(self, context: &Context) -> Result<T, E> {
         if let Err(e) = &self {
             let location = std::panic::Location::caller();
 
-            // We are using Anyhow's .context() and to show the inner error, too, we need the {:#}:
-            let full = format!(
-                "{file}:{line}: {e:#}",
-                file = location.file(),
-                line = location.line(),
-                e = e
-            );
             // We can't use the warn!() macro here as the file!() and line!() macros
             // don't work with #[track_caller]
-            context.emit_event(crate::EventType::Warning(full));
+            // We are using Anyhow's .context() and to show the inner error, too, we need the {:#}:
+            context.emit_log(LogRecord::new(
+                LogLevel::Warning,
+                None,
+                location.file(),
+                location.line(),
+                format!("{e:#}"),
+            ));
         };
         self
     }
@@ -103,7 +267,9 @@ fn log_err(self, context: &Context) -> Result<T, E> {
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
+    use parking_lot::Mutex;
 
+    use super::*;
     use crate::test_utils::TestContext;
 
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
@@ -127,4 +293,62 @@ async fn test_get_last_error() -> Result<()> {
 
         Ok(())
     }
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_log_sink() -> Result<()> {
+        let t = TestContext::new().await;
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let sink_records = records.clone();
+        t.set_log_sink(Some(Arc::new(move |record: &LogRecord| {
+            sink_records.lock().push(record.clone());
+        })));
+
+        info!(t, "foo-info");
+        error!(t, chat_id = ChatId::new(12), msg_id = MsgId::new(13); "foo-error {}", 1);
+        t.emit_log(
+            LogRecord::new(LogLevel::Warning, None, "foo.rs", 1, "bar".to_string())
+                .with_chat_id(ChatId::new(10))
+                .with_msg_id(MsgId::new(11)),
+        );
+        t.evtracker
+            .get_matching(|evt| matches!(evt, EventType::Warning(msg) if msg == "foo.rs:1: bar"))
+            .await;
+
+        let records: Vec<LogRecord> = std::mem::take(&mut *records.lock());
+        let info = records
+            .iter()
+            .find(|record| record.message == "foo-info")
+            .unwrap();
+        assert_eq!(info.level, LogLevel::Info);
+        assert_eq!(info.module, Some("deltachat::log::tests"));
+        assert_eq!(info.file, file!());
+        let error = records
+            .iter()
+            .find(|record| record.message == "foo-error 1")
+            .unwrap();
+        assert_eq!(error.level, LogLevel::Error);
+        assert_eq!(error.chat_id, Some(ChatId::new(12)));
+        assert_eq!(error.msg_id, Some(MsgId::new(13)));
+        assert_eq!(t.get_last_error(), "foo-error 1");
+        let warning = records
+            .iter()
+            .find(|record| record.message == "bar")
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(warning)?,
+            serde_json::json!({
+                "level": "warning",
+                "module": null,
+                "file": "foo.rs",
+                "line": 1,
+                "message": "bar",
+                "chat_id": 10,
+                "msg_id": 11,
+            })
+        );
+
+        t.set_log_sink(None);
+        info!(t, "baz-info");
+        assert!(records.lock().is_empty());
+        Ok(())
+    }
 }
@@ -1,6 +1,6 @@
 //! # Messages and their identifiers.
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashSet};
 use std::path::{Path, PathBuf};
 use std::str;
 
@@ -15,7 +15,8 @@
 use crate::chatlist_events;
 use crate::config::Config;
 use crate::constants::{
-    Blocked, Chattype, VideochatType, DC_CHAT_ID_TRASH, DC_DESIRED_TEXT_LEN, DC_MSG_ID_LAST_SPECIAL,
+    Blocked, Chattype, VideochatType, DC_CHAT_ID_TRASH, DC_DESIRED_TEXT_LEN, DC_ELLIPSIS,
+    DC_MSG_ID_LAST_SPECIAL,
 };
 use crate::contact::{self, Contact, ContactId};
 use crate::context::Context;
@@ -134,7 +135,7 @@ pub async fn trash(self, context: &Context, on_server: bool) -> Result<()> {
                 // which information receive_imf::add_parts() still adds to the db if the chat_id is TRASH
                 &format!(
                     "UPDATE msgs SET \
-                     chat_id=?, txt='', txt_normalized=NULL, \
+                     chat_id=?, txt='', txt_normalized=NULL, txt_ocr=NULL, \
                      subject='', txt_raw='', \
                      mime_headers='', \
                      from_id=0, to_id=0, \
@@ -148,6 +149,21 @@ pub async fn trash(self, context: &Context, on_server: bool) -> Result<()> {
         Ok(())
     }
 
+    /// Records that the message was sent over `path`.
+    pub(crate) async fn add_delivery_path(
+        self,
+        context: &Context,
+        path: DeliveryPath,
+    ) -> Result<()> {
+        let Some(mut msg) = Message::load_from_db_optional(context, self).await? else {
+            return Ok(());
+        };
+        if msg.add_delivery_path(path) {
+            msg.update_param(context).await?;
+        }
+        Ok(())
+    }
+
     pub(crate) async fn set_delivered(self, context: &Context) -> Result<()> {
         update_msg_state(context, self, MessageState::OutDelivered).await?;
         let chat_id: Option<ChatId> = context
@@ -164,6 +180,101 @@ pub(crate) async fn set_delivered(self, context: &Context) -> Result<()> {
         Ok(())
     }
 
+    /// Returns recipients which likely did not receive the message
+    /// because their servers rejected it.
+    pub async fn get_failed_recipients(self, context: &Context) -> Result<Vec<FailedRecipient>> {
+        context
+            .sql
+            .query_map(
+                "SELECT addr, error FROM msgs_failed_recipients WHERE msg_id=? ORDER BY addr",
+                (self,),
+                |row| {
+                    let addr: String = row.get(0)?;
+                    let error: String = row.get(1)?;
+                    Ok(FailedRecipient { addr, error })
+                },
+                |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+            )
+            .await
+    }
+
+    /// Returns the delivery status of the message per recipient
+    /// as reported by positive delivery status notifications (DSNs).
+    ///
+    /// Recipients which rejected the message are returned by [`MsgId::get_failed_recipients`].
+    pub async fn get_delivery_status(self, context: &Context) -> Result<Vec<RecipientDelivery>> {
+        context
+            .sql
+            .query_map(
+                "SELECT addr, action, timestamp FROM msgs_delivery_status
+                 WHERE msg_id=? ORDER BY addr",
+                (self,),
+                |row| {
+                    let addr: String = row.get(0)?;
+                    let action: String = row.get(1)?;
+                    let timestamp: i64 = row.get(2)?;
+                    Ok((addr, action, timestamp))
+                },
+                |rows| {
+                    let mut res = Vec::new();
+                    for row in rows {
+                        let (addr, action, timestamp) = row?;
+                        if let Ok(action) = action.parse() {
+                            res.push(RecipientDelivery {
+                                addr,
+                                action,
+                                timestamp,
+                            });
+                        }
+                    }
+                    Ok(res)
+                },
+            )
+            .await
+    }
+
+    /// Records the delivery status of the message for `addr` reported by a DSN.
+    ///
+    /// Returns true if the status changed.
+    pub(crate) async fn set_recipient_delivery(
+        self,
+        context: &Context,
+        addr: &str,
+        action: DeliveryAction,
+        timestamp: i64,
+    ) -> Result<bool> {
+        let changed = context
+            .sql
+            .execute(
+                "INSERT INTO msgs_delivery_status (msg_id, addr, action, timestamp)
+                 VALUES (?, ?, ?, ?)
+                 ON CONFLICT (msg_id, addr) DO UPDATE
+                 SET action=excluded.action, timestamp=excluded.timestamp
+                 WHERE action!=excluded.action",
+                (self, addr, action.to_string(), timestamp),
+            )
+            .await?;
+        Ok(changed > 0)
+    }
+
+    /// Records that sending the message to `addr` failed permanently.
+    pub(crate) async fn add_failed_recipient(
+        self,
+        context: &Context,
+        addr: &str,
+        error: &str,
+    ) -> Result<()> {
+        context
+            .sql
+            .execute(
+                "INSERT OR REPLACE INTO msgs_failed_recipients (msg_id, addr, error)
+                 VALUES (?, ?, ?)",
+                (self, addr, error),
+            )
+            .await?;
+        Ok(())
+    }
+
     /// Bad evil escape hatch.
     ///
     /// Avoid using this, eventually types should be cleaned up enough
@@ -299,6 +410,29 @@ pub async fn get_info(self, context: &Context) -> Result<String> {
 
         ret += "\n";
 
+        let delivery_paths = msg.get_delivery_paths();
+        if !delivery_paths.is_empty() {
+            let delivery_paths = delivery_paths
+                .iter()
+                .map(|path| path.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            ret += &format!("Delivered via: {delivery_paths}\n");
+        }
+
+        for failed in self.get_failed_recipients(context).await? {
+            ret += &format!("Not sent to {}: {}\n", failed.addr, failed.error);
+        }
+
+        for delivery in self.get_delivery_status(context).await? {
+            ret += &format!(
+                "{} to {}: {}\n",
+                delivery.action,
+                delivery.addr,
+                timestamp_to_str(delivery.timestamp)
+            );
+        }
+
         let reactions = get_msg_reactions(context, self).await?;
         if !reactions.is_empty() {
             ret += &format!("Reactions: {reactions}\n");
@@ -452,6 +586,9 @@ pub struct Message {
 
     /// Whether the message is hidden.
     pub(crate) hidden: bool,
+
+    /// Whether the message is hidden locally by the user, see [`set_hidden_local`].
+    pub(crate) hidden_local: bool,
     pub(crate) timestamp_sort: i64,
     pub(crate) timestamp_sent: i64,
     pub(crate) timestamp_rcvd: i64,
@@ -543,6 +680,7 @@ pub async fn load_from_db_optional(context: &Context, id: MsgId) -> Result<Optio
                     "    m.subject AS subject,",
                     "    m.param AS param,",
                     "    m.hidden AS hidden,",
+                    "    m.hidden_local AS hidden_local,",
                     "    m.location_id AS location,",
                     "    c.blocked AS blocked",
                     " FROM msgs m",
@@ -600,6 +738,7 @@ pub async fn load_from_db_optional(context: &Context, id: MsgId) -> Result<Optio
                         subject: row.get("subject")?,
                         param: row.get::<_, String>("param")?.parse().unwrap_or_default(),
                         hidden: row.get("hidden")?,
+                        hidden_local: row.get("hidden_local")?,
                         location_id: row.get("location")?,
                         chat_blocked: row
                             .get::<_, Option<Blocked>>("blocked")?
@@ -901,6 +1040,63 @@ pub fn get_override_sender_name(&self) -> Option<String> {
             .map(|name| name.to_string())
     }
 
+    /// Returns the transports an outgoing message was successfully sent over.
+    pub fn get_delivery_paths(&self) -> Vec<DeliveryPath> {
+        self.param
+            .get(Param::DeliveryPaths)
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|path| path.parse().ok())
+            .collect()
+    }
+
+    /// Adds `path` to the delivery paths of the message.
+    ///
+    /// Returns false if the path was already recorded.
+    /// The caller is responsible for saving the parameters.
+    pub(crate) fn add_delivery_path(&mut self, path: DeliveryPath) -> bool {
+        let mut paths = self.get_delivery_paths();
+        if paths.contains(&path) {
+            return false;
+        }
+        paths.push(path);
+        let paths = paths
+            .iter()
+            .map(|path| path.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        self.param.set(Param::DeliveryPaths, paths);
+        true
+    }
+
+    /// Returns true if the message was sent, but some recipients rejected it.
+    ///
+    /// Use [`MsgId::get_failed_recipients`] to get the recipients.
+    pub fn is_partially_failed(&self) -> bool {
+        self.param
+            .get_bool(Param::PartiallyFailed)
+            .unwrap_or_default()
+    }
+
+    /// Returns the reason of a delivery failure reported by the recipient's server.
+    pub fn get_delivery_failure(&self) -> Option<DeliveryFailure> {
+        self.param.get(Param::DeliveryFailure)?.parse().ok()
+    }
+
+    /// Returns custom headers of a received message as `(name, value)` pairs.
+    ///
+    /// Only headers listed in [`crate::config::Config::AllowedCustomHeaders`] are stored,
+    /// names are lowercased.
+    pub fn get_custom_headers(&self) -> Vec<(String, String)> {
+        self.param
+            .get(Param::CustomHeaders)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| line.split_once(": "))
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
     // Exposing this function over the ffi instead of get_override_sender_name() would mean that at least Android Java code has
     // to handle raw C-data (as it is done for msg_get_summary())
     pub(crate) fn get_sender_name(&self, contact: &Contact) -> String {
@@ -931,6 +1127,11 @@ pub fn is_forwarded(&self) -> bool {
         0 != self.param.get_int(Param::Forwarded).unwrap_or_default()
     }
 
+    /// Returns true if the message is hidden locally with [`set_hidden_local`].
+    pub fn is_hidden_local(&self) -> bool {
+        self.hidden_local
+    }
+
     /// Returns true if the message is an informational message.
     pub fn is_info(&self) -> bool {
         let cmd = self.param.get_cmd();
@@ -1288,6 +1489,43 @@ pub async fn quoted_message(&self, context: &Context) -> Result<Option<Message>>
         Ok(None)
     }
 
+    /// Verifies the quoted text against the quoted message.
+    ///
+    /// The quoted text is sent along with the message and may be spoofed by the sender,
+    /// so UIs may use this to flag quotes that do not match the original message.
+    pub async fn quote_integrity(&self, context: &Context) -> Result<QuoteIntegrity> {
+        let Some(quoted_text) = self.param.get(Param::Quote) else {
+            return Ok(QuoteIntegrity::Unknown);
+        };
+        // Other MUAs quote the whole email including signatures and headers.
+        if self.is_dc_message == MessengerMessage::No {
+            return Ok(QuoteIntegrity::Unknown);
+        }
+        // Quotes of encrypted messages are replaced with "..." in unencrypted replies.
+        if quoted_text == "..." {
+            return Ok(QuoteIntegrity::Unknown);
+        }
+        let Some(quote) = self.quoted_message(context).await? else {
+            return Ok(QuoteIntegrity::Unknown);
+        };
+
+        let text = quote.get_text();
+        if text.is_empty() {
+            // Messages without text are quoted with their summary
+            // which is localized to the language of the sender.
+            let summary = quote.get_summary(context, None).await?;
+            return Ok(
+                match normalize_quote(summary.truncated_text(500).as_ref())
+                    == normalize_quote(quoted_text)
+                {
+                    true => QuoteIntegrity::Verified,
+                    false => QuoteIntegrity::Unknown,
+                },
+            );
+        }
+        Ok(compare_quote(quoted_text, &text))
+    }
+
     /// Returns parent message according to the `In-Reply-To` header
     /// if it exists in the database and is not trashed.
     ///
@@ -1336,6 +1574,26 @@ pub fn force_plaintext(&mut self) {
         self.param.set_int(Param::ForcePlaintext, 1);
     }
 
+    /// Requests positive delivery status notifications (DSNs, RFC 3461)
+    /// from the servers of the recipients.
+    ///
+    /// The message is then sent with `NOTIFY=SUCCESS,FAILURE` SMTP recipient parameters
+    /// if the SMTP server supports it.
+    /// Reported deliveries are returned by [`MsgId::get_delivery_status`].
+    pub fn request_dsn(&mut self, request: bool) {
+        if request {
+            self.param.set_int(Param::RequestDsn, 1);
+        } else {
+            self.param.remove(Param::RequestDsn);
+        }
+    }
+
+    /// Returns true if positive delivery status notifications are requested,
+    /// see [`Message::request_dsn`].
+    pub fn is_dsn_requested(&self) -> bool {
+        self.param.get_bool(Param::RequestDsn).unwrap_or_default()
+    }
+
     /// Updates `param` column of the message in the database without changing other columns.
     pub async fn update_param(&self, context: &Context) -> Result<()> {
         context
@@ -1376,6 +1634,46 @@ pub fn error(&self) -> Option<String> {
     }
 }
 
+/// Result of verifying a quote against the quoted message,
+/// see [`Message::quote_integrity`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum QuoteIntegrity {
+    /// The message has no quote,
+    /// the quoted message is not available locally
+    /// or the quote cannot be compared to it.
+    #[default]
+    Unknown = 0,
+
+    /// The quoted text matches the quoted message.
+    Verified = 1,
+
+    /// The quoted text differs from the quoted message.
+    Modified = 2,
+}
+
+/// Compares quoted text with the text of the quoted message.
+///
+/// Whitespace is ignored as quotes may be rewrapped on the way.
+fn compare_quote(quoted_text: &str, text: &str) -> QuoteIntegrity {
+    let quoted_text = normalize_quote(quoted_text);
+    let text = normalize_quote(text);
+    if quoted_text == text {
+        return QuoteIntegrity::Verified;
+    }
+    // Long messages are truncated on receiving, but quoted in full by the sender.
+    if let Some(prefix) = text.strip_suffix(DC_ELLIPSIS) {
+        if quoted_text.starts_with(prefix.trim_end()) {
+            return QuoteIntegrity::Verified;
+        }
+    }
+    QuoteIntegrity::Modified
+}
+
+fn normalize_quote(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
 /// State of the message.
 /// For incoming messages, stores the information on whether the message was read or not.
 /// For outgoing message, the message could be pending, already delivered or confirmed.
@@ -1490,6 +1788,92 @@ pub(crate) fn with_mdns(self, has_mdns: bool) -> Self {
     }
 }
 
+/// Transport over which an outgoing message was sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[strum(serialize_all = "snake_case")]
+pub enum DeliveryPath {
+    /// Message was sent by email over SMTP.
+    Smtp,
+
+    /// Message was submitted by email over JMAP.
+    Jmap,
+
+    /// Message was broadcast over an iroh peer channel,
+    /// e.g. a Secure-Join handshake message sent over the local network.
+    PeerChannel,
+}
+
+/// Reason why delivery of an outgoing message failed,
+/// as reported by a delivery status notification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString, Serialize, Deserialize)]
+#[strum(serialize_all = "snake_case")]
+pub enum DeliveryFailure {
+    /// The mailbox of the recipient is full.
+    MailboxFull,
+
+    /// The recipient address does not exist.
+    UnknownRecipient,
+
+    /// The message was rejected because of a policy, e.g. by a spam filter.
+    Policy,
+
+    /// Any other failure.
+    Other,
+}
+
+impl DeliveryFailure {
+    /// Maps an enhanced mail system status code (RFC 3463), e.g. `5.2.2`, to a failure reason.
+    pub(crate) fn from_status(status: &str) -> Self {
+        let mut codes = status.trim().split('.').skip(1);
+        match (codes.next(), codes.next()) {
+            (Some("2"), Some("2")) => Self::MailboxFull,
+            (Some("1"), _) => Self::UnknownRecipient,
+            (Some("7"), _) => Self::Policy,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// Positive action reported for a recipient by a delivery status notification (RFC 3464).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString, Serialize, Deserialize)]
+#[strum(serialize_all = "snake_case")]
+pub enum DeliveryAction {
+    /// The message was delivered to the mailbox of the recipient.
+    Delivered,
+
+    /// The message was relayed to a system which does not report delivery,
+    /// so no further notifications are expected.
+    Relayed,
+
+    /// The message was delivered to the recipient address
+    /// and forwarded by it to multiple addresses, e.g. by a mailing list.
+    Expanded,
+}
+
+/// Delivery status of an outgoing message for a single recipient.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecipientDelivery {
+    /// Address of the recipient.
+    pub addr: String,
+
+    /// Action reported by the DSN.
+    pub action: DeliveryAction,
+
+    /// Time the DSN was sent.
+    pub timestamp: i64,
+}
+
+/// Recipient which likely did not receive an outgoing message
+/// because the SMTP server rejected it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailedRecipient {
+    /// Address of the recipient.
+    pub addr: String,
+
+    /// Error returned by the server.
+    pub error: String,
+}
+
 /// Returns contacts that sent read receipts and the time of reading.
 pub async fn get_msg_read_receipts(
     context: &Context,
@@ -1661,6 +2045,100 @@ pub async fn get_mime_headers(context: &Context, msg_id: MsgId) -> Result<Vec<u8
     Ok(headers)
 }
 
+/// Exports the raw MIME of the given messages as `.eml` files to `dest_dir`.
+///
+/// Raw messages are only available for messages received
+/// while `save_mime_headers` was set
+/// and for messages with the "Show Full Message" button, see [`Message::has_html`].
+/// Messages that were split into several messages on receiving are exported only once.
+///
+/// Returns the paths of the written files.
+pub async fn export_eml(
+    context: &Context,
+    msg_ids: &[MsgId],
+    dest_dir: &Path,
+) -> Result<Vec<PathBuf>> {
+    fs::create_dir_all(dest_dir)
+        .await
+        .with_context(|| format!("Cannot create {}", dest_dir.display()))?;
+    let mut exported_mids = HashSet::new();
+    let mut paths = Vec::new();
+    for &msg_id in msg_ids {
+        let msg = Message::load_from_db(context, msg_id).await?;
+        if !exported_mids.insert(msg.rfc724_mid.clone()) {
+            continue;
+        }
+        ensure!(
+            msg.download_state() == DownloadState::Done,
+            "Message {msg_id} is not fully downloaded"
+        );
+        let raw = get_mime_headers(context, msg_id).await?;
+        // Outgoing messages only store the HTML part.
+        let is_complete = mailparse::parse_headers(&raw).is_ok_and(|(headers, _)| {
+            headers
+                .iter()
+                .any(|header| header.get_key_ref().eq_ignore_ascii_case("From"))
+        });
+        ensure!(is_complete, "No raw message saved for message {msg_id}");
+
+        let path = dest_dir.join(eml_filename(&msg));
+        fs::write(&path, raw)
+            .await
+            .with_context(|| format!("Cannot write {}", path.display()))?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+/// Returns a filename like `2024-03-01_10-00-00_123_Subject.eml`.
+///
+/// The message ID makes the filename unique within the account.
+fn eml_filename(msg: &Message) -> String {
+    let date = chrono::DateTime::<chrono::Utc>::from_timestamp(msg.get_timestamp(), 0)
+        .map(|date| date.format("%Y-%m-%d_%H-%M-%S").to_string())
+        .unwrap_or_default();
+    let subject: String = msg
+        .subject
+        .chars()
+        .take(50)
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+    let subject = subject.trim_matches('_');
+    if subject.is_empty() {
+        format!("{date}_{}.eml", msg.id.to_u32())
+    } else {
+        format!("{date}_{}_{subject}.eml", msg.id.to_u32())
+    }
+}
+
+/// Hides the message from the chat on this device without deleting it.
+///
+/// Hidden messages are not returned by [`crate::chat::get_chat_msgs`], searches and media lists,
+/// but can still be listed with [`crate::chat::MessageListOptions::include_hidden_local`].
+/// Hidden messages are also not counted as fresh and not shown in the chatlist summary,
+/// hiding a fresh message marks it as noticed.
+/// This is not sent to other devices or chat members, but is kept in backups.
+pub async fn set_hidden_local(context: &Context, msg_id: MsgId, hidden: bool) -> Result<()> {
+    let msg = Message::load_from_db(context, msg_id).await?;
+    ensure!(!msg.chat_id.is_special(), "Cannot hide {msg_id}.");
+    context
+        .sql
+        .execute(
+            "UPDATE msgs SET hidden_local=? WHERE id=?",
+            (hidden, msg_id),
+        )
+        .await?;
+    info!(context, msg_id = msg_id; "Set {msg_id} hidden locally to {hidden}.");
+    if hidden && msg.state == MessageState::InFresh {
+        update_msg_state(context, msg_id, MessageState::InNoticed).await?;
+        // Lets UIs remove the notification of the message.
+        context.emit_event(EventType::MsgsNoticed(msg.chat_id));
+    }
+    context.emit_msgs_changed(msg.chat_id, msg_id);
+    chatlist_events::emit_chatlist_item_changed(context, msg.chat_id);
+    Ok(())
+}
+
 /// Deletes requested messages
 /// by moving them to the trash chat
 /// and scheduling for deletion on IMAP.
@@ -1697,6 +2175,7 @@ pub async fn delete_msgs(context: &Context, msg_ids: &[MsgId]) -> Result<()> {
                 (target, msg.rfc724_mid),
             )?;
             trans.execute("DELETE FROM smtp WHERE msg_id=?", (msg_id,))?;
+            trans.execute("DELETE FROM smtp_quarantine WHERE msg_id=?", (msg_id,))?;
             Ok(())
         };
         if let Err(e) = context.sql.transaction(update_db).await {
@@ -1857,8 +2336,9 @@ pub async fn markseen_msgs(context: &Context, msg_ids: Vec<MsgId>) -> Result<()>
                 context
                     .sql
                     .execute(
-                        "INSERT INTO smtp_mdns (msg_id, from_id, rfc724_mid) VALUES(?, ?, ?)",
-                        (id, curr_from_id, curr_rfc724_mid),
+                        "INSERT INTO smtp_mdns (msg_id, from_id, rfc724_mid, chat_id, timestamp)
+                         VALUES(?, ?, ?, ?, ?)",
+                        (id, curr_from_id, curr_rfc724_mid, curr_chat_id, time()),
                     )
                     .await
                     .context("failed to insert into smtp_mdns")?;
@@ -1919,10 +2399,11 @@ pub(crate) async fn set_msg_failed(
 ) -> Result<()> {
     if msg.state.can_fail() {
         msg.state = MessageState::OutFailed;
-        warn!(context, "{} failed: {}", msg.id, error);
+        warn!(context, chat_id = msg.chat_id, msg_id = msg.id; "{} failed: {}", msg.id, error);
     } else {
         warn!(
             context,
+            chat_id = msg.chat_id, msg_id = msg.id;
             "{} seems to have failed ({}), but state is {}", msg.id, error, msg.state
         )
     }
@@ -1939,6 +2420,7 @@ pub(crate) async fn set_msg_failed(
     context.emit_event(EventType::MsgFailed {
         chat_id: msg.chat_id,
         msg_id: msg.id,
+        reason: msg.get_delivery_failure(),
     });
     if exists {
         chatlist_events::emit_chatlist_item_changed(context, msg.chat_id);
@@ -231,6 +231,61 @@ async fn test_unencrypted_quote_encrypted_message() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_quote_integrity() -> Result<()> {
+    let mut tcm = TestContextManager::new();
+    let alice = &tcm.alice().await;
+    let bob = &tcm.bob().await;
+
+    let alice_chat = alice.create_chat(bob).await;
+    let sent = alice.send_text(alice_chat.id, "Meet at 5 pm").await;
+    let bob_msg = bob.recv_msg(&sent).await;
+    bob_msg.chat_id.accept(bob).await?;
+    let alice_msg = Message::load_from_db(alice, sent.sender_msg_id).await?;
+    assert_eq!(
+        alice_msg.quote_integrity(alice).await?,
+        QuoteIntegrity::Unknown
+    );
+
+    let mut reply = Message::new_text("Ok".to_string());
+    reply.set_quote(bob, Some(&bob_msg)).await?;
+    let sent = bob.send_msg(bob_msg.chat_id, &mut reply).await;
+    let received = alice.recv_msg(&sent).await;
+    assert_eq!(
+        received.quote_integrity(alice).await?,
+        QuoteIntegrity::Verified
+    );
+
+    // Bob pretends that Alice wrote something else.
+    let mut reply = Message::new_text("Ok".to_string());
+    reply.set_quote(bob, Some(&bob_msg)).await?;
+    reply.set_quote_text(Some(("Meet at 6 pm".to_string(), false)));
+    let sent = bob.send_msg(bob_msg.chat_id, &mut reply).await;
+    let received = alice.recv_msg(&sent).await;
+    assert_eq!(received.quoted_text().unwrap(), "Meet at 6 pm");
+    assert_eq!(
+        received.quote_integrity(alice).await?,
+        QuoteIntegrity::Modified
+    );
+    Ok(())
+}
+
+#[test]
+fn test_compare_quote() {
+    assert_eq!(
+        compare_quote("Hello\nworld", "Hello world"),
+        QuoteIntegrity::Verified
+    );
+    assert_eq!(
+        compare_quote("Line one. Line two.", "Line one. [...]"),
+        QuoteIntegrity::Verified
+    );
+    assert_eq!(
+        compare_quote("Line three.", "Line one. [...]"),
+        QuoteIntegrity::Modified
+    );
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn test_get_chat_id() {
     // Alice receives a message that pops up as a contact request
@@ -755,3 +810,165 @@ async fn test_delete_msgs_offline() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_delivery_paths() -> Result<()> {
+    let mut tcm = TestContextManager::new();
+    let alice = &tcm.alice().await;
+    let bob = &tcm.bob().await;
+    let chat = alice.create_chat(bob).await;
+
+    let msg_id = send_text_msg(alice, chat.id, "hi".to_string()).await?;
+    let msg = Message::load_from_db(alice, msg_id).await?;
+    assert!(msg.get_delivery_paths().is_empty());
+
+    // Test contexts do not send over a real transport.
+    alice.pop_sent_msg().await;
+    let msg = Message::load_from_db(alice, msg_id).await?;
+    assert!(msg.get_delivery_paths().is_empty());
+
+    msg_id.add_delivery_path(alice, DeliveryPath::Jmap).await?;
+    msg_id.add_delivery_path(alice, DeliveryPath::Jmap).await?;
+    msg_id
+        .add_delivery_path(alice, DeliveryPath::PeerChannel)
+        .await?;
+    let msg = Message::load_from_db(alice, msg_id).await?;
+    assert_eq!(
+        msg.get_delivery_paths(),
+        vec![DeliveryPath::Jmap, DeliveryPath::PeerChannel]
+    );
+    let info = msg_id.get_info(alice).await?;
+    assert!(info.contains("Delivered via: jmap, peer_channel"));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_partial_delivery_failure() -> Result<()> {
+    let mut tcm = TestContextManager::new();
+    let alice = &tcm.alice().await;
+    let bob = &tcm.bob().await;
+    let fiona = &tcm.fiona().await;
+    let err = anyhow::anyhow!("Permanent SMTP error: 550 5.1.1 User unknown");
+
+    // Sending to Bob succeeded, Fiona's server rejected the message.
+    let chat_id = alice
+        .create_group_with_members(ProtectionStatus::Unprotected, "Group", &[bob, fiona])
+        .await;
+    let msg_id = alice.send_text(chat_id, "Hello").await.sender_msg_id;
+    msg_id.add_delivery_path(alice, DeliveryPath::Jmap).await?;
+    let fiona_addr = async_smtp::EmailAddress::new("fiona@example.net".to_string()).unwrap();
+    crate::smtp::handle_failed_entry(alice, msg_id, &[fiona_addr], &err).await?;
+    let msg = Message::load_from_db(alice, msg_id).await?;
+    assert_eq!(msg.state, MessageState::OutDelivered);
+    assert!(msg.is_partially_failed());
+    assert_eq!(
+        msg_id.get_failed_recipients(alice).await?,
+        vec![FailedRecipient {
+            addr: "fiona@example.net".to_string(),
+            error: err.to_string(),
+        }]
+    );
+    assert!(msg_id
+        .get_info(alice)
+        .await?
+        .contains("Not sent to fiona@example.net"));
+
+    // The only recipient rejected the message.
+    let chat = alice.create_chat(bob).await;
+    let msg_id = send_text_msg(alice, chat.id, "Hi".to_string()).await?;
+    alice
+        .sql
+        .execute("DELETE FROM smtp WHERE msg_id=?", (msg_id,))
+        .await?;
+    let bob_addr = async_smtp::EmailAddress::new("bob@example.net".to_string()).unwrap();
+    crate::smtp::handle_failed_entry(alice, msg_id, &[bob_addr], &err).await?;
+    let msg = Message::load_from_db(alice, msg_id).await?;
+    assert_eq!(msg.state, MessageState::OutFailed);
+    assert!(!msg.is_partially_failed());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_export_eml() -> Result<()> {
+    let mut tcm = TestContextManager::new();
+    let alice = &tcm.alice().await;
+    let bob = &tcm.bob().await;
+    bob.set_config_bool(Config::SaveMimeHeaders, true).await?;
+
+    let chat_id = alice.create_chat(bob).await.id;
+    let mut msg = Message::new(Viewtype::File);
+    msg.set_file_from_bytes(alice, "report.txt", b"quarterly numbers", None)?;
+    msg.set_text("Here is the report".to_string());
+    let sent = alice.send_msg(chat_id, &mut msg).await;
+    let received = bob.recv_msg(&sent).await;
+
+    let dir = tempfile::tempdir()?;
+    let paths = export_eml(bob, &[received.id], dir.path()).await?;
+    assert_eq!(paths.len(), 1);
+    assert_eq!(paths[0].extension().unwrap(), "eml");
+    let exported = tokio::fs::read(&paths[0]).await?;
+    assert_eq!(exported, get_mime_headers(bob, received.id).await?);
+    let parsed = crate::mimeparser::MimeMessage::from_bytes(bob, &exported, None).await?;
+    assert!(parsed.parts.iter().any(|part| part.typ == Viewtype::File));
+
+    // Sent messages have no raw message stored.
+    let sent_msg_id = alice.get_last_msg().await.id;
+    assert!(export_eml(alice, &[sent_msg_id], dir.path()).await.is_err());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_set_hidden_local() -> Result<()> {
+    let mut tcm = TestContextManager::new();
+    let alice = &tcm.alice().await;
+    let bob = &tcm.bob().await;
+
+    let chat = alice.create_chat(bob).await;
+    let sent = alice.send_text(chat.id, "offensive").await;
+    let msg = bob.recv_msg(&sent).await;
+    let sent = alice.send_text(chat.id, "nice").await;
+    let nice = bob.recv_msg(&sent).await;
+    assert_eq!(chat::get_chat_msgs(bob, msg.chat_id).await?.len(), 2);
+    assert_eq!(msg.chat_id.get_fresh_msg_cnt(bob).await?, 2);
+
+    bob.evtracker.clear_events();
+    set_hidden_local(bob, msg.id, true).await?;
+    let hidden = Message::load_from_db(bob, msg.id).await?;
+    assert!(hidden.is_hidden_local());
+    // Hidden messages are not fresh anymore.
+    assert_eq!(hidden.state, MessageState::InNoticed);
+    assert_eq!(msg.chat_id.get_fresh_msg_cnt(bob).await?, 1);
+    bob.evtracker
+        .get_matching(|ev| matches!(ev, EventType::MsgsNoticed(_)))
+        .await;
+    assert_eq!(
+        chat::get_chat_msgs(bob, msg.chat_id).await?,
+        vec![ChatItem::Message { msg_id: nice.id }]
+    );
+    assert!(bob.search_msgs(None, "offensive").await?.is_empty());
+
+    let all = chat::get_chat_msgs_ex(
+        bob,
+        msg.chat_id,
+        chat::MessageListOptions {
+            info_only: false,
+            add_daymarker: false,
+            include_hidden_local: true,
+        },
+    )
+    .await?;
+    assert_eq!(all.len(), 2);
+
+    set_hidden_local(bob, msg.id, false).await?;
+    assert_eq!(chat::get_chat_msgs(bob, msg.chat_id).await?.len(), 2);
+    assert_eq!(bob.search_msgs(None, "offensive").await?, vec![msg.id]);
+
+    // The chatlist summary skips hidden messages.
+    set_hidden_local(bob, nice.id, true).await?;
+    let chatlist = Chatlist::try_load(bob, 0, None, None).await?;
+    let index = chatlist.get_index_for_id(msg.chat_id).unwrap();
+    assert_eq!(chatlist.get_msg_id(index)?, Some(msg.id));
+    Ok(())
+}
@@ -0,0 +1,261 @@
+//! # Metrics for monitoring of bots and servers.
+//!
+//! Counters are kept in memory and start at zero when the account is opened.
+//! Queue sizes are read from the database when the metrics are requested.
+//! [`Accounts::get_openmetrics`] formats the metrics of all accounts
+//! in the OpenMetrics text format understood by Prometheus.
+
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::accounts::Accounts;
+use crate::context::Context;
+
+/// In-memory counters of an account.
+#[derive(Debug, Default)]
+pub(crate) struct MetricsCounters {
+    msgs_received: AtomicU64,
+    msgs_sent: AtomicU64,
+    imap_connections: AtomicU64,
+    smtp_connections: AtomicU64,
+    ratelimit_delays: AtomicU64,
+    ratelimit_delay_ms: AtomicU64,
+}
+
+impl MetricsCounters {
+    pub(crate) fn count_msg_received(&self) {
+        self.msgs_received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn count_msg_sent(&self) {
+        self.msgs_sent.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn count_imap_connection(&self) {
+        self.imap_connections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn count_smtp_connection(&self) {
+        self.smtp_connections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts a wait caused by the IMAP connection or SMTP sending rate limiter.
+    pub(crate) fn count_ratelimit_delay(&self, delay: Duration) {
+        self.ratelimit_delays.fetch_add(1, Ordering::Relaxed);
+        self.ratelimit_delay_ms.fetch_add(
+            delay.as_millis().try_into().unwrap_or(u64::MAX),
+            Ordering::Relaxed,
+        );
+    }
+}
+
+/// Metrics of an account.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Metrics {
+    /// Number of messages received since the account was opened.
+    pub msgs_received: u64,
+
+    /// Number of SMTP messages sent since the account was opened.
+    pub msgs_sent: u64,
+
+    /// Number of successful IMAP logins since the account was opened.
+    pub imap_connections: u64,
+
+    /// Number of successful SMTP logins since the account was opened.
+    pub smtp_connections: u64,
+
+    /// Number of times the rate limiter delayed IMAP connections or sending.
+    pub ratelimit_delays: u64,
+
+    /// Total time of the rate limiter delays in milliseconds.
+    pub ratelimit_delay_ms: u64,
+
+    /// Number of messages waiting to be sent.
+    pub smtp_queue: u64,
+
+    /// Number of read receipts waiting to be sent.
+    pub mdns_queue: u64,
+
+    /// Number of webxdc instances with status updates waiting to be sent.
+    pub status_updates_queue: u64,
+
+    /// Number of messages waiting to be downloaded completely.
+    pub download_queue: u64,
+}
+
+impl Context {
+    /// Returns the metrics of the account.
+    pub async fn get_metrics(&self) -> Result<Metrics> {
+        let counters = &self.metrics;
+        let (smtp_queue, mdns_queue, status_updates_queue, download_queue) = self
+            .sql
+            .query_row(
+                "SELECT (SELECT COUNT(*) FROM smtp),
+                        (SELECT COUNT(*) FROM smtp_mdns),
+                        (SELECT COUNT(*) FROM smtp_status_updates),
+                        (SELECT COUNT(*) FROM download)",
+                (),
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .await?;
+        Ok(Metrics {
+            msgs_received: counters.msgs_received.load(Ordering::Relaxed),
+            msgs_sent: counters.msgs_sent.load(Ordering::Relaxed),
+            imap_connections: counters.imap_connections.load(Ordering::Relaxed),
+            smtp_connections: counters.smtp_connections.load(Ordering::Relaxed),
+            ratelimit_delays: counters.ratelimit_delays.load(Ordering::Relaxed),
+            ratelimit_delay_ms: counters.ratelimit_delay_ms.load(Ordering::Relaxed),
+            smtp_queue,
+            mdns_queue,
+            status_updates_queue,
+            download_queue,
+        })
+    }
+}
+
+/// Metric families in the OpenMetrics output:
+/// name, whether the metric is a counter rather than a gauge, help text and value.
+#[allow(clippy::type_complexity)]
+const METRIC_FAMILIES: &[(&str, bool, &str, fn(&Metrics) -> u64)] = &[
+    (
+        "deltachat_messages_received",
+        true,
+        "Messages received.",
+        |m| m.msgs_received,
+    ),
+    (
+        "deltachat_messages_sent",
+        true,
+        "Messages sent over SMTP.",
+        |m| m.msgs_sent,
+    ),
+    (
+        "deltachat_imap_connections",
+        true,
+        "Successful IMAP logins.",
+        |m| m.imap_connections,
+    ),
+    (
+        "deltachat_smtp_connections",
+        true,
+        "Successful SMTP logins.",
+        |m| m.smtp_connections,
+    ),
+    (
+        "deltachat_ratelimit_delays",
+        true,
+        "Delays caused by rate limiting.",
+        |m| m.ratelimit_delays,
+    ),
+    (
+        "deltachat_ratelimit_delay_milliseconds",
+        true,
+        "Total time of delays caused by rate limiting.",
+        |m| m.ratelimit_delay_ms,
+    ),
+    (
+        "deltachat_smtp_queue",
+        false,
+        "Messages waiting to be sent.",
+        |m| m.smtp_queue,
+    ),
+    (
+        "deltachat_mdns_queue",
+        false,
+        "Read receipts waiting to be sent.",
+        |m| m.mdns_queue,
+    ),
+    (
+        "deltachat_status_updates_queue",
+        false,
+        "Webxdc instances with status updates waiting to be sent.",
+        |m| m.status_updates_queue,
+    ),
+    (
+        "deltachat_download_queue",
+        false,
+        "Messages waiting to be downloaded.",
+        |m| m.download_queue,
+    ),
+];
+
+/// Formats metrics of accounts in the OpenMetrics text format.
+fn format_openmetrics(metrics: &[(u32, Metrics)]) -> String {
+    let mut out = String::new();
+    for (name, is_counter, help, value) in METRIC_FAMILIES {
+        let (metric_type, suffix) = match *is_counter {
+            true => ("counter", "_total"),
+            false => ("gauge", ""),
+        };
+        writeln!(out, "# TYPE {name} {metric_type}").ok();
+        writeln!(out, "# HELP {name} {help}").ok();
+        for (account_id, account_metrics) in metrics {
+            writeln!(
+                out,
+                "{name}{suffix}{{account=\"{account_id}\"}} {}",
+                value(account_metrics)
+            )
+            .ok();
+        }
+    }
+    out.push_str("# EOF\n");
+    out
+}
+
+impl Accounts {
+    /// Returns the metrics of all open accounts in the OpenMetrics text format.
+    pub async fn get_openmetrics(&self) -> Result<String> {
+        let mut metrics = Vec::new();
+        for account_id in self.get_all() {
+            let Some(context) = self.get_account(account_id) else {
+                continue;
+            };
+            if context.is_open().await {
+                metrics.push((account_id, context.get_metrics().await?));
+            }
+        }
+        Ok(format_openmetrics(&metrics))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::send_text_msg;
+    use crate::test_utils::TestContextManager;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_get_metrics() -> Result<()> {
+        let mut tcm = TestContextManager::new();
+        let alice = &tcm.alice().await;
+        let bob = &tcm.bob().await;
+
+        let chat = alice.create_chat(bob).await;
+        send_text_msg(alice, chat.id, "Hi!".to_string()).await?;
+        let metrics = alice.get_metrics().await?;
+        assert_eq!(metrics.smtp_queue, 1);
+        assert_eq!(metrics.msgs_received, 0);
+
+        let sent = alice.pop_sent_msg().await;
+        bob.recv_msg(&sent).await;
+        assert_eq!(bob.get_metrics().await?.msgs_received, 1);
+
+        alice
+            .metrics
+            .count_ratelimit_delay(Duration::from_millis(1500));
+        let metrics = alice.get_metrics().await?;
+        assert_eq!(metrics.smtp_queue, 0);
+        assert_eq!(metrics.ratelimit_delays, 1);
+        assert_eq!(metrics.ratelimit_delay_ms, 1500);
+
+        let text = format_openmetrics(&[(1, metrics)]);
+        assert!(text.contains("# TYPE deltachat_ratelimit_delays counter\n"));
+        assert!(text.contains("deltachat_ratelimit_delays_total{account=\"1\"} 1\n"));
+        assert!(text.contains("deltachat_smtp_queue{account=\"1\"} 0\n"));
+        assert!(text.ends_with("# EOF\n"));
+        Ok(())
+    }
+}
@@ -6,16 +6,22 @@
 use anyhow::{bail, Context as _, Result};
 use base64::Engine as _;
 use chrono::TimeZone;
+use deltachat_contact_tools::{addr_cmp, addr_with_ascii_domain};
 use email::Mailbox;
 use lettre_email::{Address, Header, MimeMultipartType, PartBuilder};
+use once_cell::sync::Lazy;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use regex::Regex;
 use tokio::fs;
 
 use crate::blob::BlobObject;
-use crate::chat::{self, Chat};
+use crate::chat::{self, Chat, MailinglistReplyMode};
 use crate::config::Config;
 use crate::constants::{Chattype, DC_FROM_HANDSHAKE};
-use crate::contact::{Contact, ContactId, Origin};
+use crate::contact::{self, Contact, ContactId, EncryptionOverride, Origin};
 use crate::context::Context;
+use crate::dkim::DkimSigner;
 use crate::e2ee::EncryptHelper;
 use crate::ephemeral::Timer as EphemeralTimer;
 use crate::headerdef::HeaderDef;
@@ -23,14 +29,15 @@
 use crate::location;
 use crate::message::{self, Message, MsgId, Viewtype};
 use crate::mimeparser::SystemMessage;
-use crate::param::Param;
+use crate::param::{Param, Params};
 use crate::peer_channels::create_iroh_header;
 use crate::peerstate::Peerstate;
 use crate::simplify::escape_message_footer_marks;
 use crate::stock_str;
 use crate::tools::IsNoneOrEmpty;
 use crate::tools::{
-    create_outgoing_rfc724_mid, create_smeared_timestamp, remove_subject_prefix, time,
+    create_outgoing_rfc724_mid, create_smeared_timestamp, remove_reply_prefixes,
+    remove_subject_prefix, time,
 };
 use crate::webxdc::StatusUpdateSerial;
 
@@ -124,6 +131,23 @@ pub struct MimeFactory {
 
     /// True if the avatar should be attached.
     pub attach_selfavatar: bool,
+
+    /// Contact asked to resend the profile data because the local copy is stale.
+    pub request_profile_from: Option<ContactId>,
+
+    /// Hash of the avatar announced instead of attaching it,
+    /// see [`chat::uses_lazy_selfavatar`].
+    selfavatar_hash: Option<String>,
+
+    /// Space-separated addresses of members asked to attach their avatar.
+    pub avatar_requests: Option<String>,
+
+    /// Custom headers of the chat, see [`chat::ChatId::set_custom_header`].
+    custom_headers: Vec<(String, String)>,
+
+    /// Addresses of recipients for which the user has set
+    /// [`EncryptionOverride::ForcePlaintext`].
+    plaintext_recipients: Vec<String>,
 }
 
 /// Result of rendering a message, ready to be submitted to a send job.
@@ -146,6 +170,66 @@ pub struct RenderedEmail {
     pub subject: String,
 }
 
+/// Options for deterministic rendering of outgoing messages.
+///
+/// Meant for snapshot tests of rendered messages,
+/// see [`Context::set_deterministic_mime`].
+/// MIME boundaries and generated Message-IDs are taken from a random generator
+/// seeded with `seed`, so rendering the same messages in the same order produces the same output.
+/// The `Date` header is taken from [`crate::tools::time`] as usual,
+/// tests can shift it with [`crate::tools::SystemTime::shift`].
+/// Encrypted and signed messages are still not reproducible.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeterministicMime {
+    /// Seed of the random generator.
+    pub seed: u64,
+}
+
+/// State of the deterministic rendering mode of a context.
+#[derive(Debug)]
+pub(crate) struct DeterministicMimeState {
+    rng: StdRng,
+}
+
+/// Matches the MIME boundaries of a rendered message.
+static BOUNDARY: Lazy<Regex> = Lazy::new(|| Regex::new(r#"boundary="([^"]+)""#).unwrap());
+
+impl DeterministicMimeState {
+    pub(crate) fn new(options: DeterministicMime) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(options.seed),
+        }
+    }
+
+    /// Returns the next ID in the same format as [`crate::tools::create_id`].
+    fn create_id(&mut self) -> String {
+        let mut arr = [0u8; 18];
+        self.rng.fill(&mut arr[..]);
+        base64::engine::general_purpose::URL_SAFE.encode(arr)
+    }
+
+    /// Replaces random MIME boundaries of the rendered message with seeded ones.
+    fn replace_boundaries(&mut self, message: String) -> String {
+        let boundaries: Vec<String> = BOUNDARY
+            .captures_iter(&message)
+            .map(|captures| captures[1].to_string())
+            .collect();
+        boundaries.into_iter().fold(message, |message, boundary| {
+            let replacement = self.create_id();
+            message.replace(&boundary, &replacement)
+        })
+    }
+}
+
+/// Returns a new Message-ID for an outgoing message,
+/// taking the deterministic rendering mode into account.
+pub(crate) fn create_rfc724_mid(context: &Context) -> String {
+    match context.deterministic_mime.lock().as_mut() {
+        Some(state) => format!("{}@localhost", state.create_id()),
+        None => create_outgoing_rfc724_mid(),
+    }
+}
+
 fn new_address_with_name(name: &str, address: String) -> Address {
     match name == address {
         true => Address::new_mailbox(address),
@@ -154,13 +238,50 @@ fn new_address_with_name(name: &str, address: String) -> Address {
 }
 
 impl MimeFactory {
+    /// Returns the address a message in a mailing list chat is privately sent to
+    /// instead of the `List-Post` address.
+    ///
+    /// This is the case if the chat uses [`MailinglistReplyMode::Sender`]
+    /// and the message quotes a message from someone else.
+    /// The `Reply-To` address of the quoted message is preferred over its sender.
+    async fn mailinglist_private_reply_addr(
+        context: &Context,
+        chat: &Chat,
+        msg: &Message,
+    ) -> Result<Option<String>> {
+        if chat.get_mailinglist_reply_mode() != MailinglistReplyMode::Sender {
+            return Ok(None);
+        }
+        let Some(quoted_msg) = msg.quoted_message(context).await? else {
+            return Ok(None);
+        };
+        if quoted_msg.from_id == ContactId::SELF || quoted_msg.chat_id != chat.id {
+            return Ok(None);
+        }
+        let addr = match quoted_msg.param.get(Param::ReplyTo) {
+            Some(reply_to) => reply_to.to_string(),
+            None => Contact::get_by_id(context, quoted_msg.from_id)
+                .await?
+                .get_addr()
+                .to_string(),
+        };
+        if chat
+            .param
+            .get(Param::ListPost)
+            .is_some_and(|list_post| addr_cmp(list_post, &addr))
+        {
+            return Ok(None);
+        }
+        Ok(Some(addr))
+    }
+
     pub async fn from_msg(context: &Context, msg: Message) -> Result<MimeFactory> {
         let now = time();
         let chat = Chat::load_from_db(context, msg.chat_id).await?;
         let attach_profile_data = Self::should_attach_profile_data(&msg);
         let undisclosed_recipients = chat.typ == Chattype::Broadcast;
 
-        let from_addr = context.get_primary_self_addr().await?;
+        let from_addr = addr_with_ascii_domain(&context.get_primary_self_addr().await?);
         let config_displayname = context
             .get_config(Config::Displayname)
             .await?
@@ -181,6 +302,7 @@ pub async fn from_msg(context: &Context, msg: Message) -> Result<MimeFactory> {
         let mut past_members = Vec::new();
         let mut member_timestamps = Vec::new();
         let mut recipient_ids = HashSet::new();
+        let mut plaintext_recipients = Vec::new();
         let mut req_mdn = false;
 
         if chat.is_self_talk() {
@@ -189,12 +311,22 @@ pub async fn from_msg(context: &Context, msg: Message) -> Result<MimeFactory> {
             }
             to.push((from_displayname.to_string(), from_addr.to_string()));
         } else if chat.is_mailing_list() {
-            let list_post = chat
-                .param
-                .get(Param::ListPost)
-                .context("Can't write to mailinglist without ListPost param")?;
-            to.push(("".to_string(), list_post.to_string()));
-            recipients.push(list_post.to_string());
+            let addr = if let Some(addr) = msg.param.get(Param::MailinglistRecipient) {
+                // Subscribe or unsubscribe request.
+                Some(addr.to_string())
+            } else {
+                Self::mailinglist_private_reply_addr(context, &chat, &msg).await?
+            };
+            let addr = match addr {
+                Some(addr) => addr,
+                None => chat
+                    .param
+                    .get(Param::ListPost)
+                    .context("Can't write to mailinglist without ListPost param")?
+                    .to_string(),
+            };
+            to.push(("".to_string(), addr.clone()));
+            recipients.push(addr);
         } else {
             let email_to_remove = if msg.param.get_cmd() == SystemMessage::MemberRemovedFromGroup {
                 msg.param.get(Param::Arg)
@@ -205,7 +337,7 @@ pub async fn from_msg(context: &Context, msg: Message) -> Result<MimeFactory> {
             context
                 .sql
                 .query_map(
-                    "SELECT c.authname, c.addr, c.id, cc.add_timestamp, cc.remove_timestamp
+                    "SELECT c.authname, c.addr, c.id, cc.add_timestamp, cc.remove_timestamp, c.param
                      FROM chats_contacts cc
                      LEFT JOIN contacts c ON cc.contact_id=c.id
                      WHERE cc.chat_id=? AND (cc.contact_id>9 OR (cc.contact_id=1 AND ?))",
@@ -216,13 +348,15 @@ pub async fn from_msg(context: &Context, msg: Message) -> Result<MimeFactory> {
                         let id: ContactId = row.get(2)?;
                         let add_timestamp: i64 = row.get(3)?;
                         let remove_timestamp: i64 = row.get(4)?;
-                        Ok((authname, addr, id, add_timestamp, remove_timestamp))
+                        let param: Params = row.get::<_, String>(5)?.parse().unwrap_or_default();
+                        Ok((authname, addr, id, add_timestamp, remove_timestamp, param))
                     },
                     |rows| {
                         let mut past_member_timestamps = Vec::new();
 
                         for row in rows {
-                            let (authname, addr, id, add_timestamp, remove_timestamp) = row?;
+                            let (authname, addr, id, add_timestamp, remove_timestamp, param) =
+                                row?;
                             let addr = if id == ContactId::SELF {
                                 from_addr.to_string()
                             } else {
@@ -233,6 +367,13 @@ pub async fn from_msg(context: &Context, msg: Message) -> Result<MimeFactory> {
                                 false => "".to_string(),
                             };
                             if add_timestamp >= remove_timestamp {
+                                if chat.typ == Chattype::Single
+                                    && id != ContactId::SELF
+                                    && param.get_int(Param::EncryptionOverride)
+                                        == Some(EncryptionOverride::ForcePlaintext as i32)
+                                {
+                                    plaintext_recipients.push(addr.clone());
+                                }
                                 if !recipients_contain_addr(&to, &addr) {
                                     recipients.push(addr.clone());
                                     if !undisclosed_recipients {
@@ -280,6 +421,7 @@ pub async fn from_msg(context: &Context, msg: Message) -> Result<MimeFactory> {
 
             if !msg.is_system_message()
                 && msg.param.get_int(Param::Reaction).unwrap_or_default() == 0
+                && !chat.is_mdn_requests_disabled()
                 && context.should_request_mdns().await?
             {
                 req_mdn = true;
@@ -310,6 +452,34 @@ pub async fn from_msg(context: &Context, msg: Message) -> Result<MimeFactory> {
             false => "".to_string(),
         };
         let attach_selfavatar = Self::should_attach_selfavatar(context, &msg).await;
+        let request_profile_from =
+            if attach_profile_data && msg.param.get_cmd() == SystemMessage::Unknown {
+                chat::shall_request_profile(context, &chat).await?
+            } else {
+                None
+            };
+        let selfavatar_hash = if !attach_selfavatar
+            && attach_profile_data
+            && chat.typ == Chattype::Group
+            && chat::uses_lazy_selfavatar(context, chat.id).await?
+        {
+            let selfavatar = context.get_config(Config::Selfavatar).await?;
+            match contact::avatar_hash(selfavatar.as_deref().map(Path::new)).await {
+                Ok(hash) => Some(hash),
+                Err(err) => {
+                    warn!(context, "Cannot get selfavatar hash: {err:#}.");
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        let avatar_requests = if attach_profile_data {
+            chat.param.get(Param::AvatarRequests).map(|s| s.to_string())
+        } else {
+            None
+        };
+        let custom_headers = chat.id.get_custom_headers(context).await?;
 
         debug_assert!(
             member_timestamps.is_empty()
@@ -332,6 +502,11 @@ pub async fn from_msg(context: &Context, msg: Message) -> Result<MimeFactory> {
             last_added_location_id: None,
             sync_ids_to_delete: None,
             attach_selfavatar,
+            request_profile_from,
+            selfavatar_hash,
+            avatar_requests,
+            custom_headers,
+            plaintext_recipients,
         };
         Ok(factory)
     }
@@ -342,8 +517,29 @@ pub async fn from_mdn(
         rfc724_mid: String,
         additional_msg_ids: Vec<String>,
     ) -> Result<MimeFactory> {
-        let contact = Contact::get_by_id(context, from_id).await?;
-        let from_addr = context.get_primary_self_addr().await?;
+        Self::from_batched_mdn(context, &[from_id], rfc724_mid, additional_msg_ids).await
+    }
+
+    /// Creates a single MDN for messages of several contacts, e.g. in the same group.
+    ///
+    /// The recipients only process the Message-IDs of their own messages.
+    pub(crate) async fn from_batched_mdn(
+        context: &Context,
+        contact_ids: &[ContactId],
+        rfc724_mid: String,
+        additional_msg_ids: Vec<String>,
+    ) -> Result<MimeFactory> {
+        let mut recipients = Vec::new();
+        let mut plaintext_recipients = Vec::new();
+        for &contact_id in contact_ids {
+            let contact = Contact::get_by_id(context, contact_id).await?;
+            let addr = contact.get_addr().to_string();
+            if contact.get_encryption_override() == EncryptionOverride::ForcePlaintext {
+                plaintext_recipients.push(addr.clone());
+            }
+            recipients.push(addr);
+        }
+        let from_addr = addr_with_ascii_domain(&context.get_primary_self_addr().await?);
         let timestamp = create_smeared_timestamp(context);
 
         let res = MimeFactory {
@@ -351,8 +547,11 @@ pub async fn from_mdn(
             from_displayname: "".to_string(),
             sender_displayname: None,
             selfstatus: "".to_string(),
-            recipients: vec![contact.get_addr().to_string()],
-            to: vec![("".to_string(), contact.get_addr().to_string())],
+            to: recipients
+                .iter()
+                .map(|addr| ("".to_string(), addr.clone()))
+                .collect(),
+            recipients,
             past_members: vec![],
             member_timestamps: vec![],
             timestamp,
@@ -366,6 +565,11 @@ pub async fn from_mdn(
             last_added_location_id: None,
             sync_ids_to_delete: None,
             attach_selfavatar: false,
+            request_profile_from: None,
+            selfavatar_hash: None,
+            avatar_requests: None,
+            custom_headers: Vec::new(),
+            plaintext_recipients,
         };
 
         Ok(res)
@@ -392,6 +596,7 @@ fn is_e2ee_guaranteed(&self) -> bool {
                     .get_bool(Param::ForcePlaintext)
                     .unwrap_or_default()
                     && (chat.is_protected()
+                        || chat.is_encryption_required()
                         || msg.param.get_bool(Param::GuaranteeE2ee).unwrap_or_default())
             }
             Loaded::Mdn { .. } => false,
@@ -419,8 +624,11 @@ fn should_force_plaintext(&self) -> bool {
                     .get_bool(Param::ForcePlaintext)
                     .unwrap_or_default()
                     || chat.typ == Chattype::Broadcast
+                    || (!self.plaintext_recipients.is_empty()
+                        && !chat.is_protected()
+                        && !chat.is_encryption_required())
             }
-            Loaded::Mdn { .. } => false,
+            Loaded::Mdn { .. } => !self.plaintext_recipients.is_empty(),
         }
     }
 
@@ -546,7 +754,13 @@ async fn subject_str(&self, context: &Context) -> Result<String> {
                     quoted_msg_subject.as_deref()
                 };
                 if let Some(last_subject) = parent_subject {
-                    return Ok(format!("Re: {}", remove_subject_prefix(last_subject)));
+                    let last_subject =
+                        if context.get_config_bool(Config::MuaThreadingCompat).await? {
+                            remove_reply_prefixes(last_subject)
+                        } else {
+                            remove_subject_prefix(last_subject)
+                        };
+                    return Ok(format!("Re: {last_subject}"));
                 }
 
                 let self_name = match Self::should_attach_profile_data(msg) {
@@ -569,6 +783,11 @@ pub fn recipients(&self) -> Vec<String> {
         self.recipients.clone()
     }
 
+    /// Returns true if the message is sent to a broadcast list.
+    pub(crate) fn is_broadcast(&self) -> bool {
+        matches!(&self.loaded, Loaded::Message { chat, .. } if chat.typ == Chattype::Broadcast)
+    }
+
     /// Consumes a `MimeFactory` and renders it into a message which is then stored in
     /// `smtp`-table to be used by the SMTP loop
     pub async fn render(mut self, context: &Context) -> Result<RenderedEmail> {
@@ -661,7 +880,7 @@ pub async fn render(mut self, context: &Context) -> Result<RenderedEmail> {
 
         let rfc724_mid = match &self.loaded {
             Loaded::Message { msg, .. } => msg.rfc724_mid.clone(),
-            Loaded::Mdn { .. } => create_outgoing_rfc724_mid(),
+            Loaded::Mdn { .. } => create_rfc724_mid(context),
         };
         let rfc724_mid_headervalue = render_rfc724_mid(&rfc724_mid);
         let rfc724_mid_header = Header::new("Message-ID".into(), rfc724_mid_headervalue);
@@ -750,6 +969,20 @@ pub async fn render(mut self, context: &Context) -> Result<RenderedEmail> {
 
         let mut is_gossiped = false;
 
+        if !self.plaintext_recipients.is_empty() {
+            let addrs = self.plaintext_recipients.join(", ");
+            if self.should_force_plaintext() {
+                warn!(
+                    context,
+                    "Sending message unencrypted because plaintext is forced for {addrs}."
+                );
+            } else {
+                warn!(
+                    context,
+                    "Ignoring forced plaintext for {addrs} as encryption is required."
+                );
+            }
+        }
         let peerstates = self.peerstates_for_recipients(context).await?;
         let is_encrypted = !self.should_force_plaintext()
             && encrypt_helper
@@ -1073,8 +1306,16 @@ pub async fn render(mut self, context: &Context) -> Result<RenderedEmail> {
             ..
         } = self;
 
+        let mut message = outer_message.build().as_string();
+        if let Some(state) = context.deterministic_mime.lock().as_mut() {
+            message = state.replace_boundaries(message);
+        }
+        if let Some(signer) = DkimSigner::load(context, &self.from_addr).await? {
+            message = signer.sign(&message, time())?;
+        }
+
         Ok(RenderedEmail {
-            message: outer_message.build().as_string(),
+            message,
             // envelope: Envelope::new,
             is_encrypted,
             is_gossiped,
@@ -1184,6 +1425,28 @@ async fn render_message(
             let encoded = encode_words(&chat.name);
             headers.push(Header::new("Chat-Group-Name".into(), encoded));
 
+            // Admins are sent from the creation of the group on,
+            // so that members only accept changes of admins and moderation from known admins.
+            let admin_ids = chat::get_group_admins(context, chat.id).await?;
+            if !admin_ids.is_empty() {
+                let mut admins = Vec::new();
+                for contact_id in admin_ids {
+                    let contact = Contact::get_by_id(context, contact_id).await?;
+                    admins.push(contact.get_addr().to_string());
+                }
+                headers.push(Header::new("Chat-Group-Admins".into(), admins.join(" ")));
+                if chat.is_moderated() {
+                    headers.push(Header::new("Chat-Group-Moderated".into(), "1".into()));
+                }
+            }
+            if msg
+                .param
+                .get_bool(Param::GroupPromotion)
+                .unwrap_or_default()
+            {
+                headers.push(Header::new("Chat-Group-Created".into(), "1".into()));
+            }
+
             match command {
                 SystemMessage::MemberRemovedFromGroup => {
                     let email_to_remove = msg.param.get(Param::Arg).unwrap_or_default();
@@ -1236,6 +1499,12 @@ async fn render_message(
                         maybe_encode_words(old_name),
                     ));
                 }
+                SystemMessage::GroupAdminsChanged => {
+                    headers.push(Header::new(
+                        "Chat-Content".to_string(),
+                        "group-admins-changed".to_string(),
+                    ));
+                }
                 SystemMessage::GroupImageChanged => {
                     headers.push(Header::new(
                         "Chat-Content".to_string(),
@@ -1328,6 +1597,12 @@ async fn render_message(
                     "protection-disabled".to_string(),
                 ));
             }
+            SystemMessage::KeyTransition => {
+                headers.push(Header::new(
+                    HeaderDef::ChatKeyTransition.get_headername().to_string(),
+                    msg.param.get(Param::Arg).unwrap_or_default().to_string(),
+                ));
+            }
             SystemMessage::IrohNodeAddr => {
                 headers.push(Header::new(
                     HeaderDef::IrohNodeAddr.get_headername().to_string(),
@@ -1446,6 +1721,12 @@ async fn render_message(
 
         if is_reaction {
             main_part = main_part.header(("Content-Disposition", "reaction"));
+            if let Some(custom_emojis) = msg.param.get(Param::CustomEmojis) {
+                headers.push(Header::new(
+                    HeaderDef::ChatCustomEmojis.get_headername().to_string(),
+                    maybe_encode_words(custom_emojis),
+                ));
+            }
         }
 
         let mut parts = Vec::new();
@@ -1522,6 +1803,25 @@ async fn render_message(
             }
         }
 
+        if self.request_profile_from.is_some() {
+            headers.push(Header::new("Chat-Profile-Request".into(), "1".into()));
+        }
+
+        if let Some(hash) = &self.selfavatar_hash {
+            headers.push(Header::new("Chat-User-Avatar-Hash".into(), hash.clone()));
+        }
+
+        if let Some(requests) = &self.avatar_requests {
+            headers.push(Header::new(
+                "Chat-User-Avatar-Request".into(),
+                requests.clone(),
+            ));
+        }
+
+        for (name, value) in &self.custom_headers {
+            headers.push(Header::new(name.clone(), value.clone()));
+        }
+
         Ok((main_part, parts))
     }
 
@@ -2331,6 +2631,79 @@ async fn test_render_reply() {
             .unwrap();
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_mua_threading_compat() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        t.set_config_bool(Config::MuaThreadingCompat, true).await?;
+
+        let references: Vec<String> = (1..=12).map(|i| format!("<{i}@example.com>")).collect();
+        let imf_raw = format!(
+            "Received: (Postfix, from userid 1000); Mon, 4 Dec 2006 14:51:39 +0100 (CET)\n\
+             From: Bob <bob@example.com>\n\
+             To: alice@example.org\n\
+             Subject: AW: Re: Hello\n\
+             Message-ID: <13@example.com>\n\
+             In-Reply-To: <12@example.com>\n\
+             References: {}\n\
+             Date: Sun, 22 Mar 2020 22:37:56 +0000\n\
+             \n\
+             hello\n",
+            references.join(" ")
+        );
+        let mut msg = incoming_msg_to_reply_msg(imf_raw.as_bytes(), &t).await;
+        let incoming_msg = t.get_last_msg().await;
+        chat::send_msg(&t, msg.chat_id, &mut msg).await?;
+
+        let mimefactory = MimeFactory::from_msg(&t, msg).await?;
+        assert_eq!(mimefactory.subject_str(&t).await?, "Re: Hello");
+        let rendered_msg = mimefactory.render(&t).await?;
+        let mail = mailparse::parse_mail(rendered_msg.message.as_bytes())?;
+        assert_eq!(
+            mail.headers.get_first_value("In-Reply-To").unwrap(),
+            "<13@example.com>"
+        );
+        // The thread root is kept and the parent is the last reference.
+        let references = mail.headers.get_first_value("References").unwrap();
+        let references: Vec<&str> = references.split_ascii_whitespace().collect();
+        assert_eq!(references.len(), 10);
+        assert_eq!(references[0], "<1@example.com>");
+        assert_eq!(references[1], "<5@example.com>");
+        assert_eq!(references[9], "<13@example.com>");
+
+        // Another message arrives, but Alice quotes the previous one.
+        receive_imf(
+            &t,
+            b"Received: (Postfix, from userid 1000); Mon, 4 Dec 2006 14:51:39 +0100 (CET)\n\
+              From: Bob <bob@example.com>\n\
+              To: alice@example.org\n\
+              Subject: Re: Hello\n\
+              Message-ID: <14@example.com>\n\
+              In-Reply-To: <13@example.com>\n\
+              References: <1@example.com> <13@example.com>\n\
+              Date: Sun, 22 Mar 2020 22:38:56 +0000\n\
+              \n\
+              hello again\n",
+            false,
+        )
+        .await?;
+        let mut msg = Message::new_text("Quoting".to_string());
+        msg.set_quote(&t, Some(&incoming_msg)).await?;
+        chat::send_msg(&t, incoming_msg.chat_id, &mut msg).await?;
+
+        let rendered_msg = MimeFactory::from_msg(&t, msg).await?.render(&t).await?;
+        let mail = mailparse::parse_mail(rendered_msg.message.as_bytes())?;
+        assert_eq!(
+            mail.headers.get_first_value("In-Reply-To").unwrap(),
+            "<13@example.com>"
+        );
+        assert_eq!(
+            mail.headers.get_first_value("References").unwrap(),
+            "<1@example.com> <14@example.com> <13@example.com>"
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn test_no_empty_lines_in_header() {
         // See <https://github.com/deltachat/deltachat-core-rust/issues/2118>
@@ -2684,4 +3057,32 @@ async fn test_dont_remove_self() -> Result<()> {
 
         Ok(())
     }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_deterministic_mime() -> Result<()> {
+        let options = DeterministicMime { seed: 42 };
+        let mut payloads = Vec::new();
+        for _ in 0..2 {
+            let t = TestContext::new_alice().await;
+            t.set_deterministic_mime(Some(options));
+            let chat = t.create_chat_with_contact("bob", "bob@example.org").await;
+            let mut msg = Message::new(Viewtype::File);
+            msg.set_file_from_bytes(&t, "file.txt", b"Hello", None)?;
+            msg.set_text("Hi".to_string());
+            let sent = t.send_msg(chat.id, &mut msg).await;
+            let payload = sent.payload;
+            // The `Date` header follows the system time.
+            let timestamp = Message::load_from_db(&t, sent.sender_msg_id)
+                .await?
+                .timestamp_sort;
+            let date = chrono::DateTime::<chrono::Utc>::from_timestamp(timestamp, 0)
+                .unwrap()
+                .to_rfc2822();
+            let date_line = format!("Date: {date}\r\n");
+            assert!(payload.contains(&date_line));
+            payloads.push(payload.replace(&date_line, ""));
+        }
+        assert_eq!(payloads[0], payloads[1]);
+        Ok(())
+    }
 }
@@ -7,7 +7,7 @@
 use std::str::FromStr;
 
 use anyhow::{bail, Context as _, Result};
-use deltachat_contact_tools::{addr_cmp, addr_normalize, sanitize_bidi_characters};
+use deltachat_contact_tools::{addr_cmp, addr_normalize, sanitize_bidi_characters, EmailAddress};
 use deltachat_derive::{FromSql, ToSql};
 use format_flowed::unformat_flowed;
 use lettre_email::mime::Mime;
@@ -23,13 +23,16 @@
 use crate::context::Context;
 use crate::decrypt::{
     get_autocrypt_peerstate, get_encrypted_mime, keyring_from_peerstate, try_decrypt,
-    validate_detached_signature,
+    validate_detached_signature, DecryptionResult,
 };
 use crate::dehtml::dehtml;
 use crate::events::EventType;
 use crate::headerdef::{HeaderDef, HeaderDefMap};
 use crate::key::{self, load_self_secret_keyring, DcKey, Fingerprint, SignedPublicKey};
-use crate::message::{self, get_vcard_summary, set_msg_failed, Message, MsgId, Viewtype};
+use crate::message::{
+    self, get_vcard_summary, set_msg_failed, DeliveryAction, DeliveryFailure, Message, MsgId,
+    Viewtype,
+};
 use crate::param::{Param, Params};
 use crate::peerstate::Peerstate;
 use crate::simplify::{simplify, SimplifiedText};
@@ -38,6 +41,7 @@
 use crate::tools::{
     get_filemeta, parse_receive_headers, smeared_time, truncate_msg_text, validate_id,
 };
+use crate::webxdc::{WebxdcUnsupported, WEBXDC_SUFFIX};
 use crate::{chatlist_events, location, stock_str, tools};
 
 /// A parsed MIME message.
@@ -71,6 +75,9 @@ pub(crate) struct MimeMessage {
     /// Whether the From address was repeated in the signed part
     /// (and we know that the signer intended to send from this address)
     pub from_is_signed: bool,
+    /// Whether the DKIM check of the sender's domain passed,
+    /// see [`crate::authres::DkimResults`].
+    pub(crate) dkim_passed: bool,
     /// Whether the message is incoming or outgoing (self-sent).
     pub incoming: bool,
     /// The List-Post address is only set for mailing lists. Users can send
@@ -214,6 +221,12 @@ pub enum SystemMessage {
 
     /// This message contains a users iroh node address.
     IrohNodeAddr = 40,
+
+    /// Hidden message announcing the new key to verified contacts.
+    KeyTransition = 41,
+
+    /// Group admins changed or group moderation enabled or disabled.
+    GroupAdminsChanged = 42,
 }
 
 const MIME_AC_SETUP_FILE: &str = "application/autocrypt-setup";
@@ -227,6 +240,17 @@ pub(crate) async fn from_bytes(
         context: &Context,
         body: &[u8],
         partial: Option<u32>,
+    ) -> Result<Self> {
+        Self::from_bytes_ex(context, body, partial, None).await
+    }
+
+    /// Parses a message like [`MimeMessage::from_bytes`],
+    /// using the `predecrypted` result instead of decrypting the message if it is given.
+    pub(crate) async fn from_bytes_ex(
+        context: &Context,
+        body: &[u8],
+        partial: Option<u32>,
+        predecrypted: Option<DecryptionResult>,
     ) -> Result<Self> {
         let mail = mailparse::parse_mail(body)?;
 
@@ -333,42 +357,45 @@ pub(crate) async fn from_bytes(
         let mail_raw; // Memory location for a possible decrypted message.
         let decrypted_msg; // Decrypted signed OpenPGP message.
 
-        let (mail, encrypted) =
-            match tokio::task::block_in_place(|| try_decrypt(&mail, &private_keyring)) {
-                Ok(Some(msg)) => {
-                    mail_raw = msg.get_content()?.unwrap_or_default();
-
-                    let decrypted_mail = mailparse::parse_mail(&mail_raw)?;
-                    if std::env::var(crate::DCC_MIME_DEBUG).is_ok() {
-                        info!(
-                            context,
-                            "decrypted message mime-body:\n{}",
-                            String::from_utf8_lossy(&mail_raw),
-                        );
-                    }
-
-                    decrypted_msg = Some(msg);
-                    if let Some(protected_aheader_value) = decrypted_mail
-                        .headers
-                        .get_header_value(HeaderDef::Autocrypt)
-                    {
-                        aheader_value = Some(protected_aheader_value);
-                    }
+        let decryption = match predecrypted {
+            Some(decryption) => decryption,
+            None => tokio::task::block_in_place(|| try_decrypt(&mail, &private_keyring)),
+        };
+        let (mail, encrypted) = match decryption {
+            Ok(Some(msg)) => {
+                mail_raw = msg.get_content()?.unwrap_or_default();
 
-                    (Ok(decrypted_mail), true)
-                }
-                Ok(None) => {
-                    mail_raw = Vec::new();
-                    decrypted_msg = None;
-                    (Ok(mail), false)
+                let decrypted_mail = mailparse::parse_mail(&mail_raw)?;
+                if std::env::var(crate::DCC_MIME_DEBUG).is_ok() {
+                    info!(
+                        context,
+                        "decrypted message mime-body:\n{}",
+                        String::from_utf8_lossy(&mail_raw),
+                    );
                 }
-                Err(err) => {
-                    mail_raw = Vec::new();
-                    decrypted_msg = None;
-                    warn!(context, "decryption failed: {:#}", err);
-                    (Err(err), false)
+
+                decrypted_msg = Some(msg);
+                if let Some(protected_aheader_value) = decrypted_mail
+                    .headers
+                    .get_header_value(HeaderDef::Autocrypt)
+                {
+                    aheader_value = Some(protected_aheader_value);
                 }
-            };
+
+                (Ok(decrypted_mail), true)
+            }
+            Ok(None) => {
+                mail_raw = Vec::new();
+                decrypted_msg = None;
+                (Ok(mail), false)
+            }
+            Err(err) => {
+                mail_raw = Vec::new();
+                decrypted_msg = None;
+                warn!(context, "decryption failed: {:#}", err);
+                (Err(err), false)
+            }
+        };
 
         let autocrypt_header = if !incoming {
             None
@@ -448,6 +475,9 @@ pub(crate) async fn from_bytes(
                     HeaderDef::ChatGroupMemberAdded,
                     HeaderDef::ChatGroupMemberTimestamps,
                     HeaderDef::ChatGroupPastMembers,
+                    HeaderDef::ChatGroupAdmins,
+                    HeaderDef::ChatGroupCreated,
+                    HeaderDef::ChatGroupModerated,
                 ] {
                     headers.remove(h.get_headername());
                 }
@@ -526,6 +556,7 @@ pub(crate) async fn from_bytes(
             list_post,
             from,
             from_is_signed,
+            dkim_passed: dkim_results.dkim_passed,
             incoming,
             chat_disposition_notification_to,
             autocrypt_header,
@@ -640,6 +671,8 @@ fn parse_system_message_headers(&mut self, context: &Context) {
                 self.is_system_message = SystemMessage::ChatProtectionDisabled;
             } else if value == "group-avatar-changed" {
                 self.is_system_message = SystemMessage::GroupImageChanged;
+            } else if value == "group-admins-changed" {
+                self.is_system_message = SystemMessage::GroupAdminsChanged;
             }
         } else if self.get_header(HeaderDef::ChatGroupMemberRemoved).is_some() {
             self.is_system_message = SystemMessage::MemberRemovedFromGroup;
@@ -916,6 +949,14 @@ pub(crate) fn get_subject(&self) -> Option<String> {
             .map(|s| s.to_string())
     }
 
+    /// Returns `X-` headers of the message as `(lowercased name, value)` pairs.
+    pub(crate) fn x_headers(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.headers
+            .iter()
+            .filter(|(name, _)| name.starts_with("x-"))
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
     pub fn get_header(&self, headerdef: HeaderDef) -> Option<&str> {
         self.headers
             .get(headerdef.get_headername())
@@ -1314,13 +1355,7 @@ async fn do_add_single_file_part(
             }
         }
         let mut part = Part::default();
-        let msg_type = if context
-            .is_webxdc_file(filename, decoded_data)
-            .await
-            .unwrap_or(false)
-        {
-            Viewtype::Webxdc
-        } else if filename.ends_with(".kml") {
+        let mut msg_type = if filename.ends_with(".kml") {
             // XXX what if somebody sends eg an "location-highlights.kml"
             // attachment unrelated to location streaming?
             if filename.starts_with("location") || filename.starts_with("message") {
@@ -1384,6 +1419,21 @@ async fn do_add_single_file_part(
             };
         info!(context, "added blobfile: {:?}", blob.as_name());
 
+        if filename.ends_with(WEBXDC_SUFFIX) {
+            // The archive is checked from the blob file, so it is not read into memory again.
+            match context
+                .check_webxdc_file(filename, &blob.to_abs_path())
+                .await
+                .unwrap_or(Some(WebxdcUnsupported::Invalid))
+            {
+                None => msg_type = Viewtype::Webxdc,
+                Some(unsupported) => {
+                    part.param
+                        .set_int(Param::WebxdcUnsupported, unsupported as i32);
+                }
+            }
+        }
+
         if mime_type.type_() == mime::IMAGE {
             if let Ok((width, height)) = get_filemeta(decoded_data) {
                 part.param.set_int(Param::Width, width as i32);
@@ -1470,6 +1520,34 @@ fn do_add_single_part(&mut self, mut part: Part) {
         self.parts.push(part);
     }
 
+    /// Returns the URIs in angle brackets from a `List-*` header
+    /// as defined in [RFC 2369](https://datatracker.ietf.org/doc/html/rfc2369).
+    ///
+    /// Only `mailto:` and `https:` URIs are returned.
+    pub(crate) fn get_list_uris(&self, headerdef: HeaderDef) -> Vec<String> {
+        let Some(value) = self.get_header(headerdef) else {
+            return Vec::new();
+        };
+        value
+            .split(',')
+            .filter_map(|item| {
+                let item = item.trim();
+                let uri = item.strip_prefix('<')?.split('>').next()?.trim();
+                let scheme = uri.split(':').next()?.to_ascii_lowercase();
+                (scheme == "mailto" || scheme == "https").then(|| uri.to_string())
+            })
+            .collect()
+    }
+
+    /// Returns the normalized and lowercased address from the `Reply-To` header
+    /// if it contains a single address.
+    pub(crate) fn get_reply_to(&self) -> Option<String> {
+        let value = self.get_header(HeaderDef::ReplyTo)?;
+        let addrs = mailparse::addrparse(value).ok()?;
+        let info = addrs.extract_single_info()?;
+        Some(addr_normalize(&info.addr).to_lowercase())
+    }
+
     pub(crate) fn get_mailinglist_header(&self) -> Option<&str> {
         if let Some(list_id) = self.get_header(HeaderDef::ListId) {
             // The message belongs to a mailing list and has a `ListId:`-header
@@ -1633,6 +1711,8 @@ fn process_delivery_status(
     ) -> Result<Option<DeliveryReport>> {
         // Assume failure.
         let mut failure = true;
+        let mut failure_reason = None;
+        let mut delivered = Vec::new();
 
         if let Some(status_part) = report.subparts.get(1) {
             // RFC 3464 defines `message/delivery-status`
@@ -1660,6 +1740,10 @@ fn process_delivery_status(
                 } else {
                     warn!(context, "DSN without action");
                 }
+                failure_reason = status_fields
+                    .get_first_value("status")
+                    .map(|status| DeliveryFailure::from_status(&status));
+                delivered = parse_dsn_recipients(status_body)?;
             } else {
                 warn!(context, "DSN without per-recipient fields");
             }
@@ -1684,6 +1768,8 @@ fn process_delivery_status(
                 return Ok(Some(DeliveryReport {
                     rfc724_mid: original_message_id,
                     failure,
+                    failure_reason,
+                    delivered,
                 }));
             }
 
@@ -1781,6 +1867,8 @@ async fn heuristically_parse_ndn(&mut self, context: &Context) {
                     self.delivery_report = Some(DeliveryReport {
                         rfc724_mid: original_message_id,
                         failure: true,
+                        failure_reason: None,
+                        delivered: Vec::new(),
                     })
                 }
             }
@@ -1815,6 +1903,20 @@ pub async fn handle_reports(&self, context: &Context, from_id: ContactId, parts:
                     warn!(context, "Could not handle NDN: {err:#}.");
                 }
             }
+            if !delivery_report.delivered.is_empty() {
+                if !self.dkim_passed {
+                    warn!(context, "Ignoring DSN without valid DKIM signature.");
+                } else if let Err(err) = handle_positive_dsn(
+                    context,
+                    delivery_report,
+                    &self.from.addr,
+                    self.timestamp_sent,
+                )
+                .await
+                {
+                    warn!(context, "Could not handle DSN: {err:#}.");
+                }
+            }
         }
     }
 
@@ -1932,6 +2034,43 @@ pub(crate) struct Report {
 pub(crate) struct DeliveryReport {
     pub rfc724_mid: String,
     pub failure: bool,
+
+    /// Reason derived from the `Status` field, if present.
+    pub failure_reason: Option<DeliveryFailure>,
+
+    /// Recipients with a positive action, e.g. `delivered`.
+    pub delivered: Vec<(String, DeliveryAction)>,
+}
+
+/// Parses all per-recipient field groups of a DSN
+/// and returns the recipients with a positive action.
+fn parse_dsn_recipients(mut body: &[u8]) -> Result<Vec<(String, DeliveryAction)>> {
+    let mut recipients = Vec::new();
+    loop {
+        let (fields, sz) = mailparse::parse_headers(body)?;
+        if fields.is_empty() || sz == 0 {
+            break;
+        }
+        let action = fields
+            .get_first_value("action")
+            .and_then(|action| action.trim().to_ascii_lowercase().parse().ok());
+        let addr = fields
+            .get_first_value("final-recipient")
+            .and_then(|recipient| {
+                // The address type, e.g. `rfc822;`, precedes the address.
+                let (_, addr) = recipient.split_once(';')?;
+                Some(addr.trim().to_string())
+            })
+            .filter(|addr| !addr.is_empty());
+        if let (Some(action), Some(addr)) = (action, addr) {
+            recipients.push((addr, action));
+        }
+        match body.get(sz..) {
+            Some(rest) => body = rest,
+            None => break,
+        }
+    }
+    Ok(recipients)
 }
 
 pub(crate) fn parse_message_ids(ids: &str) -> Vec<String> {
@@ -2282,6 +2421,67 @@ async fn handle_mdn(
     Ok(())
 }
 
+/// Returns true if a DSN sent from `from_addr`
+/// may report the delivery status of `recipient`,
+/// i.e. the DSN comes from the server of the recipient's domain.
+fn is_recipient_server(from_addr: &str, recipient: &str) -> bool {
+    let (Ok(from), Ok(recipient)) = (EmailAddress::new(from_addr), EmailAddress::new(recipient))
+    else {
+        return false;
+    };
+    let from_domain = from.domain.to_lowercase();
+    let recipient_domain = recipient.domain.to_lowercase();
+    from_domain == recipient_domain || from_domain.ends_with(&format!(".{recipient_domain}"))
+}
+
+/// Records the per-recipient delivery status after a positive DSN arrived.
+///
+/// Only recipients of the domain the DSN is sent from are considered,
+/// so that servers cannot report deliveries to other domains.
+async fn handle_positive_dsn(
+    context: &Context,
+    report: &DeliveryReport,
+    from_addr: &str,
+    timestamp: i64,
+) -> Result<()> {
+    if report.rfc724_mid.is_empty() {
+        return Ok(());
+    }
+    let msgs = context
+        .sql
+        .query_map(
+            "SELECT id, chat_id FROM msgs WHERE rfc724_mid=? AND from_id=1",
+            (&report.rfc724_mid,),
+            |row| {
+                let msg_id: MsgId = row.get(0)?;
+                let chat_id: ChatId = row.get(1)?;
+                Ok((msg_id, chat_id))
+            },
+            |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+        )
+        .await?;
+    for (msg_id, chat_id) in msgs {
+        let mut changed = false;
+        for (addr, action) in &report.delivered {
+            if !is_recipient_server(from_addr, addr) {
+                info!(
+                    context,
+                    "Ignoring DSN status of {addr} sent by another server for {msg_id}."
+                );
+                continue;
+            }
+            changed |= msg_id
+                .set_recipient_delivery(context, addr, *action, timestamp)
+                .await?;
+        }
+        if changed {
+            info!(context, "Updated delivery status of {msg_id} from DSN.");
+            context.emit_msgs_changed(chat_id, msg_id);
+        }
+    }
+    Ok(())
+}
+
 /// Marks a message as failed after an ndn (non-delivery-notification) arrived.
 /// Where appropriate, also adds an info message telling the user which of the recipients of a group message failed.
 async fn handle_ndn(
@@ -2319,6 +2519,12 @@ async fn handle_ndn(
     for msg in msgs {
         let msg_id = msg?;
         let mut message = Message::load_from_db(context, msg_id).await?;
+        if let Some(failure_reason) = failed.failure_reason {
+            message
+                .param
+                .set(Param::DeliveryFailure, failure_reason.to_string());
+            message.update_param(context).await?;
+        }
         let aggregated_error = message
             .error
             .as_ref()
@@ -5,6 +5,7 @@
 use std::time::Duration;
 
 use anyhow::{format_err, Context as _, Result};
+use serde::{Deserialize, Serialize};
 use tokio::net::TcpStream;
 use tokio::task::JoinSet;
 use tokio::time::timeout;
@@ -17,12 +18,13 @@
 
 pub(crate) mod dns;
 pub(crate) mod http;
+pub(crate) mod jmap;
 pub(crate) mod proxy;
 pub(crate) mod session;
 pub(crate) mod tls;
 
 use dns::lookup_host_with_cache;
-pub use http::{read_url, read_url_blob, Response as HttpResponse};
+pub use http::{read_url, read_url_blob, read_url_blob_ex, Response as HttpResponse};
 use tls::wrap_tls;
 
 /// Connection, write and read timeout.
@@ -33,6 +35,53 @@
 /// TTL for caches in seconds.
 pub(crate) const CACHE_TTL: u64 = 30 * 24 * 60 * 60;
 
+/// Kind of the network the device is currently connected to.
+///
+/// UIs should report it using [`Context::set_network_profile`]
+/// whenever the platform notifies them about a network change.
+/// On metered networks the core defers traffic
+/// that is not needed to deliver and display messages.
+#[derive(
+    Debug,
+    Default,
+    Display,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    FromPrimitive,
+    ToPrimitive,
+    Serialize,
+    Deserialize,
+)]
+#[repr(u32)]
+pub enum NetworkProfile {
+    /// Unmetered network, e.g. Wi-Fi or Ethernet.
+    ///
+    /// This is the default if the UI never reports the network profile.
+    #[default]
+    Unmetered = 0,
+
+    /// Metered network, e.g. mobile data.
+    ///
+    /// Large messages are only downloaded partially.
+    Metered = 1,
+
+    /// Mobile data while roaming.
+    ///
+    /// In addition to the restrictions of [`NetworkProfile::Metered`],
+    /// webxdc status updates are queued until the network changes
+    /// and additional IMAP folders are not scanned.
+    Roaming = 2,
+}
+
+impl NetworkProfile {
+    /// Returns true if traffic on this network is metered.
+    pub fn is_metered(self) -> bool {
+        matches!(self, NetworkProfile::Metered | NetworkProfile::Roaming)
+    }
+}
+
 /// Removes connection history entries after `CACHE_TTL`.
 pub(crate) async fn prune_connection_history(context: &Context) -> Result<()> {
     let now = time();
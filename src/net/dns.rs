@@ -714,7 +714,9 @@ pub(crate) async fn lookup_host_with_cache(
     load_cache: bool,
 ) -> Result<Vec<SocketAddr>> {
     let now = time();
-    let resolved_addrs = match lookup_host_and_update_cache(context, hostname, port, now).await {
+    let res = lookup_host_and_update_cache(context, hostname, port, now).await;
+    context.trace_configure_step(format!("DNS lookup for {hostname}:{port}"), &res);
+    let resolved_addrs = match res {
         Ok(res) => {
             if alpn.is_empty() {
                 res
@@ -1,6 +1,6 @@
 //! # HTTP module.
 
-use anyhow::{anyhow, bail, Context as _, Result};
+use anyhow::{anyhow, bail, ensure, Context as _, Result};
 use bytes::Bytes;
 use http_body_util::BodyExt;
 use hyper_util::rt::TokioIo;
@@ -15,6 +15,12 @@
 use crate::net::tls::wrap_rustls;
 use crate::tools::time;
 
+/// Default limit of the HTTP response body size.
+pub(crate) const HTTP_MAX_SIZE: usize = 100 * 1024 * 1024;
+
+/// Number of times an interrupted download is resumed.
+const HTTP_MAX_RESUMPTIONS: usize = 3;
+
 /// HTTP(S) GET response.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Response {
@@ -35,7 +41,7 @@ pub async fn read_url(context: &Context, url: &str) -> Result<String> {
     Ok(text.to_string())
 }
 
-async fn get_http_sender<B>(
+pub(crate) async fn get_http_sender<B>(
     context: &Context,
     parsed_url: hyper::Uri,
 ) -> Result<hyper::client::conn::http1::SendRequest<B>>
@@ -224,14 +230,59 @@ pub(crate) async fn http_cache_cleanup(context: &Context) -> Result<()> {
     Ok(())
 }
 
+/// Parses the start offset and the total size from a `Content-Range` header,
+/// e.g. `bytes 100-199/2000`.
+fn parse_content_range(value: &str) -> Option<(usize, Option<usize>)> {
+    let (range, total) = value.trim().strip_prefix("bytes ")?.split_once('/')?;
+    let (start, _end) = range.split_once('-')?;
+    let start = start.trim().parse().ok()?;
+    let total = total.trim().parse().ok();
+    Some((start, total))
+}
+
+/// Returns the validator to use in the `If-Range` header
+/// when resuming a download of the response with the given headers.
+///
+/// `If-Range` requires a strong validator, so weak ETags are ignored.
+fn get_range_validator(headers: &hyper::HeaderMap) -> Option<String> {
+    headers
+        .get(hyper::header::ETAG)
+        .and_then(|value| value.to_str().ok())
+        .filter(|etag| !etag.starts_with("W/"))
+        .or_else(|| {
+            headers
+                .get(hyper::header::LAST_MODIFIED)
+                .and_then(|value| value.to_str().ok())
+        })
+        .map(|validator| validator.to_string())
+}
+
 /// Fetches URL and updates the cache.
 ///
 /// URL is fetched regardless of whether there is an existing result in the cache.
-async fn fetch_url(context: &Context, original_url: &str) -> Result<Response> {
+///
+/// If the download is interrupted and the server supports range requests,
+/// it is resumed up to [`HTTP_MAX_RESUMPTIONS`] times.
+/// Fails if the response body exceeds `max_size` bytes.
+async fn fetch_url<F>(
+    context: &Context,
+    original_url: &str,
+    max_size: usize,
+    progress: &F,
+) -> Result<Response>
+where
+    F: Fn(usize, Option<usize>) + Sync,
+{
     let mut url = original_url.to_string();
+    let mut redirects = 0;
+    let mut resumptions = 0;
 
-    // Follow up to 10 http-redirects
-    for _i in 0..10 {
+    // Response body received so far and the validator to resume it.
+    let mut blob = Vec::new();
+    let mut validator: Option<String> = None;
+    let mut content_type: Option<Mime> = None;
+
+    loop {
         let parsed_url = url
             .parse::<hyper::Uri>()
             .with_context(|| format!("Failed to parse URL {url:?}"))?;
@@ -242,13 +293,22 @@ async fn fetch_url(context: &Context, original_url: &str) -> Result<Response> {
             .context("URL has no authority")?
             .clone();
 
-        let req = hyper::Request::builder()
+        let mut req = hyper::Request::builder()
             .uri(parsed_url.path())
-            .header(hyper::header::HOST, authority.as_str())
-            .body(http_body_util::Empty::<Bytes>::new())?;
+            .header(hyper::header::HOST, authority.as_str());
+        if let Some(validator) = validator.as_deref().filter(|_| !blob.is_empty()) {
+            req = req
+                .header(hyper::header::RANGE, format!("bytes={}-", blob.len()))
+                .header(hyper::header::IF_RANGE, validator);
+        }
+        let req = req.body(http_body_util::Empty::<Bytes>::new())?;
         let response = sender.send_request(req).await?;
 
         if response.status().is_redirection() {
+            redirects += 1;
+            if redirects == 10 {
+                bail!("Followed 10 redirections");
+            }
             let header = response
                 .headers()
                 .get_all("location")
@@ -258,59 +318,195 @@ async fn fetch_url(context: &Context, original_url: &str) -> Result<Response> {
                 .to_str()?;
             info!(context, "Following redirect to {}", header);
             url = header.to_string();
+            blob.clear();
+            validator = None;
             continue;
         }
 
-        let content_type = response
-            .headers()
-            .get("content-type")
-            .and_then(|value| value.to_str().ok())
-            .and_then(|value| value.parse::<Mime>().ok());
-        let mimetype = content_type
-            .as_ref()
-            .map(|mime| mime.essence_str().to_string());
-        let encoding = content_type.as_ref().and_then(|mime| {
-            mime.get_param(mime::CHARSET)
-                .map(|charset| charset.as_str().to_string())
-        });
-        let body = response.collect().await?.to_bytes();
-        let blob: Vec<u8> = body.to_vec();
-        let response = Response {
-            blob,
-            mimetype,
-            encoding,
+        let content_range = if response.status() == hyper::StatusCode::PARTIAL_CONTENT {
+            let content_range = response
+                .headers()
+                .get(hyper::header::CONTENT_RANGE)
+                .and_then(|value| value.to_str().ok())
+                .and_then(parse_content_range)
+                .with_context(|| format!("Invalid Content-Range in response for {url:?}"))?;
+            Some(content_range)
+        } else {
+            None
         };
-        info!(context, "Inserting {original_url:?} into cache.");
-        http_cache_put(context, &url, &response).await?;
-        return Ok(response);
+        let total = match content_range {
+            Some((start, total)) if start == blob.len() && !blob.is_empty() => {
+                info!(context, "Resuming {url:?} at {start} bytes.");
+                total
+            }
+            Some(_) => bail!("Unexpected range in response for {url:?}"),
+            None => {
+                // Full response, the server ignored the range or the resource has changed.
+                blob.clear();
+                validator = get_range_validator(response.headers());
+                content_type = response
+                    .headers()
+                    .get("content-type")
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.parse::<Mime>().ok());
+                response
+                    .headers()
+                    .get(hyper::header::CONTENT_LENGTH)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.parse().ok())
+            }
+        };
+        if let Some(total) = total {
+            ensure!(
+                total <= max_size,
+                "{url:?} has {total} bytes, exceeding the limit of {max_size} bytes"
+            );
+        }
+
+        let mut body = response.into_body();
+        let interrupted = loop {
+            match body.frame().await {
+                None => break None,
+                Some(Err(err)) => break Some(err),
+                Some(Ok(frame)) => {
+                    if let Ok(data) = frame.into_data() {
+                        ensure!(
+                            blob.len() + data.len() <= max_size,
+                            "{url:?} exceeds the limit of {max_size} bytes"
+                        );
+                        blob.extend_from_slice(&data);
+                        progress(blob.len(), total);
+                    }
+                }
+            }
+        };
+        let Some(err) = interrupted else {
+            break;
+        };
+        if validator.is_none() || resumptions >= HTTP_MAX_RESUMPTIONS {
+            return Err(err).with_context(|| format!("Failed to download {url:?}"));
+        }
+        resumptions += 1;
+        warn!(
+            context,
+            "Download of {url:?} interrupted after {} bytes, resuming: {err:#}.",
+            blob.len()
+        );
     }
 
-    Err(anyhow!("Followed 10 redirections"))
+    let mimetype = content_type
+        .as_ref()
+        .map(|mime| mime.essence_str().to_string());
+    let encoding = content_type.as_ref().and_then(|mime| {
+        mime.get_param(mime::CHARSET)
+            .map(|charset| charset.as_str().to_string())
+    });
+    let response = Response {
+        blob,
+        mimetype,
+        encoding,
+    };
+    info!(context, "Inserting {original_url:?} into cache.");
+    http_cache_put(context, &url, &response).await?;
+    Ok(response)
 }
 
 /// Retrieves the binary contents of URL using HTTP GET request.
+///
+/// The response body is limited to [`HTTP_MAX_SIZE`] bytes,
+/// use [`read_url_blob_ex`] for another limit or to track the progress.
 pub async fn read_url_blob(context: &Context, url: &str) -> Result<Response> {
+    read_url_blob_ex(context, url, HTTP_MAX_SIZE, |_, _| {}).await
+}
+
+/// Retrieves the binary contents of URL using HTTP GET request,
+/// failing if the response body exceeds `max_size` bytes.
+///
+/// `progress` is called with the number of received bytes
+/// and the total size if known.
+/// Interrupted downloads are resumed using range requests if the server supports them.
+pub async fn read_url_blob_ex<F>(
+    context: &Context,
+    url: &str,
+    max_size: usize,
+    progress: F,
+) -> Result<Response>
+where
+    F: Fn(usize, Option<usize>) + Sync,
+{
     if let Some((response, is_stale)) = http_cache_get(context, url).await? {
         info!(context, "Returning {url:?} from cache.");
+        ensure!(
+            response.blob.len() <= max_size,
+            "{url:?} exceeds the limit of {max_size} bytes"
+        );
         if is_stale {
             let context = context.clone();
             let url = url.to_string();
             tokio::spawn(async move {
                 // Fetch URL in background to update the cache.
                 info!(context, "Fetching stale {url:?} in background.");
-                if let Err(err) = fetch_url(&context, &url).await {
+                if let Err(err) =
+                    fetch_url(&context, &url, max_size, &|_: usize, _: Option<usize>| {}).await
+                {
                     warn!(context, "Failed to revalidate {url:?}: {err:#}.");
                 }
             });
         }
+        progress(response.blob.len(), Some(response.blob.len()));
         return Ok(response);
     }
 
     info!(context, "Not found {url:?} in cache, fetching.");
-    let response = fetch_url(context, url).await?;
+    let response = fetch_url(context, url, max_size, &progress).await?;
     Ok(response)
 }
 
+/// Collects the response body, failing if it exceeds [`HTTP_MAX_SIZE`] bytes.
+pub(crate) async fn collect_limited(
+    response: hyper::Response<hyper::body::Incoming>,
+) -> Result<Bytes> {
+    let body = http_body_util::Limited::new(response.into_body(), HTTP_MAX_SIZE);
+    let bytes = body
+        .collect()
+        .await
+        .map_err(|err| anyhow!("Failed to read HTTP response: {err}"))?
+        .to_bytes();
+    Ok(bytes)
+}
+
+/// Sends a GET request to the HTTPS URL and returns the redirect target, if any.
+///
+/// Returns `None` if the response is not a redirection.
+///
+/// Does not follow redirects.
+pub(crate) async fn get_redirect_location(context: &Context, url: &str) -> Result<Option<String>> {
+    let parsed_url = url
+        .parse::<hyper::Uri>()
+        .with_context(|| format!("Failed to parse URL {url:?}"))?;
+    let scheme = parsed_url.scheme_str().context("URL has no scheme")?;
+    ensure!(scheme == "https", "Only HTTPS URLs are allowed");
+
+    let mut sender = get_http_sender(context, parsed_url.clone()).await?;
+    let authority = parsed_url
+        .authority()
+        .context("URL has no authority")?
+        .clone();
+    let req = hyper::Request::get(parsed_url.path())
+        .header(hyper::header::HOST, authority.as_str())
+        .body(http_body_util::Empty::<Bytes>::new())?;
+    let response = sender.send_request(req).await?;
+    if !response.status().is_redirection() {
+        return Ok(None);
+    }
+    let location = response
+        .headers()
+        .get(hyper::header::LOCATION)
+        .context("Redirection doesn't have a target location")?
+        .to_str()?;
+    Ok(Some(location.to_string()))
+}
+
 /// Sends an empty POST request to the URL.
 ///
 /// Returns response text and whether request was successful or not.
@@ -337,7 +533,7 @@ pub(crate) async fn post_empty(context: &Context, url: &str) -> Result<(String,
     let response = sender.send_request(req).await?;
 
     let response_status = response.status();
-    let body = response.collect().await?.to_bytes();
+    let body = collect_limited(response).await?;
     let text = String::from_utf8_lossy(&body);
     let response_text = text.to_string();
 
@@ -373,6 +569,38 @@ pub(crate) async fn post_string(context: &Context, url: &str, body: String) -> R
     Ok(response.status().is_success())
 }
 
+/// Sends a one-click unsubscribe request to a mailing list
+/// as defined in [RFC 8058](https://datatracker.ietf.org/doc/html/rfc8058).
+///
+/// Returns true if successful HTTP response code was returned.
+///
+/// Does not follow redirects.
+pub(crate) async fn post_one_click_unsubscribe(context: &Context, url: &str) -> Result<bool> {
+    let parsed_url = url
+        .parse::<hyper::Uri>()
+        .with_context(|| format!("Failed to parse URL {url:?}"))?;
+    let scheme = parsed_url.scheme_str().context("URL has no scheme")?;
+    if scheme != "https" {
+        bail!("POST requests to non-HTTPS URLs are not allowed");
+    }
+
+    let mut sender = get_http_sender(context, parsed_url.clone()).await?;
+    let authority = parsed_url
+        .authority()
+        .context("URL has no authority")?
+        .clone();
+    let path_and_query = parsed_url
+        .path_and_query()
+        .map_or("/", |path_and_query| path_and_query.as_str());
+    let request = hyper::Request::post(path_and_query)
+        .header(hyper::header::HOST, authority.as_str())
+        .header("content-type", "application/x-www-form-urlencoded")
+        .body("List-Unsubscribe=One-Click".to_string())?;
+    let response = sender.send_request(request).await?;
+
+    Ok(response.status().is_success())
+}
+
 /// Sends a POST request with x-www-form-urlencoded data.
 ///
 /// Does not follow redirects.
@@ -400,19 +628,189 @@ pub(crate) async fn post_form<T: Serialize + ?Sized>(
         .header("content-type", "application/x-www-form-urlencoded")
         .body(encoded_body)?;
     let response = sender.send_request(request).await?;
-    let bytes = response.collect().await?.to_bytes();
+    let bytes = collect_limited(response).await?;
     Ok(bytes)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::Mutex;
     use std::time::Duration;
 
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
     use crate::sql::housekeeping;
     use crate::test_utils::TestContext;
     use crate::tools::SystemTime;
 
+    #[test]
+    fn test_parse_content_range() {
+        assert_eq!(
+            parse_content_range("bytes 100-199/2000"),
+            Some((100, Some(2000)))
+        );
+        assert_eq!(parse_content_range("bytes 0-0/*"), Some((0, None)));
+        assert_eq!(parse_content_range("bytes */2000"), None);
+        assert_eq!(parse_content_range("items 1-2/3"), None);
+    }
+
+    #[test]
+    fn test_get_range_validator() {
+        let mut headers = hyper::HeaderMap::new();
+        assert_eq!(get_range_validator(&headers), None);
+
+        headers.insert(
+            hyper::header::LAST_MODIFIED,
+            "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap(),
+        );
+        headers.insert(hyper::header::ETAG, "W/\"weak\"".parse().unwrap());
+        assert_eq!(
+            get_range_validator(&headers).as_deref(),
+            Some("Wed, 21 Oct 2015 07:28:00 GMT")
+        );
+
+        headers.insert(hyper::header::ETAG, "\"strong\"".parse().unwrap());
+        assert_eq!(get_range_validator(&headers).as_deref(), Some("\"strong\""));
+    }
+
+    /// Starts an HTTP server answering each connection with the next of `responses`
+    /// and closing it afterwards.
+    ///
+    /// Returns the server URL and a handle resolving to the received request heads.
+    async fn serve(
+        responses: Vec<&'static str>,
+    ) -> Result<(String, tokio::task::JoinHandle<Vec<String>>)> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}/file", listener.local_addr()?);
+        let handle = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for response in responses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                while !request.ends_with(b"\r\n\r\n") {
+                    request.push(stream.read_u8().await.unwrap());
+                }
+                requests.push(String::from_utf8(request).unwrap().to_lowercase());
+                stream.write_all(response.as_bytes()).await.unwrap();
+                stream.shutdown().await.unwrap();
+            }
+            requests
+        });
+        Ok((url, handle))
+    }
+
+    /// Response interrupted after 5 of 10 bytes.
+    const INTERRUPTED_RESPONSE: &str = "HTTP/1.1 200 OK\r\n\
+        Content-Type: text/plain\r\n\
+        Content-Length: 10\r\n\
+        ETag: \"v1\"\r\n\
+        \r\n\
+        01234";
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_fetch_url_resume() -> Result<()> {
+        let t = &TestContext::new().await;
+        let (url, server) = serve(vec![
+            INTERRUPTED_RESPONSE,
+            "HTTP/1.1 206 Partial Content\r\n\
+             Content-Range: bytes 5-9/10\r\n\
+             Content-Length: 5\r\n\
+             \r\n\
+             56789",
+        ])
+        .await?;
+
+        let progress = Mutex::new(Vec::new());
+        let response = fetch_url(t, &url, HTTP_MAX_SIZE, &|received, total| {
+            progress.lock().unwrap().push((received, total))
+        })
+        .await?;
+        assert_eq!(response.blob, b"0123456789");
+        assert_eq!(response.mimetype.as_deref(), Some("text/plain"));
+        assert_eq!(progress.lock().unwrap().last(), Some(&(10, Some(10))));
+
+        let requests = server.await?;
+        assert_eq!(requests.len(), 2);
+        assert!(!requests[0].contains("range:"));
+        assert!(requests[1].contains("\r\nrange: bytes=5-\r\n"));
+        assert!(requests[1].contains("\r\nif-range: \"v1\"\r\n"));
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_fetch_url_resume_restarted() -> Result<()> {
+        let t = &TestContext::new().await;
+
+        // The resource has changed, so the server ignores the range
+        // and sends the whole new version.
+        let (url, server) = serve(vec![
+            INTERRUPTED_RESPONSE,
+            "HTTP/1.1 200 OK\r\n\
+             Content-Type: text/plain\r\n\
+             Content-Length: 10\r\n\
+             ETag: \"v2\"\r\n\
+             \r\n\
+             abcdefghij",
+        ])
+        .await?;
+        let response = fetch_url(t, &url, HTTP_MAX_SIZE, &|_, _| {}).await?;
+        assert_eq!(response.blob, b"abcdefghij");
+        assert_eq!(server.await?.len(), 2);
+
+        // Without a validator the download is not resumed.
+        let (url, server) = serve(vec![
+            "HTTP/1.1 200 OK\r\n\
+             Content-Length: 10\r\n\
+             \r\n\
+             01234",
+        ])
+        .await?;
+        assert!(fetch_url(t, &url, HTTP_MAX_SIZE, &|_, _| {}).await.is_err());
+        assert_eq!(server.await?.len(), 1);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_fetch_url_max_size() -> Result<()> {
+        let t = &TestContext::new().await;
+
+        // Announced size is checked before reading the body.
+        let (url, server) = serve(vec![
+            "HTTP/1.1 200 OK\r\n\
+             Content-Length: 10\r\n\
+             \r\n\
+             0123456789",
+        ])
+        .await?;
+        assert!(fetch_url(t, &url, 9, &|_, _| {}).await.is_err());
+        server.await?;
+
+        // Body without Content-Length is limited while reading.
+        let (url, server) = serve(vec![
+            "HTTP/1.1 200 OK\r\n\
+             Connection: close\r\n\
+             \r\n\
+             0123456789",
+        ])
+        .await?;
+        assert!(fetch_url(t, &url, 9, &|_, _| {}).await.is_err());
+        server.await?;
+
+        let (url, server) = serve(vec![
+            "HTTP/1.1 200 OK\r\n\
+             Connection: close\r\n\
+             \r\n\
+             0123456789",
+        ])
+        .await?;
+        let response = fetch_url(t, &url, 10, &|_, _| {}).await?;
+        assert_eq!(response.blob, b"0123456789");
+        server.await?;
+        Ok(())
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_http_cache() -> Result<()> {
         let t = &TestContext::new().await;
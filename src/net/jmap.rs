@@ -0,0 +1,874 @@
+//! # JMAP transport.
+//!
+//! JMAP ([RFC 8620](https://www.rfc-editor.org/rfc/rfc8620)
+//! and [RFC 8621](https://www.rfc-editor.org/rfc/rfc8621))
+//! is a JSON-over-HTTPS alternative to IMAP and SMTP.
+//!
+//! If the server advertises a JMAP session resource at `/.well-known/jmap`
+//! of the address domain, its URL is stored during configuration
+//! as [`Config::ConfiguredJmapUrl`].
+//! Then new messages are fetched over JMAP
+//! as soon as the server pushes a state change over EventSource
+//! and outgoing messages are submitted over JMAP,
+//! falling back to SMTP if the message was certainly not submitted.
+//! IMAP is still used for everything else, such as moving and deleting messages.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, ensure, Context as _, Result};
+use base64::Engine as _;
+use bytes::Bytes;
+use http_body_util::BodyExt;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::config::Config;
+use crate::context::Context;
+use crate::events::EventType;
+use crate::login_param::ConfiguredLoginParam;
+use crate::message::rfc724_mid_exists;
+use crate::net::http::{collect_limited, get_http_sender};
+use crate::receive_imf::receive_imf;
+
+/// JMAP capability of the core protocol.
+const CAPABILITY_CORE: &str = "urn:ietf:params:jmap:core";
+
+/// JMAP capability for mail access.
+const CAPABILITY_MAIL: &str = "urn:ietf:params:jmap:mail";
+
+/// JMAP capability for mail submission.
+const CAPABILITY_SUBMISSION: &str = "urn:ietf:params:jmap:submission";
+
+/// Raw config key storing the last seen `Email` state string.
+const EMAIL_STATE_KEY: &str = "jmap_email_state";
+
+/// How long to wait for a push notification before fetching anyway.
+const PUSH_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+/// JMAP session resource, RFC 8620 section 2.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Session {
+    capabilities: HashMap<String, Value>,
+    primary_accounts: HashMap<String, String>,
+    api_url: String,
+    download_url: String,
+    upload_url: String,
+    event_source_url: String,
+}
+
+impl Session {
+    /// Returns the ID of the account used for mail.
+    fn mail_account_id(&self) -> Result<&str> {
+        self.primary_accounts
+            .get(CAPABILITY_MAIL)
+            .map(String::as_str)
+            .context("No primary mail account")
+    }
+
+    fn supports_submission(&self) -> bool {
+        self.capabilities.contains_key(CAPABILITY_SUBMISSION)
+    }
+}
+
+/// Expands URL template variables of RFC 6570 level 1 as used by JMAP session resources.
+fn expand_url_template(template: &str, variables: &[(&str, &str)]) -> String {
+    let mut url = template.to_string();
+    for (name, value) in variables {
+        let value = utf8_percent_encode(value, NON_ALPHANUMERIC).to_string();
+        url = url.replace(&format!("{{{name}}}"), &value);
+    }
+    url
+}
+
+/// Sends a request and returns the response
+/// without following redirects.
+async fn send_request(
+    context: &Context,
+    method: hyper::Method,
+    url: &str,
+    authorization: Option<&str>,
+    content_type: Option<&str>,
+    body: Bytes,
+) -> Result<hyper::Response<hyper::body::Incoming>> {
+    let parsed_url = url
+        .parse::<hyper::Uri>()
+        .with_context(|| format!("Failed to parse URL {url:?}"))?;
+    let scheme = parsed_url.scheme_str().context("URL has no scheme")?;
+    ensure!(
+        scheme == "https",
+        "JMAP requests to non-HTTPS URLs are not allowed"
+    );
+
+    let mut sender = get_http_sender(context, parsed_url.clone()).await?;
+    let authority = parsed_url
+        .authority()
+        .context("URL has no authority")?
+        .clone();
+    let path_and_query = parsed_url
+        .path_and_query()
+        .map(|path_and_query| path_and_query.as_str())
+        .unwrap_or("/");
+    let mut builder = hyper::Request::builder()
+        .method(method)
+        .uri(path_and_query)
+        .header(hyper::header::HOST, authority.as_str());
+    if let Some(authorization) = authorization {
+        builder = builder.header(hyper::header::AUTHORIZATION, authorization);
+    }
+    if let Some(content_type) = content_type {
+        builder = builder.header(hyper::header::CONTENT_TYPE, content_type);
+    }
+    let request = builder.body(http_body_util::Full::new(body))?;
+    let response = sender.send_request(request).await?;
+    Ok(response)
+}
+
+/// Returns the target of a redirect response.
+fn redirect_target(url: &str, response: &hyper::Response<hyper::body::Incoming>) -> Result<String> {
+    let location = response
+        .headers()
+        .get(hyper::header::LOCATION)
+        .context("Redirection doesn't have a target location")?
+        .to_str()?;
+    let base = url::Url::parse(url)?;
+    Ok(base.join(location)?.to_string())
+}
+
+/// Returns true if both URLs have the same scheme, host and port.
+///
+/// Credentials are only sent to the origin they are meant for,
+/// redirects to other origins are followed without them.
+fn is_same_origin(a: &str, b: &str) -> bool {
+    match (url::Url::parse(a), url::Url::parse(b)) {
+        (Ok(a), Ok(b)) => a.origin() == b.origin(),
+        _ => false,
+    }
+}
+
+/// Sends a GET request following up to 10 redirects
+/// and returns the response body.
+///
+/// `authorization` is dropped when a redirect leads to another origin.
+async fn get(context: &Context, original_url: &str, authorization: Option<&str>) -> Result<Bytes> {
+    let mut url = original_url.to_string();
+    let mut authorization = authorization;
+    for _i in 0..10 {
+        let response = send_request(
+            context,
+            hyper::Method::GET,
+            &url,
+            authorization,
+            None,
+            Bytes::new(),
+        )
+        .await?;
+        if response.status().is_redirection() {
+            let target = redirect_target(&url, &response)?;
+            if authorization.is_some() && !is_same_origin(&url, &target) {
+                info!(
+                    context,
+                    "JMAP redirect to another origin, dropping credentials."
+                );
+                authorization = None;
+            }
+            url = target;
+            info!(context, "Following JMAP redirect to {url}.");
+            continue;
+        }
+        let status = response.status();
+        ensure!(status.is_success(), "GET {url} failed with status {status}");
+        return collect_limited(response).await;
+    }
+    bail!("Followed 10 redirections")
+}
+
+/// Follows the redirects of the `/.well-known/jmap` URL without credentials
+/// and returns the URL of the session resource.
+async fn resolve_session_url(context: &Context, well_known_url: &str) -> Result<String> {
+    let mut url = well_known_url.to_string();
+    for _i in 0..10 {
+        let response =
+            send_request(context, hyper::Method::GET, &url, None, None, Bytes::new()).await?;
+        if !response.status().is_redirection() {
+            // The session resource itself usually requires authentication.
+            let status = response.status();
+            ensure!(
+                status.is_success() || status == hyper::StatusCode::UNAUTHORIZED,
+                "GET {url} failed with status {status}"
+            );
+            return Ok(url);
+        }
+        url = redirect_target(&url, &response)?;
+    }
+    bail!("Followed 10 redirections")
+}
+
+/// Sends a POST request and returns the response body.
+async fn post(
+    context: &Context,
+    url: &str,
+    authorization: &str,
+    content_type: &str,
+    body: Bytes,
+) -> Result<Bytes> {
+    let response = send_request(
+        context,
+        hyper::Method::POST,
+        url,
+        Some(authorization),
+        Some(content_type),
+        body,
+    )
+    .await?;
+    let status = response.status();
+    ensure!(
+        status.is_success(),
+        "POST {url} failed with status {status}"
+    );
+    collect_limited(response).await
+}
+
+/// Returns `Authorization` header value for the configured credentials.
+///
+/// JMAP servers accept the same credentials as IMAP.
+fn authorization(param: &ConfiguredLoginParam) -> Result<String> {
+    ensure!(!param.oauth2, "JMAP does not support OAuth 2");
+    let user = param
+        .imap
+        .first()
+        .map(|imap| imap.user.as_str())
+        .filter(|user| !user.is_empty())
+        .unwrap_or(&param.addr);
+    let credentials =
+        base64::engine::general_purpose::STANDARD.encode(format!("{user}:{}", param.imap_password));
+    Ok(format!("Basic {credentials}"))
+}
+
+/// Looks for a JMAP session resource advertised by the domain of the configured address.
+///
+/// Returns the session URL if the server supports JMAP mail
+/// and accepts the credentials.
+/// Credentials are only sent once the session URL is known.
+pub(crate) async fn discover(
+    context: &Context,
+    param: &ConfiguredLoginParam,
+) -> Result<Option<String>> {
+    if param.oauth2 {
+        return Ok(None);
+    }
+    let (_local, domain) = param.addr.rsplit_once('@').context("Invalid address")?;
+    let well_known_url = format!("https://{domain}/.well-known/jmap");
+    let Ok(url) = resolve_session_url(context, &well_known_url).await else {
+        return Ok(None);
+    };
+    let authorization = authorization(param)?;
+    let Ok(body) = get(context, &url, Some(&authorization)).await else {
+        return Ok(None);
+    };
+    let Ok(session) = serde_json::from_slice::<Session>(&body) else {
+        return Ok(None);
+    };
+    if !session.capabilities.contains_key(CAPABILITY_MAIL) {
+        return Ok(None);
+    }
+    info!(context, "Server advertises JMAP session at {url}.");
+    Ok(Some(url))
+}
+
+/// JMAP client for a configured account.
+#[derive(Debug)]
+pub(crate) struct JmapClient {
+    session: Session,
+    authorization: String,
+}
+
+impl JmapClient {
+    /// Connects to the configured JMAP server.
+    ///
+    /// Returns `None` if JMAP is not configured.
+    pub(crate) async fn load(context: &Context) -> Result<Option<Self>> {
+        let Some(url) = context.get_config(Config::ConfiguredJmapUrl).await? else {
+            return Ok(None);
+        };
+        let param = ConfiguredLoginParam::load(context)
+            .await?
+            .context("Not configured")?;
+        let authorization = authorization(&param)?;
+        let body = get(context, &url, Some(&authorization)).await?;
+        let session: Session =
+            serde_json::from_slice(&body).context("Failed to parse JMAP session")?;
+        Ok(Some(Self {
+            session,
+            authorization,
+        }))
+    }
+
+    /// Calls JMAP methods and returns method responses.
+    async fn call(
+        &self,
+        context: &Context,
+        using: &[&str],
+        method_calls: Value,
+    ) -> Result<Vec<(String, Value, String)>> {
+        let method_responses = self.post_request(context, using, method_calls).await?;
+        check_method_errors(&method_responses)?;
+        Ok(method_responses)
+    }
+
+    /// Sends a JMAP request and returns method responses
+    /// without checking them for errors.
+    async fn post_request(
+        &self,
+        context: &Context,
+        using: &[&str],
+        method_calls: Value,
+    ) -> Result<Vec<(String, Value, String)>> {
+        let request = json!({
+            "using": using,
+            "methodCalls": method_calls,
+        });
+        let body = post(
+            context,
+            &self.session.api_url,
+            &self.authorization,
+            "application/json",
+            serde_json::to_vec(&request)?.into(),
+        )
+        .await?;
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Response {
+            method_responses: Vec<(String, Value, String)>,
+        }
+        let response: Response =
+            serde_json::from_slice(&body).context("Failed to parse JMAP response")?;
+        Ok(response.method_responses)
+    }
+
+    /// Returns IDs of mailboxes having the role, e.g. "inbox".
+    async fn mailbox_ids(&self, context: &Context, role: &str) -> Result<Vec<String>> {
+        let account_id = self.session.mail_account_id()?;
+        let responses = self
+            .call(
+                context,
+                &[CAPABILITY_CORE, CAPABILITY_MAIL],
+                json!([["Mailbox/get", {
+                    "accountId": account_id,
+                    "properties": ["id", "role"],
+                }, "0"]]),
+            )
+            .await?;
+        let (_, arguments, _) = responses.first().context("No Mailbox/get response")?;
+        let ids = arguments["list"]
+            .as_array()
+            .context("Invalid Mailbox/get response")?
+            .iter()
+            .filter(|mailbox| mailbox["role"].as_str() == Some(role))
+            .filter_map(|mailbox| mailbox["id"].as_str().map(str::to_string))
+            .collect();
+        Ok(ids)
+    }
+
+    /// Fetches messages that arrived in the inbox since the last call.
+    ///
+    /// The first call only remembers the current state,
+    /// existing messages are fetched over IMAP.
+    /// Returns the number of fetched messages.
+    async fn fetch_new_messages(&self, context: &Context) -> Result<usize> {
+        let account_id = self.session.mail_account_id()?;
+        let Some(mut state) = context.sql.get_raw_config(EMAIL_STATE_KEY).await? else {
+            let responses = self
+                .call(
+                    context,
+                    &[CAPABILITY_CORE, CAPABILITY_MAIL],
+                    json!([["Email/get", {"accountId": account_id, "ids": []}, "0"]]),
+                )
+                .await?;
+            let (_, arguments, _) = responses.first().context("No Email/get response")?;
+            let state = arguments["state"].as_str().context("No Email state")?;
+            context
+                .sql
+                .set_raw_config(EMAIL_STATE_KEY, Some(state))
+                .await?;
+            return Ok(0);
+        };
+
+        let inbox_ids = self.mailbox_ids(context, "inbox").await?;
+        let mut fetched = 0;
+        loop {
+            let responses = self
+                .call(
+                    context,
+                    &[CAPABILITY_CORE, CAPABILITY_MAIL],
+                    json!([
+                        ["Email/changes", {
+                            "accountId": account_id,
+                            "sinceState": state,
+                            "maxChanges": 50,
+                        }, "0"],
+                        ["Email/get", {
+                            "accountId": account_id,
+                            "#ids": {"resultOf": "0", "name": "Email/changes", "path": "/created"},
+                            "properties": ["blobId", "messageId", "mailboxIds", "keywords"],
+                        }, "1"],
+                    ]),
+                )
+                .await;
+            let responses = match responses {
+                Ok(responses) => responses,
+                Err(err) => {
+                    // Most likely `cannotCalculateChanges`,
+                    // start over from the current state.
+                    warn!(context, "Failed to get JMAP changes: {err:#}.");
+                    context.sql.set_raw_config(EMAIL_STATE_KEY, None).await?;
+                    return Ok(fetched);
+                }
+            };
+            let changes = &responses.first().context("No Email/changes response")?.1;
+            let emails = &responses.get(1).context("No Email/get response")?.1;
+
+            for email in inbox_emails(emails, &inbox_ids) {
+                // The IMAP inbox loop may have fetched the message already.
+                if let Some(rfc724_mid) = email["messageId"][0].as_str() {
+                    if rfc724_mid_exists(context, rfc724_mid).await?.is_some() {
+                        continue;
+                    }
+                }
+                let blob_id = email["blobId"].as_str().context("No blobId")?;
+                let seen = email["keywords"]["$seen"].as_bool() == Some(true);
+                let url = expand_url_template(
+                    &self.session.download_url,
+                    &[
+                        ("accountId", account_id),
+                        ("blobId", blob_id),
+                        ("name", "message.eml"),
+                        ("type", "message/rfc822"),
+                    ],
+                );
+                let raw = get(context, &url, Some(&self.authorization)).await?;
+                if let Err(err) = receive_imf(context, &raw, seen).await {
+                    warn!(
+                        context,
+                        "receive_imf failed for JMAP blob {blob_id}: {err:#}."
+                    );
+                }
+                fetched += 1;
+            }
+
+            state = changes["newState"]
+                .as_str()
+                .context("No newState")?
+                .to_string();
+            context
+                .sql
+                .set_raw_config(EMAIL_STATE_KEY, Some(&state))
+                .await?;
+            if changes["hasMoreChanges"].as_bool() != Some(true) {
+                break;
+            }
+        }
+        Ok(fetched)
+    }
+
+    /// Waits until the server pushes an `Email` state change over EventSource
+    /// or [`PUSH_TIMEOUT`] passes.
+    async fn wait_for_changes(&self, context: &Context) -> Result<()> {
+        let url = expand_url_template(
+            &self.session.event_source_url,
+            &[("types", "Email"), ("closeafter", "state"), ("ping", "60")],
+        );
+        let res = tokio::time::timeout(PUSH_TIMEOUT, async {
+            // With `closeafter=state` the server closes the stream
+            // after the first state change.
+            let response = send_request(
+                context,
+                hyper::Method::GET,
+                &url,
+                Some(&self.authorization),
+                None,
+                Bytes::new(),
+            )
+            .await?;
+            let status = response.status();
+            ensure!(
+                status.is_success(),
+                "EventSource failed with status {status}"
+            );
+            response.collect().await?;
+            Ok(())
+        })
+        .await;
+        match res {
+            Ok(res) => res,
+            Err(_) => {
+                info!(context, "No JMAP push notification, fetching anyway.");
+                Ok(())
+            }
+        }
+    }
+
+    /// Fetches new messages and waits for push notifications until an error occurs.
+    async fn run(&self, context: &Context) -> Result<()> {
+        loop {
+            let fetched = self.fetch_new_messages(context).await?;
+            if fetched > 0 {
+                info!(context, "Fetched {fetched} messages over JMAP.");
+            }
+            self.wait_for_changes(context).await?;
+        }
+    }
+
+    /// Submits a message over JMAP.
+    async fn send(
+        &self,
+        context: &Context,
+        from: &str,
+        recipients: &[&str],
+        message: &[u8],
+    ) -> Result<(), SubmissionError> {
+        let email = self
+            .prepare_submission(context, from, recipients, message)
+            .await
+            .map_err(SubmissionError::NotSubmitted)?;
+        // If the request fails, the server may have submitted the message anyway.
+        let responses = self
+            .post_request(
+                context,
+                &[CAPABILITY_CORE, CAPABILITY_MAIL, CAPABILITY_SUBMISSION],
+                email,
+            )
+            .await
+            .map_err(SubmissionError::Unknown)?;
+        check_submission(&responses)
+    }
+
+    /// Uploads the message and returns the method calls importing and submitting it.
+    async fn prepare_submission(
+        &self,
+        context: &Context,
+        from: &str,
+        recipients: &[&str],
+        message: &[u8],
+    ) -> Result<Value> {
+        ensure!(
+            self.session.supports_submission(),
+            "Server does not support JMAP submission"
+        );
+        let account_id = self.session.mail_account_id()?;
+
+        let upload_url =
+            expand_url_template(&self.session.upload_url, &[("accountId", account_id)]);
+        let body = post(
+            context,
+            &upload_url,
+            &self.authorization,
+            "message/rfc822",
+            Bytes::copy_from_slice(message),
+        )
+        .await?;
+        let upload: Value = serde_json::from_slice(&body).context("Failed to parse upload")?;
+        let blob_id = upload["blobId"].as_str().context("No blobId")?;
+
+        // Emails must be in some mailbox,
+        // so the message is imported into Drafts
+        // and destroyed after successful submission.
+        let drafts_id = self
+            .mailbox_ids(context, "drafts")
+            .await?
+            .into_iter()
+            .next()
+            .context("No Drafts mailbox")?;
+
+        let responses = self
+            .call(
+                context,
+                &[CAPABILITY_CORE, CAPABILITY_MAIL, CAPABILITY_SUBMISSION],
+                json!([["Identity/get", {"accountId": account_id}, "0"]]),
+            )
+            .await?;
+        let identities = responses.first().context("No Identity/get response")?;
+        let identity_id = identities.1["list"]
+            .as_array()
+            .and_then(|list| {
+                list.iter()
+                    .find(|identity| identity["email"].as_str() == Some(from))
+                    .or_else(|| list.first())
+            })
+            .and_then(|identity| identity["id"].as_str())
+            .context("No JMAP identity")?;
+
+        let rcpt_to: Vec<Value> = recipients
+            .iter()
+            .map(|addr| json!({"email": addr}))
+            .collect();
+        Ok(json!([
+            ["Email/import", {
+                "accountId": account_id,
+                "emails": {"m": {
+                    "blobId": blob_id,
+                    "mailboxIds": {drafts_id: true},
+                    "keywords": {"$seen": true},
+                }},
+            }, "0"],
+            ["EmailSubmission/set", {
+                "accountId": account_id,
+                "create": {"s": {
+                    "identityId": identity_id,
+                    "emailId": "#m",
+                    "envelope": {
+                        "mailFrom": {"email": from},
+                        "rcptTo": rcpt_to,
+                    },
+                }},
+                "onSuccessDestroyEmail": ["#s"],
+            }, "1"],
+        ]))
+    }
+}
+
+/// Error of a JMAP submission.
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum SubmissionError {
+    /// The message was certainly not submitted
+    /// and can be sent over SMTP instead.
+    #[error("{0:#}")]
+    NotSubmitted(anyhow::Error),
+
+    /// The message may have been submitted,
+    /// sending it over SMTP could deliver it twice.
+    #[error("{0:#}")]
+    Unknown(anyhow::Error),
+}
+
+/// Returns an error if any method call failed.
+fn check_method_errors(method_responses: &[(String, Value, String)]) -> Result<()> {
+    for (name, arguments, _call_id) in method_responses {
+        if name == "error" {
+            bail!("JMAP method error: {arguments}");
+        }
+    }
+    Ok(())
+}
+
+/// Checks the responses to `Email/import` and `EmailSubmission/set`.
+///
+/// Method errors and `notCreated` prove that nothing was submitted,
+/// while a response without the submission leaves it unknown.
+fn check_submission(method_responses: &[(String, Value, String)]) -> Result<(), SubmissionError> {
+    check_method_errors(method_responses).map_err(SubmissionError::NotSubmitted)?;
+    let submission = &method_responses
+        .get(1)
+        .filter(|(name, _, _)| name == "EmailSubmission/set")
+        .ok_or_else(|| SubmissionError::Unknown(anyhow!("No EmailSubmission/set response")))?
+        .1;
+    if let Some(not_created) = submission["notCreated"]["s"].as_object() {
+        return Err(SubmissionError::NotSubmitted(anyhow!(
+            "JMAP submission failed: {not_created:?}"
+        )));
+    }
+    if submission["created"]["s"].is_null() {
+        return Err(SubmissionError::Unknown(anyhow!(
+            "JMAP submission result missing"
+        )));
+    }
+    Ok(())
+}
+
+/// Returns the emails of an `Email/get` response that are in one of the inbox mailboxes.
+fn inbox_emails<'a>(emails: &'a Value, inbox_ids: &'a [String]) -> impl Iterator<Item = &'a Value> {
+    emails["list"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|email| {
+            inbox_ids
+                .iter()
+                .any(|id| email["mailboxIds"][id].as_bool() == Some(true))
+        })
+}
+
+/// Submits a message over JMAP if JMAP is configured.
+///
+/// `client` caches the connected client between messages,
+/// it is reset after errors to reload the session.
+///
+/// Returns false if JMAP is not configured
+/// and the message should be sent over SMTP.
+pub(crate) async fn send(
+    context: &Context,
+    client: &mut Option<JmapClient>,
+    recipients: &[&str],
+    message: &[u8],
+) -> Result<bool, SubmissionError> {
+    if client.is_none() {
+        *client = JmapClient::load(context)
+            .await
+            .map_err(SubmissionError::NotSubmitted)?;
+    }
+    let Some(jmap_client) = client.as_ref() else {
+        return Ok(false);
+    };
+    let from = context
+        .get_primary_self_addr()
+        .await
+        .map_err(SubmissionError::NotSubmitted)?;
+    if let Err(err) = jmap_client.send(context, &from, recipients, message).await {
+        *client = None;
+        return Err(err);
+    }
+
+    if context.get_config_bool(Config::Bot).await.ok() != Some(true) {
+        context.ratelimit.write().await.send();
+    }
+    let info_msg = format!(
+        "Message len={} was JMAP-sent to {}",
+        message.len(),
+        recipients.join(",")
+    );
+    info!(context, "{info_msg}.");
+    context.emit_event(EventType::SmtpMessageSent(info_msg));
+    Ok(true)
+}
+
+/// Fetches messages over JMAP whenever the server pushes a change.
+///
+/// Returns immediately if JMAP is not configured.
+pub(crate) async fn jmap_loop(context: &Context) {
+    loop {
+        match JmapClient::load(context).await {
+            Ok(None) => return,
+            Ok(Some(client)) => {
+                if let Err(err) = client.run(context).await {
+                    warn!(context, "JMAP loop failed: {err:#}.");
+                }
+            }
+            Err(err) => {
+                warn!(context, "Failed to connect to JMAP server: {err:#}.");
+            }
+        }
+        tokio::time::sleep(Duration::from_secs(60)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_url_template() {
+        assert_eq!(
+            expand_url_template(
+                "https://example.org/download/{accountId}/{blobId}/{name}?accept={type}",
+                &[
+                    ("accountId", "A1"),
+                    ("blobId", "B2"),
+                    ("name", "message.eml"),
+                    ("type", "message/rfc822"),
+                ],
+            ),
+            "https://example.org/download/A1/B2/message%2Eeml?accept=message%2Frfc822"
+        );
+    }
+
+    #[test]
+    fn test_parse_session() -> Result<()> {
+        let session: Session = serde_json::from_str(
+            r#"{
+                "capabilities": {
+                    "urn:ietf:params:jmap:core": {},
+                    "urn:ietf:params:jmap:mail": {}
+                },
+                "accounts": {},
+                "primaryAccounts": {"urn:ietf:params:jmap:mail": "u123"},
+                "username": "alice@example.org",
+                "apiUrl": "https://example.org/api/",
+                "downloadUrl": "https://example.org/download/{accountId}/{blobId}/{name}?accept={type}",
+                "uploadUrl": "https://example.org/upload/{accountId}/",
+                "eventSourceUrl": "https://example.org/eventsource/?types={types}&closeafter={closeafter}&ping={ping}",
+                "state": "75128aab4b1b"
+            }"#,
+        )?;
+        assert_eq!(session.mail_account_id()?, "u123");
+        assert!(!session.supports_submission());
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_same_origin() {
+        assert!(is_same_origin(
+            "https://example.org/.well-known/jmap",
+            "https://example.org/jmap/session"
+        ));
+        assert!(is_same_origin(
+            "https://example.org/jmap",
+            "https://example.org:443/jmap"
+        ));
+        assert!(!is_same_origin(
+            "https://example.org/jmap",
+            "https://jmap.example.org/jmap"
+        ));
+        assert!(!is_same_origin(
+            "https://example.org/jmap",
+            "https://example.org:8443/jmap"
+        ));
+        assert!(!is_same_origin("https://example.org/jmap", "invalid"));
+    }
+
+    fn responses(value: Value) -> Vec<(String, Value, String)> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_check_submission() {
+        let ok = responses(json!([
+            ["Email/import", {"created": {"m": {"id": "M1"}}}, "0"],
+            ["EmailSubmission/set", {"created": {"s": {"id": "S1"}}}, "1"],
+            ["Email/set", {"destroyed": ["M1"]}, "1"],
+        ]));
+        assert!(check_submission(&ok).is_ok());
+
+        let rejected = responses(json!([
+            ["Email/import", {"created": {"m": {"id": "M1"}}}, "0"],
+            ["EmailSubmission/set", {"notCreated": {"s": {"type": "forbiddenFrom"}}}, "1"],
+        ]));
+        assert!(matches!(
+            check_submission(&rejected),
+            Err(SubmissionError::NotSubmitted(_))
+        ));
+
+        let method_error = responses(json!([
+            ["Email/import", {"created": {"m": {"id": "M1"}}}, "0"],
+            ["error", {"type": "serverFail"}, "1"],
+        ]));
+        assert!(matches!(
+            check_submission(&method_error),
+            Err(SubmissionError::NotSubmitted(_))
+        ));
+
+        let truncated = responses(json!([
+            ["Email/import", {"created": {"m": {"id": "M1"}}}, "0"],
+        ]));
+        assert!(matches!(
+            check_submission(&truncated),
+            Err(SubmissionError::Unknown(_))
+        ));
+    }
+
+    #[test]
+    fn test_inbox_emails() {
+        let emails = json!({"list": [
+            {"blobId": "B1", "mailboxIds": {"inbox": true}},
+            {"blobId": "B2", "mailboxIds": {"sent": true}},
+            {"blobId": "B3", "mailboxIds": {"sent": true, "inbox": true}},
+        ]});
+        let inbox_ids = vec!["inbox".to_string()];
+        let blob_ids: Vec<_> = inbox_emails(&emails, &inbox_ids)
+            .filter_map(|email| email["blobId"].as_str())
+            .collect();
+        assert_eq!(blob_ids, vec!["B1", "B3"]);
+    }
+}
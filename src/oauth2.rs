@@ -3,42 +3,27 @@
 use std::collections::HashMap;
 
 use anyhow::{Context as _, Result};
-use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use deltachat_contact_tools::addr_cmp;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
 use serde::Deserialize;
 
 use crate::context::Context;
 use crate::net::http::post_form;
 use crate::net::read_url_blob;
 use crate::provider;
-use crate::provider::Oauth2Authorizer;
+use crate::provider::Oauth2Endpoints;
 use crate::tools::time;
 
-const OAUTH2_GMAIL: Oauth2 = Oauth2 {
-    // see <https://developers.google.com/identity/protocols/OAuth2InstalledApp>
-    client_id: "959970109878-4mvtgf6feshskf7695nfln6002mom908.apps.googleusercontent.com",
-    get_code: "https://accounts.google.com/o/oauth2/auth?client_id=$CLIENT_ID&redirect_uri=$REDIRECT_URI&response_type=code&scope=https%3A%2F%2Fmail.google.com%2F%20email&access_type=offline",
-    init_token: "https://accounts.google.com/o/oauth2/token?client_id=$CLIENT_ID&redirect_uri=$REDIRECT_URI&code=$CODE&grant_type=authorization_code",
-    refresh_token: "https://accounts.google.com/o/oauth2/token?client_id=$CLIENT_ID&redirect_uri=$REDIRECT_URI&refresh_token=$REFRESH_TOKEN&grant_type=refresh_token",
-    get_userinfo: Some("https://www.googleapis.com/oauth2/v1/userinfo?alt=json&access_token=$ACCESS_TOKEN"),
-};
-
-const OAUTH2_YANDEX: Oauth2 = Oauth2 {
-    // see <https://tech.yandex.com/oauth/doc/dg/reference/auto-code-client-docpage/>
-    client_id: "c4d0b6735fc8420a816d7e1303469341",
-    get_code: "https://oauth.yandex.com/authorize?client_id=$CLIENT_ID&response_type=code&scope=mail%3Aimap_full%20mail%3Asmtp&force_confirm=true",
-    init_token: "https://oauth.yandex.com/token?grant_type=authorization_code&code=$CODE&client_id=$CLIENT_ID&client_secret=58b8c6e94cf44fbe952da8511955dacf",
-    refresh_token: "https://oauth.yandex.com/token?grant_type=refresh_token&refresh_token=$REFRESH_TOKEN&client_id=$CLIENT_ID&client_secret=58b8c6e94cf44fbe952da8511955dacf",
-    get_userinfo: None,
-};
-
-#[derive(Debug, Clone, PartialEq, Eq)]
-struct Oauth2 {
-    client_id: &'static str,
-    get_code: &'static str,
-    init_token: &'static str,
-    refresh_token: &'static str,
-    get_userinfo: Option<&'static str>,
-}
+/// Access tokens are refreshed this many seconds before they expire,
+/// so that IMAP and SMTP reconnects do not run into expired tokens.
+const OAUTH2_REFRESH_MARGIN: u64 = 300;
+
+/// Characters to percent-encode in scopes, everything except unreserved characters.
+const SCOPE_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
 
 /// OAuth 2 Access Token Response
 #[derive(Debug, Deserialize)]
@@ -51,6 +36,8 @@ struct Response {
     /// Duration of time the token is granted for, in seconds
     expires_in: Option<u64>,
     refresh_token: Option<String>,
+    /// Duration of time the refresh token is valid for, in seconds, not sent by all providers.
+    refresh_token_expires_in: Option<u64>,
     scope: Option<String>,
 }
 
@@ -61,13 +48,14 @@ pub async fn get_oauth2_url(
     addr: &str,
     redirect_uri: &str,
 ) -> Result<Option<String>> {
-    if let Some(oauth2) = Oauth2::from_address(context, addr).await {
+    if let Some(oauth2) = Oauth2Endpoints::from_address(context, addr).await {
         context
             .sql
             .set_raw_config("oauth2_pending_redirect_uri", Some(redirect_uri))
             .await?;
         let oauth2_url = replace_in_uri(oauth2.get_code, "$CLIENT_ID", oauth2.client_id);
         let oauth2_url = replace_in_uri(&oauth2_url, "$REDIRECT_URI", redirect_uri);
+        let oauth2_url = oauth2_url.replace("$SCOPE", &oauth2.scope());
 
         Ok(Some(oauth2_url))
     } else {
@@ -81,8 +69,9 @@ pub(crate) async fn get_oauth2_access_token(
     code: &str,
     regenerate: bool,
 ) -> Result<Option<String>> {
-    if let Some(oauth2) = Oauth2::from_address(context, addr).await {
+    if let Some(oauth2) = Oauth2Endpoints::from_address(context, addr).await {
         let lock = context.oauth2_mutex.lock().await;
+        let addr = normalize_addr(addr);
 
         // read generated token
         if !regenerate && !is_expired(context).await? {
@@ -94,7 +83,7 @@ pub(crate) async fn get_oauth2_access_token(
         }
 
         // generate new token: build & call auth url
-        let refresh_token = context.sql.get_raw_config("oauth2_refresh_token").await?;
+        let refresh_token = get_refresh_token(context, addr).await?;
         let refresh_token_for = context
             .sql
             .get_raw_config("oauth2_refresh_token_for")
@@ -185,6 +174,18 @@ pub(crate) async fn get_oauth2_access_token(
                 .sql
                 .set_raw_config("oauth2_refresh_token_for", Some(code))
                 .await?;
+            context
+                .sql
+                .set_raw_config("oauth2_refresh_token_addr", Some(addr))
+                .await?;
+            let refresh_token_expires = response
+                .refresh_token_expires_in
+                .map(|t| time() + t as i64)
+                .unwrap_or_default();
+            context
+                .sql
+                .set_raw_config_int64("oauth2_refresh_token_expires", refresh_token_expires)
+                .await?;
         }
 
         // after that, save the access token.
@@ -196,8 +197,8 @@ pub(crate) async fn get_oauth2_access_token(
                 .await?;
             let expires_in = response
                 .expires_in
-                // refresh a bit before
-                .map(|t| time() + t as i64 - 5)
+                // refresh before the token expires, but not too early for short-lived tokens
+                .map(|t| time() + (t - OAUTH2_REFRESH_MARGIN.min(t / 2)) as i64)
                 .unwrap_or_else(|| 0);
             context
                 .sql
@@ -229,7 +230,7 @@ pub(crate) async fn get_oauth2_addr(
     addr: &str,
     code: &str,
 ) -> Result<Option<String>> {
-    let oauth2 = match Oauth2::from_address(context, addr).await {
+    let oauth2 = match Oauth2Endpoints::from_address(context, addr).await {
         Some(o) => o,
         None => return Ok(None),
     };
@@ -263,8 +264,51 @@ pub(crate) async fn get_oauth2_addr(
     }
 }
 
-impl Oauth2 {
-    async fn from_address(context: &Context, addr: &str) -> Option<Self> {
+/// Removes the cached access token,
+/// so that a new one is requested on the next connection attempt.
+///
+/// Called if the server rejects the access token before it was expected to expire.
+pub(crate) async fn invalidate_oauth2_access_token(context: &Context) -> Result<()> {
+    let _lock = context.oauth2_mutex.lock().await;
+    context
+        .sql
+        .set_raw_config("oauth2_access_token", None)
+        .await?;
+    Ok(())
+}
+
+/// Returns the stored refresh token if it was issued for `addr` and has not expired.
+async fn get_refresh_token(context: &Context, addr: &str) -> Result<Option<String>> {
+    let Some(refresh_token) = context.sql.get_raw_config("oauth2_refresh_token").await? else {
+        return Ok(None);
+    };
+    if let Some(token_addr) = context
+        .sql
+        .get_raw_config("oauth2_refresh_token_addr")
+        .await?
+    {
+        if !addr_cmp(&token_addr, addr) {
+            info!(
+                context,
+                "OAuth2 refresh token was issued for another address, not using it."
+            );
+            return Ok(None);
+        }
+    }
+    let expires = context
+        .sql
+        .get_raw_config_int64("oauth2_refresh_token_expires")
+        .await?
+        .unwrap_or_default();
+    if expires > 0 && expires <= time() {
+        info!(context, "OAuth2 refresh token expired.");
+        return Ok(None);
+    }
+    Ok(Some(refresh_token))
+}
+
+impl Oauth2Endpoints {
+    async fn from_address(context: &Context, addr: &str) -> Option<&'static Self> {
         let addr_normalized = normalize_addr(addr);
         let skip_mx = true;
         if let Some(domain) = addr_normalized
@@ -275,15 +319,17 @@ async fn from_address(context: &Context, addr: &str) -> Option<Self> {
                 .await
                 .and_then(|provider| provider.oauth2_authorizer.as_ref())
             {
-                return Some(match oauth2_authorizer {
-                    Oauth2Authorizer::Gmail => OAUTH2_GMAIL,
-                    Oauth2Authorizer::Yandex => OAUTH2_YANDEX,
-                });
+                return Some(oauth2_authorizer.endpoints());
             }
         }
         None
     }
 
+    /// Returns percent-encoded scopes to be inserted as `$SCOPE`.
+    fn scope(&self) -> String {
+        utf8_percent_encode(&self.scopes.join(" "), SCOPE_ENCODE_SET).to_string()
+    }
+
     async fn get_addr(&self, context: &Context, access_token: &str) -> Result<Option<String>> {
         let userinfo_url = self.get_userinfo.unwrap_or("");
         let userinfo_url = replace_in_uri(userinfo_url, "$ACCESS_TOKEN", access_token);
@@ -345,6 +391,7 @@ fn normalize_addr(addr: &str) -> &str {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::provider::{OAUTH2_GMAIL, OAUTH2_YANDEX};
     use crate::test_utils::TestContext;
 
     #[test]
@@ -366,18 +413,58 @@ async fn test_oauth_from_address() {
         let t = TestContext::new().await;
 
         // Delta Chat does not have working Gmail client ID anymore.
-        assert_eq!(Oauth2::from_address(&t, "hello@gmail.com").await, None);
-        assert_eq!(Oauth2::from_address(&t, "hello@googlemail.com").await, None);
+        assert_eq!(
+            Oauth2Endpoints::from_address(&t, "hello@gmail.com").await,
+            None
+        );
+        assert_eq!(
+            Oauth2Endpoints::from_address(&t, "hello@googlemail.com").await,
+            None
+        );
 
         assert_eq!(
-            Oauth2::from_address(&t, "hello@yandex.com").await,
-            Some(OAUTH2_YANDEX)
+            Oauth2Endpoints::from_address(&t, "hello@yandex.com").await,
+            Some(&OAUTH2_YANDEX)
         );
         assert_eq!(
-            Oauth2::from_address(&t, "hello@yandex.ru").await,
-            Some(OAUTH2_YANDEX)
+            Oauth2Endpoints::from_address(&t, "hello@yandex.ru").await,
+            Some(&OAUTH2_YANDEX)
         );
-        assert_eq!(Oauth2::from_address(&t, "hello@web.de").await, None);
+        assert_eq!(
+            Oauth2Endpoints::from_address(&t, "hello@web.de").await,
+            None
+        );
+    }
+
+    #[test]
+    fn test_scope() {
+        assert_eq!(
+            OAUTH2_GMAIL.scope(),
+            "https%3A%2F%2Fmail.google.com%2F%20email"
+        );
+        assert_eq!(OAUTH2_YANDEX.scope(), "mail%3Aimap_full%20mail%3Asmtp");
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_refresh_token_per_addr() -> Result<()> {
+        let t = TestContext::new().await;
+        t.sql
+            .set_raw_config("oauth2_refresh_token", Some("token"))
+            .await?;
+        t.sql
+            .set_raw_config("oauth2_refresh_token_addr", Some("alice@yandex.com"))
+            .await?;
+        assert_eq!(
+            get_refresh_token(&t, "Alice@yandex.com").await?,
+            Some("token".to_string())
+        );
+        assert_eq!(get_refresh_token(&t, "bob@yandex.com").await?, None);
+
+        t.sql
+            .set_raw_config_int64("oauth2_refresh_token_expires", time() - 1)
+            .await?;
+        assert_eq!(get_refresh_token(&t, "alice@yandex.com").await?, None);
+        Ok(())
     }
 
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
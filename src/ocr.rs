@@ -0,0 +1,177 @@
+//! # Text recognition in image attachments.
+//!
+//! Text recognition (OCR) itself is done by the embedder, e.g. using the engine of the platform,
+//! so core does not need to bundle one.
+//! If [`Config::RequestOcr`] is set, core requests recognition of new images
+//! with [`EventType::OcrRequest`] and caches the results delivered with
+//! [`Context::set_msg_ocr_text`] in the message parameters.
+//! The text is also stored normalized in the `msgs.txt_ocr` column
+//! which has a full-text index, so that [`Context::search_msgs`]
+//! finds e.g. screenshots by their contents.
+
+use anyhow::{ensure, Context as _, Result};
+
+use crate::config::Config;
+use crate::constants::DC_CHAT_ID_LAST_SPECIAL;
+use crate::context::Context;
+use crate::download::DownloadState;
+use crate::events::EventType;
+use crate::message::{normalize_text, Message, MsgId, Viewtype};
+use crate::param::Param;
+
+impl Message {
+    /// Returns the text recognized in the image attachment,
+    /// `None` if recognition was not done yet.
+    ///
+    /// The text may be empty if the image contains no text.
+    pub fn get_ocr_text(&self) -> Option<&str> {
+        self.param.get(Param::OcrText)
+    }
+}
+
+impl Context {
+    /// Requests text recognition in the image attachment of a message.
+    ///
+    /// This is done automatically for new messages if [`Config::RequestOcr`] is set,
+    /// but may also be called for older messages, see [`get_pending_ocr`].
+    /// [`EventType::OcrRequest`] is emitted unless the text is recognized already.
+    pub async fn request_msg_ocr(&self, msg_id: MsgId) -> Result<()> {
+        let msg = Message::load_from_db(self, msg_id).await?;
+        ensure!(
+            msg.viewtype == Viewtype::Image,
+            "{msg_id} has no image attachment"
+        );
+        if msg.get_ocr_text().is_some() {
+            return Ok(());
+        }
+        let path = msg
+            .get_file(self)
+            .with_context(|| format!("{msg_id} has no file"))?;
+        self.emit_event(EventType::OcrRequest {
+            msg_id,
+            path: path.to_string_lossy().into_owned(),
+        });
+        Ok(())
+    }
+
+    /// Stores the text recognized in the image attachment of a message,
+    /// usually after it was requested with [`EventType::OcrRequest`].
+    ///
+    /// Pass an empty string if the image contains no text,
+    /// so that recognition is not requested again.
+    pub async fn set_msg_ocr_text(&self, msg_id: MsgId, text: &str) -> Result<()> {
+        let mut msg = Message::load_from_db(self, msg_id).await?;
+        ensure!(
+            msg.viewtype == Viewtype::Image,
+            "{msg_id} has no image attachment"
+        );
+        let text = text.trim();
+        msg.param.set(Param::OcrText, text);
+        self.sql
+            .execute(
+                "UPDATE msgs SET param=?, txt_ocr=? WHERE id=?",
+                (
+                    msg.param.to_string(),
+                    normalize_text(text).as_deref().unwrap_or(text),
+                    msg_id,
+                ),
+            )
+            .await?;
+        self.emit_msgs_changed(msg.chat_id, msg_id);
+        Ok(())
+    }
+}
+
+/// Requests text recognition for a new message if [`Config::RequestOcr`] is set.
+pub(crate) async fn handle_new_msg(context: &Context, msg_id: MsgId) -> Result<()> {
+    if !context.get_config_bool(Config::RequestOcr).await? {
+        return Ok(());
+    }
+    let msg = Message::load_from_db(context, msg_id).await?;
+    if msg.viewtype == Viewtype::Image && msg.download_state == DownloadState::Done {
+        context.request_msg_ocr(msg_id).await?;
+    }
+    Ok(())
+}
+
+/// Returns the images in which text was not recognized yet, newest first,
+/// e.g. to request recognition after enabling [`Config::RequestOcr`] or after a restart.
+pub async fn get_pending_ocr(context: &Context) -> Result<Vec<MsgId>> {
+    context
+        .sql
+        .query_map(
+            "SELECT id FROM msgs
+             WHERE chat_id>? AND type=? AND download_state=? AND txt_ocr IS NULL
+             ORDER BY id DESC",
+            (
+                DC_CHAT_ID_LAST_SPECIAL,
+                Viewtype::Image,
+                DownloadState::Done,
+            ),
+            |row| row.get::<_, MsgId>(0),
+            |rows| {
+                rows.collect::<std::result::Result<Vec<_>, _>>()
+                    .map_err(Into::into)
+            },
+        )
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestContextManager;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_ocr() -> Result<()> {
+        let mut tcm = TestContextManager::new();
+        let alice = &tcm.alice().await;
+        let bob = &tcm.bob().await;
+        bob.set_config_bool(Config::RequestOcr, true).await?;
+
+        let alice_chat = alice.create_chat(bob).await;
+        let file = alice.get_blobdir().join("screenshot.png");
+        tokio::fs::write(&file, include_bytes!("../test-data/image/logo.png")).await?;
+        let mut msg = Message::new(Viewtype::Image);
+        msg.set_file_and_deduplicate(alice, &file, None, None)?;
+        let sent = alice.send_msg(alice_chat.id, &mut msg).await;
+        let msg = bob.recv_msg(&sent).await;
+        assert_eq!(msg.get_ocr_text(), None);
+
+        let event = bob
+            .evtracker
+            .get_matching(|evt| matches!(evt, EventType::OcrRequest { .. }))
+            .await;
+        let EventType::OcrRequest { msg_id, path } = event else {
+            unreachable!();
+        };
+        assert_eq!(msg_id, msg.id);
+        assert_eq!(
+            path,
+            msg.get_file(bob).unwrap().to_string_lossy().into_owned()
+        );
+        assert_eq!(get_pending_ocr(bob).await?, vec![msg.id]);
+        assert!(bob.search_msgs(None, "delta").await?.is_empty());
+
+        bob.set_msg_ocr_text(msg.id, "Delta Chat\n").await?;
+        let msg = Message::load_from_db(bob, msg.id).await?;
+        assert_eq!(msg.get_ocr_text(), Some("Delta Chat"));
+        assert!(get_pending_ocr(bob).await?.is_empty());
+        assert_eq!(bob.search_msgs(None, "delta").await?, vec![msg.id]);
+        assert_eq!(
+            bob.search_msgs(Some(msg.chat_id), "CHAT").await?,
+            vec![msg.id]
+        );
+
+        // Non-ASCII text is found case-insensitively too.
+        bob.set_msg_ocr_text(msg.id, "ÄPFEL").await?;
+        assert_eq!(bob.search_msgs(None, "äpf").await?, vec![msg.id]);
+        assert!(bob.search_msgs(None, "delta").await?.is_empty());
+
+        // Text messages can't have recognized text.
+        let text_msg = tcm.send_recv(alice, bob, "Hi").await;
+        assert!(bob.set_msg_ocr_text(text_msg.id, "Hi").await.is_err());
+        assert!(bob.request_msg_ocr(text_msg.id).await.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,286 @@
+//! # Inspection of the outgoing message queue.
+//!
+//! Messages are put into the SMTP queue when they are sent
+//! and stay there until the SMTP server accepted them
+//! or the number of retries exceeded the limit.
+//! While a message is queued, it is shown as being sent.
+//! [`Context::get_outgoing_queue`] allows UIs to show why a message is not sent yet,
+//! queued messages can be canceled or sent before other messages.
+//!
+//! Entries exceeding [`crate::config::Config::SmtpMaxRetries`] retries are moved to a quarantine,
+//! so that a message that can't be sent, e.g. because it makes the sending code fail,
+//! doesn't block the queue.
+//! The message is marked as failed with the reason
+//! and can be sent again with [`Context::retry_quarantined_message`]
+//! until the quarantined entry expires after 30 days.
+
+use anyhow::{ensure, Result};
+
+use crate::context::Context;
+use crate::message::{self, Message, MessageState, MsgId};
+
+/// Entry of the outgoing message queue.
+///
+/// Messages to many recipients may be split into several entries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueuedMessage {
+    /// ID of the queued message.
+    pub msg_id: MsgId,
+
+    /// Addresses the entry is sent to.
+    pub recipients: Vec<String>,
+
+    /// Number of attempts to send the entry.
+    pub retries: u32,
+
+    /// Error of the last failed attempt, if any.
+    pub last_error: Option<String>,
+
+    /// Timestamp of the next attempt if sending is currently postponed after an error.
+    pub next_retry: Option<i64>,
+}
+
+/// Entry of the outgoing message queue for which sending was given up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuarantinedMessage {
+    /// ID of the quarantined message.
+    pub msg_id: MsgId,
+
+    /// Addresses the entry should be sent to.
+    pub recipients: Vec<String>,
+
+    /// Why the entry was quarantined.
+    pub reason: String,
+
+    /// Timestamp of quarantining the entry.
+    pub timestamp: i64,
+}
+
+impl Context {
+    /// Returns the messages waiting in the SMTP queue in the order they will be sent.
+    pub async fn get_outgoing_queue(&self) -> Result<Vec<QueuedMessage>> {
+        let next_retry = *self.smtp_retry_timestamp.lock();
+        self.sql
+            .query_map(
+                "SELECT msg_id, recipients, retries, last_error
+                 FROM smtp ORDER BY priority DESC, id ASC",
+                (),
+                |row| {
+                    let msg_id: MsgId = row.get(0)?;
+                    let recipients: String = row.get(1)?;
+                    let retries: u32 = row.get(2)?;
+                    let last_error: Option<String> = row.get(3)?;
+                    Ok(QueuedMessage {
+                        msg_id,
+                        recipients: recipients.split(' ').map(|addr| addr.to_string()).collect(),
+                        retries,
+                        last_error,
+                        next_retry,
+                    })
+                },
+                |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+            )
+            .await
+    }
+
+    /// Removes a message from the SMTP queue and marks it as failed.
+    ///
+    /// Recipients for which sending already succeeded still receive the message.
+    pub async fn cancel_queued_message(&self, msg_id: MsgId) -> Result<()> {
+        let deleted = self
+            .sql
+            .execute("DELETE FROM smtp WHERE msg_id=?", (msg_id,))
+            .await?;
+        ensure!(deleted > 0, "{msg_id} is not queued");
+        if let Some(mut msg) = Message::load_from_db_optional(self, msg_id).await? {
+            message::set_msg_failed(self, &mut msg, "Sending was canceled.").await?;
+        }
+        info!(self, "Canceled sending {msg_id}.");
+        Ok(())
+    }
+
+    /// Moves a message to the front of the SMTP queue and triggers sending.
+    pub async fn prioritize_queued_message(&self, msg_id: MsgId) -> Result<()> {
+        let updated = self
+            .sql
+            .execute(
+                // The priority must be above all priority classes even if only automatic messages are queued.
+                "UPDATE smtp SET priority=(SELECT MAX(MAX(priority), 0) FROM smtp)+1 WHERE msg_id=?",
+                (msg_id,),
+            )
+            .await?;
+        ensure!(updated > 0, "{msg_id} is not queued");
+        self.scheduler.interrupt_smtp().await;
+        Ok(())
+    }
+
+    /// Returns the quarantined SMTP queue entries, oldest first.
+    pub async fn get_quarantined_messages(&self) -> Result<Vec<QuarantinedMessage>> {
+        self.sql
+            .query_map(
+                "SELECT msg_id, recipients, reason, timestamp
+                 FROM smtp_quarantine ORDER BY id",
+                (),
+                |row| {
+                    let msg_id: MsgId = row.get(0)?;
+                    let recipients: String = row.get(1)?;
+                    let reason: String = row.get(2)?;
+                    let timestamp: i64 = row.get(3)?;
+                    Ok(QuarantinedMessage {
+                        msg_id,
+                        recipients: recipients.split(' ').map(|addr| addr.to_string()).collect(),
+                        reason,
+                        timestamp,
+                    })
+                },
+                |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+            )
+            .await
+    }
+
+    /// Moves a quarantined message back to the SMTP queue with a reset retry count
+    /// and triggers sending.
+    pub async fn retry_quarantined_message(&self, msg_id: MsgId) -> Result<()> {
+        let retried = self
+            .sql
+            .transaction(|transaction| {
+                let retried = transaction.execute(
+                    "INSERT INTO smtp (rfc724_mid, mime, msg_id, recipients, priority)
+                     SELECT rfc724_mid, mime, msg_id, recipients, priority
+                     FROM smtp_quarantine WHERE msg_id=? ORDER BY id",
+                    (msg_id,),
+                )?;
+                transaction.execute("DELETE FROM smtp_quarantine WHERE msg_id=?", (msg_id,))?;
+                Ok(retried)
+            })
+            .await?;
+        ensure!(retried > 0, "{msg_id} is not quarantined");
+        if let Some(msg) = Message::load_from_db_optional(self, msg_id).await? {
+            message::update_msg_state(self, msg_id, MessageState::OutPending).await?;
+            self.emit_msgs_changed(msg.chat_id, msg_id);
+        }
+        info!(self, "Retrying quarantined {msg_id}.");
+        self.scheduler.interrupt_smtp().await;
+        Ok(())
+    }
+
+    /// Removes a message from the quarantine, the message stays failed.
+    pub async fn discard_quarantined_message(&self, msg_id: MsgId) -> Result<()> {
+        let deleted = self
+            .sql
+            .execute("DELETE FROM smtp_quarantine WHERE msg_id=?", (msg_id,))
+            .await?;
+        ensure!(deleted > 0, "{msg_id} is not quarantined");
+        info!(self, "Discarded quarantined {msg_id}.");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::send_text_msg;
+    use crate::config::Config;
+    use crate::smtp;
+    use crate::test_utils::TestContextManager;
+    use crate::tools::time;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_outgoing_queue() -> Result<()> {
+        let mut tcm = TestContextManager::new();
+        let alice = &tcm.alice().await;
+        let bob = &tcm.bob().await;
+        let chat = alice.create_chat(bob).await;
+
+        assert!(alice.get_outgoing_queue().await?.is_empty());
+
+        let msg_id1 = send_text_msg(alice, chat.id, "first".to_string()).await?;
+        let msg_id2 = send_text_msg(alice, chat.id, "second".to_string()).await?;
+        let queue = alice.get_outgoing_queue().await?;
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue[0].msg_id, msg_id1);
+        assert!(queue[0].recipients.contains(&"bob@example.net".to_string()));
+        assert_eq!(queue[0].retries, 0);
+        assert_eq!(queue[0].last_error, None);
+
+        alice.prioritize_queued_message(msg_id2).await?;
+        let queue = alice.get_outgoing_queue().await?;
+        assert_eq!(queue[0].msg_id, msg_id2);
+        assert_eq!(queue[1].msg_id, msg_id1);
+
+        alice.cancel_queued_message(msg_id2).await?;
+        let queue = alice.get_outgoing_queue().await?;
+        assert_eq!(queue.len(), 1);
+        let msg = Message::load_from_db(alice, msg_id2).await?;
+        assert_eq!(msg.state, MessageState::OutFailed);
+        assert!(alice.cancel_queued_message(msg_id2).await.is_err());
+
+        Ok(())
+    }
+
+    /// Moves the SMTP queue entry of `msg_id` to the quarantine
+    /// by failing to send it until the retry limit is exceeded.
+    async fn exceed_retry_limit(t: &Context, msg_id: MsgId) -> Result<()> {
+        let rowid: i64 = t
+            .sql
+            .query_get_value("SELECT id FROM smtp WHERE msg_id=?", (msg_id,))
+            .await?
+            .unwrap();
+        loop {
+            if smtp::start_retry(t, rowid).await?.is_none() {
+                return Ok(());
+            }
+            smtp::set_last_error(t, rowid, Some("Broken")).await;
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_quarantine() -> Result<()> {
+        let mut tcm = TestContextManager::new();
+        let alice = &tcm.alice().await;
+        let bob = &tcm.bob().await;
+        alice.set_config_u32(Config::SmtpMaxRetries, 2).await?;
+        let chat = alice.create_chat(bob).await;
+
+        let msg_id = send_text_msg(alice, chat.id, "Hi".to_string()).await?;
+        exceed_retry_limit(alice, msg_id).await?;
+        assert!(alice.get_outgoing_queue().await?.is_empty());
+        let quarantined = alice.get_quarantined_messages().await?;
+        assert_eq!(quarantined.len(), 1);
+        assert_eq!(quarantined[0].msg_id, msg_id);
+        let reason = "Number of retries exceeded the limit, last error: Broken";
+        assert_eq!(quarantined[0].reason, reason);
+        let msg = Message::load_from_db(alice, msg_id).await?;
+        assert_eq!(msg.state, MessageState::OutFailed);
+        assert_eq!(msg.error(), Some(reason.to_string()));
+        assert!(alice.cancel_queued_message(msg_id).await.is_err());
+
+        alice.retry_quarantined_message(msg_id).await?;
+        assert!(alice.get_quarantined_messages().await?.is_empty());
+        let queue = alice.get_outgoing_queue().await?;
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue[0].retries, 0);
+        let msg = Message::load_from_db(alice, msg_id).await?;
+        assert_eq!(msg.state, MessageState::OutPending);
+        assert_eq!(msg.error(), None);
+        assert!(alice.retry_quarantined_message(msg_id).await.is_err());
+
+        exceed_retry_limit(alice, msg_id).await?;
+        alice.discard_quarantined_message(msg_id).await?;
+        assert!(alice.get_quarantined_messages().await?.is_empty());
+        assert!(alice.get_outgoing_queue().await?.is_empty());
+        assert!(alice.discard_quarantined_message(msg_id).await.is_err());
+
+        // Quarantined entries expire.
+        let msg_id = send_text_msg(alice, chat.id, "Hi again".to_string()).await?;
+        exceed_retry_limit(alice, msg_id).await?;
+        smtp::delete_expired_quarantine(alice, time()).await?;
+        assert_eq!(alice.get_quarantined_messages().await?.len(), 1);
+        smtp::delete_expired_quarantine(alice, time() + smtp::QUARANTINE_MAX_AGE + 1).await?;
+        assert!(alice.get_quarantined_messages().await?.is_empty());
+        let msg = Message::load_from_db(alice, msg_id).await?;
+        assert_eq!(msg.state, MessageState::OutFailed);
+
+        Ok(())
+    }
+}
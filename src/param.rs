@@ -71,9 +71,30 @@ pub enum Param {
     /// For Messages: the message is a reaction.
     Reaction = b'x',
 
+    /// For Messages: custom emojis used in a reaction,
+    /// see [`crate::reaction::CustomEmoji`].
+    CustomEmojis = b'(',
+
+    /// For Messages: number of recipients a broadcast list message was sent to.
+    BroadcastRecipients = b')',
+
+    /// For Messages: text recognized in the image attachment, see [`crate::ocr`].
+    OcrText = b'.',
+
+    /// For Messages: why a `.xdc` attachment is not a usable webxdc app,
+    /// see [`crate::webxdc::WebxdcUnsupported`].
+    WebxdcUnsupported = b'?',
+
     /// For Chats: the timestamp of the last reaction.
     LastReactionTimestamp = b'y',
 
+    /// For Chats: Message-ID of the last read message, synchronized across devices.
+    LastReadMsg = b'[',
+
+    /// For Chats: "sent" timestamp of the last read message,
+    /// used to order markers if one of the messages is not available locally.
+    LastReadTimestamp = b']',
+
     /// For Chats: Message ID of the last reaction.
     LastReactionMsgId = b'Y',
 
@@ -111,6 +132,10 @@ pub enum Param {
     /// For Messages
     AttachGroupImage = b'A',
 
+    /// For Messages: the message promotes a group created by the sender,
+    /// so the recipients accept the admins of the group from it.
+    GroupPromotion = b'_',
+
     /// For Messages
     WebrtcRoom = b'V',
 
@@ -167,12 +192,37 @@ pub enum Param {
     /// the List-Id of the mailing list (which is also used as the group id of the chat).
     ListId = b's',
 
+    /// For Chats: space-separated URIs from the `List-Subscribe` header of a mailing list.
+    ListSubscribe = b'{',
+
+    /// For Chats: space-separated URIs from the `List-Unsubscribe` header of a mailing list.
+    ListUnsubscribe = b'}',
+
+    /// For Chats: set to "1" if the mailing list supports one-click unsubscribing
+    /// as defined in RFC 8058.
+    ListUnsubscribeOneClick = b'~',
+
+    /// For Messages in mailing list chats: address to send the message to
+    /// instead of the `List-Post` address, used for subscribe and unsubscribe requests.
+    MailinglistRecipient = b'>',
+
     /// For Contacts: timestamp of status (aka signature or footer) update.
     StatusTimestamp = b'j',
 
     /// For Contacts and Chats: timestamp of avatar update.
     AvatarTimestamp = b'J',
 
+    /// For Contacts: timestamp of the last request to resend the profile data.
+    ProfileRequestTimestamp = b'I',
+
+    /// For Chats: space-separated addresses of members
+    /// whose avatar is requested with the next message sent to the chat.
+    AvatarRequests = b'!',
+
+    /// For Contacts: encryption preference set by the user,
+    /// a value from [`crate::contact::EncryptionOverride`].
+    EncryptionOverride = b'L',
+
     /// For Chats: timestamp of status/signature/footer update.
     EphemeralSettingsTimestamp = b'B',
 
@@ -203,8 +253,79 @@ pub enum Param {
     /// For Webxdc Message Instances: Chat to integrate the Webxdc for.
     WebxdcIntegrateFor = b'2',
 
+    /// For Webxdc Message Instances: [`crate::webxdc::WebxdcSendGrant`],
+    /// whether the app may send messages on the user's behalf.
+    WebxdcSendGrant = b'+',
+
     /// For messages: Whether [crate::message::Viewtype::Sticker] should be forced.
     ForceSticker = b'X',
+
+    /// For Chats: do not request read receipts for outgoing messages.
+    DisableMdnRequests = b'M',
+
+    /// For Chats: refuse to send messages that cannot be end-to-end encrypted.
+    EncryptionRequired = b'Z',
+
+    /// For Chats requiring encryption: whether encryption was available on the last check.
+    EncryptionAvailable = b'z',
+
+    /// For Messages: custom headers allowed by [`crate::config::Config::AllowedCustomHeaders`],
+    /// one `name: value` pair per line.
+    CustomHeaders = b'-',
+
+    /// For Messages: comma-separated list of [`crate::message::DeliveryPath`]s
+    /// the message was successfully sent over.
+    DeliveryPaths = b'5',
+
+    /// For Messages: [`crate::message::DeliveryFailure`]
+    /// taken from a delivery status notification.
+    DeliveryFailure = b'6',
+
+    /// For Messages: set to "1" if the message was sent,
+    /// but some recipients rejected it,
+    /// see [`crate::message::MsgId::get_failed_recipients`].
+    PartiallyFailed = b'7',
+
+    /// For Chats: [`crate::chat::MailinglistReplyMode`] of a mailing list chat.
+    MailinglistReplyMode = b'8',
+
+    /// For Chats: 1 if fresh reactions count towards the fresh message counter, 0 if not.
+    /// If unset, [`crate::config::Config::ReactionsCountAsFresh`] is used.
+    ReactionsCountAsFresh = b'<',
+
+    /// For Messages: address from the `Reply-To` header of a mailing list message
+    /// if it is neither the sender nor the `List-Post` address.
+    ReplyTo = b'9',
+
+    /// For Chats: language to translate incoming messages to,
+    /// see [`crate::translate::AutoTranslate`].
+    AutoTranslateLang = b'#',
+
+    /// For Chats: ID of the provider used to translate incoming messages.
+    AutoTranslateProvider = b'$',
+
+    /// For Chats: timestamp of the last visibility change (pinned, archived),
+    /// used to ignore outdated changes synced from other devices.
+    VisibilityTimestamp = b'%',
+
+    /// For Chats: timestamp of the last mute change,
+    /// used to ignore outdated changes synced from other devices.
+    MuteTimestamp = b'&',
+
+    /// For Contacts: timestamp of the last block/unblock by the user,
+    /// used to ignore outdated changes synced from other devices.
+    BlockedTimestamp = b'*',
+
+    /// For Groups: only admins may change members, name and avatar,
+    /// see [`crate::chat::set_group_moderated`].
+    GroupModerated = b'@',
+
+    /// For Groups: timestamp of the last change of the group admins.
+    GroupAdminsTimestamp = b'^',
+
+    /// For Messages: set to "1" to request positive delivery status notifications,
+    /// see [`crate::message::Message::request_dsn`].
+    RequestDsn = b':',
     // 'L' was defined as ProtectionSettingsTimestamp for Chats, however, never used in production.
 }
 
@@ -454,6 +575,7 @@ pub fn from_param(context: &'a Context, src: &str) -> Result<ParamsFile<'a>> {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeSet;
     use std::path::Path;
     use std::str::FromStr;
 
@@ -462,6 +584,25 @@ mod tests {
     use super::*;
     use crate::test_utils::TestContext;
 
+    /// Tests that no two parameters share the same key byte.
+    #[test]
+    fn test_param_keys_unique() {
+        let src = include_str!("param.rs");
+        let start = src.find("pub enum Param {").unwrap();
+        let end = start + src[start..].find("\n}").unwrap();
+        let mut keys = BTreeSet::new();
+        for line in src[start..end].lines() {
+            let Some((_, key)) = line.trim().trim_end_matches(',').split_once(" = b'") else {
+                continue;
+            };
+            let key = key.strip_suffix('\'').unwrap().as_bytes();
+            assert_eq!(key.len(), 1, "{line}");
+            assert!(keys.insert(key[0]), "Duplicate parameter key in {line:?}");
+            assert_eq!(Param::from_u8(key[0]).map(|p| p as u8), Some(key[0]));
+        }
+        assert!(keys.len() > 50);
+    }
+
     #[test]
     fn test_dc_param() {
         let mut p1: Params = "a=1\nf=2\nc=3".parse().unwrap();
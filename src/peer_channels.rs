@@ -22,6 +22,12 @@
 //! 5. Upon receiving an announcement message, other peers store the sender's [NodeAddr] in the database
 //!    (scoped per WebXDC app instance/message-id). The other peers can then join the gossip with `joinRealtimeChannel().setListener()`
 //!    and `joinRealtimeChannel().send()` just like the other peers.
+//!
+//! Peer channels are also used to complete Secure-Join handshakes over the local network
+//! if [`Config::SecurejoinLocalNetwork`] is enabled.
+//! The inviter adds its [NodeAddr] including direct IP addresses to the QR code
+//! and both sides join a topic derived from the invite number.
+//! Handshake messages are broadcast to the topic in addition to being sent by email.
 
 use anyhow::{anyhow, bail, Context as _, Result};
 use data_encoding::BASE32_NOPAD;
@@ -31,20 +37,30 @@
 use iroh_gossip::net::{Event, Gossip, GossipEvent, JoinOptions, GOSSIP_ALPN};
 use iroh_gossip::proto::TopicId;
 use parking_lot::Mutex;
+use sha2::{Digest, Sha256};
 use std::collections::{BTreeSet, HashMap};
 use std::env;
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::{oneshot, RwLock};
 use tokio::task::JoinHandle;
 use url::Url;
 
-use crate::chat::send_msg;
+use crate::chat::{self, send_msg};
 use crate::config::Config;
+use crate::contact::{Contact, ContactId, Origin};
 use crate::context::Context;
 use crate::headerdef::HeaderDef;
+use crate::log::LogExt;
 use crate::message::{Message, MsgId, Viewtype};
 use crate::mimeparser::SystemMessage;
+use crate::param::Param;
+use crate::securejoin;
 use crate::EventType;
 
+/// How long Secure-Join channels are kept open.
+const SECUREJOIN_LOCAL_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
 /// The length of an ed25519 `PublicKey`, in bytes.
 const PUBLIC_KEY_LENGTH: usize = 32;
 const PUBLIC_KEY_STUB: &[u8] = "static_string".as_bytes();
@@ -127,18 +143,106 @@ async fn join_and_subscribe_gossip(
             .subscribe_with_opts(topic, JoinOptions::with_bootstrap(node_ids))
             .split();
 
+        let kind = ChannelKind::Webxdc(msg_id);
+        let loop_kind = kind.clone();
+        let ctx = ctx.clone();
+        let subscribe_loop = tokio::spawn(async move {
+            if let Err(e) = subscribe_loop(&ctx, gossip_receiver, topic, loop_kind, join_tx).await {
+                warn!(ctx, "subscribe_loop failed: {e}")
+            }
+        });
+
+        iroh_channels.insert(
+            topic,
+            ChannelState::new(subscribe_loop, gossip_sender, kind),
+        );
+
+        Ok(Some(join_rx))
+    }
+
+    /// Join the local network channel of a Secure-Join handshake.
+    ///
+    /// If `peer` is given, only messages from this node and contact are accepted.
+    /// Otherwise the channel is bound to the first node and contact
+    /// a handshake message is received from.
+    ///
+    /// The returned future resolves when at least one peer joined.
+    async fn join_securejoin_channel(
+        &self,
+        ctx: &Context,
+        topic: TopicId,
+        peer: Option<(NodeAddr, ContactId)>,
+    ) -> Result<Option<oneshot::Receiver<()>>> {
+        let mut iroh_channels = self.iroh_channels.write().await;
+
+        if iroh_channels.contains_key(&topic) {
+            return Ok(None);
+        }
+
+        let mut node_ids = Vec::new();
+        let mut securejoin_peer = None;
+        if let Some((node_addr, contact_id)) = peer {
+            node_ids.push(node_addr.node_id);
+            securejoin_peer = Some(SecurejoinPeer {
+                node_id: node_addr.node_id,
+                contact_id,
+            });
+            self.router.endpoint().add_node_addr(node_addr)?;
+        }
+
+        info!(
+            ctx,
+            "Joining Secure-Join peer channel with peers: {node_ids:?}."
+        );
+
+        let (join_tx, join_rx) = oneshot::channel();
+
+        let (gossip_sender, gossip_receiver) = self
+            .gossip
+            .subscribe_with_opts(topic, JoinOptions::with_bootstrap(node_ids))
+            .split();
+
+        let kind = ChannelKind::Securejoin(Arc::new(Mutex::new(securejoin_peer)));
         let ctx = ctx.clone();
+        let loop_kind = kind.clone();
         let subscribe_loop = tokio::spawn(async move {
-            if let Err(e) = subscribe_loop(&ctx, gossip_receiver, topic, msg_id, join_tx).await {
+            if let Err(e) = subscribe_loop(&ctx, gossip_receiver, topic, loop_kind, join_tx).await {
                 warn!(ctx, "subscribe_loop failed: {e}")
             }
         });
 
-        iroh_channels.insert(topic, ChannelState::new(subscribe_loop, gossip_sender));
+        iroh_channels.insert(
+            topic,
+            ChannelState::new(subscribe_loop, gossip_sender, kind),
+        );
 
         Ok(Some(join_rx))
     }
 
+    /// Broadcasts a rendered handshake message
+    /// to the Secure-Join channels bound to `contact_id`.
+    ///
+    /// Returns true if the message was broadcast to at least one channel.
+    async fn send_securejoin_data(&self, contact_id: ContactId, data: &[u8]) -> Result<bool> {
+        let mut sent = false;
+        let mut iroh_channels = self.iroh_channels.write().await;
+        for (topic, state) in iroh_channels.iter_mut() {
+            let ChannelKind::Securejoin(ref peer) = state.kind else {
+                continue;
+            };
+            if peer.lock().as_ref().map(|peer| peer.contact_id) != Some(contact_id) {
+                continue;
+            }
+            let seq_num = self.get_and_incr(topic);
+            let mut data = data.to_vec();
+            data.extend(seq_num.to_le_bytes());
+            data.extend(self.public_key.as_bytes());
+            state.sender.broadcast(data.into()).await?;
+            sent = true;
+        }
+        Ok(sent)
+    }
+
     /// Add gossip peers to realtime channel if it is already active.
     pub async fn maybe_add_gossip_peers(&self, topic: TopicId, peers: Vec<NodeAddr>) -> Result<()> {
         if self.iroh_channels.read().await.get(&topic).is_some() {
@@ -198,6 +302,13 @@ pub(crate) async fn get_node_addr(&self) -> Result<NodeAddr> {
         Ok(addr)
     }
 
+    /// Get the iroh [NodeAddr] including direct IP addresses.
+    ///
+    /// This must only be shared over local channels such as QR codes.
+    async fn get_local_node_addr(&self) -> Result<NodeAddr> {
+        self.router.endpoint().node_addr().await
+    }
+
     /// Leave the realtime channel for a given topic.
     pub(crate) async fn leave_realtime(&self, topic: TopicId) -> Result<()> {
         if let Some(channel) = self.iroh_channels.write().await.remove(&topic) {
@@ -222,17 +333,49 @@ pub(crate) struct ChannelState {
     subscribe_loop: JoinHandle<()>,
 
     sender: iroh_gossip::net::GossipSender,
+
+    /// What the channel is used for.
+    kind: ChannelKind,
 }
 
 impl ChannelState {
-    fn new(subscribe_loop: JoinHandle<()>, sender: iroh_gossip::net::GossipSender) -> Self {
+    fn new(
+        subscribe_loop: JoinHandle<()>,
+        sender: iroh_gossip::net::GossipSender,
+        kind: ChannelKind,
+    ) -> Self {
         Self {
             subscribe_loop,
             sender,
+            kind,
         }
     }
 }
 
+/// Purpose of a gossip channel.
+#[derive(Debug, Clone)]
+pub(crate) enum ChannelKind {
+    /// Realtime channel of a webxdc instance.
+    Webxdc(MsgId),
+
+    /// Local network channel of a Secure-Join handshake
+    /// and the peer it is bound to, if already known.
+    Securejoin(Arc<Mutex<Option<SecurejoinPeer>>>),
+}
+
+/// Authenticated peer of a Secure-Join channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SecurejoinPeer {
+    /// Node the handshake messages are accepted from.
+    ///
+    /// Gossip messages are only accepted if delivered directly by this node,
+    /// the connection to it is authenticated by iroh.
+    node_id: NodeId,
+
+    /// Contact the handshake messages are exchanged with.
+    contact_id: ContactId,
+}
+
 impl Context {
     /// Create iroh endpoint and gossip.
     async fn init_peer_channels(&self) -> Result<Iroh> {
@@ -289,7 +432,9 @@ async fn init_peer_channels(&self) -> Result<Iroh> {
     pub async fn get_or_try_init_peer_channel(
         &self,
     ) -> Result<tokio::sync::RwLockReadGuard<'_, Iroh>> {
-        if !self.get_config_bool(Config::WebxdcRealtimeEnabled).await? {
+        if !self.get_config_bool(Config::WebxdcRealtimeEnabled).await?
+            && !self.get_config_bool(Config::SecurejoinLocalNetwork).await?
+        {
             bail!("Attempt to get Iroh when realtime is disabled");
         }
 
@@ -469,8 +614,10 @@ pub async fn send_webxdc_realtime_data(ctx: &Context, msg_id: MsgId, data: Vec<u
         return Ok(());
     }
 
+    let len = data.len();
     let iroh = ctx.get_or_try_init_peer_channel().await?;
     iroh.send_webxdc_realtime_data(ctx, msg_id, data).await?;
+    ctx.add_webxdc_realtime_bytes(msg_id, len).await;
     Ok(())
 }
 
@@ -506,11 +653,122 @@ pub(crate) async fn create_iroh_header(ctx: &Context, msg_id: MsgId) -> Result<H
     ))
 }
 
+/// Returns the gossip topic of the Secure-Join handshake started with `invitenumber`.
+pub(crate) fn securejoin_topic(invitenumber: &str) -> TopicId {
+    let mut hasher = Sha256::new();
+    hasher.update(b"securejoin:");
+    hasher.update(invitenumber.as_bytes());
+    TopicId::from_bytes(hasher.finalize().into())
+}
+
+/// Joins the local network channel for the Secure-Join handshake started with `invitenumber`.
+///
+/// `peer` is the inviter's address taken from the QR code and the inviter contact,
+/// it is `None` on the inviter side.
+/// Returns a receiver resolving when a peer joined
+/// or `None` if local network handshakes are disabled or the channel is already joined.
+pub(crate) async fn join_securejoin_local(
+    ctx: &Context,
+    invitenumber: &str,
+    peer: Option<(NodeAddr, ContactId)>,
+) -> Result<Option<oneshot::Receiver<()>>> {
+    if !ctx.get_config_bool(Config::SecurejoinLocalNetwork).await? {
+        return Ok(None);
+    }
+    let iroh = ctx.get_or_try_init_peer_channel().await?;
+    let topic = securejoin_topic(invitenumber);
+    let join_rx = iroh.join_securejoin_channel(ctx, topic, peer).await?;
+    if join_rx.is_some() {
+        let ctx = ctx.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(SECUREJOIN_LOCAL_TIMEOUT).await;
+            if let Some(ref iroh) = *ctx.iroh.read().await {
+                iroh.leave_realtime(topic).await.log_err(&ctx).ok();
+            }
+        });
+    }
+    Ok(join_rx)
+}
+
+/// Returns own [NodeAddr] for Secure-Join QR codes
+/// or `None` if local network handshakes are disabled.
+pub(crate) async fn get_securejoin_node_addr(ctx: &Context) -> Result<Option<NodeAddr>> {
+    if !ctx.get_config_bool(Config::SecurejoinLocalNetwork).await? {
+        return Ok(None);
+    }
+    let iroh = ctx.get_or_try_init_peer_channel().await?;
+    Ok(Some(iroh.get_local_node_addr().await?))
+}
+
+/// Broadcasts a rendered Secure-Join handshake message
+/// to the Secure-Join channel of the contact the handshake is done with.
+///
+/// Returns true if the message was broadcast to a channel.
+pub(crate) async fn send_securejoin_local(
+    ctx: &Context,
+    msg: &Message,
+    data: &[u8],
+) -> Result<bool> {
+    let Some(ref iroh) = *ctx.iroh.read().await else {
+        return Ok(false);
+    };
+    let contact_id = if msg.param.get_cmd() == SystemMessage::MemberAddedToGroup {
+        // Only the added member is sent the message over the local network.
+        let addr = msg.param.get(Param::Arg).unwrap_or_default();
+        Contact::lookup_id_by_addr(ctx, addr, Origin::Unknown).await?
+    } else {
+        // Handshake messages are sent to 1:1 chats.
+        match chat::get_chat_contacts(ctx, msg.chat_id).await?[..] {
+            [contact_id] => Some(contact_id),
+            _ => None,
+        }
+    };
+    let Some(contact_id) = contact_id else {
+        return Ok(false);
+    };
+    iroh.send_securejoin_data(contact_id, data).await
+}
+
+/// Receives a handshake message from a Secure-Join channel.
+///
+/// Messages are only accepted from the node and contact the channel is bound to.
+/// An unbound channel is bound to the node and contact of the first accepted message.
+async fn receive_securejoin_data(
+    context: &Context,
+    peer: &Mutex<Option<SecurejoinPeer>>,
+    delivered_from: NodeId,
+    data: &[u8],
+) -> Result<()> {
+    let bound_peer = *peer.lock();
+    if let Some(bound_peer) = bound_peer {
+        if bound_peer.node_id != delivered_from {
+            warn!(
+                context,
+                "Ignoring Secure-Join message from unknown node {delivered_from}."
+            );
+            return Ok(());
+        }
+    }
+    let contact_id = securejoin::receive_local_handshake_msg(
+        context,
+        data,
+        bound_peer.map(|peer| peer.contact_id),
+    )
+    .await?;
+    if let Some(contact_id) = contact_id {
+        peer.lock().get_or_insert(SecurejoinPeer {
+            node_id: delivered_from,
+            contact_id,
+        });
+    }
+    Ok(())
+}
+
 async fn subscribe_loop(
     context: &Context,
     mut stream: iroh_gossip::net::GossipReceiver,
     topic: TopicId,
-    msg_id: MsgId,
+    kind: ChannelKind,
     join_tx: oneshot::Sender<()>,
 ) -> Result<()> {
     let mut join_tx = Some(join_tx);
@@ -525,25 +783,40 @@ async fn subscribe_loop(
                         join_tx.send(()).ok();
                     }
 
-                    for node in nodes {
-                        iroh_add_peer_for_topic(context, msg_id, topic, node, None).await?;
+                    if let ChannelKind::Webxdc(msg_id) = &kind {
+                        let msg_id = *msg_id;
+                        for node in nodes {
+                            iroh_add_peer_for_topic(context, msg_id, topic, node, None).await?;
+                        }
                     }
                 }
                 GossipEvent::NeighborUp(node) => {
                     info!(context, "IROH_REALTIME: NeighborUp: {}", node.to_string());
-                    iroh_add_peer_for_topic(context, msg_id, topic, node, None).await?;
+                    if let ChannelKind::Webxdc(msg_id) = &kind {
+                        iroh_add_peer_for_topic(context, *msg_id, topic, node, None).await?;
+                    }
                 }
                 GossipEvent::NeighborDown(_node) => {}
                 GossipEvent::Received(message) => {
                     info!(context, "IROH_REALTIME: Received realtime data");
-                    context.emit_event(EventType::WebxdcRealtimeData {
-                        msg_id,
-                        data: message
-                            .content
-                            .get(0..message.content.len() - 4 - PUBLIC_KEY_LENGTH)
-                            .context("too few bytes in iroh message")?
-                            .into(),
-                    });
+                    let data: Vec<u8> = message
+                        .content
+                        .get(0..message.content.len() - 4 - PUBLIC_KEY_LENGTH)
+                        .context("too few bytes in iroh message")?
+                        .into();
+                    match &kind {
+                        ChannelKind::Webxdc(msg_id) => {
+                            let msg_id = *msg_id;
+                            context.add_webxdc_realtime_bytes(msg_id, data.len()).await;
+                            context.emit_event(EventType::WebxdcRealtimeData { msg_id, data });
+                        }
+                        ChannelKind::Securejoin(peer) => {
+                            receive_securejoin_data(context, peer, message.delivered_from, &data)
+                                .await
+                                .log_err(context)
+                                .ok();
+                        }
+                    }
                 }
             },
             Event::Lagged => {
@@ -3,6 +3,7 @@
 use std::mem;
 
 use anyhow::{Context as _, Error, Result};
+use base64::Engine as _;
 use deltachat_contact_tools::{addr_cmp, ContactAddress};
 use num_traits::FromPrimitive;
 
@@ -14,6 +15,7 @@
 use crate::contact::{Contact, Origin};
 use crate::context::Context;
 use crate::events::EventType;
+use crate::headerdef::HeaderDef;
 use crate::key::{DcKey, Fingerprint, SignedPublicKey};
 use crate::message::Message;
 use crate::mimeparser::SystemMessage;
@@ -672,6 +674,19 @@ async fn handle_setup_change(
             }
         };
         for (chat_id, msg_id) in chats.iter() {
+            if let PeerstateChange::FingerprintChange = &change {
+                let chat = Chat::load_from_db(context, *chat_id).await?;
+                if chat.typ == Chattype::Group
+                    && chat.is_protected()
+                    && self.verified_key_fingerprint.is_some()
+                {
+                    context.emit_event(EventType::VerifiedGroupMemberKeyChanged {
+                        chat_id: *chat_id,
+                        contact_id,
+                    });
+                }
+            }
+
             let timestamp_sort = if let Some(msg_id) = msg_id {
                 let lastmsg = Message::load_from_db(context, *msg_id).await?;
                 lastmsg.timestamp_sort
@@ -878,6 +893,60 @@ pub(crate) async fn maybe_do_aeap_transition(
     Ok(())
 }
 
+/// Moves the verification to the new key of the peer
+/// if the message contains a key transition statement signed with the verified key.
+///
+/// See [`crate::key::rotate_self_key`] for the sending side.
+pub(crate) async fn maybe_do_key_transition(
+    context: &Context,
+    mime_parser: &mut crate::mimeparser::MimeMessage,
+) -> Result<()> {
+    let Some(transition) = mime_parser.get_header(HeaderDef::ChatKeyTransition) else {
+        return Ok(());
+    };
+    let Some(peerstate) = &mime_parser.peerstate else {
+        return Ok(());
+    };
+    let (Some(verified_key), Some(public_key)) = (&peerstate.verified_key, &peerstate.public_key)
+    else {
+        return Ok(());
+    };
+    let old_fingerprint = verified_key.dc_fingerprint();
+    let new_fingerprint = public_key.dc_fingerprint();
+    if old_fingerprint == new_fingerprint || !mime_parser.signatures.contains(&new_fingerprint) {
+        return Ok(());
+    }
+
+    let signature = base64::engine::general_purpose::STANDARD
+        .decode(transition.as_bytes())
+        .context("Invalid key transition header")?;
+    // `pk_validate()` expects the content to end with CRLF like a MIME part.
+    let statement = format!(
+        "{}\r\n",
+        crate::key::key_transition_statement(&old_fingerprint, &new_fingerprint)
+    );
+    let valid = crate::pgp::pk_validate(statement.as_bytes(), &signature, &[verified_key.clone()])
+        .unwrap_or_default();
+    if !valid.contains(&old_fingerprint) {
+        warn!(
+            context,
+            "Key transition of {} is not signed with the verified key.", &peerstate.addr
+        );
+        return Ok(());
+    }
+
+    let public_key = public_key.clone();
+    let peerstate = mime_parser.peerstate.as_mut().context("no peerstate??")?;
+    let verifier = peerstate.verifier.clone().unwrap_or_default();
+    peerstate.set_verified(public_key, new_fingerprint, verifier)?;
+    peerstate.save_to_db(&context.sql).await?;
+    info!(
+        context,
+        "Moved verification of {} to the new key.", &peerstate.addr
+    );
+    Ok(())
+}
+
 /// Type of the peerstate change.
 ///
 /// Changes to the peerstate are notified to the user via a message
@@ -5,6 +5,7 @@
 use std::io::Cursor;
 
 use anyhow::{bail, Context as _, Result};
+use chrono::{DateTime, Utc};
 use deltachat_contact_tools::EmailAddress;
 use pgp::armor::BlockType;
 use pgp::composed::{
@@ -14,6 +15,7 @@
 use pgp::crypto::ecc_curve::ECCCurve;
 use pgp::crypto::hash::HashAlgorithm;
 use pgp::crypto::sym::SymmetricKeyAlgorithm;
+use pgp::packet::{Signature, SignatureType};
 use pgp::types::{CompressionAlgorithm, PublicKeyTrait, SignatureBytes, StringToKey};
 use rand::{thread_rng, CryptoRng, Rng};
 use tokio::runtime::Handle;
@@ -243,17 +245,81 @@ pub(crate) fn create_keypair(addr: EmailAddress, keygen_type: KeyGenType) -> Res
     Ok(key_pair)
 }
 
+/// Returns the latest binding signature of the subkey.
+fn subkey_binding_signature(subkey: &SignedPublicSubKey) -> Option<&Signature> {
+    subkey
+        .signatures
+        .iter()
+        .filter(|sig| sig.typ() == SignatureType::SubkeyBinding)
+        .max_by_key(|sig| sig.created().copied())
+}
+
+/// Returns true if the subkey has expired before `now`.
+fn is_subkey_expired(subkey: &SignedPublicSubKey, now: DateTime<Utc>) -> bool {
+    let Some(expiration) =
+        subkey_binding_signature(subkey).and_then(|sig| sig.key_expiration_time())
+    else {
+        return false;
+    };
+    // Zero expiration time means that the key never expires.
+    expiration.num_seconds() > 0 && *subkey.created_at() + *expiration <= now
+}
+
+/// Returns true if the subkey can be used for encryption at `now`.
+///
+/// Revoked and expired subkeys are not usable.
+/// If the binding signature has key flags,
+/// they must allow encryption.
+fn is_usable_encryption_subkey(subkey: &SignedPublicSubKey, now: DateTime<Utc>) -> bool {
+    if !subkey.is_encryption_key()
+        || is_subkey_expired(subkey, now)
+        || subkey
+            .signatures
+            .iter()
+            .any(|sig| sig.typ() == SignatureType::SubkeyRevocation)
+    {
+        return false;
+    }
+    let Some(binding) = subkey_binding_signature(subkey) else {
+        return true;
+    };
+    let flags = binding.key_flags();
+    let has_flags = flags.certify()
+        || flags.sign()
+        || flags.encrypt_comms()
+        || flags.encrypt_storage()
+        || flags.authentication();
+    !has_flags || flags.encrypt_comms() || flags.encrypt_storage()
+}
+
+/// Returns fingerprints of the expired encryption subkeys of the key.
+pub(crate) fn expired_encryption_subkeys(key: &SignedPublicKey) -> Vec<Fingerprint> {
+    let now = Utc::now();
+    key.public_subkeys
+        .iter()
+        .filter(|subkey| subkey.is_encryption_key() && is_subkey_expired(subkey, now))
+        .map(|subkey| subkey.fingerprint().into())
+        .collect()
+}
+
+/// Returns true if the key has a subkey or primary key usable for encryption.
+pub(crate) fn has_encryption_key(key: &SignedPublicKey) -> bool {
+    select_pk_for_encryption(key).is_some()
+}
+
 /// Select public key or subkey to use for encryption.
 ///
-/// First, tries to use subkeys. If none of the subkeys are suitable
-/// for encryption, tries to use primary key. Returns `None` if the public
+/// First, tries to use the most recently created subkey
+/// that is flagged for encryption, not expired and not revoked.
+/// If none of the subkeys are suitable for encryption,
+/// tries to use primary key. Returns `None` if the public
 /// key cannot be used for encryption.
-///
-/// TODO: take key flags and expiration dates into account
 fn select_pk_for_encryption(key: &SignedPublicKey) -> Option<SignedPublicKeyOrSubkey> {
+    let now = Utc::now();
     key.public_subkeys
         .iter()
-        .find(|subkey| subkey.is_encryption_key())
+        .filter(|subkey| is_usable_encryption_subkey(subkey, now))
+        .max_by_key(|subkey| *subkey.created_at())
         .map_or_else(
             || {
                 // No usable subkey found, try primary key
@@ -498,6 +564,27 @@ fn test_create_keypair() {
         assert_ne!(keypair0.public, keypair1.public);
     }
 
+    #[test]
+    fn test_select_pk_for_encryption_gnupg() {
+        // Key generated by GnuPG 2.2 with an expired encryption subkey
+        // followed by a valid one.
+        let (secret, _) =
+            SignedSecretKey::from_asc(include_str!("../test-data/key/gnupg-multi-uid-secret.asc"))
+                .unwrap();
+        let keypair = KeyPair::new(secret).unwrap();
+        let pk = select_pk_for_encryption(&keypair.public).unwrap();
+        assert_eq!(
+            Fingerprint::from(pk.fingerprint()).hex(),
+            "BF94F8180A817D797AB9E4464632E16B83489061"
+        );
+        assert_eq!(
+            expired_encryption_subkeys(&keypair.public),
+            vec!["2738D4E156C7A99DF0BAF818778B4E1FDAC0622F"
+                .parse::<Fingerprint>()
+                .unwrap()]
+        );
+    }
+
     /// [SignedSecretKey] and [SignedPublicKey] objects
     /// to use in tests.
     struct TestKeys {
@@ -3,7 +3,7 @@
 pub(crate) mod data;
 
 use anyhow::Result;
-use deltachat_contact_tools::EmailAddress;
+use deltachat_contact_tools::{domain_to_ascii, EmailAddress};
 use hickory_resolver::{config, Resolver, TokioResolver};
 
 use crate::config::Config;
@@ -76,6 +76,61 @@ pub enum Oauth2Authorizer {
     Gmail = 2,
 }
 
+impl Oauth2Authorizer {
+    /// Returns OAuth 2 endpoints and scopes of the authorizer.
+    pub fn endpoints(&self) -> &'static Oauth2Endpoints {
+        match self {
+            Oauth2Authorizer::Gmail => &OAUTH2_GMAIL,
+            Oauth2Authorizer::Yandex => &OAUTH2_YANDEX,
+        }
+    }
+}
+
+/// OAuth 2 endpoints of a provider.
+///
+/// URLs are given in GET format and may contain the placeholders
+/// `$CLIENT_ID`, `$REDIRECT_URI`, `$SCOPE`, `$CODE`, `$REFRESH_TOKEN` and `$ACCESS_TOKEN`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Oauth2Endpoints {
+    /// Client ID registered at the provider.
+    pub client_id: &'static str,
+
+    /// URL to be opened in the browser to get the authorization code.
+    pub get_code: &'static str,
+
+    /// URL to exchange the authorization code for a refresh token and an access token.
+    pub init_token: &'static str,
+
+    /// URL to get a new access token using the refresh token.
+    pub refresh_token: &'static str,
+
+    /// URL to get the user info containing the email address.
+    pub get_userinfo: Option<&'static str>,
+
+    /// Scopes to request, separated by spaces when inserted as `$SCOPE`.
+    pub scopes: &'static [&'static str],
+}
+
+pub(crate) static OAUTH2_GMAIL: Oauth2Endpoints = Oauth2Endpoints {
+    // see <https://developers.google.com/identity/protocols/OAuth2InstalledApp>
+    client_id: "959970109878-4mvtgf6feshskf7695nfln6002mom908.apps.googleusercontent.com",
+    get_code: "https://accounts.google.com/o/oauth2/auth?client_id=$CLIENT_ID&redirect_uri=$REDIRECT_URI&response_type=code&scope=$SCOPE&access_type=offline",
+    init_token: "https://accounts.google.com/o/oauth2/token?client_id=$CLIENT_ID&redirect_uri=$REDIRECT_URI&code=$CODE&grant_type=authorization_code",
+    refresh_token: "https://accounts.google.com/o/oauth2/token?client_id=$CLIENT_ID&redirect_uri=$REDIRECT_URI&refresh_token=$REFRESH_TOKEN&grant_type=refresh_token",
+    get_userinfo: Some("https://www.googleapis.com/oauth2/v1/userinfo?alt=json&access_token=$ACCESS_TOKEN"),
+    scopes: &["https://mail.google.com/", "email"],
+};
+
+pub(crate) static OAUTH2_YANDEX: Oauth2Endpoints = Oauth2Endpoints {
+    // see <https://tech.yandex.com/oauth/doc/dg/reference/auto-code-client-docpage/>
+    client_id: "c4d0b6735fc8420a816d7e1303469341",
+    get_code: "https://oauth.yandex.com/authorize?client_id=$CLIENT_ID&response_type=code&scope=$SCOPE&force_confirm=true",
+    init_token: "https://oauth.yandex.com/token?grant_type=authorization_code&code=$CODE&client_id=$CLIENT_ID&client_secret=58b8c6e94cf44fbe952da8511955dacf",
+    refresh_token: "https://oauth.yandex.com/token?grant_type=refresh_token&refresh_token=$REFRESH_TOKEN&client_id=$CLIENT_ID&client_secret=58b8c6e94cf44fbe952da8511955dacf",
+    get_userinfo: None,
+    scopes: &["mail:imap_full", "mail:smtp"],
+};
+
 /// Email server endpoint.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Server {
@@ -160,12 +215,68 @@ const fn new() -> Self {
     }
 }
 
+/// Capabilities of a [chatmail](https://github.com/deltachat/chatmail) server.
+///
+/// Detected during configuration and saved together with the other `configured_*` values.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ChatmailCapabilities {
+    /// Server sends push notifications for devices
+    /// registered with `/private/devicetoken` IMAP METADATA.
+    pub push: bool,
+
+    /// Server creates accounts on first login,
+    /// so no registration is needed.
+    ///
+    /// Detected by the server redirecting `https://<domain>/new` to a `dcaccount:` URL.
+    pub instant_account: bool,
+
+    /// Server has a valid TLS certificate,
+    /// so connections with invalid certificates are not accepted.
+    ///
+    /// Detected by verifying the certificate
+    /// of the IMAP connection or the request to `https://<domain>/new`.
+    pub strict_tls: bool,
+}
+
+impl ChatmailCapabilities {
+    /// Serializes capabilities into a value for [`Config::ConfiguredChatmailCapabilities`].
+    pub(crate) fn to_config_value(self) -> String {
+        let mut caps = Vec::new();
+        if self.push {
+            caps.push("push");
+        }
+        if self.instant_account {
+            caps.push("instant_account");
+        }
+        if self.strict_tls {
+            caps.push("strict_tls");
+        }
+        caps.join(",")
+    }
+
+    /// Parses a [`Config::ConfiguredChatmailCapabilities`] value.
+    ///
+    /// Unknown capabilities are ignored.
+    pub(crate) fn from_config_value(value: &str) -> Self {
+        let mut caps = Self::default();
+        for cap in value.split(',').map(str::trim) {
+            match cap {
+                "push" => caps.push = true,
+                "instant_account" => caps.instant_account = true,
+                "strict_tls" => caps.strict_tls = true,
+                _ => {}
+            }
+        }
+        caps
+    }
+}
+
 /// Get resolver to query MX records.
 ///
 /// We first try to read the system's resolver from `/etc/resolv.conf`.
 /// This does not work at least on some Androids, therefore we fallback
 /// to the default `ResolverConfig` which uses eg. to google's `8.8.8.8` or `8.8.4.4`.
-fn get_resolver() -> Result<TokioResolver> {
+pub(crate) fn get_resolver() -> Result<TokioResolver> {
     if let Ok(resolver) = Resolver::tokio_from_system_conf() {
         return Ok(resolver);
     }
@@ -195,11 +306,13 @@ pub async fn get_provider_info_by_addr(
 /// This function looks up domain in offline database first. If not
 /// found, it queries MX record for the domain and looks up offline
 /// database for MX domains.
+/// Internationalized domains are looked up in ASCII (punycode) form.
 pub async fn get_provider_info(
     context: &Context,
     domain: &str,
     skip_mx: bool,
 ) -> Option<&'static Provider> {
+    let domain = &domain_to_ascii(domain);
     if let Some(provider) = get_provider_by_domain(domain) {
         return Some(provider);
     }
@@ -215,7 +328,7 @@ pub async fn get_provider_info(
 
 /// Finds a provider in offline database based on domain.
 pub fn get_provider_by_domain(domain: &str) -> Option<&'static Provider> {
-    let domain = domain.to_lowercase();
+    let domain = domain_to_ascii(&domain.to_lowercase());
     for (pattern, provider) in PROVIDER_DATA {
         if let Some(suffix) = pattern.strip_prefix('*') {
             // Wildcard domain pattern.
@@ -291,6 +404,28 @@ mod tests {
     use super::*;
     use crate::test_utils::TestContext;
 
+    #[test]
+    fn test_chatmail_capabilities_config_value() {
+        let caps = ChatmailCapabilities {
+            push: true,
+            instant_account: false,
+            strict_tls: true,
+        };
+        assert_eq!(caps.to_config_value(), "push,strict_tls");
+        assert_eq!(
+            ChatmailCapabilities::from_config_value("push,strict_tls"),
+            caps
+        );
+        assert_eq!(
+            ChatmailCapabilities::from_config_value("instant_account, unknown"),
+            ChatmailCapabilities {
+                instant_account: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(ChatmailCapabilities::default().to_config_value(), "");
+    }
+
     #[test]
     fn test_get_provider_by_domain_unexistant() {
         let provider = get_provider_by_domain("unexistant.org");
@@ -6,6 +6,8 @@
 //! It provides [`PushSubscriber`] type
 //! which holds push notification token for the device,
 //! shared by all accounts.
+//! Registrations of further devices and push services of a single account
+//! are managed in the [`registrations`] submodule.
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
@@ -20,6 +22,12 @@
 use crate::context::Context;
 use crate::key::DcKey;
 
+mod registrations;
+mod webpush;
+
+pub(crate) use registrations::sync_push_registrations;
+pub use registrations::{PushRegistration, PushTransport};
+
 /// Manages subscription to Apple Push Notification services.
 ///
 /// This structure is created by account manager and is shared between accounts.
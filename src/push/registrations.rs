@@ -0,0 +1,414 @@
+//! # Push notification registrations of an account.
+//!
+//! Besides the device token shared by all accounts via [`super::PushSubscriber`],
+//! an account may have registrations for several push transports,
+//! e.g. an APNs token of one device and a UnifiedPush endpoint of another.
+//! Registrations are stored in the `push_registrations` table
+//! and synced by the IMAP loop.
+//!
+//! APNs and FCM tokens are stored encrypted in the IMAP METADATA of the server
+//! like the device token of [`super::PushSubscriber`],
+//! the server then notifies the devices via the notification proxy.
+//! Revoked tokens are deleted locally,
+//! the server drops them once the push service reports them as invalid.
+//!
+//! UnifiedPush registrations are sent to the gateway configured with
+//! [`Config::UnifiedPushGateway`],
+//! together with the keys the gateway uses to encrypt notifications,
+//! see [`super::webpush`].
+//! Without a gateway, UnifiedPush registrations are not synced.
+
+use anyhow::{ensure, Context as _, Result};
+use deltachat_derive::{FromSql, ToSql};
+
+use super::webpush::WebPushKeys;
+use crate::config::Config;
+use crate::context::Context;
+use crate::imap::session::Session;
+use crate::log::LogExt;
+use crate::net::http;
+use crate::tools::time;
+
+/// URL to subscribe for heartbeat notifications
+/// if the server does not support push notifications.
+const NOTIFICATION_HEARTBEAT_URL: &str = "https://notifications.delta.chat/register";
+
+/// Interval after which registrations are synced again, 30 days.
+const PUSH_REGISTRATION_REFRESH: i64 = 30 * 24 * 60 * 60;
+
+/// Push notification service a token belongs to.
+#[derive(
+    Debug, Display, Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive, FromSql, ToSql,
+)]
+#[repr(u32)]
+pub enum PushTransport {
+    /// Apple Push Notification service device token.
+    Apns = 1,
+
+    /// Firebase Cloud Messaging registration token.
+    Fcm = 2,
+
+    /// UnifiedPush endpoint URL.
+    UnifiedPush = 3,
+}
+
+/// Registration of a device for push notifications.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PushRegistration {
+    /// Database ID of the registration.
+    pub id: u32,
+
+    /// Push notification service.
+    pub transport: PushTransport,
+
+    /// Device token or UnifiedPush endpoint URL.
+    pub token: String,
+
+    /// Name of the device set by the UI, may be empty.
+    pub device_name: String,
+
+    /// Timestamp of the creation of the registration.
+    pub created_timestamp: i64,
+
+    /// Timestamp of the last sync of the registration with the server,
+    /// 0 if the registration is not synced yet.
+    pub synced_timestamp: i64,
+}
+
+impl Context {
+    /// Adds a push notification registration for a device
+    /// or updates the device name of an existing registration with the same token.
+    ///
+    /// For UnifiedPush, `token` is the endpoint URL received from the distributor.
+    /// A new key pair is generated for the endpoint
+    /// and sent to the gateway together with the endpoint,
+    /// use [`Context::decrypt_push_payload`] to decrypt the received notifications.
+    ///
+    /// The registration is sent to the server or the UnifiedPush gateway by the IMAP loop.
+    /// Returns the ID of the registration.
+    pub async fn add_push_registration(
+        &self,
+        transport: PushTransport,
+        token: &str,
+        device_name: &str,
+    ) -> Result<u32> {
+        let token = token.trim();
+        ensure!(!token.is_empty(), "Push token is empty");
+        if transport == PushTransport::UnifiedPush {
+            ensure!(
+                token.starts_with("https://"),
+                "UnifiedPush endpoint must be an HTTPS URL"
+            );
+        }
+        let (private_key, auth_secret) = match transport {
+            PushTransport::UnifiedPush => {
+                let keys = WebPushKeys::generate();
+                (
+                    Some(keys.private_key_bytes()),
+                    Some(keys.auth_secret().to_vec()),
+                )
+            }
+            PushTransport::Apns | PushTransport::Fcm => (None, None),
+        };
+        let id = self
+            .sql
+            .call_write(|conn| {
+                // Keys of existing registrations are kept,
+                // the gateway may already use them.
+                let id: u32 = conn.query_row(
+                    "INSERT INTO push_registrations
+                     (transport, token, device_name, created_timestamp, webpush_private_key, webpush_auth)
+                     VALUES (?, ?, ?, ?, ?, ?)
+                     ON CONFLICT (transport, token) DO UPDATE
+                     SET device_name=excluded.device_name,
+                         synced_timestamp=IIF(revoked, 0, synced_timestamp),
+                         revoked=0,
+                         webpush_private_key=IFNULL(webpush_private_key, excluded.webpush_private_key),
+                         webpush_auth=IFNULL(webpush_auth, excluded.webpush_auth)
+                     RETURNING id",
+                    (
+                        transport,
+                        token,
+                        device_name,
+                        time(),
+                        private_key,
+                        auth_secret,
+                    ),
+                    |row| row.get(0),
+                )?;
+                Ok(id)
+            })
+            .await?;
+        info!(self, "Added {transport} push registration {id}.");
+        self.scheduler.interrupt_inbox().await;
+        Ok(id)
+    }
+
+    /// Returns the push notification registrations of the account,
+    /// excluding revoked ones.
+    pub async fn get_push_registrations(&self) -> Result<Vec<PushRegistration>> {
+        self.sql
+            .query_map(
+                "SELECT id, transport, token, device_name, created_timestamp, synced_timestamp
+                 FROM push_registrations WHERE revoked=0 ORDER BY id",
+                (),
+                |row| {
+                    Ok(PushRegistration {
+                        id: row.get(0)?,
+                        transport: row.get(1)?,
+                        token: row.get(2)?,
+                        device_name: row.get(3)?,
+                        created_timestamp: row.get(4)?,
+                        synced_timestamp: row.get(5)?,
+                    })
+                },
+                |rows| {
+                    rows.collect::<std::result::Result<Vec<_>, _>>()
+                        .map_err(Into::into)
+                },
+            )
+            .await
+    }
+
+    /// Revokes a push notification registration.
+    ///
+    /// The registration is unregistered at the UnifiedPush gateway by the IMAP loop
+    /// and deleted afterwards.
+    pub async fn revoke_push_registration(&self, id: u32) -> Result<()> {
+        let updated = self
+            .sql
+            .execute(
+                "UPDATE push_registrations SET revoked=1, synced_timestamp=0
+                 WHERE id=? AND revoked=0",
+                (id,),
+            )
+            .await?;
+        ensure!(updated > 0, "No push registration {id}");
+        info!(self, "Revoked push registration {id}.");
+        self.scheduler.interrupt_inbox().await;
+        Ok(())
+    }
+
+    /// Decrypts a notification received for the UnifiedPush registration `id`.
+    ///
+    /// Returns the decrypted body of the notification.
+    pub async fn decrypt_push_payload(&self, id: u32, payload: &[u8]) -> Result<Vec<u8>> {
+        let keys = load_webpush_keys(self, id)
+            .await?
+            .with_context(|| format!("Push registration {id} has no UnifiedPush keys"))?;
+        keys.decrypt(payload)
+    }
+}
+
+async fn load_webpush_keys(context: &Context, id: u32) -> Result<Option<WebPushKeys>> {
+    let Some((private_key, auth_secret)) = context
+        .sql
+        .query_row_optional(
+            "SELECT webpush_private_key, webpush_auth FROM push_registrations
+             WHERE id=? AND webpush_private_key IS NOT NULL AND webpush_auth IS NOT NULL",
+            (id,),
+            |row| {
+                let private_key: Vec<u8> = row.get(0)?;
+                let auth_secret: Vec<u8> = row.get(1)?;
+                Ok((private_key, auth_secret))
+            },
+        )
+        .await?
+    else {
+        return Ok(None);
+    };
+    Ok(Some(WebPushKeys::from_bytes(&private_key, &auth_secret)?))
+}
+
+/// Registers new and unregisters revoked push registrations.
+///
+/// Registrations are synced again after [`PUSH_REGISTRATION_REFRESH`]
+/// so that the server does not expire them.
+/// Failures are logged and retried next time without affecting other registrations.
+pub(crate) async fn sync_push_registrations(
+    context: &Context,
+    session: &mut Session,
+) -> Result<()> {
+    let pending = context
+        .sql
+        .query_map(
+            "SELECT id, transport, token, revoked FROM push_registrations
+             WHERE synced_timestamp<=?",
+            (time().saturating_sub(PUSH_REGISTRATION_REFRESH),),
+            |row| {
+                let id: u32 = row.get(0)?;
+                let transport: PushTransport = row.get(1)?;
+                let token: String = row.get(2)?;
+                let revoked: bool = row.get(3)?;
+                Ok((id, transport, token, revoked))
+            },
+            |rows| {
+                rows.collect::<std::result::Result<Vec<_>, _>>()
+                    .map_err(Into::into)
+            },
+        )
+        .await?;
+    let gateway = context
+        .get_config(Config::UnifiedPushGateway)
+        .await?
+        .filter(|gateway| !gateway.is_empty());
+    for (id, transport, token, revoked) in pending {
+        let synced = match transport {
+            PushTransport::Apns | PushTransport::Fcm => {
+                register_device_token(context, session, id, transport, &token, revoked).await
+            }
+            PushTransport::UnifiedPush => {
+                register_unifiedpush_endpoint(context, gateway.as_deref(), id, &token, revoked)
+                    .await
+            }
+        };
+        match synced {
+            Ok(true) => {}
+            Ok(false) => continue,
+            Err(err) => {
+                warn!(context, "Failed to sync push registration {id}: {err:#}.");
+                continue;
+            }
+        }
+        let res = if revoked {
+            context
+                .sql
+                .execute("DELETE FROM push_registrations WHERE id=?", (id,))
+                .await
+        } else {
+            context
+                .sql
+                .execute(
+                    "UPDATE push_registrations SET synced_timestamp=? WHERE id=?",
+                    (time(), id),
+                )
+                .await
+        };
+        res.log_err(context).ok();
+        info!(context, "Synced push registration {id}.");
+    }
+    Ok(())
+}
+
+/// Stores an APNs or FCM device token on the server
+/// in the same way as the device token of [`super::PushSubscriber`].
+///
+/// Returns true if the registration is synced.
+async fn register_device_token(
+    context: &Context,
+    session: &mut Session,
+    id: u32,
+    transport: PushTransport,
+    token: &str,
+    revoked: bool,
+) -> Result<bool> {
+    if revoked {
+        // There is no way to remove a single token from the server,
+        // it is dropped once the push service reports it as invalid.
+        return Ok(true);
+    }
+    if session.can_metadata() && session.can_push() {
+        return session.set_device_token_metadata(context, token).await;
+    }
+    if transport == PushTransport::Apns {
+        // Subscribe for heartbeat notifications as a fallback.
+        let body = serde_json::json!({ "token": token }).to_string();
+        return http::post_string(context, NOTIFICATION_HEARTBEAT_URL, body).await;
+    }
+    info!(
+        context,
+        "Server does not support push notifications, not registering {transport} registration {id}."
+    );
+    Ok(false)
+}
+
+/// Registers or unregisters a UnifiedPush endpoint at the configured gateway.
+///
+/// Returns true if the registration is synced.
+async fn register_unifiedpush_endpoint(
+    context: &Context,
+    gateway: Option<&str>,
+    id: u32,
+    endpoint: &str,
+    revoked: bool,
+) -> Result<bool> {
+    let Some(gateway) = gateway else {
+        // Without a gateway, nobody sends notifications to the endpoint.
+        return Ok(revoked);
+    };
+    let mut body = serde_json::json!({ "endpoint": endpoint });
+    let action = match revoked {
+        true => "unregister",
+        false => {
+            let keys = load_webpush_keys(context, id)
+                .await?
+                .with_context(|| format!("Push registration {id} has no UnifiedPush keys"))?;
+            body["p256dh"] = keys.p256dh().into();
+            body["auth"] = keys.auth().into();
+            "register"
+        }
+    };
+    let url = format!("{}/{action}", gateway.trim_end_matches('/'));
+    let synced = http::post_string(context, &url, body.to_string()).await?;
+    if !synced {
+        warn!(
+            context,
+            "UnifiedPush gateway rejected to {action} push registration {id}."
+        );
+    }
+    Ok(synced)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestContext;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_push_registrations() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        assert!(t
+            .add_push_registration(PushTransport::UnifiedPush, "http://example.org/up", "")
+            .await
+            .is_err());
+        let id1 = t
+            .add_push_registration(PushTransport::Apns, "0155b93b7eb867a0", "iPhone")
+            .await?;
+        let id2 = t
+            .add_push_registration(PushTransport::UnifiedPush, "https://example.org/up", "")
+            .await?;
+        assert_ne!(id1, id2);
+        assert!(load_webpush_keys(&t, id1).await?.is_none());
+        let keys = load_webpush_keys(&t, id2).await?.unwrap();
+        assert!(t.decrypt_push_payload(id1, b"payload").await.is_err());
+
+        // Adding the same token again only updates the device name.
+        let id = t
+            .add_push_registration(PushTransport::Apns, "0155b93b7eb867a0", "iPad")
+            .await?;
+        assert_eq!(id, id1);
+        t.add_push_registration(PushTransport::UnifiedPush, "https://example.org/up", "")
+            .await?;
+        let new_keys = load_webpush_keys(&t, id2).await?.unwrap();
+        assert_eq!(new_keys.public_key(), keys.public_key());
+        let registrations = t.get_push_registrations().await?;
+        assert_eq!(registrations.len(), 2);
+        assert_eq!(registrations[0].device_name, "iPad");
+        assert_eq!(registrations[0].synced_timestamp, 0);
+        assert_eq!(registrations[1].transport, PushTransport::UnifiedPush);
+
+        t.revoke_push_registration(id1).await?;
+        assert!(t.revoke_push_registration(id1).await.is_err());
+        let registrations = t.get_push_registrations().await?;
+        assert_eq!(registrations.len(), 1);
+        assert_eq!(registrations[0].id, id2);
+
+        // Registering a revoked token again restores it.
+        let id = t
+            .add_push_registration(PushTransport::Apns, "0155b93b7eb867a0", "iPad")
+            .await?;
+        assert_eq!(id, id1);
+        assert_eq!(t.get_push_registrations().await?.len(), 2);
+        Ok(())
+    }
+}
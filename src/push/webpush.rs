@@ -0,0 +1,271 @@
+//! # Web Push message encryption for UnifiedPush.
+//!
+//! UnifiedPush endpoints are public URLs,
+//! so notifications sent to them are encrypted
+//! according to [RFC 8291](https://datatracker.ietf.org/doc/html/rfc8291)
+//! using the `aes128gcm` content coding of [RFC 8188](https://datatracker.ietf.org/doc/html/rfc8188).
+//! Each UnifiedPush registration has its own P-256 key pair and authentication secret.
+//! The public key and the secret are sent to the push gateway on registration,
+//! the private key never leaves the device.
+
+use aes_gcm::aead::Aead;
+use aes_gcm::{Aes128Gcm, KeyInit, Nonce};
+use anyhow::{bail, ensure, format_err, Context as _, Result};
+use base64::Engine as _;
+use hkdf::Hkdf;
+use p256::ecdh::diffie_hellman;
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use p256::{PublicKey, SecretKey};
+use rand::{thread_rng, RngCore};
+use sha2::Sha256;
+
+/// Length of an uncompressed P-256 public key.
+const PUBLIC_KEY_LEN: usize = 65;
+
+/// Length of the authentication secret.
+const AUTH_SECRET_LEN: usize = 16;
+
+/// Length of the salt in the `aes128gcm` header.
+const SALT_LEN: usize = 16;
+
+/// Length of the AES-GCM authentication tag.
+const TAG_LEN: usize = 16;
+
+/// Key material of a UnifiedPush registration.
+#[derive(Debug, Clone)]
+pub(crate) struct WebPushKeys {
+    private_key: SecretKey,
+    auth_secret: [u8; AUTH_SECRET_LEN],
+}
+
+impl WebPushKeys {
+    /// Generates a new key pair and authentication secret.
+    pub(crate) fn generate() -> Self {
+        let mut rng = thread_rng();
+        let mut auth_secret = [0; AUTH_SECRET_LEN];
+        rng.fill_bytes(&mut auth_secret);
+        Self {
+            private_key: SecretKey::random(&mut rng),
+            auth_secret,
+        }
+    }
+
+    /// Loads keys stored with [`Self::private_key_bytes`] and [`Self::auth_secret`].
+    pub(crate) fn from_bytes(private_key: &[u8], auth_secret: &[u8]) -> Result<Self> {
+        let private_key = SecretKey::from_slice(private_key)
+            .map_err(|_| format_err!("Invalid Web Push private key"))?;
+        let auth_secret = auth_secret
+            .try_into()
+            .context("Invalid Web Push authentication secret")?;
+        Ok(Self {
+            private_key,
+            auth_secret,
+        })
+    }
+
+    pub(crate) fn private_key_bytes(&self) -> Vec<u8> {
+        self.private_key.to_bytes().to_vec()
+    }
+
+    pub(crate) fn auth_secret(&self) -> &[u8] {
+        &self.auth_secret
+    }
+
+    /// Returns the uncompressed public key.
+    pub(crate) fn public_key(&self) -> Vec<u8> {
+        self.private_key
+            .public_key()
+            .to_encoded_point(false)
+            .as_bytes()
+            .to_vec()
+    }
+
+    /// Returns the public key as unpadded base64url string
+    /// as used in the `p256dh` field of push subscriptions.
+    pub(crate) fn p256dh(&self) -> String {
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(self.public_key())
+    }
+
+    /// Returns the authentication secret as unpadded base64url string
+    /// as used in the `auth` field of push subscriptions.
+    pub(crate) fn auth(&self) -> String {
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(self.auth_secret)
+    }
+
+    /// Decrypts a push message body encrypted with the `aes128gcm` content coding.
+    pub(crate) fn decrypt(&self, payload: &[u8]) -> Result<Vec<u8>> {
+        // Header: salt, record size, key ID length and the sender public key as key ID.
+        let header_len = SALT_LEN + 4 + 1 + PUBLIC_KEY_LEN;
+        ensure!(
+            payload.len() > header_len + TAG_LEN,
+            "Push message is too short"
+        );
+        let salt = &payload[..SALT_LEN];
+        let record_size = u32::from_be_bytes(payload[SALT_LEN..SALT_LEN + 4].try_into()?);
+        let key_id_len = usize::from(payload[SALT_LEN + 4]);
+        ensure!(
+            key_id_len == PUBLIC_KEY_LEN,
+            "Unexpected key ID length {key_id_len} in push message"
+        );
+        let sender_public_key = &payload[SALT_LEN + 5..header_len];
+        let ciphertext = &payload[header_len..];
+        ensure!(
+            ciphertext.len() <= record_size as usize,
+            "Push messages with multiple records are not supported"
+        );
+
+        let shared_secret = ecdh(&self.private_key, sender_public_key)?;
+        let (cek, nonce) = derive_key_and_nonce(
+            &shared_secret,
+            &self.public_key(),
+            sender_public_key,
+            &self.auth_secret,
+            salt,
+        )?;
+        let mut plaintext = Aes128Gcm::new(&cek.into())
+            .decrypt(Nonce::from_slice(&nonce), ciphertext)
+            .map_err(|_| format_err!("Failed to decrypt push message"))?;
+
+        // Remove the padding, the last record ends with the delimiter 2 followed by zeros.
+        while plaintext.last() == Some(&0) {
+            plaintext.pop();
+        }
+        match plaintext.pop() {
+            Some(2) => Ok(plaintext),
+            _ => bail!("Invalid padding in push message"),
+        }
+    }
+}
+
+/// Computes the ECDH shared secret of the own private key and the public key of the peer.
+fn ecdh(private_key: &SecretKey, peer_public_key: &[u8]) -> Result<Vec<u8>> {
+    let peer_public_key = PublicKey::from_sec1_bytes(peer_public_key)
+        .map_err(|_| format_err!("Invalid public key in push message"))?;
+    let shared_secret =
+        diffie_hellman(private_key.to_nonzero_scalar(), peer_public_key.as_affine());
+    Ok(shared_secret.raw_secret_bytes().to_vec())
+}
+
+/// Derives the content encryption key and nonce from the ECDH shared secret.
+fn derive_key_and_nonce(
+    shared_secret: &[u8],
+    receiver_public_key: &[u8],
+    sender_public_key: &[u8],
+    auth_secret: &[u8],
+    salt: &[u8],
+) -> Result<([u8; 16], [u8; 12])> {
+    let mut key_info = b"WebPush: info\0".to_vec();
+    key_info.extend_from_slice(receiver_public_key);
+    key_info.extend_from_slice(sender_public_key);
+    let mut ikm = [0; 32];
+    Hkdf::<Sha256>::new(Some(auth_secret), shared_secret)
+        .expand(&key_info, &mut ikm)
+        .map_err(|_| format_err!("Failed to derive push message key"))?;
+
+    let hkdf = Hkdf::<Sha256>::new(Some(salt), &ikm);
+    let mut cek = [0; 16];
+    hkdf.expand(b"Content-Encoding: aes128gcm\0", &mut cek)
+        .map_err(|_| format_err!("Failed to derive push message key"))?;
+    let mut nonce = [0; 12];
+    hkdf.expand(b"Content-Encoding: nonce\0", &mut nonce)
+        .map_err(|_| format_err!("Failed to derive push message nonce"))?;
+    Ok((cek, nonce))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encrypts a push message like a push gateway does.
+    fn encrypt(receiver_public_key: &[u8], auth_secret: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        let mut rng = thread_rng();
+        let sender_key = SecretKey::random(&mut rng);
+        let sender_public_key = sender_key.public_key().to_encoded_point(false);
+        let mut salt = [0; SALT_LEN];
+        rng.fill_bytes(&mut salt);
+
+        let shared_secret = ecdh(&sender_key, receiver_public_key).unwrap();
+        let (cek, nonce) = derive_key_and_nonce(
+            &shared_secret,
+            receiver_public_key,
+            sender_public_key.as_bytes(),
+            auth_secret,
+            &salt,
+        )
+        .unwrap();
+        let mut padded = plaintext.to_vec();
+        padded.push(2);
+        let ciphertext = Aes128Gcm::new(&cek.into())
+            .encrypt(Nonce::from_slice(&nonce), padded.as_slice())
+            .unwrap();
+
+        let mut payload = salt.to_vec();
+        payload.extend_from_slice(&4096u32.to_be_bytes());
+        payload.push(PUBLIC_KEY_LEN as u8);
+        payload.extend_from_slice(sender_public_key.as_bytes());
+        payload.extend_from_slice(&ciphertext);
+        payload
+    }
+
+    #[test]
+    fn test_webpush_decrypt() -> Result<()> {
+        let keys = WebPushKeys::generate();
+        assert_eq!(keys.public_key().len(), PUBLIC_KEY_LEN);
+        let keys = WebPushKeys::from_bytes(&keys.private_key_bytes(), keys.auth_secret())?;
+
+        let payload = encrypt(&keys.public_key(), keys.auth_secret(), b"New messages");
+        assert_eq!(keys.decrypt(&payload)?, b"New messages");
+
+        // Other keys cannot decrypt the message.
+        let other_keys = WebPushKeys::generate();
+        assert!(other_keys.decrypt(&payload).is_err());
+        assert!(keys.decrypt(&payload[..50]).is_err());
+        Ok(())
+    }
+    /// Tests decryption with the example of
+    /// [RFC 8291 Appendix A](https://datatracker.ietf.org/doc/html/rfc8291#appendix-A).
+    #[test]
+    fn test_webpush_rfc8291_vector() -> Result<()> {
+        let b64 = |s: &str| {
+            base64::engine::general_purpose::URL_SAFE_NO_PAD
+                .decode(s)
+                .unwrap()
+        };
+        let keys = WebPushKeys::from_bytes(
+            &b64("q1dXpw3UpT5VOmu_cf_v6ih07Aems3njxI-JWgLcM94"),
+            &b64("BTBZMqHH6r4Tts7J_aSIgg"),
+        )?;
+        assert_eq!(
+            keys.p256dh(),
+            "BCVxsr7N_eNgVRqvHtD0zTZsEc6-VV-JvLexhqUzORcxaOzi6-AYWXvTBHm4bjyPjs7Vd8pZGH6SRpkNtoIAiw4"
+        );
+        assert_eq!(keys.auth(), "BTBZMqHH6r4Tts7J_aSIgg");
+
+        let payload = b64(
+            "DGv6ra1nlYgDCS1FRnbzlwAAEABBBP4z9KsN6nGRTbVYI_c7VJSPQTBtkgcy27mlmlMoZIIgDll6e3vCYLocInmYWAmS6TlzAC8wEqKK6PBru3jl7A_yl95bQpu6cVPTpK4Mqgkf1CXztLVBSt2Ks3oZwbuwXPXLWyouBWLVWGNWQexSgSxsj_Qulcy4a-fN",
+        );
+
+        // Intermediate values of the key derivation.
+        let sender_public_key = &payload[SALT_LEN + 5..SALT_LEN + 5 + PUBLIC_KEY_LEN];
+        let shared_secret = ecdh(&keys.private_key, sender_public_key)?;
+        assert_eq!(
+            shared_secret,
+            b64("kyrL1jIIOHEzg3sM2ZWRHDRB62YACZhhSlknJ672kSs")
+        );
+        let (cek, nonce) = derive_key_and_nonce(
+            &shared_secret,
+            &keys.public_key(),
+            sender_public_key,
+            keys.auth_secret(),
+            &payload[..SALT_LEN],
+        )?;
+        assert_eq!(cek.to_vec(), b64("oIhVW04MRdy2XN9CiKLxTg"));
+        assert_eq!(nonce.to_vec(), b64("4h_95klXJ5E_qnoN"));
+
+        assert_eq!(
+            keys.decrypt(&payload)?,
+            b"When I grow up, I want to be a watermelon"
+        );
+        Ok(())
+    }
+}
@@ -2,10 +2,12 @@
 
 mod dclogin_scheme;
 use std::collections::BTreeMap;
+use std::net::SocketAddr;
 
 use anyhow::{anyhow, bail, ensure, Context as _, Result};
 pub use dclogin_scheme::LoginOptions;
 use deltachat_contact_tools::{addr_normalize, may_be_valid_addr, ContactAddress};
+use iroh::{NodeAddr, NodeId};
 use once_cell::sync::Lazy;
 use percent_encoding::{percent_decode_str, percent_encode, NON_ALPHANUMERIC};
 use serde::Deserialize;
@@ -22,8 +24,9 @@
 use crate::net::http::post_empty;
 use crate::net::proxy::{ProxyConfig, DEFAULT_SOCKS_PORT};
 use crate::peerstate::Peerstate;
+use crate::securejoin::invite_words::{split_invite_code, InviteWords};
 use crate::token;
-use crate::tools::validate_id;
+use crate::tools::{time, validate_id};
 
 const OPENPGP4FPR_SCHEME: &str = "OPENPGP4FPR:"; // yes: uppercase
 const IDELTACHAT_SCHEME: &str = "https://i.delta.chat/#";
@@ -60,6 +63,9 @@ pub enum Qr {
 
         /// Authentication code.
         authcode: String,
+
+        /// Address of the inviter's device on the local network, if advertised.
+        node_addr: Option<NodeAddr>,
     },
 
     /// Ask the user whether to join the group.
@@ -81,6 +87,9 @@ pub enum Qr {
 
         /// Authentication code.
         authcode: String,
+
+        /// Address of the inviter's device on the local network, if advertised.
+        node_addr: Option<NodeAddr>,
     },
 
     /// Contact fingerprint is verified.
@@ -299,6 +308,8 @@ pub async fn check_qr(context: &Context, qr: &str) -> Result<Qr> {
         decode_matmsg(context, qr).await?
     } else if qr.starts_with(VCARD_SCHEME) {
         decode_vcard(context, qr).await?
+    } else if let Some((addr, words)) = split_invite_code(qr) {
+        decode_invite_words(context, addr, &words).await?
     } else if let Ok(url) = url::Url::parse(qr) {
         match url.scheme() {
             "socks5" => Qr::Proxy {
@@ -425,6 +436,9 @@ async fn decode_openpgp(context: &Context, qr: &str) -> Result<Qr> {
         .get("x")
         .filter(|&s| validate_id(s))
         .map(|s| s.to_string());
+    let node_addr = param
+        .get("p")
+        .and_then(|node_id| decode_node_addr(node_id, param.get("d").copied()));
 
     let grpname = if grpid.is_some() {
         if let Some(encoded_name) = param.get("g") {
@@ -485,6 +499,7 @@ async fn decode_openpgp(context: &Context, qr: &str) -> Result<Qr> {
                     fingerprint,
                     invitenumber,
                     authcode,
+                    node_addr,
                 })
             }
         } else if context.is_self_addr(&addr).await? {
@@ -509,6 +524,7 @@ async fn decode_openpgp(context: &Context, qr: &str) -> Result<Qr> {
                 fingerprint,
                 invitenumber,
                 authcode,
+                node_addr,
             })
         }
     } else if let Some(addr) = addr {
@@ -535,6 +551,21 @@ async fn decode_openpgp(context: &Context, qr: &str) -> Result<Qr> {
     }
 }
 
+/// Decodes the inviter's local network address
+/// from the `p=` (node ID) and `d=` (comma-separated direct addresses) parameters.
+///
+/// Invalid addresses are ignored as the handshake can still be done by email.
+fn decode_node_addr(node_id: &str, direct_addresses: Option<&str>) -> Option<NodeAddr> {
+    let node_id: NodeId = node_id.parse().ok()?;
+    let direct_addresses = percent_decode_str(direct_addresses.unwrap_or_default())
+        .decode_utf8()
+        .ok()?
+        .split(',')
+        .filter_map(|addr| addr.parse::<SocketAddr>().ok())
+        .collect::<Vec<_>>();
+    Some(NodeAddr::from_parts(node_id, None, direct_addresses))
+}
+
 /// scheme: `https://i.delta.chat[/]#FINGERPRINT&a=ADDR[&OPTIONAL_PARAMS]`
 async fn decode_ideltachat(context: &Context, prefix: &str, qr: &str) -> Result<Qr> {
     let qr = qr.replacen(prefix, OPENPGP4FPR_SCHEME, 1);
@@ -935,6 +966,29 @@ pub async fn from_address(
     }
 }
 
+/// Decodes a word-encoded setup-contact invite code
+/// as generated by [`crate::securejoin::get_securejoin_invite_words`].
+async fn decode_invite_words(context: &Context, addr: &str, words: &[&str]) -> Result<Qr> {
+    let invite = InviteWords::decode(words)?;
+    ensure!(!invite.is_expired(time()), "Invite code has expired");
+    let addr = ContactAddress::new(&normalize_address(addr)?)?;
+    ensure!(
+        !context.is_self_addr(&addr).await?,
+        "Cannot use own invite code"
+    );
+    let (contact_id, _) =
+        Contact::add_or_lookup(context, "", &addr, Origin::UnhandledSecurejoinQrScan)
+            .await
+            .with_context(|| format!("failed to add or lookup contact for address {addr:?}"))?;
+    Ok(Qr::AskVerifyContact {
+        contact_id,
+        invitenumber: invite.invitenumber_token(),
+        authcode: invite.auth_token(),
+        fingerprint: invite.fingerprint,
+        node_addr: None,
+    })
+}
+
 /// URL decodes a given address, does basic email validation on the result.
 fn normalize_address(addr: &str) -> Result<String> {
     // urldecoding is needed at least for OPENPGP4FPR but should not hurt in the other cases
@@ -1300,6 +1354,41 @@ async fn test_decode_openpgp_secure_join() -> Result<()> {
         Ok(())
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_decode_secure_join_node_addr() -> Result<()> {
+        let ctx = TestContext::new().await;
+        let node_id = iroh::SecretKey::generate(rand::rngs::OsRng).public();
+
+        let qr = check_qr(
+            &ctx.ctx,
+            &format!("https://i.delta.chat/#79252762C34C5096AF57958F4FC3D21A81B0F0A7&a=cli%40deltachat.de&n=&i=TbnwJ6lSvD5&s=0ejvbdFSQxB&p={node_id}&d=192.168.1.2%3A4433%2C%5Bfe80%3A%3A1%5D%3A4433%2Cfoo")
+        ).await?;
+        let Qr::AskVerifyContact { node_addr, .. } = qr else {
+            bail!("Wrong QR code type");
+        };
+        let node_addr = node_addr.context("No node address")?;
+        assert_eq!(node_addr.node_id, node_id);
+        assert_eq!(node_addr.direct_addresses.len(), 2);
+        assert!(node_addr
+            .direct_addresses
+            .contains(&"192.168.1.2:4433".parse()?));
+
+        // Invalid node IDs are ignored.
+        let qr = check_qr(
+            &ctx.ctx,
+            "https://i.delta.chat/#79252762C34C5096AF57958F4FC3D21A81B0F0A7&a=cli%40deltachat.de&n=&i=TbnwJ6lSvD5&s=0ejvbdFSQxB&p=invalid"
+        ).await?;
+        assert!(matches!(
+            qr,
+            Qr::AskVerifyContact {
+                node_addr: None,
+                ..
+            }
+        ));
+
+        Ok(())
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_decode_openpgp_fingerprint() -> Result<()> {
         let ctx = TestContext::new().await;
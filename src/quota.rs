@@ -41,20 +41,80 @@ pub struct QuotaInfo {
     /// set to `Ok()` for valid quota information.
     pub(crate) recent: Result<BTreeMap<String, Vec<QuotaResource>>>,
 
+    /// Watched folders for each quota root in `recent`.
+    pub(crate) folders: BTreeMap<String, Vec<String>>,
+
     /// When the structure was modified.
     pub(crate) modified: tools::Time,
 }
 
+/// Usage of a single quota resource.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuotaResourceUsage {
+    /// Name of the resource, e.g. `STORAGE` or `MESSAGE`.
+    pub name: String,
+
+    /// Current usage, in KiB for `STORAGE`.
+    pub usage: u64,
+
+    /// Limit of the resource, in KiB for `STORAGE`.
+    pub limit: u64,
+
+    /// Usage in percent of the limit.
+    pub percentage: u64,
+}
+
+/// Usage of a quota root.
+///
+/// Servers usually have a single quota root for the whole account,
+/// but some have separate roots e.g. for some folders.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuotaRootUsage {
+    /// Name of the quota root, may be empty.
+    pub name: String,
+
+    /// Watched folders counted against this root.
+    pub folders: Vec<String>,
+
+    /// Resources limited by this root.
+    pub resources: Vec<QuotaResourceUsage>,
+}
+
+impl From<&QuotaResource> for QuotaResourceUsage {
+    fn from(resource: &QuotaResource) -> Self {
+        use async_imap::types::QuotaResourceName::*;
+        let name = match &resource.name {
+            Storage => "STORAGE".to_string(),
+            Message => "MESSAGE".to_string(),
+            Atom(name) => name.to_string(),
+        };
+        Self {
+            name,
+            usage: resource.usage,
+            limit: resource.limit,
+            percentage: resource.get_usage_percentage(),
+        }
+    }
+}
+
+type QuotaRoots = BTreeMap<String, Vec<QuotaResource>>;
+
+/// Returns usage of the quota roots and the folders belonging to each root.
 async fn get_unique_quota_roots_and_usage(
     session: &mut ImapSession,
     folders: Vec<String>,
-) -> Result<BTreeMap<String, Vec<QuotaResource>>> {
-    let mut unique_quota_roots: BTreeMap<String, Vec<QuotaResource>> = BTreeMap::new();
+) -> Result<(QuotaRoots, BTreeMap<String, Vec<String>>)> {
+    let mut unique_quota_roots: QuotaRoots = BTreeMap::new();
+    let mut root_folders: BTreeMap<String, Vec<String>> = BTreeMap::new();
     for folder in folders {
         let (quota_roots, quotas) = &session.get_quota_root(&folder).await?;
         // if there are new quota roots found in this imap folder, add them to the list
         for qr_entries in quota_roots {
             for quota_root_name in &qr_entries.quota_root_names {
+                root_folders
+                    .entry(quota_root_name.clone())
+                    .or_default()
+                    .push(folder.clone());
                 // the quota for that quota root
                 let quota: Quota = quotas
                     .iter()
@@ -69,7 +129,7 @@ async fn get_unique_quota_roots_and_usage(
             }
         }
     }
-    Ok(unique_quota_roots)
+    Ok((unique_quota_roots, root_folders))
 }
 
 fn get_highest_usage<'t>(
@@ -97,8 +157,20 @@ fn get_highest_usage<'t>(
 
 /// Checks if a quota warning is needed.
 pub fn needs_quota_warning(curr_percentage: u64, warned_at_percentage: u64) -> bool {
-    (curr_percentage >= QUOTA_WARN_THRESHOLD_PERCENTAGE
-        && warned_at_percentage < QUOTA_WARN_THRESHOLD_PERCENTAGE)
+    needs_quota_warning_at(
+        curr_percentage,
+        warned_at_percentage,
+        QUOTA_WARN_THRESHOLD_PERCENTAGE,
+    )
+}
+
+/// Checks if a quota warning is needed for the warning threshold `warn_percentage`.
+fn needs_quota_warning_at(
+    curr_percentage: u64,
+    warned_at_percentage: u64,
+    warn_percentage: u64,
+) -> bool {
+    (curr_percentage >= warn_percentage && warned_at_percentage < warn_percentage)
         || (curr_percentage >= QUOTA_ERROR_THRESHOLD_PERCENTAGE
             && warned_at_percentage < QUOTA_ERROR_THRESHOLD_PERCENTAGE)
 }
@@ -123,19 +195,27 @@ pub(crate) async fn quota_needs_update(&self, ratelimit_secs: u64) -> bool {
     /// in case for some providers the quota is always at ~100%
     /// and new space is allocated as needed.
     pub(crate) async fn update_recent_quota(&self, session: &mut ImapSession) -> Result<()> {
-        let quota = if session.can_check_quota() {
+        let (quota, folders) = if session.can_check_quota() {
             let folders = get_watched_folders(self).await?;
-            get_unique_quota_roots_and_usage(session, folders).await
+            match get_unique_quota_roots_and_usage(session, folders).await {
+                Ok((quota, folders)) => (Ok(quota), folders),
+                Err(err) => (Err(err), BTreeMap::new()),
+            }
         } else {
-            Err(anyhow!(stock_str::not_supported_by_provider(self).await))
+            (
+                Err(anyhow!(stock_str::not_supported_by_provider(self).await)),
+                BTreeMap::new(),
+            )
         };
 
         if let Ok(quota) = &quota {
             match get_highest_usage(quota) {
                 Ok((highest, _, _)) => {
-                    if needs_quota_warning(
+                    let warn_percentage = self.get_config_u64(Config::QuotaWarnThreshold).await?;
+                    if needs_quota_warning_at(
                         highest,
                         self.get_config_int(Config::QuotaExceeding).await? as u64,
+                        warn_percentage,
                     ) {
                         self.set_config_internal(
                             Config::QuotaExceeding,
@@ -145,7 +225,9 @@ pub(crate) async fn update_recent_quota(&self, session: &mut ImapSession) -> Res
                         let mut msg =
                             Message::new_text(stock_str::quota_exceeding(self, highest).await);
                         add_device_msg_with_importance(self, None, Some(&mut msg), true).await?;
-                    } else if highest <= QUOTA_ALLCLEAR_PERCENTAGE {
+                    } else if highest
+                        <= QUOTA_ALLCLEAR_PERCENTAGE.min(warn_percentage.saturating_sub(5))
+                    {
                         self.set_config_internal(Config::QuotaExceeding, None)
                             .await?;
                     }
@@ -156,12 +238,38 @@ pub(crate) async fn update_recent_quota(&self, session: &mut ImapSession) -> Res
 
         *self.quota.write().await = Some(QuotaInfo {
             recent: quota,
+            folders,
             modified: tools::Time::now(),
         });
 
         self.emit_event(EventType::ConnectivityChanged);
         Ok(())
     }
+
+    /// Returns the most recently loaded quota usage, broken down by quota roots.
+    ///
+    /// Returns `None` if quota was not loaded yet
+    /// and an error if the provider does not support quota or loading failed.
+    pub async fn get_quota_usage(&self) -> Result<Option<Vec<QuotaRootUsage>>> {
+        let quota = self.quota.read().await;
+        let Some(quota) = &*quota else {
+            return Ok(None);
+        };
+        let roots = match &quota.recent {
+            Ok(roots) => roots,
+            Err(err) => return Err(anyhow!("{err:#}")),
+        };
+        Ok(Some(
+            roots
+                .iter()
+                .map(|(name, resources)| QuotaRootUsage {
+                    name: name.clone(),
+                    folders: quota.folders.get(name).cloned().unwrap_or_default(),
+                    resources: resources.iter().map(Into::into).collect(),
+                })
+                .collect(),
+        ))
+    }
 }
 
 #[cfg(test)]
@@ -187,6 +295,63 @@ async fn test_needs_quota_warning() -> Result<()> {
         Ok(())
     }
 
+    #[test]
+    fn test_needs_quota_warning_at() {
+        assert!(!needs_quota_warning_at(55, 0, 60));
+        assert!(needs_quota_warning_at(60, 0, 60));
+        assert!(!needs_quota_warning_at(70, 60, 60));
+        assert!(needs_quota_warning_at(95, 60, 60));
+        assert!(!needs_quota_warning_at(90, 0, 99));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_get_quota_usage() -> Result<()> {
+        let mut tcm = TestContextManager::new();
+        let t = &tcm.alice().await;
+        assert_eq!(t.get_quota_usage().await?, None);
+
+        let mut roots = BTreeMap::new();
+        roots.insert(
+            "User quota".to_string(),
+            vec![QuotaResource {
+                name: async_imap::types::QuotaResourceName::Storage,
+                usage: 512,
+                limit: 1024,
+            }],
+        );
+        let mut folders = BTreeMap::new();
+        folders.insert(
+            "User quota".to_string(),
+            vec!["INBOX".to_string(), "DeltaChat".to_string()],
+        );
+        *t.quota.write().await = Some(QuotaInfo {
+            recent: Ok(roots),
+            folders,
+            modified: tools::Time::now(),
+        });
+        assert_eq!(
+            t.get_quota_usage().await?,
+            Some(vec![QuotaRootUsage {
+                name: "User quota".to_string(),
+                folders: vec!["INBOX".to_string(), "DeltaChat".to_string()],
+                resources: vec![QuotaResourceUsage {
+                    name: "STORAGE".to_string(),
+                    usage: 512,
+                    limit: 1024,
+                    percentage: 50,
+                }],
+            }])
+        );
+
+        *t.quota.write().await = Some(QuotaInfo {
+            recent: Err(anyhow!("Not supported.")),
+            folders: Default::default(),
+            modified: tools::Time::now(),
+        });
+        assert!(t.get_quota_usage().await.is_err());
+        Ok(())
+    }
+
     #[expect(clippy::assertions_on_constants)]
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_quota_thresholds() -> anyhow::Result<()> {
@@ -206,12 +371,14 @@ async fn test_quota_needs_update() -> Result<()> {
 
         *t.quota.write().await = Some(QuotaInfo {
             recent: Ok(Default::default()),
+            folders: Default::default(),
             modified: tools::Time::now() - Duration::from_secs(TIMEOUT + 1),
         });
         assert!(t.quota_needs_update(TIMEOUT).await);
 
         *t.quota.write().await = Some(QuotaInfo {
             recent: Ok(Default::default()),
+            folders: Default::default(),
             modified: tools::Time::now(),
         });
         assert!(!t.quota_needs_update(TIMEOUT).await);
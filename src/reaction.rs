@@ -13,18 +13,25 @@
 //! all previously received reactions from the same user and it is
 //! possible to remove all reactions by sending an empty string as a reaction,
 //! even though RFC 9078 requires at least one emoji to be sent.
+//!
+//! Reactions may contain custom emojis such as `:deltacat:`
+//! whose images are taken from emoji packs synced between clients.
+//! Custom emojis are sent as their Unicode fallback emoji
+//! so that they are displayed by clients not supporting custom emojis,
+//! the shortcode and the pack are sent in the `Chat-Custom-Emojis` header.
 
 use std::cmp::Ordering;
 use std::collections::BTreeMap;
 use std::fmt;
 
-use anyhow::Result;
+use anyhow::{ensure, Result};
 use serde::{Deserialize, Serialize};
 
 use crate::chat::{send_msg, Chat, ChatId};
 use crate::chatlist_events;
 use crate::contact::ContactId;
 use crate::context::Context;
+use crate::emoji;
 use crate::events::EventType;
 use crate::message::{rfc724_mid_exists, Message, MsgId};
 use crate::param::Param;
@@ -61,14 +68,46 @@ fn from(reaction: &str) -> Self {
             .split_ascii_whitespace()
             .filter(|&emoji| emoji.len() < 30)
             .collect();
-        emojis.sort_unstable();
-        emojis.dedup();
+        sort_and_dedup(&mut emojis);
         let reaction = emojis.join(" ");
         Self { reaction }
     }
 }
 
+/// Sorts emojis and removes duplicates,
+/// treating variants of an emoji with different skin tones as duplicates.
+fn sort_and_dedup(emojis: &mut Vec<&str>) {
+    emojis.sort_unstable_by_key(|&item| (emoji::normalize(item), item));
+    emojis.dedup_by_key(|item| emoji::normalize(item));
+}
+
 impl Reaction {
+    /// Replaces the shortcodes of custom emojis by their fallback emojis.
+    fn with_fallbacks(&self, custom_emojis: &[CustomEmoji]) -> Self {
+        let emojis: Vec<&str> = self
+            .emojis()
+            .into_iter()
+            .map(|emoji| {
+                custom_emojis
+                    .iter()
+                    .find(|custom| custom.shortcode == emoji)
+                    .map_or(emoji, |custom| custom.fallback.as_str())
+            })
+            .collect();
+        Self::from(emojis.join(" ").as_str())
+    }
+
+    /// Replaces the fallback emojis of custom emojis by their shortcodes.
+    fn with_shortcodes(&self, custom_emojis: &[CustomEmoji]) -> Self {
+        let mut emojis: Vec<&str> = self
+            .emojis()
+            .into_iter()
+            .filter(|&emoji| !custom_emojis.iter().any(|custom| custom.fallback == emoji))
+            .collect();
+        emojis.extend(custom_emojis.iter().map(|custom| custom.shortcode.as_str()));
+        Self::from(emojis.join(" ").as_str())
+    }
+
     /// Returns true if reaction contains no emojis.
     pub fn is_empty(&self) -> bool {
         self.reaction.is_empty()
@@ -88,18 +127,90 @@ pub fn as_str(&self) -> &str {
     pub fn add(&self, other: Self) -> Self {
         let mut emojis: Vec<&str> = self.emojis();
         emojis.append(&mut other.emojis());
-        emojis.sort_unstable();
-        emojis.dedup();
+        sort_and_dedup(&mut emojis);
         let reaction = emojis.join(" ");
         Self { reaction }
     }
 }
 
+/// Custom emoji referencing an image in an emoji pack.
+///
+/// Emoji packs are synced between clients independently of reactions,
+/// e.g. as sticker packs. Clients that do not have the pack
+/// display the fallback emoji instead.
+#[derive(Debug, Clone, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CustomEmoji {
+    /// Shortcode used in the reaction, such as `:deltacat:`.
+    pub shortcode: String,
+
+    /// ID of the emoji pack containing the image.
+    pub pack: String,
+
+    /// Unicode emoji displayed if the pack is not available.
+    pub fallback: String,
+}
+
+impl CustomEmoji {
+    /// Returns true if the custom emoji can be sent in a reaction.
+    fn is_valid(&self) -> bool {
+        self.shortcode.len() > 2
+            && self.shortcode.len() < 30
+            && self.shortcode.starts_with(':')
+            && self.shortcode.ends_with(':')
+            && !self
+                .shortcode
+                .contains(|c: char| c.is_whitespace() || c == ',')
+            && !self.pack.is_empty()
+            && self.pack.len() < 100
+            && self
+                .pack
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+            && !self.fallback.is_empty()
+            && self.fallback.len() < 30
+            && !self
+                .fallback
+                .contains(|c: char| c.is_whitespace() || c == ',')
+    }
+
+    /// Parses a list of custom emojis
+    /// from the `Chat-Custom-Emojis` header or the database.
+    ///
+    /// The list is comma-separated,
+    /// every custom emoji consists of the shortcode, the pack ID and the fallback emoji
+    /// separated by whitespace. Invalid entries are skipped.
+    pub(crate) fn parse_list(list: &str) -> Vec<Self> {
+        list.split(',')
+            .filter_map(|item| {
+                let mut fields = item.split_ascii_whitespace();
+                let custom = Self {
+                    shortcode: fields.next()?.to_string(),
+                    pack: fields.next()?.to_string(),
+                    fallback: fields.next()?.to_string(),
+                };
+                (fields.next().is_none() && custom.is_valid()).then_some(custom)
+            })
+            .collect()
+    }
+
+    /// Formats a list of custom emojis, see [`Self::parse_list`].
+    pub(crate) fn format_list(custom_emojis: &[Self]) -> String {
+        custom_emojis
+            .iter()
+            .map(|custom| format!("{} {} {}", custom.shortcode, custom.pack, custom.fallback))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
 /// Structure representing all reactions to a particular message.
 #[derive(Debug)]
 pub struct Reactions {
     /// Map from a contact to its reaction to message.
     reactions: BTreeMap<ContactId, Reaction>,
+
+    /// Map from shortcodes to custom emojis used in the reactions.
+    custom_emojis: BTreeMap<String, CustomEmoji>,
 }
 
 impl Reactions {
@@ -121,6 +232,15 @@ pub fn is_empty(&self) -> bool {
         self.reactions.is_empty()
     }
 
+    /// Returns the custom emoji for an emoji returned by the other methods,
+    /// or `None` if the emoji is a normal emoji.
+    ///
+    /// If several contacts used the same shortcode from different packs,
+    /// the first one is returned so that the shortcode is displayed the same way everywhere.
+    pub fn custom_emoji(&self, emoji: &str) -> Option<&CustomEmoji> {
+        self.custom_emojis.get(emoji)
+    }
+
     /// Returns a map from emojis to their frequencies.
     pub fn emoji_frequencies(&self) -> BTreeMap<String, usize> {
         let mut emoji_frequencies: BTreeMap<String, usize> = BTreeMap::new();
@@ -175,6 +295,7 @@ async fn set_msg_id_reaction(
     contact_id: ContactId,
     timestamp: i64,
     reaction: &Reaction,
+    custom_emojis: &[CustomEmoji],
 ) -> Result<()> {
     if reaction.is_empty() {
         // Simply remove the record instead of setting it to empty string.
@@ -191,11 +312,16 @@ async fn set_msg_id_reaction(
         context
             .sql
             .execute(
-                "INSERT INTO reactions (msg_id, contact_id, reaction)
-                 VALUES (?1, ?2, ?3)
+                "INSERT INTO reactions (msg_id, contact_id, reaction, custom_emojis)
+                 VALUES (?1, ?2, ?3, ?4)
                  ON CONFLICT(msg_id, contact_id)
-                 DO UPDATE SET reaction=excluded.reaction",
-                (msg_id, contact_id, reaction.as_str()),
+                 DO UPDATE SET reaction=excluded.reaction, custom_emojis=excluded.custom_emojis",
+                (
+                    msg_id,
+                    contact_id,
+                    reaction.as_str(),
+                    CustomEmoji::format_list(custom_emojis),
+                ),
             )
             .await?;
         let mut chat = Chat::load_from_db(context, chat_id).await?;
@@ -225,12 +351,43 @@ async fn set_msg_id_reaction(
 /// `reaction` is a string consisting of space-separated emoji. Use
 /// empty string to retract a reaction.
 pub async fn send_reaction(context: &Context, msg_id: MsgId, reaction: &str) -> Result<MsgId> {
+    send_reaction_with_custom_emojis(context, msg_id, reaction, &[]).await
+}
+
+/// Sends a reaction containing custom emojis to message `msg_id`,
+/// overriding previously sent reactions.
+///
+/// `reaction` is a string consisting of space-separated emoji
+/// and shortcodes of custom emojis from `custom_emojis`.
+/// Custom emojis are sent as their fallback emoji to clients not supporting them.
+/// Custom emojis not used in `reaction` are ignored.
+pub async fn send_reaction_with_custom_emojis(
+    context: &Context,
+    msg_id: MsgId,
+    reaction: &str,
+    custom_emojis: &[CustomEmoji],
+) -> Result<MsgId> {
     let msg = Message::load_from_db(context, msg_id).await?;
     let chat_id = msg.chat_id;
 
     let reaction: Reaction = reaction.into();
-    let mut reaction_msg = Message::new_text(reaction.as_str().to_string());
+    for custom in custom_emojis {
+        ensure!(custom.is_valid(), "Invalid custom emoji {custom:?}");
+    }
+    let custom_emojis: Vec<CustomEmoji> = custom_emojis
+        .iter()
+        .filter(|custom| reaction.emojis().contains(&custom.shortcode.as_str()))
+        .cloned()
+        .collect();
+    let mut reaction_msg =
+        Message::new_text(reaction.with_fallbacks(&custom_emojis).as_str().to_string());
     reaction_msg.set_reaction();
+    if !custom_emojis.is_empty() {
+        reaction_msg.param.set(
+            Param::CustomEmojis,
+            CustomEmoji::format_list(&custom_emojis),
+        );
+    }
     reaction_msg.in_reply_to = Some(msg.rfc724_mid);
     reaction_msg.hidden = true;
 
@@ -245,6 +402,7 @@ pub async fn send_reaction(context: &Context, msg_id: MsgId, reaction: &str) ->
         ContactId::SELF,
         reaction_msg.timestamp_sort,
         &reaction,
+        &custom_emojis,
     )
     .await?;
     Ok(reaction_msg_id)
@@ -257,9 +415,9 @@ pub async fn send_reaction(context: &Context, msg_id: MsgId, reaction: &str) ->
 /// to use [`send_reaction()`] instead so reacting with a new emoji
 /// removes previous emoji at the same time.
 pub async fn add_reaction(context: &Context, msg_id: MsgId, reaction: &str) -> Result<MsgId> {
-    let self_reaction = get_self_reaction(context, msg_id).await?;
+    let (self_reaction, custom_emojis) = get_self_reaction(context, msg_id).await?;
     let reaction = self_reaction.add(Reaction::from(reaction));
-    send_reaction(context, msg_id, reaction.as_str()).await
+    send_reaction_with_custom_emojis(context, msg_id, reaction.as_str(), &custom_emojis).await
 }
 
 /// Updates reaction of `contact_id` on the message with `in_reply_to`
@@ -268,6 +426,8 @@ pub async fn add_reaction(context: &Context, msg_id: MsgId, reaction: &str) -> R
 ///
 /// `reaction` is a space-separated string of emojis. It can be empty
 /// if contact wants to remove all reactions.
+/// Fallback emojis of `custom_emojis` are replaced by the shortcodes.
+#[expect(clippy::too_many_arguments)]
 pub(crate) async fn set_msg_reaction(
     context: &Context,
     in_reply_to: &str,
@@ -275,20 +435,45 @@ pub(crate) async fn set_msg_reaction(
     contact_id: ContactId,
     timestamp: i64,
     reaction: Reaction,
+    custom_emojis: &[CustomEmoji],
     is_incoming_fresh: bool,
 ) -> Result<()> {
     if let Some((msg_id, _)) = rfc724_mid_exists(context, in_reply_to).await? {
-        set_msg_id_reaction(context, msg_id, chat_id, contact_id, timestamp, &reaction).await?;
+        let custom_emojis: Vec<CustomEmoji> = custom_emojis
+            .iter()
+            .filter(|custom| reaction.emojis().contains(&custom.fallback.as_str()))
+            .cloned()
+            .collect();
+        let reaction = reaction.with_shortcodes(&custom_emojis);
+        set_msg_id_reaction(
+            context,
+            msg_id,
+            chat_id,
+            contact_id,
+            timestamp,
+            &reaction,
+            &custom_emojis,
+        )
+        .await?;
 
         if is_incoming_fresh
             && !reaction.is_empty()
             && msg_id.get_state(context).await?.is_outgoing()
         {
+            context
+                .sql
+                .execute(
+                    "UPDATE reactions SET fresh=1 WHERE msg_id=? AND contact_id=?",
+                    (msg_id, contact_id),
+                )
+                .await?;
             context.emit_event(EventType::IncomingReaction {
+                chat_id,
                 contact_id,
                 msg_id,
                 reaction,
             });
+            chatlist_events::emit_chatlist_item_changed(context, chat_id);
         }
     } else {
         info!(
@@ -299,48 +484,63 @@ pub(crate) async fn set_msg_reaction(
     Ok(())
 }
 
-/// Get our own reaction for a given message.
-async fn get_self_reaction(context: &Context, msg_id: MsgId) -> Result<Reaction> {
-    let reaction_str: Option<String> = context
+/// Get our own reaction for a given message and the custom emojis used in it.
+async fn get_self_reaction(
+    context: &Context,
+    msg_id: MsgId,
+) -> Result<(Reaction, Vec<CustomEmoji>)> {
+    let row: Option<(String, String)> = context
         .sql
-        .query_get_value(
-            "SELECT reaction
+        .query_row_optional(
+            "SELECT reaction, custom_emojis
              FROM reactions
              WHERE msg_id=? AND contact_id=?",
             (msg_id, ContactId::SELF),
+            |row| Ok((row.get(0)?, row.get(1)?)),
         )
         .await?;
-    Ok(reaction_str
-        .as_deref()
-        .map(Reaction::from)
+    Ok(row
+        .map(|(reaction, custom_emojis)| {
+            (
+                Reaction::from(reaction.as_str()),
+                CustomEmoji::parse_list(&custom_emojis),
+            )
+        })
         .unwrap_or_default())
 }
 
 /// Returns a structure containing all reactions to the message.
 pub async fn get_msg_reactions(context: &Context, msg_id: MsgId) -> Result<Reactions> {
-    let reactions = context
+    let rows = context
         .sql
         .query_map(
-            "SELECT contact_id, reaction FROM reactions WHERE msg_id=?",
+            "SELECT contact_id, reaction, custom_emojis FROM reactions
+             WHERE msg_id=? ORDER BY contact_id",
             (msg_id,),
             |row| {
                 let contact_id: ContactId = row.get(0)?;
                 let reaction: String = row.get(1)?;
-                Ok((contact_id, reaction))
-            },
-            |rows| {
-                let mut reactions = Vec::new();
-                for row in rows {
-                    let (contact_id, reaction) = row?;
-                    reactions.push((contact_id, Reaction::from(reaction.as_str())));
-                }
-                Ok(reactions)
+                let custom_emojis: String = row.get(2)?;
+                Ok((contact_id, reaction, custom_emojis))
             },
+            |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
         )
-        .await?
-        .into_iter()
-        .collect();
-    Ok(Reactions { reactions })
+        .await?;
+
+    let mut reactions = BTreeMap::new();
+    let mut custom_emojis = BTreeMap::new();
+    for (contact_id, reaction, custom_emojis_str) in rows {
+        for custom in CustomEmoji::parse_list(&custom_emojis_str) {
+            custom_emojis
+                .entry(custom.shortcode.clone())
+                .or_insert(custom);
+        }
+        reactions.insert(contact_id, Reaction::from(reaction.as_str()));
+    }
+    Ok(Reactions {
+        reactions,
+        custom_emojis,
+    })
 }
 
 impl Chat {
@@ -397,7 +597,7 @@ mod tests {
     use deltachat_contact_tools::ContactAddress;
 
     use super::*;
-    use crate::chat::{forward_msgs, get_chat_msgs, send_text_msg};
+    use crate::chat::{forward_msgs, get_chat_msgs, marknoticed_chat, send_text_msg};
     use crate::chatlist::Chatlist;
     use crate::config::Config;
     use crate::contact::{Contact, Origin};
@@ -405,6 +605,7 @@ mod tests {
     use crate::message::{delete_msgs, MessageState};
     use crate::receive_imf::{receive_imf, receive_imf_from_inbox};
     use crate::sql::housekeeping;
+    use crate::summary::SummaryReaction;
     use crate::test_utils::TestContext;
     use crate::test_utils::TestContextManager;
     use crate::tools::SystemTime;
@@ -444,6 +645,10 @@ fn test_parse_reaction() {
 
         // Duplicates are removed.
         assert_eq!(Reaction::from("👍 👍").emojis(), vec!["👍"]);
+
+        // Variants with different skin tones are duplicates, too.
+        assert_eq!(Reaction::from("👍🏽 👍🏿").emojis(), vec!["👍🏽"]);
+        assert_eq!(Reaction::from("❤️ ❤").emojis(), vec!["❤"]);
     }
 
     #[test]
@@ -602,6 +807,7 @@ async fn expect_incoming_reactions_event(
                 msg_id,
                 contact_id,
                 reaction,
+                ..
             } => {
                 assert_eq!(msg_id, expected_msg_id);
                 assert_eq!(contact_id, expected_contact_id);
@@ -695,12 +901,128 @@ async fn test_send_reaction() -> Result<()> {
         Ok(())
     }
 
+    #[test]
+    fn test_parse_custom_emojis() {
+        let list = ":deltacat: c0ffee 😺, :party: c0ffee 🎉";
+        let custom_emojis = CustomEmoji::parse_list(list);
+        assert_eq!(custom_emojis.len(), 2);
+        assert_eq!(custom_emojis[0].shortcode, ":deltacat:");
+        assert_eq!(custom_emojis[0].pack, "c0ffee");
+        assert_eq!(custom_emojis[0].fallback, "😺");
+        assert_eq!(CustomEmoji::format_list(&custom_emojis), list);
+
+        // Invalid entries are skipped.
+        assert_eq!(CustomEmoji::parse_list("deltacat c0ffee 😺").len(), 0);
+        assert_eq!(CustomEmoji::parse_list(":deltacat: c0ffee").len(), 0);
+        assert_eq!(CustomEmoji::parse_list(":a: b/c 😺, :b: c 😺").len(), 1);
+        assert_eq!(CustomEmoji::parse_list("").len(), 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_send_custom_emoji_reaction() -> Result<()> {
+        let mut tcm = TestContextManager::new();
+        let alice = &tcm.alice().await;
+        let bob = &tcm.bob().await;
+
+        let chat_alice = alice.create_chat(bob).await;
+        let alice_msg = alice.send_text(chat_alice.id, "Hi!").await;
+        let bob_msg = bob.recv_msg(&alice_msg).await;
+        bob_msg.chat_id.accept(bob).await?;
+
+        let deltacat = CustomEmoji {
+            shortcode: ":deltacat:".to_string(),
+            pack: "c0ffee".to_string(),
+            fallback: "😺".to_string(),
+        };
+        send_reaction_with_custom_emojis(bob, bob_msg.id, ":deltacat: 👍", &[deltacat.clone()])
+            .await?;
+        let bob_reaction_msg = bob.pop_sent_msg().await;
+
+        // Clients not supporting custom emojis see the fallback.
+        let reaction_msg = Message::load_from_db(bob, bob_reaction_msg.sender_msg_id).await?;
+        assert_eq!(reaction_msg.get_text(), "👍 😺");
+
+        alice.recv_msg_trash(&bob_reaction_msg).await;
+        let reactions = get_msg_reactions(alice, alice_msg.sender_msg_id).await?;
+        assert_eq!(reactions.to_string(), ":deltacat:1 👍1");
+        assert_eq!(reactions.custom_emoji(":deltacat:"), Some(&deltacat));
+        assert_eq!(reactions.custom_emoji("👍"), None);
+
+        // Alice reacts with the same custom emoji, it is counted together.
+        send_reaction_with_custom_emojis(
+            alice,
+            alice_msg.sender_msg_id,
+            ":deltacat:",
+            &[deltacat.clone()],
+        )
+        .await?;
+        let reactions = get_msg_reactions(alice, alice_msg.sender_msg_id).await?;
+        assert_eq!(reactions.to_string(), ":deltacat:2 👍1");
+
+        // Adding a reaction keeps the custom emoji.
+        add_reaction(alice, alice_msg.sender_msg_id, "😀").await?;
+        let sent = alice.pop_sent_msg().await;
+        let reaction_msg = Message::load_from_db(alice, sent.sender_msg_id).await?;
+        assert_eq!(reaction_msg.get_text(), "😀 😺");
+        bob.recv_msg_trash(&sent).await;
+        let reactions = get_msg_reactions(bob, bob_msg.id).await?;
+        assert_eq!(reactions.to_string(), ":deltacat:2 👍1 😀1");
+
+        Ok(())
+    }
+
     async fn assert_summary(t: &TestContext, expected: &str) {
         let chatlist = Chatlist::try_load(t, 0, None, None).await.unwrap();
         let summary = chatlist.get_summary(t, 0, None).await.unwrap();
         assert_eq!(summary.text, expected);
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_reactions_count_as_fresh() -> Result<()> {
+        let mut tcm = TestContextManager::new();
+        let alice = &tcm.alice().await;
+        let bob = &tcm.bob().await;
+
+        let chat_id = alice.create_chat(bob).await.id;
+        let sent = alice.send_text(chat_id, "Hi!").await;
+        let bob_msg = bob.recv_msg(&sent).await;
+        bob_msg.chat_id.accept(bob).await?;
+        send_reaction(bob, bob_msg.id, "👍").await?;
+        alice.recv_msg_trash(&bob.pop_sent_msg().await).await;
+        let event = alice
+            .evtracker
+            .get_matching(|evt| matches!(evt, EventType::IncomingReaction { .. }))
+            .await;
+        let EventType::IncomingReaction {
+            chat_id: event_chat_id,
+            ..
+        } = event
+        else {
+            unreachable!();
+        };
+        assert_eq!(event_chat_id, chat_id);
+
+        // By default, reactions do not count.
+        assert_eq!(chat_id.get_fresh_msg_cnt(alice).await?, 0);
+
+        alice
+            .set_config_bool(Config::ReactionsCountAsFresh, true)
+            .await?;
+        assert_eq!(chat_id.get_fresh_msg_cnt(alice).await?, 1);
+
+        // The per-chat setting overrides the global one.
+        chat_id
+            .set_reactions_count_as_fresh(alice, Some(false))
+            .await?;
+        assert_eq!(chat_id.get_fresh_msg_cnt(alice).await?, 0);
+        chat_id.set_reactions_count_as_fresh(alice, None).await?;
+        assert_eq!(chat_id.get_fresh_msg_cnt(alice).await?, 1);
+
+        marknoticed_chat(alice, chat_id).await?;
+        assert_eq!(chat_id.get_fresh_msg_cnt(alice).await?, 0);
+        Ok(())
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_reaction_summary() -> Result<()> {
         let alice = TestContext::new_alice().await;
@@ -731,6 +1053,14 @@ async fn test_reaction_summary() -> Result<()> {
         assert_eq!(summary.state, MessageState::InFresh); // state refers to message, not to reaction
         assert!(summary.prefix.is_none());
         assert!(summary.thumbnail_path.is_none());
+        assert_eq!(
+            summary.reaction,
+            Some(SummaryReaction {
+                contact_id: ContactId::SELF,
+                emoji: "👍".to_string(),
+                msg_text: "Party?".to_string(),
+            })
+        );
         assert_summary(&alice, "BOB reacted 👍 to \"Party?\"").await;
 
         // Alice reacts to own message as well
@@ -13,32 +13,37 @@
 use regex::Regex;
 
 use crate::aheader::EncryptPreference;
-use crate::chat::{self, Chat, ChatId, ChatIdBlocked, ProtectionStatus};
+use crate::bot;
+use crate::chat::{self, Chat, ChatId, ChatIdBlocked, ProtectionLogEvent, ProtectionStatus};
 use crate::config::Config;
 use crate::constants::{Blocked, Chattype, ShowEmails, DC_CHAT_ID_TRASH};
 use crate::contact::{Contact, ContactId, Origin};
 use crate::context::Context;
 use crate::debug_logging::maybe_set_logging_xdc_inner;
+use crate::decrypt::DecryptionResult;
 use crate::download::DownloadState;
 use crate::ephemeral::{stock_ephemeral_timer_changed, Timer as EphemeralTimer};
 use crate::events::EventType;
 use crate::headerdef::{HeaderDef, HeaderDefMap};
 use crate::imap::{markseen_on_imap_table, GENERATED_PREFIX};
+use crate::key_import;
 use crate::log::LogExt;
 use crate::message::{
     self, rfc724_mid_exists, Message, MessageState, MessengerMessage, MsgId, Viewtype,
 };
 use crate::mimeparser::{parse_message_ids, AvatarAction, MimeMessage, SystemMessage};
+use crate::ocr;
 use crate::param::{Param, Params};
 use crate::peer_channels::{add_gossip_peer_from_header, insert_topic_stub};
 use crate::peerstate::Peerstate;
-use crate::reaction::{set_msg_reaction, Reaction};
+use crate::reaction::{set_msg_reaction, CustomEmoji, Reaction};
 use crate::rusqlite::OptionalExtension;
 use crate::securejoin::{self, handle_securejoin_handshake, observe_securejoin_on_other_device};
 use crate::simplify;
 use crate::stock_str;
 use crate::sync::Sync::*;
 use crate::tools::{self, buf_compress, remove_subject_prefix};
+use crate::translate;
 use crate::{chatlist_events, location};
 use crate::{contact, imap};
 
@@ -125,6 +130,7 @@ pub(crate) async fn receive_imf_from_inbox(
         seen,
         is_partial_download,
         fetching_existing_messages,
+        None,
     )
     .await
 }
@@ -158,6 +164,8 @@ async fn insert_tombstone(context: &Context, rfc724_mid: &str) -> Result<MsgId>
 /// If `is_partial_download` is set, it contains the full message size in bytes.
 /// Do not confuse that with `replace_msg_id` that will be set when the full message is loaded
 /// later.
+///
+/// `predecrypted` is the result of decrypting `imf_raw` ahead of time, if any.
 #[expect(clippy::too_many_arguments)]
 pub(crate) async fn receive_imf_inner(
     context: &Context,
@@ -169,6 +177,7 @@ pub(crate) async fn receive_imf_inner(
     seen: bool,
     is_partial_download: Option<u32>,
     fetching_existing_messages: bool,
+    predecrypted: Option<DecryptionResult>,
 ) -> Result<Option<ReceivedMsg>> {
     if std::env::var(crate::DCC_MIME_DEBUG).is_ok() {
         info!(
@@ -178,31 +187,33 @@ pub(crate) async fn receive_imf_inner(
         );
     }
 
-    let mut mime_parser = match MimeMessage::from_bytes(context, imf_raw, is_partial_download).await
-    {
-        Err(err) => {
-            warn!(context, "receive_imf: can't parse MIME: {err:#}.");
-            if rfc724_mid.starts_with(GENERATED_PREFIX) {
-                // We don't have an rfc724_mid, there's no point in adding a trash entry
-                return Ok(None);
-            }
+    let mut mime_parser =
+        match MimeMessage::from_bytes_ex(context, imf_raw, is_partial_download, predecrypted).await
+        {
+            Err(err) => {
+                warn!(context, "receive_imf: can't parse MIME: {err:#}.");
+                if rfc724_mid.starts_with(GENERATED_PREFIX) {
+                    // We don't have an rfc724_mid, there's no point in adding a trash entry
+                    return Ok(None);
+                }
 
-            let msg_ids = vec![insert_tombstone(context, rfc724_mid).await?];
+                let msg_ids = vec![insert_tombstone(context, rfc724_mid).await?];
 
-            return Ok(Some(ReceivedMsg {
-                chat_id: DC_CHAT_ID_TRASH,
-                state: MessageState::Undefined,
-                sort_timestamp: 0,
-                msg_ids,
-                needs_delete_job: false,
-                #[cfg(test)]
-                from_is_signed: false,
-            }));
-        }
-        Ok(mime_parser) => mime_parser,
-    };
+                return Ok(Some(ReceivedMsg {
+                    chat_id: DC_CHAT_ID_TRASH,
+                    state: MessageState::Undefined,
+                    sort_timestamp: 0,
+                    msg_ids,
+                    needs_delete_job: false,
+                    #[cfg(test)]
+                    from_is_signed: false,
+                }));
+            }
+            Ok(mime_parser) => mime_parser,
+        };
 
     crate::peerstate::maybe_do_aeap_transition(context, &mut mime_parser).await?;
+    crate::peerstate::maybe_do_key_transition(context, &mut mime_parser).await?;
     if let Some(peerstate) = &mime_parser.peerstate {
         peerstate
             .handle_fingerprint_change(context, mime_parser.timestamp_sent)
@@ -400,7 +411,7 @@ pub(crate) async fn receive_imf_inner(
         received_msg = None;
     }
 
-    let verified_encryption =
+    let (verified_encryption, verified_ids) =
         has_verified_encryption(context, &mime_parser, from_id, &to_ids).await?;
 
     if verified_encryption == VerifiedEncryption::Verified
@@ -452,6 +463,23 @@ pub(crate) async fn receive_imf_inner(
     // Autocrypt-Gossip for all recipients in the chat to avoid sending Autocrypt-Gossip ourselves
     // and waste traffic.
     let chat_id = received_msg.chat_id;
+
+    if !verified_ids.is_empty()
+        && !chat_id.is_special()
+        && chat_id.is_protected(context).await? == ProtectionStatus::Protected
+    {
+        for id in verified_ids {
+            chat_id
+                .add_protection_log(
+                    context,
+                    ProtectionLogEvent::MemberVerified,
+                    from_id,
+                    Some(id),
+                    mime_parser.timestamp_sent,
+                )
+                .await?;
+        }
+    }
     if !chat_id.is_special()
         && mime_parser.recipients.iter().all(|recipient| {
             recipient.addr == mime_parser.from.addr
@@ -558,6 +586,44 @@ pub(crate) async fn receive_imf_inner(
         }
     }
 
+    if mime_parser
+        .get_header(HeaderDef::ChatProfileRequest)
+        .is_some()
+        && !from_id.is_special()
+    {
+        if let Err(err) = contact::handle_profile_request(context, from_id).await {
+            warn!(
+                context,
+                "receive_imf cannot handle profile request: {err:#}."
+            );
+        }
+    }
+
+    if let Some(requests) = mime_parser.get_header(HeaderDef::ChatUserAvatarRequest) {
+        if !from_id.is_special() {
+            let mut requested = false;
+            for addr in requests.split_ascii_whitespace() {
+                requested |= context.is_self_addr(addr).await?;
+            }
+            if requested {
+                if let Err(err) = contact::handle_profile_request(context, from_id).await {
+                    warn!(
+                        context,
+                        "receive_imf cannot handle avatar request: {err:#}."
+                    );
+                }
+            }
+        }
+    }
+
+    if let Some(hash) = mime_parser.get_header(HeaderDef::ChatUserAvatarHash) {
+        if mime_parser.user_avatar.is_none() && !from_id.is_special() && !chat_id.is_special() {
+            if let Err(err) = contact::handle_avatar_hash(context, chat_id, from_id, hash).await {
+                warn!(context, "receive_imf cannot handle avatar hash: {err:#}.");
+            }
+        }
+    }
+
     // Ignore footers from mailinglists as they are often created or modified by the mailinglist software.
     if let Some(footer) = &mime_parser.footer {
         if !mime_parser.is_mailinglist_message()
@@ -624,10 +690,39 @@ pub(crate) async fn receive_imf_inner(
     } else if !chat_id.is_trash() {
         let fresh = received_msg.state == MessageState::InFresh;
         for msg_id in &received_msg.msg_ids {
-            chat_id.emit_msg_event(context, *msg_id, mime_parser.incoming && fresh);
+            chat_id
+                .emit_msg_event(context, *msg_id, mime_parser.incoming && fresh)
+                .await;
+        }
+        if mime_parser.incoming && fresh {
+            for msg_id in &received_msg.msg_ids {
+                bot::handle_incoming_msg(context, chat_id, *msg_id)
+                    .await
+                    .log_err(context)
+                    .ok();
+            }
+        }
+        if mime_parser.incoming {
+            for msg_id in &received_msg.msg_ids {
+                translate::handle_incoming_msg(context, chat_id, *msg_id)
+                    .await
+                    .log_err(context)
+                    .ok();
+                key_import::handle_incoming_msg(context, chat_id, *msg_id)
+                    .await
+                    .log_err(context)
+                    .ok();
+            }
+        }
+        for msg_id in &received_msg.msg_ids {
+            ocr::handle_new_msg(context, *msg_id)
+                .await
+                .log_err(context)
+                .ok();
         }
     }
     context.new_msgs_notify.notify_one();
+    context.metrics.count_msg_received();
 
     mime_parser
         .handle_reports(context, from_id, &mime_parser.parts)
@@ -778,6 +873,7 @@ async fn add_parts(
     // (of course, the user can add other chats manually later)
     let to_id: ContactId;
     let state: MessageState;
+    let mut read_on_other_device = false;
     let mut hidden = false;
     let mut needs_delete_job = false;
     let mut restore_protection = false;
@@ -792,11 +888,36 @@ async fn add_parts(
         }
     }
 
+    let mut custom_headers = Vec::new();
+    for (name, value) in mime_parser.x_headers() {
+        if !value.chars().any(char::is_control)
+            && chat::is_allowed_custom_header(context, name).await?
+        {
+            custom_headers.push(format!("{name}: {}", value.trim()));
+        }
+    }
+    if !custom_headers.is_empty() {
+        custom_headers.sort();
+        let custom_headers = custom_headers.join("\n");
+        for part in &mut mime_parser.parts {
+            part.param.set(Param::CustomHeaders, &custom_headers);
+        }
+    }
+
     if chat_id.is_none() && is_mdn {
         chat_id = Some(DC_CHAT_ID_TRASH);
         info!(context, "Message is an MDN (TRASH).",);
     }
 
+    if chat_id.is_none()
+        && mime_parser
+            .get_header(HeaderDef::ChatKeyTransition)
+            .is_some()
+    {
+        chat_id = Some(DC_CHAT_ID_TRASH);
+        info!(context, "Message is a key transition (TRASH).");
+    }
+
     if mime_parser.incoming {
         to_id = ContactId::SELF;
 
@@ -1033,6 +1154,16 @@ async fn add_parts(
             }
         }
 
+        // The message may be the last read message set on another device
+        // which was synchronized before the message arrived.
+        // Then it is already read there and must not be counted as fresh.
+        read_on_other_device = match chat_id {
+            Some(chat_id) if !chat_id.is_special() => {
+                chat_id.is_last_read_msg(context, rfc724_mid).await?
+            }
+            _ => false,
+        };
+
         state = if seen
             || fetching_existing_messages
             || is_mdn
@@ -1040,6 +1171,8 @@ async fn add_parts(
             || chat_id_blocked == Blocked::Yes
         {
             MessageState::InSeen
+        } else if read_on_other_device {
+            MessageState::InNoticed
         } else {
             MessageState::InFresh
         };
@@ -1292,7 +1425,7 @@ async fn add_parts(
         && !mime_parser.parts.is_empty()
         && chat_id.get_ephemeral_timer(context).await? != ephemeral_timer
     {
-        info!(context, "Received new ephemeral timer value {ephemeral_timer:?} for chat {chat_id}, checking if it should be applied.");
+        info!(context, chat_id = chat_id; "Received new ephemeral timer value {ephemeral_timer:?} for chat {chat_id}, checking if it should be applied.");
         if is_dc_message == MessengerMessage::Yes
             && get_previous_message(context, mime_parser)
                 .await?
@@ -1378,6 +1511,21 @@ async fn add_parts(
                 warn!(context, "Verification problem: {err:#}.");
                 let s = format!("{err}. See 'Info' for more details");
                 mime_parser.replace_msg_by_error(&s);
+
+                let key_changed = mime_parser
+                    .peerstate
+                    .as_ref()
+                    .is_some_and(|peerstate| peerstate.verified_key_fingerprint.is_some());
+                if mime_parser.incoming
+                    && chat.typ == Chattype::Group
+                    && key_changed
+                    && chat::is_contact_in_chat(context, chat_id, from_id).await?
+                {
+                    context.emit_event(EventType::VerifiedGroupMemberKeyChanged {
+                        chat_id,
+                        contact_id: from_id,
+                    });
+                }
             }
         }
     }
@@ -1490,11 +1638,28 @@ async fn add_parts(
         }
     }
 
+    // Remember where private replies to mailing list messages should go
+    // if the sender asks for replies to an address other than `From`.
+    let reply_to = mime_parser
+        .get_reply_to()
+        .filter(|_| mime_parser.is_mailinglist_message())
+        .filter(|addr| {
+            !addr_cmp(addr, &mime_parser.from.addr)
+                && mime_parser
+                    .list_post
+                    .as_deref()
+                    .map_or(true, |list_post| !addr_cmp(addr, list_post))
+        });
+
     let mut parts = mime_parser.parts.iter().peekable();
     while let Some(part) = parts.next() {
         if part.is_reaction {
             let reaction_str = simplify::remove_footers(part.msg.as_str());
             let is_incoming_fresh = mime_parser.incoming && !seen && !fetching_existing_messages;
+            let custom_emojis = mime_parser
+                .get_header(HeaderDef::ChatCustomEmojis)
+                .map(CustomEmoji::parse_list)
+                .unwrap_or_default();
             set_msg_reaction(
                 context,
                 mime_in_reply_to,
@@ -1502,6 +1667,7 @@ async fn add_parts(
                 from_id,
                 sort_timestamp,
                 Reaction::from(reaction_str.as_str()),
+                &custom_emojis,
                 is_incoming_fresh,
             )
             .await?;
@@ -1511,6 +1677,9 @@ async fn add_parts(
         if is_system_message != SystemMessage::Unknown {
             param.set_int(Param::Cmd, is_system_message as i32);
         }
+        if let Some(reply_to) = &reply_to {
+            param.set(Param::ReplyTo, reply_to);
+        }
 
         if let Some(replace_msg_id) = replace_msg_id {
             let placeholder = Message::load_from_db(context, replace_msg_id).await?;
@@ -1698,12 +1867,33 @@ async fn add_parts(
         replace_msg_id.trash(context, on_server).await?;
     }
 
+    if read_on_other_device && !chat_id.is_trash() {
+        if let Some(&msg_id) = created_db_entries.last() {
+            chat_id.notice_until(context, msg_id).await?;
+        }
+    }
+
     let unarchive = match mime_parser.get_header(HeaderDef::ChatGroupMemberRemoved) {
         Some(addr) => context.is_self_addr(addr).await?,
         None => true,
     };
     if unarchive {
-        chat_id.unarchive_if_not_muted(context, state).await?;
+        let mut mention = false;
+        if state == MessageState::InFresh && chat_id.is_archived_until_mention(context).await? {
+            mention = parent
+                .as_ref()
+                .is_some_and(|parent| parent.from_id == ContactId::SELF);
+            for part in &mime_parser.parts {
+                if mention {
+                    break;
+                }
+                mention = chat::is_mention(context, &part.msg)
+                    .await
+                    .log_err(context)
+                    .unwrap_or_default();
+            }
+        }
+        chat_id.unarchive_for_msg(context, state, mention).await?;
     }
 
     info!(
@@ -1728,6 +1918,10 @@ async fn add_parts(
             chat.param.set(Param::LastSubject, subject);
             chat.update_param(context).await?;
         }
+
+        if chat.is_encryption_required() {
+            chat_id.update_encryption_availability(context).await?;
+        }
     }
 
     if !mime_parser.incoming && is_mdn && is_dc_message == MessengerMessage::Yes {
@@ -2110,6 +2304,32 @@ async fn self_explicitly_added(
             chat::add_to_chat_contacts_table(context, timestamp, new_chat_id, &members).await?;
         }
 
+        // Admins are sent from the creation of the group on,
+        // but any member could claim to be an admin in a message
+        // to a member who does not know the group yet.
+        // So the initial admins are only accepted from the creator of the group
+        // promoting it, from the member adding us and from other devices of the user.
+        // Otherwise the group has no admins until one is set by a member, see
+        // `chat::set_group_admin()`.
+        let from_creator = from_id == ContactId::SELF
+            || mime_parser
+                .get_header(HeaderDef::ChatGroupCreated)
+                .is_some()
+            || self_explicitly_added(context, &mime_parser).await?;
+        if mime_parser.get_header(HeaderDef::ChatGroupAdmins).is_some() {
+            if from_creator {
+                apply_group_admins(context, new_chat_id, mime_parser).await?;
+            } else {
+                info!(
+                    context,
+                    "Not accepting admins of new group {new_chat_id} from a member."
+                );
+            }
+        } else if from_id == ContactId::SELF {
+            // The group was created on another device of the user.
+            chat::set_group_admins(context, new_chat_id, &[ContactId::SELF]).await?;
+        }
+
         context.emit_event(EventType::ChatModified(new_chat_id));
         chatlist_events::emit_chatlist_changed(context);
         chatlist_events::emit_chatlist_item_changed(context, new_chat_id);
@@ -2205,6 +2425,76 @@ async fn update_chats_contacts_timestamps(
     Ok(modified)
 }
 
+/// Returns the sorted contact IDs of the admins listed in the `Chat-Group-Admins` header.
+async fn parse_group_admins(
+    context: &Context,
+    mime_parser: &MimeMessage,
+) -> Result<Vec<ContactId>> {
+    let mut admins = Vec::new();
+    let Some(admins_header) = mime_parser.get_header(HeaderDef::ChatGroupAdmins) else {
+        return Ok(admins);
+    };
+    for addr in admins_header.split_ascii_whitespace() {
+        if let Some(contact_id) = Contact::lookup_id_by_addr(context, addr, Origin::Unknown).await?
+        {
+            admins.push(contact_id);
+        } else {
+            warn!(context, "Admin {addr:?} has no contact id.");
+        }
+    }
+    admins.sort_unstable();
+    admins.dedup();
+    Ok(admins)
+}
+
+/// Applies the admins from the `Chat-Group-Admins` header
+/// and the moderation from the `Chat-Group-Moderated` header to the group
+/// unless a newer change was applied already.
+///
+/// The caller must check that the sender is allowed to do this.
+/// Returns true if the admins or the moderation changed.
+async fn apply_group_admins(
+    context: &Context,
+    chat_id: ChatId,
+    mime_parser: &MimeMessage,
+) -> Result<bool> {
+    if mime_parser.get_header(HeaderDef::ChatGroupAdmins).is_none() {
+        return Ok(false);
+    }
+    if !chat_id
+        .update_timestamp(
+            context,
+            Param::GroupAdminsTimestamp,
+            mime_parser.timestamp_sent,
+        )
+        .await?
+    {
+        return Ok(false);
+    }
+
+    let mut changed = false;
+    let admins = parse_group_admins(context, mime_parser).await?;
+    if !admins.is_empty() && admins != chat::get_group_admins(context, chat_id).await? {
+        chat::set_group_admins(context, chat_id, &admins).await?;
+        changed = true;
+    }
+
+    let mut chat = Chat::load_from_db(context, chat_id).await?;
+    let moderated = mime_parser
+        .get_header(HeaderDef::ChatGroupModerated)
+        .is_some();
+    if chat.is_moderated() != moderated {
+        if moderated {
+            chat.param.set_int(Param::GroupModerated, 1);
+        } else {
+            chat.param.remove(Param::GroupModerated);
+        }
+        chat.update_param(context).await?;
+        changed = true;
+    }
+    Ok(changed)
+}
+
 /// Apply group member list, name, avatar and protection status changes from the MIME message.
 ///
 /// Returns `Vec` of group changes messages and, optionally, a better message to replace the
@@ -2248,6 +2538,11 @@ async fn apply_group_changes(
     let is_from_in_chat =
         !chat_contacts.contains(&ContactId::SELF) || chat_contacts.contains(&from_id);
 
+    // In moderated groups only admins may change members, name and avatar.
+    // Other members may only leave the group.
+    let from_may_manage = chat::may_manage_group(context, &chat, from_id).await?;
+    let mut change_rejected = false;
+
     if mime_parser.get_header(HeaderDef::ChatVerified).is_some() {
         if let VerifiedEncryption::NotVerified(err) = verified_encryption {
             warn!(context, "Verification problem: {err:#}.");
@@ -2269,7 +2564,10 @@ async fn apply_group_changes(
 
     if let Some(removed_addr) = mime_parser.get_header(HeaderDef::ChatGroupMemberRemoved) {
         removed_id = Contact::lookup_id_by_addr(context, removed_addr, Origin::Unknown).await?;
-        if let Some(id) = removed_id {
+        if removed_id.is_some_and(|id| id != from_id) && !from_may_manage {
+            removed_id = None;
+            change_rejected = true;
+        } else if let Some(id) = removed_id {
             better_msg = if id == from_id {
                 Some(stock_str::msg_group_left_local(context, from_id).await)
             } else {
@@ -2279,7 +2577,9 @@ async fn apply_group_changes(
             warn!(context, "Removed {removed_addr:?} has no contact id.")
         }
     } else if let Some(added_addr) = mime_parser.get_header(HeaderDef::ChatGroupMemberAdded) {
-        if let Some(contact_id) =
+        if !from_may_manage {
+            change_rejected = true;
+        } else if let Some(contact_id) =
             Contact::lookup_id_by_addr(context, added_addr, Origin::Unknown).await?
         {
             added_id = Some(contact_id);
@@ -2287,12 +2587,16 @@ async fn apply_group_changes(
             warn!(context, "Added {added_addr:?} has no contact id.");
         }
 
-        better_msg = Some(stock_str::msg_add_member_local(context, added_addr, from_id).await);
+        if !change_rejected {
+            better_msg = Some(stock_str::msg_add_member_local(context, added_addr, from_id).await);
+        }
     } else if let Some(old_name) = mime_parser
         .get_header(HeaderDef::ChatGroupNameChanged)
         .map(|s| s.trim())
     {
-        if let Some(grpname) = mime_parser
+        if !from_may_manage {
+            change_rejected = true;
+        } else if let Some(grpname) = mime_parser
             .get_header(HeaderDef::ChatGroupName)
             .map(|grpname| grpname.trim())
             .filter(|grpname| grpname.len() < 200)
@@ -2307,7 +2611,7 @@ async fn apply_group_changes(
                 )
                 .await?
             {
-                info!(context, "Updating grpname for chat {chat_id}.");
+                info!(context, chat_id = chat_id; "Updating grpname for chat {chat_id}.");
                 context
                     .sql
                     .execute("UPDATE chats SET name=? WHERE id=?;", (grpname, chat_id))
@@ -2318,7 +2622,9 @@ async fn apply_group_changes(
             better_msg = Some(stock_str::msg_grp_name(context, old_name, grpname, from_id).await);
         }
     } else if let Some(value) = mime_parser.get_header(HeaderDef::ChatContent) {
-        if value == "group-avatar-changed" {
+        if value == "group-avatar-changed" && !from_may_manage {
+            change_rejected = true;
+        } else if value == "group-avatar-changed" {
             if let Some(avatar_action) = &mime_parser.group_avatar {
                 // this is just an explicit message containing the group-avatar,
                 // apart from that, the group-avatar is send along with various other messages
@@ -2334,7 +2640,14 @@ async fn apply_group_changes(
         }
     }
 
-    if is_from_in_chat {
+    if is_from_in_chat && !from_may_manage {
+        if removed_id == Some(from_id) {
+            // Members of moderated groups may leave the group,
+            // but not change other members.
+            chat::remove_from_chat_contacts_table(context, chat_id, from_id).await?;
+            send_event_chat_modified = true;
+        }
+    } else if is_from_in_chat {
         if chat.member_list_is_stale(context).await? {
             info!(context, "Member list is stale.");
             let mut new_members: HashSet<ContactId> = HashSet::from_iter(to_ids.iter().copied());
@@ -2445,6 +2758,25 @@ async fn apply_group_changes(
         .copied()
         .collect();
 
+    if chat_id.is_protected(context).await? == ProtectionStatus::Protected {
+        for (event, ids) in [
+            (ProtectionLogEvent::MemberAdded, &added_ids),
+            (ProtectionLogEvent::MemberRemoved, &removed_ids),
+        ] {
+            for id in ids {
+                chat_id
+                    .add_protection_log(
+                        context,
+                        event,
+                        from_id,
+                        Some(*id),
+                        mime_parser.timestamp_sent,
+                    )
+                    .await?;
+            }
+        }
+    }
+
     if let Some(added_id) = added_id {
         if !added_ids.remove(&added_id) && !self_added {
             // No-op "Member added" message.
@@ -2458,8 +2790,63 @@ async fn apply_group_changes(
     }
     let group_changes_msgs = group_changes_msgs(context, &added_ids, &removed_ids, chat_id).await?;
 
+    let admins_changed = mime_parser.is_system_message == SystemMessage::GroupAdminsChanged;
+    if mime_parser.get_header(HeaderDef::ChatGroupAdmins).is_some() && is_from_in_chat {
+        // Admins and moderation may only be changed by known admins,
+        // otherwise any member could make themselves an admin.
+        // Groups without admins, e.g. created by older versions,
+        // accept the member who explicitly makes themselves the first admin
+        // and the creator promoting the group if this message arrives late.
+        let may_change_admins = if chat::get_group_admins(context, chat_id).await?.is_empty() {
+            (admins_changed
+                || mime_parser
+                    .get_header(HeaderDef::ChatGroupCreated)
+                    .is_some())
+                && parse_group_admins(context, mime_parser)
+                    .await?
+                    .contains(&from_id)
+        } else {
+            chat::is_group_admin(context, chat_id, from_id).await?
+        };
+        if !may_change_admins {
+            if admins_changed {
+                change_rejected = true;
+            }
+        } else {
+            let was_moderated = chat.is_moderated();
+            if apply_group_admins(context, chat_id, mime_parser).await? {
+                send_event_chat_modified = true;
+            }
+
+            // Reload the chat to not overwrite the parameters updated above.
+            chat = Chat::load_from_db(context, chat_id).await?;
+            if admins_changed {
+                better_msg = Some(if chat.is_moderated() == was_moderated {
+                    stock_str::msg_grp_admins_changed(context, from_id).await
+                } else if chat.is_moderated() {
+                    stock_str::msg_grp_moderated(context, from_id).await
+                } else {
+                    stock_str::msg_grp_unmoderated(context, from_id).await
+                });
+            }
+        }
+    }
+
+    if change_rejected {
+        warn!(
+            context,
+            "Contact {from_id} attempts to change moderated group chat {chat_id} without being an admin."
+        );
+        better_msg = Some(stock_str::msg_grp_change_rejected(context, from_id).await);
+    }
+
     if let Some(avatar_action) = &mime_parser.group_avatar {
-        if !new_chat_contacts.contains(&ContactId::SELF) {
+        if !from_may_manage {
+            warn!(
+                context,
+                "Contact {from_id} attempts to modify moderated group chat {chat_id} avatar without being an admin."
+            );
+        } else if !new_chat_contacts.contains(&ContactId::SELF) {
             warn!(
                 context,
                 "Received group avatar update for group chat {chat_id} we are not a member of."
@@ -2470,7 +2857,7 @@ async fn apply_group_changes(
                 "Contact {from_id} attempts to modify group chat {chat_id} avatar without being a member.",
             );
         } else {
-            info!(context, "Group-avatar change for {chat_id}.");
+            info!(context, chat_id = chat_id; "Group-avatar change for {chat_id}.");
             if chat
                 .param
                 .update_timestamp(Param::AvatarTimestamp, mime_parser.timestamp_sent)?
@@ -2686,7 +3073,7 @@ fn compute_mailinglist_name(
     sanitize_single_line(&name)
 }
 
-/// Set ListId param on the contact and ListPost param the chat.
+/// Set ListId param on the contact and ListPost, List-Subscribe and List-Unsubscribe params on the chat.
 /// Only called for incoming messages since outgoing messages never have a
 /// List-Post header, anyway.
 async fn apply_mailinglist_changes(
@@ -2714,7 +3101,7 @@ async fn apply_mailinglist_changes(
             )
             .await?
     {
-        info!(context, "Updating listname for chat {chat_id}.");
+        info!(context, chat_id = chat_id; "Updating listname for chat {chat_id}.");
         context
             .sql
             .execute("UPDATE chats SET name=? WHERE id=?;", (new_name, chat_id))
@@ -2722,6 +3109,51 @@ async fn apply_mailinglist_changes(
         context.emit_event(EventType::ChatModified(chat_id));
     }
 
+    let list_subscribe = mime_parser
+        .get_list_uris(HeaderDef::ListSubscribe)
+        .join(" ");
+    let list_unsubscribe = mime_parser
+        .get_list_uris(HeaderDef::ListUnsubscribe)
+        .join(" ");
+    let one_click = mime_parser
+        .get_header(HeaderDef::ListUnsubscribePost)
+        .is_some_and(|value| {
+            value
+                .trim()
+                .eq_ignore_ascii_case("List-Unsubscribe=One-Click")
+        });
+    // Messages without these headers, e.g. sent to the list by another device,
+    // do not reset the stored values.
+    let has_list_uris = !list_subscribe.is_empty() || !list_unsubscribe.is_empty();
+    if has_list_uris && !mime_parser.dkim_passed {
+        // Otherwise anybody could make us send unsubscribe requests
+        // to arbitrary URIs by forging the list's From address.
+        warn!(
+            context,
+            "Ignoring List-(Un)Subscribe headers of {chat_id} because DKIM failed."
+        );
+    } else if has_list_uris
+        && (chat.param.get(Param::ListSubscribe).unwrap_or_default() != list_subscribe
+            || chat.param.get(Param::ListUnsubscribe).unwrap_or_default() != list_unsubscribe
+            || chat
+                .param
+                .get_bool(Param::ListUnsubscribeOneClick)
+                .unwrap_or_default()
+                != one_click)
+    {
+        chat.param.set_optional(
+            Param::ListSubscribe,
+            Some(list_subscribe).filter(|s| !s.is_empty()),
+        );
+        chat.param.set_optional(
+            Param::ListUnsubscribe,
+            Some(list_unsubscribe).filter(|s| !s.is_empty()),
+        );
+        chat.param
+            .set_optional(Param::ListUnsubscribeOneClick, one_click.then_some("1"));
+        chat.update_param(context).await?;
+    }
+
     let Some(list_post) = &mime_parser.list_post else {
         return Ok(());
     };
@@ -2890,13 +3322,14 @@ async fn update_verified_keys(
 ///
 /// This means that it is encrypted and signed with a verified key.
 ///
-/// Also propagates gossiped keys to verified if needed.
+/// Also propagates gossiped keys to verified if needed
+/// and returns the contacts which became verified this way.
 async fn has_verified_encryption(
     context: &Context,
     mimeparser: &MimeMessage,
     from_id: ContactId,
     to_ids: &[ContactId],
-) -> Result<VerifiedEncryption> {
+) -> Result<(VerifiedEncryption, Vec<ContactId>)> {
     use VerifiedEncryption::*;
 
     // We do not need to check if we are verified with ourself.
@@ -2907,7 +3340,10 @@ async fn has_verified_encryption(
         .collect::<Vec<ContactId>>();
 
     if !mimeparser.was_encrypted() {
-        return Ok(NotVerified("This message is not encrypted".to_string()));
+        return Ok((
+            NotVerified("This message is not encrypted".to_string()),
+            Vec::new(),
+        ));
     };
 
     // ensure, the contact is verified
@@ -2916,8 +3352,9 @@ async fn has_verified_encryption(
     // and results in group-splits otherwise.
     if from_id != ContactId::SELF {
         let Some(peerstate) = &mimeparser.peerstate else {
-            return Ok(NotVerified(
-                "No peerstate, the contact isn't verified".to_string(),
+            return Ok((
+                NotVerified("No peerstate, the contact isn't verified".to_string()),
+                Vec::new(),
             ));
         };
 
@@ -2928,14 +3365,15 @@ async fn has_verified_encryption(
             .is_some();
 
         if !signed_with_verified_key {
-            return Ok(NotVerified(
-                "The message was sent with non-verified encryption".to_string(),
+            return Ok((
+                NotVerified("The message was sent with non-verified encryption".to_string()),
+                Vec::new(),
             ));
         }
     }
 
-    mark_recipients_as_verified(context, from_id, to_ids, mimeparser).await?;
-    Ok(Verified)
+    let verified_ids = mark_recipients_as_verified(context, from_id, to_ids, mimeparser).await?;
+    Ok((Verified, verified_ids))
 }
 
 async fn mark_recipients_as_verified(
@@ -2943,9 +3381,10 @@ async fn mark_recipients_as_verified(
     from_id: ContactId,
     to_ids: Vec<ContactId>,
     mimeparser: &MimeMessage,
-) -> Result<()> {
+) -> Result<Vec<ContactId>> {
+    let mut verified_ids = Vec::new();
     if mimeparser.get_header(HeaderDef::ChatVerified).is_none() {
-        return Ok(());
+        return Ok(verified_ids);
     }
     let contact = Contact::get_by_id(context, from_id).await?;
     for id in to_ids {
@@ -2999,6 +3438,7 @@ async fn mark_recipients_as_verified(
                             mimeparser.timestamp_sent,
                         )
                         .await?;
+                        verified_ids.push(id);
                     }
                 } else {
                     // The contact already has a verified key.
@@ -3010,7 +3450,7 @@ async fn mark_recipients_as_verified(
         }
     }
 
-    Ok(())
+    Ok(verified_ids)
 }
 
 /// Returns the last message referenced from `References` header if it is in the database.
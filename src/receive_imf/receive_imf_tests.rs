@@ -6,7 +6,7 @@
 use crate::chat::{
     add_contact_to_chat, add_to_chat_contacts_table, create_group_chat, get_chat_contacts,
     get_chat_msgs, is_contact_in_chat, remove_contact_from_chat, send_text_msg, ChatItem,
-    ChatVisibility,
+    ChatVisibility, MailinglistReplyMode,
 };
 use crate::chatlist::Chatlist;
 use crate::constants::{DC_GCL_FOR_FORWARDING, DC_GCL_NO_SPECIALS};
@@ -14,6 +14,7 @@
 use crate::download::MIN_DOWNLOAD_LIMIT;
 use crate::imap::prefetch_should_download;
 use crate::imex::{imex, ImexMode};
+use crate::message::{DeliveryAction, DeliveryFailure};
 use crate::securejoin::get_securejoin_qr;
 use crate::test_utils::{get_chat_msg, mark_as_verified, TestContext, TestContextManager};
 use crate::tools::{time, SystemTime};
@@ -683,6 +684,47 @@ async fn test_parse_ndn_gmail() {
         .await;
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_parse_ndn_failure_reason() {
+    let (t, msg_id) = test_parse_ndn(
+            "alice@gmail.com",
+            "assidhfaaspocwaeofi@gmail.com",
+            "CABXKi8zruXJc_6e4Dr087H5wE7sLp+u250o0N2q5DdjF_r-8wg@mail.gmail.com",
+            include_bytes!("../../test-data/message/gmail_ndn.eml"),
+            Some("Delivery Status Notification (Failure) – ** Die Adresse wurde nicht gefunden **\n\nIhre Nachricht wurde nicht an assidhfaaspocwaeofi@gmail.com zugestellt, weil die Adresse nicht gefunden wurde oder keine E-Mails empfangen kann.\n\nHier erfahren Sie mehr: https://support.google.com/mail/?p=NoSuchUser\n\nAntwort:\n\n550 5.1.1 The email account that you tried to reach does not exist. Please try double-checking the recipient\'s email address for typos or unnecessary spaces. Learn more at https://support.google.com/mail/?p=NoSuchUser i18sor6261697wrs.38 - gsmtp"),
+        )
+        .await;
+    let msg = Message::load_from_db(&t, msg_id).await.unwrap();
+    assert_eq!(
+        msg.get_delivery_failure(),
+        Some(DeliveryFailure::UnknownRecipient)
+    );
+    t.evtracker
+        .get_matching(|evt| {
+            matches!(
+                evt,
+                EventType::MsgFailed {
+                    reason: Some(DeliveryFailure::UnknownRecipient),
+                    ..
+                }
+            )
+        })
+        .await;
+
+    assert_eq!(
+        DeliveryFailure::from_status("5.2.2"),
+        DeliveryFailure::MailboxFull
+    );
+    assert_eq!(
+        DeliveryFailure::from_status("5.7.1"),
+        DeliveryFailure::Policy
+    );
+    assert_eq!(
+        DeliveryFailure::from_status("4.4.7"),
+        DeliveryFailure::Other
+    );
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn test_parse_ndn_gmx() {
     test_parse_ndn(
@@ -735,7 +777,7 @@ async fn test_parse_ndn_with_attachment() {
 /// Test that DSN is not treated as NDN if Action: is not "failed"
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn test_parse_dsn_relayed() {
-    test_parse_ndn(
+    let (t, msg_id) = test_parse_ndn(
         "anon_1@posteo.de",
         "anon_2@gmx.at",
         "8b7b1a9d0c8cc588c7bcac47f5687634@posteo.de",
@@ -743,6 +785,64 @@ async fn test_parse_dsn_relayed() {
         None,
     )
     .await;
+
+    // The DSN is sent by the sender's server, not by the recipient's one,
+    // so the per-recipient status is not recorded.
+    assert!(msg_id.get_delivery_status(&t).await.unwrap().is_empty());
+    assert!(msg_id.get_failed_recipients(&t).await.unwrap().is_empty());
+}
+
+/// Tests that positive DSNs are only accepted from the server of the recipient.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_parse_dsn_delivered() -> Result<()> {
+    let dsn = |from_domain: &str| {
+        format!(
+            "From: MAILER-DAEMON@{from_domain}\n\
+             To: alice@example.org\n\
+             Subject: Successful Mail Delivery Report\n\
+             Message-ID: <dsn-{from_domain}@{from_domain}>\n\
+             Date: Sun, 22 Mar 2020 22:40:00 +0000\n\
+             Content-Type: multipart/report; report-type=delivery-status; boundary=\"B\"\n\
+             \n\
+             --B\n\
+             Content-Type: text/plain\n\
+             \n\
+             Delivered.\n\
+             --B\n\
+             Content-Type: message/delivery-status\n\
+             \n\
+             Reporting-MTA: dns; {from_domain}\n\
+             \n\
+             Final-Recipient: rfc822; bob@example.net\n\
+             Action: delivered\n\
+             Status: 2.0.0\n\
+             \n\
+             Final-Recipient: rfc822; claire@example.com\n\
+             Action: delivered\n\
+             Status: 2.0.0\n\
+             --B\n\
+             Content-Type: text/rfc822-headers\n\
+             \n\
+             Message-ID: <outgoing@example.org>\n\
+             --B--\n"
+        )
+    };
+    let (t, msg_id) = test_parse_ndn(
+        "alice@example.org",
+        "bob@example.net",
+        "outgoing@example.org",
+        dsn("example.org").as_bytes(),
+        None,
+    )
+    .await;
+    assert!(msg_id.get_delivery_status(&t).await?.is_empty());
+
+    receive_imf(&t, dsn("mx.example.net").as_bytes(), false).await?;
+    let status = msg_id.get_delivery_status(&t).await?;
+    assert_eq!(status.len(), 1);
+    assert_eq!(status[0].addr, "bob@example.net");
+    assert_eq!(status[0].action, DeliveryAction::Delivered);
+    Ok(())
 }
 
 // ndn = Non Delivery Notification
@@ -1082,6 +1182,162 @@ async fn test_classic_mailing_list() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_mailing_list_private_reply() -> Result<()> {
+    let t = TestContext::new_alice().await;
+    receive_imf(&t, DC_MAILINGLIST, false).await?;
+    let msg = t.get_last_msg().await;
+    let chat_id = msg.chat_id;
+    chat_id.accept(&t).await?;
+    assert_eq!(
+        Chat::load_from_db(&t, chat_id)
+            .await?
+            .get_mailinglist_reply_mode(),
+        MailinglistReplyMode::List
+    );
+
+    // By default, replies go to the list.
+    let mut reply = Message::new_text("Reply to the list".to_string());
+    reply.set_quote(&t, Some(&msg)).await?;
+    let sent = t.send_msg(chat_id, &mut reply).await;
+    assert!(sent.payload().contains("To: <delta@codespeak.net>\r\n"));
+
+    chat_id
+        .set_mailinglist_reply_mode(&t, MailinglistReplyMode::Sender)
+        .await?;
+    let mut reply = Message::new_text("Private reply".to_string());
+    reply.set_quote(&t, Some(&msg)).await?;
+    let sent = t.send_msg(chat_id, &mut reply).await;
+    assert!(sent.payload().contains("To: <bob@posteo.org>\r\n"));
+    assert!(sent
+        .payload()
+        .contains("In-Reply-To: <38942@posteo.org>\r\n"));
+
+    // Messages without a quote still go to the list.
+    let sent = t.send_text(chat_id, "Hello list").await;
+    assert!(sent.payload().contains("To: <delta@codespeak.net>\r\n"));
+
+    // If the list rewrites `From`, the `Reply-To` address is used.
+    receive_imf(
+        &t,
+        b"From: \"Charlie via delta-dev\" <delta@codespeak.net>\n\
+          Reply-To: Charlie <charlie@posteo.org>\n\
+          To: delta@codespeak.net\n\
+          Subject: Re: [delta-dev] DC is nice!\n\
+          Message-ID: <38944@posteo.org>\n\
+          List-ID: \"discussions about and around https://delta.chat developments\" <delta.codespeak.net>\n\
+          List-Post: <mailto:delta@codespeak.net>\n\
+          Precedence: list\n\
+          Date: Sun, 22 Mar 2020 22:38:57 +0000\n\
+          \n\
+          body 5\n",
+        false,
+    )
+    .await?;
+    let msg = t.get_last_msg().await;
+    assert_eq!(msg.chat_id, chat_id);
+    assert_eq!(msg.param.get(Param::ReplyTo), Some("charlie@posteo.org"));
+    let mut reply = Message::new_text("Private reply".to_string());
+    reply.set_quote(&t, Some(&msg)).await?;
+    let sent = t.send_msg(chat_id, &mut reply).await;
+    assert!(sent.payload().contains("To: <charlie@posteo.org>\r\n"));
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_mailing_list_unsubscribe() -> Result<()> {
+    let t = TestContext::new_alice().await;
+    receive_imf(
+        &t,
+        b"From: Bob <bob@posteo.org>\n\
+          To: delta@codespeak.net\n\
+          Subject: [delta-dev] Welcome\n\
+          Message-ID: <38945@posteo.org>\n\
+          List-ID: \"discussions about and around https://delta.chat developments\" <delta.codespeak.net>\n\
+          List-Post: <mailto:delta@codespeak.net>\n\
+          List-Subscribe: <mailto:delta-request@codespeak.net?subject=subscribe>\n\
+          List-Unsubscribe: <mailto:delta-request@codespeak.net?subject=unsubscribe>,\n \
+          <https://codespeak.net/unsubscribe/delta>\n\
+          Precedence: list\n\
+          Date: Sun, 22 Mar 2020 22:37:57 +0000\n\
+          \n\
+          body\n",
+        false,
+    )
+    .await?;
+    let chat_id = t.get_last_msg().await.chat_id;
+    let chat = Chat::load_from_db(&t, chat_id).await?;
+    assert!(chat.is_mailing_list());
+    assert!(chat.can_unsubscribe());
+
+    // Without `List-Unsubscribe-Post`, the `mailto:` URI is used
+    // even for not yet accepted lists.
+    chat_id.unsubscribe(&t).await?;
+    let sent = t.pop_sent_msg().await;
+    assert!(sent
+        .payload()
+        .contains("To: <delta-request@codespeak.net>\r\n"));
+    assert!(sent.payload().contains("Subject: unsubscribe\r\n"));
+    assert_eq!(
+        t.get_last_msg_in(chat_id).await.get_text(),
+        stock_str::mailinglist_unsubscribed(&t).await
+    );
+
+    chat_id.subscribe(&t).await?;
+    let sent = t.pop_sent_msg().await;
+    assert!(sent
+        .payload()
+        .contains("To: <delta-request@codespeak.net>\r\n"));
+    assert!(sent.payload().contains("Subject: subscribe\r\n"));
+
+    // Normal lists can't be unsubscribed from.
+    receive_imf(&t, DC_MAILINGLIST, false).await?;
+    let chat = Chat::load_from_db(&t, t.get_last_msg().await.chat_id).await?;
+    assert!(!chat.can_unsubscribe());
+    assert!(chat.id.unsubscribe(&t).await.is_err());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_mailing_list_unsubscribe_requires_dkim() -> Result<()> {
+    let t = TestContext::new_alice().await;
+    t.set_config(Config::AuthservIdCandidates, Some("example.org"))
+        .await?;
+    let mail = |dkim: &str, id: u32| {
+        format!(
+            "Authentication-Results: example.org; dkim={dkim}\n\
+             From: Bob <bob@posteo.org>\n\
+             To: delta@codespeak.net\n\
+             Subject: [delta-dev] Welcome\n\
+             Message-ID: <{id}@posteo.org>\n\
+             List-ID: <delta.codespeak.net>\n\
+             List-Post: <mailto:delta@codespeak.net>\n\
+             List-Unsubscribe: <https://codespeak.net/unsubscribe/delta>\n\
+             List-Unsubscribe-Post: List-Unsubscribe=One-Click\n\
+             Precedence: list\n\
+             Date: Sun, 22 Mar 2020 22:37:57 +0000\n\
+             \n\
+             body\n"
+        )
+    };
+
+    // Forged mail can't make us POST to arbitrary URLs.
+    receive_imf(&t, mail("fail", 1).as_bytes(), false).await?;
+    let chat = Chat::load_from_db(&t, t.get_last_msg().await.chat_id).await?;
+    assert!(chat.is_mailing_list());
+    assert!(!chat.can_unsubscribe());
+    assert!(chat.param.get(Param::ListUnsubscribe).is_none());
+
+    receive_imf(&t, mail("pass header.d=posteo.org", 2).as_bytes(), false).await?;
+    let chat = Chat::load_from_db(&t, chat.id).await?;
+    assert!(chat.can_unsubscribe());
+    assert_eq!(
+        chat.param.get(Param::ListUnsubscribe),
+        Some("https://codespeak.net/unsubscribe/delta")
+    );
+    Ok(())
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn test_other_device_writes_to_mailinglist() -> Result<()> {
     let t = TestContext::new_alice().await;
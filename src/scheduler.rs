@@ -1,4 +1,3 @@
-use std::cmp;
 use std::iter::{self, once};
 use std::num::NonZeroUsize;
 use std::sync::atomic::Ordering;
@@ -18,15 +17,21 @@
 use crate::download::{download_msg, DownloadState};
 use crate::ephemeral::{self, delete_expired_imap_messages};
 use crate::events::EventType;
+use crate::health;
 use crate::imap::{session::Session, FolderMeaning, Imap};
 use crate::location;
 use crate::log::LogExt;
 use crate::message::MsgId;
-use crate::smtp::{send_smtp_messages, Smtp};
+use crate::net::{jmap, NetworkProfile};
+use crate::push;
+use crate::smtp::{self, send_smtp_messages, Smtp};
 use crate::sql;
-use crate::tools::{self, duration_to_str, maybe_add_time_based_warnings, time, time_elapsed};
+use crate::tools::{
+    self, duration_to_str, maybe_add_time_based_warnings, next_backoff, time, time_elapsed,
+};
 
 pub(crate) mod connectivity;
+pub(crate) mod work_queue;
 
 /// State of the IO scheduler, as stored on the [`Context`].
 ///
@@ -334,6 +339,7 @@ pub(crate) struct Scheduler {
     ephemeral_interrupt_send: Sender<()>,
     location_handle: task::JoinHandle<()>,
     location_interrupt_send: Sender<()>,
+    jmap_handle: task::JoinHandle<()>,
 
     recently_seen_loop: RecentlySeenLoop,
 }
@@ -471,8 +477,12 @@ async fn inbox_fetch_idle(ctx: &Context, imap: &mut Imap, mut session: Session)
         .await?;
     }
 
-    // Update quota no more than once a minute.
-    if ctx.quota_needs_update(60).await {
+    let background_jobs_managed = ctx.background_jobs_managed.load(Ordering::Relaxed);
+
+    // Update quota no more than once a minute
+    // unless quota updates are requested by the background job scheduler.
+    let quota_update_requested = ctx.quota_update_request.swap(false, Ordering::Relaxed);
+    if (quota_update_requested || !background_jobs_managed) && ctx.quota_needs_update(60).await {
         if let Err(err) = ctx.update_recent_quota(&mut session).await {
             warn!(ctx, "Failed to update quota: {:#}.", err);
         }
@@ -486,23 +496,32 @@ async fn inbox_fetch_idle(ctx: &Context, imap: &mut Imap, mut session: Session)
         }
     }
 
-    maybe_add_time_based_warnings(ctx).await;
+    // Periodic jobs are run by the background job scheduler of the account manager if there is one.
+    if !background_jobs_managed {
+        maybe_add_time_based_warnings(ctx).await;
+        health::maybe_add_health_warnings(ctx).await;
 
-    match ctx.get_config_i64(Config::LastHousekeeping).await {
-        Ok(last_housekeeping_time) => {
-            let next_housekeeping_time = last_housekeeping_time.saturating_add(60 * 60 * 24);
-            if next_housekeeping_time <= time() {
-                sql::housekeeping(ctx).await.log_err(ctx).ok();
+        match ctx.get_config_i64(Config::LastHousekeeping).await {
+            Ok(last_housekeeping_time) => {
+                let next_housekeeping_time = last_housekeeping_time.saturating_add(60 * 60 * 24);
+                if next_housekeeping_time <= time() {
+                    sql::housekeeping(ctx).await.log_err(ctx).ok();
+                }
             }
-        }
-        Err(err) => {
-            warn!(ctx, "Failed to get last housekeeping time: {}", err);
-        }
-    };
+            Err(err) => {
+                warn!(ctx, "Failed to get last housekeeping time: {}", err);
+            }
+        };
+    }
 
     match ctx.get_config_bool(Config::FetchedExistingMsgs).await {
         Ok(fetched_existing_msgs) => {
-            if !fetched_existing_msgs {
+            if !fetched_existing_msgs && ctx.get_network_profile().is_metered() {
+                info!(
+                    ctx,
+                    "Postponing fetching existing messages on metered network."
+                );
+            } else if !fetched_existing_msgs {
                 // Consider it done even if we fail.
                 //
                 // This operation is not critical enough to retry,
@@ -535,6 +554,9 @@ async fn inbox_fetch_idle(ctx: &Context, imap: &mut Imap, mut session: Session)
         .register_token(ctx)
         .await
         .context("Failed to register push token")?;
+    if let Err(err) = push::sync_push_registrations(ctx, &mut session).await {
+        warn!(ctx, "Failed to sync push registrations: {err:#}.");
+    }
 
     let session = fetch_idle(ctx, imap, session, FolderMeaning::Inbox).await?;
     Ok(session)
@@ -557,6 +579,7 @@ async fn fetch_idle(
         // For example, this happens if the server does not have Sent folder
         // but watching Sent folder is enabled.
         connection.connectivity.set_not_configured(ctx).await;
+        ctx.finish_sync_report(folder_meaning);
         connection.idle_interrupt_receiver.recv().await.ok();
         bail!("Cannot fetch folder {folder_meaning} because it is not configured");
     };
@@ -602,6 +625,17 @@ async fn fetch_idle(
         delete_expired_imap_messages(ctx)
             .await
             .context("delete_expired_imap_messages")?;
+
+        // Detect messages deleted by other clients while I/O was stopped.
+        if ctx.is_sync_report_pending(folder_meaning) {
+            match session.reconcile_expunged(ctx, &watch_folder).await {
+                Ok(0) => {}
+                Ok(expunged) => {
+                    ctx.add_to_sync_report(&watch_folder, |report| report.expunged += expunged)
+                }
+                Err(err) => warn!(ctx, "Failed to reconcile {watch_folder:?}: {err:#}."),
+            }
+        }
     } else if folder_config == Config::ConfiguredInboxFolder {
         ctx.last_full_folder_scan.lock().await.take();
     }
@@ -610,7 +644,11 @@ async fn fetch_idle(
     //
     // On iOS the application has strictly limited time to work in background, so we may not
     // be able to scan all folders before time is up if there are many of them.
-    if folder_config == Config::ConfiguredInboxFolder {
+    //
+    // While roaming, additional folders are not scanned at all to save traffic.
+    if folder_config == Config::ConfiguredInboxFolder
+        && ctx.get_network_profile() != NetworkProfile::Roaming
+    {
         // Only scan on the Inbox thread in order to prevent parallel scans, which might lead to duplicate messages
         match connection
             .scan_folders(ctx, &mut session)
@@ -646,6 +684,8 @@ async fn fetch_idle(
         .log_err(ctx)
         .ok();
 
+    ctx.finish_sync_report(folder_meaning);
+
     connection.connectivity.set_idle(ctx).await;
 
     ctx.emit_event(EventType::ImapInboxIdle);
@@ -771,7 +811,14 @@ async fn smtp_loop(
         loop {
             if let Err(err) = send_smtp_messages(&ctx, &mut connection).await {
                 warn!(ctx, "send_smtp_messages failed: {:#}.", err);
-                timeout = Some(timeout.unwrap_or(30));
+                if timeout.is_none() {
+                    let (initial_delay, _) = smtp_retry_delays(&ctx).await;
+                    // Add jitter so that clients failing at the same time do not retry together.
+                    timeout = Some(
+                        initial_delay
+                            .saturating_add(rand::thread_rng().gen_range(0..=initial_delay / 2)),
+                    );
+                }
             } else {
                 timeout = None;
                 let duration_until_can_send = ctx.ratelimit.read().await.until_can_send();
@@ -781,6 +828,7 @@ async fn smtp_loop(
                         "smtp got rate limited, waiting for {} until can send again",
                         duration_to_str(duration_until_can_send)
                     );
+                    ctx.metrics.count_ratelimit_delay(duration_until_can_send);
                     tokio::time::sleep(duration_until_can_send).await;
                     continue;
                 }
@@ -804,17 +852,27 @@ async fn smtp_loop(
                     "SMTP has messages to retry, planning to retry {t} seconds later."
                 );
                 let duration = std::time::Duration::from_secs(t);
+                *ctx.smtp_retry_timestamp.lock() =
+                    Some(time().saturating_add(t.try_into().unwrap_or(i64::MAX)));
                 tokio::time::timeout(duration, async {
                     idle_interrupt_receiver.recv().await.unwrap_or_default()
                 })
                 .await
                 .unwrap_or_default();
                 let slept = time_elapsed(&now).as_secs();
-                timeout = Some(cmp::max(
-                    t,
-                    slept.saturating_add(rand::thread_rng().gen_range((slept / 2)..=slept)),
-                ));
+                let (_, max_delay) = smtp_retry_delays(&ctx).await;
+                timeout = Some(next_backoff(slept, t, max_delay));
+            } else if let Some(t) = smtp::mdns_due_in(&ctx).await.log_err(&ctx).ok().flatten() {
+                // MDNs are held back for a short time to send MDNs of a chat together.
+                *ctx.smtp_retry_timestamp.lock() = None;
+                info!(ctx, "SMTP has MDNs to send {t} seconds later, waiting.");
+                tokio::time::timeout(std::time::Duration::from_secs(t), async {
+                    idle_interrupt_receiver.recv().await.unwrap_or_default()
+                })
+                .await
+                .unwrap_or_default();
             } else {
+                *ctx.smtp_retry_timestamp.lock() = None;
                 info!(ctx, "SMTP has no messages to retry, waiting for interrupt.");
                 idle_interrupt_receiver.recv().await.unwrap_or_default();
             };
@@ -832,6 +890,19 @@ async fn smtp_loop(
         .await;
 }
 
+/// Returns the initial and the maximum delay in seconds between SMTP retries.
+async fn smtp_retry_delays(ctx: &Context) -> (u64, u64) {
+    async {
+        let initial_delay = ctx.get_config_u64(Config::SmtpRetryInitialDelay).await?;
+        let max_delay = ctx.get_config_u64(Config::SmtpRetryMaxDelay).await?;
+        anyhow::Ok((initial_delay, max_delay))
+    }
+    .await
+    .context("Failed to get SMTP retry delays")
+    .log_err(ctx)
+    .unwrap_or((30, 86400))
+}
+
 impl Scheduler {
     /// Start the scheduler.
     pub async fn start(ctx: &Context) -> Result<Self> {
@@ -844,6 +915,19 @@ pub async fn start(ctx: &Context) -> Result<Self> {
         let mut oboxes = Vec::new();
         let mut start_recvs = Vec::new();
 
+        let mut watched_oboxes = Vec::new();
+        for (meaning, should_watch) in [
+            (FolderMeaning::Mvbox, ctx.should_watch_mvbox().await),
+            (FolderMeaning::Sent, ctx.should_watch_sentbox().await),
+        ] {
+            if should_watch? {
+                watched_oboxes.push(meaning);
+            }
+        }
+        let mut watched = vec![FolderMeaning::Inbox];
+        watched.extend_from_slice(&watched_oboxes);
+        ctx.start_sync_report(watched);
+
         let (conn_state, inbox_handlers) = ImapConnectionState::new(ctx).await?;
         let (inbox_start_send, inbox_start_recv) = oneshot::channel();
         let handle = {
@@ -857,22 +941,17 @@ pub async fn start(ctx: &Context) -> Result<Self> {
         };
         start_recvs.push(inbox_start_recv);
 
-        for (meaning, should_watch) in [
-            (FolderMeaning::Mvbox, ctx.should_watch_mvbox().await),
-            (FolderMeaning::Sent, ctx.should_watch_sentbox().await),
-        ] {
-            if should_watch? {
-                let (conn_state, handlers) = ImapConnectionState::new(ctx).await?;
-                let (start_send, start_recv) = oneshot::channel();
-                let ctx = ctx.clone();
-                let handle = task::spawn(simple_imap_loop(ctx, start_send, handlers, meaning));
-                oboxes.push(SchedBox {
-                    meaning,
-                    conn_state,
-                    handle,
-                });
-                start_recvs.push(start_recv);
-            }
+        for meaning in watched_oboxes {
+            let (conn_state, handlers) = ImapConnectionState::new(ctx).await?;
+            let (start_send, start_recv) = oneshot::channel();
+            let ctx = ctx.clone();
+            let handle = task::spawn(simple_imap_loop(ctx, start_send, handlers, meaning));
+            oboxes.push(SchedBox {
+                meaning,
+                conn_state,
+                handle,
+            });
+            start_recvs.push(start_recv);
         }
 
         let smtp_handle = {
@@ -895,6 +974,13 @@ pub async fn start(ctx: &Context) -> Result<Self> {
             })
         };
 
+        let jmap_handle = {
+            let ctx = ctx.clone();
+            task::spawn(async move {
+                jmap::jmap_loop(&ctx).await;
+            })
+        };
+
         let recently_seen_loop = RecentlySeenLoop::new(ctx.clone());
 
         let res = Self {
@@ -906,6 +992,7 @@ pub async fn start(ctx: &Context) -> Result<Self> {
             ephemeral_interrupt_send,
             location_handle,
             location_interrupt_send,
+            jmap_handle,
             recently_seen_loop,
         };
 
@@ -994,6 +1081,8 @@ pub(crate) async fn stop(self, context: &Context) {
         self.ephemeral_handle.await.ok();
         self.location_handle.abort();
         self.location_handle.await.ok();
+        self.jmap_handle.abort();
+        self.jmap_handle.await.ok();
         self.recently_seen_loop.abort().await;
     }
 }
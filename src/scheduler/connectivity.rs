@@ -6,10 +6,13 @@
 use humansize::{format_size, BINARY};
 use tokio::sync::Mutex;
 
+use crate::diagnostics::{sanitize_connection_error, ConnectionDiagnostics};
 use crate::events::EventType;
 use crate::imap::{scan_folders::get_watched_folder_configs, FolderMeaning};
+use crate::net::NetworkProfile;
 use crate::quota::{QUOTA_ERROR_THRESHOLD_PERCENTAGE, QUOTA_WARN_THRESHOLD_PERCENTAGE};
 use crate::stock_str;
+use crate::tools::time;
 use crate::{context::Context, log::LogExt};
 
 use super::InnerSchedulerState;
@@ -159,19 +162,25 @@ fn all_work_done(&self) -> bool {
 }
 
 #[derive(Clone, Default)]
-pub(crate) struct ConnectivityStore(Arc<Mutex<DetailedConnectivity>>);
+pub(crate) struct ConnectivityStore {
+    state: Arc<Mutex<DetailedConnectivity>>,
+
+    /// Last error and its timestamp, kept after the connection recovers.
+    last_error: Arc<parking_lot::Mutex<Option<(String, i64)>>>,
+}
 
 impl ConnectivityStore {
     async fn set(&self, context: &Context, v: DetailedConnectivity) {
         {
-            *self.0.lock().await = v;
+            *self.state.lock().await = v;
         }
         context.emit_event(EventType::ConnectivityChanged);
     }
 
     pub(crate) async fn set_err(&self, context: &Context, e: impl ToString) {
-        self.set(context, DetailedConnectivity::Error(e.to_string()))
-            .await;
+        let e = e.to_string();
+        *self.last_error.lock() = Some((e.clone(), time()));
+        self.set(context, DetailedConnectivity::Error(e)).await;
     }
     pub(crate) async fn set_connecting(&self, context: &Context) {
         self.set(context, DetailedConnectivity::Connecting).await;
@@ -190,13 +199,13 @@ pub(crate) async fn set_idle(&self, context: &Context) {
     }
 
     async fn get_detailed(&self) -> DetailedConnectivity {
-        self.0.lock().await.deref().clone()
+        self.state.lock().await.deref().clone()
     }
     async fn get_basic(&self) -> Option<Connectivity> {
-        self.0.lock().await.to_basic()
+        self.state.lock().await.to_basic()
     }
     async fn get_all_work_done(&self) -> bool {
-        self.0.lock().await.all_work_done()
+        self.state.lock().await.all_work_done()
     }
 }
 
@@ -204,7 +213,7 @@ async fn get_all_work_done(&self) -> bool {
 /// Called during `dc_maybe_network()` to make sure that `all_work_done()`
 /// returns false immediately after `dc_maybe_network()`.
 pub(crate) async fn idle_interrupted(inbox: ConnectivityStore, oboxes: Vec<ConnectivityStore>) {
-    let mut connectivity_lock = inbox.0.lock().await;
+    let mut connectivity_lock = inbox.state.lock().await;
     // For the inbox, we also have to set the connectivity to InterruptingIdle if it was
     // NotConfigured before: If all folders are NotConfigured, dc_get_connectivity()
     // returns Connected. But after dc_maybe_network(), dc_get_connectivity() must not
@@ -218,7 +227,7 @@ pub(crate) async fn idle_interrupted(inbox: ConnectivityStore, oboxes: Vec<Conne
     drop(connectivity_lock);
 
     for state in oboxes {
-        let mut connectivity_lock = state.0.lock().await;
+        let mut connectivity_lock = state.state.lock().await;
         if *connectivity_lock == DetailedConnectivity::Idle {
             *connectivity_lock = DetailedConnectivity::InterruptingIdle;
         }
@@ -232,7 +241,7 @@ pub(crate) async fn idle_interrupted(inbox: ConnectivityStore, oboxes: Vec<Conne
 /// after `maybe_network_lost()` was called.
 pub(crate) async fn maybe_network_lost(context: &Context, stores: Vec<ConnectivityStore>) {
     for store in &stores {
-        let mut connectivity_lock = store.0.lock().await;
+        let mut connectivity_lock = store.state.lock().await;
         if !matches!(
             *connectivity_lock,
             DetailedConnectivity::Uninitialized
@@ -248,7 +257,7 @@ pub(crate) async fn maybe_network_lost(context: &Context, stores: Vec<Connectivi
 
 impl fmt::Debug for ConnectivityStore {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if let Ok(guard) = self.0.try_lock() {
+        if let Ok(guard) = self.state.try_lock() {
             write!(f, "ConnectivityStore {:?}", &*guard)
         } else {
             write!(f, "ConnectivityStore [LOCKED]")
@@ -294,6 +303,46 @@ pub async fn get_connectivity(&self) -> Connectivity {
             .unwrap_or(Connectivity::Connected)
     }
 
+    /// Returns the state and the last error of each connection.
+    pub(crate) async fn get_connection_diagnostics(&self) -> Vec<ConnectionDiagnostics> {
+        let lock = self.scheduler.inner.read().await;
+        let stores: Vec<(String, ConnectivityStore)> = match *lock {
+            InnerSchedulerState::Started(ref sched) => sched
+                .boxes()
+                .map(|b| {
+                    (
+                        format!("IMAP {}", b.meaning),
+                        b.conn_state.state.connectivity.clone(),
+                    )
+                })
+                .chain(once((
+                    "SMTP".to_string(),
+                    sched.smtp.state.connectivity.clone(),
+                )))
+                .collect(),
+            _ => return Vec::new(),
+        };
+        drop(lock);
+
+        let mut connections = Vec::new();
+        for (name, store) in stores {
+            let state = match store.get_detailed().await {
+                DetailedConnectivity::Error(err) => {
+                    format!("Error: {}", sanitize_connection_error(&err))
+                }
+                detailed => format!("{detailed:?}"),
+            };
+            let last_error = store.last_error.lock().clone();
+            connections.push(ConnectionDiagnostics {
+                name,
+                state,
+                last_error_timestamp: last_error.as_ref().map(|(_, timestamp)| *timestamp),
+                last_error: last_error.map(|(err, _)| sanitize_connection_error(&err)),
+            });
+        }
+        connections
+    }
+
     /// Get an overview of the current connectivity, and possibly more statistics.
     /// Meant to give the user more insight about the current status than
     /// the basic connectivity info returned by dc_get_connectivity(); show this
@@ -433,6 +482,22 @@ pub async fn get_connectivity_html(&self) -> Result<String> {
         ret += &*escaper::encode_minimal(&detailed.to_string_smtp(self).await);
         ret += "</li></ul>";
 
+        // =============================================================================================
+        // Add e.g.
+        //                              Network
+        //                                Metered, large messages are downloaded on demand
+        // =============================================================================================
+
+        ret += "<h3>Network</h3><ul><li>";
+        ret += match self.get_network_profile() {
+            NetworkProfile::Unmetered => "Unmetered",
+            NetworkProfile::Metered => "Metered, large messages are downloaded on demand",
+            NetworkProfile::Roaming => {
+                "Roaming, large messages are downloaded on demand and webxdc updates are delayed"
+            }
+        };
+        ret += "</li></ul>";
+
         // =============================================================================================
         // Add e.g.
         //                              Storage on testrun.org
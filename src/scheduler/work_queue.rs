@@ -0,0 +1,241 @@
+//! # Prioritized work queue for time-limited background fetch.
+//!
+//! Operating systems give apps only a short time to run in the background,
+//! e.g. iOS gives about 30 seconds.
+//! Background fetch puts the work into a [`WorkQueue`]
+//! and does the most user-visible work first,
+//! so new messages are shown even if the process is killed
+//! before all the work is done.
+
+use anyhow::{Context as _, Result};
+use tokio::time::Instant;
+
+use super::convert_folder_meaning;
+use crate::config::Config;
+use crate::constants::DC_BACKGROUND_FETCH_QUOTA_CHECK_RATELIMIT;
+use crate::context::Context;
+use crate::imap::{session::Session, FolderMeaning, Imap};
+use crate::log::LogExt;
+use crate::smtp::{send_mdns, Smtp};
+use crate::sql;
+use crate::tools::time;
+
+/// Priority of background work.
+///
+/// Work with lower value is done first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum WorkPriority {
+    /// Fetching new messages.
+    NewMessages,
+
+    /// Sending queued read receipts.
+    Mdns,
+
+    /// Moving and deleting messages on the server.
+    MoveDelete,
+
+    /// Quota update and database housekeeping.
+    Housekeeping,
+}
+
+/// Single item of background work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Work {
+    /// Fetch new messages from the folder.
+    FetchNewMessages(FolderMeaning),
+
+    /// Send queued MDNs.
+    SendMdns,
+
+    /// Move and delete messages in the folder as planned in the `imap` table.
+    MoveDelete(FolderMeaning),
+
+    /// Update quota and run housekeeping if it is due.
+    Housekeeping,
+}
+
+impl Work {
+    /// Returns the priority of the work item.
+    pub(crate) fn priority(self) -> WorkPriority {
+        match self {
+            Work::FetchNewMessages(_) => WorkPriority::NewMessages,
+            Work::SendMdns => WorkPriority::Mdns,
+            Work::MoveDelete(_) => WorkPriority::MoveDelete,
+            Work::Housekeeping => WorkPriority::Housekeeping,
+        }
+    }
+}
+
+/// Queue of background work ordered by priority.
+///
+/// Work items with the same priority are done
+/// in the order they were added.
+#[derive(Debug, Default)]
+pub(crate) struct WorkQueue {
+    items: Vec<Work>,
+}
+
+impl WorkQueue {
+    /// Creates an empty work queue.
+    pub(crate) fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds a work item to the queue.
+    ///
+    /// Does nothing if the same work item is already queued.
+    pub(crate) fn push(&mut self, work: Work) {
+        if !self.items.contains(&work) {
+            self.items.push(work);
+        }
+    }
+
+    /// Removes and returns the work item with the highest priority.
+    pub(crate) fn pop(&mut self) -> Option<Work> {
+        // `min_by_key` returns the first of equal elements,
+        // so items with the same priority are returned in FIFO order.
+        let (index, _) = self
+            .items
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, work)| work.priority())?;
+        Some(self.items.remove(index))
+    }
+
+    /// Returns the number of queued work items.
+    pub(crate) fn len(&self) -> usize {
+        self.items.len()
+    }
+}
+
+/// Does the queued work in the order of priority
+/// until the queue is empty or the `deadline` is reached.
+///
+/// Work item that is in progress when the deadline is reached is cancelled.
+/// Returns the number of work items that were not done.
+pub(crate) async fn run_work_queue(
+    context: &Context,
+    imap: &mut Imap,
+    session: &mut Session,
+    mut queue: WorkQueue,
+    deadline: Instant,
+) -> Result<usize> {
+    while let Some(work) = queue.pop() {
+        if Instant::now() >= deadline {
+            return Ok(queue.len() + 1);
+        }
+
+        match tokio::time::timeout_at(deadline, do_work(context, imap, session, work)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(err)) => {
+                if work.priority() == WorkPriority::NewMessages {
+                    // Failure to fetch likely means the connection is broken,
+                    // there is no point in doing the rest of the work.
+                    return Err(err);
+                }
+                warn!(context, "Background work {work:?} failed: {err:#}.");
+            }
+            Err(_) => {
+                info!(
+                    context,
+                    "Background work {work:?} cancelled at the deadline."
+                );
+                return Ok(queue.len() + 1);
+            }
+        }
+    }
+    Ok(0)
+}
+
+async fn do_work(
+    context: &Context,
+    imap: &mut Imap,
+    session: &mut Session,
+    work: Work,
+) -> Result<()> {
+    match work {
+        Work::FetchNewMessages(folder_meaning) => {
+            if let Some((_folder_config, folder)) =
+                convert_folder_meaning(context, folder_meaning).await?
+            {
+                let msgs_fetched = imap
+                    .fetch_new_messages(context, session, &folder, folder_meaning, false)
+                    .await
+                    .context("fetch_new_messages")?;
+                if msgs_fetched && context.get_config_delete_device_after().await?.is_some() {
+                    // New messages were fetched and shall be deleted later.
+                    context.scheduler.interrupt_ephemeral_task().await;
+                }
+            }
+        }
+        Work::SendMdns => {
+            let mut smtp = Smtp::new();
+            // The app may be suspended soon, so MDNs are not held back for batching.
+            let res = send_mdns(context, &mut smtp, true).await;
+            smtp.disconnect();
+            res.context("send_mdns")?;
+        }
+        Work::MoveDelete(folder_meaning) => {
+            if let Some((_folder_config, folder)) =
+                convert_folder_meaning(context, folder_meaning).await?
+            {
+                session
+                    .move_delete_messages(context, &folder)
+                    .await
+                    .context("move_delete_messages")?;
+            }
+        }
+        Work::Housekeeping => {
+            // Update quota (to send warning if full) - but only check it once in a while.
+            if context
+                .quota_needs_update(DC_BACKGROUND_FETCH_QUOTA_CHECK_RATELIMIT)
+                .await
+            {
+                if let Err(err) = context.update_recent_quota(session).await {
+                    warn!(context, "Failed to update quota: {err:#}.");
+                }
+            }
+
+            let last_housekeeping_time = context.get_config_i64(Config::LastHousekeeping).await?;
+            if last_housekeeping_time.saturating_add(60 * 60 * 24) <= time() {
+                sql::housekeeping(context).await.log_err(context).ok();
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_work_queue_order() {
+        let mut queue = WorkQueue::new();
+        assert_eq!(queue.len(), 0);
+
+        queue.push(Work::Housekeeping);
+        queue.push(Work::MoveDelete(FolderMeaning::Inbox));
+        queue.push(Work::SendMdns);
+        queue.push(Work::FetchNewMessages(FolderMeaning::Inbox));
+        queue.push(Work::MoveDelete(FolderMeaning::Mvbox));
+        queue.push(Work::FetchNewMessages(FolderMeaning::Mvbox));
+        queue.push(Work::FetchNewMessages(FolderMeaning::Inbox));
+        assert_eq!(queue.len(), 6);
+
+        assert_eq!(
+            queue.pop(),
+            Some(Work::FetchNewMessages(FolderMeaning::Inbox))
+        );
+        assert_eq!(
+            queue.pop(),
+            Some(Work::FetchNewMessages(FolderMeaning::Mvbox))
+        );
+        assert_eq!(queue.pop(), Some(Work::SendMdns));
+        assert_eq!(queue.pop(), Some(Work::MoveDelete(FolderMeaning::Inbox)));
+        assert_eq!(queue.pop(), Some(Work::MoveDelete(FolderMeaning::Mvbox)));
+        assert_eq!(queue.pop(), Some(Work::Housekeeping));
+        assert_eq!(queue.pop(), None);
+        assert_eq!(queue.len(), 0);
+    }
+}
@@ -1,5 +1,7 @@
 //! Implementation of [SecureJoin protocols](https://securejoin.delta.chat/).
 
+use std::time::Duration;
+
 use anyhow::{ensure, Context as _, Error, Result};
 use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 
@@ -17,23 +19,30 @@
 use crate::message::{Message, Viewtype};
 use crate::mimeparser::{MimeMessage, SystemMessage};
 use crate::param::Param;
+use crate::peer_channels;
 use crate::peerstate::Peerstate;
-use crate::qr::check_qr;
+use crate::qr::{check_qr, Qr};
+use crate::receive_imf::receive_imf;
 use crate::securejoin::bob::JoinerProgress;
 use crate::stock_str;
 use crate::sync::Sync::*;
 use crate::token;
-use crate::tools::time;
+use crate::tools::{create_id, time};
 
 mod bob;
 mod bobstate;
+pub(crate) mod invite_words;
 mod qrinvite;
 
 pub(crate) use bobstate::BobState;
+use invite_words::InviteWords;
 use qrinvite::QrInvite;
 
 use crate::token::Namespace;
 
+/// How long the joiner waits for the inviter to join the local network channel.
+const LOCAL_NETWORK_JOIN_TIMEOUT: Duration = Duration::from_secs(5);
+
 fn inviter_progress(context: &Context, contact_id: ContactId, progress: usize) {
     debug_assert!(
         progress <= 1000,
@@ -50,6 +59,67 @@ fn inviter_progress(context: &Context, contact_id: ContactId, progress: usize) {
 /// With `group` set to `None` this generates a setup-contact QR code, with `group` set to a
 /// [`ChatId`] generates a join-group QR code for the given chat.
 pub async fn get_securejoin_qr(context: &Context, group: Option<ChatId>) -> Result<String> {
+    get_securejoin_qr_ex(context, group, InviteTokens::Shared).await
+}
+
+/// Generates a one-time join-group QR code which requires approval of the inviter.
+///
+/// The QR code can be used by a single joiner only.
+/// Instead of adding the joiner to the group automatically,
+/// [`EventType::SecurejoinApprovalRequest`] is emitted
+/// and the joiner is added after [`approve_join_request`] is called.
+pub async fn get_securejoin_qr_with_approval(context: &Context, group: ChatId) -> Result<String> {
+    get_securejoin_qr_ex(context, Some(group), InviteTokens::OneTime).await
+}
+
+/// Generates a join-group QR code with an expiration time and/or a use limit.
+///
+/// `expires` is the timestamp after which the QR code is not accepted anymore,
+/// `max_uses` is the number of contacts that may join the group using the QR code.
+/// `None` means no limit.
+///
+/// Each call creates a new invite which can be revoked individually,
+/// see [`get_group_invites`] and [`revoke_group_invite`].
+/// As the tokens are not synchronized, only this device answers the QR code.
+pub async fn get_securejoin_qr_limited(
+    context: &Context,
+    group: ChatId,
+    expires: Option<i64>,
+    max_uses: Option<u32>,
+) -> Result<String> {
+    if let Some(expires) = expires {
+        ensure!(expires > time(), "Expiration time {expires} is in the past");
+    }
+    ensure!(max_uses != Some(0), "Use limit must be positive");
+    get_securejoin_qr_ex(
+        context,
+        Some(group),
+        InviteTokens::Limited {
+            expires: expires.unwrap_or_default(),
+            max_uses: max_uses.unwrap_or_default(),
+        },
+    )
+    .await
+}
+
+/// Kind of tokens a Secure Join QR code is generated with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InviteTokens {
+    /// Reusable tokens, synchronized to other devices.
+    Shared,
+
+    /// One-time tokens which require approval of the inviter.
+    OneTime,
+
+    /// Tokens with an expiration time and a use limit, zero meaning no limit.
+    Limited { expires: i64, max_uses: u32 },
+}
+
+async fn get_securejoin_qr_ex(
+    context: &Context,
+    group: Option<ChatId>,
+    tokens: InviteTokens,
+) -> Result<String> {
     /*=======================================================
     ====             Alice - the inviter side            ====
     ====   Step 1 in "Setup verified contact" protocol   ====
@@ -70,16 +140,58 @@ pub async fn get_securejoin_qr(context: &Context, group: Option<ChatId>) -> Resu
             );
             Some(chat)
         }
-        None => None,
+        None => {
+            ensure!(
+                tokens == InviteTokens::Shared,
+                "Can't generate one-time or limited SecureJoin QR code without a group"
+            );
+            None
+        }
     };
     let grpid = chat.as_ref().map(|c| c.grpid.as_str());
-    let sync_token = token::lookup(context, Namespace::InviteNumber, grpid)
-        .await?
-        .is_none();
-    // invitenumber will be used to allow starting the handshake,
-    // auth will be used to verify the fingerprint
-    let invitenumber = token::lookup_or_new(context, Namespace::InviteNumber, grpid).await?;
-    let auth = token::lookup_or_new(context, Namespace::Auth, grpid).await?;
+    let (invitenumber, auth, sync_token) = match tokens {
+        InviteTokens::OneTime => {
+            // One-time tokens are not reused and not synchronized,
+            // the request is approved on this device.
+            let invitenumber = create_id();
+            let auth = create_id();
+            token::save(context, Namespace::OneTimeAuth, grpid, &auth).await?;
+            token::save(
+                context,
+                Namespace::OneTimeInviteNumber,
+                Some(&auth),
+                &invitenumber,
+            )
+            .await?;
+            (invitenumber, auth, false)
+        }
+        InviteTokens::Limited { expires, max_uses } => {
+            // Uses are counted on this device, so the tokens are not synchronized either.
+            let invitenumber = create_id();
+            let auth = create_id();
+            token::save_limited_invite(
+                context,
+                grpid.unwrap_or_default(),
+                &invitenumber,
+                &auth,
+                expires,
+                max_uses,
+            )
+            .await?;
+            (invitenumber, auth, false)
+        }
+        InviteTokens::Shared => {
+            let sync_token = token::lookup(context, Namespace::InviteNumber, grpid)
+                .await?
+                .is_none();
+            // invitenumber will be used to allow starting the handshake,
+            // auth will be used to verify the fingerprint
+            let invitenumber =
+                token::lookup_or_new(context, Namespace::InviteNumber, grpid).await?;
+            let auth = token::lookup_or_new(context, Namespace::Auth, grpid).await?;
+            (invitenumber, auth, sync_token)
+        }
+    };
     let self_addr = context.get_primary_self_addr().await?;
     let self_name = context
         .get_config(Config::Displayname)
@@ -93,6 +205,18 @@ pub async fn get_securejoin_qr(context: &Context, group: Option<ChatId>) -> Resu
     let self_name_urlencoded =
         utf8_percent_encode(&self_name, NON_ALPHANUMERIC_WITHOUT_DOT).to_string();
 
+    // parameters used: p=d=
+    let local_params = match get_local_network_params(context, &invitenumber).await {
+        Ok(params) => params,
+        Err(err) => {
+            warn!(
+                context,
+                "Failed to join local Secure-Join channel: {err:#}."
+            );
+            String::new()
+        }
+    };
+
     let qr = if let Some(chat) = chat {
         // parameters used: a=g=x=i=s=
         let group_name = chat.get_name();
@@ -104,13 +228,14 @@ pub async fn get_securejoin_qr(context: &Context, group: Option<ChatId>) -> Resu
             context.scheduler.interrupt_inbox().await;
         }
         format!(
-            "https://i.delta.chat/#{}&a={}&g={}&x={}&i={}&s={}",
+            "https://i.delta.chat/#{}&a={}&g={}&x={}&i={}&s={}{}",
             fingerprint.hex(),
             self_addr_urlencoded,
             &group_name_urlencoded,
             &chat.grpid,
             &invitenumber,
             &auth,
+            &local_params,
         )
     } else {
         // parameters used: a=n=i=s=
@@ -119,12 +244,13 @@ pub async fn get_securejoin_qr(context: &Context, group: Option<ChatId>) -> Resu
             context.scheduler.interrupt_inbox().await;
         }
         format!(
-            "https://i.delta.chat/#{}&a={}&n={}&i={}&s={}",
+            "https://i.delta.chat/#{}&a={}&n={}&i={}&s={}{}",
             fingerprint.hex(),
             self_addr_urlencoded,
             self_name_urlencoded,
             &invitenumber,
             &auth,
+            &local_params,
         )
     };
 
@@ -132,6 +258,63 @@ pub async fn get_securejoin_qr(context: &Context, group: Option<ChatId>) -> Resu
     Ok(qr)
 }
 
+/// Generates a word-encoded setup-contact invite code.
+///
+/// The code consists of the own address followed by 24 words
+/// and can be dictated or typed in by hand instead of scanning a QR code.
+/// It is accepted by [`check_qr`] and [`join_securejoin`] like a setup-contact QR code.
+///
+/// Each call creates new tokens which are valid for at least two days
+/// and are deleted during housekeeping after that.
+/// Unlike the tokens of QR codes they are not synchronized to other devices,
+/// so the code can only be answered by this device.
+pub async fn get_securejoin_invite_words(context: &Context) -> Result<String> {
+    ensure_secret_key_exists(context).await.ok();
+    let self_addr = context.get_primary_self_addr().await?;
+    let fingerprint = get_self_fingerprint(context).await?;
+    let invite = InviteWords::new(fingerprint, time())?;
+    let words = invite.encode()?;
+    token::save(
+        context,
+        Namespace::InviteWordsNumber,
+        None,
+        &invite.invitenumber_token(),
+    )
+    .await?;
+    token::save(
+        context,
+        Namespace::InviteWordsAuth,
+        None,
+        &invite.auth_token(),
+    )
+    .await?;
+
+    info!(context, "Generated invite code.");
+    Ok(format!("{self_addr}: {words}"))
+}
+
+/// Joins the local network channel for `invitenumber`
+/// and returns the QR code parameters advertising this device.
+///
+/// Returns an empty string if local network handshakes are disabled.
+async fn get_local_network_params(context: &Context, invitenumber: &str) -> Result<String> {
+    let Some(node_addr) = peer_channels::get_securejoin_node_addr(context).await? else {
+        return Ok(String::new());
+    };
+    peer_channels::join_securejoin_local(context, invitenumber, None).await?;
+    let direct_addresses = node_addr
+        .direct_addresses
+        .iter()
+        .map(|addr| addr.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    Ok(format!(
+        "&p={}&d={}",
+        node_addr.node_id,
+        utf8_percent_encode(&direct_addresses, NON_ALPHANUMERIC_WITHOUT_DOT)
+    ))
+}
+
 async fn get_self_fingerprint(context: &Context) -> Result<Fingerprint> {
     let key = load_self_public_key(context)
         .await
@@ -162,9 +345,41 @@ async fn securejoin(context: &Context, qr: &str) -> Result<ChatId> {
 
     info!(context, "Requesting secure-join ...",);
     let qr_scan = check_qr(context, qr).await?;
+    let node_addr = match &qr_scan {
+        Qr::AskVerifyContact { node_addr, .. } | Qr::AskVerifyGroup { node_addr, .. } => {
+            node_addr.clone()
+        }
+        _ => None,
+    };
 
     let invite = QrInvite::try_from(qr_scan)?;
 
+    if let Some(node_addr) = node_addr {
+        // Wait for the inviter to become reachable
+        // so that the first handshake message is not lost.
+        match peer_channels::join_securejoin_local(
+            context,
+            invite.invitenumber(),
+            Some((node_addr, invite.contact_id())),
+        )
+        .await
+        {
+            Ok(Some(join_rx)) => {
+                if tokio::time::timeout(LOCAL_NETWORK_JOIN_TIMEOUT, join_rx)
+                    .await
+                    .is_err()
+                {
+                    info!(context, "Inviter is not reachable on the local network.");
+                }
+            }
+            Ok(None) => {}
+            Err(err) => warn!(
+                context,
+                "Failed to join local Secure-Join channel: {err:#}."
+            ),
+        }
+    }
+
     bob::start_protocol(context, invite).await
 }
 
@@ -195,6 +410,42 @@ async fn send_alice_handshake_msg(
     Ok(())
 }
 
+/// Receives a handshake message from a local network peer channel.
+///
+/// Only Secure-Join messages are accepted.
+/// If `contact_id` is given, the message must be from this contact.
+/// When the same message arrives by email later, it is ignored as a duplicate.
+///
+/// Returns the sender contact of an accepted message.
+pub(crate) async fn receive_local_handshake_msg(
+    context: &Context,
+    data: &[u8],
+    contact_id: Option<ContactId>,
+) -> Result<Option<ContactId>> {
+    let mime_message = MimeMessage::from_bytes(context, data, None).await?;
+    if mime_message.get_header(HeaderDef::SecureJoin).is_none() {
+        warn!(
+            context,
+            "Ignoring non-Secure-Join message received over local network."
+        );
+        return Ok(None);
+    }
+    let from_addr = &mime_message.from.addr;
+    if let Some(contact_id) = contact_id {
+        if Contact::lookup_id_by_addr(context, from_addr, Origin::Unknown).await?
+            != Some(contact_id)
+        {
+            warn!(
+                context,
+                "Ignoring Secure-Join message from unexpected sender received over local network."
+            );
+            return Ok(None);
+        }
+    }
+    receive_imf(context, data, false).await?;
+    Contact::lookup_id_by_addr(context, from_addr, Origin::Unknown).await
+}
+
 /// Get an unblocked chat that can be used for info messages.
 async fn info_chat_id(context: &Context, contact_id: ContactId) -> Result<ChatId> {
     let chat_id_blocked = ChatIdBlocked::get_for_contact(context, contact_id, Blocked::Not).await?;
@@ -330,7 +581,18 @@ pub(crate) async fn handle_securejoin_handshake(
                     return Ok(HandshakeMessage::Ignore);
                 }
             };
-            if !token::exists(context, token::Namespace::InviteNumber, invitenumber).await? {
+            if !token::exists(context, token::Namespace::InviteNumber, invitenumber).await?
+                && !token::exists(context, token::Namespace::OneTimeInviteNumber, invitenumber)
+                    .await?
+                && !token::limited_invite_number_valid(context, invitenumber).await?
+                && !token::exists_since(
+                    context,
+                    token::Namespace::InviteWordsNumber,
+                    invitenumber,
+                    time() - invite_words::MAX_TOKEN_AGE,
+                )
+                .await?
+            {
                 warn!(context, "Secure-join denied (bad invitenumber).");
                 return Ok(HandshakeMessage::Ignore);
             }
@@ -399,7 +661,27 @@ pub(crate) async fn handle_securejoin_handshake(
                 );
                 return Ok(HandshakeMessage::Ignore);
             };
-            let Some(grpid) = token::auth_foreign_key(context, auth).await? else {
+            let (grpid, needs_approval, limited) = if let Some(grpid) =
+                token::auth_foreign_key(context, auth).await?
+            {
+                (grpid, false, false)
+            } else if let Some(grpid) =
+                token::foreign_key(context, Namespace::OneTimeAuth, auth).await?
+            {
+                (grpid, true, false)
+            } else if let Some(grpid) = token::limited_invite_foreign_key(context, auth).await? {
+                (grpid, false, true)
+            } else if token::exists_since(
+                context,
+                Namespace::InviteWordsAuth,
+                auth,
+                time() - invite_words::MAX_TOKEN_AGE,
+            )
+            .await?
+            {
+                // Word-encoded invite codes are for setup-contact only.
+                (String::new(), false, false)
+            } else {
                 warn!(
                     context,
                     "Ignoring {step} message because of invalid auth code."
@@ -442,18 +724,24 @@ pub(crate) async fn handle_securejoin_handshake(
             context.emit_event(EventType::ContactsChanged(Some(contact_id)));
             inviter_progress(context, contact_id, 600);
             if let Some(group_chat_id) = group_chat_id {
+                if needs_approval {
+                    // The invite can be used only once.
+                    token::delete_one_time_invite(context, auth).await?;
+                    add_join_request(context, contact_id, group_chat_id, &fingerprint).await?;
+                    return Ok(HandshakeMessage::Done);
+                }
                 // Join group.
-                secure_connection_established(
+                let added = add_joined_member(
                     context,
                     contact_id,
                     group_chat_id,
                     mime_message.timestamp_sent,
                 )
                 .await?;
-                chat::add_contact_to_chat_ex(context, Nosync, group_chat_id, contact_id, true)
-                    .await?;
-                inviter_progress(context, contact_id, 800);
-                inviter_progress(context, contact_id, 1000);
+                // Members joining again do not use up the invite.
+                if limited && added {
+                    token::use_limited_invite(context, auth).await?;
+                }
                 // IMAP-delete the message to avoid handling it by another device and adding the
                 // member twice. Another device will know the member's key from Autocrypt-Gossip.
                 Ok(HandshakeMessage::Done)
@@ -654,6 +942,200 @@ pub(crate) async fn observe_securejoin_on_other_device(
     }
 }
 
+/// Adds a contact that completed the join-group handshake to the group.
+///
+/// Returns true if the contact was not a member of the group before.
+async fn add_joined_member(
+    context: &Context,
+    contact_id: ContactId,
+    chat_id: ChatId,
+    timestamp: i64,
+) -> Result<bool> {
+    let was_member = chat::is_contact_in_chat(context, chat_id, contact_id).await?;
+    secure_connection_established(context, contact_id, chat_id, timestamp).await?;
+    chat::add_contact_to_chat_ex(context, Nosync, chat_id, contact_id, true).await?;
+    let added = !was_member && chat::is_contact_in_chat(context, chat_id, contact_id).await?;
+    inviter_progress(context, contact_id, 800);
+    inviter_progress(context, contact_id, 1000);
+    Ok(added)
+}
+
+/// Request to join a group waiting for approval of the inviter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JoinRequest {
+    /// ID of the contact that wants to join.
+    pub contact_id: ContactId,
+
+    /// ID of the group chat the contact wants to join.
+    pub chat_id: ChatId,
+
+    /// Timestamp of the request.
+    pub timestamp: i64,
+}
+
+async fn add_join_request(
+    context: &Context,
+    contact_id: ContactId,
+    chat_id: ChatId,
+    fingerprint: &Fingerprint,
+) -> Result<()> {
+    context
+        .sql
+        .execute(
+            "INSERT OR REPLACE INTO join_requests (contact_id, chat_id, timestamp) VALUES (?, ?, ?)",
+            (contact_id, chat_id, time()),
+        )
+        .await?;
+    let display_name = Contact::get_by_id(context, contact_id)
+        .await?
+        .get_display_name()
+        .to_string();
+    info!(
+        context,
+        "Contact {contact_id} requests to join {chat_id}, waiting for approval."
+    );
+    context.emit_event(EventType::SecurejoinApprovalRequest {
+        contact_id,
+        chat_id,
+        fingerprint: fingerprint.hex(),
+        display_name,
+    });
+    Ok(())
+}
+
+/// Removes a join request from the database.
+///
+/// Returns false if there is no such request.
+async fn take_join_request(
+    context: &Context,
+    contact_id: ContactId,
+    chat_id: ChatId,
+) -> Result<bool> {
+    let removed = context
+        .sql
+        .execute(
+            "DELETE FROM join_requests WHERE contact_id=? AND chat_id=?",
+            (contact_id, chat_id),
+        )
+        .await?;
+    Ok(removed > 0)
+}
+
+/// Returns join requests waiting for approval.
+pub async fn get_join_requests(context: &Context) -> Result<Vec<JoinRequest>> {
+    context
+        .sql
+        .query_map(
+            "SELECT contact_id, chat_id, timestamp FROM join_requests ORDER BY timestamp",
+            (),
+            |row| {
+                Ok(JoinRequest {
+                    contact_id: row.get(0)?,
+                    chat_id: row.get(1)?,
+                    timestamp: row.get(2)?,
+                })
+            },
+            |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+        )
+        .await
+}
+
+/// Approves a join request and adds the contact to the group.
+pub async fn approve_join_request(
+    context: &Context,
+    contact_id: ContactId,
+    chat_id: ChatId,
+) -> Result<()> {
+    ensure!(
+        take_join_request(context, contact_id, chat_id).await?,
+        "No request of contact {contact_id} to join {chat_id}"
+    );
+    add_joined_member(context, contact_id, chat_id, time()).await?;
+    Ok(())
+}
+
+/// Rejects a join request.
+///
+/// The contact is not added to the group and the one-time invite stays used.
+pub async fn reject_join_request(
+    context: &Context,
+    contact_id: ContactId,
+    chat_id: ChatId,
+) -> Result<()> {
+    ensure!(
+        take_join_request(context, contact_id, chat_id).await?,
+        "No request of contact {contact_id} to join {chat_id}"
+    );
+    info!(
+        context,
+        "Rejected request of contact {contact_id} to join {chat_id}."
+    );
+    inviter_progress(context, contact_id, 0);
+    Ok(())
+}
+
+/// Group invite created by [`get_securejoin_qr_limited`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupInvite {
+    /// ID of the invite, used to revoke it.
+    pub id: u32,
+
+    /// Timestamp of the invite creation.
+    pub timestamp: i64,
+
+    /// Timestamp after which the invite is not accepted anymore.
+    pub expires: Option<i64>,
+
+    /// Number of contacts that may join using the invite.
+    pub max_uses: Option<u32>,
+
+    /// Number of contacts that joined using the invite so far.
+    pub uses: u32,
+}
+
+/// Returns the limited invites of a group which are not expired or used up yet.
+pub async fn get_group_invites(context: &Context, chat_id: ChatId) -> Result<Vec<GroupInvite>> {
+    let chat = Chat::load_from_db(context, chat_id).await?;
+    context
+        .sql
+        .query_map(
+            "SELECT id, timestamp, expires, max_uses, uses FROM tokens
+             WHERE namespc=? AND foreign_key=?
+             AND (expires=0 OR expires>?) AND (max_uses=0 OR uses<max_uses)
+             ORDER BY timestamp, id",
+            (Namespace::LimitedAuth, &chat.grpid, time()),
+            |row| {
+                let expires: i64 = row.get(2)?;
+                let max_uses: u32 = row.get(3)?;
+                Ok(GroupInvite {
+                    id: row.get(0)?,
+                    timestamp: row.get(1)?,
+                    expires: Some(expires).filter(|&expires| expires > 0),
+                    max_uses: Some(max_uses).filter(|&max_uses| max_uses > 0),
+                    uses: row.get(4)?,
+                })
+            },
+            |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+        )
+        .await
+}
+
+/// Revokes a limited group invite, so its QR code is not accepted anymore.
+pub async fn revoke_group_invite(context: &Context, chat_id: ChatId, invite_id: u32) -> Result<()> {
+    let chat = Chat::load_from_db(context, chat_id).await?;
+    let auth: Option<String> = context
+        .sql
+        .query_get_value(
+            "SELECT token FROM tokens WHERE id=? AND namespc=? AND foreign_key=?",
+            (invite_id, Namespace::LimitedAuth, &chat.grpid),
+        )
+        .await?;
+    let auth = auth.with_context(|| format!("No invite {invite_id} for {chat_id}"))?;
+    token::delete_limited_invite(context, &auth).await?;
+    info!(context, "Revoked invite {invite_id} for {chat_id}.");
+    Ok(())
+}
+
 async fn secure_connection_established(
     context: &Context,
     contact_id: ContactId,
@@ -1590,4 +2072,224 @@ async fn test_shared_bobs_key() -> Result<()> {
         assert_eq!(bob_ids.len(), 3);
         Ok(())
     }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_secure_join_with_approval() -> Result<()> {
+        let mut tcm = TestContextManager::new();
+        let alice = &tcm.alice().await;
+        let bob = &tcm.bob().await;
+        let fiona = &tcm.fiona().await;
+
+        let alice_chat_id =
+            chat::create_group_chat(alice, ProtectionStatus::Protected, "the chat").await?;
+        let qr = get_securejoin_qr_with_approval(alice, alice_chat_id).await?;
+        // One-time invites do not replace the regular QR code.
+        assert_ne!(get_securejoin_qr(alice, Some(alice_chat_id)).await?, qr);
+
+        tcm.exec_securejoin_qr(bob, alice, &qr).await;
+        let event = alice
+            .evtracker
+            .get_matching(|evt| matches!(evt, EventType::SecurejoinApprovalRequest { .. }))
+            .await;
+        let EventType::SecurejoinApprovalRequest {
+            contact_id,
+            chat_id,
+            fingerprint,
+            ..
+        } = event
+        else {
+            unreachable!();
+        };
+        assert_eq!(chat_id, alice_chat_id);
+        assert_eq!(
+            fingerprint,
+            load_self_public_key(bob).await?.dc_fingerprint().hex()
+        );
+        assert!(!chat::is_contact_in_chat(alice, alice_chat_id, contact_id).await?);
+        assert_eq!(get_join_requests(alice).await?.len(), 1);
+
+        approve_join_request(alice, contact_id, alice_chat_id).await?;
+        assert!(get_join_requests(alice).await?.is_empty());
+        assert!(chat::is_contact_in_chat(alice, alice_chat_id, contact_id).await?);
+        let msg = bob.recv_msg(&alice.pop_sent_msg().await).await;
+        let bob_chat = Chat::load_from_db(bob, msg.chat_id).await?;
+        assert_eq!(bob_chat.typ, Chattype::Group);
+        assert!(approve_join_request(alice, contact_id, alice_chat_id)
+            .await
+            .is_err());
+
+        // The invite cannot be used again.
+        join_securejoin(fiona, &qr).await?;
+        alice.recv_msg_opt(&fiona.pop_sent_msg().await).await;
+        assert!(alice.pop_sent_msg_opt(Duration::ZERO).await.is_none());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_secure_join_limited() -> Result<()> {
+        let mut tcm = TestContextManager::new();
+        let alice = &tcm.alice().await;
+        let bob = &tcm.bob().await;
+        let fiona = &tcm.fiona().await;
+
+        let alice_chat_id =
+            chat::create_group_chat(alice, ProtectionStatus::Protected, "the chat").await?;
+        assert!(
+            get_securejoin_qr_limited(alice, alice_chat_id, Some(time() - 1), None)
+                .await
+                .is_err()
+        );
+        assert!(
+            get_securejoin_qr_limited(alice, alice_chat_id, None, Some(0))
+                .await
+                .is_err()
+        );
+
+        let expires = time() + 3600;
+        let qr = get_securejoin_qr_limited(alice, alice_chat_id, Some(expires), Some(1)).await?;
+        let revoked_qr = get_securejoin_qr_limited(alice, alice_chat_id, None, None).await?;
+        assert_ne!(qr, revoked_qr);
+        let invites = get_group_invites(alice, alice_chat_id).await?;
+        assert_eq!(invites.len(), 2);
+        assert_eq!(invites[0].expires, Some(expires));
+        assert_eq!(invites[0].max_uses, Some(1));
+        assert_eq!(invites[0].uses, 0);
+        assert_eq!(invites[1].expires, None);
+        assert_eq!(invites[1].max_uses, None);
+
+        revoke_group_invite(alice, alice_chat_id, invites[1].id).await?;
+        assert!(revoke_group_invite(alice, alice_chat_id, invites[1].id)
+            .await
+            .is_err());
+        join_securejoin(fiona, &revoked_qr).await?;
+        alice.recv_msg_opt(&fiona.pop_sent_msg().await).await;
+        assert!(alice.pop_sent_msg_opt(Duration::ZERO).await.is_none());
+
+        tcm.exec_securejoin_qr(bob, alice, &qr).await;
+        let contact_bob = alice.add_or_lookup_contact_id(bob).await;
+        assert!(chat::is_contact_in_chat(alice, alice_chat_id, contact_bob).await?);
+        // The use limit is reached.
+        assert!(get_group_invites(alice, alice_chat_id).await?.is_empty());
+
+        join_securejoin(fiona, &qr).await?;
+        alice.recv_msg_opt(&fiona.pop_sent_msg().await).await;
+        assert!(alice.pop_sent_msg_opt(Duration::ZERO).await.is_none());
+        Ok(())
+    }
+
+    /// Tests that members joining again do not use up a limited invite.
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_secure_join_limited_existing_member() -> Result<()> {
+        let mut tcm = TestContextManager::new();
+        let alice = &tcm.alice().await;
+        let bob = &tcm.bob().await;
+        let fiona = &tcm.fiona().await;
+
+        let alice_chat_id =
+            chat::create_group_chat(alice, ProtectionStatus::Unprotected, "the chat").await?;
+        let qr = get_securejoin_qr_limited(alice, alice_chat_id, None, Some(1)).await?;
+        let contact_bob = alice.add_or_lookup_contact_id(bob).await;
+        chat::add_contact_to_chat(alice, alice_chat_id, contact_bob).await?;
+
+        tcm.exec_securejoin_qr(bob, alice, &qr).await;
+        assert!(chat::is_contact_in_chat(alice, alice_chat_id, contact_bob).await?);
+        let invites = get_group_invites(alice, alice_chat_id).await?;
+        assert_eq!(invites.len(), 1);
+        assert_eq!(invites[0].uses, 0);
+
+        tcm.exec_securejoin_qr(fiona, alice, &qr).await;
+        let contact_fiona = alice.add_or_lookup_contact_id(fiona).await;
+        assert!(chat::is_contact_in_chat(alice, alice_chat_id, contact_fiona).await?);
+        assert!(get_group_invites(alice, alice_chat_id).await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_setup_contact_invite_words() -> Result<()> {
+        let mut tcm = TestContextManager::new();
+        let alice = &tcm.alice().await;
+        let bob = &tcm.bob().await;
+
+        let code = get_securejoin_invite_words(alice).await?;
+        assert!(code.starts_with("alice@example.org: "));
+        assert_eq!(code.split_whitespace().count(), 25);
+        let qr = check_qr(bob, &code).await?;
+        let Qr::AskVerifyContact { fingerprint, .. } = qr else {
+            panic!("Wrong QR code type {qr:?}");
+        };
+        assert_eq!(
+            fingerprint,
+            load_self_public_key(alice).await?.dc_fingerprint()
+        );
+        assert!(check_qr(alice, &code).await.is_err());
+
+        tcm.exec_securejoin_qr(bob, alice, &code).await;
+        let contact_alice = bob.add_or_lookup_contact(alice).await;
+        assert!(contact_alice.is_verified(bob).await?);
+        let contact_bob = alice.add_or_lookup_contact(bob).await;
+        assert!(contact_bob.is_verified(alice).await?);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_invite_words_tokens_deleted() -> Result<()> {
+        async fn count_tokens(t: &TestContext) -> Result<usize> {
+            t.sql
+                .count(
+                    "SELECT COUNT(*) FROM tokens WHERE namespc IN (?, ?)",
+                    (Namespace::InviteWordsNumber, Namespace::InviteWordsAuth),
+                )
+                .await
+        }
+
+        let alice = &TestContext::new_alice().await;
+        get_securejoin_invite_words(alice).await?;
+        assert_eq!(count_tokens(alice).await?, 2);
+
+        crate::sql::housekeeping(alice).await?;
+        assert_eq!(count_tokens(alice).await?, 2);
+
+        SystemTime::shift(Duration::from_secs(
+            (invite_words::MAX_TOKEN_AGE + 1).try_into()?,
+        ));
+        crate::sql::housekeeping(alice).await?;
+        assert_eq!(count_tokens(alice).await?, 0);
+        Ok(())
+    }
+
+    /// Tests that handshake messages received over the local network
+    /// are only accepted from the contact the channel is bound to.
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_receive_local_handshake_msg() -> Result<()> {
+        let mut tcm = TestContextManager::new();
+        let alice = &tcm.alice().await;
+        let bob = &tcm.bob().await;
+        let fiona = &tcm.fiona().await;
+
+        let qr = get_securejoin_qr(alice, None).await?;
+        join_securejoin(bob, &qr).await?;
+        let sent = bob.pop_sent_msg().await;
+        let data = sent.payload().as_bytes();
+
+        let alice_fiona_id = alice.add_or_lookup_contact_id(fiona).await;
+        let res = receive_local_handshake_msg(alice, data, Some(alice_fiona_id)).await?;
+        assert_eq!(res, None);
+        assert!(
+            Contact::lookup_id_by_addr(alice, "bob@example.net", Origin::Unknown)
+                .await?
+                .is_none()
+        );
+
+        let alice_bob_id = receive_local_handshake_msg(alice, data, None)
+            .await?
+            .context("Handshake message not accepted")?;
+        let contact_bob = Contact::get_by_id(alice, alice_bob_id).await?;
+        assert_eq!(contact_bob.get_addr(), "bob@example.net");
+        assert!(alice
+            .pop_sent_msg()
+            .await
+            .payload()
+            .contains("vc-auth-required"));
+        Ok(())
+    }
 }
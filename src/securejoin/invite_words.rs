@@ -0,0 +1,264 @@
+//! Word-encoded setup-contact invite codes.
+//!
+//! QR codes are awkward to read out over the phone,
+//! so an invite can also be given as the inviter's address followed by 24 words
+//! from the [BIP39](https://github.com/bitcoin/bips/blob/master/bip-0039.mediawiki) English word list,
+//! e.g. `alice@example.org: abandon ability able ...`.
+//!
+//! Like a 256-bit BIP39 mnemonic, the words encode 32 bytes followed by an 8-bit checksum
+//! which is the first byte of the SHA-256 hash of the 32 bytes.
+//! The 32 bytes are the key fingerprint (20 bytes), the invite number (4 bytes),
+//! the auth code (6 bytes) and the last day the code is valid
+//! as a big endian `u16` counting days since the Unix epoch.
+
+use anyhow::{ensure, Context as _, Result};
+use once_cell::sync::Lazy;
+use sha2::{Digest, Sha256};
+
+use crate::key::Fingerprint;
+
+/// Number of words in an invite code.
+pub(crate) const WORD_COUNT: usize = 24;
+
+/// Number of days after the day of creation an invite code is valid.
+pub(crate) const VALIDITY_DAYS: i64 = 2;
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Maximum age of the tokens of a valid invite code.
+///
+/// The code is valid until the end of the day it expires,
+/// which is at most one day more than [`VALIDITY_DAYS`] after its creation.
+pub(crate) const MAX_TOKEN_AGE: i64 = (VALIDITY_DAYS + 1) * SECONDS_PER_DAY;
+
+const BITS_PER_WORD: usize = 11;
+
+/// Length of the key fingerprint in bytes.
+const FINGERPRINT_LEN: usize = 20;
+
+/// Length of the encoded data without the checksum in bytes.
+const PAYLOAD_LEN: usize = 32;
+
+/// BIP39 English word list, sorted alphabetically.
+static WORDS: Lazy<Vec<&'static str>> = Lazy::new(|| {
+    include_str!("../../assets/bip39-english.txt")
+        .lines()
+        .collect()
+});
+
+/// Contents of a word-encoded invite code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct InviteWords {
+    /// Fingerprint of the inviter's key.
+    pub fingerprint: Fingerprint,
+
+    /// Random invite number.
+    pub invitenumber: [u8; 4],
+
+    /// Random auth code.
+    pub auth: [u8; 6],
+
+    /// Last day the code is valid, in days since the Unix epoch.
+    pub expires: u16,
+}
+
+impl InviteWords {
+    /// Creates an invite code with random tokens expiring [`VALIDITY_DAYS`] after `now`.
+    pub fn new(fingerprint: Fingerprint, now: i64) -> Result<Self> {
+        ensure!(
+            fingerprint.as_bytes().len() == FINGERPRINT_LEN,
+            "Invite codes only support {FINGERPRINT_LEN}-byte key fingerprints"
+        );
+        Ok(Self {
+            fingerprint,
+            invitenumber: rand::random(),
+            auth: rand::random(),
+            expires: u16::try_from(now / SECONDS_PER_DAY + VALIDITY_DAYS)?,
+        })
+    }
+
+    /// Returns the invite number as used in the Secure-Join handshake.
+    pub fn invitenumber_token(&self) -> String {
+        hex::encode(self.invitenumber)
+    }
+
+    /// Returns the auth code as used in the Secure-Join handshake.
+    pub fn auth_token(&self) -> String {
+        hex::encode(self.auth)
+    }
+
+    /// Returns true if the code is not valid anymore at `now`.
+    pub fn is_expired(&self, now: i64) -> bool {
+        now / SECONDS_PER_DAY > i64::from(self.expires)
+    }
+
+    /// Returns the space-separated words encoding the invite.
+    pub fn encode(&self) -> Result<String> {
+        let fingerprint = self.fingerprint.as_bytes();
+        ensure!(
+            fingerprint.len() == FINGERPRINT_LEN,
+            "Invite codes only support {FINGERPRINT_LEN}-byte key fingerprints, got {} bytes",
+            fingerprint.len()
+        );
+        let mut bytes = Vec::with_capacity(PAYLOAD_LEN + 1);
+        bytes.extend_from_slice(fingerprint);
+        bytes.extend_from_slice(&self.invitenumber);
+        bytes.extend_from_slice(&self.auth);
+        bytes.extend_from_slice(&self.expires.to_be_bytes());
+        let checksum = Sha256::digest(&bytes)[0];
+        bytes.push(checksum);
+
+        Ok((0..WORD_COUNT)
+            .map(|i| WORDS[read_bits(&bytes, i * BITS_PER_WORD)])
+            .collect::<Vec<_>>()
+            .join(" "))
+    }
+
+    /// Decodes the words of an invite code.
+    ///
+    /// Words may be abbreviated to their first four letters.
+    pub fn decode(words: &[&str]) -> Result<Self> {
+        ensure!(
+            words.len() == WORD_COUNT,
+            "Invite code has {} words instead of {WORD_COUNT}",
+            words.len()
+        );
+        let mut bytes = [0u8; PAYLOAD_LEN + 1];
+        for (i, word) in words.iter().enumerate() {
+            let index = word_index(word).with_context(|| format!("Unknown word {word:?}"))?;
+            write_bits(&mut bytes, i * BITS_PER_WORD, index);
+        }
+        let (payload, checksum) = bytes.split_at(PAYLOAD_LEN);
+        ensure!(
+            Sha256::digest(payload)[0] == checksum[0],
+            "Invite code checksum mismatch"
+        );
+        Ok(Self {
+            fingerprint: Fingerprint::new(payload[..FINGERPRINT_LEN].to_vec()),
+            invitenumber: payload[FINGERPRINT_LEN..24].try_into()?,
+            auth: payload[24..30].try_into()?,
+            expires: u16::from_be_bytes([payload[30], payload[31]]),
+        })
+    }
+}
+
+/// Splits an invite code into the address and the words.
+///
+/// Words may be separated by whitespace, dashes or commas,
+/// the address may be followed by a colon.
+/// Returns `None` if the text does not look like an invite code.
+pub(crate) fn split_invite_code(text: &str) -> Option<(&str, Vec<&str>)> {
+    let (addr, words) = text.trim().split_once(char::is_whitespace)?;
+    let addr = addr.trim_end_matches(':');
+    if !addr.contains('@') {
+        return None;
+    }
+    let words: Vec<&str> = words
+        .split(|c: char| c.is_whitespace() || c == '-' || c == ',')
+        .filter(|word| !word.is_empty())
+        .collect();
+    if words.len() != WORD_COUNT
+        || !words
+            .iter()
+            .all(|word| word.chars().all(|c| c.is_ascii_alphabetic()))
+    {
+        return None;
+    }
+    Some((addr, words))
+}
+
+/// Returns the index of a word in the word list.
+///
+/// As the first four letters identify a BIP39 word uniquely,
+/// abbreviations of at least four letters are accepted.
+fn word_index(word: &str) -> Option<usize> {
+    let word = word.to_ascii_lowercase();
+    match WORDS.binary_search(&word.as_str()) {
+        Ok(index) => Some(index),
+        Err(index) => {
+            // `index` is the position of the first word greater than `word`.
+            let candidate = WORDS.get(index)?;
+            (word.len() >= 4 && candidate.starts_with(&word)).then_some(index)
+        }
+    }
+}
+
+fn read_bits(bytes: &[u8], start: usize) -> usize {
+    (start..start + BITS_PER_WORD).fold(0, |acc, bit| {
+        (acc << 1) | usize::from((bytes[bit / 8] >> (7 - bit % 8)) & 1)
+    })
+}
+
+fn write_bits(bytes: &mut [u8], start: usize, value: usize) {
+    for i in 0..BITS_PER_WORD {
+        if (value >> (BITS_PER_WORD - 1 - i)) & 1 == 1 {
+            let bit = start + i;
+            bytes[bit / 8] |= 0x80 >> (bit % 8);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_invite() -> InviteWords {
+        InviteWords {
+            fingerprint: Fingerprint::new((1..=20).collect()),
+            invitenumber: [0xde, 0xad, 0xbe, 0xef],
+            auth: [1, 2, 3, 4, 5, 6],
+            expires: 20000,
+        }
+    }
+
+    #[test]
+    fn test_word_list() {
+        assert_eq!(WORDS.len(), 1 << BITS_PER_WORD);
+        assert!(WORDS.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let invite = test_invite();
+        let code = format!("alice@example.org: {}", invite.encode().unwrap());
+        let (addr, words) = split_invite_code(&code).unwrap();
+        assert_eq!(addr, "alice@example.org");
+        assert_eq!(InviteWords::decode(&words).unwrap(), invite);
+        assert_eq!(invite.invitenumber_token(), "deadbeef");
+
+        // Abbreviated and dash-separated words are accepted.
+        let abbreviated = words
+            .iter()
+            .map(|word| word.get(..4).unwrap_or(word).to_uppercase())
+            .collect::<Vec<_>>()
+            .join("-");
+        let code = format!("alice@example.org {abbreviated}");
+        let (_, words) = split_invite_code(&code).unwrap();
+        assert_eq!(InviteWords::decode(&words).unwrap(), invite);
+    }
+
+    #[test]
+    fn test_decode_checksum() {
+        let encoded = test_invite().encode().unwrap();
+        let mut words: Vec<&str> = encoded.split(' ').collect();
+        words.swap(0, 1);
+        assert!(InviteWords::decode(&words).is_err());
+        assert!(InviteWords::decode(&words[1..]).is_err());
+    }
+
+    #[test]
+    fn test_split_invite_code() {
+        assert!(split_invite_code("hello world").is_none());
+        assert!(split_invite_code("alice@example.org: abandon ability").is_none());
+        let words = ["abandon"; WORD_COUNT].join(" ");
+        assert!(split_invite_code(&format!("alice@my-domain.example: {words}")).is_some());
+    }
+
+    #[test]
+    fn test_is_expired() {
+        let invite = test_invite();
+        assert!(!invite.is_expired(20000 * SECONDS_PER_DAY));
+        assert!(!invite.is_expired(20001 * SECONDS_PER_DAY - 1));
+        assert!(invite.is_expired(20001 * SECONDS_PER_DAY));
+    }
+}
@@ -74,6 +74,7 @@ fn try_from(qr: Qr) -> Result<Self> {
                 fingerprint,
                 invitenumber,
                 authcode,
+                ..
             } => Ok(QrInvite::Contact {
                 contact_id,
                 fingerprint,
@@ -87,6 +88,7 @@ fn try_from(qr: Qr) -> Result<Self> {
                 fingerprint,
                 invitenumber,
                 authcode,
+                ..
             } => Ok(QrInvite::Group {
                 contact_id,
                 fingerprint,
@@ -3,26 +3,35 @@
 mod connect;
 pub mod send;
 
+use std::collections::HashSet;
+
 use anyhow::{bail, format_err, Context as _, Error, Result};
-use async_smtp::response::{Category, Code, Detail};
+use async_smtp::response::{Category, Code, Detail, Response};
 use async_smtp::{EmailAddress, SmtpTransport};
+use deltachat_contact_tools::addr_with_ascii_domain;
 use tokio::task;
 
 use crate::chat::{add_info_msg_with_cmd, ChatId};
 use crate::config::Config;
+use crate::constants::DC_CHAT_ID_TRASH;
 use crate::contact::{Contact, ContactId};
 use crate::context::Context;
 use crate::events::EventType;
+use crate::log::LogExt;
 use crate::login_param::prioritize_server_login_params;
 use crate::login_param::{ConfiguredLoginParam, ConfiguredServerLoginParam};
 use crate::message::Message;
-use crate::message::{self, MsgId};
+use crate::message::{self, DeliveryPath, MsgId};
 use crate::mimefactory::MimeFactory;
+use crate::mimeparser::SystemMessage;
+use crate::net::jmap;
 use crate::net::proxy::ProxyConfig;
 use crate::net::session::SessionBufStream;
+use crate::net::NetworkProfile;
+use crate::param::Param;
 use crate::scheduler::connectivity::ConnectivityStore;
 use crate::stock_str::unencrypted_email;
-use crate::tools::{self, time_elapsed};
+use crate::tools::{self, time, time_elapsed};
 
 #[derive(Default)]
 pub(crate) struct Smtp {
@@ -41,6 +50,9 @@ pub(crate) struct Smtp {
 
     /// If sending the last message failed, contains the error message.
     pub(crate) last_send_error: Option<String>,
+
+    /// JMAP client reused for submitting messages if JMAP is configured.
+    jmap: Option<jmap::JmapClient>,
 }
 
 impl Smtp {
@@ -119,7 +131,7 @@ pub async fn connect(
             return Ok(());
         }
 
-        let from = EmailAddress::new(addr.to_string())
+        let from = EmailAddress::new(addr_with_ascii_domain(addr))
             .with_context(|| format!("Invalid address {addr:?}"))?;
         self.from = Some(from);
 
@@ -128,7 +140,7 @@ pub async fn connect(
         let mut first_error = None;
         for lp in login_params {
             info!(context, "SMTP trying to connect to {}.", &lp.connection);
-            let transport = match connect::connect_and_auth(
+            let res = connect::connect_and_auth(
                 context,
                 proxy_config,
                 strict_tls,
@@ -138,8 +150,12 @@ pub async fn connect(
                 &lp.user,
                 password,
             )
-            .await
-            {
+            .await;
+            context.trace_configure_step(
+                format!("SMTP connection to {} as {}", lp.connection, lp.user),
+                &res,
+            );
+            let transport = match res {
                 Ok(transport) => transport,
                 Err(err) => {
                     warn!(context, "SMTP failed to connect and authenticate: {err:#}.");
@@ -150,6 +166,7 @@ pub async fn connect(
 
             self.transport = Some(transport);
             self.last_success = Some(tools::Time::now());
+            context.metrics.count_smtp_connection();
 
             context.emit_event(EventType::SmtpConnected(format!(
                 "SMTP-LOGIN as {} ok",
@@ -162,13 +179,71 @@ pub async fn connect(
     }
 }
 
+/// Priority class of an entry in the `smtp` table.
+///
+/// Entries of a higher class are sent first,
+/// so that a message sent by the user does not wait behind automatic traffic
+/// such as webxdc status updates or location streaming.
+/// [`Context::prioritize_queued_message`] moves a single entry above all classes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SendPriority {
+    /// Messages sent automatically without user interaction.
+    Automatic,
+
+    /// Messages sent by the user.
+    User,
+}
+
+impl SendPriority {
+    /// Returns the priority class of an outgoing message.
+    ///
+    /// Key transition messages tell contacts about a new key,
+    /// so they are not delayed behind other messages
+    /// even though they are hidden.
+    pub(crate) fn of_msg(msg: &Message) -> Self {
+        if msg.param.get_cmd() == SystemMessage::KeyTransition {
+            return SendPriority::User;
+        }
+        let automatic = msg.hidden
+            || matches!(
+                msg.param.get_cmd(),
+                SystemMessage::LocationOnly
+                    | SystemMessage::MultiDeviceSync
+                    | SystemMessage::WebxdcStatusUpdate
+                    | SystemMessage::IrohNodeAddr
+            );
+        if automatic {
+            SendPriority::Automatic
+        } else {
+            SendPriority::User
+        }
+    }
+
+    /// Returns the value of the `priority` column.
+    ///
+    /// User messages use the default value of the column,
+    /// so entries queued by older versions are treated as user messages.
+    pub(crate) fn to_i64(self) -> i64 {
+        match self {
+            SendPriority::Automatic => -1,
+            SendPriority::User => 0,
+        }
+    }
+}
+
 pub(crate) enum SendResult {
-    /// Message was sent successfully.
-    Success,
+    /// Message was sent successfully over the given transport.
+    Success(DeliveryPath),
 
     /// Permanent error, message sending has failed.
     Failure(Error),
 
+    /// Permanent error caused by a recipient address,
+    /// e.g. `550 5.1.1 User unknown`.
+    ///
+    /// Other recipients of the message may still be reachable.
+    RecipientRejected(Error),
+
     /// Temporary error, the message should be retried later.
     Retry,
 }
@@ -179,12 +254,35 @@ pub(crate) async fn smtp_send(
     recipients: &[async_smtp::EmailAddress],
     message: &str,
     smtp: &mut Smtp,
-    msg_id: Option<MsgId>,
+    request_dsn: bool,
 ) -> SendResult {
     if std::env::var(crate::DCC_MIME_DEBUG).is_ok() {
         info!(context, "SMTP-sending out mime message:\n{message}");
     }
 
+    let jmap_recipients: Vec<&str> = recipients.iter().map(|addr| addr.as_ref()).collect();
+    match jmap::send(
+        context,
+        &mut smtp.jmap,
+        &jmap_recipients,
+        message.as_bytes(),
+    )
+    .await
+    {
+        Ok(true) => return SendResult::Success(DeliveryPath::Jmap),
+        Ok(false) => {}
+        Err(jmap::SubmissionError::NotSubmitted(err)) => warn!(
+            context,
+            "JMAP submission failed, falling back to SMTP: {err:#}."
+        ),
+        Err(jmap::SubmissionError::Unknown(err)) => {
+            // Sending over SMTP could deliver the message twice.
+            warn!(context, "JMAP submission failed: {err:#}.");
+            smtp.last_send_error = Some(format!("{err:#}"));
+            return SendResult::Retry;
+        }
+    }
+
     smtp.connectivity.set_working(context).await;
 
     if let Err(err) = smtp
@@ -196,7 +294,9 @@ pub(crate) async fn smtp_send(
         return SendResult::Retry;
     }
 
-    let send_result = smtp.send(context, recipients, message.as_bytes()).await;
+    let send_result = smtp
+        .send(context, recipients, message.as_bytes(), request_dsn)
+        .await;
     smtp.last_send_error = send_result.as_ref().err().map(|e| e.to_string());
 
     let status = match send_result {
@@ -231,6 +331,12 @@ pub(crate) async fn smtp_send(
                     if maybe_transient {
                         info!(context, "Permanent error that is likely to actually be transient, postponing retry for later.");
                         SendResult::Retry
+                    } else if is_recipient_rejected(response) {
+                        info!(
+                            context,
+                            "Recipient address rejected, message sending failed."
+                        );
+                        SendResult::RecipientRejected(format_err!("Permanent SMTP error: {}", err))
                     } else {
                         info!(context, "Permanent error, message sending failed.");
                         // If we do not retry, add an info message to the chat.
@@ -298,32 +404,22 @@ pub(crate) async fn smtp_send(
             warn!(context, "Unable to load SMTP job: {err:#}.");
             SendResult::Failure(err)
         }
-        Ok(()) => SendResult::Success,
+        Ok(()) => SendResult::Success(DeliveryPath::Smtp),
     };
 
-    if let SendResult::Failure(err) = &status {
-        if let Some(msg_id) = msg_id {
-            // We couldn't send the message, so mark it as failed
-            match Message::load_from_db(context, msg_id).await {
-                Ok(mut msg) => {
-                    if let Err(err) =
-                        message::set_msg_failed(context, &mut msg, &err.to_string()).await
-                    {
-                        error!(context, "Failed to mark {msg_id} as failed: {err:#}.");
-                    }
-                }
-                Err(err) => {
-                    error!(
-                        context,
-                        "Failed to load {msg_id} to mark it as failed: {err:#}."
-                    );
-                }
-            }
-        }
-    }
     status
 }
 
+/// Returns true if a permanent error response is caused by a recipient address.
+///
+/// Enhanced status codes 5.1.x are addressing errors,
+/// see <https://tools.ietf.org/html/rfc3463#section-3.2>.
+fn is_recipient_rejected(response: &Response) -> bool {
+    response
+        .first_word()
+        .is_some_and(|word| word.starts_with("5.1."))
+}
+
 /// Sends message identified by `smtp` table rowid over SMTP connection.
 ///
 /// Removes row if the message should not be retried, otherwise increments retry count.
@@ -338,6 +434,7 @@ pub(crate) async fn send_msg_to_smtp(
         .context("SMTP connection failure")
     {
         smtp.last_send_error = Some(format!("{err:#}"));
+        set_last_error(context, rowid, smtp.last_send_error.as_deref()).await;
         return Err(err);
     }
 
@@ -345,50 +442,19 @@ pub(crate) async fn send_msg_to_smtp(
     // eventually removed from the queue by exceeding retry limit even in case of an error that
     // keeps happening early in the message sending code, e.g. failure to read the message from the
     // database.
-    context
-        .sql
-        .execute("UPDATE smtp SET retries=retries+1 WHERE id=?", (rowid,))
-        .await
-        .context("failed to update retries count")?;
-
-    let Some((body, recipients, msg_id, retries)) = context
-        .sql
-        .query_row_optional(
-            "SELECT mime, recipients, msg_id, retries FROM smtp WHERE id=?",
-            (rowid,),
-            |row| {
-                let mime: String = row.get(0)?;
-                let recipients: String = row.get(1)?;
-                let msg_id: MsgId = row.get(2)?;
-                let retries: i64 = row.get(3)?;
-                Ok((mime, recipients, msg_id, retries))
-            },
-        )
-        .await?
-    else {
+    let Some((body, recipients, msg_id, retries)) = start_retry(context, rowid).await? else {
         return Ok(());
     };
-    if retries > 6 {
-        if let Some(mut msg) = Message::load_from_db_optional(context, msg_id).await? {
-            message::set_msg_failed(context, &mut msg, "Number of retries exceeded the limit.")
-                .await?;
-        }
-        context
-            .sql
-            .execute("DELETE FROM smtp WHERE id=?", (rowid,))
-            .await
-            .context("Failed to remove message with exceeded retry limit from smtp table")?;
-        return Ok(());
-    }
     info!(
         context,
+        msg_id = msg_id;
         "Try number {retries} to send message {msg_id} (entry {rowid}) over SMTP."
     );
 
     let recipients_list = recipients
         .split(' ')
         .filter_map(
-            |addr| match async_smtp::EmailAddress::new(addr.to_string()) {
+            |addr| match async_smtp::EmailAddress::new(addr_with_ascii_domain(addr)) {
                 Ok(addr) => Some(addr),
                 Err(err) => {
                     warn!(context, "Invalid recipient: {} {:?}.", addr, err);
@@ -398,17 +464,50 @@ pub(crate) async fn send_msg_to_smtp(
         )
         .collect::<Vec<_>>();
 
-    let status = smtp_send(context, &recipients_list, body.as_str(), smtp, Some(msg_id)).await;
+    let request_dsn = Message::load_from_db_optional(context, msg_id)
+        .await?
+        .is_some_and(|msg| msg.is_dsn_requested());
+    let status = smtp_send(context, &recipients_list, body.as_str(), smtp, request_dsn).await;
 
     match status {
-        SendResult::Retry => {}
-        SendResult::Success => {
+        SendResult::Retry => {
+            set_last_error(context, rowid, smtp.last_send_error.as_deref()).await;
+        }
+        SendResult::Success(_) => {
             context
                 .sql
                 .execute("DELETE FROM smtp WHERE id=?", (rowid,))
                 .await?;
+            context.metrics.count_msg_sent();
+        }
+        SendResult::RecipientRejected(ref err) if recipients_list.len() > 1 => {
+            // The server rejected one of the recipients,
+            // retry sending to each recipient separately
+            // so that the message still reaches the others.
+            info!(
+                context,
+                "Splitting SMTP entry {rowid} to send to {} recipients separately.",
+                recipients_list.len()
+            );
+            let last_error = err.to_string();
+            context
+                .sql
+                .transaction(|transaction| {
+                    for addr in &recipients_list {
+                        transaction.execute(
+                            "INSERT INTO smtp (rfc724_mid, recipients, mime, msg_id, priority, last_error)
+                             SELECT rfc724_mid, ?, mime, msg_id, priority, ? FROM smtp WHERE id=?",
+                            (addr.as_ref(), &last_error, rowid),
+                        )?;
+                    }
+                    transaction.execute("DELETE FROM smtp WHERE id=?", (rowid,))?;
+                    Ok(())
+                })
+                .await?;
+            context.scheduler.interrupt_smtp().await;
+            return Ok(());
         }
-        SendResult::Failure(ref err) => {
+        SendResult::Failure(ref err) | SendResult::RecipientRejected(ref err) => {
             if err.to_string().contains("Invalid unencrypted mail") {
                 let res = context
                     .sql
@@ -446,12 +545,14 @@ pub(crate) async fn send_msg_to_smtp(
                 .sql
                 .execute("DELETE FROM smtp WHERE id=?", (rowid,))
                 .await?;
+            handle_failed_entry(context, msg_id, &recipients_list, err).await?;
         }
     };
 
     match status {
         SendResult::Retry => Err(format_err!("Retry")),
-        SendResult::Success => {
+        SendResult::Success(path) => {
+            msg_id.add_delivery_path(context, path).await?;
             if !context
                 .sql
                 .exists("SELECT COUNT(*) FROM smtp WHERE msg_id=?", (msg_id,))
@@ -461,19 +562,173 @@ pub(crate) async fn send_msg_to_smtp(
             }
             Ok(())
         }
-        SendResult::Failure(err) => Err(format_err!("{}", err)),
+        SendResult::Failure(err) | SendResult::RecipientRejected(err) => {
+            Err(format_err!("{}", err))
+        }
+    }
+}
+
+/// Updates the message state after sending a removed SMTP queue entry failed permanently.
+///
+/// The message is only marked as failed if it was not sent to any recipient.
+/// Otherwise the failed recipients are recorded
+/// and the message is marked as partially failed.
+pub(crate) async fn handle_failed_entry(
+    context: &Context,
+    msg_id: MsgId,
+    recipients: &[async_smtp::EmailAddress],
+    err: &anyhow::Error,
+) -> Result<()> {
+    let Some(mut msg) = Message::load_from_db_optional(context, msg_id).await? else {
+        return Ok(());
+    };
+    let error = err.to_string();
+    for addr in recipients {
+        msg_id
+            .add_failed_recipient(context, addr.as_ref(), &error)
+            .await?;
+    }
+    let sent_to_others = msg
+        .get_delivery_paths()
+        .iter()
+        .any(|path| matches!(path, DeliveryPath::Smtp | DeliveryPath::Jmap));
+    let queued = context
+        .sql
+        .exists("SELECT COUNT(*) FROM smtp WHERE msg_id=?", (msg_id,))
+        .await?;
+    if !sent_to_others && !queued {
+        message::set_msg_failed(context, &mut msg, &error).await?;
+        return Ok(());
+    }
+
+    if !msg.is_partially_failed() {
+        msg.param.set_int(Param::PartiallyFailed, 1);
+        msg.update_param(context).await?;
+    }
+    if sent_to_others && !queued {
+        msg_id.set_delivered(context).await?;
+    } else {
+        context.emit_msgs_changed(msg.chat_id, msg_id);
+    }
+    Ok(())
+}
+
+/// Quarantined SMTP queue entries are removed after 30 days.
+pub(crate) const QUARANTINE_MAX_AGE: i64 = 30 * 24 * 60 * 60;
+
+/// Increases the retry count of the SMTP queue entry `rowid` and loads it for sending.
+///
+/// Returns `None` if the entry does not exist anymore or was quarantined
+/// because the number of retries exceeded [`Config::SmtpMaxRetries`].
+pub(crate) async fn start_retry(
+    context: &Context,
+    rowid: i64,
+) -> Result<Option<(String, String, MsgId, i64)>> {
+    context
+        .sql
+        .execute("UPDATE smtp SET retries=retries+1 WHERE id=?", (rowid,))
+        .await
+        .context("failed to update retries count")?;
+
+    let Some((body, recipients, msg_id, retries, last_error)) = context
+        .sql
+        .query_row_optional(
+            "SELECT mime, recipients, msg_id, retries, last_error FROM smtp WHERE id=?",
+            (rowid,),
+            |row| {
+                let mime: String = row.get(0)?;
+                let recipients: String = row.get(1)?;
+                let msg_id: MsgId = row.get(2)?;
+                let retries: i64 = row.get(3)?;
+                let last_error: Option<String> = row.get(4)?;
+                Ok((mime, recipients, msg_id, retries, last_error))
+            },
+        )
+        .await?
+    else {
+        return Ok(None);
+    };
+    let max_retries = context.get_config_u64(Config::SmtpMaxRetries).await?;
+    if u64::try_from(retries).unwrap_or_default() > max_retries {
+        let reason = match last_error {
+            Some(err) if !err.is_empty() => {
+                format!("Number of retries exceeded the limit, last error: {err}")
+            }
+            _ => "Number of retries exceeded the limit.".to_string(),
+        };
+        quarantine_entry(context, rowid, &reason)
+            .await
+            .context("Failed to quarantine message with exceeded retry limit")?;
+        if let Some(mut msg) = Message::load_from_db_optional(context, msg_id).await? {
+            message::set_msg_failed(context, &mut msg, &reason).await?;
+        }
+        return Ok(None);
     }
+    Ok(Some((body, recipients, msg_id, retries)))
+}
+
+/// Moves the SMTP queue entry `rowid` to the `smtp_quarantine` table,
+/// so that it is not retried until [`Context::retry_quarantined_message`] is called.
+///
+/// Quarantined entries are removed after [`QUARANTINE_MAX_AGE`] by the housekeeping.
+async fn quarantine_entry(context: &Context, rowid: i64, reason: &str) -> Result<()> {
+    warn!(context, "Quarantining SMTP entry {rowid}: {reason}");
+    context
+        .sql
+        .transaction(|transaction| {
+            transaction.execute(
+                "INSERT INTO smtp_quarantine
+                 (rfc724_mid, mime, msg_id, recipients, priority, reason, timestamp)
+                 SELECT rfc724_mid, mime, msg_id, recipients, priority, ?, ?
+                 FROM smtp WHERE id=?",
+                (reason, time(), rowid),
+            )?;
+            transaction.execute("DELETE FROM smtp WHERE id=?", (rowid,))?;
+            Ok(())
+        })
+        .await
 }
 
+/// Removes quarantined entries older than [`QUARANTINE_MAX_AGE`]
+/// and entries of deleted messages.
+pub(crate) async fn delete_expired_quarantine(context: &Context, now: i64) -> Result<()> {
+    context
+        .sql
+        .execute(
+            "DELETE FROM smtp_quarantine
+             WHERE timestamp<? OR msg_id NOT IN (SELECT id FROM msgs WHERE chat_id!=?)",
+            (now.saturating_sub(QUARANTINE_MAX_AGE), DC_CHAT_ID_TRASH),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Stores the error of the last attempt to send the SMTP queue entry `rowid`.
+pub(crate) async fn set_last_error(context: &Context, rowid: i64, error: Option<&str>) {
+    context
+        .sql
+        .execute("UPDATE smtp SET last_error=? WHERE id=?", (error, rowid))
+        .await
+        .log_err(context)
+        .ok();
+}
+
+/// MDNs are sent this number of seconds after the message was marked as seen,
+/// so that MDNs for messages of the same chat seen meanwhile are sent in a single message.
+const MDN_BATCH_WINDOW: i64 = 10;
+
 /// Attempts to send queued MDNs.
-async fn send_mdns(context: &Context, connection: &mut Smtp) -> Result<()> {
+///
+/// Unless `flush` is true, MDNs are only sent after [`MDN_BATCH_WINDOW`],
+/// see [`mdns_due_in`].
+pub(crate) async fn send_mdns(context: &Context, connection: &mut Smtp, flush: bool) -> Result<()> {
     loop {
         if !context.ratelimit.read().await.can_send() {
             info!(context, "Ratelimiter does not allow sending MDNs now.");
             return Ok(());
         }
 
-        let more_mdns = send_mdn(context, connection).await?;
+        let more_mdns = send_mdn(context, connection, flush).await?;
         if !more_mdns {
             // No more MDNs to send or one of them failed.
             return Ok(());
@@ -484,32 +739,24 @@ async fn send_mdns(context: &Context, connection: &mut Smtp) -> Result<()> {
 /// Tries to send all messages currently in `smtp`, `smtp_status_updates` and `smtp_mdns` tables.
 pub(crate) async fn send_smtp_messages(context: &Context, connection: &mut Smtp) -> Result<()> {
     let ratelimited = if context.ratelimit.read().await.can_send() {
-        // add status updates and sync messages to end of sending queue
-        context.flush_status_updates().await?;
+        if context.get_network_profile() == NetworkProfile::Roaming {
+            // Status updates stay queued until the network changes,
+            // `Context::set_network_profile()` interrupts SMTP loop then.
+            info!(context, "Not flushing webxdc status updates while roaming.");
+        } else {
+            // add status updates and sync messages to end of sending queue
+            context.flush_status_updates().await?;
+        }
         false
     } else {
         true
     };
 
-    let rowids = context
-        .sql
-        .query_map(
-            "SELECT id FROM smtp ORDER BY id ASC",
-            (),
-            |row| {
-                let rowid: i64 = row.get(0)?;
-                Ok(rowid)
-            },
-            |rowids| {
-                rowids
-                    .collect::<std::result::Result<Vec<_>, _>>()
-                    .map_err(Into::into)
-            },
-        )
-        .await?;
-
-    info!(context, "Selected rows from SMTP queue: {rowids:?}.");
-    for rowid in rowids {
+    // The next entry is selected after each sent message,
+    // so that messages queued meanwhile by the user are sent before remaining automatic traffic.
+    let mut attempted = HashSet::new();
+    while let Some(rowid) = next_queued_rowid(context, &attempted).await? {
+        attempted.insert(rowid);
         send_msg_to_smtp(context, connection, rowid)
             .await
             .context("Failed to send message")?;
@@ -519,16 +766,40 @@ pub(crate) async fn send_smtp_messages(context: &Context, connection: &mut Smtp)
     // do not attempt to send MDNs if ratelimited happened before on status-updates/sync:
     // instead, let the caller recall this function so that more important status-updates/sync are sent out.
     if !ratelimited {
-        send_mdns(context, connection)
+        send_mdns(context, connection, false)
             .await
             .context("Failed to send MDNs")?;
     }
     Ok(())
 }
 
+/// Returns the `smtp` table entry to send next, skipping the `attempted` ones.
+async fn next_queued_rowid(context: &Context, attempted: &HashSet<i64>) -> Result<Option<i64>> {
+    let attempted = attempted
+        .iter()
+        .map(|rowid| rowid.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    context
+        .sql
+        .query_get_value(
+            &format!(
+                "SELECT id FROM smtp WHERE id NOT IN ({attempted})
+                 ORDER BY priority DESC, id ASC LIMIT 1"
+            ),
+            (),
+        )
+        .await
+}
+
 /// Tries to send MDN for message identified by `rfc724_mdn` to `contact_id`.
 ///
-/// Attempts to aggregate additional MDNs for `contact_id` into sent MDN.
+/// Attempts to aggregate additional MDNs for messages in the same chat into sent MDN,
+/// so that recipients do not learn about messages in other chats.
+/// MDNs for messages of other contacts in the chat are aggregated as well
+/// and the MDN is sent to all these contacts,
+/// which only process the Message-IDs of their own messages.
+/// Entries queued without `chat_id` are only aggregated with other such entries for `contact_id`.
 ///
 /// On failure returns an error without removing any `smtp_mdns` entries, the caller is responsible
 /// for removing the corresponding entry to prevent endless loop in case the entry is invalid, e.g.
@@ -539,6 +810,7 @@ async fn send_mdn_rfc724_mid(
     context: &Context,
     rfc724_mid: &str,
     contact_id: ContactId,
+    chat_id: ChatId,
     smtp: &mut Smtp,
 ) -> Result<bool> {
     let contact = Contact::get_by_id(context, contact_id).await?;
@@ -547,41 +819,47 @@ async fn send_mdn_rfc724_mid(
     }
 
     // Try to aggregate additional MDNs into this MDN.
-    let additional_rfc724_mids: Vec<String> = context
-        .sql
-        .query_map(
-            "SELECT rfc724_mid
-             FROM smtp_mdns
-             WHERE from_id=? AND rfc724_mid!=?",
-            (contact_id, &rfc724_mid),
-            |row| {
-                let rfc724_mid: String = row.get(0)?;
-                Ok(rfc724_mid)
-            },
-            |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
-        )
-        .await?
-        .into_iter()
-        .collect();
+    let additional = additional_mdns(context, rfc724_mid, contact_id, chat_id).await?;
+    let mut contact_ids = vec![contact_id];
+    let mut additional_rfc724_mids = Vec::new();
+    for (additional_rfc724_mid, from_id) in additional {
+        if !contact_ids.contains(&from_id) {
+            // MDNs to blocked contacts are removed when they are tried on their own.
+            if Contact::get_by_id(context, from_id).await?.is_blocked() {
+                continue;
+            }
+            contact_ids.push(from_id);
+        }
+        additional_rfc724_mids.push(additional_rfc724_mid);
+    }
 
-    let mimefactory = MimeFactory::from_mdn(
+    let mimefactory = MimeFactory::from_batched_mdn(
         context,
-        contact_id,
+        &contact_ids,
         rfc724_mid.to_string(),
         additional_rfc724_mids.clone(),
     )
     .await?;
+    let recipients = mimefactory
+        .recipients()
+        .into_iter()
+        .map(|addr| {
+            async_smtp::EmailAddress::new(addr_with_ascii_domain(&addr))
+                .map_err(|err| format_err!("invalid recipient: {} {:?}", addr, err))
+        })
+        .collect::<Result<Vec<_>>>()?;
     let rendered_msg = mimefactory.render(context).await?;
     let body = rendered_msg.message;
 
-    let addr = contact.get_addr();
-    let recipient = async_smtp::EmailAddress::new(addr.to_string())
-        .map_err(|err| format_err!("invalid recipient: {} {:?}", addr, err))?;
-    let recipients = vec![recipient];
-
-    match smtp_send(context, &recipients, &body, smtp, None).await {
-        SendResult::Success => {
-            info!(context, "Successfully sent MDN for {rfc724_mid}.");
+    let request_dsn = false;
+    match smtp_send(context, &recipients, &body, smtp, request_dsn).await {
+        SendResult::Success(_) => {
+            info!(
+                context,
+                "Successfully sent MDN for {rfc724_mid} and {} more messages to {} recipients.",
+                additional_rfc724_mids.len(),
+                recipients.len()
+            );
             context
                 .sql
                 .transaction(|transaction| {
@@ -603,12 +881,51 @@ async fn send_mdn_rfc724_mid(
             );
             Ok(false)
         }
-        SendResult::Failure(err) => Err(err),
+        SendResult::Failure(err) | SendResult::RecipientRejected(err) => Err(err),
     }
 }
 
+/// Returns the queued MDNs which can be aggregated into the MDN for `rfc724_mid`,
+/// see [`send_mdn_rfc724_mid`].
+async fn additional_mdns(
+    context: &Context,
+    rfc724_mid: &str,
+    contact_id: ContactId,
+    chat_id: ChatId,
+) -> Result<Vec<(String, ContactId)>> {
+    context
+        .sql
+        .query_map(
+            "SELECT rfc724_mid, from_id
+             FROM smtp_mdns
+             WHERE chat_id=?1 AND (chat_id!=0 OR from_id=?2) AND rfc724_mid!=?3",
+            (chat_id, contact_id, rfc724_mid),
+            |row| {
+                let rfc724_mid: String = row.get(0)?;
+                let from_id: ContactId = row.get(1)?;
+                Ok((rfc724_mid, from_id))
+            },
+            |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+        )
+        .await
+}
+
+/// Returns the number of seconds until the next queued MDN is due to be sent,
+/// or `None` if there are no queued MDNs.
+pub(crate) async fn mdns_due_in(context: &Context) -> Result<Option<u64>> {
+    let oldest: Option<i64> = context
+        .sql
+        .query_get_value("SELECT MIN(timestamp) FROM smtp_mdns", ())
+        .await?;
+    Ok(oldest.map(|timestamp| {
+        u64::try_from(timestamp + MDN_BATCH_WINDOW - time())
+            .unwrap_or_default()
+            .max(1)
+    }))
+}
+
 /// Tries to send a single MDN. Returns true if more MDNs should be sent.
-async fn send_mdn(context: &Context, smtp: &mut Smtp) -> Result<bool> {
+async fn send_mdn(context: &Context, smtp: &mut Smtp, flush: bool) -> Result<bool> {
     if !context.should_send_mdns().await? {
         context.sql.execute("DELETE FROM smtp_mdns", []).await?;
         return Ok(false);
@@ -619,22 +936,28 @@ async fn send_mdn(context: &Context, smtp: &mut Smtp) -> Result<bool> {
         .sql
         .execute("DELETE FROM smtp_mdns WHERE retries > 6", [])
         .await?;
+    let due_timestamp = match flush {
+        true => i64::MAX,
+        false => time() - MDN_BATCH_WINDOW,
+    };
     let Some(msg_row) = context
         .sql
         .query_row_optional(
-            "SELECT rfc724_mid, from_id FROM smtp_mdns ORDER BY retries LIMIT 1",
-            [],
+            "SELECT rfc724_mid, from_id, chat_id FROM smtp_mdns
+             WHERE timestamp<=? ORDER BY retries LIMIT 1",
+            (due_timestamp,),
             |row| {
                 let rfc724_mid: String = row.get(0)?;
                 let from_id: ContactId = row.get(1)?;
-                Ok((rfc724_mid, from_id))
+                let chat_id: ChatId = row.get(2)?;
+                Ok((rfc724_mid, from_id, chat_id))
             },
         )
         .await?
     else {
         return Ok(false);
     };
-    let (rfc724_mid, contact_id) = msg_row;
+    let (rfc724_mid, contact_id, chat_id) = msg_row;
 
     context
         .sql
@@ -645,7 +968,7 @@ async fn send_mdn(context: &Context, smtp: &mut Smtp) -> Result<bool> {
         .await
         .context("Failed to update MDN retries count")?;
 
-    match send_mdn_rfc724_mid(context, &rfc724_mid, contact_id, smtp).await {
+    match send_mdn_rfc724_mid(context, &rfc724_mid, contact_id, chat_id, smtp).await {
         Err(err) => {
             // If there is an error, for example there is no message corresponding to the msg_id in the
             // database, do not try to send this MDN again.
@@ -668,3 +991,165 @@ async fn send_mdn(context: &Context, smtp: &mut Smtp) -> Result<bool> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use async_smtp::response::Severity;
+
+    use super::*;
+    use crate::chat::{send_msg, ProtectionStatus};
+    use crate::message::MessageState;
+    use crate::receive_imf::receive_imf;
+    use crate::test_utils::TestContextManager;
+
+    #[test]
+    fn test_is_recipient_rejected() {
+        let code = Code::new(
+            Severity::PermanentNegativeCompletion,
+            Category::MailSystem,
+            Detail::Zero,
+        );
+        let response = |text: &str| Response::new(code, vec![text.to_string()]);
+        assert!(is_recipient_rejected(&response(
+            "5.1.1 <bob@example.net>: Recipient address rejected: User unknown"
+        )));
+        assert!(is_recipient_rejected(&response(
+            "5.1.2 Bad destination system address"
+        )));
+        assert!(!is_recipient_rejected(&response(
+            "5.7.1 Message rejected under suspicion of SPAM"
+        )));
+        assert!(!is_recipient_rejected(&response(
+            "5.5.0 Service unavailable"
+        )));
+        assert!(!is_recipient_rejected(&response(
+            "Requested action not taken"
+        )));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_user_messages_sent_first() -> Result<()> {
+        let mut tcm = TestContextManager::new();
+        let alice = &tcm.alice().await;
+        let bob = &tcm.bob().await;
+        let chat_id = alice.create_chat(bob).await.id;
+
+        let mut update = Message::new_text("update".to_string());
+        update.hidden = true;
+        let update_id = send_msg(alice, chat_id, &mut update).await?;
+        let mut location = Message::new_text("location".to_string());
+        location.param.set_cmd(SystemMessage::LocationOnly);
+        let location_id = send_msg(alice, chat_id, &mut location).await?;
+        let text_id = send_msg(alice, chat_id, &mut Message::new_text("Hi".to_string())).await?;
+
+        let queue: Vec<MsgId> = alice
+            .get_outgoing_queue()
+            .await?
+            .into_iter()
+            .map(|queued| queued.msg_id)
+            .collect();
+        assert_eq!(queue, vec![text_id, update_id, location_id]);
+
+        // Entries already attempted in this pass are skipped.
+        let first = next_queued_rowid(alice, &HashSet::new()).await?.unwrap();
+        let msg_id: MsgId = alice
+            .sql
+            .query_get_value("SELECT msg_id FROM smtp WHERE id=?", (first,))
+            .await?
+            .unwrap();
+        assert_eq!(msg_id, text_id);
+        let second = next_queued_rowid(alice, &HashSet::from([first]))
+            .await?
+            .unwrap();
+        assert_ne!(first, second);
+        let third = next_queued_rowid(alice, &HashSet::from([first, second]))
+            .await?
+            .unwrap();
+        assert!(
+            next_queued_rowid(alice, &HashSet::from([first, second, third]))
+                .await?
+                .is_none()
+        );
+
+        // Prioritizing moves automatic messages above user messages.
+        alice.prioritize_queued_message(location_id).await?;
+        assert_eq!(alice.get_outgoing_queue().await?[0].msg_id, location_id);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_batched_mdn() -> Result<()> {
+        let mut tcm = TestContextManager::new();
+        let alice = &tcm.alice().await;
+        let bob = &tcm.bob().await;
+        let fiona = &tcm.fiona().await;
+
+        let alice_chat_id = alice
+            .create_group_with_members(ProtectionStatus::Unprotected, "Group", &[bob, fiona])
+            .await;
+        let sent = alice.send_text(alice_chat_id, "Hi").await;
+        let bob_chat_id = bob.recv_msg(&sent).await.chat_id;
+        let fiona_chat_id = fiona.recv_msg(&sent).await.chat_id;
+        bob_chat_id.accept(bob).await?;
+        fiona_chat_id.accept(fiona).await?;
+        let bob_sent = bob.send_text(bob_chat_id, "Hi from Bob").await;
+        let fiona_sent = fiona.send_text(fiona_chat_id, "Hi from Fiona").await;
+        let bob_msg = alice.recv_msg(&bob_sent).await;
+        let fiona_msg = alice.recv_msg(&fiona_sent).await;
+        fiona.recv_msg(&bob_sent).await;
+
+        assert_eq!(mdns_due_in(alice).await?, None);
+        message::markseen_msgs(alice, vec![bob_msg.id, fiona_msg.id]).await?;
+        let queued = alice
+            .sql
+            .count(
+                "SELECT COUNT(*) FROM smtp_mdns WHERE chat_id=?",
+                (alice_chat_id,),
+            )
+            .await?;
+        assert_eq!(queued, 2);
+
+        // MDNs for messages in other chats are not aggregated.
+        let bob_one_to_one = bob.create_chat(alice).await.id;
+        let one_to_one_msg = alice
+            .recv_msg(&bob.send_text(bob_one_to_one, "Private").await)
+            .await;
+        one_to_one_msg.chat_id.accept(alice).await?;
+        message::markseen_msgs(alice, vec![one_to_one_msg.id]).await?;
+        let additional =
+            additional_mdns(alice, &bob_msg.rfc724_mid, bob_msg.from_id, alice_chat_id).await?;
+        assert_eq!(
+            additional,
+            vec![(fiona_msg.rfc724_mid.clone(), fiona_msg.from_id)]
+        );
+        let additional = additional_mdns(
+            alice,
+            &one_to_one_msg.rfc724_mid,
+            one_to_one_msg.from_id,
+            one_to_one_msg.chat_id,
+        )
+        .await?;
+        assert!(additional.is_empty());
+
+        let due_in = mdns_due_in(alice).await?.unwrap();
+        assert!(due_in > 0 && due_in <= MDN_BATCH_WINDOW as u64);
+
+        // A single MDN reports both messages to both senders.
+        let mimefactory = MimeFactory::from_batched_mdn(
+            alice,
+            &[bob_msg.from_id, fiona_msg.from_id],
+            bob_msg.rfc724_mid.clone(),
+            vec![fiona_msg.rfc724_mid.clone()],
+        )
+        .await?;
+        assert_eq!(mimefactory.recipients().len(), 2);
+        let mdn = mimefactory.render(alice).await?.message;
+        receive_imf(bob, mdn.as_bytes(), false).await?;
+        receive_imf(fiona, mdn.as_bytes(), false).await?;
+        let bob_own = Message::load_from_db(bob, bob_sent.sender_msg_id).await?;
+        assert_eq!(bob_own.state, MessageState::OutMdnRcvd);
+        let fiona_own = Message::load_from_db(fiona, fiona_sent.sender_msg_id).await?;
+        assert_eq!(fiona_own.state, MessageState::OutMdnRcvd);
+        Ok(())
+    }
+}
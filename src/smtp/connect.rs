@@ -15,7 +15,7 @@
 use crate::net::{
     connect_tcp_inner, connect_tls_inner, run_connection_attempts, update_connection_history,
 };
-use crate::oauth2::get_oauth2_access_token;
+use crate::oauth2::{get_oauth2_access_token, invalidate_oauth2_access_token};
 use crate::tools::time;
 
 /// Converts port number to ALPN list.
@@ -87,10 +87,12 @@ pub(crate) async fn connect_and_auth(
             ],
         )
     };
-    transport
-        .try_login(&creds, &mechanism)
-        .await
-        .context("SMTP failed to login")?;
+    if let Err(err) = transport.try_login(&creds, &mechanism).await {
+        if oauth2 {
+            invalidate_oauth2_access_token(context).await?;
+        }
+        return Err(err).context("SMTP failed to login");
+    }
     Ok(transport)
 }
 
@@ -1,11 +1,14 @@
 //! # SMTP message sending
 
-use async_smtp::{EmailAddress, Envelope, SendableEmail};
+use async_smtp::commands::{DataCommand, MailCommand, RcptCommand, RsetCommand};
+use async_smtp::extension::{Extension, MailBodyParameter, MailParameter, RcptParameter};
+use async_smtp::{EmailAddress, Envelope, SendableEmail, SmtpTransport};
 
 use super::Smtp;
 use crate::config::Config;
 use crate::context::Context;
 use crate::events::EventType;
+use crate::net::session::SessionBufStream;
 use crate::tools;
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -25,11 +28,14 @@ pub enum Error {
 impl Smtp {
     /// Send a prepared mail to recipients.
     /// On successful send out Ok() is returned.
+    ///
+    /// If `request_dsn` is set, positive delivery status notifications are requested.
     pub async fn send(
         &mut self,
         context: &Context,
         recipients: &[EmailAddress],
         message: &[u8],
+        request_dsn: bool,
     ) -> Result<()> {
         if !context.get_config_bool(Config::Bot).await? {
             // Notify ratelimiter about sent message regardless of whether quota is exceeded or not.
@@ -47,10 +53,16 @@ pub async fn send(
 
         let envelope =
             Envelope::new(self.from.clone(), recipients.to_vec()).map_err(Error::Envelope)?;
-        let mail = SendableEmail::new(envelope, message);
 
         if let Some(ref mut transport) = self.transport {
-            transport.send(mail).await.map_err(Error::SmtpSend)?;
+            if request_dsn {
+                send_requesting_dsn(context, transport, envelope, message)
+                    .await
+                    .map_err(Error::SmtpSend)?;
+            } else {
+                let mail = SendableEmail::new(envelope, message);
+                transport.send(mail).await.map_err(Error::SmtpSend)?;
+            }
 
             let info_msg =
                 format!("Message len={message_len_bytes} was SMTP-sent to {recipients_display}");
@@ -67,3 +79,60 @@ pub async fn send(
         Ok(())
     }
 }
+
+/// Sends the mail with `NOTIFY=SUCCESS,FAILURE` recipient parameters
+/// requesting positive delivery status notifications as defined in RFC 3461.
+///
+/// Servers not supporting the DSN extension reject the parameters,
+/// then the mail is sent without them.
+async fn send_requesting_dsn(
+    context: &Context,
+    transport: &mut SmtpTransport<Box<dyn SessionBufStream>>,
+    envelope: Envelope,
+    message: &[u8],
+) -> std::result::Result<(), async_smtp::error::Error> {
+    let server_info = transport.server_info();
+    let mut mail_options = Vec::new();
+    if server_info.supports_feature(Extension::EightBitMime) {
+        mail_options.push(MailParameter::Body(MailBodyParameter::EightBitMime));
+    }
+    if server_info.supports_feature(Extension::SmtpUtfEight) {
+        mail_options.push(MailParameter::SmtpUtfEight);
+    }
+
+    let stream = transport.get_mut();
+    stream
+        .command(MailCommand::new(envelope.from().cloned(), mail_options))
+        .await?;
+    for (i, addr) in envelope.to().iter().enumerate() {
+        let notify = RcptParameter::Other {
+            keyword: "NOTIFY".to_string(),
+            value: Some("SUCCESS,FAILURE".to_string()),
+        };
+        match stream
+            .command(RcptCommand::new(addr.clone(), vec![notify]))
+            .await
+        {
+            Ok(_) => {}
+            // 555 is returned for unrecognized parameters, 501 by some servers
+            // for syntax errors in parameters.
+            Err(async_smtp::error::Error::Permanent(ref response))
+                if i == 0 && matches!(response.code.to_string().as_str(), "555" | "501") =>
+            {
+                info!(
+                    context,
+                    "SMTP server does not support DSNs, sending without requesting them."
+                );
+                stream.command(RsetCommand).await?;
+                transport
+                    .send(SendableEmail::new(envelope.clone(), message))
+                    .await?;
+                return Ok(());
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    stream.command(DataCommand).await?;
+    stream.message(message).await?;
+    Ok(())
+}
@@ -8,13 +8,14 @@
 use tokio::sync::RwLock;
 
 use crate::blob::BlobObject;
+use crate::blob_gc;
 use crate::chat::{self, add_device_msg, update_device_icon, update_saved_messages_icon};
 use crate::config::Config;
 use crate::constants::DC_CHAT_ID_TRASH;
 use crate::context::Context;
 use crate::debug_logging::set_debug_logging_xdc;
-use crate::ephemeral::start_ephemeral_timers;
-use crate::imex::BLOBS_BACKUP_NAME;
+use crate::ephemeral::{delete_expired_messages, start_ephemeral_timers};
+use crate::imex;
 use crate::location::delete_orphaned_poi_locations;
 use crate::log::LogExt;
 use crate::message::{Message, MsgId};
@@ -23,8 +24,11 @@
 use crate::net::prune_connection_history;
 use crate::param::{Param, Params};
 use crate::peerstate::Peerstate;
+use crate::securejoin::invite_words;
+use crate::smtp;
 use crate::stock_str;
-use crate::tools::{delete_file, time, SystemTime};
+use crate::token;
+use crate::tools::time;
 
 /// Extension to [`rusqlite::ToSql`] trait
 /// which also includes [`Send`] and [`Sync`].
@@ -336,7 +340,7 @@ pub async fn change_passphrase(&self, passphrase: String) -> Result<()> {
     /// otherwise allocates write connection.
     ///
     /// Returns the result of the function.
-    async fn call<'a, F, R>(&'a self, query_only: bool, function: F) -> Result<R>
+    pub(crate) async fn call<'a, F, R>(&'a self, query_only: bool, function: F) -> Result<R>
     where
         F: 'a + FnOnce(&mut Connection) -> Result<R> + Send,
         R: Send + 'static,
@@ -753,6 +757,18 @@ pub async fn housekeeping(context: &Context) -> Result<()> {
         );
     }
 
+    smtp::delete_expired_quarantine(context, time())
+        .await
+        .context("Failed to remove expired quarantined SMTP entries")
+        .log_err(context)
+        .ok();
+
+    imex::remove_stale_transfer_parts(context, None)
+        .await
+        .context("Failed to remove stale backup transfer data")
+        .log_err(context)
+        .ok();
+
     if let Err(err) = start_ephemeral_timers(context).await {
         warn!(
             context,
@@ -760,6 +776,13 @@ pub async fn housekeeping(context: &Context) -> Result<()> {
         );
     }
 
+    if let Err(err) = delete_expired_messages(context, time()).await {
+        warn!(
+            context,
+            "Housekeeping: cannot delete expired messages: {:#}.", err
+        );
+    }
+
     if let Err(err) = prune_tombstones(&context.sql).await {
         warn!(
             context,
@@ -771,6 +794,17 @@ pub async fn housekeeping(context: &Context) -> Result<()> {
         warn!(context, "Failed to run incremental vacuum: {err:#}.");
     }
 
+    token::delete_expired_limited_invites(context, time())
+        .await
+        .context("Failed to remove expired group invites")
+        .log_err(context)
+        .ok();
+    token::delete_invite_words_before(context, time() - invite_words::MAX_TOKEN_AGE)
+        .await
+        .context("Failed to remove expired invite codes")
+        .log_err(context)
+        .ok();
+
     context
         .sql
         .execute(
@@ -795,6 +829,72 @@ pub async fn housekeeping(context: &Context) -> Result<()> {
         .log_err(context)
         .ok();
 
+    context
+        .sql
+        .execute(
+            "DELETE FROM webxdc_send_requests WHERE msg_id NOT IN \
+            (SELECT id FROM msgs WHERE chat_id!=?)",
+            (DC_CHAT_ID_TRASH,),
+        )
+        .await
+        .context("Failed to remove webxdc send requests of deleted instances")
+        .log_err(context)
+        .ok();
+
+    context
+        .sql
+        .execute(
+            "DELETE FROM msg_translations WHERE msg_id NOT IN \
+            (SELECT id FROM msgs WHERE chat_id!=?)",
+            (DC_CHAT_ID_TRASH,),
+        )
+        .await
+        .context("Failed to remove translations of deleted messages")
+        .log_err(context)
+        .ok();
+
+    context
+        .sql
+        .execute(
+            "DELETE FROM msgs_failed_recipients WHERE msg_id NOT IN \
+            (SELECT id FROM msgs WHERE chat_id!=?)",
+            (DC_CHAT_ID_TRASH,),
+        )
+        .await
+        .context("failed to remove old failed recipients")
+        .log_err(context)
+        .ok();
+
+    context
+        .sql
+        .execute(
+            "DELETE FROM msgs_delivery_status WHERE msg_id NOT IN \
+            (SELECT id FROM msgs WHERE chat_id!=?)",
+            (DC_CHAT_ID_TRASH,),
+        )
+        .await
+        .context("Failed to remove old delivery status")
+        .log_err(context)
+        .ok();
+
+    context
+        .flush_webxdc_realtime_bytes()
+        .await
+        .context("failed to flush webxdc realtime usage")
+        .log_err(context)
+        .ok();
+    context
+        .sql
+        .execute(
+            "DELETE FROM webxdc_usage WHERE msg_id NOT IN \
+            (SELECT id FROM msgs WHERE chat_id!=?)",
+            (DC_CHAT_ID_TRASH,),
+        )
+        .await
+        .context("failed to remove old webxdc usage")
+        .log_err(context)
+        .ok();
+
     prune_connection_history(context)
         .await
         .context("Failed to prune connection history")
@@ -829,11 +929,18 @@ pub fn row_get_vec(row: &Row, idx: usize) -> rusqlite::Result<Vec<u8>> {
 }
 
 /// Enumerates used files in the blobdir and removes unused ones.
+///
+/// See [`crate::blob_gc`] for details.
 pub async fn remove_unused_files(context: &Context) -> Result<()> {
-    let mut files_in_use = HashSet::new();
-    let mut unreferenced_count = 0;
-
     info!(context, "Start housekeeping...");
+    blob_gc::collect_garbage(context, false).await?;
+    Ok(())
+}
+
+/// Returns the names of the files in the blobdir which are referenced
+/// by messages, chats, contacts, the config or the HTTP cache.
+pub(crate) async fn get_files_in_use(context: &Context) -> Result<HashSet<String>> {
+    let mut files_in_use = HashSet::new();
     maybe_add_from_param(
         &context.sql,
         &mut files_in_use,
@@ -888,100 +995,14 @@ pub async fn remove_unused_files(context: &Context) -> Result<()> {
         .await
         .context("Failed to SELECT blobname FROM http_cache")?;
 
-    info!(context, "{} files in use.", files_in_use.len());
-    /* go through directories and delete unused files */
-    let blobdir = context.get_blobdir();
-    for p in [&blobdir.join(BLOBS_BACKUP_NAME), blobdir] {
-        match tokio::fs::read_dir(p).await {
-            Ok(mut dir_handle) => {
-                /* avoid deletion of files that are just created to build a message object */
-                let diff = std::time::Duration::from_secs(60 * 60);
-                let keep_files_newer_than = SystemTime::now()
-                    .checked_sub(diff)
-                    .unwrap_or(SystemTime::UNIX_EPOCH);
-
-                while let Ok(Some(entry)) = dir_handle.next_entry().await {
-                    let name_f = entry.file_name();
-                    let name_s = name_f.to_string_lossy();
-
-                    if p == blobdir
-                        && (is_file_in_use(&files_in_use, None, &name_s)
-                            || is_file_in_use(&files_in_use, Some(".waveform"), &name_s)
-                            || is_file_in_use(&files_in_use, Some("-preview.jpg"), &name_s))
-                    {
-                        continue;
-                    }
-
-                    if let Ok(stats) = tokio::fs::metadata(entry.path()).await {
-                        if stats.is_dir() {
-                            if let Err(e) = tokio::fs::remove_dir(entry.path()).await {
-                                // The dir could be created not by a user, but by a desktop
-                                // environment f.e. So, no warning.
-                                info!(
-                                    context,
-                                    "Housekeeping: Cannot rmdir {}: {:#}.",
-                                    entry.path().display(),
-                                    e
-                                );
-                            }
-                            continue;
-                        }
-                        unreferenced_count += 1;
-                        let recently_created =
-                            stats.created().is_ok_and(|t| t > keep_files_newer_than);
-                        let recently_modified =
-                            stats.modified().is_ok_and(|t| t > keep_files_newer_than);
-                        let recently_accessed =
-                            stats.accessed().is_ok_and(|t| t > keep_files_newer_than);
-
-                        if p == blobdir
-                            && (recently_created || recently_modified || recently_accessed)
-                        {
-                            info!(
-                                context,
-                                "Housekeeping: Keeping new unreferenced file #{}: {:?}.",
-                                unreferenced_count,
-                                entry.file_name(),
-                            );
-                            continue;
-                        }
-                    } else {
-                        unreferenced_count += 1;
-                    }
-                    info!(
-                        context,
-                        "Housekeeping: Deleting unreferenced file #{}: {:?}.",
-                        unreferenced_count,
-                        entry.file_name()
-                    );
-                    let path = entry.path();
-                    if let Err(err) = delete_file(context, &path).await {
-                        error!(
-                            context,
-                            "Failed to delete unused file {}: {:#}.",
-                            path.display(),
-                            err
-                        );
-                    }
-                }
-            }
-            Err(err) => {
-                if !p.ends_with(BLOBS_BACKUP_NAME) {
-                    warn!(
-                        context,
-                        "Housekeeping: Cannot read dir {}: {:#}.",
-                        p.display(),
-                        err
-                    );
-                }
-            }
-        }
-    }
-
-    Ok(())
+    Ok(files_in_use)
 }
 
-fn is_file_in_use(files_in_use: &HashSet<String>, namespc_opt: Option<&str>, name: &str) -> bool {
+pub(crate) fn is_file_in_use(
+    files_in_use: &HashSet<String>,
+    namespc_opt: Option<&str>,
+    name: &str,
+) -> bool {
     let name_to_check = if let Some(namespc) = namespc_opt {
         let Some(name) = name.strip_suffix(namespc) else {
             return false;
@@ -999,7 +1020,7 @@ fn maybe_add_file(files_in_use: &mut HashSet<String>, file: &str) {
     }
 }
 
-async fn maybe_add_from_param(
+pub(crate) async fn maybe_add_from_param(
     sql: &Sql,
     files_in_use: &mut HashSet<String>,
     query: &str,
@@ -1046,6 +1067,7 @@ async fn prune_tombstones(sql: &Sql) -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::imex::BLOBS_BACKUP_NAME;
     use crate::{test_utils::TestContext, EventType};
 
     #[test]
@@ -1,11 +1,12 @@
 //! Migrations module.
 
 use anyhow::{ensure, Context as _, Result};
-use deltachat_contact_tools::EmailAddress;
+use deltachat_contact_tools::{addr_normalize, EmailAddress};
 use rusqlite::OptionalExtension;
 
 use crate::config::Config;
 use crate::constants::ShowEmails;
+use crate::contact::ContactId;
 use crate::context::Context;
 use crate::imap;
 use crate::message::MsgId;
@@ -1169,6 +1170,381 @@ pub async fn run(context: &Context, sql: &Sql) -> Result<(bool, bool, bool, bool
         .await?;
     }
 
+    inc_and_check(&mut migration_version, 130)?;
+    if dbversion < migration_version {
+        // Local contact labels such as "Family" or "Work".
+        sql.execute_migration(
+            "CREATE TABLE contact_labels (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL UNIQUE
+            ) STRICT;
+            CREATE TABLE contact_labels_contacts (
+                label_id INTEGER NOT NULL,
+                contact_id INTEGER NOT NULL,
+                PRIMARY KEY (label_id, contact_id)
+            ) STRICT;
+            CREATE INDEX contact_labels_contacts_index1 ON contact_labels_contacts (contact_id);",
+            migration_version,
+        )
+        .await?;
+    }
+
+    inc_and_check(&mut migration_version, 131)?;
+    if dbversion < migration_version {
+        // Secure-join requests waiting for approval of the inviter.
+        sql.execute_migration(
+            "CREATE TABLE join_requests (
+                contact_id INTEGER NOT NULL,
+                chat_id INTEGER NOT NULL,
+                timestamp INTEGER NOT NULL,
+                PRIMARY KEY (contact_id, chat_id)
+            ) STRICT;",
+            migration_version,
+        )
+        .await?;
+    }
+
+    inc_and_check(&mut migration_version, 132)?;
+    if dbversion < migration_version {
+        // Resource usage of webxdc instances that is not stored elsewhere.
+        sql.execute_migration(
+            "CREATE TABLE webxdc_usage (
+                msg_id INTEGER PRIMARY KEY,
+                realtime_bytes INTEGER NOT NULL DEFAULT 0,
+                warned INTEGER NOT NULL DEFAULT 0
+            ) STRICT;",
+            migration_version,
+        )
+        .await?;
+    }
+
+    inc_and_check(&mut migration_version, 133)?;
+    if dbversion < migration_version {
+        // Audit trail of protection-relevant changes in chats.
+        // `target_id` is 0 if the event has no target contact.
+        sql.execute_migration(
+            "CREATE TABLE protection_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                chat_id INTEGER NOT NULL,
+                timestamp INTEGER NOT NULL,
+                event INTEGER NOT NULL,
+                contact_id INTEGER NOT NULL,
+                target_id INTEGER NOT NULL DEFAULT 0
+            ) STRICT;
+            CREATE INDEX protection_log_index1 ON protection_log (chat_id);",
+            migration_version,
+        )
+        .await?;
+    }
+
+    inc_and_check(&mut migration_version, 134)?;
+    if dbversion < migration_version {
+        // Custom headers added to outgoing messages of a chat.
+        sql.execute_migration(
+            "CREATE TABLE chat_custom_headers (
+                chat_id INTEGER NOT NULL,
+                name TEXT NOT NULL COLLATE NOCASE,
+                value TEXT NOT NULL,
+                PRIMARY KEY (chat_id, name)
+            ) STRICT;",
+            migration_version,
+        )
+        .await?;
+    }
+
+    inc_and_check(&mut migration_version, 135)?;
+    if dbversion < migration_version {
+        // Priority and last error of queued outgoing messages.
+        sql.execute_migration(
+            "ALTER TABLE smtp ADD COLUMN priority INTEGER NOT NULL DEFAULT 0;
+             ALTER TABLE smtp ADD COLUMN last_error TEXT;",
+            migration_version,
+        )
+        .await?;
+    }
+
+    inc_and_check(&mut migration_version, 136)?;
+    if dbversion < migration_version {
+        // Recipients which rejected an outgoing message.
+        sql.execute_migration(
+            "CREATE TABLE msgs_failed_recipients (
+                msg_id INTEGER NOT NULL,
+                addr TEXT NOT NULL,
+                error TEXT NOT NULL,
+                PRIMARY KEY (msg_id, addr)
+             ) STRICT",
+            migration_version,
+        )
+        .await?;
+    }
+
+    inc_and_check(&mut migration_version, 137)?;
+    if dbversion < migration_version {
+        // Local per-chat retention policy, 0 means unlimited.
+        sql.execute_migration(
+            "ALTER TABLE chats ADD COLUMN retention_msgs INTEGER NOT NULL DEFAULT 0;
+             ALTER TABLE chats ADD COLUMN retention_days INTEGER NOT NULL DEFAULT 0;",
+            migration_version,
+        )
+        .await?;
+    }
+
+    inc_and_check(&mut migration_version, 138)?;
+    if dbversion < migration_version {
+        // Requests of webxdc apps to send messages waiting for approval of the user.
+        sql.execute_migration(
+            "CREATE TABLE webxdc_send_requests (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                msg_id INTEGER NOT NULL,
+                text TEXT NOT NULL,
+                timestamp INTEGER NOT NULL
+            ) STRICT;
+            CREATE INDEX webxdc_send_requests_msg_id ON webxdc_send_requests (msg_id);",
+            migration_version,
+        )
+        .await?;
+    }
+
+    inc_and_check(&mut migration_version, 139)?;
+    if dbversion < migration_version {
+        // Incoming reactions to own messages not noticed yet.
+        sql.execute_migration(
+            "ALTER TABLE reactions ADD COLUMN fresh INTEGER NOT NULL DEFAULT 0;
+             CREATE INDEX reactions_fresh_index ON reactions (fresh);",
+            migration_version,
+        )
+        .await?;
+    }
+
+    inc_and_check(&mut migration_version, 140)?;
+    if dbversion < migration_version {
+        // Push notification registrations of devices, see `push::registrations`.
+        sql.execute_migration(
+            "CREATE TABLE push_registrations (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                transport INTEGER NOT NULL,
+                token TEXT NOT NULL,
+                device_name TEXT NOT NULL DEFAULT '',
+                created_timestamp INTEGER NOT NULL,
+                synced_timestamp INTEGER NOT NULL DEFAULT 0,
+                revoked INTEGER NOT NULL DEFAULT 0,
+                UNIQUE(transport, token)
+            ) STRICT",
+            migration_version,
+        )
+        .await?;
+    }
+
+    inc_and_check(&mut migration_version, 141)?;
+    if dbversion < migration_version {
+        // Web Push keys of UnifiedPush registrations.
+        sql.execute_migration(
+            "ALTER TABLE push_registrations ADD COLUMN webpush_private_key BLOB;
+             ALTER TABLE push_registrations ADD COLUMN webpush_auth BLOB;",
+            migration_version,
+        )
+        .await?;
+    }
+
+    inc_and_check(&mut migration_version, 142)?;
+    if dbversion < migration_version {
+        // Translations of messages, `text` is NULL while the translation is pending.
+        sql.execute_migration(
+            "CREATE TABLE msg_translations (
+                msg_id INTEGER PRIMARY KEY,
+                target_lang TEXT NOT NULL,
+                provider TEXT NOT NULL,
+                text TEXT,
+                show_original INTEGER NOT NULL DEFAULT 0
+            ) STRICT",
+            migration_version,
+        )
+        .await?;
+    }
+
+    inc_and_check(&mut migration_version, 143)?;
+    if dbversion < migration_version {
+        // Per-recipient delivery status reported by positive DSNs.
+        sql.execute_migration(
+            "CREATE TABLE msgs_delivery_status (
+                msg_id INTEGER NOT NULL,
+                addr TEXT NOT NULL,
+                action TEXT NOT NULL,
+                timestamp INTEGER NOT NULL,
+                PRIMARY KEY (msg_id, addr)
+             ) STRICT",
+            migration_version,
+        )
+        .await?;
+    }
+
+    inc_and_check(&mut migration_version, 144)?;
+    if dbversion < migration_version {
+        // Messages hidden locally by the user, see `message::set_hidden_local()`.
+        sql.execute_migration(
+            "ALTER TABLE msgs ADD COLUMN hidden_local INTEGER NOT NULL DEFAULT 0;",
+            migration_version,
+        )
+        .await?;
+    }
+
+    inc_and_check(&mut migration_version, 145)?;
+    if dbversion < migration_version {
+        // Chat and time of queued MDNs, used to send MDNs of a chat in a single message.
+        sql.execute_migration(
+            "ALTER TABLE smtp_mdns ADD COLUMN chat_id INTEGER NOT NULL DEFAULT 0;
+             ALTER TABLE smtp_mdns ADD COLUMN timestamp INTEGER NOT NULL DEFAULT 0;",
+            migration_version,
+        )
+        .await?;
+    }
+
+    inc_and_check(&mut migration_version, 146)?;
+    if dbversion < migration_version {
+        // Admins of moderated groups, see `chat::set_group_moderated()`.
+        sql.execute_migration(
+            "CREATE TABLE chats_admins (
+                chat_id INTEGER NOT NULL,
+                contact_id INTEGER NOT NULL,
+                PRIMARY KEY (chat_id, contact_id)
+             ) STRICT",
+            migration_version,
+        )
+        .await?;
+    }
+
+    inc_and_check(&mut migration_version, 147)?;
+    if dbversion < migration_version {
+        // Custom emojis used in a reaction, see `reaction::CustomEmoji`.
+        sql.execute_migration(
+            "ALTER TABLE reactions ADD COLUMN custom_emojis TEXT NOT NULL DEFAULT '';",
+            migration_version,
+        )
+        .await?;
+    }
+
+    inc_and_check(&mut migration_version, 148)?;
+    if dbversion < migration_version {
+        // Expiration time and use limit of group invites, see `token::Namespace::LimitedAuth`.
+        sql.execute_migration(
+            "ALTER TABLE tokens ADD COLUMN expires INTEGER NOT NULL DEFAULT 0;
+             ALTER TABLE tokens ADD COLUMN max_uses INTEGER NOT NULL DEFAULT 0;
+             ALTER TABLE tokens ADD COLUMN uses INTEGER NOT NULL DEFAULT 0;",
+            migration_version,
+        )
+        .await?;
+    }
+
+    inc_and_check(&mut migration_version, 149)?;
+    if dbversion < migration_version {
+        // Inactivity timer of chats, see `ChatId::set_inactivity_timer()`.
+        sql.execute_migration(
+            "ALTER TABLE chats ADD COLUMN inactivity_days INTEGER NOT NULL DEFAULT 0;
+             ALTER TABLE chats ADD COLUMN last_activity INTEGER NOT NULL DEFAULT 0;",
+            migration_version,
+        )
+        .await?;
+    }
+
+    inc_and_check(&mut migration_version, 150)?;
+    if dbversion < migration_version {
+        // Text recognized in images for search, see `crate::ocr`.
+        // NULL if text recognition was not done yet.
+        sql.execute_migration(
+            "ALTER TABLE msgs ADD COLUMN txt_ocr TEXT;",
+            migration_version,
+        )
+        .await?;
+    }
+
+    inc_and_check(&mut migration_version, 151)?;
+    if dbversion < migration_version {
+        // Set if the user marked the chat as unread, see `ChatId::set_marked_unread()`.
+        sql.execute_migration(
+            "ALTER TABLE chats ADD COLUMN marked_unread INTEGER NOT NULL DEFAULT 0;",
+            migration_version,
+        )
+        .await?;
+    }
+
+    inc_and_check(&mut migration_version, 152)?;
+    if dbversion < migration_version {
+        // `smtp` entries moved out of the queue after exceeding the retry limit,
+        // see `Context::retry_quarantined_message()`.
+        sql.execute_migration(
+            "CREATE TABLE smtp_quarantine (
+             id INTEGER PRIMARY KEY AUTOINCREMENT,
+             rfc724_mid TEXT NOT NULL,
+             mime TEXT NOT NULL,
+             msg_id INTEGER NOT NULL,
+             recipients TEXT NOT NULL,
+             priority INTEGER NOT NULL DEFAULT 0,
+             reason TEXT NOT NULL,     -- Why the entry was quarantined, e.g. the last error
+             timestamp INTEGER NOT NULL
+            );
+            CREATE INDEX smtp_quarantine_msg_id ON smtp_quarantine(msg_id);",
+            migration_version,
+        )
+        .await?;
+    }
+
+    inc_and_check(&mut migration_version, 153)?;
+    if dbversion < migration_version {
+        // `UnarchivePolicy` of the chat.
+        sql.execute_migration(
+            "ALTER TABLE chats ADD COLUMN unarchive_policy INTEGER NOT NULL DEFAULT 0;",
+            migration_version,
+        )
+        .await?;
+    }
+
+    inc_and_check(&mut migration_version, 154)?;
+    if dbversion < migration_version {
+        // Sender of the webxdc status update, 0 for updates received before.
+        sql.execute_migration(
+            "ALTER TABLE msgs_status_updates ADD COLUMN from_id INTEGER NOT NULL DEFAULT 0;",
+            migration_version,
+        )
+        .await?;
+    }
+
+    inc_and_check(&mut migration_version, 155)?;
+    if dbversion < migration_version {
+        // Internationalized domains of contact addresses are stored in ASCII (punycode) form now.
+        let version = migration_version;
+        sql.transaction(move |trans| {
+            Sql::set_db_version_trans(trans, version)?;
+            normalize_contact_addrs(trans)
+        })
+        .await
+        .with_context(|| format!("migration failed for version {version}"))?;
+        sql.set_db_version_in_cache(version).await?;
+    }
+
+    inc_and_check(&mut migration_version, 156)?;
+    if dbversion < migration_version {
+        // Full-text index of the text recognized in images, see `crate::ocr`.
+        // The trigram tokenizer allows to use the index for `LIKE` substring searches.
+        // The index is kept in sync with `msgs.txt_ocr` by the triggers.
+        sql.execute_migration(
+            "CREATE VIRTUAL TABLE msgs_ocr USING fts5(
+               txt_ocr, content='msgs', content_rowid='id', tokenize='trigram');
+             INSERT INTO msgs_ocr(msgs_ocr) VALUES('rebuild');
+             CREATE TRIGGER msgs_ocr_delete AFTER DELETE ON msgs
+             WHEN old.txt_ocr IS NOT NULL BEGIN
+               INSERT INTO msgs_ocr(msgs_ocr, rowid, txt_ocr) VALUES('delete', old.id, old.txt_ocr);
+             END;
+             CREATE TRIGGER msgs_ocr_update AFTER UPDATE OF txt_ocr ON msgs BEGIN
+               INSERT INTO msgs_ocr(msgs_ocr, rowid, txt_ocr)
+                 SELECT 'delete', old.id, old.txt_ocr WHERE old.txt_ocr IS NOT NULL;
+               INSERT INTO msgs_ocr(rowid, txt_ocr)
+                 SELECT new.id, new.txt_ocr WHERE new.txt_ocr IS NOT NULL;
+             END;",
+            migration_version,
+        )
+        .await?;
+    }
+
     let new_version = sql
         .get_raw_config_int(VERSION_CFG)
         .await?
@@ -1191,6 +1567,90 @@ pub async fn run(context: &Context, sql: &Sql) -> Result<(bool, bool, bool, bool
     ))
 }
 
+/// Converts internationalized domains of contact addresses and peerstates
+/// to ASCII (punycode) form as done by [`addr_normalize`].
+///
+/// Contacts that already exist with the normalized address
+/// are merged into the existing contact.
+fn normalize_contact_addrs(trans: &mut rusqlite::Transaction) -> Result<()> {
+    let contacts = trans
+        .prepare("SELECT id, addr FROM contacts WHERE id>?")?
+        .query_map((ContactId::LAST_SPECIAL,), |row| {
+            let id: ContactId = row.get(0)?;
+            let addr: String = row.get(1)?;
+            Ok((id, addr))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    for (id, addr) in contacts {
+        if addr.is_ascii() {
+            continue;
+        }
+        let normalized = addr_normalize(&addr);
+        if normalized == addr {
+            continue;
+        }
+        let existing: Option<ContactId> = trans
+            .query_row(
+                "SELECT id FROM contacts WHERE addr=? COLLATE NOCASE AND id!=?",
+                (&normalized, id),
+                |row| row.get(0),
+            )
+            .optional()?;
+        if let Some(existing) = existing {
+            for (table, column) in [
+                ("msgs", "from_id"),
+                ("msgs", "to_id"),
+                ("locations", "from_id"),
+                ("msgs_status_updates", "from_id"),
+            ] {
+                trans.execute(
+                    &format!("UPDATE {table} SET {column}=? WHERE {column}=?"),
+                    (existing, id),
+                )?;
+            }
+            for table in [
+                "chats_contacts",
+                "chats_admins",
+                "msgs_mdns",
+                "reactions",
+                "contact_labels_contacts",
+                "join_requests",
+            ] {
+                // Rows that exist for both contacts are kept for the existing contact.
+                trans.execute(
+                    &format!("UPDATE OR IGNORE {table} SET contact_id=? WHERE contact_id=?"),
+                    (existing, id),
+                )?;
+                trans.execute(&format!("DELETE FROM {table} WHERE contact_id=?"), (id,))?;
+            }
+            trans.execute("DELETE FROM contacts WHERE id=?", (id,))?;
+        } else {
+            trans.execute("UPDATE contacts SET addr=? WHERE id=?", (&normalized, id))?;
+        }
+    }
+
+    let peerstates = trans
+        .prepare("SELECT addr FROM acpeerstates")?
+        .query_map((), |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    for addr in peerstates {
+        if addr.is_ascii() {
+            continue;
+        }
+        let normalized = addr_normalize(&addr);
+        if normalized == addr {
+            continue;
+        }
+        // If a peerstate for the normalized address exists already, it is kept.
+        trans.execute(
+            "UPDATE OR IGNORE acpeerstates SET addr=? WHERE addr=?",
+            (&normalized, &addr),
+        )?;
+        trans.execute("DELETE FROM acpeerstates WHERE addr=?", (&addr,))?;
+    }
+    Ok(())
+}
+
 impl Sql {
     async fn set_db_version(&self, version: i32) -> Result<()> {
         self.set_raw_config_int(VERSION_CFG, version).await?;
@@ -1238,7 +1698,9 @@ async fn execute_migration(&self, query: &str, version: i32) -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::chat::{create_group_chat, get_chat_contacts, ProtectionStatus};
     use crate::config::Config;
+    use crate::contact::Contact;
     use crate::test_utils::TestContext;
 
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
@@ -1260,4 +1722,40 @@ async fn test_clear_config_cache() -> anyhow::Result<()> {
 
         Ok(())
     }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_normalize_contact_addrs() -> anyhow::Result<()> {
+        let t = TestContext::new_alice().await;
+        let bob_id = Contact::create(&t, "Bob", "bob@xn--mnchen-3ya.de").await?;
+        let chat_id = create_group_chat(&t, ProtectionStatus::Unprotected, "foo").await?;
+
+        // Contacts created before internationalized domains were normalized.
+        let old_bob_id = t
+            .sql
+            .insert("INSERT INTO contacts (addr) VALUES ('bob@münchen.de')", ())
+            .await?;
+        let old_bob_id = ContactId::new(old_bob_id.try_into()?);
+        let carol_id = t
+            .sql
+            .insert(
+                "INSERT INTO contacts (addr) VALUES ('carol@münchen.de')",
+                (),
+            )
+            .await?;
+        let carol_id = ContactId::new(carol_id.try_into()?);
+        t.sql
+            .execute(
+                "INSERT INTO chats_contacts (chat_id, contact_id) VALUES (?, ?)",
+                (chat_id, old_bob_id),
+            )
+            .await?;
+
+        t.sql.transaction(normalize_contact_addrs).await?;
+
+        assert!(Contact::get_by_id_optional(&t, old_bob_id).await?.is_none());
+        assert_eq!(get_chat_contacts(&t, chat_id).await?, vec![bob_id]);
+        let carol = Contact::get_by_id(&t, carol_id).await?;
+        assert_eq!(carol.get_addr(), "carol@xn--mnchen-3ya.de");
+        Ok(())
+    }
 }
@@ -441,6 +441,77 @@ pub enum StockMessage {
         fallback = "Could not yet establish guaranteed end-to-end encryption, but you may already send a message."
     ))]
     SecurejoinWaitTimeout = 191,
+
+    #[strum(props(fallback = "⚠️ You have not made a backup for a long time.\n\n\
+                    If you lose this device, you lose your messages and your encryption key. \
+                    Use \"Settings / Chats and Media / Export Backup\" \
+                    or \"Settings / Add Second Device\" to keep them safe."))]
+    BackupReminderMsgBody = 192,
+
+    #[strum(props(
+        fallback = "⚠️ Your encryption key was created on %1$s and may use outdated algorithms."
+    ))]
+    KeyAgeWarningMsgBody = 193,
+
+    #[strum(
+        props(fallback = "⚠️ The database of this profile has grown to %1$s.\n\n\
+                    To free space, delete old chats or enable \"Delete Messages from Device\".")
+    )]
+    DbSizeWarningMsgBody = 194,
+
+    #[strum(props(fallback = "Messages can be end-to-end encrypted again."))]
+    EncryptionAvailable = 195,
+
+    #[strum(props(
+        fallback = "⚠️ Messages cannot be end-to-end encrypted. Sending is blocked until encryption is available again."
+    ))]
+    EncryptionUnavailable = 196,
+
+    #[strum(props(fallback = "Subscription request sent to the mailing list."))]
+    MailinglistSubscribeSent = 197,
+
+    #[strum(props(fallback = "You unsubscribed from this mailing list."))]
+    MailinglistUnsubscribed = 198,
+
+    #[strum(props(fallback = "Unsubscribing failed: %1$s"))]
+    MailinglistUnsubscribeFailed = 199,
+
+    #[strum(props(
+        fallback = "⚠️ This group has more than %1$s members. Changing the members of large groups causes a lot of traffic for all members."
+    ))]
+    GroupMemberSoftLimit = 201,
+
+    #[strum(props(
+        fallback = "You allowed only admins to change members, name and image of this group."
+    ))]
+    MsgYouModeratedGrp = 202,
+
+    #[strum(props(
+        fallback = "%1$s allowed only admins to change members, name and image of this group."
+    ))]
+    MsgGrpModeratedBy = 203,
+
+    #[strum(props(fallback = "You allowed all members to change this group."))]
+    MsgYouUnmoderatedGrp = 204,
+
+    #[strum(props(fallback = "%1$s allowed all members to change this group."))]
+    MsgGrpUnmoderatedBy = 205,
+
+    #[strum(props(fallback = "You changed the group admins."))]
+    MsgYouChangedGrpAdmins = 206,
+
+    #[strum(props(fallback = "Group admins changed by %1$s."))]
+    MsgGrpAdminsChangedBy = 207,
+
+    #[strum(props(
+        fallback = "%1$s tried to change the group, but only admins may change this group."
+    ))]
+    MsgGrpChangeRejected = 208,
+
+    #[strum(props(
+        fallback = "⚠️ This broadcast list is now a group. With the next message, all members will see each other's addresses."
+    ))]
+    BroadcastConvertedToGroup = 209,
 }
 
 impl StockMessage {
@@ -808,6 +879,51 @@ pub(crate) async fn msg_grp_img_deleted(context: &Context, by_contact: ContactId
     }
 }
 
+/// Stock string: `Only admins may change this group now.`.
+pub(crate) async fn msg_grp_moderated(context: &Context, by_contact: ContactId) -> String {
+    if by_contact == ContactId::SELF {
+        translated(context, StockMessage::MsgYouModeratedGrp).await
+    } else {
+        translated(context, StockMessage::MsgGrpModeratedBy)
+            .await
+            .replace1(&by_contact.get_stock_name_n_addr(context).await)
+    }
+}
+
+/// Stock string: `All members may change this group now.`.
+pub(crate) async fn msg_grp_unmoderated(context: &Context, by_contact: ContactId) -> String {
+    if by_contact == ContactId::SELF {
+        translated(context, StockMessage::MsgYouUnmoderatedGrp).await
+    } else {
+        translated(context, StockMessage::MsgGrpUnmoderatedBy)
+            .await
+            .replace1(&by_contact.get_stock_name_n_addr(context).await)
+    }
+}
+
+/// Stock string: `Group admins changed.`.
+pub(crate) async fn msg_grp_admins_changed(context: &Context, by_contact: ContactId) -> String {
+    if by_contact == ContactId::SELF {
+        translated(context, StockMessage::MsgYouChangedGrpAdmins).await
+    } else {
+        translated(context, StockMessage::MsgGrpAdminsChangedBy)
+            .await
+            .replace1(&by_contact.get_stock_name_n_addr(context).await)
+    }
+}
+
+/// Stock string: `%1$s tried to change the group, but only admins may change this group.`.
+pub(crate) async fn msg_grp_change_rejected(context: &Context, by_contact: ContactId) -> String {
+    translated(context, StockMessage::MsgGrpChangeRejected)
+        .await
+        .replace1(&by_contact.get_stock_name_n_addr(context).await)
+}
+
+/// Stock string: `⚠️ This broadcast list is now a group. With the next message, all members will see each other's addresses.`.
+pub(crate) async fn broadcast_converted_to_group(context: &Context) -> String {
+    translated(context, StockMessage::BroadcastConvertedToGroup).await
+}
+
 /// Stock string: `End-to-end encryption preferred.`.
 pub(crate) async fn e2e_preferred(context: &Context) -> String {
     translated(context, StockMessage::E2ePreferred).await
@@ -845,6 +961,59 @@ pub(crate) async fn securejoin_wait_timeout(context: &Context) -> String {
     translated(context, StockMessage::SecurejoinWaitTimeout).await
 }
 
+/// Stock string: `⚠️ You have not made a backup for a long time...`.
+pub(crate) async fn backup_reminder_msg_body(context: &Context) -> String {
+    translated(context, StockMessage::BackupReminderMsgBody).await
+}
+
+/// Stock string: `⚠️ Your encryption key was created on %1$s and may use outdated algorithms.`.
+pub(crate) async fn key_age_warning_msg_body(context: &Context, created: &str) -> String {
+    translated(context, StockMessage::KeyAgeWarningMsgBody)
+        .await
+        .replace1(created)
+}
+
+/// Stock string: `⚠️ The database of this profile has grown to %1$s...`.
+pub(crate) async fn db_size_warning_msg_body(context: &Context, db_size: &str) -> String {
+    translated(context, StockMessage::DbSizeWarningMsgBody)
+        .await
+        .replace1(db_size)
+}
+
+/// Stock string: `Messages can be end-to-end encrypted again.`
+pub(crate) async fn encryption_available(context: &Context) -> String {
+    translated(context, StockMessage::EncryptionAvailable).await
+}
+
+/// Stock string: `⚠️ Messages cannot be end-to-end encrypted...`.
+pub(crate) async fn encryption_unavailable(context: &Context) -> String {
+    translated(context, StockMessage::EncryptionUnavailable).await
+}
+
+/// Stock string: `Subscription request sent to the mailing list.`.
+pub(crate) async fn mailinglist_subscribe_sent(context: &Context) -> String {
+    translated(context, StockMessage::MailinglistSubscribeSent).await
+}
+
+/// Stock string: `You unsubscribed from this mailing list.`.
+pub(crate) async fn mailinglist_unsubscribed(context: &Context) -> String {
+    translated(context, StockMessage::MailinglistUnsubscribed).await
+}
+
+/// Stock string: `Unsubscribing failed: %1$s`.
+pub(crate) async fn mailinglist_unsubscribe_failed(context: &Context, error: &str) -> String {
+    translated(context, StockMessage::MailinglistUnsubscribeFailed)
+        .await
+        .replace1(error)
+}
+
+/// Stock string: `⚠️ This group has more than %1$s members...`.
+pub(crate) async fn group_member_soft_limit(context: &Context, limit: u64) -> String {
+    translated(context, StockMessage::GroupMemberSoftLimit)
+        .await
+        .replace1(&limit.to_string())
+}
+
 /// Stock string: `Scan to chat with %1$s`.
 pub(crate) async fn setup_contact_qr_description(
     context: &Context,
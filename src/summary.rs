@@ -39,6 +39,19 @@ fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
     }
 }
 
+/// Reaction shown in the chatlist instead of the last message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SummaryReaction {
+    /// ID of the contact who reacted.
+    pub contact_id: ContactId,
+
+    /// The reaction, usually a single emoji.
+    pub emoji: String,
+
+    /// Summary text of the message reacted to, without "Forwarded:" prefix.
+    pub msg_text: String,
+}
+
 /// Message summary.
 #[derive(Debug, Default)]
 pub struct Summary {
@@ -56,6 +69,14 @@ pub struct Summary {
 
     /// Message preview image path
     pub thumbnail_path: Option<String>,
+
+    /// Viewtype of the message, may be used to show an icon.
+    pub viewtype: Viewtype,
+
+    /// Reaction newer than the message.
+    ///
+    /// If set, [`Summary::text`] describes the reaction.
+    pub reaction: Option<SummaryReaction>,
 }
 
 impl Summary {
@@ -81,6 +102,12 @@ pub async fn new_with_reaction_details(
                 timestamp: msg.get_timestamp(), // message timestamp (not reaction) to make timestamps more consistent with chats ordering
                 state: msg.state, // message state (not reaction) - indicating if it was me sending the last message
                 thumbnail_path: None,
+                viewtype: reaction_msg.viewtype,
+                reaction: Some(SummaryReaction {
+                    contact_id: reaction_contact_id,
+                    emoji: reaction,
+                    msg_text: summary,
+                }),
             });
         }
         Self::new(context, msg, chat, contact).await
@@ -141,6 +168,8 @@ pub async fn new(
             timestamp: msg.get_timestamp(),
             state: msg.state,
             thumbnail_path,
+            viewtype: msg.viewtype,
+            reaction: None,
         })
     }
 
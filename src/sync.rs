@@ -8,6 +8,7 @@
 use crate::config::Config;
 use crate::constants::Blocked;
 use crate::contact::ContactId;
+use crate::contact_label;
 use crate::context::Context;
 use crate::log::LogExt;
 use crate::message::{Message, MsgId, Viewtype};
@@ -66,6 +67,17 @@ pub(crate) enum SyncData {
         src: String,  // RFC724 id (i.e. "Message-Id" header)
         dest: String, // RFC724 id (i.e. "Message-Id" header)
     },
+    AlterContactLabel {
+        name: String,
+        action: contact_label::SyncAction,
+    },
+    WebxdcStatusUpdates {
+        msg: String,     // RFC724 id (i.e. "Message-Id" header) of the webxdc instance
+        updates: String, // Status updates as sent on the wire
+        #[serde(default)]
+        senders: Vec<String>, // Sender addresses of the updates, empty if unknown
+    },
+    MarknoticedAllChats,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -261,9 +273,22 @@ pub(crate) async fn execute_sync_items(&self, items: &SyncItems) {
                 SyncDataOrUnknown::SyncData(data) => match data {
                     AddQrToken(token) => self.add_qr_token(token).await,
                     DeleteQrToken(token) => self.delete_qr_token(token).await,
-                    AlterChat { id, action } => self.sync_alter_chat(id, action).await,
+                    AlterChat { id, action } => {
+                        self.sync_alter_chat(id, action, item.timestamp).await
+                    }
                     SyncData::Config { key, val } => self.sync_config(key, val).await,
                     SyncData::SaveMessage { src, dest } => self.save_message(src, dest).await,
+                    SyncData::AlterContactLabel { name, action } => {
+                        self.sync_alter_contact_label(name, action).await
+                    }
+                    SyncData::WebxdcStatusUpdates {
+                        msg,
+                        updates,
+                        senders,
+                    } => self.add_synced_status_updates(msg, updates, senders).await,
+                    SyncData::MarknoticedAllChats => {
+                        chat::marknoticed_all_chats_ex(self, Sync::Nosync, item.timestamp).await
+                    }
                 },
                 SyncDataOrUnknown::Unknown(data) => {
                     warn!(self, "Ignored unknown sync item: {data}.");
@@ -0,0 +1,376 @@
+//! # Progress of fetching messages from the server.
+//!
+//! Fetching existing messages after adding an account with a big mailbox
+//! or fetching a lot of new messages at once may take minutes.
+//! While messages are fetched, [`EventType::SyncProgress`] events are emitted
+//! and UIs can query the current state with [`Context::get_sync_state`]
+//! to show a progress bar.
+//!
+//! After starting I/O, changes found on the server are collected
+//! until the first full sync is done.
+//! Then [`EventType::SyncReport`] is emitted once and the UI can show
+//! that the account is up to date, see [`Context::get_sync_report`].
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::Result;
+
+use crate::config::Config;
+use crate::context::Context;
+use crate::imap::FolderMeaning;
+use crate::tools::time;
+use crate::EventType;
+
+/// Minimum number of messages fetched at once to report the progress
+/// unless existing messages are fetched.
+const MIN_REPORTED_MSGS: usize = 10;
+
+/// Progress of fetching messages from a single folder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FolderSyncState {
+    /// Folder name.
+    pub folder: String,
+
+    /// Number of messages fetched so far.
+    pub fetched: usize,
+
+    /// Number of messages to fetch.
+    pub total: usize,
+}
+
+/// State of fetching messages from the server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncState {
+    /// True if the initial fetch of existing messages after configuration is done.
+    pub initial_sync_done: bool,
+
+    /// Folders messages are currently fetched from.
+    pub folders: Vec<FolderSyncState>,
+}
+
+/// Changes found in a single folder during the first full sync after starting I/O.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FolderSyncReport {
+    /// Folder name.
+    pub folder: String,
+
+    /// Number of new messages.
+    pub new_msgs: usize,
+
+    /// Number of messages deleted from the folder by other clients.
+    pub expunged: usize,
+
+    /// Number of messages marked as seen by other clients.
+    pub flags_changed: usize,
+}
+
+/// Changes found on the server during the first full sync after starting I/O.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SyncReport {
+    /// Timestamp of starting I/O.
+    pub started: i64,
+
+    /// Timestamp of finishing the first full sync.
+    pub finished: i64,
+
+    /// Folders with changes.
+    pub folders: Vec<FolderSyncReport>,
+}
+
+impl SyncReport {
+    /// Returns the total number of new messages.
+    pub fn new_msgs(&self) -> usize {
+        self.folders.iter().map(|folder| folder.new_msgs).sum()
+    }
+
+    /// Returns the total number of messages deleted by other clients.
+    pub fn expunged(&self) -> usize {
+        self.folders.iter().map(|folder| folder.expunged).sum()
+    }
+
+    /// Returns the total number of messages marked as seen by other clients.
+    pub fn flags_changed(&self) -> usize {
+        self.folders.iter().map(|folder| folder.flags_changed).sum()
+    }
+}
+
+/// Progress of the folders messages are currently fetched from.
+#[derive(Debug, Default)]
+pub(crate) struct SyncProgress {
+    /// Map from folder name to the number of fetched messages and the total number of messages.
+    folders: parking_lot::Mutex<BTreeMap<String, (usize, usize)>>,
+
+    /// True while existing messages are fetched after configuration.
+    pub(crate) fetching_existing_msgs: AtomicBool,
+
+    /// Sync report being collected or finished, see [`Context::get_sync_report`].
+    ///
+    /// The report is being collected as long as `finished` is 0.
+    report: parking_lot::Mutex<Option<SyncReport>>,
+
+    /// Watched folders which did not finish the first full sync yet.
+    report_pending_folders: parking_lot::Mutex<Vec<FolderMeaning>>,
+}
+
+impl Context {
+    /// Returns the state of fetching messages from the server.
+    pub async fn get_sync_state(&self) -> Result<SyncState> {
+        let folders: Vec<FolderSyncState> = self
+            .sync_progress
+            .folders
+            .lock()
+            .iter()
+            .map(|(folder, (fetched, total))| FolderSyncState {
+                folder: folder.clone(),
+                fetched: *fetched,
+                total: *total,
+            })
+            .collect();
+        let initial_sync_done = self.is_configured().await?
+            && self.get_config_bool(Config::FetchedExistingMsgs).await?
+            && !self
+                .sync_progress
+                .fetching_existing_msgs
+                .load(Ordering::Relaxed);
+        Ok(SyncState {
+            initial_sync_done,
+            folders,
+        })
+    }
+
+    /// Starts reporting the progress of fetching `total` messages from `folder`.
+    pub(crate) fn start_sync_progress(
+        &self,
+        folder: &str,
+        total: usize,
+        fetch_existing_msgs: bool,
+    ) {
+        if total == 0 || (total < MIN_REPORTED_MSGS && !fetch_existing_msgs) {
+            return;
+        }
+        self.sync_progress
+            .folders
+            .lock()
+            .insert(folder.to_string(), (0, total));
+        self.emit_sync_progress(folder, 0, total);
+    }
+
+    /// Reports that a message was fetched from `folder`.
+    pub(crate) fn advance_sync_progress(&self, folder: &str) {
+        let progress = self
+            .sync_progress
+            .folders
+            .lock()
+            .get_mut(folder)
+            .map(|(fetched, total)| {
+                *fetched = (*fetched + 1).min(*total);
+                (*fetched, *total)
+            });
+        if let Some((fetched, total)) = progress {
+            self.emit_sync_progress(folder, fetched, total);
+        }
+    }
+
+    /// Stops reporting the progress for `folder`.
+    pub(crate) fn finish_sync_progress(&self, folder: &str) {
+        let progress = self.sync_progress.folders.lock().remove(folder);
+        if let Some((fetched, total)) = progress {
+            if fetched < total {
+                // Some messages were skipped, report the folder as done anyway.
+                self.emit_sync_progress(folder, total, total);
+            }
+        }
+    }
+
+    fn emit_sync_progress(&self, folder: &str, fetched: usize, total: usize) {
+        self.emit_event(EventType::SyncProgress {
+            folder: folder.to_string(),
+            fetched,
+            total,
+        });
+    }
+
+    /// Returns the changes found on the server during the first full sync
+    /// after starting I/O the last time.
+    ///
+    /// Returns `None` if I/O was not started yet or the first full sync is not done.
+    pub fn get_sync_report(&self) -> Option<SyncReport> {
+        self.sync_progress
+            .report
+            .lock()
+            .clone()
+            .filter(|report| report.finished != 0)
+    }
+
+    /// Starts collecting a new sync report, called when I/O is started.
+    ///
+    /// The report is finished when all `folders` finished their first full sync.
+    pub(crate) fn start_sync_report(&self, folders: Vec<FolderMeaning>) {
+        let mut report = self.sync_progress.report.lock();
+        *report = Some(SyncReport {
+            started: time(),
+            ..Default::default()
+        });
+        *self.sync_progress.report_pending_folders.lock() = folders;
+    }
+
+    /// Returns true if the first full sync of the folder is not done yet
+    /// and changes are collected for the sync report.
+    pub(crate) fn is_sync_report_pending(&self, folder_meaning: FolderMeaning) -> bool {
+        let report = self.sync_progress.report.lock();
+        report.as_ref().is_some_and(|report| report.finished == 0)
+            && self
+                .sync_progress
+                .report_pending_folders
+                .lock()
+                .contains(&folder_meaning)
+    }
+
+    /// Adds changes found in `folder` to the pending sync report.
+    pub(crate) fn add_to_sync_report(&self, folder: &str, f: impl FnOnce(&mut FolderSyncReport)) {
+        let mut report = self.sync_progress.report.lock();
+        let Some(report) = report.as_mut().filter(|report| report.finished == 0) else {
+            return;
+        };
+        let index = match report.folders.iter().position(|r| r.folder == folder) {
+            Some(index) => index,
+            None => {
+                report.folders.push(FolderSyncReport {
+                    folder: folder.to_string(),
+                    ..Default::default()
+                });
+                report.folders.len() - 1
+            }
+        };
+        f(&mut report.folders[index]);
+    }
+
+    /// Marks the first full sync of the folder as done.
+    ///
+    /// When all watched folders are done,
+    /// finishes the pending sync report and emits [`EventType::SyncReport`].
+    /// Does nothing if the report is already finished.
+    pub(crate) fn finish_sync_report(&self, folder_meaning: FolderMeaning) {
+        let report = {
+            let mut report = self.sync_progress.report.lock();
+            let mut pending_folders = self.sync_progress.report_pending_folders.lock();
+            pending_folders.retain(|meaning| *meaning != folder_meaning);
+            if !pending_folders.is_empty() {
+                return;
+            }
+            match report.as_mut() {
+                Some(report) if report.finished == 0 => {
+                    report.finished = time();
+                    report.clone()
+                }
+                _ => return,
+            }
+        };
+        info!(
+            self,
+            "First sync done: {} new messages, {} deleted, {} marked as seen.",
+            report.new_msgs(),
+            report.expunged(),
+            report.flags_changed()
+        );
+        self.emit_event(EventType::SyncReport {
+            new_msgs: report.new_msgs(),
+            expunged: report.expunged(),
+            flags_changed: report.flags_changed(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestContext;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_sync_progress() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        assert!(t.get_sync_state().await?.folders.is_empty());
+
+        // Few new messages are not reported.
+        t.start_sync_progress("INBOX", 3, false);
+        assert!(t.get_sync_state().await?.folders.is_empty());
+
+        t.start_sync_progress("INBOX", 3, true);
+        t.advance_sync_progress("INBOX");
+        assert_eq!(
+            t.get_sync_state().await?.folders,
+            [FolderSyncState {
+                folder: "INBOX".to_string(),
+                fetched: 1,
+                total: 3
+            }]
+        );
+        let event = t
+            .evtracker
+            .get_matching(|evt| matches!(evt, EventType::SyncProgress { fetched: 1, .. }))
+            .await;
+        let EventType::SyncProgress { folder, total, .. } = event else {
+            unreachable!();
+        };
+        assert_eq!(folder, "INBOX");
+        assert_eq!(total, 3);
+
+        // Finishing reports the folder as done even if messages were skipped.
+        t.finish_sync_progress("INBOX");
+        t.evtracker
+            .get_matching(|evt| matches!(evt, EventType::SyncProgress { fetched: 3, .. }))
+            .await;
+        assert!(t.get_sync_state().await?.folders.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_sync_report() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        assert!(t.get_sync_report().is_none());
+
+        // Changes are not collected before starting I/O.
+        t.add_to_sync_report("INBOX", |report| report.new_msgs += 1);
+
+        t.start_sync_report(vec![FolderMeaning::Inbox, FolderMeaning::Mvbox]);
+        assert!(t.is_sync_report_pending(FolderMeaning::Inbox));
+        assert!(!t.is_sync_report_pending(FolderMeaning::Sent));
+        t.add_to_sync_report("INBOX", |report| report.new_msgs += 2);
+        t.add_to_sync_report("DeltaChat", |report| report.expunged += 1);
+        t.add_to_sync_report("INBOX", |report| report.flags_changed += 3);
+        assert!(t.get_sync_report().is_none());
+
+        // The report is finished only after all watched folders are synced.
+        t.finish_sync_report(FolderMeaning::Inbox);
+        assert!(!t.is_sync_report_pending(FolderMeaning::Inbox));
+        assert!(t.is_sync_report_pending(FolderMeaning::Mvbox));
+        t.add_to_sync_report("DeltaChat", |report| report.expunged += 1);
+        assert!(t.get_sync_report().is_none());
+
+        t.finish_sync_report(FolderMeaning::Mvbox);
+        let event = t
+            .evtracker
+            .get_matching(|evt| matches!(evt, EventType::SyncReport { .. }))
+            .await;
+        assert_eq!(
+            event,
+            EventType::SyncReport {
+                new_msgs: 2,
+                expunged: 2,
+                flags_changed: 3
+            }
+        );
+        let report = t.get_sync_report().unwrap();
+        assert_eq!(report.folders.len(), 2);
+        assert_eq!(report.folders[0].folder, "INBOX");
+        assert_eq!(report.folders[0].new_msgs, 2);
+        assert!(report.finished >= report.started);
+
+        // Later changes are not added to the finished report.
+        t.add_to_sync_report("INBOX", |report| report.new_msgs += 1);
+        t.finish_sync_report(FolderMeaning::Inbox);
+        assert_eq!(t.get_sync_report().unwrap().new_msgs(), 2);
+        Ok(())
+    }
+}
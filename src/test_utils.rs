@@ -790,6 +790,7 @@ async fn display_chat(&self, chat_id: ChatId) -> String {
             MessageListOptions {
                 info_only: false,
                 add_daymarker: false,
+                include_hidden_local: false,
             },
         )
         .await
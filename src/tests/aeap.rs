@@ -341,6 +341,7 @@ async fn get_last_info_msg(t: &TestContext, chat_id: ChatId) -> Option<Message>
         chat::MessageListOptions {
             info_only: true,
             add_daymarker: false,
+            include_hidden_local: false,
         },
     )
     .await
@@ -6,6 +6,7 @@
 use crate::config::Config;
 use crate::constants::{Chattype, DC_GCL_FOR_FORWARDING};
 use crate::contact::{Contact, ContactId, Origin};
+use crate::events::EventType;
 use crate::message::Message;
 use crate::mimefactory::MimeFactory;
 use crate::mimeparser::SystemMessage;
@@ -953,6 +954,72 @@ async fn test_no_unencrypted_name_if_encrypted() -> Result<()> {
     Ok(())
 }
 
+/// Tests that a member of a protected group who reinstalled Delta Chat
+/// can be verified again and added back to the existing group.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_repair_verified_group_member() -> Result<()> {
+    let mut tcm = TestContextManager::new();
+    let alice = &tcm.alice().await;
+    let bob = tcm.bob().await;
+
+    tcm.execute_securejoin(&bob, alice).await;
+    let alice_chat_id = alice
+        .create_group_with_members(ProtectionStatus::Protected, "Group", &[&bob])
+        .await;
+    let sent = alice.send_text(alice_chat_id, "I created a group").await;
+    bob.recv_msg(&sent).await;
+    let alice_bob_id = alice.add_or_lookup_contact(&bob).await.id;
+    assert!(
+        chat::get_members_needing_reverification(alice, alice_chat_id)
+            .await?
+            .is_empty()
+    );
+
+    tcm.section("Bob reinstalls DC");
+    drop(bob);
+    let bob_new = &tcm.unconfigured().await;
+    bob_new.configure_addr("bob@example.net").await;
+    e2ee::ensure_secret_key_exists(bob_new).await?;
+
+    tcm.send_recv(bob_new, alice, "Hi, I reinstalled").await;
+    let event = alice
+        .evtracker
+        .get_matching(|evt| matches!(evt, EventType::VerifiedGroupMemberKeyChanged { .. }))
+        .await;
+    assert_eq!(
+        event,
+        EventType::VerifiedGroupMemberKeyChanged {
+            chat_id: alice_chat_id,
+            contact_id: alice_bob_id,
+        }
+    );
+    assert_eq!(
+        chat::get_members_needing_reverification(alice, alice_chat_id).await?,
+        vec![alice_bob_id]
+    );
+
+    // Bob has to be verified again first.
+    assert!(
+        chat::repair_verified_group_member(alice, alice_chat_id, alice_bob_id)
+            .await
+            .is_err()
+    );
+
+    tcm.execute_securejoin(bob_new, alice).await;
+    chat::repair_verified_group_member(alice, alice_chat_id, alice_bob_id).await?;
+    let msg = bob_new.recv_msg(&alice.pop_sent_msg().await).await;
+    let bob_chat = Chat::load_from_db(bob_new, msg.chat_id).await?;
+    assert_eq!(bob_chat.typ, Chattype::Group);
+    assert!(bob_chat.is_protected());
+    assert!(
+        chat::get_members_needing_reverification(alice, alice_chat_id)
+            .await?
+            .is_empty()
+    );
+
+    Ok(())
+}
+
 // ============== Helper Functions ==============
 
 async fn assert_verified(this: &TestContext, other: &TestContext, protected: ProtectionStatus) {
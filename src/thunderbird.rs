@@ -0,0 +1,563 @@
+//! # Import of account settings and contacts from Thunderbird profiles.
+//!
+//! Helps users switching from Thunderbird to set up Delta Chat.
+//! [`get_thunderbird_accounts`] reads the IMAP accounts from the `prefs.js` file of a profile,
+//! [`set_config_from_thunderbird_account`] sets up one of them for [`Context::configure`]
+//! and [`import_thunderbird_contacts`] adds the contacts of the personal address book.
+//!
+//! Passwords are stored encrypted by Thunderbird and are not imported,
+//! the user has to enter them before configuring.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context as _, Result};
+use rusqlite::{Connection, OpenFlags};
+
+use crate::config::Config;
+use crate::constants::{DC_LP_AUTH_NORMAL, DC_LP_AUTH_OAUTH2};
+use crate::contact::Contact;
+use crate::context::Context;
+use crate::provider::Socket;
+
+/// Thunderbird `authMethod` value for OAuth2.
+const TB_AUTH_OAUTH2: i64 = 10;
+
+/// Server settings of an account found in a Thunderbird profile.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ThunderbirdServer {
+    /// Server hostname.
+    pub hostname: String,
+
+    /// Server port.
+    ///
+    /// 0 if not specified.
+    pub port: u16,
+
+    /// Socket security.
+    pub security: Socket,
+
+    /// Username.
+    ///
+    /// Empty string if not specified.
+    pub user: String,
+}
+
+/// IMAP account found in a Thunderbird profile.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ThunderbirdAccount {
+    /// Email address of the default identity of the account.
+    pub addr: String,
+
+    /// Name of the default identity of the account.
+    pub displayname: Option<String>,
+
+    /// IMAP settings.
+    pub imap: ThunderbirdServer,
+
+    /// SMTP settings, `None` if the identity has no outgoing server.
+    pub smtp: Option<ThunderbirdServer>,
+
+    /// Whether the account uses OAuth2 for IMAP.
+    pub oauth2: bool,
+}
+
+/// Preferences read from a `prefs.js` file.
+struct Prefs(HashMap<String, serde_json::Value>);
+
+impl Prefs {
+    /// Parses the `user_pref()` calls of a `prefs.js` file.
+    ///
+    /// The arguments are JSON-compatible literals, other lines are ignored.
+    fn parse(prefs_js: &str) -> Self {
+        let mut prefs = HashMap::new();
+        for line in prefs_js.lines() {
+            let Some(args) = line
+                .trim()
+                .strip_prefix("user_pref(")
+                .and_then(|s| s.strip_suffix(");"))
+            else {
+                continue;
+            };
+            if let Ok((name, value)) =
+                serde_json::from_str::<(String, serde_json::Value)>(&format!("[{args}]"))
+            {
+                prefs.insert(name, value);
+            }
+        }
+        Self(prefs)
+    }
+
+    /// Returns a non-empty string preference.
+    fn get_str(&self, name: &str) -> Option<&str> {
+        self.0
+            .get(name)?
+            .as_str()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+    }
+
+    fn get_int(&self, name: &str) -> Option<i64> {
+        self.0.get(name)?.as_i64()
+    }
+
+    /// Reads the SMTP server with the given key.
+    ///
+    /// Older Thunderbird versions use the `mail.smtpserver` prefix,
+    /// newer ones `mail.outgoingserver`.
+    fn get_smtp_server(&self, key: &str) -> Option<ThunderbirdServer> {
+        ["mail.smtpserver", "mail.outgoingserver"]
+            .iter()
+            .find_map(|prefix| {
+                let hostname = self.get_str(&format!("{prefix}.{key}.hostname"))?;
+                Some(ThunderbirdServer {
+                    hostname: hostname.to_string(),
+                    port: self.get_port(&format!("{prefix}.{key}.port")),
+                    security: self.get_security(&format!("{prefix}.{key}.try_ssl")),
+                    user: self
+                        .get_str(&format!("{prefix}.{key}.username"))
+                        .unwrap_or_default()
+                        .to_string(),
+                })
+            })
+    }
+
+    fn get_port(&self, name: &str) -> u16 {
+        self.get_int(name)
+            .and_then(|port| u16::try_from(port).ok())
+            .unwrap_or_default()
+    }
+
+    /// Converts Thunderbird's socket type to [`Socket`].
+    fn get_security(&self, name: &str) -> Socket {
+        match self.get_int(name) {
+            Some(0) => Socket::Plain,
+            Some(2) => Socket::Starttls,
+            Some(3) => Socket::Ssl,
+            // 1 is the deprecated "STARTTLS, if available".
+            _ => Socket::Automatic,
+        }
+    }
+}
+
+/// Extracts the IMAP accounts from the preferences.
+///
+/// POP3, local folders, news and feed accounts are skipped.
+fn get_accounts(prefs: &Prefs) -> Vec<ThunderbirdAccount> {
+    let mut accounts = Vec::new();
+    let Some(account_keys) = prefs.get_str("mail.accountmanager.accounts") else {
+        return accounts;
+    };
+    for account in account_keys.split(',').map(str::trim) {
+        let Some(server) = prefs.get_str(&format!("mail.account.{account}.server")) else {
+            continue;
+        };
+        if prefs.get_str(&format!("mail.server.{server}.type")) != Some("imap") {
+            continue;
+        }
+        let Some(hostname) = prefs.get_str(&format!("mail.server.{server}.hostname")) else {
+            continue;
+        };
+        let Some(identity) = prefs
+            .get_str(&format!("mail.account.{account}.identities"))
+            .and_then(|identities| identities.split(',').next())
+            .map(str::trim)
+        else {
+            continue;
+        };
+        let Some(addr) = prefs.get_str(&format!("mail.identity.{identity}.useremail")) else {
+            continue;
+        };
+
+        let smtp = prefs
+            .get_str(&format!("mail.identity.{identity}.smtpServer"))
+            .or_else(|| prefs.get_str("mail.smtp.defaultserver"))
+            .and_then(|key| prefs.get_smtp_server(key));
+        accounts.push(ThunderbirdAccount {
+            addr: addr.to_string(),
+            displayname: prefs
+                .get_str(&format!("mail.identity.{identity}.fullName"))
+                .map(|s| s.to_string()),
+            imap: ThunderbirdServer {
+                hostname: hostname.to_string(),
+                port: prefs.get_port(&format!("mail.server.{server}.port")),
+                security: prefs.get_security(&format!("mail.server.{server}.socketType")),
+                user: prefs
+                    .get_str(&format!("mail.server.{server}.userName"))
+                    .unwrap_or_default()
+                    .to_string(),
+            },
+            smtp,
+            oauth2: prefs.get_int(&format!("mail.server.{server}.authMethod"))
+                == Some(TB_AUTH_OAUTH2),
+        });
+    }
+    accounts
+}
+
+/// Returns the profile directories listed in `profiles.ini`
+/// of the given Thunderbird directory, the default profile first.
+///
+/// The Thunderbird directory is e.g. `~/.thunderbird` on Linux
+/// or `%APPDATA%\Thunderbird` on Windows.
+pub async fn get_thunderbird_profiles(dir: &Path) -> Result<Vec<PathBuf>> {
+    let ini_path = dir.join("profiles.ini");
+    let ini = tokio::fs::read_to_string(&ini_path)
+        .await
+        .with_context(|| format!("Failed to read {}", ini_path.display()))?;
+
+    let mut profiles = Vec::new();
+    let mut default_profile = None;
+    let mut path = None;
+    let mut is_relative = true;
+    let mut is_default = false;
+    let mut is_profile_section = false;
+    // Empty section header at the end flushes the last section.
+    for line in ini.lines().map(str::trim).chain(std::iter::once("[]")) {
+        if line.starts_with('[') {
+            if let Some(path) = path.take() {
+                let path = match is_relative {
+                    true => dir.join(path),
+                    false => PathBuf::from(path),
+                };
+                match is_default {
+                    true => default_profile = Some(path),
+                    false => profiles.push(path),
+                }
+            }
+            is_relative = true;
+            is_default = false;
+            is_profile_section = line.starts_with("[Profile");
+        } else if let Some((key, value)) = line.split_once('=') {
+            match key.trim() {
+                "Path" if is_profile_section => path = Some(value.trim().to_string()),
+                "IsRelative" => is_relative = value.trim() != "0",
+                "Default" => is_default = value.trim() == "1",
+                _ => {}
+            }
+        }
+    }
+    if let Some(default_profile) = default_profile {
+        profiles.insert(0, default_profile);
+    }
+    Ok(profiles)
+}
+
+/// Returns the IMAP accounts configured in the given Thunderbird profile directory.
+pub async fn get_thunderbird_accounts(profile: &Path) -> Result<Vec<ThunderbirdAccount>> {
+    let prefs_path = profile.join("prefs.js");
+    let prefs_js = tokio::fs::read_to_string(&prefs_path)
+        .await
+        .with_context(|| format!("Failed to read {}", prefs_path.display()))?;
+    Ok(get_accounts(&Prefs::parse(&prefs_js)))
+}
+
+/// Sets the login parameters of a Thunderbird account,
+/// so that [`Context::configure`] can be called after setting [`Config::MailPw`].
+///
+/// Previously entered server settings are replaced.
+/// The display name is only set if there is none yet.
+pub async fn set_config_from_thunderbird_account(
+    context: &Context,
+    account: &ThunderbirdAccount,
+) -> Result<()> {
+    context
+        .set_config_internal(Config::Addr, Some(&account.addr))
+        .await?;
+    set_server_config(
+        context,
+        Some(&account.imap),
+        Config::MailServer,
+        Config::MailPort,
+        Config::MailSecurity,
+        Config::MailUser,
+    )
+    .await?;
+    set_server_config(
+        context,
+        account.smtp.as_ref(),
+        Config::SendServer,
+        Config::SendPort,
+        Config::SendSecurity,
+        Config::SendUser,
+    )
+    .await?;
+    let server_flags = match account.oauth2 {
+        true => DC_LP_AUTH_OAUTH2,
+        false => DC_LP_AUTH_NORMAL,
+    };
+    context
+        .set_config_internal(Config::ServerFlags, Some(&server_flags.to_string()))
+        .await?;
+
+    if let Some(displayname) = &account.displayname {
+        if context.get_config(Config::Displayname).await?.is_none() {
+            context
+                .set_config(Config::Displayname, Some(displayname))
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+async fn set_server_config(
+    context: &Context,
+    server: Option<&ThunderbirdServer>,
+    server_key: Config,
+    port_key: Config,
+    security_key: Config,
+    user_key: Config,
+) -> Result<()> {
+    let hostname = server.map(|server| server.hostname.as_str());
+    let port = server
+        .map(|server| server.port)
+        .filter(|port| *port != 0)
+        .map(|port| port.to_string());
+    let security = server
+        .map(|server| server.security)
+        .filter(|security| *security != Socket::Automatic)
+        .map(|security| (security as u8).to_string());
+    let user = server
+        .map(|server| server.user.as_str())
+        .filter(|user| !user.is_empty());
+    context.set_config_internal(server_key, hostname).await?;
+    context
+        .set_config_internal(port_key, port.as_deref())
+        .await?;
+    context
+        .set_config_internal(security_key, security.as_deref())
+        .await?;
+    context.set_config_internal(user_key, user).await?;
+    Ok(())
+}
+
+/// Reads the personal address book `abook.sqlite` of a Thunderbird profile
+/// in the format expected by [`Contact::add_address_book`].
+fn read_address_book(path: &Path) -> Result<String> {
+    let conn = Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut stmt = conn.prepare(
+        "SELECT e.value, IFNULL(n.value, '')
+         FROM properties e
+         LEFT JOIN properties n ON n.card=e.card AND n.name='DisplayName'
+         WHERE e.name IN ('PrimaryEmail', 'SecondEmail') AND e.value!=''",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        let addr: String = row.get(0)?;
+        let name: String = row.get(1)?;
+        Ok((addr, name))
+    })?;
+
+    let mut addr_book = String::new();
+    for row in rows {
+        let (addr, name) = row?;
+        addr_book += &format!("{}\n{}\n", name.replace('\n', " "), addr.trim());
+    }
+    Ok(addr_book)
+}
+
+/// Adds the contacts of the personal address book of the given Thunderbird profile directory.
+///
+/// Returns the number of modified contacts.
+pub async fn import_thunderbird_contacts(context: &Context, profile: &Path) -> Result<usize> {
+    let path = profile.join("abook.sqlite");
+    let addr_book = tokio::task::spawn_blocking(move || read_address_book(&path)).await??;
+    Contact::add_address_book(context, &addr_book).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contact::Origin;
+    use crate::test_utils::TestContext;
+
+    const PREFS_JS: &str = r#"// Mozilla User Preferences
+
+// DO NOT EDIT THIS FILE.
+user_pref("mail.account.account1.identities", "id1");
+user_pref("mail.account.account1.server", "server1");
+user_pref("mail.account.account2.server", "server2");
+user_pref("mail.account.account3.identities", "id2,id3");
+user_pref("mail.account.account3.server", "server3");
+user_pref("mail.accountmanager.accounts", "account1,account3,account2");
+user_pref("mail.identity.id1.fullName", "Alice \"Al\" Example");
+user_pref("mail.identity.id1.smtpServer", "smtp1");
+user_pref("mail.identity.id1.useremail", "alice@example.org");
+user_pref("mail.identity.id2.useremail", "alice@example.net");
+user_pref("mail.identity.id3.useremail", "alias@example.net");
+user_pref("mail.server.server1.hostname", "imap.example.org");
+user_pref("mail.server.server1.port", 993);
+user_pref("mail.server.server1.socketType", 3);
+user_pref("mail.server.server1.type", "imap");
+user_pref("mail.server.server1.userName", "alice");
+user_pref("mail.server.server2.hostname", "Local Folders");
+user_pref("mail.server.server2.type", "none");
+user_pref("mail.server.server3.authMethod", 10);
+user_pref("mail.server.server3.hostname", "imap.example.net");
+user_pref("mail.server.server3.type", "imap");
+user_pref("mail.smtp.defaultserver", "smtp2");
+user_pref("mail.smtpserver.smtp1.hostname", "smtp.example.org");
+user_pref("mail.smtpserver.smtp1.port", 587);
+user_pref("mail.smtpserver.smtp1.try_ssl", 2);
+user_pref("mail.smtpserver.smtp1.username", "alice");
+user_pref("mail.outgoingserver.smtp2.hostname", "smtp.example.net");
+"#;
+
+    #[test]
+    fn test_get_accounts() {
+        let accounts = get_accounts(&Prefs::parse(PREFS_JS));
+        assert_eq!(
+            accounts,
+            vec![
+                ThunderbirdAccount {
+                    addr: "alice@example.org".to_string(),
+                    displayname: Some("Alice \"Al\" Example".to_string()),
+                    imap: ThunderbirdServer {
+                        hostname: "imap.example.org".to_string(),
+                        port: 993,
+                        security: Socket::Ssl,
+                        user: "alice".to_string(),
+                    },
+                    smtp: Some(ThunderbirdServer {
+                        hostname: "smtp.example.org".to_string(),
+                        port: 587,
+                        security: Socket::Starttls,
+                        user: "alice".to_string(),
+                    }),
+                    oauth2: false,
+                },
+                ThunderbirdAccount {
+                    addr: "alice@example.net".to_string(),
+                    displayname: None,
+                    imap: ThunderbirdServer {
+                        hostname: "imap.example.net".to_string(),
+                        ..Default::default()
+                    },
+                    smtp: Some(ThunderbirdServer {
+                        hostname: "smtp.example.net".to_string(),
+                        ..Default::default()
+                    }),
+                    oauth2: true,
+                },
+            ]
+        );
+        assert!(get_accounts(&Prefs::parse("")).is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_get_thunderbird_profiles() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        tokio::fs::write(
+            dir.path().join("profiles.ini"),
+            "[Profile1]\nName=work\nIsRelative=0\nPath=/home/alice/work\n\n\
+             [Profile0]\nName=default\nIsRelative=1\nPath=Profiles/abcd.default\nDefault=1\n\n\
+             [General]\nStartWithLastProfile=1\n",
+        )
+        .await?;
+        assert_eq!(
+            get_thunderbird_profiles(dir.path()).await?,
+            vec![
+                dir.path().join("Profiles/abcd.default"),
+                PathBuf::from("/home/alice/work"),
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_set_config_from_thunderbird_account() -> Result<()> {
+        let t = TestContext::new().await;
+        let dir = tempfile::tempdir()?;
+        tokio::fs::write(dir.path().join("prefs.js"), PREFS_JS).await?;
+        let accounts = get_thunderbird_accounts(dir.path()).await?;
+
+        t.set_config(Config::SendPort, Some("25")).await?;
+        set_config_from_thunderbird_account(&t, &accounts[0]).await?;
+        assert_eq!(
+            t.get_config(Config::Addr).await?.as_deref(),
+            Some("alice@example.org")
+        );
+        assert_eq!(
+            t.get_config(Config::MailServer).await?.as_deref(),
+            Some("imap.example.org")
+        );
+        assert_eq!(
+            t.get_config(Config::MailPort).await?.as_deref(),
+            Some("993")
+        );
+        assert_eq!(
+            t.get_config(Config::MailSecurity).await?.as_deref(),
+            Some("1")
+        );
+        assert_eq!(
+            t.get_config(Config::MailUser).await?.as_deref(),
+            Some("alice")
+        );
+        assert_eq!(
+            t.get_config(Config::SendPort).await?.as_deref(),
+            Some("587")
+        );
+        assert_eq!(
+            t.get_config(Config::SendSecurity).await?.as_deref(),
+            Some("2")
+        );
+        assert_eq!(
+            t.get_config(Config::Displayname).await?.as_deref(),
+            Some("Alice \"Al\" Example")
+        );
+
+        set_config_from_thunderbird_account(&t, &accounts[1]).await?;
+        assert_eq!(
+            t.get_config(Config::MailServer).await?.as_deref(),
+            Some("imap.example.net")
+        );
+        assert_eq!(t.get_config(Config::MailPort).await?, None);
+        assert_eq!(t.get_config(Config::MailUser).await?, None);
+        assert_eq!(t.get_config(Config::SendPort).await?, None);
+        assert_eq!(
+            t.get_config_int(Config::ServerFlags).await?,
+            DC_LP_AUTH_OAUTH2
+        );
+        assert_eq!(
+            t.get_config(Config::Displayname).await?.as_deref(),
+            Some("Alice \"Al\" Example")
+        );
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_import_thunderbird_contacts() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let dir = tempfile::tempdir()?;
+        let conn = Connection::open(dir.path().join("abook.sqlite"))?;
+        conn.execute_batch(
+            "CREATE TABLE properties (card TEXT, name TEXT, value TEXT);
+             INSERT INTO properties VALUES
+               ('1', 'DisplayName', 'Bob'),
+               ('1', 'PrimaryEmail', 'bob@example.net'),
+               ('1', 'SecondEmail', 'bob@example.com'),
+               ('2', 'PrimaryEmail', 'fiona@example.net'),
+               ('3', 'DisplayName', 'No address');",
+        )?;
+        drop(conn);
+
+        assert_eq!(import_thunderbird_contacts(&t, dir.path()).await?, 3);
+        let bob_id = Contact::lookup_id_by_addr(&t, "bob@example.net", Origin::AddressBook)
+            .await?
+            .unwrap();
+        let bob = Contact::get_by_id(&t, bob_id).await?;
+        assert_eq!(bob.get_name(), "Bob");
+        assert!(
+            Contact::lookup_id_by_addr(&t, "bob@example.com", Origin::AddressBook)
+                .await?
+                .is_some()
+        );
+
+        // Importing again does not modify anything.
+        assert_eq!(import_thunderbird_contacts(&t, dir.path()).await?, 0);
+        Ok(())
+    }
+}
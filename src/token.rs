@@ -10,6 +10,11 @@
 use crate::context::Context;
 use crate::tools::{create_id, time};
 
+/// SQL condition selecting limited invite auth tokens `a` that are still valid
+/// at the time passed as the first parameter.
+const LIMITED_INVITE_VALID: &str =
+    "(a.expires=0 OR a.expires>?) AND (a.max_uses=0 OR a.uses<a.max_uses)";
+
 /// Token namespace
 #[derive(
     Debug, Default, Display, Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive, ToSql, FromSql,
@@ -20,6 +25,36 @@ pub enum Namespace {
     Unknown = 0,
     Auth = 110,
     InviteNumber = 100,
+
+    /// Auth token of a one-time group invite which requires approval of the inviter.
+    ///
+    /// The foreign key is the group ID.
+    OneTimeAuth = 111,
+
+    /// Invite number of a one-time group invite.
+    ///
+    /// The foreign key is the corresponding [`Namespace::OneTimeAuth`] token.
+    OneTimeInviteNumber = 101,
+
+    /// Auth token of a word-encoded setup-contact invite code.
+    ///
+    /// The token is only valid for a limited time after its creation.
+    InviteWordsAuth = 112,
+
+    /// Invite number of a word-encoded setup-contact invite code.
+    InviteWordsNumber = 102,
+
+    /// Auth token of a group invite with an expiration time and/or a use limit.
+    ///
+    /// The foreign key is the group ID.
+    /// The limits are stored in the `expires` and `max_uses` columns of the token,
+    /// zero meaning no limit.
+    LimitedAuth = 113,
+
+    /// Invite number of a limited group invite.
+    ///
+    /// The foreign key is the corresponding [`Namespace::LimitedAuth`] token.
+    LimitedInviteNumber = 103,
 }
 
 /// Saves a token to the database.
@@ -86,16 +121,44 @@ pub async fn exists(context: &Context, namespace: Namespace, token: &str) -> Res
     Ok(exists)
 }
 
+/// Returns true if the token exists in the namespace and was created at `timestamp` or later.
+pub async fn exists_since(
+    context: &Context,
+    namespace: Namespace,
+    token: &str,
+    timestamp: i64,
+) -> Result<bool> {
+    let exists = context
+        .sql
+        .exists(
+            "SELECT COUNT(*) FROM tokens WHERE namespc=? AND token=? AND timestamp>=?",
+            (namespace, token, timestamp),
+        )
+        .await?;
+    Ok(exists)
+}
+
 /// Looks up foreign key by auth token.
 ///
 /// Returns None if auth token is not valid.
 /// Returns an empty string if the token corresponds to "setup contact" rather than group join.
 pub async fn auth_foreign_key(context: &Context, token: &str) -> Result<Option<String>> {
+    foreign_key(context, Namespace::Auth, token).await
+}
+
+/// Looks up foreign key of a token.
+///
+/// Returns None if the token does not exist in the namespace.
+pub async fn foreign_key(
+    context: &Context,
+    namespace: Namespace,
+    token: &str,
+) -> Result<Option<String>> {
     context
         .sql
         .query_row_optional(
             "SELECT foreign_key FROM tokens WHERE namespc=? AND token=?",
-            (Namespace::Auth, token),
+            (namespace, token),
             |row| {
                 let foreign_key: String = row.get(0)?;
                 Ok(foreign_key)
@@ -114,3 +177,170 @@ pub async fn delete(context: &Context, namespace: Namespace, token: &str) -> Res
         .await?;
     Ok(())
 }
+
+/// Deletes the tokens of a one-time group invite, so it cannot be used again.
+pub async fn delete_one_time_invite(context: &Context, auth: &str) -> Result<()> {
+    context
+        .sql
+        .execute(
+            "DELETE FROM tokens WHERE (namespc=? AND token=?) OR (namespc=? AND foreign_key=?);",
+            (
+                Namespace::OneTimeAuth,
+                auth,
+                Namespace::OneTimeInviteNumber,
+                auth,
+            ),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Saves the tokens of a limited group invite.
+///
+/// `expires` is a timestamp after which the invite is not valid anymore,
+/// `max_uses` is the number of joiners that may use the invite.
+/// Zero means no limit in both cases.
+pub async fn save_limited_invite(
+    context: &Context,
+    grpid: &str,
+    invitenumber: &str,
+    auth: &str,
+    expires: i64,
+    max_uses: u32,
+) -> Result<()> {
+    let now = time();
+    context
+        .sql
+        .transaction(|transaction| {
+            transaction.execute(
+                "INSERT INTO tokens (namespc, foreign_key, token, timestamp, expires, max_uses)
+                 VALUES (?, ?, ?, ?, ?, ?)",
+                (Namespace::LimitedAuth, grpid, auth, now, expires, max_uses),
+            )?;
+            transaction.execute(
+                "INSERT INTO tokens (namespc, foreign_key, token, timestamp) VALUES (?, ?, ?, ?)",
+                (Namespace::LimitedInviteNumber, auth, invitenumber, now),
+            )?;
+            Ok(())
+        })
+        .await
+}
+
+/// Returns true if the invite number belongs to a limited group invite that is still valid.
+pub async fn limited_invite_number_valid(context: &Context, invitenumber: &str) -> Result<bool> {
+    let exists = context
+        .sql
+        .exists(
+            &format!(
+                "SELECT COUNT(*) FROM tokens n
+                 INNER JOIN tokens a ON a.namespc=? AND a.token=n.foreign_key
+                 WHERE n.namespc=? AND n.token=? AND {LIMITED_INVITE_VALID}"
+            ),
+            (
+                Namespace::LimitedAuth,
+                Namespace::LimitedInviteNumber,
+                invitenumber,
+                time(),
+            ),
+        )
+        .await?;
+    Ok(exists)
+}
+
+/// Looks up the group ID of a limited group invite by its auth token.
+///
+/// Returns None if there is no such invite or if it is expired or used up.
+pub async fn limited_invite_foreign_key(context: &Context, auth: &str) -> Result<Option<String>> {
+    context
+        .sql
+        .query_row_optional(
+            &format!(
+                "SELECT a.foreign_key FROM tokens a
+                 WHERE a.namespc=? AND a.token=? AND {LIMITED_INVITE_VALID}"
+            ),
+            (Namespace::LimitedAuth, auth, time()),
+            |row| {
+                let foreign_key: String = row.get(0)?;
+                Ok(foreign_key)
+            },
+        )
+        .await
+}
+
+/// Counts a use of a limited group invite.
+///
+/// The invite is deleted once it reaches its use limit.
+pub async fn use_limited_invite(context: &Context, auth: &str) -> Result<()> {
+    let exhausted = context
+        .sql
+        .transaction(|transaction| {
+            transaction.execute(
+                "UPDATE tokens SET uses=uses+1 WHERE namespc=? AND token=?",
+                (Namespace::LimitedAuth, auth),
+            )?;
+            let exhausted = transaction.query_row(
+                "SELECT COUNT(*) FROM tokens WHERE namespc=? AND token=? AND max_uses>0 AND uses>=max_uses",
+                (Namespace::LimitedAuth, auth),
+                |row| row.get::<_, i64>(0),
+            )? > 0;
+            Ok(exhausted)
+        })
+        .await?;
+    if exhausted {
+        delete_limited_invite(context, auth).await?;
+    }
+    Ok(())
+}
+
+/// Deletes the tokens of a limited group invite.
+pub async fn delete_limited_invite(context: &Context, auth: &str) -> Result<()> {
+    context
+        .sql
+        .execute(
+            "DELETE FROM tokens WHERE (namespc=? AND token=?) OR (namespc=? AND foreign_key=?);",
+            (
+                Namespace::LimitedAuth,
+                auth,
+                Namespace::LimitedInviteNumber,
+                auth,
+            ),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Deletes the tokens of word-encoded invite codes created before `timestamp`.
+pub async fn delete_invite_words_before(context: &Context, timestamp: i64) -> Result<()> {
+    context
+        .sql
+        .execute(
+            "DELETE FROM tokens WHERE namespc IN (?, ?) AND timestamp<?",
+            (
+                Namespace::InviteWordsNumber,
+                Namespace::InviteWordsAuth,
+                timestamp,
+            ),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Deletes limited group invites which expired before `now`.
+pub async fn delete_expired_limited_invites(context: &Context, now: i64) -> Result<()> {
+    context
+        .sql
+        .execute(
+            "DELETE FROM tokens WHERE namespc=? AND foreign_key IN
+             (SELECT token FROM tokens WHERE namespc=? AND expires>0 AND expires<=?)",
+            (Namespace::LimitedInviteNumber, Namespace::LimitedAuth, now),
+        )
+        .await?;
+    context
+        .sql
+        .execute(
+            "DELETE FROM tokens WHERE namespc=? AND expires>0 AND expires<=?",
+            (Namespace::LimitedAuth, now),
+        )
+        .await?;
+    Ok(())
+}
@@ -514,6 +514,16 @@ pub(crate) fn time_elapsed(time: &Time) -> Duration {
     time.elapsed().unwrap_or_default()
 }
 
+/// Returns the next delay of an exponential backoff with jitter.
+///
+/// The delay grows by a random factor between 1.5 and 2
+/// and is kept between `min` and `max`.
+pub(crate) fn next_backoff(delay: u64, min: u64, max: u64) -> u64 {
+    let delay = delay.min(max / 2);
+    let delay = delay.saturating_add(thread_rng().gen_range((delay / 2)..=delay));
+    delay.min(max).max(min)
+}
+
 /// Struct containing all mailto information
 #[derive(Debug, Default, Eq, PartialEq)]
 pub struct MailTo {
@@ -585,6 +595,28 @@ pub fn remove_subject_prefix(last_subject: &str) -> String {
         .to_string()
 }
 
+/// Reply prefixes used by common MUAs, compared case-insensitively.
+const REPLY_PREFIXES: &[&str] = &["Re", "Aw", "Antw", "Sv", "Vs", "Odp", "Res", "Ynt"];
+
+/// Removes all reply prefixes such as "Re:", "AW:" or "Re[2]:" from the start of the subject.
+///
+/// Other prefixes, e.g. "Fwd:", are kept.
+pub(crate) fn remove_reply_prefixes(subject: &str) -> String {
+    let mut subject = subject.trim();
+    while let Some((prefix, rest)) = subject.split_once(':') {
+        let prefix = prefix
+            .trim_end_matches(|c: char| c.is_ascii_digit() || matches!(c, '[' | ']' | '(' | ')'));
+        if !REPLY_PREFIXES
+            .iter()
+            .any(|reply_prefix| reply_prefix.eq_ignore_ascii_case(prefix))
+        {
+            break;
+        }
+        subject = rest.trim_start();
+    }
+    subject.to_string()
+}
+
 // Types and methods to create hop-info for message-info
 
 fn extract_address_from_receive_header<'a>(header: &'a str, start: &str) -> Option<&'a str> {
@@ -1210,6 +1242,35 @@ fn test_remove_subject_prefix() {
         assert_eq!(remove_subject_prefix("Fw: Subject"), "Subject");
     }
 
+    #[test]
+    fn test_next_backoff() {
+        assert_eq!(next_backoff(0, 2, 80), 2);
+        for _ in 0..100 {
+            let delay = next_backoff(10, 2, 80);
+            assert!((15..=20).contains(&delay));
+        }
+        for _ in 0..100 {
+            let delay = next_backoff(1000, 30, 80);
+            assert!((60..=80).contains(&delay));
+        }
+
+        // Misconfigured minimum takes precedence.
+        assert_eq!(next_backoff(10, 100, 50), 100);
+    }
+
+    #[test]
+    fn test_remove_reply_prefixes() {
+        assert_eq!(remove_reply_prefixes("Subject"), "Subject");
+        assert_eq!(remove_reply_prefixes("Re: AW: RE: Subject"), "Subject");
+        assert_eq!(remove_reply_prefixes("Re[2]: Subject"), "Subject");
+        assert_eq!(remove_reply_prefixes("Re: Fwd: Subject"), "Fwd: Subject");
+        assert_eq!(
+            remove_reply_prefixes("Chat: Re: Subject"),
+            "Chat: Re: Subject"
+        );
+        assert_eq!(remove_reply_prefixes("Infos: 42"), "Infos: 42");
+    }
+
     #[test]
     fn test_parse_mailto() {
         let mailto_url = "mailto:someone@example.com";
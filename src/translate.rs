@@ -0,0 +1,291 @@
+//! # Automatic translation of incoming messages.
+//!
+//! Translation itself is done by the embedder, e.g. using an online service or a local model.
+//! Core only keeps per-chat settings, see [`ChatId::set_auto_translate`],
+//! requests translations of incoming messages with [`EventType::TranslationRequest`]
+//! and caches the results delivered with [`Context::set_msg_translation`]
+//! in the `msg_translations` table alongside the original message.
+
+use anyhow::{ensure, Result};
+
+use crate::chat::{Chat, ChatId};
+use crate::context::Context;
+use crate::events::EventType;
+use crate::message::{Message, MsgId};
+use crate::param::Param;
+
+/// Automatic translation settings of a chat.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AutoTranslate {
+    /// Language to translate incoming messages to, e.g. `de` or `pt-BR`.
+    pub target_lang: String,
+
+    /// ID of the translation provider, defined by the embedder.
+    pub provider: String,
+}
+
+/// Translation of a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MsgTranslation {
+    /// Language the message was translated to.
+    pub target_lang: String,
+
+    /// ID of the translation provider, defined by the embedder.
+    pub provider: String,
+
+    /// Translated text, `None` while the translation is pending.
+    pub text: Option<String>,
+
+    /// True if the user chose to see the original text instead of the translation.
+    pub show_original: bool,
+}
+
+impl ChatId {
+    /// Enables automatic translation of incoming messages in the chat,
+    /// or disables it if `settings` is `None`.
+    ///
+    /// Messages that are already translated keep their translation.
+    pub async fn set_auto_translate(
+        self,
+        context: &Context,
+        settings: Option<&AutoTranslate>,
+    ) -> Result<()> {
+        let mut chat = Chat::load_from_db(context, self).await?;
+        match settings {
+            Some(settings) => {
+                for value in [&settings.target_lang, &settings.provider] {
+                    ensure!(
+                        !value.is_empty() && !value.contains(['\n', '\r']),
+                        "Invalid auto-translate setting {value:?}"
+                    );
+                }
+                chat.param
+                    .set(Param::AutoTranslateLang, &settings.target_lang)
+                    .set(Param::AutoTranslateProvider, &settings.provider);
+            }
+            None => {
+                chat.param
+                    .remove(Param::AutoTranslateLang)
+                    .remove(Param::AutoTranslateProvider);
+            }
+        }
+        chat.update_param(context).await?;
+        context.emit_event(EventType::ChatModified(self));
+        Ok(())
+    }
+
+    /// Returns the automatic translation settings of the chat,
+    /// `None` if automatic translation is disabled.
+    pub async fn get_auto_translate(self, context: &Context) -> Result<Option<AutoTranslate>> {
+        let chat = Chat::load_from_db(context, self).await?;
+        Ok(chat.get_auto_translate())
+    }
+}
+
+impl Chat {
+    /// Returns the automatic translation settings of the chat.
+    pub(crate) fn get_auto_translate(&self) -> Option<AutoTranslate> {
+        let target_lang = self.param.get(Param::AutoTranslateLang)?;
+        let provider = self.param.get(Param::AutoTranslateProvider)?;
+        Some(AutoTranslate {
+            target_lang: target_lang.to_string(),
+            provider: provider.to_string(),
+        })
+    }
+}
+
+impl MsgId {
+    /// Returns the translation of the message, if it was requested.
+    pub async fn get_translation(self, context: &Context) -> Result<Option<MsgTranslation>> {
+        context
+            .sql
+            .query_row_optional(
+                "SELECT target_lang, provider, text, show_original
+                 FROM msg_translations WHERE msg_id=?",
+                (self,),
+                |row| {
+                    Ok(MsgTranslation {
+                        target_lang: row.get(0)?,
+                        provider: row.get(1)?,
+                        text: row.get(2)?,
+                        show_original: row.get(3)?,
+                    })
+                },
+            )
+            .await
+    }
+
+    /// Switches between showing the original text and the translation of the message.
+    pub async fn set_show_original(self, context: &Context, show_original: bool) -> Result<()> {
+        let msg = Message::load_from_db(context, self).await?;
+        let updated = context
+            .sql
+            .execute(
+                "UPDATE msg_translations SET show_original=? WHERE msg_id=?",
+                (show_original, self),
+            )
+            .await?;
+        ensure!(updated > 0, "{self} has no translation");
+        context.emit_msgs_changed(msg.chat_id, self);
+        Ok(())
+    }
+}
+
+impl Context {
+    /// Requests translation of a message.
+    ///
+    /// This is done automatically for incoming messages in chats with automatic translation,
+    /// but may also be called for single messages.
+    /// [`EventType::TranslationRequest`] is emitted unless the message is translated already.
+    pub async fn request_msg_translation(
+        &self,
+        msg_id: MsgId,
+        settings: &AutoTranslate,
+    ) -> Result<()> {
+        let msg = Message::load_from_db(self, msg_id).await?;
+        let text = msg.get_text();
+        if text.trim().is_empty() {
+            return Ok(());
+        }
+        if let Some(translation) = msg_id.get_translation(self).await? {
+            if translation.target_lang == settings.target_lang && translation.text.is_some() {
+                return Ok(());
+            }
+        }
+        self.sql
+            .execute(
+                "INSERT INTO msg_translations (msg_id, target_lang, provider, text) VALUES (?, ?, ?, NULL)
+                 ON CONFLICT (msg_id) DO UPDATE
+                 SET target_lang=excluded.target_lang, provider=excluded.provider, text=NULL",
+                (msg_id, &settings.target_lang, &settings.provider),
+            )
+            .await?;
+        self.emit_event(EventType::TranslationRequest {
+            msg_id,
+            target_lang: settings.target_lang.clone(),
+            provider: settings.provider.clone(),
+            text,
+        });
+        Ok(())
+    }
+
+    /// Stores the translation of a message
+    /// after it was requested with [`EventType::TranslationRequest`].
+    ///
+    /// Translations for another language than requested are ignored,
+    /// e.g. if the settings changed while the translation was running.
+    pub async fn set_msg_translation(
+        &self,
+        msg_id: MsgId,
+        target_lang: &str,
+        text: &str,
+    ) -> Result<()> {
+        let msg = Message::load_from_db(self, msg_id).await?;
+        let updated = self
+            .sql
+            .execute(
+                "UPDATE msg_translations SET text=? WHERE msg_id=? AND target_lang=?",
+                (text, msg_id, target_lang),
+            )
+            .await?;
+        if updated == 0 {
+            info!(
+                self,
+                "Ignoring translation of {msg_id} to {target_lang:?} which was not requested."
+            );
+            return Ok(());
+        }
+        self.emit_msgs_changed(msg.chat_id, msg_id);
+        Ok(())
+    }
+}
+
+/// Requests translation of an incoming message if the chat has automatic translation enabled.
+pub(crate) async fn handle_incoming_msg(
+    context: &Context,
+    chat_id: ChatId,
+    msg_id: MsgId,
+) -> Result<()> {
+    let chat = Chat::load_from_db(context, chat_id).await?;
+    if let Some(settings) = chat.get_auto_translate() {
+        context.request_msg_translation(msg_id, &settings).await?;
+    }
+    Ok(())
+}
+
+/// Returns the translations which are still pending, e.g. to request them again after a restart.
+pub async fn get_pending_translations(context: &Context) -> Result<Vec<MsgId>> {
+    context
+        .sql
+        .query_map(
+            "SELECT msg_id FROM msg_translations WHERE text IS NULL ORDER BY msg_id",
+            (),
+            |row| row.get::<_, MsgId>(0),
+            |rows| {
+                rows.collect::<std::result::Result<Vec<_>, _>>()
+                    .map_err(Into::into)
+            },
+        )
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestContextManager;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_auto_translate() -> Result<()> {
+        let mut tcm = TestContextManager::new();
+        let alice = &tcm.alice().await;
+        let bob = &tcm.bob().await;
+
+        let msg = tcm.send_recv_accept(alice, bob, "Hallo").await;
+        assert_eq!(msg.chat_id.get_auto_translate(bob).await?, None);
+        assert_eq!(msg.id.get_translation(bob).await?, None);
+
+        let settings = AutoTranslate {
+            target_lang: "en".to_string(),
+            provider: "test".to_string(),
+        };
+        msg.chat_id.set_auto_translate(bob, Some(&settings)).await?;
+        assert_eq!(
+            msg.chat_id.get_auto_translate(bob).await?,
+            Some(settings.clone())
+        );
+
+        let msg = tcm.send_recv(alice, bob, "Wie geht's?").await;
+        let event = bob
+            .evtracker
+            .get_matching(|evt| matches!(evt, EventType::TranslationRequest { .. }))
+            .await;
+        assert_eq!(
+            event,
+            EventType::TranslationRequest {
+                msg_id: msg.id,
+                target_lang: "en".to_string(),
+                provider: "test".to_string(),
+                text: "Wie geht's?".to_string(),
+            }
+        );
+        assert_eq!(get_pending_translations(bob).await?, vec![msg.id]);
+
+        // Translations to other languages are ignored.
+        bob.set_msg_translation(msg.id, "fr", "Comment ça va ?")
+            .await?;
+        bob.set_msg_translation(msg.id, "en", "How are you?")
+            .await?;
+        let translation = msg.id.get_translation(bob).await?.unwrap();
+        assert_eq!(translation.text.as_deref(), Some("How are you?"));
+        assert!(!translation.show_original);
+        assert!(get_pending_translations(bob).await?.is_empty());
+
+        msg.id.set_show_original(bob, true).await?;
+        assert!(msg.id.get_translation(bob).await?.unwrap().show_original);
+
+        msg.chat_id.set_auto_translate(bob, None).await?;
+        let msg = tcm.send_recv(alice, bob, "Tschüss").await;
+        assert_eq!(msg.id.get_translation(bob).await?, None);
+        Ok(())
+    }
+}
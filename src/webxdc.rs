@@ -17,37 +17,46 @@
 
 mod integration;
 mod maps_integration;
+mod notification;
+mod send_request;
 
 use std::cmp::max;
 use std::collections::HashMap;
 use std::path::Path;
+use std::time::Duration;
 
 use anyhow::{anyhow, bail, ensure, format_err, Context as _, Result};
 
 use async_zip::tokio::read::seek::ZipFileReader as SeekZipFileReader;
-use deltachat_contact_tools::sanitize_bidi_characters;
+use deltachat_contact_tools::{sanitize_bidi_characters, ContactAddress};
 use deltachat_derive::FromSql;
 use lettre_email::PartBuilder;
+use num_traits::FromPrimitive;
 use rusqlite::OptionalExtension;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use sha2::{Digest, Sha256};
 use tokio::{fs::File, io::BufReader};
 
-use crate::chat::{self, Chat};
+use crate::chat::{self, Chat, ChatId};
+use crate::config::Config;
 use crate::constants::Chattype;
-use crate::contact::ContactId;
+use crate::contact::{Contact, ContactId, Origin};
 use crate::context::Context;
 use crate::events::EventType;
 use crate::key::{load_self_public_key, DcKey};
-use crate::message::{Message, MessageState, MsgId, Viewtype};
+use crate::log::LogExt;
+use crate::message::{rfc724_mid_exists, Message, MessageState, MsgId, Viewtype};
 use crate::mimefactory::wrapped_base64_encode;
 use crate::mimefactory::RECOMMENDED_FILE_SIZE;
 use crate::mimeparser::SystemMessage;
 use crate::param::Param;
 use crate::param::Params;
+use crate::sync::SyncData;
 use crate::tools::create_id;
-use crate::tools::{create_smeared_timestamp, get_abs_path};
+use crate::tools::{self, create_smeared_timestamp, get_abs_path, time, time_elapsed};
+
+pub use send_request::{WebxdcSendGrant, WebxdcSendOutcome};
 
 /// The current API version.
 /// If `min_api` in manifest.toml is set to a larger value,
@@ -121,6 +130,42 @@ pub struct WebxdcInfo {
     pub send_update_max_size: usize,
 }
 
+/// Resource usage of a webxdc instance or of all webxdc instances in a chat.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct WebxdcUsage {
+    /// Size of the `.xdc` files in bytes.
+    pub blob_bytes: u64,
+
+    /// Number of stored status updates.
+    pub status_updates: u64,
+
+    /// Size of the stored status updates in bytes.
+    pub status_update_bytes: u64,
+
+    /// Number of realtime data bytes sent and received.
+    pub realtime_bytes: u64,
+}
+
+impl WebxdcUsage {
+    /// Returns the number of bytes used in total.
+    pub fn total_bytes(&self) -> u64 {
+        self.blob_bytes
+            .saturating_add(self.status_update_bytes)
+            .saturating_add(self.realtime_bytes)
+    }
+}
+
+/// Realtime data bytes accounted in memory and written to the database
+/// at most every [`REALTIME_USAGE_FLUSH_INTERVAL`].
+#[derive(Debug, Default)]
+pub(crate) struct WebxdcRealtimeUsage {
+    /// Bytes per webxdc instance not yet written to the database.
+    pending: HashMap<MsgId, u64>,
+
+    /// Time of the last flush, `None` if nothing was flushed yet.
+    last_flush: Option<tools::Time>,
+}
+
 /// Status Update ID.
 #[derive(
     Debug,
@@ -236,27 +281,104 @@ fn find_zip_entry<'a>(
 /// Status update JSON size soft limit.
 const STATUS_UPDATE_SIZE_MAX: usize = 100 << 10;
 
+/// Factor by which the unpacked size of a webxdc app
+/// may exceed [`Config::WebxdcMaxSize`], to reject zip bombs.
+const WEBXDC_UNPACKED_SIZE_FACTOR: u64 = 10;
+
+/// Reason why a `.xdc` attachment is received as a file instead of a webxdc app.
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive)]
+#[repr(u32)]
+pub enum WebxdcUnsupported {
+    /// The app exceeds [`Config::WebxdcMaxSize`].
+    Oversized = 1,
+
+    /// The file is no zip archive or misses `index.html`.
+    Invalid = 2,
+}
+
+/// Maximum size of status updates synced to other devices at once.
+const SYNC_STATUS_UPDATES_SIZE_MAX: usize = 1 << 20;
+
+/// Minimum interval between writes of realtime data usage to the database.
+const REALTIME_USAGE_FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
 impl Context {
     /// check if a file is an acceptable webxdc for sending or receiving.
+    #[cfg(test)]
     pub(crate) async fn is_webxdc_file(&self, filename: &str, file: &[u8]) -> Result<bool> {
         if !filename.ends_with(WEBXDC_SUFFIX) {
             return Ok(false);
         }
+        let size = file.len() as u64;
+        Ok(self
+            .check_webxdc_archive(filename, std::io::Cursor::new(file), size)
+            .await?
+            .is_none())
+    }
 
-        let archive = match async_zip::base::read::mem::ZipFileReader::new(file.to_vec()).await {
+    /// Checks a received webxdc file, returns why it can't be used as a webxdc app, if so.
+    ///
+    /// The file is read from disk after decryption,
+    /// so the size limit applies to encrypted messages as well.
+    pub(crate) async fn check_webxdc_file(
+        &self,
+        filename: &str,
+        path: &Path,
+    ) -> Result<Option<WebxdcUnsupported>> {
+        let file = File::open(path).await?;
+        let size = file.metadata().await?.len();
+        self.check_webxdc_archive(filename, BufReader::new(file), size)
+            .await
+    }
+
+    /// Checks a webxdc archive of `size` bytes read from `reader`.
+    ///
+    /// Only the central directory of the archive is read, entries are not unpacked.
+    async fn check_webxdc_archive<R>(
+        &self,
+        filename: &str,
+        reader: R,
+        size: u64,
+    ) -> Result<Option<WebxdcUnsupported>>
+    where
+        R: tokio::io::AsyncBufRead + tokio::io::AsyncSeek + Unpin,
+    {
+        let max_size = self.get_config_u64(Config::WebxdcMaxSize).await?;
+        if max_size > 0 && size > max_size {
+            info!(
+                self,
+                "{filename} exceeds the maximum webxdc size ({size} > {max_size} bytes)"
+            );
+            return Ok(Some(WebxdcUnsupported::Oversized));
+        }
+
+        let archive = match SeekZipFileReader::with_tokio(reader).await {
             Ok(archive) => archive,
             Err(_) => {
                 info!(self, "{} cannot be opened as zip-file", &filename);
-                return Ok(false);
+                return Ok(Some(WebxdcUnsupported::Invalid));
             }
         };
 
         if find_zip_entry(archive.file(), "index.html").is_none() {
             info!(self, "{} misses index.html", &filename);
-            return Ok(false);
+            return Ok(Some(WebxdcUnsupported::Invalid));
+        }
+
+        if max_size > 0 {
+            let unpacked_size = archive.file().entries().iter().fold(0u64, |size, entry| {
+                size.saturating_add(entry.uncompressed_size())
+            });
+            if unpacked_size > max_size.saturating_mul(WEBXDC_UNPACKED_SIZE_FACTOR) {
+                info!(
+                    self,
+                    "{filename} exceeds the maximum webxdc size when unpacked ({unpacked_size} bytes)"
+                );
+                return Ok(Some(WebxdcUnsupported::Oversized));
+            }
         }
 
-        Ok(true)
+        Ok(None)
     }
 
     /// Ensure that a file is an acceptable webxdc for sending.
@@ -332,7 +454,7 @@ async fn create_status_update_record(
         from_id: ContactId,
     ) -> Result<Option<StatusUpdateSerial>> {
         let Some(status_update_serial) = self
-            .write_status_update_inner(&instance.id, &status_update_item, timestamp)
+            .write_status_update_inner(&instance.id, &status_update_item, timestamp, from_id)
             .await?
         else {
             return Ok(None);
@@ -411,6 +533,10 @@ async fn create_status_update_record(
                 msg_id: instance.id,
                 status_update_serial,
             });
+            self.check_webxdc_usage(instance.id)
+                .await
+                .log_err(self)
+                .ok();
         }
 
         if from_id != ContactId::SELF {
@@ -441,6 +567,7 @@ pub(crate) async fn write_status_update_inner(
         instance_id: &MsgId,
         status_update_item: &StatusUpdateItem,
         timestamp: i64,
+        from_id: ContactId,
     ) -> Result<Option<StatusUpdateSerial>> {
         let uid = status_update_item.uid.as_deref();
         let status_update_item = serde_json::to_string(&status_update_item)?;
@@ -451,10 +578,10 @@ pub(crate) async fn write_status_update_inner(
             )?;
             let rowid = t
                 .query_row(
-                    "INSERT INTO msgs_status_updates (msg_id, update_item, uid) VALUES(?, ?, ?)
+                    "INSERT INTO msgs_status_updates (msg_id, update_item, uid, from_id) VALUES(?, ?, ?, ?)
                      ON CONFLICT (uid) DO NOTHING
                      RETURNING id",
-                    (instance_id, status_update_item, uid),
+                    (instance_id, status_update_item, uid, from_id),
                     |row| {
                         let id: u32 = row.get(0)?;
                         Ok(id)
@@ -777,6 +904,286 @@ pub async fn get_webxdc_status_updates(
         Ok(format!("[{json}]"))
     }
 
+    /// Returns the resource usage of a webxdc instance.
+    pub async fn get_webxdc_usage(&self, instance_msg_id: MsgId) -> Result<WebxdcUsage> {
+        let instance = Message::load_from_db(self, instance_msg_id).await?;
+        ensure!(instance.viewtype == Viewtype::Webxdc, "No webxdc instance.");
+        let blob_bytes = instance.get_filebytes(self).await?.unwrap_or_default();
+        let (status_updates, status_update_bytes) = self
+            .sql
+            .query_row(
+                "SELECT COUNT(*), IFNULL(SUM(LENGTH(CAST(update_item AS BLOB))), 0)
+                 FROM msgs_status_updates WHERE msg_id=?",
+                (instance_msg_id,),
+                |row| {
+                    let count: i64 = row.get(0)?;
+                    let bytes: i64 = row.get(1)?;
+                    Ok((count, bytes))
+                },
+            )
+            .await?;
+        let realtime_bytes: i64 = self
+            .sql
+            .query_get_value(
+                "SELECT realtime_bytes FROM webxdc_usage WHERE msg_id=?",
+                (instance_msg_id,),
+            )
+            .await?
+            .unwrap_or_default();
+        let pending_bytes = self
+            .webxdc_realtime_usage
+            .lock()
+            .pending
+            .get(&instance_msg_id)
+            .copied()
+            .unwrap_or_default();
+        Ok(WebxdcUsage {
+            blob_bytes,
+            status_updates: u64::try_from(status_updates)?,
+            status_update_bytes: u64::try_from(status_update_bytes)?,
+            realtime_bytes: u64::try_from(realtime_bytes)?.saturating_add(pending_bytes),
+        })
+    }
+
+    /// Returns the summed up resource usage of all webxdc instances in a chat.
+    pub async fn get_chat_webxdc_usage(&self, chat_id: ChatId) -> Result<WebxdcUsage> {
+        let instance_msg_ids = self
+            .sql
+            .query_map(
+                "SELECT id FROM msgs WHERE chat_id=? AND type=?",
+                (chat_id, Viewtype::Webxdc),
+                |row| row.get::<_, MsgId>(0),
+                |ids| ids.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+            )
+            .await?;
+        let mut total = WebxdcUsage::default();
+        for instance_msg_id in instance_msg_ids {
+            let usage = self.get_webxdc_usage(instance_msg_id).await?;
+            total.blob_bytes += usage.blob_bytes;
+            total.status_updates += usage.status_updates;
+            total.status_update_bytes += usage.status_update_bytes;
+            total.realtime_bytes += usage.realtime_bytes;
+        }
+        Ok(total)
+    }
+
+    /// Accounts realtime data sent or received by a webxdc instance.
+    ///
+    /// The bytes are summed up in memory
+    /// and written to the database at most every [`REALTIME_USAGE_FLUSH_INTERVAL`]
+    /// so that realtime packets do not cause database writes.
+    pub(crate) async fn add_webxdc_realtime_bytes(&self, instance_msg_id: MsgId, bytes: usize) {
+        let flush = {
+            let mut usage = self.webxdc_realtime_usage.lock();
+            let pending = usage.pending.entry(instance_msg_id).or_default();
+            *pending = pending.saturating_add(bytes as u64);
+            match usage.last_flush {
+                Some(last_flush) => time_elapsed(&last_flush) >= REALTIME_USAGE_FLUSH_INTERVAL,
+                None => {
+                    usage.last_flush = Some(tools::Time::now());
+                    false
+                }
+            }
+        };
+        if flush {
+            self.flush_webxdc_realtime_bytes().await.log_err(self).ok();
+        }
+    }
+
+    /// Writes realtime data usage accounted in memory to the database
+    /// and checks the usage thresholds of the affected instances.
+    pub(crate) async fn flush_webxdc_realtime_bytes(&self) -> Result<()> {
+        let pending = {
+            let mut usage = self.webxdc_realtime_usage.lock();
+            usage.last_flush = Some(tools::Time::now());
+            std::mem::take(&mut usage.pending)
+        };
+        let mut pending = pending.into_iter();
+        while let Some((instance_msg_id, bytes)) = pending.next() {
+            let res = self
+                .sql
+                .execute(
+                    "INSERT INTO webxdc_usage (msg_id, realtime_bytes) VALUES (?, ?)
+                     ON CONFLICT (msg_id) DO UPDATE SET realtime_bytes=realtime_bytes+excluded.realtime_bytes",
+                    (instance_msg_id, i64::try_from(bytes).unwrap_or(i64::MAX)),
+                )
+                .await;
+            if let Err(err) = res {
+                // Keep the bytes not written yet for the next flush.
+                let mut usage = self.webxdc_realtime_usage.lock();
+                for (instance_msg_id, bytes) in
+                    std::iter::once((instance_msg_id, bytes)).chain(pending)
+                {
+                    let entry = usage.pending.entry(instance_msg_id).or_default();
+                    *entry = entry.saturating_add(bytes);
+                }
+                return Err(err);
+            }
+            self.check_webxdc_usage(instance_msg_id)
+                .await
+                .log_err(self)
+                .ok();
+        }
+        Ok(())
+    }
+
+    /// Emits a warning once if a webxdc instance exceeds
+    /// [`Config::WebxdcUsageWarnBytes`] or [`Config::WebxdcUsageWarnUpdates`].
+    ///
+    /// A threshold of 0 disables the warning.
+    async fn check_webxdc_usage(&self, instance_msg_id: MsgId) -> Result<()> {
+        let warned: bool = self
+            .sql
+            .query_get_value(
+                "SELECT warned FROM webxdc_usage WHERE msg_id=?",
+                (instance_msg_id,),
+            )
+            .await?
+            .unwrap_or_default();
+        if warned {
+            return Ok(());
+        }
+
+        let max_bytes = self.get_config_u64(Config::WebxdcUsageWarnBytes).await?;
+        let max_updates = self.get_config_u64(Config::WebxdcUsageWarnUpdates).await?;
+        let usage = self.get_webxdc_usage(instance_msg_id).await?;
+        if !(max_bytes > 0 && usage.total_bytes() > max_bytes
+            || max_updates > 0 && usage.status_updates > max_updates)
+        {
+            return Ok(());
+        }
+
+        self.sql
+            .execute(
+                "INSERT INTO webxdc_usage (msg_id, warned) VALUES (?, 1)
+                 ON CONFLICT (msg_id) DO UPDATE SET warned=1",
+                (instance_msg_id,),
+            )
+            .await?;
+        warn!(
+            self,
+            "Webxdc instance {instance_msg_id} uses {} bytes and {} status updates.",
+            usage.total_bytes(),
+            usage.status_updates
+        );
+        Ok(())
+    }
+
+    /// Sends all status updates of a webxdc instance to other devices.
+    ///
+    /// Devices added after the updates were sent, e.g. without a backup,
+    /// add the missing updates so that the app state is reproduced there.
+    /// Backups contain the status updates anyway.
+    pub async fn sync_webxdc_status_updates(&self, instance_msg_id: MsgId) -> Result<()> {
+        let instance = Message::load_from_db(self, instance_msg_id).await?;
+        ensure!(
+            instance.viewtype == Viewtype::Webxdc,
+            "{instance_msg_id} is not a webxdc instance"
+        );
+        let (json, first_new) = self
+            .render_webxdc_status_update_object(
+                instance_msg_id,
+                StatusUpdateSerial::MIN,
+                StatusUpdateSerial::MAX,
+                Some(SYNC_STATUS_UPDATES_SIZE_MAX),
+            )
+            .await?;
+        let Some(json) = json else {
+            return Ok(());
+        };
+        ensure!(
+            first_new > StatusUpdateSerial::MAX,
+            "Status updates of {instance_msg_id} are too large to be synced"
+        );
+        // All updates are rendered ordered by serial, so the senders are in the same order.
+        let from_ids = self
+            .sql
+            .query_map(
+                "SELECT from_id FROM msgs_status_updates WHERE msg_id=? ORDER BY id",
+                (instance_msg_id,),
+                |row| row.get::<_, ContactId>(0),
+                |ids| ids.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+            )
+            .await?;
+        let mut senders = Vec::with_capacity(from_ids.len());
+        for from_id in from_ids {
+            let addr = if from_id == ContactId::UNDEFINED {
+                String::new()
+            } else {
+                Contact::get_by_id(self, from_id)
+                    .await?
+                    .get_addr()
+                    .to_string()
+            };
+            senders.push(addr);
+        }
+        self.add_sync_item(SyncData::WebxdcStatusUpdates {
+            msg: instance.rfc724_mid,
+            updates: json,
+            senders,
+        })
+        .await?;
+        self.scheduler.interrupt_inbox().await;
+        Ok(())
+    }
+
+    /// Adds status updates synced from another device to a webxdc instance.
+    ///
+    /// `senders` contains the sender address of each update,
+    /// an empty address if the sender is unknown.
+    ///
+    /// Updates without `uid` cannot be deduplicated
+    /// and are only added if the instance has no updates yet.
+    pub(crate) async fn add_synced_status_updates(
+        &self,
+        rfc724_mid: &str,
+        json: &str,
+        senders: &[String],
+    ) -> Result<()> {
+        let Some((instance_msg_id, _)) = rfc724_mid_exists(self, rfc724_mid).await? else {
+            warn!(
+                self,
+                "Ignoring synced status updates for unknown instance {rfc724_mid}."
+            );
+            return Ok(());
+        };
+        let instance = Message::load_from_db(self, instance_msg_id).await?;
+        ensure!(
+            instance.viewtype == Viewtype::Webxdc,
+            "{instance_msg_id} is not a webxdc instance"
+        );
+        let has_updates = self
+            .sql
+            .exists(
+                "SELECT COUNT(*) FROM msgs_status_updates WHERE msg_id=?",
+                (instance_msg_id,),
+            )
+            .await?;
+        let updates: StatusUpdates = serde_json::from_str(json)?;
+        let timestamp = time();
+        for (i, mut update_item) in updates.updates.into_iter().enumerate() {
+            if has_updates && update_item.uid.is_none() {
+                continue;
+            }
+            let addr = senders.get(i).map(String::as_str).unwrap_or_default();
+            let from_id = if addr.is_empty() {
+                ContactId::UNDEFINED
+            } else if self.is_self_addr(addr).await? {
+                ContactId::SELF
+            } else {
+                let addr = ContactAddress::new(addr)?;
+                Contact::add_or_lookup(self, "", &addr, Origin::Hidden)
+                    .await?
+                    .0
+            };
+            // The updates were already notified on the other device.
+            update_item.notify = None;
+            self.create_status_update_record(&instance, update_item, timestamp, false, from_id)
+                .await?;
+        }
+        Ok(())
+    }
+
     /// Renders JSON-object for status updates as used on the wire.
     ///
     /// Returns optional JSON and the first serial of updates not included due to a JSON size
@@ -851,6 +1258,14 @@ async fn get_blob(archive: &mut SeekZipFileReader<BufReader<File>>, name: &str)
 }
 
 impl Message {
+    /// Returns why a `.xdc` attachment is shown as a file instead of a webxdc app,
+    /// `None` for webxdc apps and other messages.
+    pub fn get_webxdc_unsupported(&self) -> Option<WebxdcUnsupported> {
+        self.param
+            .get_int(Param::WebxdcUnsupported)
+            .and_then(WebxdcUnsupported::from_i32)
+    }
+
     /// Get handle to a webxdc ZIP-archive.
     /// To check for file existence use archive.by_name(), to read a file, use get_blob(archive).
     async fn get_webxdc_archive(
@@ -0,0 +1,93 @@
+//! Local notifications requested by webxdc apps.
+//!
+//! A webxdc app may call `sendNotification(title, body)` to point the user
+//! to an important event in the app, e.g. a finished timer.
+//! The UI passes the call to [`Context::send_webxdc_notification`],
+//! which emits [`EventType::WebxdcNotify`] unless the chat is muted
+//! or the app exceeds its rate limit.
+
+use std::time::Duration;
+
+use anyhow::{ensure, Result};
+use ratelimit::Ratelimit;
+
+use super::send_request::load_webxdc_instance;
+use crate::chat::Chat;
+use crate::context::Context;
+use crate::events::EventType;
+use crate::message::MsgId;
+use crate::tools::truncate;
+
+/// Maximum number of characters of a notification title.
+const MAX_TITLE_CHARS: usize = 50;
+
+/// Maximum number of characters of a notification body.
+const MAX_BODY_CHARS: usize = 200;
+
+/// Returns the rate limiter for notifications of a single webxdc instance.
+///
+/// Allows a burst of 3 notifications and 1 notification per 20 seconds on average.
+pub(crate) fn new_notify_ratelimit() -> Ratelimit {
+    Ratelimit::new(Duration::new(60, 0), 3.0)
+}
+
+impl Context {
+    /// Asks to notify the user about an event in a webxdc app.
+    ///
+    /// Title and body are truncated and [`EventType::WebxdcNotify`] is emitted,
+    /// so the UI can show a system notification.
+    /// Returns false if no event is emitted
+    /// because the chat is muted or a contact request,
+    /// or because the app sends notifications too often.
+    pub async fn send_webxdc_notification(
+        &self,
+        instance_msg_id: MsgId,
+        title: &str,
+        body: &str,
+    ) -> Result<bool> {
+        let instance = load_webxdc_instance(self, instance_msg_id).await?;
+        let title = title.trim();
+        let body = body.trim();
+        ensure!(
+            !title.is_empty() || !body.is_empty(),
+            "Webxdc notification without text"
+        );
+
+        let chat = Chat::load_from_db(self, instance.chat_id).await?;
+        if chat.is_muted() || chat.is_contact_request() {
+            info!(
+                self,
+                "Not notifying about webxdc {instance_msg_id} in {}.", chat.id
+            );
+            return Ok(false);
+        }
+
+        {
+            let mut ratelimits = self.webxdc_notify_ratelimits.write().await;
+            let ratelimit = ratelimits
+                .entry(instance_msg_id)
+                .or_insert_with(new_notify_ratelimit);
+            if !ratelimit.can_send() {
+                warn!(
+                    self,
+                    "Webxdc {instance_msg_id} sends notifications too often."
+                );
+                return Ok(false);
+            }
+            ratelimit.send();
+        }
+
+        let title = truncate(title, MAX_TITLE_CHARS);
+        let body = truncate(body, MAX_BODY_CHARS);
+        let text = match (title.is_empty(), body.is_empty()) {
+            (false, false) => format!("{title}: {body}"),
+            (false, true) => title.to_string(),
+            (true, _) => body.to_string(),
+        };
+        self.emit_event(EventType::WebxdcNotify {
+            msg_id: instance_msg_id,
+            text,
+        });
+        Ok(true)
+    }
+}
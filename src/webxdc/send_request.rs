@@ -0,0 +1,209 @@
+//! Mediated sending of messages on behalf of webxdc apps.
+//!
+//! A webxdc app may ask to post a message, e.g. a summary, into its chat
+//! using [`Context::request_webxdc_send_message`].
+//! Unless the user has granted this permission to the app before,
+//! the request is stored in the `webxdc_send_requests` table
+//! and [`EventType::WebxdcSendRequest`] is emitted with a preview of the message.
+//! The UI asks the user and calls [`Context::approve_webxdc_send_request`]
+//! or [`Context::deny_webxdc_send_request`].
+//! Both can remember the decision for the app in the instance params.
+
+use anyhow::{bail, ensure, Context as _, Result};
+use num_traits::FromPrimitive;
+use rusqlite::OptionalExtension;
+
+use crate::chat::{send_msg, Chat};
+use crate::context::Context;
+use crate::events::EventType;
+use crate::message::{Message, MsgId, Viewtype};
+use crate::param::Param;
+use crate::tools::time;
+
+/// Permission of a webxdc app to send messages on the user's behalf.
+#[derive(Debug, Default, Display, Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive)]
+#[repr(u32)]
+pub enum WebxdcSendGrant {
+    /// Ask the user for every message.
+    #[default]
+    Ask = 0,
+
+    /// Send messages without asking.
+    Always = 1,
+
+    /// Deny all requests without asking.
+    Never = 2,
+}
+
+/// Result of [`Context::request_webxdc_send_message`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebxdcSendOutcome {
+    /// The app has a permanent grant, the message was sent.
+    Sent(MsgId),
+
+    /// The request waits for approval of the user.
+    Pending(u32),
+
+    /// The user has denied sending messages for this app.
+    Denied,
+}
+
+impl Context {
+    /// Requests sending a text message into the chat of a webxdc instance
+    /// on behalf of the webxdc app.
+    ///
+    /// If the user has not decided permanently for the app,
+    /// [`EventType::WebxdcSendRequest`] is emitted
+    /// and the message is only sent after [`Context::approve_webxdc_send_request`] is called.
+    pub async fn request_webxdc_send_message(
+        &self,
+        instance_msg_id: MsgId,
+        text: &str,
+    ) -> Result<WebxdcSendOutcome> {
+        let instance = load_webxdc_instance(self, instance_msg_id).await?;
+        ensure!(!text.trim().is_empty(), "Webxdc send request without text");
+        let chat = Chat::load_from_db(self, instance.chat_id).await?;
+        if let Some(reason) = chat.why_cant_send(self).await? {
+            bail!("Cannot send to {}: {reason}.", chat.id);
+        }
+
+        match instance.get_webxdc_send_grant() {
+            WebxdcSendGrant::Always => {
+                let msg_id = send_on_behalf(self, &instance, text).await?;
+                Ok(WebxdcSendOutcome::Sent(msg_id))
+            }
+            WebxdcSendGrant::Never => {
+                info!(
+                    self,
+                    "Webxdc {instance_msg_id} is not allowed to send messages."
+                );
+                Ok(WebxdcSendOutcome::Denied)
+            }
+            WebxdcSendGrant::Ask => {
+                let request_id = self
+                    .sql
+                    .insert(
+                        "INSERT INTO webxdc_send_requests (msg_id, text, timestamp) VALUES (?, ?, ?)",
+                        (instance_msg_id, text, time()),
+                    )
+                    .await?;
+                let request_id = u32::try_from(request_id)?;
+                self.emit_event(EventType::WebxdcSendRequest {
+                    msg_id: instance_msg_id,
+                    request_id,
+                    text: text.to_string(),
+                });
+                Ok(WebxdcSendOutcome::Pending(request_id))
+            }
+        }
+    }
+
+    /// Approves a pending request of a webxdc app to send a message and sends it.
+    ///
+    /// If `remember` is true, further requests of the app are sent without asking.
+    pub async fn approve_webxdc_send_request(
+        &self,
+        request_id: u32,
+        remember: bool,
+    ) -> Result<MsgId> {
+        let (instance_msg_id, text) = take_send_request(self, request_id).await?;
+        let mut instance = load_webxdc_instance(self, instance_msg_id).await?;
+        if remember {
+            set_grant(self, &mut instance, WebxdcSendGrant::Always).await?;
+        }
+        send_on_behalf(self, &instance, &text).await
+    }
+
+    /// Denies a pending request of a webxdc app to send a message.
+    ///
+    /// If `remember` is true, further requests of the app are denied without asking.
+    pub async fn deny_webxdc_send_request(&self, request_id: u32, remember: bool) -> Result<()> {
+        let (instance_msg_id, _text) = take_send_request(self, request_id).await?;
+        if remember {
+            let mut instance = load_webxdc_instance(self, instance_msg_id).await?;
+            set_grant(self, &mut instance, WebxdcSendGrant::Never).await?;
+        }
+        Ok(())
+    }
+
+    /// Sets the permission of a webxdc app to send messages,
+    /// e.g. to revoke a remembered decision.
+    pub async fn set_webxdc_send_grant(
+        &self,
+        instance_msg_id: MsgId,
+        grant: WebxdcSendGrant,
+    ) -> Result<()> {
+        let mut instance = load_webxdc_instance(self, instance_msg_id).await?;
+        set_grant(self, &mut instance, grant).await
+    }
+}
+
+impl Message {
+    /// Returns the permission of the webxdc app to send messages on the user's behalf.
+    pub fn get_webxdc_send_grant(&self) -> WebxdcSendGrant {
+        self.param
+            .get_int(Param::WebxdcSendGrant)
+            .and_then(WebxdcSendGrant::from_i32)
+            .unwrap_or_default()
+    }
+}
+
+pub(super) async fn load_webxdc_instance(
+    context: &Context,
+    instance_msg_id: MsgId,
+) -> Result<Message> {
+    let instance = Message::load_from_db(context, instance_msg_id)
+        .await
+        .with_context(|| format!("Failed to load webxdc instance {instance_msg_id}"))?;
+    ensure!(
+        instance.viewtype == Viewtype::Webxdc,
+        "Message {instance_msg_id} is not a webxdc instance"
+    );
+    Ok(instance)
+}
+
+async fn take_send_request(context: &Context, request_id: u32) -> Result<(MsgId, String)> {
+    context
+        .sql
+        .call_write(|conn| {
+            let request = conn
+                .query_row(
+                    "DELETE FROM webxdc_send_requests WHERE id=? RETURNING msg_id, text",
+                    (request_id,),
+                    |row| {
+                        let msg_id: MsgId = row.get(0)?;
+                        let text: String = row.get(1)?;
+                        Ok((msg_id, text))
+                    },
+                )
+                .optional()?;
+            Ok(request)
+        })
+        .await?
+        .with_context(|| format!("No webxdc send request {request_id}"))
+}
+
+async fn set_grant(
+    context: &Context,
+    instance: &mut Message,
+    grant: WebxdcSendGrant,
+) -> Result<()> {
+    instance.param.set_int(Param::WebxdcSendGrant, grant as i32);
+    instance.update_param(context).await?;
+    if grant != WebxdcSendGrant::Ask {
+        context
+            .sql
+            .execute(
+                "DELETE FROM webxdc_send_requests WHERE msg_id=?",
+                (instance.id,),
+            )
+            .await?;
+    }
+    Ok(())
+}
+
+async fn send_on_behalf(context: &Context, instance: &Message, text: &str) -> Result<MsgId> {
+    let mut msg = Message::new_text(text.to_string());
+    msg.set_quote(context, Some(instance)).await?;
+    send_msg(context, instance.chat_id, &mut msg).await
+}
@@ -14,7 +14,7 @@
 use crate::download::DownloadState;
 use crate::ephemeral;
 use crate::receive_imf::{receive_imf, receive_imf_from_inbox};
-use crate::test_utils::{TestContext, TestContextManager};
+use crate::test_utils::{self, TestContext, TestContextManager};
 use crate::tools::{self, SystemTime};
 use crate::{message, sql};
 
@@ -59,6 +59,45 @@ async fn test_is_webxdc_file() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_receive_oversized_webxdc() -> Result<()> {
+    let mut tcm = TestContextManager::new();
+    let alice = &tcm.alice().await;
+    let bob = &tcm.bob().await;
+    bob.set_config(Config::WebxdcMaxSize, Some("100")).await?;
+
+    let alice_chat = alice.create_chat(bob).await;
+    let instance = send_webxdc_instance(alice, alice_chat.id).await?;
+    assert_eq!(instance.get_webxdc_unsupported(), None);
+    let sent = alice.pop_sent_msg().await;
+    let bob_instance = bob.recv_msg(&sent).await;
+    assert_eq!(bob_instance.viewtype, Viewtype::File);
+    assert_eq!(
+        bob_instance.get_webxdc_unsupported(),
+        Some(WebxdcUnsupported::Oversized)
+    );
+
+    // Without a limit, the app is received as usual.
+    bob.set_config(Config::WebxdcMaxSize, Some("0")).await?;
+    send_webxdc_instance(alice, alice_chat.id).await?;
+    let bob_instance = bob.recv_msg(&alice.pop_sent_msg().await).await;
+    assert_eq!(bob_instance.viewtype, Viewtype::Webxdc);
+    assert_eq!(bob_instance.get_webxdc_unsupported(), None);
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("no-index-html.xdc");
+    tokio::fs::write(
+        &path,
+        include_bytes!("../../test-data/webxdc/no-index-html.xdc"),
+    )
+    .await?;
+    assert_eq!(
+        bob.check_webxdc_file("no-index-html.xdc", &path).await?,
+        Some(WebxdcUnsupported::Invalid)
+    );
+    Ok(())
+}
+
 fn create_webxdc_instance(t: &TestContext, name: &str, bytes: &[u8]) -> Result<Message> {
     let mut instance = Message::new(Viewtype::File);
     instance.set_file_from_bytes(t, name, bytes, None)?;
@@ -2199,3 +2238,225 @@ async fn test_webxdc_href() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_webxdc_usage() -> Result<()> {
+    let t = TestContext::new_alice().await;
+    let chat_id = create_group_chat(&t, ProtectionStatus::Unprotected, "foo").await?;
+    let instance = send_webxdc_instance(&t, chat_id).await?;
+
+    let usage = t.get_webxdc_usage(instance.id).await?;
+    assert!(usage.blob_bytes > 0);
+    assert_eq!(usage.status_updates, 0);
+    assert_eq!(usage.status_update_bytes, 0);
+    assert_eq!(usage.realtime_bytes, 0);
+
+    t.set_config(Config::WebxdcUsageWarnUpdates, Some("1"))
+        .await?;
+    t.send_webxdc_status_update(instance.id, r#"{"payload": 1}"#)
+        .await?;
+    t.add_webxdc_realtime_bytes(instance.id, 4).await;
+    t.add_webxdc_realtime_bytes(instance.id, 6).await;
+    let usage = t.get_webxdc_usage(instance.id).await?;
+    assert_eq!(usage.status_updates, 1);
+    assert!(usage.status_update_bytes > 0);
+    assert_eq!(usage.realtime_bytes, 10);
+
+    // Realtime usage is only written to the database on flush.
+    let query = "SELECT realtime_bytes FROM webxdc_usage WHERE msg_id=?";
+    let stored: Option<i64> = t.sql.query_get_value(query, (instance.id,)).await?;
+    assert_eq!(stored, None);
+    t.flush_webxdc_realtime_bytes().await?;
+    let stored: Option<i64> = t.sql.query_get_value(query, (instance.id,)).await?;
+    assert_eq!(stored, Some(10));
+    assert_eq!(t.get_webxdc_usage(instance.id).await?.realtime_bytes, 10);
+
+    t.send_webxdc_status_update(instance.id, r#"{"payload": 2}"#)
+        .await?;
+    t.evtracker
+        .get_matching(
+            |evt| matches!(evt, EventType::Warning(msg) if msg.starts_with("Webxdc instance")),
+        )
+        .await;
+
+    send_webxdc_instance(&t, chat_id).await?;
+    let chat_usage = t.get_chat_webxdc_usage(chat_id).await?;
+    assert_eq!(chat_usage.blob_bytes, 2 * usage.blob_bytes);
+    assert_eq!(chat_usage.status_updates, 2);
+    assert_eq!(chat_usage.realtime_bytes, 10);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_sync_webxdc_status_updates() -> Result<()> {
+    let mut tcm = TestContextManager::new();
+    let alice = &tcm.alice().await;
+    let bob = &tcm.bob().await;
+    alice.set_config_bool(Config::SyncMsgs, true).await?;
+
+    let chat = alice.create_chat(bob).await;
+    let instance = send_webxdc_instance(alice, chat.id).await?;
+    let sent_instance = alice.pop_sent_msg().await;
+    alice
+        .send_webxdc_status_update(instance.id, r#"{"payload": 1}"#)
+        .await?;
+    alice.flush_status_updates().await?;
+    alice.pop_sent_msg().await;
+
+    let bob_instance = bob.recv_msg(&sent_instance).await;
+    bob_instance.chat_id.accept(bob).await?;
+    bob.send_webxdc_status_update(bob_instance.id, r#"{"payload": 2}"#)
+        .await?;
+    bob.flush_status_updates().await?;
+    alice.recv_msg_trash(&bob.pop_sent_msg().await).await;
+
+    // The second device only gets the instance, but not the update.
+    let alice2 = &tcm.alice().await;
+    alice2.set_config_bool(Config::SyncMsgs, true).await?;
+    let alice2_instance = alice2.recv_msg(&sent_instance).await;
+    assert_eq!(
+        alice2
+            .get_webxdc_status_updates(alice2_instance.id, StatusUpdateSerial(0))
+            .await?,
+        "[]"
+    );
+
+    alice.sync_webxdc_status_updates(instance.id).await?;
+    test_utils::sync(alice, alice2).await;
+    assert_eq!(
+        alice2
+            .get_webxdc_status_updates(alice2_instance.id, StatusUpdateSerial(0))
+            .await?,
+        r#"[{"payload":1,"serial":1,"max_serial":2},
+{"payload":2,"serial":2,"max_serial":2}]"#
+    );
+
+    // Updates are attributed to their senders.
+    let from_ids = alice2
+        .sql
+        .query_map(
+            "SELECT from_id FROM msgs_status_updates WHERE msg_id=? ORDER BY id",
+            (alice2_instance.id,),
+            |row| row.get::<_, ContactId>(0),
+            |ids| ids.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+        )
+        .await?;
+    let alice2_bob_id = alice2.add_or_lookup_contact_id(bob).await;
+    assert_eq!(from_ids, vec![ContactId::SELF, alice2_bob_id]);
+
+    // Syncing again does not duplicate updates.
+    alice.sync_webxdc_status_updates(instance.id).await?;
+    test_utils::sync(alice, alice2).await;
+    assert_eq!(
+        alice2
+            .get_webxdc_status_updates(alice2_instance.id, StatusUpdateSerial(0))
+            .await?,
+        r#"[{"payload":1,"serial":1,"max_serial":2},
+{"payload":2,"serial":2,"max_serial":2}]"#
+    );
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_webxdc_send_request() -> Result<()> {
+    let t = TestContext::new_alice().await;
+    let chat_id = t.get_self_chat().await.id;
+    let instance_id = send_webxdc_instance(&t, chat_id).await?.id;
+
+    let WebxdcSendOutcome::Pending(request_id) = t
+        .request_webxdc_send_message(instance_id, "Summary")
+        .await?
+    else {
+        panic!("Request is not pending");
+    };
+    let event = t
+        .evtracker
+        .get_matching(|evt| matches!(evt, EventType::WebxdcSendRequest { .. }))
+        .await;
+    assert_eq!(
+        event,
+        EventType::WebxdcSendRequest {
+            msg_id: instance_id,
+            request_id,
+            text: "Summary".to_string()
+        }
+    );
+    let msg_id = t.approve_webxdc_send_request(request_id, false).await?;
+    assert_eq!(
+        Message::load_from_db(&t, msg_id).await?.get_text(),
+        "Summary"
+    );
+    assert!(t
+        .approve_webxdc_send_request(request_id, false)
+        .await
+        .is_err());
+
+    // Denying with `remember` denies further requests.
+    let WebxdcSendOutcome::Pending(request_id) = t
+        .request_webxdc_send_message(instance_id, "Another summary")
+        .await?
+    else {
+        panic!("Request is not pending");
+    };
+    t.deny_webxdc_send_request(request_id, true).await?;
+    assert_eq!(
+        t.request_webxdc_send_message(instance_id, "Spam").await?,
+        WebxdcSendOutcome::Denied
+    );
+
+    // Remembered approval sends without asking.
+    t.set_webxdc_send_grant(instance_id, WebxdcSendGrant::Always)
+        .await?;
+    let WebxdcSendOutcome::Sent(msg_id) = t
+        .request_webxdc_send_message(instance_id, "Final summary")
+        .await?
+    else {
+        panic!("Message is not sent");
+    };
+    let msg = Message::load_from_db(&t, msg_id).await?;
+    assert_eq!(msg.get_text(), "Final summary");
+    assert_eq!(msg.quoted_message(&t).await?.unwrap().id, instance_id);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_send_webxdc_notification() -> Result<()> {
+    let t = TestContext::new_alice().await;
+    let chat_id = t.get_self_chat().await.id;
+    let instance_id = send_webxdc_instance(&t, chat_id).await?.id;
+
+    assert!(t
+        .send_webxdc_notification(instance_id, " ", "")
+        .await
+        .is_err());
+    assert!(
+        t.send_webxdc_notification(instance_id, "Timer", "Tea is ready")
+            .await?
+    );
+    let event = t
+        .evtracker
+        .get_matching(|evt| matches!(evt, EventType::WebxdcNotify { .. }))
+        .await;
+    assert_eq!(
+        event,
+        EventType::WebxdcNotify {
+            msg_id: instance_id,
+            text: "Timer: Tea is ready".to_string()
+        }
+    );
+
+    // The app is rate-limited.
+    assert!(t.send_webxdc_notification(instance_id, "", "2").await?);
+    assert!(t.send_webxdc_notification(instance_id, "", "3").await?);
+    assert!(!t.send_webxdc_notification(instance_id, "", "4").await?);
+
+    // Muted chats are not notified.
+    let instance_id = send_webxdc_instance(&t, chat_id).await?.id;
+    chat::set_muted(&t, chat_id, chat::MuteDuration::Forever).await?;
+    assert!(
+        !t.send_webxdc_notification(instance_id, "Timer", "Tea is ready")
+            .await?
+    );
+    Ok(())
+}